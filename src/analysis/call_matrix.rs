@@ -0,0 +1,124 @@
+//! Calls between contracts, counted per pair.
+//!
+//! Row `i`, column `j` of the matrix counts the call sites in functions,
+//! modifiers and constructors of `contracts[i]` that call into
+//! `contracts[j]`, including calls to public variable getters and through
+//! interfaces. The diagonal holds calls within a contract. Large
+//! off-diagonal counts point at contracts coupled more tightly than their
+//! module boundaries suggest.
+
+use super::storage_heatmap::{block_parents, enclosing_function};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use traverse_graph::cg::{CallGraph, EdgeType};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CallMatrix {
+    /// Every contract, interface and library of the graph, sorted.
+    pub contracts: Vec<String>,
+    /// `counts[i][j]`: calls from `contracts[i]` into `contracts[j]`.
+    pub counts: Vec<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Coupling {
+    pub caller: String,
+    pub callee: String,
+    pub calls: usize,
+}
+
+pub fn build(graph: &CallGraph) -> CallMatrix {
+    let contracts: Vec<String> = graph
+        .nodes
+        .iter()
+        .filter_map(|node| node.contract_name.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let index = |name: &str| contracts.binary_search_by(|c| c.as_str().cmp(name)).ok();
+
+    let parents = block_parents(graph);
+    let mut counts = vec![vec![0; contracts.len()]; contracts.len()];
+    for edge in graph.edges.iter().filter(|e| e.edge_type == EdgeType::Call) {
+        let (Some(source), Some(target)) = (
+            enclosing_function(graph, &parents, edge.source_node_id),
+            graph.nodes.get(edge.target_node_id),
+        ) else {
+            continue;
+        };
+        let (Some(from), Some(to)) = (
+            source.contract_name.as_deref().and_then(index),
+            target.contract_name.as_deref().and_then(index),
+        ) else {
+            continue;
+        };
+        counts[from][to] += 1;
+    }
+    CallMatrix { contracts, counts }
+}
+
+impl CallMatrix {
+    /// Pairs of distinct contracts with at least one call, most calls first.
+    pub fn couplings(&self) -> Vec<Coupling> {
+        let mut couplings = Vec::new();
+        for (from, row) in self.counts.iter().enumerate() {
+            for (to, &calls) in row.iter().enumerate() {
+                if from != to && calls > 0 {
+                    couplings.push(Coupling {
+                        caller: self.contracts[from].clone(),
+                        callee: self.contracts[to].clone(),
+                        calls,
+                    });
+                }
+            }
+        }
+        couplings.sort_by(|a, b| {
+            b.calls
+                .cmp(&a.calls)
+                .then_with(|| a.caller.cmp(&b.caller))
+                .then_with(|| a.callee.cmp(&b.callee))
+        });
+        couplings
+    }
+
+    /// One row per calling contract, headed by the called contracts.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("caller");
+        for contract in &self.contracts {
+            csv.push(',');
+            csv.push_str(contract);
+        }
+        csv.push('\n');
+        for (contract, row) in self.contracts.iter().zip(&self.counts) {
+            csv.push_str(contract);
+            for calls in row {
+                csv.push_str(&format!(",{}", calls));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+pub fn render_call_matrix(matrix: &CallMatrix) -> String {
+    let couplings = matrix.couplings();
+    let mut md = String::from("# Contract Call Matrix\n\n");
+    md.push_str(&format!(
+        "**Contracts:** {} | **Coupled pairs:** {}\n\n",
+        matrix.contracts.len(),
+        couplings.len()
+    ));
+    if couplings.is_empty() {
+        md.push_str("No contract calls into another.\n");
+        return md;
+    }
+    md.push_str("| Caller | Callee | Calls |\n");
+    md.push_str("|--------|--------|-------|\n");
+    for coupling in &couplings {
+        md.push_str(&format!(
+            "| {} | {} | {} |\n",
+            coupling.caller, coupling.callee, coupling.calls
+        ));
+    }
+    md
+}
@@ -0,0 +1,207 @@
+//! Near-identical function bodies across the workspace.
+//!
+//! Each function and modifier body is reduced to its syntax tree with
+//! comments dropped and identifiers renamed in order of first use, so a
+//! token fork that renamed its variables still matches the original.
+//! Literals and the names of called functions and members are kept:
+//! `fee * 3 / 1000` and `fee * 25 / 10000` are different math, and
+//! `token.transfer` is not `token.approve`. Bodies with the same shape form
+//! a cluster; reviewers can audit one representative and diff the rest
+//! against it.
+
+use super::model::{for_each_callable, line_of, ParsedSource};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+/// Bodies smaller than this many syntax nodes are left out; getters and
+/// one-line wrappers are alike everywhere.
+pub const DEFAULT_MIN_NODES: usize = 30;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CloneCluster {
+    /// Hash of the normalized body, as hex.
+    pub hash: String,
+    /// Syntax nodes in each body.
+    pub nodes: usize,
+    pub members: Vec<CloneMember>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CloneMember {
+    pub contract: String,
+    pub function: String,
+    pub file: PathBuf,
+    pub line: usize,
+    /// Same text as the first member, whitespace and comments aside; `false`
+    /// when only names differ.
+    pub identical: bool,
+}
+
+pub fn find(parsed: &[ParsedSource], min_nodes: usize) -> Vec<CloneCluster> {
+    // Normalized body -> node count, and members with their canonical text.
+    let mut groups: BTreeMap<String, (usize, Vec<(CloneMember, String)>)> = BTreeMap::new();
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, function, node| {
+            let Some(body) = node.child_by_field_name("body") else {
+                return;
+            };
+            let mut shape = Shape::default();
+            shape.add(body, text);
+            if shape.nodes < min_nodes {
+                return;
+            }
+            let member = CloneMember {
+                contract: contract.to_string(),
+                function: function.to_string(),
+                file: source.unit.path.clone(),
+                line: line_of(node),
+                identical: true,
+            };
+            groups
+                .entry(shape.normalized)
+                .or_insert((shape.nodes, Vec::new()))
+                .1
+                .push((member, shape.verbatim));
+        });
+    }
+
+    let mut clusters: Vec<CloneCluster> = groups
+        .into_iter()
+        .filter(|(_, (_, members))| members.len() > 1)
+        .map(|(normalized, (nodes, mut members))| {
+            members.sort_by(|(a, _), (b, _)| (&a.file, a.line).cmp(&(&b.file, b.line)));
+            let first = members[0].1.clone();
+            let mut hasher = DefaultHasher::new();
+            normalized.hash(&mut hasher);
+            CloneCluster {
+                hash: format!("{:016x}", hasher.finish()),
+                nodes,
+                members: members
+                    .into_iter()
+                    .map(|(mut member, verbatim)| {
+                        member.identical = verbatim == first;
+                        member
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| {
+        b.nodes
+            .cmp(&a.nodes)
+            .then_with(|| b.members.len().cmp(&a.members.len()))
+            .then_with(|| a.hash.cmp(&b.hash))
+    });
+    clusters
+}
+
+pub fn render_clones(clusters: &[CloneCluster]) -> String {
+    let mut md = String::from("# Function Clones\n\n");
+    md.push_str(&format!("**Clusters:** {}\n\n", clusters.len()));
+    if clusters.is_empty() {
+        md.push_str("No duplicated function bodies found.\n");
+        return md;
+    }
+    for cluster in clusters {
+        let representative = &cluster.members[0];
+        md.push_str(&format!(
+            "## `{}.{}` and {} more ({} nodes)\n\n",
+            representative.contract,
+            representative.function,
+            cluster.members.len() - 1,
+            cluster.nodes
+        ));
+        md.push_str("| Contract | Function | Location | Match |\n");
+        md.push_str("|----------|----------|----------|-------|\n");
+        for (i, member) in cluster.members.iter().enumerate() {
+            let kind = if i == 0 {
+                "representative"
+            } else if member.identical {
+                "identical"
+            } else {
+                "renamed"
+            };
+            md.push_str(&format!(
+                "| {} | {} | {}:{} | {} |\n",
+                member.contract,
+                member.function,
+                member.file.display(),
+                member.line,
+                kind
+            ));
+        }
+        md.push('\n');
+    }
+    md
+}
+
+/// A body's syntax tree as text, once with identifiers renamed and once
+/// verbatim.
+#[derive(Default)]
+struct Shape {
+    normalized: String,
+    verbatim: String,
+    nodes: usize,
+    names: HashMap<String, usize>,
+}
+
+impl Shape {
+    fn add(&mut self, node: TsNode, source: &str) {
+        if node.kind() == "comment" {
+            return;
+        }
+        self.nodes += 1;
+        self.normalized.push('(');
+        self.normalized.push_str(node.kind());
+        self.verbatim.push('(');
+        self.verbatim.push_str(node.kind());
+        if node.child_count() == 0 {
+            let text = get_node_text(&node, source);
+            self.verbatim.push(' ');
+            self.verbatim.push_str(text);
+            self.normalized.push(' ');
+            if node.kind() == "identifier" && !is_called_name(node) {
+                let next = self.names.len();
+                let id = *self.names.entry(text.to_string()).or_insert(next);
+                self.normalized.push_str(&format!("${}", id));
+            } else {
+                self.normalized.push_str(text);
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.add(child, source);
+        }
+        self.normalized.push(')');
+        self.verbatim.push(')');
+    }
+}
+
+/// Whether `node` names a called function or an accessed member, which
+/// renaming would make indistinguishable.
+fn is_called_name(node: TsNode) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    let is_field = |field: &str| {
+        parent
+            .child_by_field_name(field)
+            .is_some_and(|child| child.id() == node.id())
+    };
+    match parent.kind() {
+        "member_expression" => is_field("property"),
+        "call_expression" => is_field("function"),
+        "expression" => parent
+            .parent()
+            .filter(|p| p.kind() == "call_expression")
+            .and_then(|p| p.child_by_field_name("function"))
+            .is_some_and(|f| f.id() == parent.id()),
+        _ => false,
+    }
+}
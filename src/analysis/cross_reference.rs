@@ -0,0 +1,360 @@
+//! Where enum values and constants are used.
+//!
+//! State machines and configuration constants are easiest to review from
+//! their use sites: which transitions assign a state, which guards compare
+//! against it, and which code paths read a limit. Uses are matched by name,
+//! qualified (`State.Open`, `Vault.MAX`) or, for constants, bare within the
+//! declaring contract and its descendants; locals shadowing a constant are
+//! not told apart.
+
+use super::model::{callable_name, field_text, line_of, visit, ParsedSource, SourceModel};
+use serde::Serialize;
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    EnumValue,
+    Constant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageKind {
+    Comparison,
+    Assignment,
+    Argument,
+    Return,
+    Other,
+}
+
+impl UsageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageKind::Comparison => "comparison",
+            UsageKind::Assignment => "assignment",
+            UsageKind::Argument => "argument",
+            UsageKind::Return => "return",
+            UsageKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Usage {
+    pub kind: UsageKind,
+    pub contract: String,
+    /// Enclosing function or modifier; `None` in state variable initializers.
+    pub function: Option<String>,
+    pub file: PathBuf,
+    pub line: usize,
+    /// The source line of the use, trimmed.
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossReference {
+    /// `Enum.Value` for enum values, the constant's name otherwise.
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Declaring contract; `None` at file level.
+    pub declared_in: Option<String>,
+    /// Initializer of a constant.
+    pub value: Option<String>,
+    pub file: PathBuf,
+    pub line: usize,
+    pub usages: Vec<Usage>,
+}
+
+/// A declared symbol and how a use can name it.
+struct Declaration {
+    reference: CrossReference,
+    /// Enum name, for enum values.
+    qualifier: Option<String>,
+    /// Value or constant name.
+    member: String,
+}
+
+pub fn build(parsed: &[ParsedSource]) -> Vec<CrossReference> {
+    let model = SourceModel::from_parsed(parsed);
+    let mut declarations = Vec::new();
+    for source in parsed {
+        collect_declarations(source, &mut declarations);
+    }
+
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        let root = source.tree.root_node();
+        let mut cursor = root.walk();
+        for decl in root.named_children(&mut cursor) {
+            if !matches!(
+                decl.kind(),
+                "contract_declaration" | "interface_declaration" | "library_declaration"
+            ) {
+                continue;
+            }
+            let contract = field_text(decl, "name", text).unwrap_or_default();
+            let scope: Vec<String> = std::iter::once(contract.clone())
+                .chain(model.ancestors(&contract).iter().map(|c| c.name.clone()))
+                .collect();
+            let Some(body) = decl.child_by_field_name("body") else {
+                continue;
+            };
+            let mut body_cursor = body.walk();
+            for member in body.named_children(&mut body_cursor) {
+                let function = callable_name(member, text);
+                visit(member, &mut |node| {
+                    let Some(index) = resolve(node, text, &declarations, &scope) else {
+                        return;
+                    };
+                    let line = line_of(node);
+                    declarations[index].reference.usages.push(Usage {
+                        kind: usage_kind(node, text),
+                        contract: contract.clone(),
+                        function: function.clone(),
+                        file: source.unit.path.clone(),
+                        line,
+                        code: text.lines().nth(line - 1).unwrap_or("").trim().to_string(),
+                    });
+                });
+            }
+        }
+    }
+
+    let mut references: Vec<CrossReference> =
+        declarations.into_iter().map(|d| d.reference).collect();
+    references.sort_by(|a, b| {
+        (a.kind == SymbolKind::Constant, &a.declared_in, &a.name).cmp(&(
+            b.kind == SymbolKind::Constant,
+            &b.declared_in,
+            &b.name,
+        ))
+    });
+    references
+}
+
+fn collect_declarations(source: &ParsedSource, out: &mut Vec<Declaration>) {
+    let text = source.unit.content.as_str();
+    let path = &source.unit.path;
+    let mut add = |node: TsNode, contract: Option<&str>| match node.kind() {
+        "enum_declaration" => {
+            let Some(name) = field_text(node, "name", text) else {
+                return;
+            };
+            let Some(body) = node.child_by_field_name("body") else {
+                return;
+            };
+            let mut cursor = body.walk();
+            for value in body
+                .named_children(&mut cursor)
+                .filter(|v| v.kind() == "enum_value")
+            {
+                let member = get_node_text(&value, text).to_string();
+                out.push(Declaration {
+                    reference: CrossReference {
+                        name: format!("{}.{}", name, member),
+                        kind: SymbolKind::EnumValue,
+                        declared_in: contract.map(str::to_string),
+                        value: None,
+                        file: path.clone(),
+                        line: line_of(value),
+                        usages: Vec::new(),
+                    },
+                    qualifier: Some(name.clone()),
+                    member,
+                });
+            }
+        }
+        "constant_variable_declaration" | "state_variable_declaration" => {
+            let mut cursor = node.walk();
+            let constant = node.kind() == "constant_variable_declaration"
+                || node.children(&mut cursor).any(|c| c.kind() == "constant");
+            let Some(name) = field_text(node, "name", text).filter(|_| constant) else {
+                return;
+            };
+            out.push(Declaration {
+                reference: CrossReference {
+                    name: name.clone(),
+                    kind: SymbolKind::Constant,
+                    declared_in: contract.map(str::to_string),
+                    value: field_text(node, "value", text),
+                    file: path.clone(),
+                    line: line_of(node),
+                    usages: Vec::new(),
+                },
+                qualifier: None,
+                member: name,
+            });
+        }
+        _ => {}
+    };
+
+    let root = source.tree.root_node();
+    let mut cursor = root.walk();
+    for decl in root.named_children(&mut cursor) {
+        match decl.kind() {
+            "contract_declaration" | "interface_declaration" | "library_declaration" => {
+                let contract = field_text(decl, "name", text).unwrap_or_default();
+                let Some(body) = decl.child_by_field_name("body") else {
+                    continue;
+                };
+                let mut body_cursor = body.walk();
+                for member in body.named_children(&mut body_cursor) {
+                    add(member, Some(&contract));
+                }
+            }
+            _ => add(decl, None),
+        }
+    }
+}
+
+/// Index of the declaration `node` refers to, if it is a use of one.
+fn resolve(
+    node: TsNode,
+    source: &str,
+    declarations: &[Declaration],
+    scope: &[String],
+) -> Option<usize> {
+    if node.parent().map(|p| p.kind()) != Some("expression") {
+        return None;
+    }
+    // Prefer declarations visible from `contract` over same-named ones
+    // elsewhere.
+    let visible = |d: &Declaration| {
+        d.reference
+            .declared_in
+            .as_ref()
+            .is_none_or(|owner| scope.contains(owner))
+    };
+    let pick = |candidates: Vec<usize>| {
+        candidates
+            .iter()
+            .copied()
+            .find(|&i| visible(&declarations[i]))
+            .or_else(|| candidates.first().copied())
+    };
+
+    match node.kind() {
+        "identifier" => {
+            let name = get_node_text(&node, source);
+            let candidates = declarations
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| d.qualifier.is_none() && d.member == name && visible(d))
+                .map(|(i, _)| i)
+                .collect();
+            pick(candidates)
+        }
+        "member_expression" => {
+            let object = node.child_by_field_name("object")?;
+            let property = node.child_by_field_name("property")?;
+            let object = get_node_text(&object, source);
+            let property = get_node_text(&property, source);
+            // `State.Open`, `Vault.State.Open` or `Vault.MAX`.
+            let last = object.rsplit('.').next().unwrap_or(object);
+            let candidates = declarations
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| {
+                    d.member == property
+                        && match &d.qualifier {
+                            Some(enum_name) => enum_name == last,
+                            None => d.reference.declared_in.as_deref() == Some(last),
+                        }
+                })
+                .map(|(i, _)| i)
+                .collect();
+            pick(candidates)
+        }
+        _ => None,
+    }
+}
+
+/// How the expression at `node` is used, judged from the nearest enclosing
+/// construct that gives it a role.
+fn usage_kind(node: TsNode, source: &str) -> UsageKind {
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        match parent.kind() {
+            "binary_expression" => {
+                let operator = parent
+                    .child_by_field_name("operator")
+                    .map(|o| get_node_text(&o, source));
+                if matches!(operator, Some("==" | "!=" | "<" | ">" | "<=" | ">=")) {
+                    return UsageKind::Comparison;
+                }
+            }
+            "assignment_expression"
+            | "augmented_assignment_expression"
+            | "variable_declaration_statement"
+            | "state_variable_declaration" => return UsageKind::Assignment,
+            "call_argument" => return UsageKind::Argument,
+            "return_statement" => return UsageKind::Return,
+            "statement" | "function_body" | "block_statement" => return UsageKind::Other,
+            _ => {}
+        }
+        current = parent.parent();
+    }
+    UsageKind::Other
+}
+
+pub fn render_cross_reference(references: &[CrossReference]) -> String {
+    let mut md = String::from("# Enum and Constant Cross-Reference\n\n");
+    if references.is_empty() {
+        md.push_str("No enums or constants declared.\n");
+        return md;
+    }
+
+    md.push_str("| Symbol | Kind | Declared in | Comparisons | Assignments | Other uses |\n");
+    md.push_str("|--------|------|-------------|-------------|-------------|------------|\n");
+    for reference in references {
+        let count = |kinds: &[UsageKind]| {
+            reference
+                .usages
+                .iter()
+                .filter(|u| kinds.contains(&u.kind))
+                .count()
+        };
+        md.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} | {} |\n",
+            reference.name,
+            match reference.kind {
+                SymbolKind::EnumValue => "enum value",
+                SymbolKind::Constant => "constant",
+            },
+            reference.declared_in.as_deref().unwrap_or("(file level)"),
+            count(&[UsageKind::Comparison]),
+            count(&[UsageKind::Assignment]),
+            count(&[UsageKind::Argument, UsageKind::Return, UsageKind::Other]),
+        ));
+    }
+
+    for reference in references {
+        md.push_str(&format!("\n## `{}`\n\n", reference.name));
+        if let Some(value) = &reference.value {
+            md.push_str(&format!("Value: `{}`\n\n", value));
+        }
+        if reference.usages.is_empty() {
+            md.push_str("_Never used._\n");
+            continue;
+        }
+        md.push_str("| Location | Function | Usage | Code |\n");
+        md.push_str("|----------|----------|-------|------|\n");
+        for usage in &reference.usages {
+            md.push_str(&format!(
+                "| {}:{} | {} | {} | `{}` |\n",
+                usage.file.display(),
+                usage.line,
+                match &usage.function {
+                    Some(function) => format!("{}.{}", usage.contract, function),
+                    None => usage.contract.clone(),
+                },
+                usage.kind.as_str(),
+                usage.code.replace('|', "\\|").replace('`', "'"),
+            ));
+        }
+    }
+    md
+}
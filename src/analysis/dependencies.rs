@@ -0,0 +1,400 @@
+//! What each function depends on outside its own contract's state.
+//!
+//! Badges mark functions that read an oracle, move or query tokens, run
+//! code through `delegatecall`, or read the block environment, directly or
+//! through the functions and modifiers they call. Each badge keeps the
+//! first expression that earned it, so a reviewer can find it.
+
+use super::model::{
+    local_types, modifier_invocations, parse_sources, summarize_callables, unwrap_expression,
+    visit, CallableSummary, ContractKind, ParsedSource, SourceModel, SourceUnit,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+/// Member calls that run another contract's code on this one's storage,
+/// directly or through OpenZeppelin's `Address` library.
+pub(crate) const DELEGATE_CALLS: &[&str] = &["delegatecall", "functionDelegateCall"];
+
+/// Chainlink, Uniswap TWAP, Aave and Compound price reads.
+const ORACLE_CALLS: &[&str] = &[
+    "latestRoundData",
+    "latestAnswer",
+    "getRoundData",
+    "consult",
+    "observe",
+    "getAssetPrice",
+    "getUnderlyingPrice",
+    "getPrice",
+    "peek",
+];
+
+/// ERC-20 and ERC-721 functions, and their `SafeERC20` wrappers.
+const TOKEN_CALLS: &[&str] = &[
+    "transfer",
+    "transferFrom",
+    "approve",
+    "balanceOf",
+    "allowance",
+    "totalSupply",
+    "ownerOf",
+    "safeTransfer",
+    "safeTransferFrom",
+    "safeApprove",
+    "safeIncreaseAllowance",
+    "safeDecreaseAllowance",
+    "forceApprove",
+    "permit",
+];
+
+/// Members of `block` that vary with the block a transaction lands in.
+const BLOCK_MEMBERS: &[&str] = &[
+    "timestamp",
+    "number",
+    "difficulty",
+    "prevrandao",
+    "basefee",
+    "blobbasefee",
+    "chainid",
+    "coinbase",
+    "gaslimit",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dependency {
+    Oracle,
+    Token,
+    DelegateCall,
+    BlockEnvironment,
+}
+
+impl Dependency {
+    pub const ALL: [Dependency; 4] = [
+        Dependency::Oracle,
+        Dependency::Token,
+        Dependency::DelegateCall,
+        Dependency::BlockEnvironment,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Dependency::Oracle => "oracle",
+            Dependency::Token => "token",
+            Dependency::DelegateCall => "delegatecall",
+            Dependency::BlockEnvironment => "block environment",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Badge {
+    pub dependency: Dependency,
+    /// The first expression making the function depend on it, or
+    /// `via f()` when a function or modifier it calls does.
+    pub evidence: String,
+}
+
+impl Badge {
+    /// `oracle (`feed.latestRoundData()`)` or `block environment (via `_now()`)`.
+    pub fn describe(&self) -> String {
+        match self.evidence.strip_prefix("via ") {
+            Some(callee) => format!("{} (via `{}`)", self.dependency.label(), callee),
+            None => format!("{} (`{}`)", self.dependency.label(), self.evidence),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionDependencies {
+    pub contract: String,
+    /// `constructor`, `fallback` and `receive` for those.
+    pub function: String,
+    pub file: PathBuf,
+    pub line: usize,
+    /// In [`Dependency`] order; empty when the function depends on none.
+    pub badges: Vec<Badge>,
+}
+
+impl FunctionDependencies {
+    /// The badges' labels, e.g. `oracle, token`.
+    pub fn labels(&self) -> String {
+        self.badges
+            .iter()
+            .map(|b| b.dependency.label())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// What a callable's own declaration and body depend on.
+#[derive(Debug, Default)]
+struct Direct {
+    badges: BTreeMap<Dependency, String>,
+    modifiers: Vec<String>,
+}
+
+type Summary = CallableSummary<Direct>;
+
+/// The functions `summary` calls and the modifiers it invokes, by name.
+fn callees(summary: &Summary) -> BTreeSet<&str> {
+    summary
+        .calls
+        .iter()
+        .chain(&summary.facts.modifiers)
+        .map(String::as_str)
+        .collect()
+}
+
+/// The dependencies of every function, modifier and constructor declared in
+/// a contract or library, in file and line order.
+pub fn analyze(parsed: &[ParsedSource], model: &SourceModel) -> Vec<FunctionDependencies> {
+    let callables: Vec<Summary> = summarize_callables(parsed, |contract, node, text| {
+        let state: HashMap<&str, &str> = model
+            .contract(contract)
+            .into_iter()
+            .chain(model.ancestors(contract))
+            .flat_map(|c| c.state_vars.iter())
+            .map(|v| (v.name.as_str(), v.type_name.as_str()))
+            .collect();
+        collect_direct(node, text, &state)
+    })
+    .into_iter()
+    .filter(|summary| {
+        model
+            .contract(&summary.contract)
+            .is_none_or(|c| c.kind != ContractKind::Interface)
+    })
+    .collect();
+
+    let mut functions = Vec::new();
+    for summary in &callables {
+        let lineage: HashSet<&str> = model
+            .ancestors(&summary.contract)
+            .into_iter()
+            .map(|c| c.name.as_str())
+            .chain([summary.contract.as_str()])
+            .collect();
+        let mut by_name: HashMap<&str, Vec<&Summary>> = HashMap::new();
+        for callable in callables
+            .iter()
+            .filter(|c| lineage.contains(c.contract.as_str()))
+        {
+            by_name
+                .entry(callable.name.as_str())
+                .or_default()
+                .push(callable);
+        }
+
+        let mut badges = summary.facts.badges.clone();
+        let mut visited = HashSet::from([summary.name.as_str()]);
+        for callee in callees(summary) {
+            let mut reached = BTreeSet::new();
+            collect_reached(callee, &by_name, &mut visited, &mut reached);
+            for dependency in reached {
+                badges
+                    .entry(dependency)
+                    .or_insert_with(|| format!("via {}()", callee));
+            }
+        }
+        functions.push(FunctionDependencies {
+            contract: summary.contract.clone(),
+            function: summary.name.clone(),
+            file: summary.file.clone(),
+            line: summary.line,
+            badges: badges
+                .into_iter()
+                .map(|(dependency, evidence)| Badge {
+                    dependency,
+                    evidence,
+                })
+                .collect(),
+        });
+    }
+    functions
+}
+
+/// The dependencies of `text` alone, for requests on one open document.
+pub fn for_source(text: &str) -> Vec<FunctionDependencies> {
+    let unit = SourceUnit {
+        path: PathBuf::new(),
+        content: text.to_string(),
+    };
+    let Ok(parsed) = parse_sources(std::slice::from_ref(&unit)) else {
+        return Vec::new();
+    };
+    analyze(&parsed, &SourceModel::from_parsed(&parsed))
+}
+
+/// Adds to `reached` what the callables named `name` depend on, directly or
+/// further down. `visited` holds the names already followed.
+fn collect_reached<'a>(
+    name: &'a str,
+    by_name: &HashMap<&str, Vec<&'a Summary>>,
+    visited: &mut HashSet<&'a str>,
+    reached: &mut BTreeSet<Dependency>,
+) {
+    if !visited.insert(name) {
+        return;
+    }
+    for summary in by_name.get(name).into_iter().flatten() {
+        reached.extend(summary.facts.badges.keys().copied());
+        for callee in callees(summary) {
+            collect_reached(callee, by_name, visited, reached);
+        }
+    }
+}
+
+/// `state` maps the contract's state variables to their types.
+fn collect_direct(node: TsNode, text: &str, state: &HashMap<&str, &str>) -> Direct {
+    let mut direct = Direct {
+        modifiers: modifier_invocations(node, text)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect(),
+        ..Direct::default()
+    };
+
+    let Some(body) = node.child_by_field_name("body") else {
+        return direct;
+    };
+    let locals = local_types(node, text);
+    let type_of = |name: &str| {
+        locals
+            .get(name)
+            .map(String::as_str)
+            .or_else(|| state.get(name).copied())
+    };
+    let mut found = |dependency: Dependency, evidence: String| {
+        direct.badges.entry(dependency).or_insert(evidence);
+    };
+    visit(body, &mut |n| match n.kind() {
+        "call_expression" => {
+            let Some(callee) = n.child_by_field_name("function").map(unwrap_expression) else {
+                return;
+            };
+            let callee_text = get_node_text(&callee, text);
+            if callee.kind() != "member_expression" {
+                if callee_text == "blockhash" {
+                    found(Dependency::BlockEnvironment, "blockhash()".to_string());
+                }
+                return;
+            }
+            let (Some(object), Some(property)) = (
+                callee.child_by_field_name("object").map(unwrap_expression),
+                callee.child_by_field_name("property"),
+            ) else {
+                return;
+            };
+            let object = get_node_text(&object, text);
+            let member = get_node_text(&property, text);
+            if matches!(
+                object,
+                "super" | "this" | "abi" | "msg" | "block" | "tx" | "string" | "bytes"
+            ) {
+                return;
+            }
+            let evidence = format!("{}.{}()", object, member);
+            // The declared type of `pool` or `pools[i]`, or the cast type of
+            // `IPool(pool)`.
+            let root = object.split(['[', '(']).next().unwrap_or(object).trim();
+            let described =
+                format!("{} {}", root, type_of(root).unwrap_or_default()).to_lowercase();
+            if DELEGATE_CALLS.contains(&member) {
+                found(Dependency::DelegateCall, evidence);
+            } else if ORACLE_CALLS.contains(&member)
+                || ["oracle", "feed", "aggregator"]
+                    .iter()
+                    .any(|hint| described.contains(hint))
+            {
+                found(Dependency::Oracle, evidence);
+            } else if (TOKEN_CALLS.contains(&member) && !is_address(root, type_of(root)))
+                || ["token", "erc20", "erc721", "erc1155"]
+                    .iter()
+                    .any(|hint| described.contains(hint))
+            {
+                found(Dependency::Token, evidence);
+            }
+        }
+        // The grammar parses `a && block.timestamp` as `(a && block).timestamp`,
+        // so the object only has to end with `block`.
+        "member_expression" => {
+            let (Some(object), Some(property)) = (
+                n.child_by_field_name("object").map(unwrap_expression),
+                n.child_by_field_name("property"),
+            ) else {
+                return;
+            };
+            let member = get_node_text(&property, text);
+            let object = get_node_text(&object, text);
+            let is_block = object.strip_suffix("block").is_some_and(|head| {
+                !head.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '.')
+            });
+            if is_block && BLOCK_MEMBERS.contains(&member) {
+                found(Dependency::BlockEnvironment, format!("block.{}", member));
+            }
+        }
+        "identifier"
+            if get_node_text(&n, text) == "now"
+                && n.parent().is_none_or(|p| p.kind() != "member_expression") =>
+        {
+            found(Dependency::BlockEnvironment, "now".to_string());
+        }
+        _ => {}
+    });
+    direct
+}
+
+/// `payable(to).transfer(amount)` and `to.transfer(amount)` on an address
+/// send ether, not tokens.
+fn is_address(root: &str, type_name: Option<&str>) -> bool {
+    root == "payable" || root == "address" || type_name.is_some_and(|t| t.starts_with("address"))
+}
+
+pub fn render_dependencies(functions: &[FunctionDependencies]) -> String {
+    let mut md = String::from("# External Dependencies\n\n");
+    let badged: Vec<&FunctionDependencies> =
+        functions.iter().filter(|f| !f.badges.is_empty()).collect();
+    md.push_str(&format!(
+        "**Functions with external dependencies:** {} of {}",
+        badged.len(),
+        functions.len()
+    ));
+    for dependency in Dependency::ALL {
+        let count = badged
+            .iter()
+            .filter(|f| f.badges.iter().any(|b| b.dependency == dependency))
+            .count();
+        md.push_str(&format!(" | **{}:** {}", dependency.label(), count));
+    }
+    md.push('\n');
+    if badged.is_empty() {
+        md.push_str(
+            "\nNo function depends on oracles, tokens, delegatecall or the block environment.\n",
+        );
+        return md;
+    }
+
+    let mut contract = None;
+    for function in badged {
+        if contract != Some(function.contract.as_str()) {
+            contract = Some(function.contract.as_str());
+            md.push_str(&format!(
+                "\n## {}\n\n`{}`\n\n| Function | Line | Depends on |\n|----------|------|------------|\n",
+                function.contract,
+                function.file.display()
+            ));
+        }
+        let badges: Vec<String> = function.badges.iter().map(Badge::describe).collect();
+        md.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            function.function,
+            function.line,
+            badges.join(", ")
+        ));
+    }
+    md
+}
@@ -0,0 +1,76 @@
+//! Fuzzable entry points for Foundry invariant and fuzz harnesses.
+//!
+//! Lists every external state-mutating function reachable on a deployable
+//! contract, including inherited ones, with ABI-canonical parameter types so
+//! harness generators can emit `targetSelector` calls and handler wrappers.
+
+use super::model::{ContractKind, SourceModel};
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTarget {
+    pub contract: String,
+    pub file: PathBuf,
+    pub functions: Vec<FuzzFunction>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzFunction {
+    pub name: String,
+    pub signature: String,
+    pub declared_in: String,
+    pub line: usize,
+    pub payable: bool,
+    pub params: Vec<FuzzParam>,
+    pub modifiers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+}
+
+pub fn extract(model: &SourceModel) -> Vec<FuzzTarget> {
+    let mut targets = Vec::new();
+
+    for contract in &model.contracts {
+        if contract.kind != ContractKind::Contract || contract.is_abstract {
+            continue;
+        }
+
+        let functions: Vec<FuzzFunction> = model
+            .external_functions(contract)
+            .into_iter()
+            .filter(|(_, function)| function.is_state_mutating())
+            .map(|(owner, function)| FuzzFunction {
+                name: function.name.clone(),
+                signature: model.signature(function),
+                declared_in: owner.name.clone(),
+                line: function.line,
+                payable: function.is_payable(),
+                params: function
+                    .params
+                    .iter()
+                    .map(|p| FuzzParam {
+                        name: p.name.clone(),
+                        type_name: model.abi_type(&p.type_name),
+                    })
+                    .collect(),
+                modifiers: function.modifiers.clone(),
+            })
+            .collect();
+
+        if !functions.is_empty() {
+            targets.push(FuzzTarget {
+                contract: contract.name.clone(),
+                file: contract.file.clone(),
+                functions,
+            });
+        }
+    }
+
+    targets
+}
@@ -0,0 +1,1162 @@
+//! Who controls what: the admin surface of each deployable contract.
+//!
+//! Recognizes `Ownable`, `Ownable2Step` and `AccessControl`, from
+//! OpenZeppelin or declared in the workspace, and hand-written checks such
+//! as `require(msg.sender == owner)`. For each contract it lists the
+//! state-changing functions only some account can call, and the paths that
+//! hand over control: transferring, accepting or renouncing ownership and
+//! granting, revoking or renouncing roles. Bases outside the workspace are
+//! assumed to behave as upstream.
+//!
+//! Timelocks are mapped too: a controller named or typed as a timelock, and
+//! operations a contract queues itself, stamping `block.timestamp + delay`
+//! and later checking the time against it. Restricted functions are either
+//! reachable only through one of them or callable directly.
+
+use super::model::{
+    modifier_invocations, summarize_callables, unwrap_expression, visit, CallableSummary,
+    ContractInfo, ContractKind, FunctionInfo, ParsedSource, SourceModel,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+/// Modifiers checking a named role or the owner, besides `onlyOwner` and
+/// `onlyRole`.
+const ACCESS_MODIFIERS: [&str; 8] = [
+    "onlyAdmin",
+    "onlyGovernance",
+    "onlyAuthorized",
+    "onlyMinter",
+    "onlyOperator",
+    "onlyTimelock",
+    "auth",
+    "requiresAuth",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminModel {
+    Ownable,
+    Ownable2Step,
+    AccessControl,
+    /// A `TimelockController` base, a timelock controller or queued
+    /// operations.
+    Timelock,
+    /// Other access-control modifiers or `msg.sender` checks.
+    Custom,
+}
+
+impl AdminModel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminModel::Ownable => "Ownable",
+            AdminModel::Ownable2Step => "Ownable2Step",
+            AdminModel::AccessControl => "AccessControl",
+            AdminModel::Timelock => "timelock",
+            AdminModel::Custom => "custom",
+        }
+    }
+}
+
+/// An account, or set of accounts, allowed to call a function.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum Controller {
+    Owner,
+    /// The account ownership is being transferred to.
+    PendingOwner,
+    /// Holders of a role, named as in the source.
+    Role(String),
+    /// Holders of the admin role of the role passed in.
+    RoleAdmin,
+    /// The account renouncing its own role.
+    RoleHolder,
+    /// The address stored in a state variable, from `msg.sender == x`.
+    Address(String),
+    /// An access-control modifier other than `onlyOwner` and `onlyRole`.
+    Modifier(String),
+}
+
+impl Controller {
+    pub fn label(&self) -> String {
+        match self {
+            Controller::Owner => "owner".to_string(),
+            Controller::PendingOwner => "pending owner".to_string(),
+            Controller::Role(role) => role.clone(),
+            Controller::RoleAdmin => "role admin".to_string(),
+            Controller::RoleHolder => "role holder".to_string(),
+            Controller::Address(variable) => format!("`{}` address", variable),
+            Controller::Modifier(modifier) => format!("`{}`", modifier),
+        }
+    }
+}
+
+/// Functions of OpenZeppelin's `Ownable`, `Ownable2Step` and
+/// `AccessControl` handing over control.
+const STANDARD_FUNCTIONS: [(&str, AdminAction); 6] = [
+    ("transferOwnership", AdminAction::TransferOwnership),
+    ("acceptOwnership", AdminAction::AcceptOwnership),
+    ("renounceOwnership", AdminAction::RenounceOwnership),
+    ("grantRole", AdminAction::GrantRole),
+    ("revokeRole", AdminAction::RevokeRole),
+    ("renounceRole", AdminAction::RenounceRole),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    TransferOwnership,
+    AcceptOwnership,
+    RenounceOwnership,
+    /// Writes the owner directly, outside the standard functions.
+    SetOwner,
+    /// Writes an address that `msg.sender` is checked against.
+    SetAdmin,
+    GrantRole,
+    RevokeRole,
+    RenounceRole,
+}
+
+impl AdminAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminAction::TransferOwnership => "transfer ownership",
+            AdminAction::AcceptOwnership => "accept ownership",
+            AdminAction::RenounceOwnership => "renounce ownership",
+            AdminAction::SetOwner => "set owner",
+            AdminAction::SetAdmin => "set admin address",
+            AdminAction::GrantRole => "grant role",
+            AdminAction::RevokeRole => "revoke role",
+            AdminAction::RenounceRole => "renounce role",
+        }
+    }
+
+    /// Who the upstream implementation lets take the action; `None` for
+    /// actions no upstream function takes.
+    fn upstream_controller(&self) -> Option<Controller> {
+        Some(match self {
+            AdminAction::TransferOwnership | AdminAction::RenounceOwnership => Controller::Owner,
+            AdminAction::AcceptOwnership => Controller::PendingOwner,
+            AdminAction::GrantRole | AdminAction::RevokeRole => Controller::RoleAdmin,
+            AdminAction::RenounceRole => Controller::RoleHolder,
+            AdminAction::SetOwner | AdminAction::SetAdmin => return None,
+        })
+    }
+
+    /// The action the upstream function `function` takes.
+    fn standard(function: &str) -> Option<Self> {
+        STANDARD_FUNCTIONS
+            .iter()
+            .find(|(name, _)| *name == function)
+            .map(|(_, action)| *action)
+    }
+
+    /// The upstream function taking the action.
+    fn function(&self) -> Option<&'static str> {
+        STANDARD_FUNCTIONS
+            .iter()
+            .find(|(_, action)| action == self)
+            .map(|(name, _)| *name)
+    }
+
+    fn is_ownership(&self) -> bool {
+        matches!(
+            self,
+            AdminAction::TransferOwnership
+                | AdminAction::AcceptOwnership
+                | AdminAction::RenounceOwnership
+                | AdminAction::SetOwner
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ControlledFunction {
+    /// `Contract.function`, naming the contract that declares it.
+    pub function: String,
+    pub controllers: Vec<Controller>,
+    /// The timelock it is only reachable through; `None` when its
+    /// controllers call it directly.
+    pub timelock: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdminPath {
+    pub action: AdminAction,
+    /// `Contract.function`, naming the contract that declares it.
+    pub function: String,
+    /// Who can take it; empty when anyone can.
+    pub controllers: Vec<Controller>,
+    /// Inherited from a base outside the workspace and assumed to behave
+    /// as upstream.
+    pub assumed: bool,
+    /// Reverts unconditionally, e.g. an override disabling renouncing.
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelockKind {
+    /// A controller named or typed as a timelock, e.g. `onlyTimelock` or a
+    /// `TimelockController timelock` checked against `msg.sender`.
+    Controller,
+    /// Operations the contract queues itself and runs after a delay.
+    Queue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Timelock {
+    pub kind: TimelockKind,
+    /// The controller's label, or the variable holding the time queued
+    /// operations become ready.
+    pub name: String,
+    /// Delays, with their values where declared, e.g. `MIN_DELAY = 2 days`.
+    pub delays: Vec<String>,
+    /// Entry points queueing operations.
+    pub queued_by: Vec<String>,
+    /// Entry points that only run through the timelock.
+    pub functions: Vec<String>,
+}
+
+impl Timelock {
+    pub fn label(&self) -> String {
+        match self.kind {
+            TimelockKind::Controller => self.name.clone(),
+            TimelockKind::Queue => format!("`{}` queue", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct RoleAdmin {
+    pub role: String,
+    pub admin: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Governance {
+    pub contract: String,
+    pub file: PathBuf,
+    pub line: usize,
+    pub models: Vec<AdminModel>,
+    /// Public and external state-changing functions only some accounts can
+    /// call, admin paths included.
+    pub functions: Vec<ControlledFunction>,
+    pub admin_paths: Vec<AdminPath>,
+    /// Role admins set with `_setRoleAdmin`; other roles are administered
+    /// by `DEFAULT_ADMIN_ROLE`.
+    pub role_admins: Vec<RoleAdmin>,
+    pub timelocks: Vec<Timelock>,
+    pub notes: Vec<String>,
+}
+
+/// What a function's declaration and body say about access control.
+#[derive(Debug, Default)]
+struct Facts {
+    controllers: BTreeSet<Controller>,
+    actions: BTreeSet<AdminAction>,
+    role_admins: Vec<RoleAdmin>,
+    /// Variables assigned to, as written on the left.
+    assigned: BTreeSet<String>,
+    /// Variables stamped with `block.timestamp + delay`, with the delay.
+    queues: BTreeMap<String, String>,
+    /// Identifiers compared with `block.timestamp`.
+    waits_for: BTreeSet<String>,
+    /// The body is a single revert.
+    only_reverts: bool,
+}
+
+type Summary = CallableSummary<Facts>;
+
+pub fn analyze(parsed: &[ParsedSource], model: &SourceModel) -> Vec<Governance> {
+    let facts: HashMap<(String, String, usize), Summary> =
+        summarize_callables(parsed, |_, node, text| collect_facts(node, text))
+            .into_iter()
+            .map(|summary| (summary.key(), summary))
+            .collect();
+    let mut values: HashMap<(String, String), String> = HashMap::new();
+    for source in parsed {
+        collect_values(source.tree.root_node(), &source.unit.content, &mut values);
+    }
+
+    model
+        .contracts
+        .iter()
+        .filter(|c| c.kind == ContractKind::Contract && !c.is_abstract)
+        .filter_map(|contract| {
+            let mut lineage = model.ancestors(&contract.name);
+            lineage.push(contract);
+            lineage.reverse();
+            let external_bases: BTreeSet<&str> = lineage
+                .iter()
+                .flat_map(|c| c.bases.iter())
+                .filter(|base| model.contract(base).is_none())
+                .map(String::as_str)
+                .collect();
+
+            // Most derived first, so overrides hide what they override.
+            let mut seen = HashSet::new();
+            let mut functions: Vec<(&str, &FunctionInfo, &Summary)> = Vec::new();
+            let mut by_name: HashMap<&str, Vec<&Summary>> = HashMap::new();
+            for declaring in &lineage {
+                for function in declaring.functions.iter().filter(|f| f.has_body) {
+                    let key = (declaring.name.clone(), function.name.clone(), function.line);
+                    if let Some(facts) = facts.get(&key) {
+                        by_name
+                            .entry(function.name.as_str())
+                            .or_default()
+                            .push(facts);
+                    }
+                    let signature: Vec<&str> = function
+                        .params
+                        .iter()
+                        .map(|p| p.type_name.as_str())
+                        .collect();
+                    if !seen.insert((function.name.as_str(), signature)) {
+                        continue;
+                    }
+                    let key = (declaring.name.clone(), function.name.clone(), function.line);
+                    if let Some(facts) = facts.get(&key) {
+                        functions.push((declaring.name.as_str(), function, facts));
+                    }
+                }
+            }
+
+            let role_admins: Vec<RoleAdmin> = lineage
+                .iter()
+                .flat_map(|declaring| {
+                    facts
+                        .iter()
+                        .filter(move |((c, _, _), _)| *c == declaring.name)
+                        .flat_map(|(_, f)| f.facts.role_admins.iter().cloned())
+                })
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+
+            let variables: HashSet<&str> = lineage
+                .iter()
+                .flat_map(|c| c.state_vars.iter().map(|v| v.name.as_str()))
+                .collect();
+            let mut governance = govern(
+                contract,
+                &functions,
+                &variables,
+                &external_bases,
+                role_admins,
+            );
+            let state: HashMap<&str, (&str, Option<&str>)> = lineage
+                .iter()
+                .flat_map(|c| {
+                    c.state_vars.iter().map(|v| {
+                        let value = values.get(&(c.name.clone(), v.name.clone()));
+                        (
+                            v.name.as_str(),
+                            (v.type_name.as_str(), value.map(String::as_str)),
+                        )
+                    })
+                })
+                .collect();
+            map_timelocks(&mut governance, &functions, &by_name, &state);
+            (!governance.models.is_empty()).then_some(governance)
+        })
+        .collect()
+}
+
+/// `functions` are those `contract` declares or inherits, and `variables`
+/// the state variables it declares or inherits.
+fn govern(
+    contract: &ContractInfo,
+    functions: &[(&str, &FunctionInfo, &Summary)],
+    variables: &HashSet<&str>,
+    external_bases: &BTreeSet<&str>,
+    role_admins: Vec<RoleAdmin>,
+) -> Governance {
+    // `msg.sender` compared with a parameter, as in `renounceRole`,
+    // restricts nothing by itself.
+    let is_variable =
+        |c: &Controller| !matches!(c, Controller::Address(v) if !variables.contains(v.as_str()));
+    let admin_variables: BTreeSet<&str> = functions
+        .iter()
+        .flat_map(|(_, _, summary)| summary.facts.controllers.iter())
+        .filter_map(|c| match c {
+            Controller::Address(v) if variables.contains(v.as_str()) => Some(v.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let mut controlled = Vec::new();
+    let mut admin_paths = Vec::new();
+    for (declaring, function, summary) in functions {
+        if !function.is_entry_point() || !function.is_state_mutating() {
+            continue;
+        }
+        let qualified = format!("{}.{}", declaring, function.name);
+        let standard = AdminAction::standard(&function.name);
+        let mut controllers: Vec<Controller> = summary
+            .facts
+            .controllers
+            .iter()
+            .filter(|c| is_variable(c))
+            .cloned()
+            .collect();
+        if let Some(action) = standard.filter(|_| controllers.is_empty()) {
+            if summary.calls_super || summary.facts.controllers.len() > controllers.len() {
+                controllers.extend(action.upstream_controller());
+            }
+        }
+        // A standard function is its own action, whatever it calls.
+        let actions: BTreeSet<AdminAction> = match standard {
+            Some(action) => BTreeSet::from([action]),
+            None => {
+                let mut actions = summary.facts.actions.clone();
+                if summary
+                    .facts
+                    .assigned
+                    .iter()
+                    .any(|v| admin_variables.contains(v.as_str()))
+                {
+                    actions.insert(AdminAction::SetAdmin);
+                }
+                actions
+            }
+        };
+        for action in actions {
+            admin_paths.push(AdminPath {
+                action,
+                function: qualified.clone(),
+                controllers: controllers.clone(),
+                assumed: false,
+                disabled: summary.facts.only_reverts,
+            });
+        }
+        if !controllers.is_empty() {
+            controlled.push(ControlledFunction {
+                function: qualified,
+                controllers,
+                timelock: None,
+            });
+        }
+    }
+
+    for base in external_bases {
+        let actions: &[AdminAction] = if base.starts_with("Ownable2Step") {
+            &[
+                AdminAction::TransferOwnership,
+                AdminAction::AcceptOwnership,
+                AdminAction::RenounceOwnership,
+            ]
+        } else if base.starts_with("Ownable") {
+            &[
+                AdminAction::TransferOwnership,
+                AdminAction::RenounceOwnership,
+            ]
+        } else if base.starts_with("AccessControl") {
+            &[
+                AdminAction::GrantRole,
+                AdminAction::RevokeRole,
+                AdminAction::RenounceRole,
+            ]
+        } else {
+            &[]
+        };
+        for &action in actions {
+            // Declared overrides were listed above.
+            let Some(function) = action.function() else {
+                continue;
+            };
+            if functions.iter().any(|(_, f, _)| f.name == function) {
+                continue;
+            }
+            admin_paths.push(AdminPath {
+                action,
+                function: format!("{}.{}", base, function),
+                controllers: action.upstream_controller().into_iter().collect(),
+                assumed: true,
+                disabled: false,
+            });
+        }
+    }
+    admin_paths.sort_by(|a, b| (a.action, &a.function).cmp(&(b.action, &b.function)));
+
+    let controllers: BTreeSet<&Controller> = controlled
+        .iter()
+        .flat_map(|f| f.controllers.iter())
+        .collect();
+    let mut models = BTreeSet::new();
+    for base in external_bases {
+        if base.starts_with("Ownable2Step") {
+            models.insert(AdminModel::Ownable2Step);
+        } else if base.starts_with("Ownable") {
+            models.insert(AdminModel::Ownable);
+        } else if base.starts_with("AccessControl") {
+            models.insert(AdminModel::AccessControl);
+        } else if base.starts_with("TimelockController") {
+            models.insert(AdminModel::Timelock);
+        }
+    }
+    let has_path = |action: AdminAction| {
+        admin_paths
+            .iter()
+            .any(|p| p.action == action && !p.disabled)
+    };
+    if has_path(AdminAction::AcceptOwnership) {
+        models.insert(AdminModel::Ownable2Step);
+    } else if controllers.contains(&Controller::Owner) || has_path(AdminAction::TransferOwnership) {
+        models.insert(AdminModel::Ownable);
+    }
+    if controllers
+        .iter()
+        .any(|c| matches!(c, Controller::Role(_) | Controller::RoleAdmin))
+    {
+        models.insert(AdminModel::AccessControl);
+    }
+    if controllers
+        .iter()
+        .any(|c| matches!(c, Controller::Address(_) | Controller::Modifier(_)))
+    {
+        models.insert(AdminModel::Custom);
+    }
+
+    let mut notes = Vec::new();
+    let owned = models.contains(&AdminModel::Ownable) || models.contains(&AdminModel::Ownable2Step);
+    if owned {
+        let transferable = admin_paths.iter().any(|p| {
+            p.action.is_ownership() && p.action != AdminAction::RenounceOwnership && !p.disabled
+        });
+        if !transferable {
+            notes.push("Ownership cannot be transferred.".to_string());
+        } else if !has_path(AdminAction::AcceptOwnership) {
+            notes.push(
+                "Ownership is transferred in one step; a wrong address loses control.".to_string(),
+            );
+        }
+        if has_path(AdminAction::RenounceOwnership) {
+            notes.push(
+                "The owner can renounce ownership, leaving owner-only functions uncallable."
+                    .to_string(),
+            );
+        }
+    }
+    if models.contains(&AdminModel::AccessControl) && role_admins.is_empty() {
+        notes.push("Every role is administered by `DEFAULT_ADMIN_ROLE`.".to_string());
+    }
+
+    Governance {
+        contract: contract.name.clone(),
+        file: contract.file.clone(),
+        line: contract.line,
+        models: models.into_iter().collect(),
+        functions: controlled,
+        admin_paths,
+        role_admins,
+        timelocks: Vec::new(),
+        notes,
+    }
+}
+
+/// Finds the timelocks of `governance`'s contract and which restricted
+/// functions only run through one. `state` maps each state variable to its
+/// type and declared value.
+fn map_timelocks(
+    governance: &mut Governance,
+    functions: &[(&str, &FunctionInfo, &Summary)],
+    by_name: &HashMap<&str, Vec<&Summary>>,
+    state: &HashMap<&str, (&str, Option<&str>)>,
+) {
+    let is_timelock = |controller: &Controller| match controller {
+        Controller::Address(variable) => {
+            variable.to_lowercase().contains("timelock")
+                || state
+                    .get(variable.as_str())
+                    .is_some_and(|(type_name, _)| type_name.contains("Timelock"))
+        }
+        Controller::Modifier(name) => name.to_lowercase().contains("timelock"),
+        Controller::Role(role) => role.to_uppercase().contains("TIMELOCK"),
+        _ => false,
+    };
+    let declared = |name: &str| match state.get(name) {
+        Some((_, Some(value))) => format!("{} = {}", name, value),
+        _ => name.to_string(),
+    };
+    let entry_points: Vec<(String, &Summary)> = functions
+        .iter()
+        .filter(|(_, f, _)| f.is_entry_point() && f.is_state_mutating())
+        .map(|(declaring, f, facts)| (format!("{}.{}", declaring, f.name), *facts))
+        .collect();
+
+    let mut timelocks = Vec::new();
+    let controllers: BTreeSet<&Controller> = governance
+        .functions
+        .iter()
+        .flat_map(|f| f.controllers.iter())
+        .filter(|c| is_timelock(c))
+        .collect();
+    if !controllers.is_empty() {
+        let mut delays: Vec<String> = state
+            .keys()
+            .filter(|name| name.to_lowercase().contains("delay"))
+            .map(|name| declared(name))
+            .collect();
+        delays.sort();
+        for controller in controllers {
+            timelocks.push(Timelock {
+                kind: TimelockKind::Controller,
+                name: controller.label(),
+                delays: delays.clone(),
+                queued_by: Vec::new(),
+                functions: governance
+                    .functions
+                    .iter()
+                    .filter(|f| f.controllers.iter().all(&is_timelock))
+                    .filter(|f| f.controllers.contains(controller))
+                    .map(|f| f.function.clone())
+                    .collect(),
+            });
+        }
+    }
+
+    let mut queues: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+    for (_, _, summary) in functions {
+        for (variable, delay) in &summary.facts.queues {
+            let delay = match delay.as_str() {
+                name if state.contains_key(name) => declared(name),
+                delay => delay.to_string(),
+            };
+            queues.entry(variable.as_str()).or_default().insert(delay);
+        }
+    }
+    for (variable, delays) in queues {
+        let runs: Vec<String> = entry_points
+            .iter()
+            .filter(|(_, facts)| {
+                reaches(facts, by_name, &mut HashSet::new(), &|f| {
+                    f.waits_for.contains(variable)
+                })
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        if runs.is_empty() {
+            continue;
+        }
+        timelocks.push(Timelock {
+            kind: TimelockKind::Queue,
+            name: variable.to_string(),
+            delays: delays.into_iter().collect(),
+            queued_by: entry_points
+                .iter()
+                .filter(|(_, facts)| {
+                    reaches(facts, by_name, &mut HashSet::new(), &|f| {
+                        f.queues.contains_key(variable)
+                    })
+                })
+                .map(|(name, _)| name.clone())
+                .collect(),
+            functions: runs,
+        });
+    }
+    if timelocks.is_empty() {
+        return;
+    }
+
+    for function in &mut governance.functions {
+        function.timelock = timelocks
+            .iter()
+            .find(|t| t.functions.contains(&function.function))
+            .map(|t| t.name.clone());
+    }
+    let queueing: BTreeSet<&str> = timelocks
+        .iter()
+        .flat_map(|t| t.queued_by.iter().map(String::as_str))
+        .collect();
+    let direct: Vec<String> = governance
+        .functions
+        .iter()
+        .filter(|f| f.timelock.is_none() && !queueing.contains(f.function.as_str()))
+        .map(|f| format!("`{}`", f.function))
+        .collect();
+    if !direct.is_empty() {
+        governance.notes.push(format!(
+            "Restricted functions callable without a timelock delay: {}.",
+            direct.join(", ")
+        ));
+    }
+    if !governance.models.contains(&AdminModel::Timelock) {
+        governance.models.push(AdminModel::Timelock);
+        governance.models.sort();
+    }
+    governance.timelocks = timelocks;
+}
+
+/// Whether `summary`'s function, or a function it calls directly or
+/// further down, satisfies `test`. `visited` holds the names already
+/// followed.
+fn reaches<'a>(
+    summary: &'a Summary,
+    by_name: &HashMap<&str, Vec<&'a Summary>>,
+    visited: &mut HashSet<&'a str>,
+    test: &dyn Fn(&Facts) -> bool,
+) -> bool {
+    if test(&summary.facts) {
+        return true;
+    }
+    for callee in &summary.calls {
+        if !visited.insert(callee.as_str()) {
+            continue;
+        }
+        for &f in by_name.get(callee.as_str()).into_iter().flatten() {
+            if reaches(f, by_name, visited, test) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// The initial values of state variables, by contract and name.
+fn collect_values(root: TsNode, source: &str, values: &mut HashMap<(String, String), String>) {
+    let mut cursor = root.walk();
+    for declaration in root.named_children(&mut cursor) {
+        let (Some(contract), Some(body)) = (
+            declaration.child_by_field_name("name"),
+            declaration.child_by_field_name("body"),
+        ) else {
+            continue;
+        };
+        let mut members = body.walk();
+        for member in body.named_children(&mut members) {
+            if member.kind() != "state_variable_declaration" {
+                continue;
+            }
+            if let (Some(name), Some(value)) = (
+                member.child_by_field_name("name"),
+                member.child_by_field_name("value"),
+            ) {
+                values.insert(
+                    (
+                        get_node_text(&contract, source).to_string(),
+                        get_node_text(&name, source).to_string(),
+                    ),
+                    get_node_text(&value, source).to_string(),
+                );
+            }
+        }
+    }
+}
+
+fn collect_facts(node: TsNode, source: &str) -> Facts {
+    let mut facts = Facts::default();
+    for (name, child) in modifier_invocations(node, source) {
+        let name = name.as_str();
+        match name {
+            "onlyOwner" => facts.controllers.insert(Controller::Owner),
+            "onlyRole" => match arguments(child, source).first() {
+                Some(role) => facts.controllers.insert(role_controller(role)),
+                None => false,
+            },
+            _ if ACCESS_MODIFIERS.contains(&name) => facts
+                .controllers
+                .insert(Controller::Modifier(name.to_string())),
+            _ => false,
+        };
+    }
+
+    let Some(body) = node.child_by_field_name("body") else {
+        return facts;
+    };
+    let is_constructor = node.kind() == "constructor_definition";
+    visit(body, &mut |n| match n.kind() {
+        "call_expression" => {
+            let Some(callee) = n.child_by_field_name("function").map(unwrap_expression) else {
+                return;
+            };
+            let callee = get_node_text(&callee, source);
+            let args = arguments(n, source);
+            match callee {
+                "_checkOwner" => {
+                    facts.controllers.insert(Controller::Owner);
+                }
+                "_checkRole" if !args.is_empty() => {
+                    facts.controllers.insert(role_controller(&args[0]));
+                }
+                "hasRole" if args.len() == 2 && is_sender(&args[1]) => {
+                    facts.controllers.insert(role_controller(&args[0]));
+                }
+                "_setRoleAdmin" if args.len() == 2 => facts.role_admins.push(RoleAdmin {
+                    role: args[0].clone(),
+                    admin: args[1].clone(),
+                }),
+                // Constructors set up the initial owner and roles.
+                "_transferOwnership" if !is_constructor => {
+                    facts.actions.insert(AdminAction::SetOwner);
+                }
+                "_grantRole" if !is_constructor => {
+                    facts.actions.insert(AdminAction::GrantRole);
+                }
+                "_revokeRole" if !is_constructor => {
+                    facts.actions.insert(AdminAction::RevokeRole);
+                }
+                _ => {}
+            }
+        }
+        "assignment_expression" if !is_constructor => {
+            let Some(left) = n.child_by_field_name("left").map(unwrap_expression) else {
+                return;
+            };
+            let left = get_node_text(&left, source);
+            if is_owner(left) {
+                facts.actions.insert(AdminAction::SetOwner);
+            }
+            facts.assigned.insert(left.to_string());
+            if let Some(delay) = n
+                .child_by_field_name("right")
+                .and_then(|right| delay_after_now(unwrap_expression(right), source))
+            {
+                facts.queues.insert(queued_name(left), delay.to_string());
+            }
+        }
+        "binary_expression" => {
+            let operator = n.child_by_field_name("operator");
+            let (Some(left), Some(right)) = (
+                n.child_by_field_name("left").map(unwrap_expression),
+                n.child_by_field_name("right").map(unwrap_expression),
+            ) else {
+                return;
+            };
+            match operator.map(|o| get_node_text(&o, source)) {
+                Some("==") => {}
+                Some("<" | "<=" | ">" | ">=") => {
+                    let (left_now, right_now) = (
+                        mentions_now(get_node_text(&left, source)),
+                        mentions_now(get_node_text(&right, source)),
+                    );
+                    let other = match (left_now, right_now) {
+                        (true, false) => right,
+                        (false, true) => left,
+                        _ => return,
+                    };
+                    visit(other, &mut |id| {
+                        if id.kind() == "identifier" {
+                            facts
+                                .waits_for
+                                .insert(get_node_text(&id, source).to_string());
+                        }
+                    });
+                    return;
+                }
+                _ => return,
+            }
+            let (left, right) = (get_node_text(&left, source), get_node_text(&right, source));
+            let other = if is_sender(left) {
+                right
+            } else if is_sender(right) {
+                left
+            } else {
+                return;
+            };
+            let controller = if is_owner(other.trim_end_matches("()")) {
+                Controller::Owner
+            } else {
+                Controller::Address(other.to_string())
+            };
+            facts.controllers.insert(controller);
+        }
+        _ => {}
+    });
+
+    let mut statements = body.walk();
+    let statements: Vec<TsNode> = body.named_children(&mut statements).collect();
+    facts.only_reverts =
+        matches!(statements.as_slice(), [statement] if is_revert(*statement, source));
+    facts
+}
+
+/// `onlyRole(getRoleAdmin(role))`, as in `grantRole`, checks the role
+/// admin.
+fn role_controller(role: &str) -> Controller {
+    if role.starts_with("getRoleAdmin(") {
+        Controller::RoleAdmin
+    } else {
+        Controller::Role(role.to_string())
+    }
+}
+
+fn arguments(node: TsNode, source: &str) -> Vec<String> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor)
+        .filter(|c| c.kind() == "call_argument")
+        .map(|c| get_node_text(&c, source).to_string())
+        .collect()
+}
+
+fn is_sender(expression: &str) -> bool {
+    matches!(expression, "msg.sender" | "_msgSender()")
+}
+
+fn is_owner(expression: &str) -> bool {
+    matches!(expression, "owner" | "_owner")
+}
+
+fn is_now(expression: &str) -> bool {
+    matches!(expression, "block.timestamp" | "now")
+}
+
+/// Whether `expression` reads the current time. The grammar parses
+/// `a && block.timestamp >= b` as `(a && block).timestamp >= b`, so the
+/// time may be the tail of a larger side.
+fn mentions_now(expression: &str) -> bool {
+    expression
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .any(is_now)
+}
+
+/// `DELAY` for `block.timestamp + DELAY`.
+fn delay_after_now<'t>(node: TsNode, source: &'t str) -> Option<&'t str> {
+    if node.kind() != "binary_expression"
+        || node
+            .child_by_field_name("operator")
+            .map(|o| get_node_text(&o, source))
+            != Some("+")
+    {
+        return None;
+    }
+    let left = get_node_text(
+        &unwrap_expression(node.child_by_field_name("left")?),
+        source,
+    );
+    let right = get_node_text(
+        &unwrap_expression(node.child_by_field_name("right")?),
+        source,
+    );
+    match (is_now(left), is_now(right)) {
+        (true, false) => Some(right),
+        (false, true) => Some(left),
+        _ => None,
+    }
+}
+
+/// The member or variable an assignment stamps: `eta` for
+/// `proposals[id].eta`, `queuedAt` for `queuedAt[id]`.
+fn queued_name(target: &str) -> String {
+    let mut depth = 0;
+    let mut outside = String::new();
+    for c in target.chars() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ if depth == 0 => outside.push(c),
+            _ => {}
+        }
+    }
+    outside
+        .rsplit('.')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+fn is_revert(statement: TsNode, source: &str) -> bool {
+    let mut found = false;
+    visit(statement, &mut |n| {
+        found |= n.kind() == "revert_statement"
+            || (n.kind() == "call_expression"
+                && n.child_by_field_name("function")
+                    .map(unwrap_expression)
+                    .is_some_and(|f| get_node_text(&f, source) == "revert"));
+    });
+    found
+}
+
+/// Flowchart of each contract's controllers and the functions they guard.
+/// Admin paths are drawn in red, role admins as dotted edges.
+pub fn render_mermaid(contracts: &[Governance]) -> String {
+    let mut mermaid = String::from("flowchart LR\n");
+    let escape = |text: &str| text.replace('"', "#quot;");
+    for (c, governance) in contracts.iter().enumerate() {
+        mermaid.push_str(&format!(
+            "    subgraph c{}[\"{}\"]\n",
+            c,
+            escape(&governance.contract)
+        ));
+        let mut controllers: Vec<Controller> = Vec::new();
+        let mut controller_id = |controller: &Controller, mermaid: &mut String| {
+            let index = match controllers.iter().position(|k| k == controller) {
+                Some(index) => index,
+                None => {
+                    controllers.push(controller.clone());
+                    mermaid.push_str(&format!(
+                        "        c{}k{}([\"{}\"])\n",
+                        c,
+                        controllers.len() - 1,
+                        escape(&controller.label())
+                    ));
+                    controllers.len() - 1
+                }
+            };
+            format!("c{}k{}", c, index)
+        };
+        let admin: BTreeSet<&str> = governance
+            .admin_paths
+            .iter()
+            .map(|p| p.function.as_str())
+            .collect();
+        let mut edges = Vec::new();
+        for (f, function) in governance.functions.iter().enumerate() {
+            mermaid.push_str(&format!(
+                "        c{}f{}[\"{}\"]\n",
+                c,
+                f,
+                escape(&function.function)
+            ));
+            if admin.contains(function.function.as_str()) {
+                mermaid.push_str(&format!("        class c{}f{} admin\n", c, f));
+            } else if function.timelock.is_some() {
+                mermaid.push_str(&format!("        class c{}f{} timelocked\n", c, f));
+            }
+            let arrow = match function.timelock {
+                Some(_) => "-- delayed -->",
+                None => "-->",
+            };
+            for controller in &function.controllers {
+                let id = controller_id(controller, &mut mermaid);
+                edges.push(format!("        {} {} c{}f{}\n", id, arrow, c, f));
+            }
+        }
+        for role_admin in &governance.role_admins {
+            let admin = controller_id(&Controller::Role(role_admin.admin.clone()), &mut mermaid);
+            let role = controller_id(&Controller::Role(role_admin.role.clone()), &mut mermaid);
+            edges.push(format!("        {} -. administers .-> {}\n", admin, role));
+        }
+        for edge in edges {
+            mermaid.push_str(&edge);
+        }
+        mermaid.push_str("    end\n");
+    }
+    mermaid.push_str("    classDef admin fill:#fee0d2,stroke:#de2d26\n");
+    mermaid.push_str("    classDef timelocked fill:#e5f5e0,stroke:#31a354\n");
+    mermaid
+}
+
+pub fn render_governance(contracts: &[Governance]) -> String {
+    let mut md = String::from("# Governance\n\n");
+    md.push_str(&format!(
+        "**Contracts with admin controls:** {}\n",
+        contracts.len()
+    ));
+    if contracts.is_empty() {
+        md.push_str("\nNo contract restricts who can call its functions.\n");
+        return md;
+    }
+    let list = |controllers: &[Controller]| {
+        if controllers.is_empty() {
+            "anyone".to_string()
+        } else {
+            controllers
+                .iter()
+                .map(Controller::label)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    for governance in contracts {
+        let models: Vec<&str> = governance.models.iter().map(AdminModel::as_str).collect();
+        md.push_str(&format!(
+            "\n## {} ({})\n\n`{}:{}`\n",
+            governance.contract,
+            models.join(", "),
+            governance.file.display(),
+            governance.line
+        ));
+        for note in &governance.notes {
+            md.push_str(&format!("\n> {}\n", note));
+        }
+        if !governance.admin_paths.is_empty() {
+            md.push_str("\n### Control Transfers\n\n");
+            md.push_str("| Action | Function | Who |\n");
+            md.push_str("|--------|----------|-----|\n");
+            for path in &governance.admin_paths {
+                let mut function = format!("`{}`", path.function);
+                if path.assumed {
+                    function.push_str(" (inherited)");
+                }
+                if path.disabled {
+                    function.push_str(" (disabled)");
+                }
+                md.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    path.action.as_str(),
+                    function,
+                    list(&path.controllers)
+                ));
+            }
+        }
+        if !governance.functions.is_empty() {
+            md.push_str("\n### Restricted Functions\n\n");
+            let timelocked = !governance.timelocks.is_empty();
+            if timelocked {
+                md.push_str("| Function | Who | Through |\n");
+                md.push_str("|----------|-----|---------|\n");
+            } else {
+                md.push_str("| Function | Who |\n");
+                md.push_str("|----------|-----|\n");
+            }
+            for function in &governance.functions {
+                md.push_str(&format!(
+                    "| `{}` | {} |",
+                    function.function,
+                    list(&function.controllers)
+                ));
+                if timelocked {
+                    let timelock = governance
+                        .timelocks
+                        .iter()
+                        .find(|t| function.timelock.as_ref() == Some(&t.name));
+                    match timelock {
+                        Some(timelock) => md.push_str(&format!(" {} |", timelock.label())),
+                        None => md.push_str(" **direct** |"),
+                    }
+                }
+                md.push('\n');
+            }
+        }
+        if !governance.timelocks.is_empty() {
+            md.push_str("\n### Timelocks\n\n");
+            md.push_str("| Timelock | Delay | Queued by | Runs |\n");
+            md.push_str("|----------|-------|-----------|------|\n");
+            let names = |names: &[String]| match names.is_empty() {
+                true => "-".to_string(),
+                false => names
+                    .iter()
+                    .map(|n| format!("`{}`", n))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            for timelock in &governance.timelocks {
+                let delay = match timelock.delays.is_empty() {
+                    true => "set in the timelock".to_string(),
+                    false => names(&timelock.delays),
+                };
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    timelock.label(),
+                    delay,
+                    names(&timelock.queued_by),
+                    names(&timelock.functions)
+                ));
+            }
+        }
+        if !governance.role_admins.is_empty() {
+            md.push_str("\n### Role Admins\n\n");
+            md.push_str("| Role | Administered by |\n");
+            md.push_str("|------|-----------------|\n");
+            for role_admin in &governance.role_admins {
+                md.push_str(&format!("| {} | {} |\n", role_admin.role, role_admin.admin));
+            }
+        }
+    }
+    md
+}
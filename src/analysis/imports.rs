@@ -0,0 +1,245 @@
+//! Import resolution between workspace files, used to narrow analysis to
+//! the files a change can affect.
+//!
+//! Relative imports resolve against the importing file. Other paths go
+//! through the project's remappings, then are tried against the workspace
+//! root, then matched against the end of a workspace file's path, which
+//! covers common remappings such as `@openzeppelin/=lib/openzeppelin-contracts/`
+//! when the project does not declare them.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+use traverse_graph::parser::{get_node_text, parse_solidity};
+
+/// An import path prefix and the directory it stands for, relative to the
+/// workspace folder, as in `@openzeppelin/=lib/openzeppelin-contracts/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Remapping {
+    pub prefix: String,
+    pub target: String,
+}
+
+impl Remapping {
+    /// Parses `prefix=target`, ignoring a `context:` before the prefix.
+    pub fn parse(line: &str) -> Option<Self> {
+        let (prefix, target) = line.trim().split_once('=')?;
+        let prefix = prefix.rsplit(':').next().unwrap_or(prefix).trim();
+        if prefix.is_empty() {
+            return None;
+        }
+        Some(Self {
+            prefix: prefix.to_string(),
+            target: target.trim().to_string(),
+        })
+    }
+}
+
+/// `import` rewritten by the remapping with the longest matching prefix.
+pub fn remap(remappings: &[Remapping], import: &str) -> Option<String> {
+    remappings
+        .iter()
+        .filter(|r| import.starts_with(&r.prefix))
+        .max_by_key(|r| r.prefix.len())
+        .map(|r| format!("{}{}", r.target, &import[r.prefix.len()..]))
+}
+
+/// The import paths written in `source`, in declaration order.
+pub fn import_paths(source: &str) -> Result<Vec<String>> {
+    let ast = parse_solidity(source)?;
+    let root = ast.tree.root_node();
+    let mut paths = Vec::new();
+    let mut cursor = root.walk();
+    for node in root.named_children(&mut cursor) {
+        if node.kind() != "import_directive" {
+            continue;
+        }
+        if let Some(path) = node.child_by_field_name("source") {
+            let text = get_node_text(&path, source);
+            paths.push(text.trim_matches(|c| c == '"' || c == '\'').to_string());
+        }
+    }
+    Ok(paths)
+}
+
+/// `roots` and every workspace file they import, directly or transitively,
+/// in `workspace_files` order. Imports that resolve to no workspace file are
+/// ignored.
+pub fn import_closure(
+    workspace_folder: &Path,
+    workspace_files: &[PathBuf],
+    roots: &[PathBuf],
+    remappings: &[Remapping],
+) -> Result<Vec<PathBuf>> {
+    let known: BTreeSet<&Path> = workspace_files.iter().map(PathBuf::as_path).collect();
+    let mut included: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut queue: VecDeque<PathBuf> = roots
+        .iter()
+        .filter(|root| known.contains(root.as_path()))
+        .cloned()
+        .collect();
+
+    while let Some(file) = queue.pop_front() {
+        if !included.insert(file.clone()) {
+            continue;
+        }
+        let content = std::fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        for import in import_paths(&content)? {
+            let target = resolve(
+                workspace_folder,
+                workspace_files,
+                remappings,
+                &file,
+                &import,
+            );
+            if let Some(target) = target {
+                if !included.contains(&target) {
+                    queue.push_back(target);
+                }
+            }
+        }
+    }
+
+    Ok(workspace_files
+        .iter()
+        .filter(|file| included.contains(*file))
+        .cloned()
+        .collect())
+}
+
+fn resolve(
+    workspace_folder: &Path,
+    workspace_files: &[PathBuf],
+    remappings: &[Remapping],
+    importer: &Path,
+    import: &str,
+) -> Option<PathBuf> {
+    let find = |candidate: PathBuf| workspace_files.iter().find(|f| **f == candidate).cloned();
+
+    if import.starts_with("./") || import.starts_with("../") {
+        return find(normalize(&importer.parent()?.join(import)));
+    }
+    if let Some(found) = remap(remappings, import)
+        .and_then(|remapped| find(normalize(&workspace_folder.join(remapped))))
+    {
+        return Some(found);
+    }
+    if let Some(found) = find(normalize(&workspace_folder.join(import))) {
+        return Some(found);
+    }
+
+    // Drop leading components (the remapping prefix) until exactly one
+    // workspace file ends with what is left; a bare file name is too
+    // ambiguous to match on.
+    let components: Vec<Component> = Path::new(import).components().collect();
+    for start in 0..components.len().saturating_sub(1) {
+        let suffix: PathBuf = components[start..].iter().collect();
+        let mut matches = workspace_files.iter().filter(|f| f.ends_with(&suffix));
+        if let (Some(found), None) = (matches.next(), matches.next()) {
+            return Some(found.clone());
+        }
+    }
+    None
+}
+
+/// An import that names no file on disk.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnresolvedImport {
+    /// The importing file.
+    pub file: PathBuf,
+    /// The path as written in the import statement.
+    pub import: String,
+}
+
+/// The files reachable through imports, and the imports that lead nowhere.
+#[derive(Debug, Clone, Default)]
+pub struct ImportClosure {
+    /// Sorted by path.
+    pub files: Vec<PathBuf>,
+    pub unresolved: Vec<UnresolvedImport>,
+}
+
+/// `entries` and every file they import, directly or transitively, read
+/// from disk. Unlike [`import_closure`] this follows imports outside
+/// `workspace_files`, into library and `node_modules` directories. Paths
+/// that are not relative are tried through `remappings`, against the
+/// workspace folder, under `node_modules`, then against the end of a
+/// workspace file's path.
+pub fn import_closure_on_disk(
+    workspace_folder: &Path,
+    workspace_files: &[PathBuf],
+    entries: &[PathBuf],
+    remappings: &[Remapping],
+) -> Result<ImportClosure> {
+    let mut included: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut unresolved = Vec::new();
+    let mut queue: VecDeque<PathBuf> = entries.iter().map(|e| normalize(e)).collect();
+    while let Some(file) = queue.pop_front() {
+        if !included.insert(file.clone()) {
+            continue;
+        }
+        let content = std::fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        for import in import_paths(&content)? {
+            match resolve_on_disk(
+                workspace_folder,
+                workspace_files,
+                remappings,
+                &file,
+                &import,
+            ) {
+                Some(target) if !included.contains(&target) => queue.push_back(target),
+                Some(_) => {}
+                None => unresolved.push(UnresolvedImport {
+                    file: file.clone(),
+                    import,
+                }),
+            }
+        }
+    }
+    Ok(ImportClosure {
+        files: included.into_iter().collect(),
+        unresolved,
+    })
+}
+
+fn resolve_on_disk(
+    workspace_folder: &Path,
+    workspace_files: &[PathBuf],
+    remappings: &[Remapping],
+    importer: &Path,
+    import: &str,
+) -> Option<PathBuf> {
+    if import.starts_with("./") || import.starts_with("../") {
+        let target = normalize(&importer.parent()?.join(import));
+        return target.is_file().then_some(target);
+    }
+    let candidates = [
+        remap(remappings, import).map(|remapped| workspace_folder.join(remapped)),
+        Some(workspace_folder.join(import)),
+        Some(workspace_folder.join("node_modules").join(import)),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .map(|candidate| normalize(&candidate))
+        .find(|candidate| candidate.is_file())
+        .or_else(|| resolve(workspace_folder, workspace_files, &[], importer, import))
+}
+
+/// Removes `.` and `..` components without touching the file system.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,194 @@
+//! Drift between an interface `IFoo` and its implementation `Foo`.
+//!
+//! Callers compile against the interface, so a member the implementation
+//! lacks, or implements with different parameter or return types, fails at
+//! runtime instead of at compile time when `Foo` does not inherit `IFoo`.
+//! External functions added to `Foo` but never to `IFoo` are unreachable
+//! for those callers. Each drift is reported on both files.
+
+use super::model::{ContractInfo, ContractKind, FunctionInfo, SourceModel};
+use super::{Confidence, Finding, Severity};
+use std::path::PathBuf;
+
+pub const RULE: &str = "interface-drift";
+
+/// An implementation of one interface member: the signature, its return
+/// types and where it is declared.
+struct Member {
+    signature: String,
+    /// `None` for getters, whose return types are not modeled.
+    returns: Option<Vec<String>>,
+    file: PathBuf,
+    line: usize,
+}
+
+pub fn check(model: &SourceModel) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for interface in model
+        .contracts
+        .iter()
+        .filter(|c| c.kind == ContractKind::Interface)
+    {
+        let Some(implementation) = interface
+            .name
+            .strip_prefix('I')
+            .and_then(|name| model.contract(name))
+            .filter(|c| c.kind == ContractKind::Contract)
+        else {
+            continue;
+        };
+        findings.extend(check_pair(model, interface, implementation));
+    }
+    findings
+}
+
+fn check_pair(
+    model: &SourceModel,
+    interface: &ContractInfo,
+    implementation: &ContractInfo,
+) -> Vec<Finding> {
+    // Pairing by name is a guess unless the implementation inherits the
+    // interface.
+    let inherits = model
+        .ancestors(&implementation.name)
+        .iter()
+        .any(|c| c.name == interface.name);
+    let confidence = if inherits {
+        Confidence::High
+    } else {
+        Confidence::Medium
+    };
+    let members = implemented(model, implementation);
+    let declared: Vec<(&ContractInfo, &FunctionInfo)> = std::iter::once(interface)
+        .chain(model.ancestors(&interface.name))
+        .flat_map(|owner| owner.functions.iter().map(move |f| (owner, f)))
+        .collect();
+
+    let mut findings = Vec::new();
+    let mut report = |message: String, at: [(&PathBuf, usize); 2], function: &str| {
+        for (file, line) in at {
+            findings.push(Finding {
+                rule: RULE,
+                severity: Severity::Medium,
+                confidence,
+                contract: Some(implementation.name.clone()),
+                function: Some(function.to_string()),
+                message: message.clone(),
+                file: Some(file.clone()),
+                line: Some(line),
+            });
+        }
+    };
+
+    for (owner, function) in &declared {
+        let signature = model.signature(function);
+        let returns = return_types(model, function);
+        match members.iter().find(|m| m.signature == signature) {
+            Some(Member {
+                returns: Some(member_returns),
+                file,
+                line,
+                ..
+            }) if *member_returns != returns => report(
+                format!(
+                    "`{}.{}` returns ({}) but `{}` returns ({})",
+                    interface.name,
+                    signature,
+                    returns.join(", "),
+                    implementation.name,
+                    member_returns.join(", ")
+                ),
+                [(&owner.file, function.line), (file, *line)],
+                &function.name,
+            ),
+            Some(_) => {}
+            None => {
+                let near = members
+                    .iter()
+                    .find(|m| m.signature.split('(').next() == Some(function.name.as_str()));
+                let (message, at) = match near {
+                    Some(member) => (
+                        format!(
+                            "`{}.{}` is implemented as `{}` in `{}`",
+                            interface.name, signature, member.signature, implementation.name
+                        ),
+                        (&member.file, member.line),
+                    ),
+                    None => (
+                        format!(
+                            "`{}.{}` has no implementation in `{}`",
+                            interface.name, signature, implementation.name
+                        ),
+                        (&implementation.file, implementation.line),
+                    ),
+                };
+                report(message, [(&owner.file, function.line), at], &function.name);
+            }
+        }
+    }
+
+    for function in implementation
+        .functions
+        .iter()
+        .filter(|f| f.visibility == "external")
+    {
+        let signature = model.signature(function);
+        let in_interface = declared
+            .iter()
+            .any(|(_, f)| model.signature(f) == signature);
+        let renamed = declared.iter().any(|(_, f)| f.name == function.name);
+        if !in_interface && !renamed {
+            report(
+                format!(
+                    "External `{}.{}` is missing from `{}`",
+                    implementation.name, signature, interface.name
+                ),
+                [
+                    (&implementation.file, function.line),
+                    (&interface.file, interface.line),
+                ],
+                &function.name,
+            );
+        }
+    }
+    findings
+}
+
+/// Public and external functions of `contract`, inherited ones included, and
+/// the getters of its public state variables.
+fn implemented(model: &SourceModel, contract: &ContractInfo) -> Vec<Member> {
+    let mut members: Vec<Member> = model
+        .external_functions(contract)
+        .into_iter()
+        .map(|(owner, function)| Member {
+            signature: model.signature(function),
+            returns: Some(return_types(model, function)),
+            file: owner.file.clone(),
+            line: function.line,
+        })
+        .collect();
+    let getters = std::iter::once(contract)
+        .chain(model.ancestors(&contract.name))
+        .flat_map(|owner| owner.state_vars.iter().map(move |v| (owner, v)))
+        .filter(|(_, v)| v.visibility == "public");
+    for (owner, var) in getters {
+        let signature = model.getter_signature(var);
+        if members.iter().all(|m| m.signature != signature) {
+            members.push(Member {
+                signature,
+                returns: None,
+                file: owner.file.clone(),
+                line: var.line,
+            });
+        }
+    }
+    members
+}
+
+fn return_types(model: &SourceModel, function: &FunctionInfo) -> Vec<String> {
+    function
+        .returns
+        .iter()
+        .map(|p| model.abi_type(&p.type_name))
+        .collect()
+}
@@ -0,0 +1,357 @@
+//! Raw numeric literals in guards, fee math and time arithmetic.
+//!
+//! Limits, basis points and durations are often typed inline instead of
+//! named. Listing them per function makes them reviewable, and values that
+//! are almost but not exactly equal across the codebase (`10000` and `9999`,
+//! `365 days` and `360 days`) point at a typo or a forgotten update.
+
+use super::model::{for_each_callable, line_of, visit, ParsedSource};
+use serde::Serialize;
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+/// Values within this relative distance of each other, but not equal, are
+/// reported as near misses.
+const NEAR_MISS_TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiteralContext {
+    /// Compared against in a `require`, `assert` or branch condition.
+    Bound,
+    /// Multiplied or divided by.
+    FeeMath,
+    /// A duration, or arithmetic on timestamps.
+    Time,
+}
+
+impl LiteralContext {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LiteralContext::Bound => "bound",
+            LiteralContext::FeeMath => "fee math",
+            LiteralContext::Time => "time",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MagicNumber {
+    /// The literal as written, e.g. `30 days`.
+    pub literal: String,
+    /// Value with units applied, in wei or seconds.
+    pub value: String,
+    pub context: LiteralContext,
+    pub contract: String,
+    pub function: String,
+    pub file: PathBuf,
+    pub line: usize,
+    #[serde(skip)]
+    numeric: f64,
+}
+
+/// Values of one context that are close but not equal.
+#[derive(Debug, Clone, Serialize)]
+pub struct NearMiss {
+    pub context: LiteralContext,
+    pub values: Vec<String>,
+    pub sites: Vec<MagicNumber>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Inventory {
+    pub literals: Vec<MagicNumber>,
+    pub near_misses: Vec<NearMiss>,
+}
+
+pub fn inventory(parsed: &[ParsedSource]) -> Inventory {
+    let mut literals = Vec::new();
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, function, node| {
+            visit(node, &mut |n| {
+                if n.kind() != "number_literal" {
+                    return;
+                }
+                let literal = get_node_text(&n, text).to_string();
+                let Some((numeric, value)) = parse_value(&literal) else {
+                    return;
+                };
+                // 0 and 1 are identities, not configuration.
+                if numeric <= 1.0 && !has_unit(n) {
+                    return;
+                }
+                let Some(context) = context(n, text, source.dialect.now_is_timestamp) else {
+                    return;
+                };
+                literals.push(MagicNumber {
+                    value,
+                    literal,
+                    context,
+                    contract: contract.to_string(),
+                    function: function.to_string(),
+                    file: source.unit.path.clone(),
+                    line: line_of(n),
+                    numeric,
+                });
+            });
+        });
+    }
+    let near_misses = near_misses(&literals);
+    Inventory {
+        literals,
+        near_misses,
+    }
+}
+
+fn near_misses(literals: &[MagicNumber]) -> Vec<NearMiss> {
+    let mut out = Vec::new();
+    for context in [
+        LiteralContext::Bound,
+        LiteralContext::FeeMath,
+        LiteralContext::Time,
+    ] {
+        let mut values: Vec<f64> = literals
+            .iter()
+            .filter(|l| l.context == context)
+            .map(|l| l.numeric)
+            .collect();
+        values.sort_by(f64::total_cmp);
+        values.dedup();
+
+        // Chains of neighbouring values that are each within tolerance.
+        let mut cluster: Vec<f64> = Vec::new();
+        for value in values {
+            if let Some(&last) = cluster.last() {
+                if (value - last) / value > NEAR_MISS_TOLERANCE {
+                    out.extend(near_miss(literals, context, &cluster));
+                    cluster.clear();
+                }
+            }
+            cluster.push(value);
+        }
+        out.extend(near_miss(literals, context, &cluster));
+    }
+    out
+}
+
+fn near_miss(
+    literals: &[MagicNumber],
+    context: LiteralContext,
+    values: &[f64],
+) -> Option<NearMiss> {
+    if values.len() < 2 {
+        return None;
+    }
+    Some(NearMiss {
+        context,
+        values: values
+            .iter()
+            .filter_map(|v| {
+                literals
+                    .iter()
+                    .find(|l| l.numeric == *v)
+                    .map(|l| l.value.clone())
+            })
+            .collect(),
+        sites: literals
+            .iter()
+            .filter(|l| l.context == context && values.contains(&l.numeric))
+            .cloned()
+            .collect(),
+    })
+}
+
+fn has_unit(node: TsNode) -> bool {
+    let mut cursor = node.walk();
+    let found = node
+        .named_children(&mut cursor)
+        .any(|c| c.kind() == "number_unit");
+    found
+}
+
+/// Value of a literal with underscores, hex, scientific notation and ether
+/// or time units, as a number and as exact decimal text where it is whole.
+pub(super) fn parse_value(literal: &str) -> Option<(f64, String)> {
+    let mut parts = literal.split_whitespace();
+    let number = parts.next()?.replace('_', "");
+    let multiplier: u128 = match parts.next() {
+        None | Some("wei" | "seconds") => 1,
+        Some("gwei") => 1_000_000_000,
+        Some("szabo") => 1_000_000_000_000,
+        Some("finney") => 1_000_000_000_000_000,
+        Some("ether") => 1_000_000_000_000_000_000,
+        Some("minutes") => 60,
+        Some("hours") => 3_600,
+        Some("days") => 86_400,
+        Some("weeks") => 604_800,
+        Some("years") => 31_536_000,
+        Some(_) => return None,
+    };
+    if let Some(hex) = number.strip_prefix("0x").or(number.strip_prefix("0X")) {
+        let value = u128::from_str_radix(hex, 16)
+            .ok()?
+            .checked_mul(multiplier)?;
+        return Some((value as f64, value.to_string()));
+    }
+    let value = number.parse::<f64>().ok()? * multiplier as f64;
+    let text = exact(&number, multiplier).map_or_else(|| value.to_string(), |v| v.to_string());
+    Some((value, text))
+}
+
+/// `number` times `multiplier` when the result is a whole `u128`.
+fn exact(number: &str, multiplier: u128) -> Option<u128> {
+    let (mantissa, exponent) = match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().ok()?),
+        None => (number, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits: u128 = format!("{}{}", whole, fraction).parse().ok()?;
+    let scale = exponent - fraction.len() as i32;
+    let value = digits.checked_mul(multiplier)?;
+    if scale >= 0 {
+        value.checked_mul(10u128.checked_pow(scale as u32)?)
+    } else {
+        let divisor = 10u128.checked_pow(scale.unsigned_abs())?;
+        (value % divisor == 0).then(|| value / divisor)
+    }
+}
+
+pub(super) fn is_time_unit(node: TsNode, source: &str) -> bool {
+    let mut cursor = node.walk();
+    let unit = node
+        .named_children(&mut cursor)
+        .find(|c| c.kind() == "number_unit")
+        .map(|c| get_node_text(&c, source));
+    matches!(
+        unit,
+        Some("seconds" | "minutes" | "hours" | "days" | "weeks" | "years")
+    )
+}
+
+/// Whether `expression` reads the clock or a duration-like variable.
+fn mentions_time(expression: TsNode, source: &str, now_is_timestamp: bool) -> bool {
+    let text = get_node_text(&expression, source).to_ascii_lowercase();
+    text.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .any(|word| {
+            (now_is_timestamp && word == "now")
+                || [
+                    "timestamp",
+                    "deadline",
+                    "duration",
+                    "period",
+                    "expiry",
+                    "delay",
+                ]
+                .iter()
+                .any(|hint| word.contains(hint))
+        })
+}
+
+/// Role of the literal at `node`, or `None` if it is in none of the reported
+/// positions.
+fn context(node: TsNode, source: &str, now_is_timestamp: bool) -> Option<LiteralContext> {
+    if is_time_unit(node, source) {
+        return Some(LiteralContext::Time);
+    }
+
+    let mut nearest: Option<&str> = None;
+    let mut guarded = false;
+    let mut current = node.parent();
+    while let Some(parent) = current {
+        match parent.kind() {
+            "binary_expression" => {
+                let operator = parent
+                    .child_by_field_name("operator")
+                    .map(|o| get_node_text(&o, source))
+                    .unwrap_or_default();
+                let arithmetic = matches!(operator, "+" | "-" | "*" | "/" | "%");
+                let comparison = matches!(operator, "==" | "!=" | "<" | ">" | "<=" | ">=");
+                if (arithmetic || comparison) && mentions_time(parent, source, now_is_timestamp) {
+                    return Some(LiteralContext::Time);
+                }
+                if nearest.is_none() && (comparison || matches!(operator, "*" | "/" | "%")) {
+                    nearest = Some(operator);
+                }
+            }
+            "call_expression" => {
+                let callee = parent
+                    .child_by_field_name("function")
+                    .map(|f| get_node_text(&f, source));
+                guarded |= matches!(callee, Some("require" | "assert"));
+            }
+            "if_statement" | "while_statement" | "for_statement" | "do_while_statement"
+            | "ternary_expression" => guarded = true,
+            "statement" | "function_body" => break,
+            _ => {}
+        }
+        current = parent.parent();
+    }
+
+    match nearest {
+        Some("*" | "/" | "%") => Some(LiteralContext::FeeMath),
+        Some(_) if guarded => Some(LiteralContext::Bound),
+        _ => None,
+    }
+}
+
+pub fn render_magic_numbers(inventory: &Inventory) -> String {
+    let mut md = String::from("# Magic Numbers\n\n");
+    if inventory.literals.is_empty() {
+        md.push_str("No numeric literals in guards, fee math or time arithmetic.\n");
+        return md;
+    }
+
+    if !inventory.near_misses.is_empty() {
+        md.push_str(&format!(
+            "## Near-Identical Values ({})\n\n",
+            inventory.near_misses.len()
+        ));
+        md.push_str("| Context | Values | Sites |\n");
+        md.push_str("|---------|--------|-------|\n");
+        for miss in &inventory.near_misses {
+            let sites: Vec<String> = miss
+                .sites
+                .iter()
+                .map(|s| {
+                    format!(
+                        "`{}` in {}.{} ({}:{})",
+                        s.literal,
+                        s.contract,
+                        s.function,
+                        s.file.display(),
+                        s.line
+                    )
+                })
+                .collect();
+            md.push_str(&format!(
+                "| {} | {} | {} |\n",
+                miss.context.as_str(),
+                miss.values.join(", "),
+                sites.join("<br>")
+            ));
+        }
+        md.push('\n');
+    }
+
+    md.push_str("## By Function\n");
+    let mut current: Option<(&str, &str)> = None;
+    for literal in &inventory.literals {
+        let key = (literal.contract.as_str(), literal.function.as_str());
+        if current != Some(key) {
+            current = Some(key);
+            md.push_str(&format!("\n### `{}.{}`\n\n", key.0, key.1));
+            md.push_str("| Line | Literal | Value | Context |\n");
+            md.push_str("|------|---------|-------|---------|\n");
+        }
+        md.push_str(&format!(
+            "| {} | `{}` | {} | {} |\n",
+            literal.line,
+            literal.literal,
+            literal.value,
+            literal.context.as_str()
+        ));
+    }
+    md
+}
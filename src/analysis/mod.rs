@@ -0,0 +1,255 @@
+//! Source-level analyses.
+//!
+//! Each audit check inspects the workspace sources and reports `Finding`s,
+//! which are collected into a single audit report.
+
+pub mod call_matrix;
+pub mod clones;
+pub mod cross_reference;
+pub mod dependencies;
+pub mod fuzz_targets;
+pub mod governance;
+pub mod imports;
+pub mod interface_drift;
+pub mod magic_numbers;
+pub mod model;
+pub mod overrides;
+pub mod pause_coverage;
+pub mod pr_comment;
+pub mod privilege_paths;
+pub mod quick_checks;
+pub mod read_only_reentrancy;
+pub mod selector_collision;
+pub mod self_balance;
+pub mod storage_access;
+pub mod storage_compare;
+pub mod storage_gap;
+pub mod storage_heatmap;
+pub mod storage_layout;
+pub mod summary;
+pub mod suppression;
+pub mod unit_mismatch;
+pub mod unresolved_calls;
+
+use crate::config::Config;
+use model::{parse_sources_with_profiles, SourceModel, SourceUnit};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+/// How likely a finding is to be a true positive. Heuristic checks report
+/// lower confidence so noisy results can be filtered out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub confidence: Confidence,
+    pub contract: Option<String>,
+    pub function: Option<String>,
+    pub message: String,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RuleInfo {
+    pub id: &'static str,
+    pub description: &'static str,
+}
+
+/// Every configurable rule: the workspace audit rules in the order they run,
+/// then the per-file checks run while typing.
+pub const RULES: &[RuleInfo] = &[
+    RuleInfo {
+        id: storage_gap::RULE,
+        description: "Upgradeable base contracts reserve a correctly sized `__gap`",
+    },
+    RuleInfo {
+        id: self_balance::RULE,
+        description: "Logic that depends on the contract's own ether balance",
+    },
+    RuleInfo {
+        id: selector_collision::RULE,
+        description: "Colliding or duplicated selectors behind a proxy or diamond",
+    },
+    RuleInfo {
+        id: interface_drift::RULE,
+        description: "Interface members missing from or mismatched in the matching implementation",
+    },
+    RuleInfo {
+        id: overrides::RULE,
+        description: "Virtual functions never overridden, incomplete override specifiers and unimplemented functions",
+    },
+    RuleInfo {
+        id: unit_mismatch::RULE,
+        description: "Arithmetic mixing 1e18-scaled and unscaled values, or seconds and day counts",
+    },
+    RuleInfo {
+        id: read_only_reentrancy::RULE,
+        description: "View functions reading state that is updated after an external call, and the contracts that consume them",
+    },
+    RuleInfo {
+        id: quick_checks::PARSE_ERROR,
+        description: "Syntax errors in the open file",
+    },
+    RuleInfo {
+        id: quick_checks::SHADOWING,
+        description: "Parameters and locals that shadow a state variable",
+    },
+    RuleInfo {
+        id: quick_checks::UNCHECKED_RETURN,
+        description: "Ignored results of low-level calls and ERC-20 transfers",
+    },
+];
+
+/// A rule together with its effective configuration, as listed to clients.
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSetting {
+    #[serde(flatten)]
+    pub info: RuleInfo,
+    pub enabled: bool,
+    /// Configured severity override; `None` keeps the rule's own severities.
+    pub severity: Option<Severity>,
+}
+
+pub fn rule_settings(config: &Config) -> Vec<RuleSetting> {
+    RULES
+        .iter()
+        .map(|info| RuleSetting {
+            info: *info,
+            enabled: config.is_rule_enabled(info.id),
+            severity: config.rule_severity(info.id),
+        })
+        .collect()
+}
+
+#[allow(dead_code)]
+pub fn run_audit(sources: &[SourceUnit]) -> anyhow::Result<Vec<Finding>> {
+    run_configured_audit(sources, &Config::default())
+}
+
+/// Runs the enabled rules and applies configured severity overrides.
+pub fn run_configured_audit(
+    sources: &[SourceUnit],
+    config: &Config,
+) -> anyhow::Result<Vec<Finding>> {
+    let parsed = parse_sources_with_profiles(sources, &config.profiles)?;
+    let model = SourceModel::from_parsed(&parsed);
+
+    let mut findings = Vec::new();
+    if config.is_rule_enabled(storage_gap::RULE) {
+        findings.extend(storage_gap::check(&model));
+    }
+    if config.is_rule_enabled(self_balance::RULE) {
+        findings.extend(self_balance::check(&parsed));
+    }
+    if config.is_rule_enabled(selector_collision::RULE) {
+        findings.extend(selector_collision::check(&model));
+    }
+    if config.is_rule_enabled(interface_drift::RULE) {
+        findings.extend(interface_drift::check(&model));
+    }
+    if config.is_rule_enabled(overrides::RULE) {
+        findings.extend(overrides::check(&model));
+    }
+    if config.is_rule_enabled(unit_mismatch::RULE) {
+        findings.extend(unit_mismatch::check(&parsed));
+    }
+    if config.is_rule_enabled(read_only_reentrancy::RULE) {
+        findings.extend(read_only_reentrancy::check(&parsed, &model));
+    }
+    apply_severity_overrides(&mut findings, config);
+
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| b.confidence.cmp(&a.confidence))
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+    Ok(findings)
+}
+
+pub(crate) fn apply_severity_overrides(findings: &mut [Finding], config: &Config) {
+    for finding in findings {
+        if let Some(severity) = config.rule_severity(finding.rule) {
+            finding.severity = severity;
+        }
+    }
+}
+
+pub fn render_report(findings: &[Finding], file_count: usize) -> String {
+    let mut md = String::from("# Audit Report\n\n");
+    md.push_str(&format!(
+        "**Files analyzed:** {} Solidity files\n\n",
+        file_count
+    ));
+
+    if findings.is_empty() {
+        md.push_str("No findings.\n");
+        return md;
+    }
+
+    md.push_str("| Severity | Confidence | Rule | Contract | Location | Message |\n");
+    md.push_str("|----------|------------|------|----------|----------|---------|\n");
+    for finding in findings {
+        let location = match (&finding.file, finding.line) {
+            (Some(file), Some(line)) => format!("{}:{}", file.display(), line),
+            (Some(file), None) => file.display().to_string(),
+            _ => String::new(),
+        };
+        let scope = match (&finding.contract, &finding.function) {
+            (Some(contract), Some(function)) => format!("{}.{}", contract, function),
+            (Some(contract), None) => contract.clone(),
+            _ => "-".to_string(),
+        };
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            finding.severity.as_str(),
+            finding.confidence.as_str(),
+            finding.rule,
+            scope,
+            location,
+            finding.message.replace('|', "\\|")
+        ));
+    }
+    md
+}
@@ -0,0 +1,698 @@
+//! Declaration-level view of Solidity sources.
+//!
+//! The call graph drops type and layout information, so source checks walk
+//! the tree-sitter AST directly and collect what they need here.
+
+use crate::profiles::{self, Dialect, Profile};
+use crate::traverse_adapter::frontend_for;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use traverse_graph::cg::Node;
+use traverse_graph::parser::{get_node_text, parse_solidity};
+use tree_sitter::{Node as TsNode, Tree};
+
+#[derive(Debug, Clone)]
+pub struct SourceUnit {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+pub struct ParsedSource<'a> {
+    pub unit: &'a SourceUnit,
+    pub tree: Tree,
+    pub dialect: Dialect,
+}
+
+/// Parses `sources` concurrently on the current rayon pool.
+pub fn parse_sources(sources: &[SourceUnit]) -> Result<Vec<ParsedSource<'_>>> {
+    parse_sources_with_profiles(sources, &[])
+}
+
+/// [`parse_sources`], with each file's dialect chosen by `profiles`.
+pub fn parse_sources_with_profiles<'a>(
+    sources: &'a [SourceUnit],
+    profiles: &[Profile],
+) -> Result<Vec<ParsedSource<'a>>> {
+    sources
+        .par_iter()
+        .map(|unit| {
+            // Files of other languages hold no Solidity declarations.
+            let source = if frontend_for(&unit.path).is_some() {
+                ""
+            } else {
+                unit.content.as_str()
+            };
+            let ast = parse_solidity(source)?;
+            Ok(ParsedSource {
+                unit,
+                tree: ast.tree,
+                dialect: profiles::dialect(profiles, &unit.path, &unit.content),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractKind {
+    Contract,
+    Interface,
+    Library,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContractInfo {
+    pub name: String,
+    pub kind: ContractKind,
+    pub is_abstract: bool,
+    pub bases: Vec<String>,
+    pub state_vars: Vec<StateVarInfo>,
+    pub functions: Vec<FunctionInfo>,
+    pub structs: Vec<StructInfo>,
+    pub enums: Vec<String>,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StateVarInfo {
+    pub name: String,
+    pub type_name: String,
+    pub visibility: String,
+    pub is_constant: bool,
+    pub is_immutable: bool,
+    pub line: usize,
+}
+
+impl StateVarInfo {
+    /// Constants and immutables live in bytecode, not in storage slots.
+    pub fn occupies_storage(&self) -> bool {
+        !self.is_constant && !self.is_immutable
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub visibility: String,
+    pub mutability: Option<String>,
+    pub params: Vec<ParamInfo>,
+    pub returns: Vec<ParamInfo>,
+    pub modifiers: Vec<String>,
+    pub is_virtual: bool,
+    pub is_override: bool,
+    /// Bases named in `override(A, B)`; empty for a bare `override`.
+    pub overrides: Vec<String>,
+    /// `false` for declarations without an implementation.
+    pub has_body: bool,
+    pub line: usize,
+}
+
+impl FunctionInfo {
+    pub fn is_entry_point(&self) -> bool {
+        matches!(self.visibility.as_str(), "public" | "external")
+    }
+
+    pub fn is_state_mutating(&self) -> bool {
+        !matches!(
+            self.mutability.as_deref(),
+            Some("view" | "pure" | "constant")
+        )
+    }
+
+    pub fn is_payable(&self) -> bool {
+        self.mutability.as_deref() == Some("payable")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParamInfo {
+    pub name: String,
+    pub type_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StructInfo {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SourceModel {
+    pub contracts: Vec<ContractInfo>,
+    pub structs: Vec<StructInfo>,
+    pub enums: Vec<String>,
+}
+
+impl SourceModel {
+    pub fn from_parsed(parsed: &[ParsedSource]) -> Self {
+        let mut model = SourceModel::default();
+        for source in parsed {
+            model.collect(source.tree.root_node(), source.unit);
+        }
+        model
+    }
+
+    pub fn contract(&self, name: &str) -> Option<&ContractInfo> {
+        self.contracts.iter().find(|c| c.name == name)
+    }
+
+    pub fn find_struct(&self, name: &str) -> Option<&StructInfo> {
+        self.contracts
+            .iter()
+            .flat_map(|c| c.structs.iter())
+            .chain(self.structs.iter())
+            .find(|s| s.name == name)
+    }
+
+    pub fn is_enum(&self, name: &str) -> bool {
+        self.enums.iter().any(|e| e == name)
+            || self
+                .contracts
+                .iter()
+                .any(|c| c.enums.iter().any(|e| e == name))
+    }
+
+    /// Canonical ABI spelling of a Solidity type, as used in signatures.
+    pub fn abi_type(&self, type_name: &str) -> String {
+        self.abi_type_at(type_name, 0)
+    }
+
+    fn abi_type_at(&self, type_name: &str, depth: usize) -> String {
+        let type_name = type_name.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if let Some(open) = type_name.rfind('[') {
+            if type_name.ends_with(']') {
+                let element = self.abi_type_at(&type_name[..open], depth);
+                let len: String = type_name[open..].split_whitespace().collect();
+                return format!("{}{}", element, len);
+            }
+        }
+
+        match type_name.as_str() {
+            "uint" => return "uint256".to_string(),
+            "int" => return "int256".to_string(),
+            "byte" => return "bytes1".to_string(),
+            "address payable" => return "address".to_string(),
+            _ => {}
+        }
+
+        let simple = type_name.rsplit('.').next().unwrap_or(&type_name);
+        if self.is_enum(simple) {
+            return "uint8".to_string();
+        }
+        if let Some(info) = self.find_struct(simple) {
+            if depth < 8 {
+                let members: Vec<String> = info
+                    .members
+                    .iter()
+                    .map(|m| self.abi_type_at(m, depth + 1))
+                    .collect();
+                return format!("({})", members.join(","));
+            }
+        }
+        if self.contract(simple).is_some() {
+            return "address".to_string();
+        }
+        type_name
+    }
+
+    /// Canonical signature of a function, e.g. `transfer(address,uint256)`.
+    pub fn signature(&self, function: &FunctionInfo) -> String {
+        let params: Vec<String> = function
+            .params
+            .iter()
+            .map(|p| self.abi_type(&p.type_name))
+            .collect();
+        format!("{}({})", function.name, params.join(","))
+    }
+
+    /// Public and external functions callable on `contract`, including
+    /// inherited ones. The most derived definition of each signature wins.
+    pub fn external_functions<'a>(
+        &'a self,
+        contract: &'a ContractInfo,
+    ) -> Vec<(&'a ContractInfo, &'a FunctionInfo)> {
+        let mut seen = HashSet::new();
+        let mut functions = Vec::new();
+        let chain =
+            std::iter::once(contract).chain(self.ancestors(&contract.name).into_iter().rev());
+        for owner in chain {
+            for function in &owner.functions {
+                if seen.insert(self.signature(function)) && function.is_entry_point() {
+                    functions.push((owner, function));
+                }
+            }
+        }
+        functions
+    }
+
+    /// Signature of the getter the compiler generates for a public state variable.
+    pub fn getter_signature(&self, var: &StateVarInfo) -> String {
+        let mut params = Vec::new();
+        let mut current = var
+            .type_name
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        loop {
+            if let Some(inner) = current
+                .strip_prefix("mapping(")
+                .and_then(|rest| rest.strip_suffix(')'))
+            {
+                let Some((key, value)) = inner.split_once("=>") else {
+                    break;
+                };
+                let key = key.split_whitespace().next().unwrap_or_default();
+                params.push(self.abi_type(key));
+                current = value.trim().to_string();
+            } else if current.ends_with(']') {
+                let open = current.rfind('[').unwrap_or(0);
+                params.push("uint256".to_string());
+                current = current[..open].trim().to_string();
+            } else {
+                break;
+            }
+        }
+        format!("{}({})", var.name, params.join(","))
+    }
+
+    pub fn is_upgradeable(&self, contract: &ContractInfo) -> bool {
+        let marks_upgradeable =
+            |name: &str| name == "Initializable" || name.ends_with("Upgradeable");
+        marks_upgradeable(&contract.name)
+            || contract.bases.iter().any(|b| marks_upgradeable(b))
+            || self
+                .ancestors(&contract.name)
+                .iter()
+                .any(|c| marks_upgradeable(&c.name))
+    }
+
+    /// Linearized base chain of `name`, most base-like first, excluding `name`.
+    pub fn ancestors(&self, name: &str) -> Vec<&ContractInfo> {
+        let mut out: Vec<&ContractInfo> = Vec::new();
+        let mut visiting = vec![name.to_string()];
+        self.collect_ancestors(name, &mut out, &mut visiting);
+        out
+    }
+
+    fn collect_ancestors<'a>(
+        &'a self,
+        name: &str,
+        out: &mut Vec<&'a ContractInfo>,
+        visiting: &mut Vec<String>,
+    ) {
+        let Some(contract) = self.contract(name) else {
+            return;
+        };
+        for base in &contract.bases {
+            if visiting.contains(base) || out.iter().any(|c| &c.name == base) {
+                continue;
+            }
+            visiting.push(base.clone());
+            self.collect_ancestors(base, out, visiting);
+            if let Some(info) = self.contract(base) {
+                out.push(info);
+            }
+        }
+    }
+
+    fn collect(&mut self, root: TsNode, unit: &SourceUnit) {
+        let source = unit.content.as_str();
+        let mut cursor = root.walk();
+        for child in root.named_children(&mut cursor) {
+            match child.kind() {
+                "contract_declaration" | "interface_declaration" | "library_declaration" => {
+                    self.contracts.push(parse_contract(child, unit));
+                }
+                "struct_declaration" => self.structs.push(parse_struct(child, source)),
+                "enum_declaration" => {
+                    if let Some(name) = field_text(child, "name", source) {
+                        self.enums.push(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn parse_contract(node: TsNode, unit: &SourceUnit) -> ContractInfo {
+    let source = unit.content.as_str();
+    let kind = match node.kind() {
+        "interface_declaration" => ContractKind::Interface,
+        "library_declaration" => ContractKind::Library,
+        _ => ContractKind::Contract,
+    };
+
+    let mut contract = ContractInfo {
+        name: field_text(node, "name", source).unwrap_or_default(),
+        kind,
+        is_abstract: false,
+        bases: Vec::new(),
+        state_vars: Vec::new(),
+        functions: Vec::new(),
+        structs: Vec::new(),
+        enums: Vec::new(),
+        file: unit.path.clone(),
+        line: line_of(node),
+    };
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "abstract" => contract.is_abstract = true,
+            "inheritance_specifier" => {
+                if let Some(ancestor) = child.child_by_field_name("ancestor") {
+                    contract
+                        .bases
+                        .push(get_node_text(&ancestor, source).to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(body) = node.child_by_field_name("body") {
+        let mut cursor = body.walk();
+        for member in body.named_children(&mut cursor) {
+            match member.kind() {
+                "state_variable_declaration" => {
+                    contract.state_vars.push(parse_state_var(member, source));
+                }
+                "function_definition" => {
+                    contract
+                        .functions
+                        .push(parse_function(member, source, kind));
+                }
+                "struct_declaration" => contract.structs.push(parse_struct(member, source)),
+                "enum_declaration" => {
+                    if let Some(name) = field_text(member, "name", source) {
+                        contract.enums.push(name);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    contract
+}
+
+fn parse_state_var(node: TsNode, source: &str) -> StateVarInfo {
+    let mut var = StateVarInfo {
+        name: field_text(node, "name", source).unwrap_or_default(),
+        type_name: field_text(node, "type", source).unwrap_or_default(),
+        visibility: field_text(node, "visibility", source).unwrap_or_else(|| "internal".into()),
+        is_constant: false,
+        is_immutable: false,
+        line: line_of(node),
+    };
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "constant" => var.is_constant = true,
+            "immutable" => var.is_immutable = true,
+            _ => {}
+        }
+    }
+    var
+}
+
+fn parse_function(node: TsNode, source: &str, kind: ContractKind) -> FunctionInfo {
+    let mut function = FunctionInfo {
+        name: field_text(node, "name", source).unwrap_or_default(),
+        visibility: String::new(),
+        mutability: None,
+        params: Vec::new(),
+        returns: Vec::new(),
+        modifiers: Vec::new(),
+        is_virtual: false,
+        is_override: false,
+        overrides: Vec::new(),
+        has_body: node.child_by_field_name("body").is_some(),
+        line: line_of(node),
+    };
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        match child.kind() {
+            "visibility" => function.visibility = get_node_text(&child, source).to_string(),
+            "state_mutability" => {
+                function.mutability = Some(get_node_text(&child, source).to_string());
+            }
+            "parameter" => function.params.push(parse_param(child, source)),
+            "modifier_invocation" => {
+                let mut inner = child.walk();
+                let name = child
+                    .named_children(&mut inner)
+                    .find(|c| c.kind() == "identifier")
+                    .map(|c| get_node_text(&c, source).to_string());
+                function.modifiers.extend(name);
+            }
+            "virtual" => function.is_virtual = true,
+            "override_specifier" => {
+                function.is_override = true;
+                let mut inner = child.walk();
+                function.overrides = child
+                    .named_children(&mut inner)
+                    .map(|c| get_node_text(&c, source).to_string())
+                    .collect();
+            }
+            "return_type_definition" => {
+                let mut inner = child.walk();
+                function.returns = child
+                    .named_children(&mut inner)
+                    .filter(|c| c.kind() == "parameter")
+                    .map(|c| parse_param(c, source))
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+
+    if function.visibility.is_empty() {
+        function.visibility = match kind {
+            ContractKind::Interface => "external",
+            _ => "public",
+        }
+        .to_string();
+    }
+    function
+}
+
+fn parse_param(node: TsNode, source: &str) -> ParamInfo {
+    ParamInfo {
+        name: field_text(node, "name", source).unwrap_or_default(),
+        type_name: field_text(node, "type", source).unwrap_or_default(),
+    }
+}
+
+fn parse_struct(node: TsNode, source: &str) -> StructInfo {
+    let mut members = Vec::new();
+    if let Some(body) = node.child_by_field_name("body") {
+        let mut cursor = body.walk();
+        for member in body.named_children(&mut cursor) {
+            if let Some(type_name) = field_text(member, "type", source) {
+                members.push(type_name);
+            }
+        }
+    }
+    StructInfo {
+        name: field_text(node, "name", source).unwrap_or_default(),
+        members,
+    }
+}
+
+pub(crate) fn field_text(node: TsNode, field: &str, source: &str) -> Option<String> {
+    node.child_by_field_name(field)
+        .map(|n| get_node_text(&n, source).to_string())
+}
+
+pub(crate) fn line_of(node: TsNode) -> usize {
+    node.start_position().row + 1
+}
+
+/// Calls `f` with the contract name, callable name and node of every function,
+/// modifier, constructor, fallback and receive body in the file.
+pub(crate) fn for_each_callable<'t>(
+    root: TsNode<'t>,
+    source: &str,
+    mut f: impl FnMut(&str, &str, TsNode<'t>),
+) {
+    let mut cursor = root.walk();
+    for decl in root.named_children(&mut cursor) {
+        let contract = match decl.kind() {
+            "contract_declaration" | "interface_declaration" | "library_declaration" => {
+                field_text(decl, "name", source).unwrap_or_default()
+            }
+            _ => continue,
+        };
+        let Some(body) = decl.child_by_field_name("body") else {
+            continue;
+        };
+        let mut body_cursor = body.walk();
+        for member in body.named_children(&mut body_cursor) {
+            if let Some(name) = callable_name(member, source) {
+                f(&contract, &name, member);
+            }
+        }
+    }
+}
+
+pub(crate) fn callable_name(node: TsNode, source: &str) -> Option<String> {
+    match node.kind() {
+        "function_definition" | "modifier_definition" => field_text(node, "name", source),
+        "constructor_definition" => Some("constructor".to_string()),
+        "fallback_receive_definition" => node
+            .child(0)
+            .map(|first| get_node_text(&first, source).to_string()),
+        _ => None,
+    }
+}
+
+/// A callable's calls to other callables, with the facts an analysis
+/// collects from it.
+#[derive(Debug)]
+pub(crate) struct CallableSummary<T> {
+    pub contract: String,
+    pub name: String,
+    pub file: PathBuf,
+    pub line: usize,
+    /// Functions called by bare name or through `super`.
+    pub calls: BTreeSet<String>,
+    /// Calls a function through `super`.
+    pub calls_super: bool,
+    pub facts: T,
+}
+
+impl<T> CallableSummary<T> {
+    /// Contract, name and line, as overloads share a name.
+    pub fn key(&self) -> (String, String, usize) {
+        (self.contract.clone(), self.name.clone(), self.line)
+    }
+}
+
+/// Summarizes every callable of `parsed` in file and line order, with the
+/// facts `collect` gathers from its contract name, node and source.
+pub(crate) fn summarize_callables<T>(
+    parsed: &[ParsedSource],
+    mut collect: impl FnMut(&str, TsNode, &str) -> T,
+) -> Vec<CallableSummary<T>> {
+    let mut summaries = Vec::new();
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, name, node| {
+            let mut calls = BTreeSet::new();
+            let mut calls_super = false;
+            if let Some(body) = node.child_by_field_name("body") {
+                visit(body, &mut |n| {
+                    let Some(callee) = n
+                        .child_by_field_name("function")
+                        .filter(|_| n.kind() == "call_expression")
+                        .map(unwrap_expression)
+                    else {
+                        return;
+                    };
+                    let callee = get_node_text(&callee, text);
+                    let name = match callee.strip_prefix("super.") {
+                        Some(name) => {
+                            calls_super = true;
+                            name
+                        }
+                        None => callee,
+                    };
+                    if name
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+                    {
+                        calls.insert(name.to_string());
+                    }
+                });
+            }
+            summaries.push(CallableSummary {
+                contract: contract.to_string(),
+                name: name.to_string(),
+                file: source.unit.path.clone(),
+                line: line_of(node),
+                calls,
+                calls_super,
+                facts: collect(contract, node, text),
+            });
+        });
+    }
+    summaries
+}
+
+/// The modifiers `callable` applies, by name, with their invocations.
+pub(crate) fn modifier_invocations<'t>(
+    callable: TsNode<'t>,
+    source: &str,
+) -> Vec<(String, TsNode<'t>)> {
+    let mut cursor = callable.walk();
+    callable
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "modifier_invocation")
+        .filter_map(|child| {
+            let mut inner = child.walk();
+            let name = child
+                .named_children(&mut inner)
+                .find(|c| c.kind() == "identifier")
+                .map(|c| get_node_text(&c, source).to_string());
+            name.map(|name| (name, child))
+        })
+        .collect()
+}
+
+/// Strips `expression` wrappers and call options (`{value: ...}`).
+pub(crate) fn unwrap_expression(mut node: TsNode) -> TsNode {
+    loop {
+        let inner = match node.kind() {
+            "expression" => node.named_child(0),
+            "struct_expression" => node.child_by_field_name("type"),
+            _ => None,
+        };
+        match inner {
+            Some(inner) => node = inner,
+            None => return node,
+        }
+    }
+}
+
+/// Calls `f` for `node` and every node below it, in source order.
+pub(crate) fn visit<'t>(node: TsNode<'t>, f: &mut impl FnMut(TsNode<'t>)) {
+    f(node);
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        visit(child, f);
+    }
+}
+
+/// `Contract.name` for a call graph node, or its bare name outside a
+/// contract.
+pub(crate) fn qualified_name(node: &Node) -> String {
+    match &node.contract_name {
+        Some(contract) => format!("{}.{}", contract, node.name),
+        None => node.name.clone(),
+    }
+}
+
+/// Types of the parameters and locals declared in `callable`, by name.
+pub(crate) fn local_types(callable: TsNode, source: &str) -> HashMap<String, String> {
+    let mut types = HashMap::new();
+    visit(callable, &mut |n| {
+        if !matches!(n.kind(), "parameter" | "variable_declaration") {
+            return;
+        }
+        if let (Some(name), Some(type_name)) =
+            (n.child_by_field_name("name"), n.child_by_field_name("type"))
+        {
+            types.insert(
+                get_node_text(&name, source).to_string(),
+                get_node_text(&type_name, source).to_string(),
+            );
+        }
+    });
+    types
+}
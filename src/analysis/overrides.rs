@@ -0,0 +1,275 @@
+//! Completeness of `virtual` / `override` across inheritance chains.
+//!
+//! Flags virtual functions nothing overrides, overriding functions whose
+//! `override` specifier is missing or does not name every base that defines
+//! them, and functions deployable contracts inherit without an
+//! implementation.
+
+use super::model::{ContractInfo, ContractKind, FunctionInfo, SourceModel};
+use super::{Confidence, Finding, Severity};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+pub const RULE: &str = "override-completeness";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    NeverOverridden,
+    MissingOverride,
+    Unimplemented,
+}
+
+impl IssueKind {
+    fn title(&self) -> &'static str {
+        match self {
+            IssueKind::NeverOverridden => "Virtual Functions Never Overridden",
+            IssueKind::MissingOverride => "Incomplete `override` Specifiers",
+            IssueKind::Unimplemented => "Unimplemented Functions",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverrideIssue {
+    pub kind: IssueKind,
+    pub contract: String,
+    pub function: String,
+    pub message: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+pub fn find(model: &SourceModel) -> Vec<OverrideIssue> {
+    let mut issues = Vec::new();
+    for contract in model
+        .contracts
+        .iter()
+        .filter(|c| c.kind != ContractKind::Library)
+    {
+        for function in contract.functions.iter().filter(|f| !f.name.is_empty()) {
+            issues.extend(missing_override(model, contract, function));
+            if contract.kind == ContractKind::Contract
+                && function.is_virtual
+                && function.has_body
+                && !is_overridden(model, contract, function)
+            {
+                issues.push(OverrideIssue {
+                    kind: IssueKind::NeverOverridden,
+                    contract: contract.name.clone(),
+                    function: function.name.clone(),
+                    message: format!(
+                        "`{}.{}` is virtual but never overridden",
+                        contract.name,
+                        model.signature(function)
+                    ),
+                    file: contract.file.clone(),
+                    line: function.line,
+                });
+            }
+        }
+        if contract.kind == ContractKind::Contract && !contract.is_abstract {
+            issues.extend(unimplemented(model, contract));
+        }
+    }
+    issues
+}
+
+pub fn check(model: &SourceModel) -> Vec<Finding> {
+    find(model)
+        .into_iter()
+        .map(|issue| {
+            // Virtual hooks are often left for code outside the workspace to
+            // override; the other two do not compile.
+            let (severity, confidence) = match issue.kind {
+                IssueKind::NeverOverridden => (Severity::Info, Confidence::Low),
+                IssueKind::MissingOverride | IssueKind::Unimplemented => {
+                    (Severity::Medium, Confidence::High)
+                }
+            };
+            Finding {
+                rule: RULE,
+                severity,
+                confidence,
+                contract: Some(issue.contract),
+                function: Some(issue.function),
+                message: issue.message,
+                file: Some(issue.file),
+                line: Some(issue.line),
+            }
+        })
+        .collect()
+}
+
+fn missing_override(
+    model: &SourceModel,
+    contract: &ContractInfo,
+    function: &FunctionInfo,
+) -> Option<OverrideIssue> {
+    let signature = model.signature(function);
+    let defining: Vec<&ContractInfo> = model
+        .ancestors(&contract.name)
+        .into_iter()
+        .filter(|base| {
+            base.functions
+                .iter()
+                .any(|f| model.signature(f) == signature)
+        })
+        .collect();
+    // Bases whose definition no other defining base has already overridden.
+    let required: Vec<&str> = defining
+        .iter()
+        .filter(|base| {
+            !defining.iter().any(|other| {
+                model
+                    .ancestors(&other.name)
+                    .iter()
+                    .any(|c| c.name == base.name)
+            })
+        })
+        .map(|base| base.name.as_str())
+        .collect();
+
+    if required.is_empty() {
+        return None;
+    }
+    let message = if !function.is_override {
+        // Since 0.8.8 implementing a single interface function needs no
+        // specifier.
+        if required.len() == 1 && defining.iter().all(|c| c.kind == ContractKind::Interface) {
+            return None;
+        }
+        format!(
+            "`{}.{}` overrides {} but is not marked `override`",
+            contract.name,
+            signature,
+            quoted(&required)
+        )
+    } else {
+        // A bare `override` is enough for a single base.
+        if required.len() == 1 && function.overrides.is_empty() {
+            return None;
+        }
+        let missing: Vec<&str> = required
+            .iter()
+            .copied()
+            .filter(|base| !function.overrides.iter().any(|o| o == base))
+            .collect();
+        if missing.is_empty() {
+            return None;
+        }
+        format!(
+            "`{}.{}` must list {} in `override(...)`",
+            contract.name,
+            signature,
+            quoted(&missing)
+        )
+    };
+
+    Some(OverrideIssue {
+        kind: IssueKind::MissingOverride,
+        contract: contract.name.clone(),
+        function: function.name.clone(),
+        message,
+        file: contract.file.clone(),
+        line: function.line,
+    })
+}
+
+fn is_overridden(model: &SourceModel, contract: &ContractInfo, function: &FunctionInfo) -> bool {
+    let signature = model.signature(function);
+    model.contracts.iter().any(|derived| {
+        derived
+            .functions
+            .iter()
+            .any(|f| model.signature(f) == signature)
+            && model
+                .ancestors(&derived.name)
+                .iter()
+                .any(|c| c.name == contract.name)
+    })
+}
+
+/// Functions `contract` declares or inherits that nothing in its chain
+/// implements.
+fn unimplemented(model: &SourceModel, contract: &ContractInfo) -> Vec<OverrideIssue> {
+    let chain: Vec<&ContractInfo> = std::iter::once(contract)
+        .chain(model.ancestors(&contract.name))
+        .collect();
+    let mut implemented: HashSet<String> = chain
+        .iter()
+        .flat_map(|owner| owner.functions.iter())
+        .filter(|f| f.has_body)
+        .map(|f| model.signature(f))
+        .collect();
+    implemented.extend(
+        chain
+            .iter()
+            .flat_map(|owner| owner.state_vars.iter())
+            .filter(|v| v.visibility == "public")
+            .map(|v| model.getter_signature(v)),
+    );
+
+    let mut issues = Vec::new();
+    for owner in &chain {
+        for function in owner.functions.iter().filter(|f| !f.has_body) {
+            let signature = model.signature(function);
+            if implemented.insert(signature.clone()) {
+                issues.push(OverrideIssue {
+                    kind: IssueKind::Unimplemented,
+                    contract: contract.name.clone(),
+                    function: function.name.clone(),
+                    message: format!(
+                        "`{}` does not implement `{}.{}`",
+                        contract.name, owner.name, signature
+                    ),
+                    file: contract.file.clone(),
+                    line: contract.line,
+                });
+            }
+        }
+    }
+    issues
+}
+
+fn quoted(names: &[&str]) -> String {
+    names
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn render_override_report(issues: &[OverrideIssue]) -> String {
+    let mut md = String::from("# Override Audit\n\n");
+    if issues.is_empty() {
+        md.push_str("No override issues found.\n");
+        return md;
+    }
+    for kind in [
+        IssueKind::MissingOverride,
+        IssueKind::Unimplemented,
+        IssueKind::NeverOverridden,
+    ] {
+        let rows: Vec<&OverrideIssue> = issues.iter().filter(|i| i.kind == kind).collect();
+        if rows.is_empty() {
+            continue;
+        }
+        md.push_str(&format!("## {} ({})\n\n", kind.title(), rows.len()));
+        md.push_str("| Contract | Function | Issue | Location |\n");
+        md.push_str("|----------|----------|-------|----------|\n");
+        for issue in rows {
+            md.push_str(&format!(
+                "| {} | {} | {} | {}:{} |\n",
+                issue.contract,
+                issue.function,
+                issue.message.replace('|', "\\|"),
+                issue.file.display(),
+                issue.line
+            ));
+        }
+        md.push('\n');
+    }
+    md
+}
@@ -0,0 +1,306 @@
+//! Which state-changing entry points a pause stops.
+//!
+//! A contract is pausable when it inherits a `Pausable` base or any of its
+//! functions checks a pause flag: a `whenNotPaused`-style modifier,
+//! `_requireNotPaused()`, or `!paused` in a condition. An entry point is
+//! covered when it checks the flag itself or calls a function of its
+//! contract that does, so a pause stops it. Uncovered entry points keep
+//! working while the contract is paused, which is only intended for admin
+//! functions.
+
+use super::model::{
+    modifier_invocations, summarize_callables, unwrap_expression, visit, CallableSummary,
+    ContractKind, FunctionInfo, ParsedSource, SourceModel,
+};
+use super::privilege_paths::DEFAULT_PRIVILEGED_MODIFIERS;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+/// Modifiers that revert while the contract is paused.
+const PAUSE_MODIFIERS: [&str; 5] = [
+    "whenNotPaused",
+    "notPaused",
+    "whenNotHalted",
+    "notHalted",
+    "whenNotFrozen",
+];
+
+/// Modifiers that revert unless the contract is paused.
+const PAUSED_ONLY_MODIFIERS: [&str; 2] = ["whenPaused", "whenHalted"];
+
+/// Pause flags negated in `require(!paused)` and similar checks.
+const PAUSE_FLAGS: [&str; 5] = ["paused", "paused()", "_paused", "halted", "frozen"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryPoint {
+    /// `Contract.function`, naming the contract that declares it.
+    pub function: String,
+    pub line: usize,
+    /// The pause check, or the function called that makes it; `None` when
+    /// a pause does not stop the function.
+    pub covered_by: Option<String>,
+    /// Access-control modifiers; empty when anyone can call the function.
+    pub restricted_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PauseCoverage {
+    pub contract: String,
+    pub file: PathBuf,
+    pub line: usize,
+    /// Functions that pause or unpause the contract.
+    pub pause_functions: Vec<String>,
+    /// Functions only callable while paused, e.g. emergency withdrawals.
+    pub paused_only: Vec<String>,
+    /// Public and external state-changing functions, uncovered first.
+    pub entry_points: Vec<EntryPoint>,
+}
+
+impl PauseCoverage {
+    pub fn uncovered(&self) -> impl Iterator<Item = &EntryPoint> {
+        self.entry_points.iter().filter(|e| e.covered_by.is_none())
+    }
+}
+
+/// What a function's declaration and body say about pausing.
+#[derive(Debug, Default)]
+struct Facts {
+    /// The pause check the function makes itself.
+    check: Option<String>,
+    paused_only: bool,
+    /// Pauses or unpauses the contract.
+    toggles: bool,
+}
+
+type Summary = CallableSummary<Facts>;
+
+pub fn analyze(parsed: &[ParsedSource], model: &SourceModel) -> Vec<PauseCoverage> {
+    let facts: HashMap<(String, String, usize), Summary> =
+        summarize_callables(parsed, |_, node, text| collect_facts(node, text))
+            .into_iter()
+            .map(|summary| (summary.key(), summary))
+            .collect();
+
+    let mut contracts = Vec::new();
+    for contract in model
+        .contracts
+        .iter()
+        .filter(|c| c.kind == ContractKind::Contract && !c.is_abstract)
+    {
+        let mut lineage = model.ancestors(&contract.name);
+        lineage.push(contract);
+        lineage.reverse();
+        let inherits_pausable = lineage
+            .iter()
+            .flat_map(|c| c.bases.iter())
+            .any(|base| base.starts_with("Pausable"));
+
+        // Every body in the lineage, most derived first; `super` calls and
+        // guarded bases still count when an override hides them.
+        let mut by_name: HashMap<&str, Vec<&Summary>> = HashMap::new();
+        let mut functions: Vec<(&str, &FunctionInfo, &Summary)> = Vec::new();
+        let mut seen = HashSet::new();
+        for declaring in &lineage {
+            for function in declaring.functions.iter().filter(|f| f.has_body) {
+                let key = (declaring.name.clone(), function.name.clone(), function.line);
+                let Some(f) = facts.get(&key) else {
+                    continue;
+                };
+                by_name.entry(function.name.as_str()).or_default().push(f);
+                let signature: Vec<&str> = function
+                    .params
+                    .iter()
+                    .map(|p| p.type_name.as_str())
+                    .collect();
+                if seen.insert((function.name.as_str(), signature)) {
+                    functions.push((declaring.name.as_str(), function, f));
+                }
+            }
+        }
+        let pausable = inherits_pausable
+            || functions
+                .iter()
+                .any(|(_, _, f)| f.facts.check.is_some() || f.facts.paused_only);
+        if !pausable {
+            continue;
+        }
+
+        let mut coverage = PauseCoverage {
+            contract: contract.name.clone(),
+            file: contract.file.clone(),
+            line: contract.line,
+            pause_functions: Vec::new(),
+            paused_only: Vec::new(),
+            entry_points: Vec::new(),
+        };
+        for (declaring, function, f) in &functions {
+            if !function.is_entry_point() || !function.is_state_mutating() {
+                continue;
+            }
+            let qualified = format!("{}.{}", declaring, function.name);
+            if f.facts.toggles {
+                coverage.pause_functions.push(qualified);
+            } else if f.facts.paused_only {
+                coverage.paused_only.push(qualified);
+            } else {
+                coverage.entry_points.push(EntryPoint {
+                    function: qualified,
+                    line: function.line,
+                    covered_by: covered_by(f, &by_name, &mut HashSet::new()),
+                    restricted_by: function
+                        .modifiers
+                        .iter()
+                        .filter(|m| DEFAULT_PRIVILEGED_MODIFIERS.contains(&m.as_str()))
+                        .cloned()
+                        .collect(),
+                });
+            }
+        }
+        coverage
+            .entry_points
+            .sort_by_key(|e| (e.covered_by.is_some(), e.restricted_by.len().min(1)));
+        contracts.push(coverage);
+    }
+    contracts
+}
+
+/// The check stopping `summary`'s function while paused: its own, or the
+/// call to a function that makes one, directly or further down. `visited`
+/// holds the names already followed.
+fn covered_by<'a>(
+    summary: &'a Summary,
+    by_name: &HashMap<&str, Vec<&'a Summary>>,
+    visited: &mut HashSet<&'a str>,
+) -> Option<String> {
+    if let Some(check) = &summary.facts.check {
+        return Some(check.clone());
+    }
+    for callee in &summary.calls {
+        if !visited.insert(callee.as_str()) {
+            continue;
+        }
+        for &f in by_name.get(callee.as_str()).into_iter().flatten() {
+            if covered_by(f, by_name, visited).is_some() {
+                return Some(format!("{}()", callee));
+            }
+        }
+    }
+    None
+}
+
+fn collect_facts(node: TsNode, source: &str) -> Facts {
+    let mut facts = Facts::default();
+    for (name, _) in modifier_invocations(node, source) {
+        facts.paused_only |= PAUSED_ONLY_MODIFIERS.contains(&name.as_str());
+        if PAUSE_MODIFIERS.contains(&name.as_str()) {
+            facts.check.get_or_insert(name);
+        }
+    }
+
+    let Some(body) = node.child_by_field_name("body") else {
+        return facts;
+    };
+    visit(body, &mut |n| match n.kind() {
+        "call_expression" => {
+            let Some(callee) = n.child_by_field_name("function").map(unwrap_expression) else {
+                return;
+            };
+            match get_node_text(&callee, source) {
+                "_requireNotPaused" => {
+                    facts
+                        .check
+                        .get_or_insert_with(|| "_requireNotPaused()".to_string());
+                }
+                "_requirePaused" => facts.paused_only = true,
+                "_pause" | "_unpause" => facts.toggles = true,
+                _ => {}
+            }
+        }
+        "unary_expression" => {
+            let operator = n.child(0);
+            let argument = n.child_by_field_name("argument").map(unwrap_expression);
+            if let (Some(operator), Some(argument)) = (operator, argument) {
+                let flag = get_node_text(&argument, source);
+                if get_node_text(&operator, source) == "!" && PAUSE_FLAGS.contains(&flag) {
+                    facts.check.get_or_insert_with(|| format!("!{}", flag));
+                }
+            }
+        }
+        "assignment_expression" => {
+            let left = n.child_by_field_name("left").map(unwrap_expression);
+            if left.is_some_and(|l| PAUSE_FLAGS.contains(&get_node_text(&l, source))) {
+                facts.toggles = true;
+            }
+        }
+        _ => {}
+    });
+    facts
+}
+
+pub fn render_pause_coverage(contracts: &[PauseCoverage]) -> String {
+    let mut md = String::from("# Pause Coverage\n\n");
+    let uncovered: usize = contracts.iter().map(|c| c.uncovered().count()).sum();
+    md.push_str(&format!(
+        "**Pausable contracts:** {} | **Entry points a pause does not stop:** {}\n",
+        contracts.len(),
+        uncovered
+    ));
+    if contracts.is_empty() {
+        md.push_str("\nNo contract has a pause mechanism.\n");
+        return md;
+    }
+    let names = |functions: &[String]| {
+        if functions.is_empty() {
+            "none".to_string()
+        } else {
+            functions
+                .iter()
+                .map(|f| format!("`{}`", f))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    for coverage in contracts {
+        let covered = coverage.entry_points.len() - coverage.uncovered().count();
+        md.push_str(&format!(
+            "\n## {} ({} of {} entry points covered)\n\n`{}:{}`\n\n",
+            coverage.contract,
+            covered,
+            coverage.entry_points.len(),
+            coverage.file.display(),
+            coverage.line
+        ));
+        md.push_str(&format!(
+            "- **Pause and unpause:** {}\n",
+            names(&coverage.pause_functions)
+        ));
+        md.push_str(&format!(
+            "- **Only while paused:** {}\n",
+            names(&coverage.paused_only)
+        ));
+        if coverage.entry_points.is_empty() {
+            continue;
+        }
+        md.push_str("\n| Function | Line | Covered by | Restricted by |\n");
+        md.push_str("|----------|------|------------|---------------|\n");
+        for entry in &coverage.entry_points {
+            let covered_by = match &entry.covered_by {
+                Some(check) => format!("`{}`", check),
+                None => "**not covered**".to_string(),
+            };
+            let restricted_by = if entry.restricted_by.is_empty() {
+                "anyone".to_string()
+            } else {
+                entry.restricted_by.join(", ")
+            };
+            md.push_str(&format!(
+                "| `{}` | {} | {} | {} |\n",
+                entry.function, entry.line, covered_by, restricted_by
+            ));
+        }
+    }
+    md
+}
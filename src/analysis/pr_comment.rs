@@ -0,0 +1,307 @@
+//! What a pull request changes relative to its base revision, rendered as a
+//! compact Markdown comment for CI bots.
+//!
+//! Reports entry points that were added, removed or edited, cross-contract
+//! calls that did not exist before, and storage slots that moved or changed
+//! type in deployable contracts.
+
+use super::model::{for_each_callable, ContractInfo, ContractKind, ParsedSource, SourceModel};
+use super::storage_compare::{compare_layouts, SlotComparison, SlotStatus};
+use super::storage_layout::{layout, SlotEntry};
+use super::unresolved_calls::PLACEHOLDER_CONTRACT;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use traverse_graph::cg::{CallGraph, EdgeType, NodeType};
+use traverse_graph::parser::get_node_text;
+
+/// Rows shown per section before the rest is summarized as a count.
+const MAX_ROWS: usize = 20;
+
+/// One revision of the workspace, parsed and analyzed.
+pub struct Snapshot<'a> {
+    pub parsed: &'a [ParsedSource<'a>],
+    pub model: &'a SourceModel,
+    pub graph: &'a CallGraph,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl Change {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Modified => "modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryPointChange {
+    pub contract: String,
+    pub signature: String,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ExternalCall {
+    /// `Contract.function` making the call.
+    pub caller: String,
+    /// `Contract.function` called, or the placeholder for an unresolved call.
+    pub callee: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageDelta {
+    pub contract: String,
+    /// Differing slots; `left` is the base revision, `right` the head.
+    pub slots: Vec<SlotComparison>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrChanges {
+    pub entry_points: Vec<EntryPointChange>,
+    pub new_external_calls: Vec<ExternalCall>,
+    pub storage: Vec<StorageDelta>,
+}
+
+impl PrChanges {
+    pub fn is_empty(&self) -> bool {
+        self.entry_points.is_empty()
+            && self.new_external_calls.is_empty()
+            && self.storage.is_empty()
+    }
+}
+
+pub fn compare(base: &Snapshot, head: &Snapshot) -> PrChanges {
+    PrChanges {
+        entry_points: entry_point_changes(base, head),
+        new_external_calls: external_calls(head)
+            .difference(&external_calls(base))
+            .cloned()
+            .collect(),
+        storage: storage_deltas(base.model, head.model),
+    }
+}
+
+/// Public and external functions of deployable contracts, keyed by contract
+/// and signature, with where they are declared.
+fn entry_points(model: &SourceModel) -> BTreeMap<(String, String), (String, String)> {
+    let mut entry_points = BTreeMap::new();
+    for contract in deployable(model) {
+        for (owner, function) in model.external_functions(contract) {
+            entry_points.insert(
+                (contract.name.clone(), model.signature(function)),
+                (owner.name.clone(), function.name.clone()),
+            );
+        }
+    }
+    entry_points
+}
+
+fn entry_point_changes(base: &Snapshot, head: &Snapshot) -> Vec<EntryPointChange> {
+    let base_entry_points = entry_points(base.model);
+    let head_entry_points = entry_points(head.model);
+    let base_bodies = bodies(base.parsed);
+    let head_bodies = bodies(head.parsed);
+
+    let mut changes = Vec::new();
+    for (key, declared) in &head_entry_points {
+        let change = match base_entry_points.get(key) {
+            None => Change::Added,
+            Some(before)
+                if before != declared || base_bodies.get(before) != head_bodies.get(declared) =>
+            {
+                Change::Modified
+            }
+            Some(_) => continue,
+        };
+        changes.push(EntryPointChange {
+            contract: key.0.clone(),
+            signature: key.1.clone(),
+            change,
+        });
+    }
+    for key in base_entry_points.keys() {
+        if !head_entry_points.contains_key(key) {
+            changes.push(EntryPointChange {
+                contract: key.0.clone(),
+                signature: key.1.clone(),
+                change: Change::Removed,
+            });
+        }
+    }
+    changes.sort_by(|a, b| (&a.contract, &a.signature).cmp(&(&b.contract, &b.signature)));
+    changes
+}
+
+/// Whitespace-normalized source of every callable, keyed by contract and
+/// name; overloads share an entry.
+fn bodies(parsed: &[ParsedSource]) -> BTreeMap<(String, String), Vec<String>> {
+    let mut bodies: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, name, node| {
+            let body = get_node_text(&node, text)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            bodies
+                .entry((contract.to_string(), name.to_string()))
+                .or_default()
+                .push(body);
+        });
+    }
+    for texts in bodies.values_mut() {
+        texts.sort();
+    }
+    bodies
+}
+
+/// Calls from one contract into another, excluding calls into its own
+/// bases, which stay internal.
+fn external_calls(snapshot: &Snapshot) -> BTreeSet<ExternalCall> {
+    let graph = snapshot.graph;
+    let model = snapshot.model;
+    let mut calls = BTreeSet::new();
+    for edge in &graph.edges {
+        if edge.edge_type != EdgeType::Call {
+            continue;
+        }
+        let (Some(source), Some(target)) = (
+            graph.nodes.get(edge.source_node_id),
+            graph.nodes.get(edge.target_node_id),
+        ) else {
+            continue;
+        };
+        let (Some(from), Some(to)) = (&source.contract_name, &target.contract_name) else {
+            continue;
+        };
+        if from == to
+            || !matches!(target.node_type, NodeType::Function | NodeType::Constructor)
+            || model.ancestors(from).iter().any(|base| &base.name == to)
+            || model
+                .contract(to)
+                .is_some_and(|c| c.kind == ContractKind::Library)
+        {
+            continue;
+        }
+        let callee = if to == PLACEHOLDER_CONTRACT {
+            target.name.clone()
+        } else {
+            format!("{}.{}", to, target.name)
+        };
+        calls.insert(ExternalCall {
+            caller: format!("{}.{}", from, source.name),
+            callee,
+        });
+    }
+    calls
+}
+
+fn storage_deltas(base: &SourceModel, head: &SourceModel) -> Vec<StorageDelta> {
+    let mut deltas = Vec::new();
+    for contract in deployable(head) {
+        let Some(before) = base.contract(&contract.name) else {
+            continue;
+        };
+        let before: Vec<SlotEntry> = layout(base, before);
+        let slots: Vec<SlotComparison> = compare_layouts(before, layout(head, contract))
+            .into_iter()
+            .filter(|row| row.status != SlotStatus::Same)
+            .collect();
+        if !slots.is_empty() {
+            deltas.push(StorageDelta {
+                contract: contract.name.clone(),
+                slots,
+            });
+        }
+    }
+    deltas.sort_by(|a, b| a.contract.cmp(&b.contract));
+    deltas
+}
+
+fn deployable(model: &SourceModel) -> impl Iterator<Item = &ContractInfo> {
+    model
+        .contracts
+        .iter()
+        .filter(|c| c.kind == ContractKind::Contract && !c.is_abstract)
+}
+
+pub fn render_pr_comment(base_ref: &str, changes: &PrChanges) -> String {
+    let mut md = format!("## Traverse: changes since `{}`\n\n", base_ref);
+    if changes.is_empty() {
+        md.push_str("No changes to entry points, external calls or storage layout.\n");
+        return md;
+    }
+
+    if !changes.entry_points.is_empty() {
+        md.push_str("### Entry Points\n\n");
+        md.push_str("| Contract | Function | Change |\n");
+        md.push_str("|----------|----------|--------|\n");
+        for change in changes.entry_points.iter().take(MAX_ROWS) {
+            md.push_str(&format!(
+                "| `{}` | `{}` | {} |\n",
+                change.contract,
+                change.signature,
+                change.change.as_str()
+            ));
+        }
+        push_overflow(&mut md, changes.entry_points.len());
+    }
+
+    if !changes.new_external_calls.is_empty() {
+        md.push_str("\n### New External Calls\n\n");
+        for call in changes.new_external_calls.iter().take(MAX_ROWS) {
+            md.push_str(&format!("- `{}` → `{}`\n", call.caller, call.callee));
+        }
+        push_overflow(&mut md, changes.new_external_calls.len());
+    }
+
+    if !changes.storage.is_empty() {
+        md.push_str("\n### Storage Layout\n\n");
+        md.push_str("| Contract | Slot | Offset | Base | Head | Change |\n");
+        md.push_str("|----------|------|--------|------|------|--------|\n");
+        let rows: Vec<(&str, &SlotComparison)> = changes
+            .storage
+            .iter()
+            .flat_map(|delta| delta.slots.iter().map(|row| (delta.contract.as_str(), row)))
+            .collect();
+        let cell = |entry: &Option<SlotEntry>| {
+            entry.as_ref().map_or_else(
+                || "-".to_string(),
+                |e| format!("`{}: {}`", e.name, e.type_name),
+            )
+        };
+        for (contract, row) in rows.iter().take(MAX_ROWS) {
+            let change = match row.status {
+                SlotStatus::OnlyLeft => "removed",
+                SlotStatus::OnlyRight => "added",
+                status => status.as_str(),
+            };
+            md.push_str(&format!(
+                "| `{}` | {} | {} | {} | {} | {} |\n",
+                contract,
+                row.slot,
+                row.offset,
+                cell(&row.left),
+                cell(&row.right),
+                change
+            ));
+        }
+        push_overflow(&mut md, rows.len());
+    }
+    md
+}
+
+fn push_overflow(md: &mut String, total: usize) {
+    if total > MAX_ROWS {
+        md.push_str(&format!("\n_…and {} more_\n", total - MAX_ROWS));
+    }
+}
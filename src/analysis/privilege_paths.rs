@@ -0,0 +1,276 @@
+//! Call paths from unprivileged entry points into privileged functions.
+//!
+//! A function is privileged when it carries an access-control modifier or is
+//! named explicitly. Starting from every public or external function that is
+//! neither, calls are followed until they reach a privileged function. Two
+//! kinds of path are reported:
+//!
+//! - the target is named explicitly but has no access-control modifier, so
+//!   anyone can reach it through the entry point;
+//! - the target is guarded, but the call crosses into another contract, so
+//!   the guard checks the calling contract instead of the original caller.
+//!
+//! Calls into a guarded function of the same contract are not reported: its
+//! modifier still sees the original `msg.sender`.
+
+use super::model::{qualified_name, ContractKind, SourceModel};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use traverse_graph::cg::{CallGraph, EdgeType, Node, NodeType, Visibility};
+
+/// Modifiers treated as access control when a request names none.
+pub const DEFAULT_PRIVILEGED_MODIFIERS: [&str; 9] = [
+    "onlyOwner",
+    "onlyRole",
+    "onlyAdmin",
+    "onlyGovernance",
+    "onlyAuthorized",
+    "onlyMinter",
+    "onlyOperator",
+    "auth",
+    "requiresAuth",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathKind {
+    /// The target has no access-control modifier.
+    Unguarded,
+    /// The target's guard sees another contract as the caller.
+    CrossContract,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrivilegePath {
+    pub kind: PathKind,
+    /// `Contract.function` anyone can call.
+    pub entry_point: String,
+    /// The privileged function reached, as `Contract.function`.
+    pub target: String,
+    /// Access-control modifiers on the target.
+    pub target_guards: Vec<String>,
+    /// Every function on the shortest path, entry point and target included.
+    pub path: Vec<String>,
+    #[serde(skip)]
+    pub node_path: Vec<usize>,
+}
+
+/// Which functions count as privileged.
+#[derive(Debug, Clone, Default)]
+pub struct Privileges {
+    /// Access-control modifier names.
+    pub modifiers: Vec<String>,
+    /// Functions privileged by name, as `function` or `Contract.function`.
+    pub functions: Vec<String>,
+}
+
+impl Privileges {
+    fn named(&self, node: &Node) -> bool {
+        let qualified = qualified_name(node);
+        self.functions
+            .iter()
+            .any(|f| *f == node.name || *f == qualified)
+    }
+}
+
+pub fn find(graph: &CallGraph, model: &SourceModel, privileges: &Privileges) -> Vec<PrivilegePath> {
+    let guards: BTreeMap<usize, Vec<String>> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Function)
+        .map(|n| (n.id, guards(n, model, &privileges.modifiers)))
+        .collect();
+    let is_privileged = |node: &Node| !guards[&node.id].is_empty() || privileges.named(node);
+
+    let mut calls: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    for edge in &graph.edges {
+        if edge.edge_type == EdgeType::Call && guards.contains_key(&edge.target_node_id) {
+            calls
+                .entry(edge.source_node_id)
+                .or_default()
+                .insert(edge.target_node_id);
+        }
+    }
+
+    let mut paths = Vec::new();
+    for entry in graph.nodes.iter().filter(|n| {
+        n.node_type == NodeType::Function
+            && matches!(n.visibility, Visibility::Public | Visibility::External)
+            && !is_privileged(n)
+    }) {
+        // Breadth first, so each target is reported with its shortest path.
+        // Paths stop at the first privileged function.
+        let mut parent: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut seen = HashSet::from([entry.id]);
+        let mut queue = VecDeque::from([entry.id]);
+        while let Some(id) = queue.pop_front() {
+            for &next in calls.get(&id).into_iter().flatten() {
+                if !seen.insert(next) {
+                    continue;
+                }
+                parent.insert(next, id);
+                if is_privileged(&graph.nodes[next]) {
+                    let node_path = path_to(&parent, entry.id, next);
+                    if let Some(kind) = kind(graph, model, &guards[&next], &node_path) {
+                        paths.push(PrivilegePath {
+                            kind,
+                            entry_point: qualified_name(entry),
+                            target: qualified_name(&graph.nodes[next]),
+                            target_guards: guards[&next].clone(),
+                            path: node_path
+                                .iter()
+                                .map(|&id| qualified_name(&graph.nodes[id]))
+                                .collect(),
+                            node_path,
+                        });
+                    }
+                } else {
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    paths.sort_by(|a, b| {
+        (a.kind, &a.target, &a.entry_point).cmp(&(b.kind, &b.target, &b.entry_point))
+    });
+    paths
+}
+
+/// Flowchart of every path, with entry points and privileged targets
+/// highlighted.
+pub fn render_mermaid(graph: &CallGraph, paths: &[PrivilegePath]) -> String {
+    let mut nodes = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for path in paths {
+        nodes.extend(path.node_path.iter().copied());
+        edges.extend(path.node_path.windows(2).map(|pair| (pair[0], pair[1])));
+    }
+    let entries: BTreeSet<usize> = paths
+        .iter()
+        .filter_map(|p| p.node_path.first())
+        .copied()
+        .collect();
+    let targets: BTreeSet<usize> = paths
+        .iter()
+        .filter_map(|p| p.node_path.last())
+        .copied()
+        .collect();
+
+    let mut mermaid = String::from("flowchart LR\n");
+    for id in &nodes {
+        mermaid.push_str(&format!(
+            "    n{}[\"{}\"]\n",
+            id,
+            qualified_name(&graph.nodes[*id]).replace('"', "#quot;")
+        ));
+    }
+    for (source, target) in &edges {
+        mermaid.push_str(&format!("    n{} --> n{}\n", source, target));
+    }
+    mermaid.push_str("    classDef entry fill:#e5f5e0,stroke:#31a354\n");
+    mermaid.push_str("    classDef privileged fill:#fee0d2,stroke:#de2d26\n");
+    for (class, ids) in [("entry", &entries), ("privileged", &targets)] {
+        if !ids.is_empty() {
+            let ids: Vec<String> = ids.iter().map(|id| format!("n{}", id)).collect();
+            mermaid.push_str(&format!("    class {} {}\n", ids.join(","), class));
+        }
+    }
+    mermaid
+}
+
+pub fn render_privilege_paths(paths: &[PrivilegePath]) -> String {
+    let mut md = String::from("# Privilege Escalation Paths\n\n");
+    md.push_str(&format!("**Paths found:** {}\n\n", paths.len()));
+    if paths.is_empty() {
+        md.push_str("No unprivileged entry point reaches a privileged function.\n");
+        return md;
+    }
+    md.push_str("| Kind | Entry point | Target | Guards | Path |\n");
+    md.push_str("|------|-------------|--------|--------|------|\n");
+    for path in paths {
+        let kind = match path.kind {
+            PathKind::Unguarded => "unguarded",
+            PathKind::CrossContract => "cross-contract",
+        };
+        let guards = if path.target_guards.is_empty() {
+            "-".to_string()
+        } else {
+            path.target_guards.join(", ")
+        };
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            kind,
+            path.entry_point,
+            path.target,
+            guards,
+            path.path.join(" → ")
+        ));
+    }
+    md
+}
+
+/// The access-control modifiers on `node`, over all its overloads.
+fn guards(node: &Node, model: &SourceModel, modifiers: &[String]) -> Vec<String> {
+    let Some(contract) = node
+        .contract_name
+        .as_deref()
+        .and_then(|c| model.contract(c))
+    else {
+        return Vec::new();
+    };
+    let guards: BTreeSet<String> = contract
+        .functions
+        .iter()
+        .filter(|f| f.name == node.name)
+        .flat_map(|f| f.modifiers.iter())
+        .filter(|m| modifiers.contains(m))
+        .cloned()
+        .collect();
+    guards.into_iter().collect()
+}
+
+/// Why the path to a privileged target is worth a look, if it is.
+fn kind(
+    graph: &CallGraph,
+    model: &SourceModel,
+    target_guards: &[String],
+    node_path: &[usize],
+) -> Option<PathKind> {
+    if target_guards.is_empty() {
+        return Some(PathKind::Unguarded);
+    }
+    let [.., caller, target] = node_path else {
+        return None;
+    };
+    let caller = graph.nodes[*caller].contract_name.as_deref()?;
+    let target = graph.nodes[*target].contract_name.as_deref()?;
+    is_external_call(model, caller, target).then_some(PathKind::CrossContract)
+}
+
+/// Whether a call from `caller` into `target` goes to another contract
+/// rather than to inherited or library code.
+fn is_external_call(model: &SourceModel, caller: &str, target: &str) -> bool {
+    if caller == target {
+        return false;
+    }
+    if model
+        .contract(target)
+        .is_some_and(|c| c.kind == ContractKind::Library)
+    {
+        return false;
+    }
+    let related =
+        |derived: &str, base: &str| model.ancestors(derived).iter().any(|c| c.name == base);
+    !related(caller, target) && !related(target, caller)
+}
+
+fn path_to(parent: &BTreeMap<usize, usize>, from: usize, to: usize) -> Vec<usize> {
+    let mut path = vec![to];
+    let mut id = to;
+    while id != from {
+        id = parent[&id];
+        path.push(id);
+    }
+    path.reverse();
+    path
+}
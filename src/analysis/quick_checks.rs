@@ -0,0 +1,200 @@
+//! Single-file checks cheap enough to run while the user types.
+//!
+//! Audit rules look across the whole workspace; these only need one parsed
+//! file, so the server runs them on the open document after each edit.
+
+use super::model::{
+    for_each_callable, line_of, parse_sources, unwrap_expression, visit, ParsedSource, SourceModel,
+    SourceUnit,
+};
+use super::{apply_severity_overrides, Confidence, Finding, Severity};
+use crate::config::Config;
+use anyhow::Result;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+pub const PARSE_ERROR: &str = "parse-error";
+pub const SHADOWING: &str = "shadowing";
+pub const UNCHECKED_RETURN: &str = "unchecked-return";
+
+const LOW_LEVEL_CALLS: &[&str] = &["call", "delegatecall", "staticcall", "send"];
+/// ERC-20 functions that report failure through a `bool`, with their arity.
+const TOKEN_CALLS: &[(&str, usize)] = &[("transfer", 2), ("transferFrom", 3), ("approve", 2)];
+const MAX_SNIPPET_LEN: usize = 32;
+
+pub fn check(unit: &SourceUnit, config: &Config) -> Result<Vec<Finding>> {
+    let parsed = parse_sources(std::slice::from_ref(unit))?;
+    let mut findings = Vec::new();
+    if config.is_rule_enabled(PARSE_ERROR) {
+        parse_errors(&parsed[0], parsed[0].tree.root_node(), &mut findings);
+    }
+    if config.is_rule_enabled(SHADOWING) {
+        findings.extend(shadowing(&parsed));
+    }
+    if config.is_rule_enabled(UNCHECKED_RETURN) {
+        findings.extend(unchecked_returns(&parsed[0]));
+    }
+    apply_severity_overrides(&mut findings, config);
+    findings.sort_by_key(|f| f.line);
+    Ok(findings)
+}
+
+/// Reports the outermost error or missing node of each broken region.
+fn parse_errors(parsed: &ParsedSource, node: TsNode, findings: &mut Vec<Finding>) {
+    let message = if node.is_missing() {
+        format!("Syntax error: missing `{}`", node.kind())
+    } else if node.is_error() {
+        let text = get_node_text(&node, &parsed.unit.content);
+        let snippet: String = text
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .chars()
+            .take(MAX_SNIPPET_LEN)
+            .collect();
+        format!("Syntax error near `{}`", snippet)
+    } else {
+        if node.has_error() {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                parse_errors(parsed, child, findings);
+            }
+        }
+        return;
+    };
+    findings.push(Finding {
+        rule: PARSE_ERROR,
+        severity: Severity::High,
+        confidence: Confidence::High,
+        contract: None,
+        function: None,
+        message,
+        file: Some(parsed.unit.path.clone()),
+        line: Some(line_of(node)),
+    });
+}
+
+/// Parameters and locals named after a state variable visible in the
+/// enclosing contract.
+fn shadowing(parsed: &[ParsedSource]) -> Vec<Finding> {
+    let model = SourceModel::from_parsed(parsed);
+    let parsed = &parsed[0];
+    let source = parsed.unit.content.as_str();
+    let mut findings = Vec::new();
+
+    for_each_callable(
+        parsed.tree.root_node(),
+        source,
+        |contract, function, callable| {
+            let Some(info) = model.contract(contract) else {
+                return;
+            };
+            let inherited = model.ancestors(contract).into_iter().flat_map(|base| {
+                base.state_vars
+                    .iter()
+                    .filter(|v| v.visibility != "private")
+                    .map(move |v| (base.name.as_str(), v))
+            });
+            let visible: Vec<_> = info
+                .state_vars
+                .iter()
+                .map(|v| (info.name.as_str(), v))
+                .chain(inherited)
+                .collect();
+
+            visit(callable, &mut |node| {
+                if !matches!(node.kind(), "parameter" | "variable_declaration") {
+                    return;
+                }
+                let Some(name_node) = node.child_by_field_name("name") else {
+                    return;
+                };
+                let name = get_node_text(&name_node, source);
+                if let Some((owner, _)) = visible.iter().find(|(_, v)| v.name == name) {
+                    findings.push(Finding {
+                        rule: SHADOWING,
+                        severity: Severity::Low,
+                        confidence: Confidence::High,
+                        contract: Some(contract.to_string()),
+                        function: Some(function.to_string()),
+                        message: format!("`{}` shadows state variable `{}.{}`", name, owner, name),
+                        file: Some(parsed.unit.path.clone()),
+                        line: Some(line_of(name_node)),
+                    });
+                }
+            });
+        },
+    );
+    findings
+}
+
+/// Calls whose `bool` result is the only failure signal, used as a bare
+/// statement.
+fn unchecked_returns(parsed: &ParsedSource) -> Vec<Finding> {
+    let source = parsed.unit.content.as_str();
+    let mut findings = Vec::new();
+
+    for_each_callable(
+        parsed.tree.root_node(),
+        source,
+        |contract, function, callable| {
+            visit(callable, &mut |node| {
+                // `for` conditions are expression statements too.
+                if node.kind() != "expression_statement"
+                    || node.parent().map(|p| p.kind()) != Some("statement")
+                {
+                    return;
+                }
+                let Some(call) = node.named_child(0).map(unwrap_expression) else {
+                    return;
+                };
+                if call.kind() != "call_expression" {
+                    return;
+                }
+                let Some(callee) = call.child_by_field_name("function").map(unwrap_expression)
+                else {
+                    return;
+                };
+                let Some(member) = callee
+                    .child_by_field_name("property")
+                    .filter(|_| callee.kind() == "member_expression")
+                    .map(|p| get_node_text(&p, source))
+                else {
+                    return;
+                };
+                let arguments = call
+                    .named_children(&mut call.walk())
+                    .filter(|c| c.kind() == "call_argument")
+                    .count();
+
+                let (severity, confidence, message) = if LOW_LEVEL_CALLS.contains(&member) {
+                    (
+                        Severity::Medium,
+                        Confidence::High,
+                        format!("Return value of low-level `{}` is not checked", member),
+                    )
+                } else if TOKEN_CALLS.contains(&(member, arguments)) {
+                    (
+                        Severity::Low,
+                        Confidence::Medium,
+                        format!("Return value of `{}` is ignored", member),
+                    )
+                } else {
+                    return;
+                };
+                findings.push(Finding {
+                    rule: UNCHECKED_RETURN,
+                    severity,
+                    confidence,
+                    contract: Some(contract.to_string()),
+                    function: Some(function.to_string()),
+                    message,
+                    file: Some(parsed.unit.path.clone()),
+                    line: Some(line_of(call)),
+                });
+            });
+        },
+    );
+    findings
+}
@@ -0,0 +1,325 @@
+//! Read-only reentrancy across contracts.
+//!
+//! A function that makes an external call before updating a state variable
+//! hands control to the callee while that variable still holds its old
+//! value. A `view` function reading the variable then answers with stale
+//! data, and a contract that trusts the answer (a lending market pricing LP
+//! tokens, say) can be exploited from the callback even though the view
+//! itself changes nothing. Findings name the whole path: the consuming call,
+//! the view, the variable, and the late write with its external call.
+
+use super::model::{
+    for_each_callable, line_of, local_types, unwrap_expression, visit, ContractKind, ParsedSource,
+    SourceModel,
+};
+use super::{Confidence, Finding, Severity};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+pub const RULE: &str = "read-only-reentrancy";
+
+/// Member calls that hand control to another address whatever its type.
+/// `staticcall` is left out: the callee cannot write state.
+const LOW_LEVEL_CALLS: &[&str] = &["call", "delegatecall", "send", "transfer"];
+
+/// A state variable written after an external call in the same function.
+struct LateWrite {
+    contract: String,
+    function: String,
+    variable: String,
+    call_line: usize,
+}
+
+/// A view reading a variable that has a late write.
+struct StaleView<'a> {
+    contract: String,
+    function: String,
+    file: PathBuf,
+    line: usize,
+    write: &'a LateWrite,
+}
+
+pub fn check(parsed: &[ParsedSource], model: &SourceModel) -> Vec<Finding> {
+    let writes = late_writes(parsed, model);
+    let views = stale_views(parsed, model, &writes);
+
+    let mut findings = Vec::new();
+    let mut consumed = BTreeSet::new();
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, function, node| {
+            if !is_state_mutating(model, contract, function, node) {
+                return;
+            }
+            let types = local_types(node, text);
+            visit(node, &mut |call| {
+                let Some((object, property)) = member_call(call, text) else {
+                    return;
+                };
+                let Some(object_type) = object_type(object, text, &types, model, contract) else {
+                    return;
+                };
+                for (index, view) in views.iter().enumerate() {
+                    if view.function != property
+                        || !related(model, &view.contract, &object_type)
+                        || related(model, &view.contract, contract)
+                    {
+                        continue;
+                    }
+                    consumed.insert(index);
+                    findings.push(Finding {
+                        rule: RULE,
+                        severity: Severity::Medium,
+                        confidence: Confidence::Medium,
+                        contract: Some(contract.to_string()),
+                        function: Some(function.to_string()),
+                        message: format!(
+                            "`{}.{}` calls `{}.{}`, which reads `{}`. `{}.{}` writes `{}` after its external call on line {}, so a callback from that call reads a stale value",
+                            contract,
+                            function,
+                            view.contract,
+                            view.function,
+                            view.write.variable,
+                            view.write.contract,
+                            view.write.function,
+                            view.write.variable,
+                            view.write.call_line,
+                        ),
+                        file: Some(source.unit.path.clone()),
+                        line: Some(line_of(call)),
+                    });
+                }
+            });
+        });
+    }
+
+    // Views nothing in the workspace consumes may still be read by
+    // integrations deployed elsewhere.
+    for (index, view) in views.iter().enumerate() {
+        if consumed.contains(&index) {
+            continue;
+        }
+        findings.push(Finding {
+            rule: RULE,
+            severity: Severity::Low,
+            confidence: Confidence::Low,
+            contract: Some(view.contract.clone()),
+            function: Some(view.function.clone()),
+            message: format!(
+                "`{}.{}` reads `{}`, which `{}.{}` writes after its external call on line {}; contracts calling it from that call see a stale value",
+                view.contract,
+                view.function,
+                view.write.variable,
+                view.write.contract,
+                view.write.function,
+                view.write.call_line,
+            ),
+            file: Some(view.file.clone()),
+            line: Some(view.line),
+        });
+    }
+    findings
+}
+
+fn late_writes(parsed: &[ParsedSource], model: &SourceModel) -> Vec<LateWrite> {
+    let mut writes = Vec::new();
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, function, node| {
+            if node.kind() != "function_definition"
+                || !is_state_mutating(model, contract, function, node)
+            {
+                return;
+            }
+            let state = state_variables(model, contract);
+            let types = local_types(node, text);
+            let mut first_call: Option<TsNode> = None;
+            let mut written = BTreeSet::new();
+            visit(node, &mut |n| {
+                if first_call.is_none() {
+                    if let Some((object, property)) = member_call(n, text) {
+                        if LOW_LEVEL_CALLS.contains(&property)
+                            || object_type(object, text, &types, model, contract).is_some()
+                        {
+                            first_call = Some(n);
+                        }
+                    }
+                    return;
+                }
+                let Some(variable) = written_variable(n, text) else {
+                    return;
+                };
+                if state.contains(&variable)
+                    && !types.contains_key(&variable)
+                    && written.insert(variable.clone())
+                {
+                    writes.push(LateWrite {
+                        contract: contract.to_string(),
+                        function: function.to_string(),
+                        variable,
+                        call_line: first_call.map(line_of).unwrap_or_default(),
+                    });
+                }
+            });
+        });
+    }
+    writes
+}
+
+fn stale_views<'a>(
+    parsed: &[ParsedSource],
+    model: &SourceModel,
+    writes: &'a [LateWrite],
+) -> Vec<StaleView<'a>> {
+    let mut views = Vec::new();
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, function, node| {
+            if node.kind() != "function_definition" || !is_view(model, contract, function) {
+                return;
+            }
+            let types = local_types(node, text);
+            let mut read = BTreeSet::new();
+            visit(node, &mut |n| {
+                if n.kind() == "identifier" && !types.contains_key(get_node_text(&n, text)) {
+                    read.insert(get_node_text(&n, text).to_string());
+                }
+            });
+            // One finding per view, for the first variable with a late write.
+            if let Some(write) = writes
+                .iter()
+                .find(|w| read.contains(&w.variable) && related(model, &w.contract, contract))
+            {
+                views.push(StaleView {
+                    contract: contract.to_string(),
+                    function: function.to_string(),
+                    file: source.unit.path.clone(),
+                    line: line_of(node),
+                    write,
+                });
+            }
+        });
+    }
+    views
+}
+
+/// The object and member name of a call like `pool.getPrice()`.
+fn member_call<'t>(node: TsNode<'t>, source: &'t str) -> Option<(TsNode<'t>, &'t str)> {
+    if node.kind() != "call_expression" {
+        return None;
+    }
+    let callee = unwrap_expression(node.child_by_field_name("function")?);
+    if callee.kind() != "member_expression" {
+        return None;
+    }
+    let object = unwrap_expression(callee.child_by_field_name("object")?);
+    let property = callee.child_by_field_name("property")?;
+    Some((object, get_node_text(&property, source)))
+}
+
+/// The contract or interface `object` is declared as, if it is one of the
+/// workspace's: a local, parameter or state variable, or a cast like
+/// `IPool(pool)`.
+fn object_type(
+    object: TsNode,
+    source: &str,
+    locals: &HashMap<String, String>,
+    model: &SourceModel,
+    contract: &str,
+) -> Option<String> {
+    let type_name = match object.kind() {
+        "identifier" => {
+            let name = get_node_text(&object, source);
+            match locals.get(name) {
+                Some(type_name) => type_name.clone(),
+                None => std::iter::once(model.contract(contract)?)
+                    .chain(model.ancestors(contract))
+                    .flat_map(|c| c.state_vars.iter())
+                    .find(|v| v.name == name)?
+                    .type_name
+                    .clone(),
+            }
+        }
+        "call_expression" => {
+            let function = unwrap_expression(object.child_by_field_name("function")?);
+            get_node_text(&function, source).to_string()
+        }
+        _ => return None,
+    };
+    let simple = type_name.rsplit('.').next().unwrap_or(&type_name).trim();
+    model
+        .contract(simple)
+        .filter(|c| c.kind != ContractKind::Library)
+        .map(|c| c.name.clone())
+}
+
+/// The state variable an assignment, `++`, `--` or `delete` writes to.
+fn written_variable(node: TsNode, source: &str) -> Option<String> {
+    let target = match node.kind() {
+        "assignment_expression" | "augmented_assignment_expression" => {
+            node.child_by_field_name("left")?
+        }
+        "update_expression" => node.child_by_field_name("argument")?,
+        "unary_expression"
+            if node
+                .child_by_field_name("operator")
+                .is_some_and(|o| get_node_text(&o, source) == "delete") =>
+        {
+            node.child_by_field_name("argument")?
+        }
+        _ => return None,
+    };
+    root_identifier(target, source)
+}
+
+/// `balances` for `balances[user].amount`.
+fn root_identifier(node: TsNode, source: &str) -> Option<String> {
+    let node = unwrap_expression(node);
+    match node.kind() {
+        "identifier" => Some(get_node_text(&node, source).to_string()),
+        "member_expression" => root_identifier(node.child_by_field_name("object")?, source),
+        "array_access" => root_identifier(node.child_by_field_name("base")?, source),
+        _ => None,
+    }
+}
+
+/// Storage variables of `contract`, inherited ones included.
+fn state_variables(model: &SourceModel, contract: &str) -> BTreeSet<String> {
+    let Some(info) = model.contract(contract) else {
+        return BTreeSet::new();
+    };
+    std::iter::once(info)
+        .chain(model.ancestors(contract))
+        .flat_map(|c| c.state_vars.iter())
+        .filter(|v| v.occupies_storage())
+        .map(|v| v.name.clone())
+        .collect()
+}
+
+fn is_view(model: &SourceModel, contract: &str, function: &str) -> bool {
+    model.contract(contract).is_some_and(|c| {
+        c.functions
+            .iter()
+            .any(|f| f.name == function && f.mutability.as_deref() == Some("view"))
+    })
+}
+
+fn is_state_mutating(model: &SourceModel, contract: &str, function: &str, node: TsNode) -> bool {
+    if node.kind() != "function_definition" {
+        return node.kind() != "modifier_definition";
+    }
+    model.contract(contract).is_some_and(|c| {
+        c.functions
+            .iter()
+            .any(|f| f.name == function && f.is_state_mutating() && f.has_body)
+    })
+}
+
+/// Whether `a` and `b` are the same contract or one inherits from the other.
+fn related(model: &SourceModel, a: &str, b: &str) -> bool {
+    a == b
+        || model.ancestors(a).iter().any(|c| c.name == b)
+        || model.ancestors(b).iter().any(|c| c.name == a)
+}
@@ -0,0 +1,174 @@
+//! Function selector collisions between contracts behind a shared router.
+//!
+//! A proxy forwards every selector it does not implement itself, and a
+//! diamond routes each selector to exactly one facet. Two different
+//! signatures hashing to the same selector, or the same function living in
+//! two contracts behind one router, silently send calls to the wrong code.
+
+use super::model::{ContractInfo, ContractKind, SourceModel};
+use super::{Confidence, Finding, Severity};
+use sha3::{Digest, Keccak256};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+pub const RULE: &str = "selector-collision";
+
+struct Entry<'a> {
+    contract: &'a ContractInfo,
+    signature: String,
+    file: PathBuf,
+    line: usize,
+}
+
+pub fn check(model: &SourceModel) -> Vec<Finding> {
+    let deployable: Vec<&ContractInfo> = model
+        .contracts
+        .iter()
+        .filter(|c| c.kind == ContractKind::Contract && !c.is_abstract)
+        .collect();
+
+    let diamond: Vec<&ContractInfo> = deployable
+        .iter()
+        .copied()
+        .filter(|c| c.name.ends_with("Facet") || c.name.contains("Diamond"))
+        .collect();
+    let proxied: Vec<&ContractInfo> = deployable
+        .iter()
+        .copied()
+        .filter(|c| is_proxy(model, c) || model.is_upgradeable(c))
+        .collect();
+
+    let mut findings = Vec::new();
+    if diamond.len() > 1 {
+        findings.extend(check_group(model, "diamond", &diamond, |_| true));
+    }
+    if proxied.len() > 1 && proxied.iter().any(|c| is_proxy(model, c)) {
+        // Two implementations behind different proxies never share a router,
+        // so only overlaps involving the proxy itself matter.
+        findings.extend(check_group(model, "proxy", &proxied, |c| {
+            is_proxy(model, c)
+        }));
+    }
+    findings
+}
+
+fn is_proxy(model: &SourceModel, contract: &ContractInfo) -> bool {
+    contract.name.contains("Proxy")
+        || model
+            .ancestors(&contract.name)
+            .iter()
+            .any(|c| c.name.contains("Proxy"))
+}
+
+fn check_group(
+    model: &SourceModel,
+    router: &str,
+    contracts: &[&ContractInfo],
+    involves: impl Fn(&ContractInfo) -> bool,
+) -> Vec<Finding> {
+    let mut by_selector: BTreeMap<[u8; 4], Vec<Entry>> = BTreeMap::new();
+
+    for contract in contracts {
+        for (owner, function) in model.external_functions(contract) {
+            let signature = model.signature(function);
+            by_selector
+                .entry(selector(&signature))
+                .or_default()
+                .push(Entry {
+                    contract,
+                    signature,
+                    file: owner.file.clone(),
+                    line: function.line,
+                });
+        }
+        let getters = std::iter::once(*contract)
+            .chain(model.ancestors(&contract.name))
+            .flat_map(|owner| owner.state_vars.iter().map(move |v| (owner, v)))
+            .filter(|(_, v)| v.visibility == "public");
+        for (owner, var) in getters {
+            let signature = model.getter_signature(var);
+            by_selector
+                .entry(selector(&signature))
+                .or_default()
+                .push(Entry {
+                    contract,
+                    signature,
+                    file: owner.file.clone(),
+                    line: var.line,
+                });
+        }
+    }
+
+    let mut findings = Vec::new();
+    for (sel, entries) in by_selector {
+        let mut contracts: Vec<&str> = entries.iter().map(|e| e.contract.name.as_str()).collect();
+        contracts.sort_unstable();
+        contracts.dedup();
+        if contracts.len() < 2 || !entries.iter().any(|e| involves(e.contract)) {
+            continue;
+        }
+
+        let mut signatures: Vec<&str> = entries.iter().map(|e| e.signature.as_str()).collect();
+        signatures.sort_unstable();
+        signatures.dedup();
+
+        let listing = entries
+            .iter()
+            .map(|e| format!("`{}` in `{}`", e.signature, e.contract.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (severity, confidence, message) = if signatures.len() > 1 {
+            (
+                Severity::High,
+                Confidence::High,
+                format!(
+                    "Selector {} collides across {} contracts: {}",
+                    to_hex(&sel),
+                    router,
+                    listing
+                ),
+            )
+        } else {
+            // Routing groups are inferred from naming, so a duplicate may
+            // be intentional when the contracts are never deployed together.
+            (
+                Severity::Medium,
+                Confidence::Medium,
+                format!(
+                    "Selector {} is implemented by several {} contracts, only one can receive it: {}",
+                    to_hex(&sel),
+                    router,
+                    listing
+                ),
+            )
+        };
+
+        let first = &entries[0];
+        findings.push(Finding {
+            rule: RULE,
+            severity,
+            confidence,
+            contract: Some(first.contract.name.clone()),
+            function: None,
+            message,
+            file: Some(first.file.clone()),
+            line: Some(first.line),
+        });
+    }
+    findings
+}
+
+/// First four bytes of the Keccak-256 hash of a canonical signature.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = Keccak256::digest(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// `0x`-prefixed hex, as selectors are written in Solidity.
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::from("0x");
+    for byte in bytes {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+    hex
+}
@@ -0,0 +1,242 @@
+//! Reads of the contract's own ether balance.
+//!
+//! Ether can be forced into any contract (selfdestruct, coinbase rewards,
+//! pre-computed addresses), so logic that branches on `address(this).balance`
+//! or `selfbalance()` must not assume the balance only changes through the
+//! contract's own payable functions.
+
+use super::model::{for_each_callable, line_of, visit, ParsedSource};
+use super::{Confidence, Finding, Severity};
+use std::collections::HashSet;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+pub const RULE: &str = "self-balance";
+
+pub fn check(parsed: &[ParsedSource]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, function, node| {
+            let mut reads = Vec::new();
+            visit(node, &mut |n| {
+                if is_self_balance_read(n, text) {
+                    reads.push(n);
+                }
+            });
+            if reads.is_empty() {
+                return;
+            }
+
+            // Locals initialized from the balance carry the dependency into
+            // later conditions (`uint256 bal = address(this).balance; if (bal > 0)`).
+            let mut tainted: HashSet<String> = HashSet::new();
+            for read in &reads {
+                if let Some(local) = assigned_local(*read, text) {
+                    tainted.insert(local);
+                }
+            }
+
+            let mut reported = HashSet::new();
+            for read in &reads {
+                let (severity, message) = match branch_kind(*read, text) {
+                    Some(Branch::StrictEquality) => (
+                        Severity::Medium,
+                        "Strict equality on the contract's own balance; forced ether makes it unreliable",
+                    ),
+                    Some(Branch::Condition) => (
+                        Severity::Low,
+                        "Branches on the contract's own balance, which can be inflated by forced ether",
+                    ),
+                    None => (Severity::Info, "Reads the contract's own balance"),
+                };
+                reported.insert(read.start_byte());
+                findings.push(finding(
+                    source,
+                    contract,
+                    function,
+                    *read,
+                    severity,
+                    Confidence::High,
+                    message,
+                ));
+            }
+
+            if tainted.is_empty() {
+                return;
+            }
+            visit(node, &mut |n| {
+                if n.kind() != "identifier" || !tainted.contains(get_node_text(&n, text)) {
+                    return;
+                }
+                if reported.contains(&n.start_byte()) {
+                    return;
+                }
+                let (severity, message) = match branch_kind(n, text) {
+                    Some(Branch::StrictEquality) => (
+                        Severity::Medium,
+                        "Strict equality on a value derived from the contract's own balance",
+                    ),
+                    Some(Branch::Condition) => (
+                        Severity::Low,
+                        "Branches on a value derived from the contract's own balance",
+                    ),
+                    None => return,
+                };
+                reported.insert(n.start_byte());
+                // One level of local dataflow; the local may be reassigned.
+                findings.push(finding(
+                    source,
+                    contract,
+                    function,
+                    n,
+                    severity,
+                    Confidence::Medium,
+                    message,
+                ));
+            });
+        });
+    }
+
+    findings
+}
+
+fn finding(
+    source: &ParsedSource,
+    contract: &str,
+    function: &str,
+    node: TsNode,
+    severity: Severity,
+    confidence: Confidence,
+    message: &str,
+) -> Finding {
+    Finding {
+        rule: RULE,
+        severity,
+        confidence,
+        contract: Some(contract.to_string()),
+        function: Some(function.to_string()),
+        message: message.to_string(),
+        file: Some(source.unit.path.clone()),
+        line: Some(line_of(node)),
+    }
+}
+
+fn is_self_balance_read(node: TsNode, source: &str) -> bool {
+    match node.kind() {
+        "member_expression" => {
+            let property = node
+                .child_by_field_name("property")
+                .map(|p| get_node_text(&p, source));
+            let object: String = node
+                .child_by_field_name("object")
+                .map(|o| get_node_text(&o, source))
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+            property == Some("balance")
+                && matches!(
+                    object.as_str(),
+                    "this" | "address(this)" | "payable(address(this))"
+                )
+        }
+        "yul_function_call" => node
+            .child_by_field_name("function")
+            .is_some_and(|f| get_node_text(&f, source) == "selfbalance"),
+        _ => false,
+    }
+}
+
+fn assigned_local(read: TsNode, source: &str) -> Option<String> {
+    let mut current = read.parent();
+    while let Some(node) = current {
+        match node.kind() {
+            "variable_declaration_statement" => {
+                let mut cursor = node.walk();
+                let declaration = node
+                    .named_children(&mut cursor)
+                    .find(|c| c.kind() == "variable_declaration")?;
+                return declaration
+                    .child_by_field_name("name")
+                    .map(|n| get_node_text(&n, source).to_string());
+            }
+            "yul_variable_declaration" => {
+                return node
+                    .child_by_field_name("left")
+                    .map(|n| get_node_text(&n, source).trim().to_string());
+            }
+            "expression"
+            | "parenthesized_expression"
+            | "type_cast_expression"
+            | "call_argument" => current = node.parent(),
+            _ => return None,
+        }
+    }
+    None
+}
+
+enum Branch {
+    StrictEquality,
+    Condition,
+}
+
+/// Determines whether `node` feeds a control-flow decision within its function.
+fn branch_kind(node: TsNode, source: &str) -> Option<Branch> {
+    let mut strict = false;
+    let mut child = node;
+    let mut current = node.parent();
+
+    while let Some(parent) = current {
+        match parent.kind() {
+            "binary_expression" => {
+                let operator = parent
+                    .child_by_field_name("operator")
+                    .map(|o| get_node_text(&o, source));
+                strict |= matches!(operator, Some("==" | "!="));
+            }
+            "if_statement" | "while_statement" | "for_statement" | "do_while_statement" => {
+                let in_condition = parent
+                    .child_by_field_name("condition")
+                    .is_some_and(|c| c.id() == child.id());
+                return in_condition.then(|| branch(strict));
+            }
+            "ternary_expression" => {
+                let in_condition = parent.named_child(0).is_some_and(|c| c.id() == child.id());
+                return in_condition.then(|| branch(strict));
+            }
+            "call_expression" => {
+                let callee = parent
+                    .child_by_field_name("function")
+                    .map(|f| get_node_text(&f, source));
+                return matches!(callee, Some("require" | "assert")).then(|| branch(strict));
+            }
+            "yul_function_call" => {
+                let callee = parent
+                    .child_by_field_name("function")
+                    .map(|f| get_node_text(&f, source));
+                strict |= matches!(callee, Some("eq"));
+            }
+            "yul_if_statement" | "yul_switch_statement" => return Some(branch(strict)),
+            "statement"
+            | "function_body"
+            | "block_statement"
+            | "assembly_statement"
+            | "yul_block"
+            | "yul_variable_declaration"
+            | "yul_assignment" => return None,
+            _ => {}
+        }
+        child = parent;
+        current = parent.parent();
+    }
+    None
+}
+
+fn branch(strict: bool) -> Branch {
+    if strict {
+        Branch::StrictEquality
+    } else {
+        Branch::Condition
+    }
+}
@@ -0,0 +1,81 @@
+//! Storage access report: which state variables each function reads and
+//! writes.
+//!
+//! The report is rendered from a [`StorageAccessReport`] through a
+//! template, the built-in [`DEFAULT_TEMPLATE`] unless the user supplies one.
+
+use crate::output::template::Template;
+use anyhow::Result;
+use serde::Serialize;
+use traverse_graph::cg::CallGraph;
+use traverse_graph::storage_access::analyze_storage_access;
+
+/// The Markdown table the report has always been.
+pub const DEFAULT_TEMPLATE: &str = "# Storage Access Analysis
+
+**Files analyzed:** {{files}} Solidity files
+
+| Endpoint | Reads | Writes |
+|----------|-------|--------|
+{{#each endpoints}}
+| {{name}} | {{#each reads}}{{this}}{{#unless @last}}, {{/unless}}{{/each}} | {{#each writes}}{{this}}{{#unless @last}}, {{/unless}}{{/each}} |
+{{/each}}
+";
+
+/// Data model templates are rendered against.
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageAccessReport {
+    /// Number of source files analyzed.
+    pub files: usize,
+    /// Sorted by `name`.
+    pub endpoints: Vec<EndpointAccess>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointAccess {
+    /// `Contract.function`.
+    pub name: String,
+    /// `Global` for free functions.
+    pub contract: String,
+    pub function: String,
+    /// Variables read, as `Contract.variable`.
+    pub reads: Vec<String>,
+    /// Variables written, as `Contract.variable`.
+    pub writes: Vec<String>,
+}
+
+impl StorageAccessReport {
+    pub fn new(graph: &CallGraph, files: usize) -> Self {
+        let variable = |id: &usize| {
+            graph.nodes.get(*id).map_or_else(
+                || format!("UnknownVar({})", id),
+                |n| format!("{}.{}", n.contract_name.as_deref().unwrap_or("?"), n.name),
+            )
+        };
+        let mut endpoints: Vec<EndpointAccess> = analyze_storage_access(graph)
+            .iter()
+            .filter_map(|(id, summary)| {
+                let node = graph.nodes.get(*id)?;
+                let contract = node.contract_name.as_deref().unwrap_or("Global");
+                Some(EndpointAccess {
+                    name: format!("{}.{}", contract, node.name),
+                    contract: contract.to_string(),
+                    function: node.name.clone(),
+                    reads: summary.reads.iter().map(variable).collect(),
+                    writes: summary.writes.iter().map(variable).collect(),
+                })
+            })
+            .collect();
+        endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { files, endpoints }
+    }
+
+    /// The report through `template`, or through [`DEFAULT_TEMPLATE`].
+    pub fn render(&self, template: Option<&Template>) -> Result<String> {
+        let data = serde_json::to_value(self)?;
+        match template {
+            Some(template) => template.render(&data),
+            None => Template::parse(DEFAULT_TEMPLATE, false)?.render(&data),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Side-by-side comparison of two contracts' storage layouts.
+
+use super::model::SourceModel;
+use super::storage_layout::{layout, SlotEntry};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlotStatus {
+    Same,
+    Renamed,
+    TypeChanged,
+    OnlyLeft,
+    OnlyRight,
+}
+
+impl SlotStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SlotStatus::Same => "same",
+            SlotStatus::Renamed => "renamed",
+            SlotStatus::TypeChanged => "type changed",
+            SlotStatus::OnlyLeft => "only left",
+            SlotStatus::OnlyRight => "only right",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotComparison {
+    pub slot: usize,
+    pub offset: usize,
+    pub left: Option<SlotEntry>,
+    pub right: Option<SlotEntry>,
+    pub status: SlotStatus,
+}
+
+pub fn compare(model: &SourceModel, left: &str, right: &str) -> Result<Vec<SlotComparison>> {
+    let find = |name: &str| {
+        model
+            .contract(name)
+            .ok_or_else(|| anyhow!("Contract `{}` not found in workspace", name))
+    };
+    Ok(compare_layouts(
+        layout(model, find(left)?),
+        layout(model, find(right)?),
+    ))
+}
+
+/// Pairs the entries of two layouts by slot and offset.
+pub fn compare_layouts(
+    left_layout: Vec<SlotEntry>,
+    right_layout: Vec<SlotEntry>,
+) -> Vec<SlotComparison> {
+    let mut positions: BTreeMap<(usize, usize), (Option<SlotEntry>, Option<SlotEntry>)> =
+        BTreeMap::new();
+    for entry in left_layout {
+        let position = (entry.slot, entry.offset);
+        positions.entry(position).or_default().0 = Some(entry);
+    }
+    for entry in right_layout {
+        let position = (entry.slot, entry.offset);
+        positions.entry(position).or_default().1 = Some(entry);
+    }
+
+    positions
+        .into_iter()
+        .map(|((slot, offset), (left, right))| {
+            let status = match (&left, &right) {
+                (Some(l), Some(r)) if l.type_name != r.type_name || l.size != r.size => {
+                    SlotStatus::TypeChanged
+                }
+                (Some(l), Some(r)) if l.name != r.name => SlotStatus::Renamed,
+                (Some(_), Some(_)) => SlotStatus::Same,
+                (Some(_), None) => SlotStatus::OnlyLeft,
+                (None, _) => SlotStatus::OnlyRight,
+            };
+            SlotComparison {
+                slot,
+                offset,
+                left,
+                right,
+                status,
+            }
+        })
+        .collect()
+}
+
+pub fn render_comparison(left: &str, right: &str, rows: &[SlotComparison]) -> String {
+    let divergent = rows.iter().filter(|r| r.status != SlotStatus::Same).count();
+
+    let mut md = format!("# Storage Layout Comparison: `{}` vs `{}`\n\n", left, right);
+    md.push_str(&format!(
+        "**Divergences:** {} of {} positions\n\n",
+        divergent,
+        rows.len()
+    ));
+    md.push_str(&format!(
+        "| Slot | Offset | {} | {} | Status |\n",
+        left, right
+    ));
+    md.push_str("|------|--------|------|------|--------|\n");
+
+    let cell = |entry: &Option<SlotEntry>| {
+        entry.as_ref().map_or_else(
+            || "-".to_string(),
+            |e| format!("`{}: {}` ({})", e.name, e.type_name, e.declared_in),
+        )
+    };
+    for row in rows {
+        let status = if row.status == SlotStatus::Same {
+            row.status.as_str().to_string()
+        } else {
+            format!("**{}**", row.status.as_str())
+        };
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            row.slot,
+            row.offset,
+            cell(&row.left),
+            cell(&row.right),
+            status
+        ));
+    }
+    md
+}
@@ -0,0 +1,124 @@
+//! Storage gap verification for upgradeable base contracts.
+//!
+//! Upgradeable bases reserve a fixed number of slots (`RESERVED_SLOTS`, the
+//! OpenZeppelin convention) split between their own variables and a trailing
+//! `__gap` array, so that new variables can be added without shifting the
+//! layout of derived contracts.
+
+use super::model::{ContractInfo, ContractKind, SourceModel, StateVarInfo};
+use super::storage_layout::slot_count;
+use super::{Confidence, Finding, Severity};
+
+pub const RULE: &str = "storage-gap";
+pub const RESERVED_SLOTS: usize = 50;
+const GAP_NAME: &str = "__gap";
+
+pub fn check(model: &SourceModel) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for contract in &model.contracts {
+        if contract.kind != ContractKind::Contract
+            || contract.name == "Initializable"
+            || !model.is_upgradeable(contract)
+        {
+            continue;
+        }
+
+        let storage: Vec<&StateVarInfo> = contract
+            .state_vars
+            .iter()
+            .filter(|v| v.occupies_storage())
+            .collect();
+        let own_types: Vec<&str> = storage
+            .iter()
+            .filter(|v| v.name != GAP_NAME)
+            .map(|v| v.type_name.as_str())
+            .collect();
+        let used = slot_count(model, &own_types);
+
+        let Some(gap) = storage.iter().find(|v| v.name == GAP_NAME) else {
+            if used > 0 && is_inherited(model, contract) {
+                findings.push(Finding {
+                    rule: RULE,
+                    severity: Severity::Medium,
+                    // Whether a contract is an upgradeable base is inferred.
+                    confidence: Confidence::Medium,
+                    contract: Some(contract.name.clone()),
+                    function: None,
+                    message: format!(
+                        "Upgradeable base `{}` uses {} storage slot(s) but declares no `{}`; \
+                         adding variables later will shift derived layouts",
+                        contract.name, used, GAP_NAME
+                    ),
+                    file: Some(contract.file.clone()),
+                    line: Some(contract.line),
+                });
+            }
+            continue;
+        };
+
+        if storage.last().map(|v| v.name.as_str()) != Some(GAP_NAME) {
+            findings.push(Finding {
+                rule: RULE,
+                severity: Severity::Medium,
+                confidence: Confidence::High,
+                contract: Some(contract.name.clone()),
+                function: None,
+                message: format!(
+                    "`{}` in `{}` is not the last state variable",
+                    GAP_NAME, contract.name
+                ),
+                file: Some(contract.file.clone()),
+                line: Some(gap.line),
+            });
+        }
+
+        let gap_slots = slot_count(model, &[gap.type_name.as_str()]);
+        let total = used + gap_slots;
+        if total > RESERVED_SLOTS {
+            findings.push(Finding {
+                rule: RULE,
+                severity: Severity::High,
+                confidence: Confidence::High,
+                contract: Some(contract.name.clone()),
+                function: None,
+                message: format!(
+                    "`{}` uses {} slot(s) + {}[{}] = {} slots, exceeding the {} reserved slots by {}",
+                    contract.name,
+                    used,
+                    GAP_NAME,
+                    gap_slots,
+                    total,
+                    RESERVED_SLOTS,
+                    total - RESERVED_SLOTS
+                ),
+                file: Some(contract.file.clone()),
+                line: Some(gap.line),
+            });
+        } else if total < RESERVED_SLOTS {
+            findings.push(Finding {
+                rule: RULE,
+                severity: Severity::Info,
+                confidence: Confidence::High,
+                contract: Some(contract.name.clone()),
+                function: None,
+                message: format!(
+                    "`{}` uses {} slot(s) + {}[{}] = {} slots; {} slots are reserved by convention",
+                    contract.name, used, GAP_NAME, gap_slots, total, RESERVED_SLOTS
+                ),
+                file: Some(contract.file.clone()),
+                line: Some(gap.line),
+            });
+        }
+    }
+
+    findings
+}
+
+fn is_inherited(model: &SourceModel, contract: &ContractInfo) -> bool {
+    contract.is_abstract
+        || model
+            .contracts
+            .iter()
+            .any(|c| c.bases.iter().any(|b| b == &contract.name))
+}
@@ -0,0 +1,213 @@
+//! Storage access heatmap.
+//!
+//! State variables are sized and colored by how many functions write them,
+//! counting writes nested in `if` and loop bodies. Variables written from
+//! many places are the ones whose invariants are hardest to keep, so they
+//! stand out first.
+
+use super::model::qualified_name;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use traverse_graph::cg::{CallGraph, EdgeType, Node, NodeType};
+
+/// Fill of a variable no function writes.
+const COLD: (u8, u8, u8) = (0xff, 0xf5, 0xf0);
+/// Fill of the most written variable.
+const HOT: (u8, u8, u8) = (0xcb, 0x18, 0x1d);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VariableHeat {
+    pub contract: String,
+    pub variable: String,
+    /// Functions that write the variable, as `Contract.function`.
+    pub writers: Vec<String>,
+    /// Functions that read the variable, as `Contract.function`.
+    pub readers: Vec<String>,
+}
+
+/// Every state variable of the graph, or of `contract` when given, most
+/// written first.
+pub fn heatmap(graph: &CallGraph, contract: Option<&str>) -> Result<Vec<VariableHeat>> {
+    if let Some(contract) = contract {
+        if !graph
+            .nodes
+            .iter()
+            .any(|n| n.contract_name.as_deref() == Some(contract))
+        {
+            return Err(anyhow!("Contract `{}` not found in workspace", contract));
+        }
+    }
+
+    let parents = block_parents(graph);
+    let mut writers: BTreeMap<usize, BTreeSet<String>> = BTreeMap::new();
+    let mut readers: BTreeMap<usize, BTreeSet<String>> = BTreeMap::new();
+    for edge in &graph.edges {
+        let (Some(source), Some(target)) = (
+            enclosing_function(graph, &parents, edge.source_node_id),
+            graph.nodes.get(edge.target_node_id),
+        ) else {
+            continue;
+        };
+        let accessors = match edge.edge_type {
+            EdgeType::StorageWrite => &mut writers,
+            EdgeType::StorageRead => &mut readers,
+            _ => continue,
+        };
+        accessors
+            .entry(target.id)
+            .or_default()
+            .insert(qualified_name(source));
+    }
+
+    let mut entries: Vec<VariableHeat> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::StorageVariable)
+        .filter(|n| contract.is_none() || n.contract_name.as_deref() == contract)
+        .map(|n| VariableHeat {
+            contract: n.contract_name.clone().unwrap_or_default(),
+            variable: n.name.clone(),
+            writers: writers
+                .remove(&n.id)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            readers: readers
+                .remove(&n.id)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.writers
+            .len()
+            .cmp(&a.writers.len())
+            .then_with(|| a.contract.cmp(&b.contract))
+            .then_with(|| a.variable.cmp(&b.variable))
+    });
+    Ok(entries)
+}
+
+/// Graphviz diagram with one cluster per contract. Nodes grow and redden
+/// with their number of writers, relative to the most written variable.
+pub fn render_dot(entries: &[VariableHeat]) -> String {
+    let max = entries.iter().map(|e| e.writers.len()).max().unwrap_or(0);
+    let mut by_contract: BTreeMap<&str, Vec<(usize, &VariableHeat)>> = BTreeMap::new();
+    for (id, entry) in entries.iter().enumerate() {
+        by_contract
+            .entry(entry.contract.as_str())
+            .or_default()
+            .push((id, entry));
+    }
+
+    let mut dot = String::from("digraph storage_heatmap {\n");
+    dot.push_str("    graph [rankdir=LR, label=\"Storage writes per variable\"];\n");
+    dot.push_str("    node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n");
+    for (cluster, (contract, variables)) in by_contract.iter().enumerate() {
+        dot.push_str(&format!("    subgraph cluster_{} {{\n", cluster));
+        dot.push_str(&format!("        label=\"{}\";\n", escape(contract)));
+        for (id, entry) in variables {
+            let heat = if max == 0 {
+                0.0
+            } else {
+                entry.writers.len() as f64 / max as f64
+            };
+            let writers = match entry.writers.len() {
+                1 => "1 writer".to_string(),
+                n => format!("{} writers", n),
+            };
+            dot.push_str(&format!(
+                "        v{} [label=\"{}\\n{}\", fillcolor=\"{}\", fontcolor=\"{}\", fontsize={:.0}, width={:.2}, tooltip=\"{}\"];\n",
+                id,
+                escape(&entry.variable),
+                writers,
+                color(heat),
+                if heat > 0.6 { "white" } else { "black" },
+                10.0 + 8.0 * heat,
+                1.0 + heat,
+                escape(&entry.writers.join(", ")),
+            ));
+        }
+        dot.push_str("    }\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn render_heatmap(entries: &[VariableHeat]) -> String {
+    let mut md = String::from("# Storage Write Heatmap\n\n");
+    md.push_str(&format!("**State variables:** {}\n\n", entries.len()));
+    md.push_str("| Contract | Variable | Writers | Readers | Written by |\n");
+    md.push_str("|----------|----------|---------|---------|------------|\n");
+    for entry in entries {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            entry.contract,
+            entry.variable,
+            entry.writers.len(),
+            entry.readers.len(),
+            entry.writers.join(", ")
+        ));
+    }
+    md
+}
+
+/// Parent of each synthetic node, such as the `then` block of an `if`, in
+/// the control flow the library draws inside functions.
+pub(crate) fn block_parents(graph: &CallGraph) -> BTreeMap<usize, usize> {
+    graph
+        .edges
+        .iter()
+        .filter(|edge| {
+            matches!(
+                edge.edge_type,
+                EdgeType::Require
+                    | EdgeType::IfConditionBranch
+                    | EdgeType::ThenBranch
+                    | EdgeType::ElseBranch
+                    | EdgeType::WhileConditionBranch
+                    | EdgeType::WhileBodyBranch
+                    | EdgeType::ForConditionBranch
+                    | EdgeType::ForBodyBranch
+            )
+        })
+        .map(|edge| (edge.target_node_id, edge.source_node_id))
+        .collect()
+}
+
+/// The function, constructor or modifier whose body holds node `id`.
+pub(crate) fn enclosing_function<'a>(
+    graph: &'a CallGraph,
+    parents: &BTreeMap<usize, usize>,
+    mut id: usize,
+) -> Option<&'a Node> {
+    // Bounded, in case the library ever draws a cycle.
+    for _ in 0..=graph.nodes.len() {
+        let node = graph.nodes.get(id)?;
+        if matches!(
+            node.node_type,
+            NodeType::Function | NodeType::Constructor | NodeType::Modifier
+        ) {
+            return Some(node);
+        }
+        id = *parents.get(&id)?;
+    }
+    None
+}
+
+/// `#rrggbb` between [`COLD`] and [`HOT`].
+fn color(heat: f64) -> String {
+    let mix = |cold: u8, hot: u8| (cold as f64 + (hot as f64 - cold as f64) * heat).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        mix(COLD.0, HOT.0),
+        mix(COLD.1, HOT.1),
+        mix(COLD.2, HOT.2)
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,169 @@
+//! Storage slot assignment for contract state variables.
+//!
+//! Mirrors the compiler's layout rules: value types pack right-to-left into
+//! 32-byte slots in declaration order, while mappings, dynamic and static
+//! arrays, structs and strings always start on a fresh slot.
+
+use super::model::{ContractInfo, SourceModel};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SlotEntry {
+    pub slot: usize,
+    pub offset: usize,
+    pub size: usize,
+    pub name: String,
+    pub type_name: String,
+    pub declared_in: String,
+}
+
+/// Full layout of `contract`, inherited variables first in linearization order.
+pub fn layout(model: &SourceModel, contract: &ContractInfo) -> Vec<SlotEntry> {
+    let vars: Vec<_> = model
+        .ancestors(&contract.name)
+        .into_iter()
+        .chain(std::iter::once(contract))
+        .flat_map(|owner| {
+            owner
+                .state_vars
+                .iter()
+                .filter(|v| v.occupies_storage())
+                .map(move |v| (owner, v))
+        })
+        .collect();
+
+    let mut packer = Packer::default();
+    vars.into_iter()
+        .map(|(owner, var)| {
+            let (slot, offset, size) = packer.place(footprint(model, &var.type_name, 0));
+            SlotEntry {
+                slot,
+                offset,
+                size,
+                name: var.name.clone(),
+                type_name: var.type_name.clone(),
+                declared_in: owner.name.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Footprint of a type in the storage layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Footprint {
+    /// Value type of the given byte width that may share a slot.
+    Packed(usize),
+    /// Type that starts on a fresh slot and spans whole slots.
+    Slots(usize),
+}
+
+/// Number of slots occupied by variables of the given types laid out in order.
+pub fn slot_count(model: &SourceModel, types: &[&str]) -> usize {
+    pack(types.iter().map(|t| footprint(model, t, 0)))
+}
+
+fn pack(footprints: impl Iterator<Item = Footprint>) -> usize {
+    let mut packer = Packer::default();
+    for fp in footprints {
+        packer.place(fp);
+    }
+    packer.slots_used()
+}
+
+#[derive(Default)]
+struct Packer {
+    slot: usize,
+    offset: usize,
+}
+
+impl Packer {
+    /// Places the next variable and returns its slot, byte offset and size.
+    fn place(&mut self, footprint: Footprint) -> (usize, usize, usize) {
+        match footprint {
+            Footprint::Packed(bytes) => {
+                if self.offset + bytes > 32 {
+                    self.slot += 1;
+                    self.offset = 0;
+                }
+                let placed = (self.slot, self.offset, bytes);
+                self.offset += bytes;
+                placed
+            }
+            Footprint::Slots(n) => {
+                if self.offset > 0 {
+                    self.slot += 1;
+                    self.offset = 0;
+                }
+                let placed = (self.slot, 0, n * 32);
+                self.slot += n;
+                placed
+            }
+        }
+    }
+
+    fn slots_used(&self) -> usize {
+        self.slot + usize::from(self.offset > 0)
+    }
+}
+
+pub(crate) fn footprint(model: &SourceModel, type_name: &str, depth: usize) -> Footprint {
+    let type_name = type_name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let type_name = type_name.as_str();
+
+    if type_name.starts_with("mapping") {
+        return Footprint::Slots(1);
+    }
+
+    if let Some(open) = type_name.rfind('[') {
+        if type_name.ends_with(']') {
+            let element = &type_name[..open];
+            let len = type_name[open + 1..type_name.len() - 1].trim();
+            let Ok(len) = len.parse::<usize>() else {
+                // Dynamic arrays keep only their length in the declared slot.
+                return Footprint::Slots(1);
+            };
+            return match footprint(model, element, depth) {
+                Footprint::Packed(bytes) => Footprint::Slots(len.div_ceil(32 / bytes)),
+                Footprint::Slots(n) => Footprint::Slots(n * len),
+            };
+        }
+    }
+
+    match type_name {
+        "bool" | "byte" => return Footprint::Packed(1),
+        "address" | "address payable" => return Footprint::Packed(20),
+        "string" | "bytes" => return Footprint::Slots(1),
+        "uint" | "int" => return Footprint::Packed(32),
+        _ => {}
+    }
+
+    for prefix in ["uint", "int"] {
+        if let Some(bits) = type_name.strip_prefix(prefix) {
+            if let Ok(n @ 1..=256) = bits.parse::<usize>() {
+                return Footprint::Packed(n.div_ceil(8));
+            }
+        }
+    }
+    // Fixed-size byte arrays stop at `bytes32`; anything wider is not a
+    // Solidity type and falls through to a whole slot.
+    if let Some(Ok(n @ 1..=32)) = type_name.strip_prefix("bytes").map(str::parse::<usize>) {
+        return Footprint::Packed(n);
+    }
+
+    let simple = type_name.rsplit('.').next().unwrap_or(type_name);
+    if model.is_enum(simple) {
+        return Footprint::Packed(1);
+    }
+    if let Some(info) = model.find_struct(simple) {
+        if depth < 8 {
+            let members = info.members.iter().map(|m| footprint(model, m, depth + 1));
+            return Footprint::Slots(pack(members));
+        }
+    }
+    if model.contract(simple).is_some() {
+        // Contract and interface references are stored as addresses.
+        return Footprint::Packed(20);
+    }
+
+    Footprint::Slots(1)
+}
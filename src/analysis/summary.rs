@@ -0,0 +1,191 @@
+//! Audit findings aggregated by severity, rule and contract, rendered as a
+//! Markdown body short enough to post as a pull request comment.
+
+use super::{Finding, Severity};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// Contracts listed under "most affected" unless the caller asks otherwise.
+pub const DEFAULT_TOP_CONTRACTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SeverityCounts {
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+    pub info: usize,
+}
+
+impl SeverityCounts {
+    fn add(&mut self, severity: Severity) {
+        match severity {
+            Severity::High => self.high += 1,
+            Severity::Medium => self.medium += 1,
+            Severity::Low => self.low += 1,
+            Severity::Info => self.info += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.high + self.medium + self.low + self.info
+    }
+
+    /// Orders by the most severe findings first.
+    fn rank(&self) -> (usize, usize, usize, usize) {
+        (self.high, self.medium, self.low, self.info)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleSummary {
+    pub rule: String,
+    #[serde(flatten)]
+    pub counts: SeverityCounts,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContractSummary {
+    pub contract: String,
+    #[serde(flatten)]
+    pub counts: SeverityCounts,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FindingsSummary {
+    pub files: usize,
+    pub suppressed: usize,
+    pub total: usize,
+    pub by_severity: SeverityCounts,
+    /// Every rule that reported something, most severe first.
+    pub by_rule: Vec<RuleSummary>,
+    /// The `top` contracts with the most severe findings.
+    pub top_contracts: Vec<ContractSummary>,
+}
+
+pub fn summarize(
+    findings: &[Finding],
+    files: usize,
+    suppressed: usize,
+    top: usize,
+) -> FindingsSummary {
+    let mut by_severity = SeverityCounts::default();
+    let mut by_rule: BTreeMap<&str, SeverityCounts> = BTreeMap::new();
+    let mut by_contract: BTreeMap<&str, SeverityCounts> = BTreeMap::new();
+    for finding in findings {
+        by_severity.add(finding.severity);
+        by_rule
+            .entry(finding.rule)
+            .or_default()
+            .add(finding.severity);
+        if let Some(contract) = &finding.contract {
+            by_contract
+                .entry(contract)
+                .or_default()
+                .add(finding.severity);
+        }
+    }
+
+    // Stable sorts keep ties in name order.
+    let mut by_rule: Vec<RuleSummary> = by_rule
+        .into_iter()
+        .map(|(rule, counts)| RuleSummary {
+            rule: rule.to_string(),
+            counts,
+        })
+        .collect();
+    by_rule.sort_by_key(|r| Reverse(r.counts.rank()));
+
+    let mut top_contracts: Vec<ContractSummary> = by_contract
+        .into_iter()
+        .map(|(contract, counts)| ContractSummary {
+            contract: contract.to_string(),
+            counts,
+        })
+        .collect();
+    top_contracts.sort_by_key(|c| Reverse(c.counts.rank()));
+    top_contracts.truncate(top);
+
+    FindingsSummary {
+        files,
+        suppressed,
+        total: findings.len(),
+        by_severity,
+        by_rule,
+        top_contracts,
+    }
+}
+
+pub fn render_summary(summary: &FindingsSummary) -> String {
+    let mut md = String::from("## Audit Summary\n\n");
+    let suppressed = if summary.suppressed > 0 {
+        format!(" ({} suppressed)", summary.suppressed)
+    } else {
+        String::new()
+    };
+    if summary.total == 0 {
+        md.push_str(&format!(
+            "No findings in {} Solidity files{}.\n",
+            summary.files, suppressed
+        ));
+        return md;
+    }
+    md.push_str(&format!(
+        "**{} findings** in {} Solidity files{}\n\n",
+        summary.total, summary.files, suppressed
+    ));
+
+    md.push_str("| High | Medium | Low | Info |\n");
+    md.push_str("|------|--------|-----|------|\n");
+    let counts = &summary.by_severity;
+    md.push_str(&format!(
+        "| {} | {} | {} | {} |\n",
+        counts.high, counts.medium, counts.low, counts.info
+    ));
+
+    md.push_str("\n### By Rule\n\n");
+    push_table(
+        &mut md,
+        "Rule",
+        summary
+            .by_rule
+            .iter()
+            .map(|r| (format!("`{}`", r.rule), &r.counts)),
+    );
+
+    if !summary.top_contracts.is_empty() {
+        md.push_str("\n### Most Affected Contracts\n\n");
+        push_table(
+            &mut md,
+            "Contract",
+            summary
+                .top_contracts
+                .iter()
+                .map(|c| (format!("`{}`", c.contract), &c.counts)),
+        );
+    }
+    md
+}
+
+fn push_table<'a>(
+    md: &mut String,
+    heading: &str,
+    rows: impl Iterator<Item = (String, &'a SeverityCounts)>,
+) {
+    md.push_str(&format!(
+        "| {} | High | Medium | Low | Info | Total |\n",
+        heading
+    ));
+    md.push_str("|------|------|--------|-----|------|-------|\n");
+    for (name, counts) in rows {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            name,
+            counts.high,
+            counts.medium,
+            counts.low,
+            counts.info,
+            counts.total()
+        ));
+    }
+}
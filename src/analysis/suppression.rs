@@ -0,0 +1,133 @@
+//! Acknowledged findings.
+//!
+//! A finding is dropped when its line carries a
+//! `// traverse-ignore: <rule>[, <rule>...]` comment or directly follows a
+//! comment line with one, or when it matches an entry in the workspace's
+//! `.traverseignore` file. `*` matches every rule.
+
+use super::model::SourceUnit;
+use super::Finding;
+use std::path::Path;
+
+pub const IGNORE_FILE: &str = ".traverseignore";
+const INLINE_MARKER: &str = "traverse-ignore:";
+
+/// One `.traverseignore` line: `<rule> [<scope>]`, where the optional scope
+/// is a contract name, `Contract.function`, or a `.sol` path relative to the
+/// workspace root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoreEntry {
+    pub rule: String,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFile {
+    pub entries: Vec<IgnoreEntry>,
+}
+
+impl IgnoreFile {
+    /// Parses the file, skipping blank lines and `#` comments.
+    pub fn parse(content: &str) -> Self {
+        let entries = content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let mut parts = line.split_whitespace();
+                IgnoreEntry {
+                    rule: parts.next().unwrap_or_default().to_string(),
+                    scope: parts.next().map(str::to_string),
+                }
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Loads `<workspace>/.traverseignore`, treating a missing file as empty.
+    pub fn load(workspace_root: &Path) -> Self {
+        std::fs::read_to_string(workspace_root.join(IGNORE_FILE))
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    pub fn matches(&self, finding: &Finding, workspace_root: &Path) -> bool {
+        self.entries.iter().any(|entry| {
+            rule_matches(&entry.rule, finding.rule)
+                && entry
+                    .scope
+                    .as_deref()
+                    .is_none_or(|scope| scope_matches(scope, finding, workspace_root))
+        })
+    }
+}
+
+/// Removes suppressed findings and returns how many were dropped.
+pub fn apply(
+    findings: &mut Vec<Finding>,
+    sources: &[SourceUnit],
+    ignore: &IgnoreFile,
+    workspace_root: &Path,
+) -> usize {
+    let before = findings.len();
+    findings.retain(|finding| {
+        !ignore.matches(finding, workspace_root) && !is_suppressed_inline(finding, sources)
+    });
+    before - findings.len()
+}
+
+fn is_suppressed_inline(finding: &Finding, sources: &[SourceUnit]) -> bool {
+    let (Some(file), Some(line)) = (&finding.file, finding.line) else {
+        return false;
+    };
+    let Some(unit) = sources.iter().find(|unit| &unit.path == file) else {
+        return false;
+    };
+    let lines: Vec<&str> = unit.content.lines().collect();
+    let suppresses = |text: &str| inline_rules(text).any(|rule| rule_matches(rule, finding.rule));
+    // Lines are 1-based. A trailing comment covers its own line; a comment on
+    // a line of its own covers the next one.
+    let same_line = lines
+        .get(line.wrapping_sub(1))
+        .is_some_and(|text| suppresses(text));
+    let line_above = line >= 2
+        && lines.get(line - 2).is_some_and(|text| {
+            let trimmed = text.trim_start();
+            (trimmed.starts_with("//") || trimmed.starts_with("/*")) && suppresses(text)
+        });
+    same_line || line_above
+}
+
+fn inline_rules(line: &str) -> impl Iterator<Item = &str> {
+    line.find(INLINE_MARKER)
+        .filter(|start| line[..*start].contains("//") || line[..*start].contains("/*"))
+        .map(|start| {
+            line[start + INLINE_MARKER.len()..]
+                .trim_end()
+                .trim_end_matches("*/")
+        })
+        .into_iter()
+        .flat_map(|rules| rules.split([',', ' ']))
+        .map(str::trim)
+        .filter(|rule| !rule.is_empty())
+}
+
+fn rule_matches(pattern: &str, rule: &str) -> bool {
+    pattern == "*" || pattern == rule
+}
+
+fn scope_matches(scope: &str, finding: &Finding, workspace_root: &Path) -> bool {
+    if scope.ends_with(".sol") {
+        return finding.file.as_deref().is_some_and(|file| {
+            let relative = file.strip_prefix(workspace_root).unwrap_or(file);
+            relative == Path::new(scope) || file.ends_with(scope)
+        });
+    }
+    match scope.split_once('.') {
+        Some((contract, function)) => {
+            finding.contract.as_deref() == Some(contract)
+                && finding.function.as_deref() == Some(function)
+        }
+        None => finding.contract.as_deref() == Some(scope),
+    }
+}
@@ -0,0 +1,208 @@
+//! Arithmetic that looks like it mixes units.
+//!
+//! Fixed-point values scaled by `1e18` and timestamps in seconds are plain
+//! integers to the compiler, so adding a bare `5` to a wad or `7` to
+//! `block.timestamp` type-checks. These checks match on the shape of the
+//! expression and on names only, and report low-confidence findings.
+
+use super::magic_numbers::{is_time_unit, parse_value};
+use super::model::{for_each_callable, line_of, visit, ParsedSource};
+use super::{Confidence, Finding, Severity};
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+pub const RULE: &str = "unit-mismatch";
+
+const WAD: f64 = 1e18;
+
+/// Bare literals at most this large added to a timestamp look like a count
+/// of days or hours rather than seconds.
+const MAX_BARE_DURATION: f64 = 1_000.0;
+
+/// Seconds per minute, hour, day and week.
+const SECONDS_PER: [f64; 4] = [60.0, 3_600.0, 86_400.0, 604_800.0];
+
+const UNIT_NAMES: [&str; 5] = ["minutes", "hours", "days", "weeks", "years"];
+
+pub fn check(parsed: &[ParsedSource]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for source in parsed {
+        let text = source.unit.content.as_str();
+        for_each_callable(source.tree.root_node(), text, |contract, function, node| {
+            visit(node, &mut |n| {
+                if n.kind() != "binary_expression" {
+                    return;
+                }
+                let Some(message) = mismatch(n, text, source.dialect.now_is_timestamp) else {
+                    return;
+                };
+                findings.push(Finding {
+                    rule: RULE,
+                    severity: Severity::Low,
+                    confidence: Confidence::Low,
+                    contract: Some(contract.to_string()),
+                    function: Some(function.to_string()),
+                    message,
+                    file: Some(source.unit.path.clone()),
+                    line: Some(line_of(n)),
+                });
+            });
+        });
+    }
+
+    findings
+}
+
+/// Describes the suspected mix in `node`, a binary expression.
+fn mismatch(node: TsNode, source: &str, now_is_timestamp: bool) -> Option<String> {
+    let operator = node
+        .child_by_field_name("operator")
+        .map(|o| get_node_text(&o, source))?;
+    let left = operand(node.child_by_field_name("left")?);
+    let right = operand(node.child_by_field_name("right")?);
+    let expression = get_node_text(&node, source);
+
+    if operator == "*" {
+        for (a, b) in [(left, right), (right, left)] {
+            if is_time_unit(a, source) && bare_value(b, source).is_some_and(is_seconds_per_unit) {
+                return Some(format!(
+                    "`{}` converts a duration to seconds twice",
+                    expression
+                ));
+            }
+        }
+        return None;
+    }
+
+    let additive = matches!(operator, "+" | "-");
+    let comparison = matches!(operator, "==" | "!=" | "<" | ">" | "<=" | ">=");
+    if !additive && !comparison {
+        return None;
+    }
+
+    for (a, b) in [(left, right), (right, left)] {
+        if is_wad_scaled(a, source) {
+            if let Some(value) = bare_value(b, source).filter(|v| *v > 1.0 && *v < WAD / 1e9) {
+                return Some(format!(
+                    "`{}` mixes a 1e18-scaled value with the unscaled literal {}",
+                    expression, value
+                ));
+            }
+        }
+        if reads_clock(a, source, now_is_timestamp) {
+            if let Some(value) =
+                bare_value(b, source).filter(|v| *v > 1.0 && *v <= MAX_BARE_DURATION)
+            {
+                return Some(format!(
+                    "`{}` uses {} seconds against a timestamp; a unit such as `days` may be missing",
+                    expression, value
+                ));
+            }
+            if let Some(unit) = unit_in_name(b, source) {
+                return Some(format!(
+                    "`{}` uses `{}`, which looks like a count of {}, against a timestamp in seconds",
+                    expression,
+                    get_node_text(&b, source),
+                    unit
+                ));
+            }
+        }
+    }
+    None
+}
+
+/// `node` with `expression` and parenthesis wrappers removed.
+fn operand(mut node: TsNode) -> TsNode {
+    while matches!(node.kind(), "expression" | "parenthesized_expression") {
+        match node.named_child(0) {
+            Some(child) if node.named_child_count() == 1 => node = child,
+            _ => break,
+        }
+    }
+    node
+}
+
+/// Value of a number literal written without a unit.
+fn bare_value(node: TsNode, source: &str) -> Option<f64> {
+    if node.kind() != "number_literal" {
+        return None;
+    }
+    let mut cursor = node.walk();
+    if node
+        .named_children(&mut cursor)
+        .any(|c| c.kind() == "number_unit")
+    {
+        return None;
+    }
+    parse_value(get_node_text(&node, source)).map(|(value, _)| value)
+}
+
+fn is_seconds_per_unit(value: f64) -> bool {
+    SECONDS_PER.contains(&value)
+}
+
+/// Whether `node` multiplies by `1e18`, `10**18`, `1 ether` or `WAD` without
+/// dividing by it again.
+fn is_wad_scaled(node: TsNode, source: &str) -> bool {
+    let mut scaled = false;
+    let mut unscaled = false;
+    visit(node, &mut |n| {
+        if n.kind() != "binary_expression" {
+            return;
+        }
+        let operator = n
+            .child_by_field_name("operator")
+            .map(|o| get_node_text(&o, source));
+        let factor = [
+            n.child_by_field_name("left"),
+            n.child_by_field_name("right"),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|side| is_wad(operand(side), source));
+        match operator {
+            Some("*") if factor => scaled = true,
+            Some("/") if factor => unscaled = true,
+            _ => {}
+        }
+    });
+    scaled && !unscaled
+}
+
+fn is_wad(node: TsNode, source: &str) -> bool {
+    match node.kind() {
+        "number_literal" => {
+            parse_value(get_node_text(&node, source)).is_some_and(|(value, _)| value == WAD)
+        }
+        "identifier" => get_node_text(&node, source) == "WAD",
+        "binary_expression" => {
+            let text: String = get_node_text(&node, source).split_whitespace().collect();
+            text == "10**18"
+        }
+        _ => false,
+    }
+}
+
+/// Whether `node` reads `block.timestamp`, or `now` where it is an alias.
+fn reads_clock(node: TsNode, source: &str, now_is_timestamp: bool) -> bool {
+    let mut found = false;
+    visit(node, &mut |n| {
+        let text = get_node_text(&n, source);
+        found |= (n.kind() == "member_expression" && text.replace(' ', "") == "block.timestamp")
+            || (now_is_timestamp && n.kind() == "identifier" && text == "now");
+    });
+    found
+}
+
+/// The time unit an identifier such as `lockDays` or `grace_hours` is named
+/// after.
+fn unit_in_name(node: TsNode, source: &str) -> Option<&'static str> {
+    let name = match node.kind() {
+        "identifier" => get_node_text(&node, source),
+        "member_expression" => get_node_text(&node.child_by_field_name("property")?, source),
+        _ => return None,
+    };
+    let lower = name.to_ascii_lowercase();
+    UNIT_NAMES.into_iter().find(|unit| lower.ends_with(unit))
+}
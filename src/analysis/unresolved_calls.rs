@@ -0,0 +1,296 @@
+//! Calls the call graph builder could not resolve to a target.
+//!
+//! Every call expression inside a function is matched against the call
+//! graph's call-site spans; whatever has no edge and is not a language
+//! builtin becomes an [`UnresolvedCall`], which [`add_placeholders`] turns
+//! into an explicit node so diagrams show the blind spot instead of omitting
+//! the edge.
+
+use super::model::{for_each_callable, parse_sources, unwrap_expression, visit, SourceUnit};
+use crate::source_map::SourceMap;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashSet;
+use traverse_graph::cg::{CallGraph, Edge, EdgeType, NodeType, Visibility};
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+/// Contract name given to placeholder nodes.
+pub const PLACEHOLDER_CONTRACT: &str = "Unresolved";
+const MAX_EXPRESSION_LEN: usize = 48;
+
+const BUILTIN_FUNCTIONS: &[&str] = &[
+    "require",
+    "assert",
+    "revert",
+    "keccak256",
+    "sha256",
+    "ripemd160",
+    "ecrecover",
+    "addmod",
+    "mulmod",
+    "blockhash",
+    "blobhash",
+    "gasleft",
+    "selfdestruct",
+    "type",
+];
+const BUILTIN_NAMESPACES: &[&str] = &["abi", "bytes", "string", "block", "msg", "tx"];
+const LOW_LEVEL_CALLS: &[&str] = &["call", "delegatecall", "staticcall"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnresolvedReason {
+    /// `call`, `delegatecall` or `staticcall` on a raw address.
+    LowLevelCall,
+    /// The callee or the type it is called through is not declared in the
+    /// analyzed sources, typically a missing import.
+    MissingDefinition,
+    /// Member call whose receiver type could not be determined.
+    UnresolvedMember,
+    /// Call to a declared name that no definition matched.
+    UnresolvedCall,
+}
+
+impl UnresolvedReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnresolvedReason::LowLevelCall => "low-level-call",
+            UnresolvedReason::MissingDefinition => "missing-definition",
+            UnresolvedReason::UnresolvedMember => "unresolved-member",
+            UnresolvedReason::UnresolvedCall => "unresolved-call",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedCall {
+    /// Graph node id of the calling function.
+    pub caller: usize,
+    /// Callee expression as written, e.g. `IVault(target).sweep`.
+    pub expression: String,
+    pub reason: UnresolvedReason,
+    /// Byte range of the call expression, offset as in [`SourceMap`].
+    pub span: (usize, usize),
+}
+
+/// Finds unresolved calls in `sources`, which must be the files the graph
+/// was built from, in the same order, so call-site spans line up.
+pub fn find(sources: &[SourceUnit], graph: &CallGraph) -> Result<Vec<UnresolvedCall>> {
+    let parsed = parse_sources(sources)?;
+    let map = SourceMap::new(sources);
+
+    let resolved: HashSet<(usize, usize)> = graph
+        .edges
+        .iter()
+        .filter(|e| e.edge_type == EdgeType::Call)
+        .map(|e| e.call_site_span)
+        .collect();
+
+    let mut declared = HashSet::new();
+    let mut types = HashSet::new();
+    for source in &parsed {
+        let text = source.unit.content.as_str();
+        visit(source.tree.root_node(), &mut |node| {
+            let Some(name) = node.child_by_field_name("name") else {
+                return;
+            };
+            let name = get_node_text(&name, text).to_string();
+            match node.kind() {
+                "function_definition" | "modifier_definition" => {
+                    declared.insert(name);
+                }
+                "contract_declaration"
+                | "interface_declaration"
+                | "library_declaration"
+                | "struct_declaration"
+                | "enum_declaration"
+                | "event_definition"
+                | "error_declaration"
+                | "user_defined_type_definition" => {
+                    declared.insert(name.clone());
+                    types.insert(name);
+                }
+                _ => {}
+            }
+        });
+    }
+
+    let mut unresolved = Vec::new();
+    for (index, source) in parsed.iter().enumerate() {
+        let text = source.unit.content.as_str();
+        let offset = map.start(index);
+        let span_of = |node: TsNode| (node.start_byte() + offset, node.end_byte() + offset);
+        for_each_callable(source.tree.root_node(), text, |_, _, callable| {
+            let span = span_of(callable);
+            let Some(caller) = graph.nodes.iter().find(|n| n.span == span) else {
+                return;
+            };
+            visit(callable, &mut |node| {
+                if node.kind() != "call_expression" {
+                    return;
+                }
+                let span = span_of(node);
+                if resolved.contains(&span) {
+                    return;
+                }
+                let Some(callee) = node.child_by_field_name("function").map(unwrap_expression)
+                else {
+                    return;
+                };
+                let Some(reason) = classify(node, callee, text, &declared, &types) else {
+                    return;
+                };
+                let expression = get_node_text(&callee, text)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                unresolved.push(UnresolvedCall {
+                    caller: caller.id,
+                    expression: truncate(&expression),
+                    reason,
+                    span,
+                });
+            });
+        });
+    }
+    Ok(unresolved)
+}
+
+/// Adds one placeholder node per distinct expression and reason, with a call
+/// edge from each caller at its original call site.
+pub fn add_placeholders(graph: &mut CallGraph, calls: &[UnresolvedCall]) {
+    for call in calls {
+        let name = format!("{} [{}]", call.expression, call.reason.as_str());
+        let existing = graph.nodes.iter().position(|n| {
+            n.contract_name.as_deref() == Some(PLACEHOLDER_CONTRACT) && n.name == name
+        });
+        // Private so sequence diagrams do not treat placeholders as entry points.
+        let target = existing.unwrap_or_else(|| {
+            graph.add_node(
+                name,
+                NodeType::Function,
+                Some(PLACEHOLDER_CONTRACT.to_string()),
+                Visibility::Private,
+                call.span,
+            )
+        });
+
+        // Sequence numbers are global and follow source order; slot the new
+        // edge in before the first edge that starts after the call site.
+        let sequence_number = graph
+            .edges
+            .iter()
+            .filter(|e| e.call_site_span.0 >= call.span.0)
+            .map(|e| e.sequence_number)
+            .min()
+            .unwrap_or_else(|| {
+                graph
+                    .edges
+                    .iter()
+                    .map(|e| e.sequence_number)
+                    .max()
+                    .unwrap_or(0)
+                    + 1
+            });
+        for edge in &mut graph.edges {
+            if edge.sequence_number >= sequence_number {
+                edge.sequence_number += 1;
+            }
+        }
+        graph.edges.push(Edge {
+            source_node_id: call.caller,
+            target_node_id: target,
+            edge_type: EdgeType::Call,
+            call_site_span: call.span,
+            return_site_span: None,
+            sequence_number,
+            returned_value: None,
+            argument_names: None,
+            event_name: None,
+            declared_return_type: None,
+        });
+    }
+}
+
+fn classify(
+    call: TsNode,
+    callee: TsNode,
+    source: &str,
+    declared: &HashSet<String>,
+    types: &HashSet<String>,
+) -> Option<UnresolvedReason> {
+    match callee.kind() {
+        "identifier" => {
+            let name = get_node_text(&callee, source);
+            if BUILTIN_FUNCTIONS.contains(&name) || types.contains(name) {
+                None
+            } else if declared.contains(name) {
+                Some(UnresolvedReason::UnresolvedCall)
+            } else if name.starts_with(char::is_uppercase) {
+                // Conversion to an undeclared type; reported through the
+                // member call made on the result, if any.
+                None
+            } else {
+                Some(UnresolvedReason::MissingDefinition)
+            }
+        }
+        "member_expression" => {
+            let object = callee
+                .child_by_field_name("object")
+                .map(unwrap_expression)?;
+            let member = field(callee, "property", source)?;
+            let object_text = get_node_text(&object, source);
+            let arguments = call
+                .named_children(&mut call.walk())
+                .filter(|c| c.kind() == "call_argument")
+                .count();
+
+            if LOW_LEVEL_CALLS.contains(&member) {
+                Some(UnresolvedReason::LowLevelCall)
+            } else if BUILTIN_NAMESPACES.contains(&object_text)
+                || matches!(member, "push" | "pop" | "wrap" | "unwrap")
+                || (matches!(member, "transfer" | "send") && arguments == 1)
+            {
+                None
+            } else if is_undeclared_conversion(object, source, declared) {
+                Some(UnresolvedReason::MissingDefinition)
+            } else {
+                Some(UnresolvedReason::UnresolvedMember)
+            }
+        }
+        // `new`, conversions to elementary types and other callee shapes are
+        // not calls into user code.
+        _ => None,
+    }
+}
+
+/// `IMissing(addr)` where `IMissing` is not declared anywhere.
+fn is_undeclared_conversion(object: TsNode, source: &str, declared: &HashSet<String>) -> bool {
+    if object.kind() != "call_expression" {
+        return false;
+    }
+    let Some(callee) = object
+        .child_by_field_name("function")
+        .map(unwrap_expression)
+    else {
+        return false;
+    };
+    let name = get_node_text(&callee, source);
+    callee.kind() == "identifier"
+        && name.starts_with(char::is_uppercase)
+        && !declared.contains(name)
+}
+
+fn field<'s>(node: TsNode, name: &str, source: &'s str) -> Option<&'s str> {
+    node.child_by_field_name(name)
+        .map(|child| get_node_text(&child, source))
+}
+
+fn truncate(expression: &str) -> String {
+    if expression.chars().count() <= MAX_EXPRESSION_LEN {
+        return expression.to_string();
+    }
+    let prefix: String = expression.chars().take(MAX_EXPRESSION_LEN - 1).collect();
+    format!("{}…", prefix)
+}
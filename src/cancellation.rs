@@ -0,0 +1,95 @@
+//! Requests the client can still cancel with `$/cancelRequest`.
+//!
+//! Each running command registers a [`CancelToken`] under its request id.
+//! Cancelling sets the token, and the worker returns
+//! [`RequestCancelled`](crate::generator_worker::RequestCancelled) at its
+//! next phase boundary, or skips the request if it is still queued.
+//!
+//! Commands that report `$/progress` also register their token under the
+//! progress token, so the cancel button of the client's progress UI,
+//! which sends `window/workDoneProgress/cancel`, stops the same job.
+
+use crate::generator_worker::CancelToken;
+use dashmap::DashMap;
+use lsp_server::RequestId;
+use lsp_types::{NumberOrString, ProgressToken};
+use once_cell::sync::Lazy;
+use std::sync::atomic::Ordering;
+
+/// Cancel tokens of the jobs behind each open progress. Progress is begun
+/// deep in command handling, so the registry is shared process-wide rather
+/// than passed down.
+static PROGRESS: Lazy<DashMap<ProgressToken, CancelToken>> = Lazy::new(DashMap::new);
+
+#[derive(Debug, Default)]
+pub struct InFlightRequests {
+    tokens: DashMap<RequestId, CancelToken>,
+}
+
+impl InFlightRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks `id` until [`finish`](Self::finish) and returns its token.
+    pub fn register(&self, id: RequestId) -> CancelToken {
+        let token = CancelToken::default();
+        self.tokens.insert(id, token.clone());
+        token
+    }
+
+    pub fn finish(&self, id: &RequestId) {
+        self.tokens.remove(id);
+    }
+
+    /// Cancels `id`; `false` if it already finished or was never tracked.
+    pub fn cancel(&self, id: &RequestId) -> bool {
+        match self.tokens.get(id) {
+            Some(token) => {
+                token.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels every tracked request because the server is stopping, so
+    /// they fail with [`ShuttingDown`] rather than as cancelled requests.
+    ///
+    /// [`ShuttingDown`]: crate::generator_worker::ShuttingDown
+    pub fn shut_down_all(&self) {
+        for token in self.tokens.iter() {
+            token.shut_down();
+        }
+    }
+}
+
+/// The request id named by `$/cancelRequest` params.
+pub fn request_id(id: NumberOrString) -> RequestId {
+    match id {
+        NumberOrString::Number(id) => RequestId::from(id),
+        NumberOrString::String(id) => RequestId::from(id),
+    }
+}
+
+/// Lets `window/workDoneProgress/cancel` for `progress` cancel `token`'s
+/// job, until [`finish_progress`].
+pub fn track_progress(progress: ProgressToken, token: CancelToken) {
+    PROGRESS.insert(progress, token);
+}
+
+pub fn finish_progress(progress: &ProgressToken) {
+    PROGRESS.remove(progress);
+}
+
+/// Cancels the job reporting on `progress`; `false` if its progress already
+/// ended or was never cancellable.
+pub fn cancel_progress(progress: &ProgressToken) -> bool {
+    match PROGRESS.get(progress) {
+        Some(token) => {
+            token.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
@@ -2,3 +2,73 @@ pub const GENERATE_CALL_GRAPH_WORKSPACE: &str = "traverse.generateCallGraph.work
 pub const GENERATE_SEQUENCE_DIAGRAM_WORKSPACE: &str = "traverse.generateSequenceDiagram.workspace";
 pub const GENERATE_ALL_WORKSPACE: &str = "traverse.generateAll.workspace";
 pub const ANALYZE_STORAGE_WORKSPACE: &str = "traverse.analyzeStorage.workspace";
+pub const GENERATE_AUDIT_REPORT_WORKSPACE: &str = "traverse.generateAuditReport.workspace";
+pub const LIST_FUZZ_TARGETS_WORKSPACE: &str = "traverse.listFuzzTargets.workspace";
+pub const AUDIT_OVERRIDES_WORKSPACE: &str = "traverse.auditOverrides.workspace";
+pub const CROSS_REFERENCE_WORKSPACE: &str = "traverse.crossReference.workspace";
+pub const MAGIC_NUMBERS_WORKSPACE: &str = "traverse.magicNumbers.workspace";
+pub const EXPORT_GRAPH_WORKSPACE: &str = "traverse.exportGraph.workspace";
+pub const STORAGE_HEATMAP_WORKSPACE: &str = "traverse.storageHeatmap.workspace";
+pub const CALL_MATRIX_WORKSPACE: &str = "traverse.callMatrix.workspace";
+pub const GOVERNANCE_REPORT_WORKSPACE: &str = "traverse.governanceReport.workspace";
+pub const PAUSE_COVERAGE_WORKSPACE: &str = "traverse.pauseCoverage.workspace";
+pub const EXTERNAL_DEPENDENCIES_WORKSPACE: &str = "traverse.externalDependencies.workspace";
+pub const FIND_PRIVILEGE_PATHS_WORKSPACE: &str = "traverse.findPrivilegePaths.workspace";
+pub const FIND_CLONES_WORKSPACE: &str = "traverse.findClones.workspace";
+pub const GENERATE_FUNCTION_DIAGRAM: &str = "traverse.generateFunctionDiagram";
+pub const GENERATE_FUNCTION_DIAGRAM_AT_POSITION: &str =
+    "traverse.generateFunctionDiagramAtPosition";
+pub const REGENERATE_CHUNK: &str = "traverse.regenerateChunk";
+pub const RENDER_DIAGRAM: &str = "traverse.renderDiagram";
+pub const COMPARE_STORAGE: &str = "traverse.compareStorage";
+pub const SUMMARIZE_FINDINGS: &str = "traverse.summarizeFindings";
+pub const GENERATE_PR_COMMENT: &str = "traverse.generatePrComment";
+pub const WARM_CACHE: &str = "traverse.warmCache";
+pub const IMPORT_TRACE: &str = "traverse.importTrace";
+/// Handled by the server itself rather than a generator worker.
+pub const CLEAR_CACHE: &str = "traverse.clearCache";
+pub const DOCTOR: &str = "traverse.doctor";
+pub const CLEAN_OUTPUTS: &str = "traverse.cleanOutputs";
+
+/// Every `workspace/executeCommand` command the server handles.
+pub const WORKSPACE_COMMANDS: &[&str] = &[
+    GENERATE_CALL_GRAPH_WORKSPACE,
+    GENERATE_SEQUENCE_DIAGRAM_WORKSPACE,
+    GENERATE_ALL_WORKSPACE,
+    ANALYZE_STORAGE_WORKSPACE,
+    GENERATE_AUDIT_REPORT_WORKSPACE,
+    LIST_FUZZ_TARGETS_WORKSPACE,
+    AUDIT_OVERRIDES_WORKSPACE,
+    CROSS_REFERENCE_WORKSPACE,
+    MAGIC_NUMBERS_WORKSPACE,
+    EXPORT_GRAPH_WORKSPACE,
+    STORAGE_HEATMAP_WORKSPACE,
+    CALL_MATRIX_WORKSPACE,
+    GOVERNANCE_REPORT_WORKSPACE,
+    PAUSE_COVERAGE_WORKSPACE,
+    EXTERNAL_DEPENDENCIES_WORKSPACE,
+    FIND_PRIVILEGE_PATHS_WORKSPACE,
+    FIND_CLONES_WORKSPACE,
+    GENERATE_FUNCTION_DIAGRAM,
+    GENERATE_FUNCTION_DIAGRAM_AT_POSITION,
+    REGENERATE_CHUNK,
+    RENDER_DIAGRAM,
+    COMPARE_STORAGE,
+    SUMMARIZE_FINDINGS,
+    GENERATE_PR_COMMENT,
+    WARM_CACHE,
+    IMPORT_TRACE,
+];
+
+/// Commands with user-authored arguments whose invocations are kept in the
+/// workspace query history.
+pub const QUERY_COMMANDS: &[&str] = &[GENERATE_FUNCTION_DIAGRAM, COMPARE_STORAGE];
+
+pub const QUERY_HISTORY_REQUEST: &str = "traverse/queryHistory";
+pub const LIST_RULES_REQUEST: &str = "traverse/listRules";
+pub const CONFIG_SCHEMA_REQUEST: &str = "traverse/configSchema";
+pub const FIND_SYMBOL_REQUEST: &str = "traverse/findSymbol";
+pub const STATUS_REQUEST: &str = "traverse/status";
+pub const RESOLVE_COMMAND_ARGUMENTS_REQUEST: &str = "traverse/resolveCommandArguments";
+
+pub const INDEX_STATUS_NOTIFICATION: &str = "traverse/indexStatus";
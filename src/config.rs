@@ -1,18 +1,486 @@
+use crate::analysis::{Severity, RULES};
+use crate::graph_cache::DEFAULT_CACHE_BUDGET_MB;
+use crate::output::large_result::DEFAULT_MAX_RESPONSE_BYTES;
+use crate::output::retention::Retention;
+use crate::output::settings::{OutputLocation, CHUNK_DIR};
+use crate::profiles::Profile;
+use crate::watchdog::{DEFAULT_GENERATION_TIMEOUT, DEFAULT_GENERATION_WORKERS};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Server-wide settings, read from the client's `initializationOptions` and
+/// updated by `workspace/didChangeConfiguration`, over those of the
+/// workspace's `traverse.toml` or `.traverse.yml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    /// Per-rule overrides keyed by rule id, e.g. `storage-gap`.
+    pub rules: BTreeMap<String, RuleConfig>,
+    /// Wall-clock limit for one generation request before the worker is
+    /// restarted; `0` disables the limit.
+    pub generation_timeout_secs: Option<u64>,
+    /// Memory budget for cached call graphs in MiB; `0` disables caching.
+    pub cache_budget_mb: Option<u64>,
+    /// Whether call graphs are cached at all; defaults to `true`.
+    pub cache_enabled: Option<bool>,
+    /// Threads the worker parses files on; `0` or unset uses one per CPU.
+    pub parse_threads: Option<usize>,
+    /// Workers that run workspace commands concurrently.
+    pub generation_workers: Option<usize>,
+    /// Sends anonymized `telemetry/event` metrics after each command; off
+    /// unless set to `true`.
+    pub telemetry: Option<bool>,
+    /// Shows storage reads and writes and callers at the end of each
+    /// function signature; defaults to `true`.
+    pub inlay_hints: Option<bool>,
+    /// Root of generated files; defaults to `./traverse-output`. Can be
+    /// changed mid-session, like `no_chunk`.
+    pub output_dir: Option<PathBuf>,
+    /// Writes Mermaid diagrams as single files even when a command asks
+    /// for chunks.
+    pub no_chunk: Option<bool>,
+    /// Where chunked sequence diagrams go, relative to `output_dir` unless
+    /// absolute; defaults to `sequence-diagrams/chunks`.
+    pub chunk_dir: Option<PathBuf>,
+    /// Sequence diagrams are chunked only when their graph has more nodes
+    /// than this; unset chunks every diagram a command asks to chunk.
+    pub chunk_threshold_nodes: Option<usize>,
+    /// Results larger than this are written to a file under the output
+    /// directory instead of being sent; `0` always sends them.
+    pub max_response_bytes: Option<u64>,
+    /// How many calls deep diagrams follow from their entry points, the
+    /// members of the contract they are narrowed to, or the function a
+    /// function diagram draws; unset follows every call.
+    pub max_call_depth: Option<usize>,
+    /// Compiler versions of parts of the workspace, for repositories
+    /// mixing old and new code; the first matching profile applies.
+    pub profiles: Vec<Profile>,
+    /// Default `include` and `exclude` patterns of workspace commands.
+    #[serde(flatten)]
+    pub files: FileFilter,
+    /// How many runs, and how much output, the output directory keeps.
+    #[serde(flatten)]
+    pub retention: Retention,
+}
+
+impl Config {
+    pub fn cache_budget_bytes(&self) -> usize {
+        if self.cache_enabled == Some(false) {
+            return 0;
+        }
+        let mb = self.cache_budget_mb.unwrap_or(DEFAULT_CACHE_BUDGET_MB);
+        usize::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(usize::MAX)
+    }
+
+    pub fn generation_timeout(&self) -> Option<Duration> {
+        match self.generation_timeout_secs {
+            None => Some(DEFAULT_GENERATION_TIMEOUT),
+            Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    pub fn generation_workers(&self) -> usize {
+        self.generation_workers
+            .unwrap_or(DEFAULT_GENERATION_WORKERS)
+            .max(1)
+    }
+
+    pub fn max_response_bytes(&self) -> Option<usize> {
+        match self
+            .max_response_bytes
+            .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES)
+        {
+            0 => None,
+            bytes => Some(usize::try_from(bytes).unwrap_or(usize::MAX)),
+        }
+    }
+
+    pub fn telemetry_enabled(&self) -> bool {
+        self.telemetry == Some(true)
+    }
+
+    pub fn inlay_hints_enabled(&self) -> bool {
+        self.inlay_hints != Some(false)
+    }
+
+    pub fn is_rule_enabled(&self, rule: &str) -> bool {
+        self.rules.get(rule).is_none_or(|r| r.enabled)
+    }
+
+    pub fn rule_severity(&self, rule: &str) -> Option<Severity> {
+        self.rules.get(rule).and_then(|r| r.severity)
+    }
+}
+
+/// Settings read once when the server starts; changing them needs a restart.
+pub const RESTART_SETTINGS: &[&str] = &[
+    "parse_threads",
+    "generation_workers",
+    "generation_timeout_secs",
+    "cache_budget_mb",
+    "cache_enabled",
+];
+
+/// JSON Schema (draft 2020-12) of [`Config`] as clients send it, with the
+/// defaults applied to unset fields.
+pub fn schema() -> Value {
+    let rule_ids: Vec<&str> = RULES.iter().map(|rule| rule.id).collect();
+    let optional = |kind: &str| json!([kind, "null"]);
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Traverse settings",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": {
+            "rules": {
+                "description": "Per-rule overrides keyed by rule id.",
+                "type": "object",
+                "propertyNames": { "enum": rule_ids },
+                "additionalProperties": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {
+                        "enabled": { "type": "boolean", "default": true },
+                        "severity": {
+                            "description": "Replaces the severity the rule assigns to each of its findings.",
+                            "enum": ["info", "low", "medium", "high", null],
+                        },
+                    },
+                },
+                "default": {},
+            },
+            "generation_timeout_secs": {
+                "description": "Wall-clock limit for one generation request before the worker is restarted; 0 disables the limit.",
+                "type": optional("integer"),
+                "minimum": 0,
+                "default": DEFAULT_GENERATION_TIMEOUT.as_secs(),
+            },
+            "cache_budget_mb": {
+                "description": "Memory budget for cached call graphs in MiB; 0 disables caching.",
+                "type": optional("integer"),
+                "minimum": 0,
+                "default": DEFAULT_CACHE_BUDGET_MB,
+            },
+            "cache_enabled": {
+                "description": "Whether call graphs are cached at all.",
+                "type": optional("boolean"),
+                "default": true,
+            },
+            "parse_threads": {
+                "description": "Threads the worker parses files on; 0 or unset uses one per CPU.",
+                "type": optional("integer"),
+                "minimum": 0,
+            },
+            "generation_workers": {
+                "description": "Workers that run workspace commands concurrently.",
+                "type": optional("integer"),
+                "minimum": 1,
+                "default": DEFAULT_GENERATION_WORKERS,
+            },
+            "telemetry": {
+                "description": "Sends anonymized telemetry/event metrics after each command.",
+                "type": optional("boolean"),
+                "default": false,
+            },
+            "inlay_hints": {
+                "description": "Shows storage reads and writes and callers at the end of each function signature.",
+                "type": optional("boolean"),
+                "default": true,
+            },
+            "output_dir": {
+                "description": "Root of generated files.",
+                "type": optional("string"),
+                "default": OutputLocation::default().dir,
+            },
+            "no_chunk": {
+                "description": "Writes Mermaid diagrams as single files even when a command asks for chunks.",
+                "type": optional("boolean"),
+                "default": false,
+            },
+            "chunk_dir": {
+                "description": "Where chunked sequence diagrams go, relative to output_dir unless absolute.",
+                "type": optional("string"),
+                "default": CHUNK_DIR,
+            },
+            "chunk_threshold_nodes": {
+                "description": "Sequence diagrams are chunked only when their graph has more nodes than this; unset chunks every diagram a command asks to chunk.",
+                "type": optional("integer"),
+                "minimum": 0,
+            },
+            "max_response_bytes": {
+                "description": "Results larger than this are written to a file under the output directory instead of being sent; 0 always sends them.",
+                "type": optional("integer"),
+                "minimum": 0,
+                "default": DEFAULT_MAX_RESPONSE_BYTES,
+            },
+            "max_call_depth": {
+                "description": "How many calls deep diagrams follow from the public and external functions of concrete contracts, from the members of the contract they are narrowed to, or from the function a function diagram draws; 0 shows those functions alone. Unlimited when unset.",
+                "type": optional("integer"),
+                "minimum": 0,
+            },
+            "keep_runs": {
+                "description": "Generated runs kept under the output directory: chunk and export directories and result files. The oldest are removed after each command; unlimited when unset.",
+                "type": optional("integer"),
+                "minimum": 1,
+            },
+            "max_output_mb": {
+                "description": "Total size in MiB of the runs kept under the output directory; the newest run is kept regardless. Unlimited when unset.",
+                "type": optional("integer"),
+                "minimum": 0,
+            },
+            "profiles": {
+                "description": "Compiler versions of parts of the workspace, for repositories mixing old and new code; the first profile matching a file applies, otherwise its pragma.",
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "additionalProperties": false,
+                    "required": ["paths", "solidity"],
+                    "properties": {
+                        "paths": {
+                            "description": "Glob patterns of the directories or files the profile applies to, matched at any depth.",
+                            "type": "array",
+                            "items": { "type": "string" },
+                        },
+                        "solidity": {
+                            "description": "Compiler version the files are written for, such as 0.4 or ^0.4.24.",
+                            "type": "string",
+                        },
+                    },
+                },
+                "default": [],
+            },
+            "include": {
+                "description": "When set, workspace commands only analyze files matching one of these glob patterns.",
+                "type": "array",
+                "items": { "type": "string" },
+                "default": [],
+            },
+            "exclude": {
+                "description": "Files matching one of these glob patterns are never analyzed, even if included.",
+                "type": "array",
+                "items": { "type": "string" },
+                "default": [],
+            },
+            "node_modules": {
+                "description": "In a Hardhat project, also analyzes the node_modules files its contracts import.",
+                "type": "boolean",
+                "default": false,
+            },
+        },
+    })
+}
+
+/// The current [`Config`], shared by the main loop and the workers. Workers
+/// read it when a request starts.
+#[derive(Debug, Default)]
+pub struct SharedConfig {
+    current: RwLock<Config>,
+}
+
+impl SharedConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            current: RwLock::new(config),
+        }
+    }
+
+    pub fn current(&self) -> Config {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replaces the config and returns the previous one if it changed.
+    pub fn update(&self, config: Config) -> Option<Config> {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        if *current == config {
+            return None;
+        }
+        Some(std::mem::replace(&mut current, config))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RuleConfig {
+    pub enabled: bool,
+    /// Replaces the severity the rule assigns to each of its findings.
+    pub severity: Option<Severity>,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            severity: None,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 pub struct MermaidConfig {
     pub no_chunk: bool,
     pub chunk_dir: PathBuf,
+    /// Mermaid theme, e.g. `dark` or `forest`.
+    pub theme: Option<String>,
+    /// Overrides Mermaid's render limit for large diagrams.
+    pub max_text_size: Option<u64>,
+    /// Additional `init` directive keys; these take precedence over
+    /// `theme` and `max_text_size`.
+    pub init: Map<String, Value>,
+    /// Writes chunk files that are byte-identical for identical inputs; set
+    /// from the command's top-level `deterministic` flag.
+    #[serde(skip)]
+    pub deterministic: bool,
+    /// Hash of the sources the diagram is rendered from; when set, a
+    /// chunk directory is written with its `manifest.json`.
+    #[serde(skip)]
+    pub input_hash: Option<String>,
 }
 
 impl Default for MermaidConfig {
     fn default() -> Self {
         Self {
             no_chunk: false,
-            chunk_dir: PathBuf::from("./traverse-output/sequence-diagrams/chunks/"),
+            chunk_dir: OutputLocation::default().chunk_dir(),
+            theme: None,
+            max_text_size: None,
+            init: Map::new(),
+            deterministic: false,
+            input_hash: None,
+        }
+    }
+}
+
+impl MermaidConfig {
+    /// Applies the server's output settings: a command that keeps the
+    /// default chunk directory writes under the configured output
+    /// directory, and chunking can be turned off server-wide.
+    pub fn with_output(mut self, location: &OutputLocation) -> Self {
+        if self.chunk_dir == OutputLocation::default().chunk_dir() {
+            self.chunk_dir = location.chunk_dir();
+        }
+        self.no_chunk |= location.no_chunk;
+        self
+    }
+
+    /// The `%%{init: ...}%%` line to prepend to generated diagrams, if any
+    /// directive options are set.
+    pub fn init_directive(&self) -> Option<String> {
+        let mut init = Map::new();
+        if let Some(theme) = &self.theme {
+            init.insert("theme".to_string(), Value::from(theme.as_str()));
         }
+        if let Some(max_text_size) = self.max_text_size {
+            init.insert("maxTextSize".to_string(), Value::from(max_text_size));
+        }
+        init.extend(self.init.clone());
+
+        if init.is_empty() {
+            None
+        } else {
+            Some(format!("%%{{init: {}}}%%", Value::Object(init)))
+        }
+    }
+
+    pub fn apply_directive(&self, diagram: &str) -> String {
+        match self.init_directive() {
+            Some(directive) => format!("{}\n{}", directive, diagram),
+            None => diagram.to_string(),
+        }
+    }
+}
+
+/// Graph-level DOT layout options, applied on top of the defaults emitted by
+/// `traverse_graph`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DotConfig {
+    /// Layout direction: `TB`, `LR`, `BT` or `RL`.
+    pub rankdir: Option<String>,
+    /// Edge routing, e.g. `ortho`, `polyline` or `false`.
+    pub splines: Option<String>,
+    /// Minimum space between nodes in the same rank, in inches.
+    pub nodesep: Option<f64>,
+    /// Merges parallel edges into a single spline.
+    pub concentrate: Option<bool>,
+    pub exclude_isolated_nodes: bool,
+    /// Draws repeated calls with a proportionally thicker pen.
+    pub scale_pen_width: bool,
+}
+
+impl DotConfig {
+    /// Attributes for an extra `graph [...]` statement; later statements
+    /// override the library's defaults.
+    pub fn graph_attributes(&self) -> Option<String> {
+        let mut attrs = Vec::new();
+        if let Some(rankdir) = &self.rankdir {
+            attrs.push(format!("rankdir=\"{}\"", escape_dot(rankdir)));
+        }
+        if let Some(splines) = &self.splines {
+            attrs.push(format!("splines=\"{}\"", escape_dot(splines)));
+        }
+        if let Some(nodesep) = self.nodesep {
+            attrs.push(format!("nodesep={}", nodesep));
+        }
+        if let Some(concentrate) = self.concentrate {
+            attrs.push(format!("concentrate={}", concentrate));
+        }
+        (!attrs.is_empty()).then(|| attrs.join(", "))
+    }
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Pruning applied to the call graph before any diagram is rendered.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct GraphFilter {
+    /// Keep only what is reachable from public and external functions of
+    /// concrete contracts.
+    pub only_external_entrypoints: bool,
+    /// Drop `view` and `pure` functions.
+    pub hide_view_functions: bool,
+    /// Drop calls to compiler-generated getters of public state variables.
+    pub hide_getters: bool,
+}
+
+impl GraphFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Glob patterns choosing which source files a workspace command analyzes,
+/// on top of the dependency and build directories that are always skipped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct FileFilter {
+    /// When set, only files matching one of these are analyzed.
+    pub include: Vec<String>,
+    /// Files matching one of these are never analyzed, even if included.
+    pub exclude: Vec<String>,
+    /// In a Hardhat project, also analyzes the `node_modules` files its
+    /// contracts import.
+    #[serde(alias = "nodeModules")]
+    pub node_modules: bool,
+}
+
+impl FileFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn has_patterns(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
     }
 }
@@ -0,0 +1,128 @@
+//! Off-chain consumers of contract events.
+//!
+//! A consumers file maps named off-chain components, such as subgraph
+//! handlers or keeper bots, to the events they listen to. Architecture
+//! diagrams draw each consumer as an external participant fed by the
+//! contracts that emit its events, so the picture covers the whole system
+//! rather than stopping at the chain.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, NodeType};
+
+/// Read from the workspace when the command names no consumers file.
+pub const CONSUMERS_FILE: &str = ".traverse/consumers.json";
+
+/// Consumers keyed by name.
+pub type Consumers = BTreeMap<String, Consumer>;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Consumer {
+    /// What the consumer is, e.g. `subgraph` or `bot`; shown in its label.
+    pub kind: Option<String>,
+    /// `Event` matches the event from any contract, `Contract.Event` only
+    /// from that contract.
+    pub events: Vec<String>,
+}
+
+impl Consumer {
+    fn listens_to(&self, contract: &str, event: &str) -> bool {
+        self.events
+            .iter()
+            .any(|pattern| match pattern.split_once('.') {
+                Some((c, e)) => c == contract && e == event,
+                None => pattern == event,
+            })
+    }
+}
+
+/// One emitted event delivered to one consumer.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Subscription {
+    pub contract: String,
+    pub event: String,
+    pub consumer: String,
+}
+
+/// Loads `file`, or the workspace's [`CONSUMERS_FILE`] if there is one.
+pub fn load(workspace_folder: &Path, file: Option<&Path>) -> Result<Consumers> {
+    let path = match file {
+        Some(file) => workspace_folder.join(file),
+        None => {
+            let path = workspace_folder.join(CONSUMERS_FILE);
+            if !path.is_file() {
+                return Ok(Consumers::new());
+            }
+            path
+        }
+    };
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read consumers file {}", path.display()))?;
+    serde_json::from_str(&text)
+        .with_context(|| format!("Invalid consumers file {}", path.display()))
+}
+
+/// The consumer's diagram label, e.g. `VaultSubgraph (subgraph)`.
+pub fn label(name: &str, consumer: &Consumer) -> String {
+    match &consumer.kind {
+        Some(kind) => format!("{} ({})", name, kind),
+        None => name.to_string(),
+    }
+}
+
+/// `(contract, event)` pairs for every `emit` in the call graph.
+pub fn emitted_events(graph: &CallGraph) -> BTreeSet<(String, String)> {
+    graph
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let event = edge.event_name.as_ref()?;
+            let source = graph.nodes.get(edge.source_node_id)?;
+            if source.node_type == NodeType::Evm {
+                return None;
+            }
+            let contract = source.contract_name.as_deref().unwrap_or("Global");
+            Some((contract.to_string(), event.clone()))
+        })
+        .collect()
+}
+
+/// Every emitted event matched to each consumer listening to it.
+pub fn subscriptions(graph: &CallGraph, consumers: &Consumers) -> Vec<Subscription> {
+    let mut subscriptions = Vec::new();
+    for (contract, event) in emitted_events(graph) {
+        for (name, consumer) in consumers {
+            if consumer.listens_to(&contract, &event) {
+                subscriptions.push(Subscription {
+                    contract: contract.clone(),
+                    event: event.clone(),
+                    consumer: name.clone(),
+                });
+            }
+        }
+    }
+    subscriptions.sort();
+    subscriptions
+}
+
+/// `Consumer: pattern` entries that match no emitted event, which usually
+/// means a typo or a renamed event.
+pub fn unmatched(graph: &CallGraph, consumers: &Consumers) -> Vec<String> {
+    let emitted = emitted_events(graph);
+    let mut unmatched = Vec::new();
+    for (name, consumer) in consumers {
+        for pattern in &consumer.events {
+            let single = Consumer {
+                kind: None,
+                events: vec![pattern.clone()],
+            };
+            if !emitted.iter().any(|(c, e)| single.listens_to(c, e)) {
+                unmatched.push(format!("{}: {}", name, pattern));
+            }
+        }
+    }
+    unmatched
+}
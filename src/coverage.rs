@@ -0,0 +1,202 @@
+//! Test coverage overlaid on the call graph.
+//!
+//! Reads function hit counts from an lcov file, as written by
+//! `forge coverage --report lcov`, and attaches them to the function nodes
+//! of a call graph. Uncovered functions reachable from an external entry
+//! point are the ones a review should look at first, so the DOT rendering
+//! fills them red and covered functions green.
+
+use crate::analysis::model::SourceModel;
+use crate::graph_filter;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use traverse_graph::cg::{CallGraph, NodeType};
+
+const COVERED_COLOR: &str = "palegreen";
+const UNCOVERED_COLOR: &str = "red";
+
+/// Execution count of one function, as recorded in an lcov file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionHits {
+    pub file: PathBuf,
+    /// Set when the name is qualified, as Foundry writes `Counter.increment`.
+    pub contract: Option<String>,
+    pub function: String,
+    pub line: usize,
+    pub hits: u64,
+}
+
+/// Coverage of one function node of the call graph.
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeCoverage {
+    #[serde(skip)]
+    pub node_id: usize,
+    pub contract: Option<String>,
+    pub function: String,
+    /// `None` when the coverage file has no record of the function.
+    pub hits: Option<u64>,
+    /// Reachable from a public or external function of a concrete contract.
+    pub externally_reachable: bool,
+}
+
+impl NodeCoverage {
+    fn color(&self) -> Option<&'static str> {
+        match self.hits {
+            Some(0) if self.externally_reachable => Some(UNCOVERED_COLOR),
+            Some(hits) if hits > 0 => Some(COVERED_COLOR),
+            _ => None,
+        }
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<FunctionHits>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read coverage file {}", path.display()))?;
+    Ok(parse_lcov(&text))
+}
+
+/// Function records of an lcov tracefile. Both `FN:<line>,<name>` and the
+/// lcov 2 form `FN:<line>,<end line>,<name>` are accepted.
+pub fn parse_lcov(text: &str) -> Vec<FunctionHits> {
+    let mut records = Vec::new();
+    let mut file = PathBuf::new();
+    let mut lines: Vec<(String, usize)> = Vec::new();
+    let mut hits: HashMap<String, u64> = HashMap::new();
+
+    let mut flush = |file: &Path, lines: &mut Vec<(String, usize)>, hits: &mut HashMap<_, _>| {
+        for (name, line) in lines.drain(..) {
+            let count = hits.get(&name).copied().unwrap_or(0);
+            let (contract, function) = match name.rsplit_once('.') {
+                Some((contract, function)) => (Some(contract.to_string()), function.to_string()),
+                None => (None, name),
+            };
+            records.push(FunctionHits {
+                file: file.to_path_buf(),
+                contract,
+                function,
+                line,
+                hits: count,
+            });
+        }
+        hits.clear();
+    };
+
+    for line in text.lines().map(str::trim) {
+        if let Some(path) = line.strip_prefix("SF:") {
+            file = PathBuf::from(path);
+        } else if let Some(rest) = line.strip_prefix("FN:") {
+            let fields: Vec<&str> = rest.split(',').collect();
+            let (Some(start), Some(name)) = (fields.first(), fields.last()) else {
+                continue;
+            };
+            if let Ok(start) = start.parse() {
+                lines.push((name.to_string(), start));
+            }
+        } else if let Some(rest) = line.strip_prefix("FNDA:") {
+            if let Some((count, name)) = rest.split_once(',') {
+                if let Ok(count) = count.parse::<u64>() {
+                    *hits.entry(name.to_string()).or_default() += count;
+                }
+            }
+        } else if line == "end_of_record" {
+            flush(&file, &mut lines, &mut hits);
+        }
+    }
+    flush(&file, &mut lines, &mut hits);
+    records
+}
+
+/// Coverage of every function, constructor and modifier node of `graph`.
+/// Unqualified records are attributed to the contract of `model` that
+/// encloses their line; overloads share a node, so their hits are summed.
+pub fn overlay(
+    graph: &CallGraph,
+    records: &[FunctionHits],
+    model: &SourceModel,
+) -> Vec<NodeCoverage> {
+    let mut hits: HashMap<(String, String), u64> = HashMap::new();
+    for record in records {
+        let Some(contract) = record
+            .contract
+            .clone()
+            .or_else(|| enclosing_contract(model, &record.file, record.line))
+        else {
+            continue;
+        };
+        let function = if record.function == "constructor" {
+            contract.clone()
+        } else {
+            record.function.clone()
+        };
+        *hits.entry((contract, function)).or_default() += record.hits;
+    }
+
+    let reachable =
+        graph_filter::reachable_from_entrypoints(graph, &HashSet::new(), &HashSet::new());
+    graph
+        .nodes
+        .iter()
+        .filter(|n| {
+            matches!(
+                n.node_type,
+                NodeType::Function | NodeType::Constructor | NodeType::Modifier
+            )
+        })
+        .map(|node| NodeCoverage {
+            node_id: node.id,
+            contract: node.contract_name.clone(),
+            function: node.name.clone(),
+            hits: node
+                .contract_name
+                .clone()
+                .and_then(|contract| hits.get(&(contract, node.name.clone())).copied()),
+            externally_reachable: reachable.contains(&node.id),
+        })
+        .collect()
+}
+
+/// The last contract declared in `file` at or before `line`.
+fn enclosing_contract(model: &SourceModel, file: &Path, line: usize) -> Option<String> {
+    let file: PathBuf = file
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    model
+        .contracts
+        .iter()
+        .filter(|c| c.file.ends_with(&file) && c.line <= line)
+        .max_by_key(|c| c.line)
+        .map(|c| c.name.clone())
+}
+
+/// Replaces the fill color of covered and of uncovered, externally
+/// reachable nodes in `dot`.
+pub fn color_dot(dot: &str, coverage: &[NodeCoverage]) -> String {
+    let colors: HashMap<usize, &str> = coverage
+        .iter()
+        .filter_map(|c| c.color().map(|color| (c.node_id, color)))
+        .collect();
+    let mut out = String::with_capacity(dot.len());
+    for line in dot.lines() {
+        let color = line
+            .trim_start()
+            .strip_prefix('n')
+            .and_then(|rest| rest.split_once(" ["))
+            .and_then(|(id, _)| id.parse::<usize>().ok())
+            .and_then(|id| colors.get(&id));
+        match (color, line.find("fillcolor=\"")) {
+            (Some(color), Some(start)) => {
+                let value = start + "fillcolor=\"".len();
+                let end = line[value..].find('"').map_or(line.len(), |i| value + i);
+                out.push_str(&line[..value]);
+                out.push_str(color);
+                out.push_str(&line[end..]);
+            }
+            _ => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,306 @@
+//! Diagnostics published to the client.
+//!
+//! Edits are debounced per document and only re-run the single-file quick
+//! checks on that document, keeping feedback well under the time it takes
+//! to finish typing a line. The workspace audit runs when a file is saved.
+
+use crate::analysis::{
+    self,
+    model::SourceUnit,
+    quick_checks,
+    suppression::{self, IgnoreFile},
+    Finding, Severity,
+};
+use crate::config::Config;
+use crate::file_filter;
+use crate::symbol_index::SymbolIndex;
+use crate::utils::find_solidity_files;
+use anyhow::Result;
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, NumberOrString, Position, PublishDiagnosticsParams, Range, Url,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Quiet period after the last edit before a document is re-checked.
+pub const DEBOUNCE: Duration = Duration::from_millis(150);
+const SOURCE: &str = "traverse";
+
+pub enum DiagnosticsEvent {
+    Changed {
+        uri: Url,
+        version: i32,
+        text: String,
+    },
+    Saved {
+        uri: Url,
+    },
+    Closed {
+        uri: Url,
+    },
+    /// Files changed on disk outside the editor.
+    FilesChanged,
+    /// Rule settings changed; every document is checked again.
+    ConfigChanged(Config),
+    Shutdown,
+}
+
+struct Document {
+    version: i32,
+    text: String,
+}
+
+pub struct DiagnosticsWorker {
+    config: Config,
+    workspace_root: Option<PathBuf>,
+    index: Arc<SymbolIndex>,
+    publish: Box<dyn FnMut(PublishDiagnosticsParams) + Send>,
+    documents: HashMap<Url, Document>,
+    /// Documents waiting for their debounce deadline.
+    pending: HashMap<Url, Instant>,
+    quick: HashMap<Url, Vec<Diagnostic>>,
+    audit: HashMap<Url, Vec<Diagnostic>>,
+}
+
+impl DiagnosticsWorker {
+    pub fn new(
+        config: Config,
+        workspace_root: Option<PathBuf>,
+        index: Arc<SymbolIndex>,
+        publish: impl FnMut(PublishDiagnosticsParams) + Send + 'static,
+    ) -> Self {
+        Self {
+            config,
+            workspace_root,
+            index,
+            publish: Box::new(publish),
+            documents: HashMap::new(),
+            pending: HashMap::new(),
+            quick: HashMap::new(),
+            audit: HashMap::new(),
+        }
+    }
+
+    pub fn run(mut self, rx: mpsc::Receiver<DiagnosticsEvent>) {
+        info!("Diagnostics worker started");
+
+        if let Some(root) = &self.workspace_root {
+            if let Err(e) = self.index.index_workspace(root) {
+                warn!("Failed to index workspace: {}", e);
+            }
+        }
+
+        loop {
+            let event = match self.pending.values().min() {
+                Some(deadline) => {
+                    match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                        Ok(event) => Some(event),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match rx.recv() {
+                    Ok(event) => Some(event),
+                    Err(_) => break,
+                },
+            };
+
+            match event {
+                Some(DiagnosticsEvent::Shutdown) => break,
+                Some(event) => self.handle(event),
+                None => {}
+            }
+            self.flush_due();
+        }
+
+        info!("Diagnostics worker shutting down");
+    }
+
+    fn handle(&mut self, event: DiagnosticsEvent) {
+        match event {
+            DiagnosticsEvent::Changed { uri, version, text } => {
+                self.documents
+                    .insert(uri.clone(), Document { version, text });
+                self.pending.insert(uri, Instant::now() + DEBOUNCE);
+            }
+            DiagnosticsEvent::Saved { uri } => {
+                self.pending.remove(&uri);
+                self.check_document(&uri);
+                self.publish(&uri);
+                self.run_workspace_audit();
+            }
+            DiagnosticsEvent::Closed { uri } => {
+                self.pending.remove(&uri);
+                self.documents.remove(&uri);
+                self.quick.remove(&uri);
+                self.publish(&uri);
+            }
+            DiagnosticsEvent::FilesChanged => self.run_workspace_audit(),
+            DiagnosticsEvent::ConfigChanged(config) => {
+                self.config = config;
+                let uris: Vec<Url> = self.documents.keys().cloned().collect();
+                for uri in &uris {
+                    self.pending.remove(uri);
+                    self.check_document(uri);
+                    self.publish(uri);
+                }
+                self.run_workspace_audit();
+            }
+            DiagnosticsEvent::Shutdown => {}
+        }
+    }
+
+    fn flush_due(&mut self) {
+        let now = Instant::now();
+        let due: Vec<Url> = self
+            .pending
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(uri, _)| uri.clone())
+            .collect();
+        for uri in due {
+            self.pending.remove(&uri);
+            self.check_document(&uri);
+            self.publish(&uri);
+            self.index.refresh(&uri);
+        }
+    }
+
+    fn check_document(&mut self, uri: &Url) {
+        let Some(document) = self.documents.get(uri) else {
+            return;
+        };
+        let unit = SourceUnit {
+            path: uri
+                .to_file_path()
+                .unwrap_or_else(|_| PathBuf::from(uri.path())),
+            content: document.text.clone(),
+        };
+        let started = Instant::now();
+        let diagnostics = match quick_checks::check(&unit, &self.config) {
+            Ok(findings) => findings
+                .iter()
+                .map(|f| to_diagnostic(f, &unit.content))
+                .collect(),
+            Err(e) => {
+                warn!("Quick checks failed for {}: {}", uri, e);
+                Vec::new()
+            }
+        };
+        debug!("Checked {} in {:?}", uri, started.elapsed());
+        self.quick.insert(uri.clone(), diagnostics);
+    }
+
+    fn run_workspace_audit(&mut self) {
+        let Some(root) = self.workspace_root.clone() else {
+            return;
+        };
+        let audit = match self.audit_workspace(&root) {
+            Ok(audit) => audit,
+            Err(e) => {
+                warn!("Workspace audit failed: {}", e);
+                return;
+            }
+        };
+        let previous = std::mem::replace(&mut self.audit, audit);
+        let mut uris: Vec<Url> = previous.into_keys().collect();
+        uris.extend(self.audit.keys().cloned());
+        uris.sort();
+        uris.dedup();
+        for uri in uris {
+            self.publish(&uri);
+        }
+    }
+
+    fn audit_workspace(&self, root: &std::path::Path) -> Result<HashMap<Url, Vec<Diagnostic>>> {
+        let mut sources = Vec::new();
+        let mut files = find_solidity_files(root)?;
+        file_filter::retain(&mut files, &self.config.files, root);
+        for uri in files {
+            let content = match self.documents.get(&uri) {
+                Some(document) => document.text.clone(),
+                None => match uri
+                    .to_file_path()
+                    .ok()
+                    .and_then(|path| std::fs::read_to_string(path).ok())
+                {
+                    Some(content) => content,
+                    None => continue,
+                },
+            };
+            sources.push(SourceUnit {
+                path: uri.to_file_path().unwrap_or_default(),
+                content,
+            });
+        }
+
+        let mut findings = analysis::run_configured_audit(&sources, &self.config)?;
+        suppression::apply(&mut findings, &sources, &IgnoreFile::load(root), root);
+
+        let mut audit: HashMap<Url, Vec<Diagnostic>> = HashMap::new();
+        for finding in &findings {
+            let Some(unit) = sources
+                .iter()
+                .find(|u| Some(&u.path) == finding.file.as_ref())
+            else {
+                continue;
+            };
+            let Ok(uri) = Url::from_file_path(&unit.path) else {
+                continue;
+            };
+            audit
+                .entry(uri)
+                .or_default()
+                .push(to_diagnostic(finding, &unit.content));
+        }
+        Ok(audit)
+    }
+
+    /// Sends the quick-check and audit diagnostics of `uri` together, since
+    /// each publish replaces everything the client shows for the document.
+    fn publish(&mut self, uri: &Url) {
+        let diagnostics = self
+            .quick
+            .get(uri)
+            .into_iter()
+            .chain(self.audit.get(uri))
+            .flatten()
+            .cloned()
+            .collect();
+        (self.publish)(PublishDiagnosticsParams {
+            uri: uri.clone(),
+            diagnostics,
+            version: self.documents.get(uri).map(|d| d.version),
+        });
+    }
+}
+
+/// Spans the finding's line, excluding indentation, in UTF-16 columns.
+fn to_diagnostic(finding: &Finding, text: &str) -> Diagnostic {
+    let row = finding.line.unwrap_or(1).saturating_sub(1);
+    let line = text.lines().nth(row).unwrap_or_default();
+    let indent = line.len() - line.trim_start().len();
+    let start = line[..indent].encode_utf16().count() as u32;
+    let end = line.trim_end().encode_utf16().count() as u32;
+
+    Diagnostic {
+        range: Range::new(
+            Position::new(row as u32, start),
+            Position::new(row as u32, end.max(start)),
+        ),
+        severity: Some(match finding.severity {
+            Severity::High => DiagnosticSeverity::ERROR,
+            Severity::Medium => DiagnosticSeverity::WARNING,
+            Severity::Low => DiagnosticSeverity::INFORMATION,
+            Severity::Info => DiagnosticSeverity::HINT,
+        }),
+        code: Some(NumberOrString::String(finding.rule.to_string())),
+        source: Some(SOURCE.to_string()),
+        message: finding.message.clone(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,237 @@
+//! Environment checks behind `traverse.doctor`.
+//!
+//! When a command seems to do nothing, the cause is usually outside the
+//! server: no workspace folder was sent, a remapping points at a library
+//! that was never installed, the output directory is read-only, or the
+//! client does not show progress. Each check reports one of these with a
+//! status a client can render as a checklist. The same checks run once at
+//! startup, and problems are logged.
+
+use crate::foundry::{self, FoundryProject};
+use crate::graph_cache::CacheStats;
+use crate::hardhat::{HardhatProject, NODE_MODULES};
+use lsp_types::ClientCapabilities;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File written and removed to test that the output directory is writable.
+const PROBE_FILE: &str = ".traverse-doctor";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    /// Works, with something missing or degraded.
+    Warning,
+    /// Commands will fail or produce nothing.
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    /// No check failed; warnings are allowed.
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
+/// What the checks look at.
+pub struct Environment<'a> {
+    pub folders: &'a [PathBuf],
+    pub output_dir: &'a Path,
+    pub cache: CacheStats,
+    pub capabilities: &'a ClientCapabilities,
+}
+
+pub fn run(env: &Environment) -> Report {
+    let checks = vec![
+        workspace(env.folders),
+        remappings(env.folders),
+        output_dir(env.output_dir),
+        cache(&env.cache),
+        client_capabilities(env.capabilities),
+    ];
+    Report {
+        ok: checks.iter().all(|check| check.status != Status::Error),
+        checks,
+    }
+}
+
+fn workspace(folders: &[PathBuf]) -> Check {
+    const NAME: &str = "workspace";
+    if folders.is_empty() {
+        return Check::new(
+            NAME,
+            Status::Error,
+            "No workspace folder; open a folder rather than a single file",
+        );
+    }
+    let missing: Vec<String> = folders
+        .iter()
+        .filter(|folder| !folder.is_dir())
+        .map(|folder| folder.display().to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Check::new(
+            NAME,
+            Status::Error,
+            format!("Not a directory: {}", missing.join(", ")),
+        );
+    }
+    let described: Vec<String> = folders
+        .iter()
+        .map(|folder| match project_kind(folder) {
+            Some(kind) => format!("{} ({})", folder.display(), kind),
+            None => folder.display().to_string(),
+        })
+        .collect();
+    Check::new(NAME, Status::Ok, described.join(", "))
+}
+
+fn project_kind(folder: &Path) -> Option<&'static str> {
+    if matches!(FoundryProject::detect(folder), Ok(Some(_))) {
+        Some("Foundry")
+    } else if matches!(HardhatProject::detect(folder), Ok(Some(_))) {
+        Some("Hardhat")
+    } else {
+        None
+    }
+}
+
+/// Every remapping target exists, and Hardhat projects have their packages
+/// installed.
+fn remappings(folders: &[PathBuf]) -> Check {
+    const NAME: &str = "remappings";
+    let mut problems = Vec::new();
+    let mut resolved = 0;
+    for folder in folders {
+        let remappings = match foundry::remappings(folder) {
+            Ok(remappings) => remappings,
+            Err(e) => return Check::new(NAME, Status::Error, format!("{:#}", e)),
+        };
+        for remapping in remappings {
+            if folder.join(&remapping.target).exists() {
+                resolved += 1;
+            } else {
+                problems.push(format!(
+                    "{}={} ({} does not exist)",
+                    remapping.prefix,
+                    remapping.target,
+                    folder.join(&remapping.target).display()
+                ));
+            }
+        }
+        if matches!(HardhatProject::detect(folder), Ok(Some(_)))
+            && !folder.join(NODE_MODULES).is_dir()
+        {
+            problems.push(format!(
+                "{} has no {}; run the package manager's install",
+                folder.display(),
+                NODE_MODULES
+            ));
+        }
+    }
+    if problems.is_empty() {
+        Check::new(NAME, Status::Ok, format!("{} remappings resolve", resolved))
+    } else {
+        Check::new(NAME, Status::Warning, problems.join("; "))
+    }
+}
+
+/// Writes and removes a probe file. A missing directory is created for the
+/// probe and removed again, so the check leaves nothing behind.
+fn output_dir(dir: &Path) -> Check {
+    const NAME: &str = "output_dir";
+    let created = dir
+        .ancestors()
+        .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+        .last()
+        .map(Path::to_path_buf);
+    let probe = dir.join(PROBE_FILE);
+    let result = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe));
+    if let Some(created) = created {
+        let _ = fs::remove_dir_all(created);
+    }
+    match result {
+        Ok(()) => Check::new(NAME, Status::Ok, format!("{} is writable", dir.display())),
+        Err(e) => Check::new(
+            NAME,
+            Status::Error,
+            format!("{} is not writable: {}", dir.display(), e),
+        ),
+    }
+}
+
+fn cache(stats: &CacheStats) -> Check {
+    const NAME: &str = "cache";
+    const MB: usize = 1024 * 1024;
+    if stats.budget_bytes == 0 {
+        return Check::new(
+            NAME,
+            Status::Warning,
+            "Disabled; every command rebuilds its call graph",
+        );
+    }
+    let bytes = stats.bytes + stats.parse_tree_bytes;
+    let detail = format!(
+        "{} graphs and {} parse trees, {} of {} MB, {} hits, {} misses",
+        stats.entries,
+        stats.parse_trees,
+        bytes.div_ceil(MB),
+        stats.budget_bytes / MB,
+        stats.hits,
+        stats.misses
+    );
+    if bytes > stats.budget_bytes {
+        return Check::new(NAME, Status::Error, format!("Over budget: {}", detail));
+    }
+    Check::new(NAME, Status::Ok, detail)
+}
+
+/// Capabilities whose absence makes the server look unresponsive.
+fn client_capabilities(capabilities: &ClientCapabilities) -> Check {
+    const NAME: &str = "client_capabilities";
+    let window = capabilities.window.as_ref();
+    let workspace = capabilities.workspace.as_ref();
+    let mut missing = Vec::new();
+    if window.and_then(|w| w.work_done_progress) != Some(true) {
+        missing.push(
+            "window.workDoneProgress: commands report progress only on a \
+             workDoneToken the request passes",
+        );
+    }
+    if workspace
+        .and_then(|w| w.did_change_watched_files.as_ref())
+        .and_then(|w| w.dynamic_registration)
+        != Some(true)
+    {
+        missing.push(
+            "workspace.didChangeWatchedFiles.dynamicRegistration: \
+             edits made outside the editor are not noticed",
+        );
+    }
+    if missing.is_empty() {
+        Check::new(NAME, Status::Ok, "Progress and file watching are supported")
+    } else {
+        Check::new(NAME, Status::Warning, missing.join("; "))
+    }
+}
@@ -0,0 +1,174 @@
+//! Text of the documents open in the editor.
+//!
+//! Analysis reads sources through the store so diagrams and reports reflect
+//! unsaved edits; documents the editor has not opened are read from disk.
+//! Clients send incremental edits, which are applied through a line index
+//! kept alongside each document's text. The same edits are applied to the
+//! document's cached parse tree, so its next parse only re-reads the edited
+//! spans.
+
+use crate::graph_cache::GraphCache;
+use crate::utils::byte_column;
+use anyhow::{bail, Context, Result};
+use dashmap::DashMap;
+use lsp_types::{Position, TextDocumentContentChangeEvent, Url};
+use std::sync::Arc;
+use tree_sitter::{InputEdit, Point};
+
+/// A document's text and the byte offset at which each line starts.
+struct Document {
+    version: i32,
+    text: String,
+    line_starts: Vec<usize>,
+}
+
+impl Document {
+    fn new(version: i32, text: String) -> Self {
+        let mut document = Document {
+            version,
+            text,
+            line_starts: vec![0],
+        };
+        document.reindex_from(0);
+        document
+    }
+
+    /// Applies one change and returns it as a tree edit; a change without a
+    /// range replaces the text.
+    fn apply(&mut self, change: TextDocumentContentChangeEvent) -> InputEdit {
+        let (start, end, line) = match change.range {
+            Some(range) => {
+                let start = self.offset(range.start);
+                let end = self.offset(range.end).max(start);
+                // Lines before the edit keep their offsets.
+                let line = (range.start.line as usize).min(self.line_starts.len() - 1);
+                (start, end, line)
+            }
+            None => (0, self.text.len(), 0),
+        };
+        let start_position = self.point(start);
+        let old_end_position = self.point(end);
+        let new_end = start + change.text.len();
+        self.text.replace_range(start..end, &change.text);
+        self.reindex_from(line);
+        InputEdit {
+            start_byte: start,
+            old_end_byte: end,
+            new_end_byte: new_end,
+            start_position,
+            old_end_position,
+            new_end_position: self.point(new_end),
+        }
+    }
+
+    /// Row and byte column of the byte offset `byte`.
+    fn point(&self, byte: usize) -> Point {
+        let row = self.line_starts.partition_point(|&start| start <= byte) - 1;
+        Point {
+            row,
+            column: byte - self.line_starts[row],
+        }
+    }
+
+    /// Byte offset of `position`, whose character counts UTF-16 code units.
+    /// Positions past the end of a line or of the text are clamped.
+    fn offset(&self, position: Position) -> usize {
+        let Some(&line_start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let line_end = self
+            .line_starts
+            .get(position.line as usize + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        line_start + byte_column(&self.text[line_start..line_end], position.character)
+    }
+
+    /// Recomputes the starts of the lines after `line`, whose own start is
+    /// unchanged.
+    fn reindex_from(&mut self, line: usize) {
+        let from = self.line_starts[line];
+        self.line_starts.truncate(line + 1);
+        self.line_starts.extend(
+            self.text[from..]
+                .match_indices('\n')
+                .map(|(index, _)| from + index + 1),
+        );
+    }
+}
+
+#[derive(Default)]
+pub struct DocumentStore {
+    documents: DashMap<Url, Document>,
+    trees: Option<Arc<GraphCache>>,
+}
+
+impl DocumentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies changes to the parse trees cached in `trees` as well.
+    pub fn with_parse_trees(mut self, trees: Arc<GraphCache>) -> Self {
+        self.trees = Some(trees);
+        self
+    }
+
+    /// Records the full text of `uri`, as sent on open. Text older than the
+    /// recorded version is ignored; returns whether it was applied.
+    pub fn update(&self, uri: Url, version: i32, text: String) -> bool {
+        if let Some(document) = self.documents.get(&uri) {
+            if version < document.version {
+                return false;
+            }
+        }
+        self.documents.insert(uri, Document::new(version, text));
+        true
+    }
+
+    /// Applies `changes` in order and returns the resulting text, or `None`
+    /// when `version` is not newer than the recorded one. Fails for a
+    /// document that was never opened.
+    pub fn apply_changes(
+        &self,
+        uri: &Url,
+        version: i32,
+        changes: Vec<TextDocumentContentChangeEvent>,
+    ) -> Result<Option<String>> {
+        let Some(mut document) = self.documents.get_mut(uri) else {
+            bail!("Change to {} before it was opened", uri);
+        };
+        if version <= document.version {
+            return Ok(None);
+        }
+        let before = self.trees.as_ref().map(|_| document.text.clone());
+        let edits: Vec<InputEdit> = changes
+            .into_iter()
+            .map(|change| document.apply(change))
+            .collect();
+        document.version = version;
+        if let (Some(trees), Some(before), Ok(path)) = (&self.trees, before, uri.to_file_path()) {
+            trees.edit_tree(&path, &before, &edits, &document.text);
+        }
+        Ok(Some(document.text.clone()))
+    }
+
+    pub fn close(&self, uri: &Url) {
+        self.documents.remove(uri);
+    }
+
+    pub fn is_open(&self, uri: &Url) -> bool {
+        self.documents.contains_key(uri)
+    }
+
+    /// The editor's text for `uri`, or the file on disk when it is not open.
+    pub fn read(&self, uri: &Url) -> Result<String> {
+        if let Some(document) = self.documents.get(uri) {
+            return Ok(document.text.clone());
+        }
+        let path = uri
+            .to_file_path()
+            .map_err(|_| anyhow::anyhow!("Invalid URI"))?;
+        std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))
+    }
+}
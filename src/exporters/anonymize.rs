@@ -0,0 +1,267 @@
+//! Pseudonymized call graphs for sharing.
+//!
+//! Contract, function, modifier, variable, event and type names are replaced
+//! with pseudonyms like `Contract3` or `Function12`, and source snippets
+//! (argument texts, returned values, `require` conditions and messages) are
+//! dropped, so the graph's structure can be shown to outsiders without its
+//! identifiers. The original names are kept in a local mapping file that is
+//! read back on the next export, so a name keeps its pseudonym from one
+//! export to the next.
+
+use crate::output::{path_lock, write_atomic};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, Node, NodeType};
+
+/// Mapping file written next to the exported artifacts. It holds the
+/// original names and must not be shared along with them.
+pub const MAPPING_FILE: &str = "anonymization-map.json";
+
+/// Function names that say nothing about the project.
+const BUILTIN_FUNCTIONS: [&str; 3] = ["constructor", "fallback", "receive"];
+
+/// Original name to pseudonym, by kind of name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pseudonyms {
+    #[serde(default)]
+    pub contracts: BTreeMap<String, String>,
+    #[serde(default)]
+    pub functions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub modifiers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    #[serde(default)]
+    pub events: BTreeMap<String, String>,
+    /// Structs, enums and other user-defined types.
+    #[serde(default)]
+    pub types: BTreeMap<String, String>,
+}
+
+impl Pseudonyms {
+    /// The mapping saved at `path`, or an empty one when there is none.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// `graph` anonymized with the mapping saved at `path`, which is saved
+    /// back with the pseudonyms added. Exports running at once share the
+    /// mapping, so the whole update holds the path's lock.
+    pub fn anonymize_with(path: &Path, graph: &CallGraph) -> Result<CallGraph> {
+        let lock = path_lock(path);
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut pseudonyms = Self::load(path)?;
+        let anonymized = pseudonyms.anonymize(graph);
+        pseudonyms.save(path)?;
+        Ok(anonymized)
+    }
+
+    /// A copy of `graph` with every identifier replaced, adding pseudonyms
+    /// for names not seen before in order of first appearance.
+    pub fn anonymize(&mut self, graph: &CallGraph) -> CallGraph {
+        for node in &graph.nodes {
+            if let Some(contract) = &node.contract_name {
+                assign(&mut self.contracts, contract, "Contract");
+            }
+            match node.node_type {
+                NodeType::Interface | NodeType::Library => {
+                    assign(&mut self.contracts, &node.name, "Contract");
+                }
+                NodeType::Function if !BUILTIN_FUNCTIONS.contains(&node.name.as_str()) => {
+                    assign(&mut self.functions, &node.name, "Function");
+                }
+                NodeType::Modifier => assign(&mut self.modifiers, &node.name, "Modifier"),
+                NodeType::StorageVariable => {
+                    assign(&mut self.variables, &node.name, "Variable");
+                }
+                _ => {}
+            }
+            for parameter in &node.parameters {
+                assign(&mut self.variables, &parameter.name, "Variable");
+            }
+        }
+        for edge in &graph.edges {
+            if let Some(event) = &edge.event_name {
+                assign(&mut self.events, event, "Event");
+            }
+        }
+        // Types last, so contract names used as types map to the contract's
+        // pseudonym.
+        let type_names = graph
+            .nodes
+            .iter()
+            .flat_map(|n| {
+                n.parameters
+                    .iter()
+                    .map(|p| p.param_type.as_str())
+                    .chain(n.declared_return_type.as_deref())
+            })
+            .chain(
+                graph
+                    .edges
+                    .iter()
+                    .filter_map(|e| e.declared_return_type.as_deref()),
+            );
+        for type_name in type_names {
+            for token in identifiers(type_name) {
+                if !is_elementary(token) && !self.contracts.contains_key(token) {
+                    assign(&mut self.types, token, "Type");
+                }
+            }
+        }
+
+        let mut anonymized = CallGraph::new();
+        for node in &graph.nodes {
+            let name = self.node_name(node);
+            let contract = node
+                .contract_name
+                .as_ref()
+                .map(|c| rename(&self.contracts, c));
+            let id = anonymized.add_node(
+                name.clone(),
+                node.node_type.clone(),
+                contract.clone(),
+                node.visibility.clone(),
+                node.span,
+            );
+            let mut parameters = node.parameters.clone();
+            for parameter in &mut parameters {
+                parameter.name = rename(&self.variables, &parameter.name);
+                parameter.param_type = self.rename_type(&parameter.param_type);
+                parameter.description = None;
+            }
+            anonymized.nodes[id] = Node {
+                id,
+                name,
+                contract_name: contract,
+                declared_return_type: node
+                    .declared_return_type
+                    .as_deref()
+                    .map(|t| self.rename_type(t)),
+                parameters,
+                revert_message: None,
+                condition_expression: None,
+                ..node.clone()
+            };
+        }
+        for edge in &graph.edges {
+            let mut edge = edge.clone();
+            edge.returned_value = None;
+            edge.argument_names = None;
+            edge.event_name = edge.event_name.map(|e| rename(&self.events, &e));
+            edge.declared_return_type = edge
+                .declared_return_type
+                .as_deref()
+                .map(|t| self.rename_type(t));
+            anonymized.edges.push(edge);
+        }
+        anonymized
+    }
+
+    fn node_name(&self, node: &Node) -> String {
+        let names = match node.node_type {
+            // Implicit constructors are named after their contract.
+            NodeType::Interface | NodeType::Library | NodeType::Constructor => &self.contracts,
+            NodeType::Function => &self.functions,
+            NodeType::Modifier => &self.modifiers,
+            NodeType::StorageVariable => &self.variables,
+            // Synthetic nodes are named after what they stand for, like
+            // `EVM` or `IfCondition_120`.
+            _ => return node.name.clone(),
+        };
+        rename(names, &node.name)
+    }
+
+    /// `mapping(address => Position)` becomes `mapping(address => Type1)`.
+    fn rename_type(&self, type_name: &str) -> String {
+        let mut renamed = String::new();
+        let mut rest = type_name;
+        while let Some(start) = rest.find(is_identifier_start) {
+            renamed.push_str(&rest[..start]);
+            let end = rest[start..]
+                .find(|c: char| !is_identifier_char(c))
+                .map_or(rest.len(), |len| start + len);
+            let token = &rest[start..end];
+            let replacement = self.contracts.get(token).or_else(|| self.types.get(token));
+            renamed.push_str(replacement.map_or(token, String::as_str));
+            rest = &rest[end..];
+        }
+        renamed.push_str(rest);
+        renamed
+    }
+}
+
+fn assign(names: &mut BTreeMap<String, String>, name: &str, prefix: &str) {
+    if name.is_empty() || names.contains_key(name) {
+        return;
+    }
+    // Skip pseudonyms taken in a hand-edited mapping file.
+    let mut n = names.len() + 1;
+    while names.values().any(|p| *p == format!("{}{}", prefix, n)) {
+        n += 1;
+    }
+    names.insert(name.to_string(), format!("{}{}", prefix, n));
+}
+
+/// Unnamed parameters and builtins stay as they are.
+fn rename(names: &BTreeMap<String, String>, name: &str) -> String {
+    names.get(name).cloned().unwrap_or_else(|| name.to_string())
+}
+
+fn identifiers(type_name: &str) -> impl Iterator<Item = &str> {
+    type_name
+        .split(|c: char| !is_identifier_char(c))
+        .filter(|token| token.starts_with(is_identifier_start))
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Built-in types and the keywords that appear in type strings.
+fn is_elementary(token: &str) -> bool {
+    let sized = |prefix: &str| {
+        token
+            .strip_prefix(prefix)
+            .is_some_and(|size| size.chars().all(|c| c.is_ascii_digit() || c == 'x'))
+    };
+    matches!(
+        token,
+        "address"
+            | "bool"
+            | "string"
+            | "byte"
+            | "mapping"
+            | "payable"
+            | "memory"
+            | "storage"
+            | "calldata"
+            | "function"
+            | "returns"
+            | "external"
+            | "internal"
+            | "view"
+            | "pure"
+    ) || sized("uint")
+        || sized("int")
+        || sized("bytes")
+        || sized("ufixed")
+        || sized("fixed")
+}
@@ -0,0 +1,110 @@
+//! Cypher and `neo4j-admin import` CSV export.
+//!
+//! Every call graph node becomes a `:Node` labelled with its node type, and
+//! every edge a relationship named after its edge type, so large protocols
+//! can be explored with graph queries instead of rendered diagrams.
+
+use traverse_graph::cg::{CallGraph, Edge, EdgeType, Node};
+
+pub fn to_cypher(graph: &CallGraph) -> String {
+    let mut out = String::new();
+    out.push_str("CREATE INDEX traverse_node_id IF NOT EXISTS FOR (n:Node) ON (n.id);\n");
+
+    for node in &graph.nodes {
+        let mut props = vec![
+            format!("id: {}", node.id),
+            format!("name: '{}'", escape_cypher(&node.name)),
+            format!("visibility: '{}'", visibility(node)),
+        ];
+        if let Some(contract) = &node.contract_name {
+            props.push(format!("contract: '{}'", escape_cypher(contract)));
+        }
+        out.push_str(&format!(
+            "CREATE (:Node:{} {{{}}});\n",
+            label(node),
+            props.join(", ")
+        ));
+    }
+
+    for edge in &graph.edges {
+        let mut props = vec![format!("sequence: {}", edge.sequence_number)];
+        if let Some(event) = &edge.event_name {
+            props.push(format!("event: '{}'", escape_cypher(event)));
+        }
+        out.push_str(&format!(
+            "MATCH (a:Node {{id: {}}}), (b:Node {{id: {}}}) CREATE (a)-[:{} {{{}}}]->(b);\n",
+            edge.source_node_id,
+            edge.target_node_id,
+            relationship(edge),
+            props.join(", ")
+        ));
+    }
+
+    out
+}
+
+/// Node and relationship files for `neo4j-admin database import`.
+pub fn to_neo4j_csv(graph: &CallGraph) -> (String, String) {
+    let mut nodes = String::from("id:ID,name,contract,visibility,:LABEL\n");
+    for node in &graph.nodes {
+        nodes.push_str(&format!(
+            "{},{},{},{},Node;{}\n",
+            node.id,
+            escape_csv(&node.name),
+            escape_csv(node.contract_name.as_deref().unwrap_or("")),
+            visibility(node),
+            label(node)
+        ));
+    }
+
+    let mut relationships = String::from(":START_ID,:END_ID,:TYPE,sequence:int,event\n");
+    for edge in &graph.edges {
+        relationships.push_str(&format!(
+            "{},{},{},{},{}\n",
+            edge.source_node_id,
+            edge.target_node_id,
+            relationship(edge),
+            edge.sequence_number,
+            escape_csv(edge.event_name.as_deref().unwrap_or(""))
+        ));
+    }
+
+    (nodes, relationships)
+}
+
+fn label(node: &Node) -> String {
+    format!("{:?}", node.node_type)
+}
+
+fn visibility(node: &Node) -> String {
+    format!("{:?}", node.visibility).to_lowercase()
+}
+
+fn relationship(edge: &Edge) -> &'static str {
+    match edge.edge_type {
+        EdgeType::Call => "CALLS",
+        EdgeType::Return => "RETURNS_TO",
+        EdgeType::StorageRead => "READS",
+        EdgeType::StorageWrite => "WRITES",
+        EdgeType::Require => "REQUIRES",
+        EdgeType::IfConditionBranch => "IF_CONDITION",
+        EdgeType::ThenBranch => "THEN",
+        EdgeType::ElseBranch => "ELSE",
+        EdgeType::WhileConditionBranch => "WHILE_CONDITION",
+        EdgeType::WhileBodyBranch => "WHILE_BODY",
+        EdgeType::ForConditionBranch => "FOR_CONDITION",
+        EdgeType::ForBodyBranch => "FOR_BODY",
+    }
+}
+
+fn escape_cypher(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,323 @@
+//! Excalidraw scene export.
+//!
+//! Produces `.excalidraw` scenes for the contract architecture and the
+//! function-level call graph. Elements carry a precomputed layered layout, so
+//! scenes open ready to annotate instead of as a pile at the origin.
+
+use super::is_callable;
+use crate::consumers::{self, Consumers};
+use crate::output::{write_atomic, StagingDir};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use traverse_graph::cg::{CallGraph, EdgeType, Node};
+
+/// Under the output directory.
+pub const EXCALIDRAW_DIR: &str = "excalidraw";
+
+const BOX_HEIGHT: f64 = 60.0;
+const COLUMN_GAP: f64 = 140.0;
+const ROW_GAP: f64 = 40.0;
+
+/// Contract-level scene: one box per contract, one arrow per calling pair.
+/// Off-chain consumers get dashed boxes, fed by arrows labeled with the
+/// events they receive.
+pub fn architecture_scene(graph: &CallGraph, consumers: &Consumers) -> Value {
+    let mut labels = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for node in graph.nodes.iter().filter(|n| is_callable(n)) {
+        labels.insert(contract_of(node).to_string());
+    }
+    for (source, target) in call_pairs(graph) {
+        let (from, to) = (contract_of(source), contract_of(target));
+        if from != to {
+            edges.insert((from.to_string(), to.to_string()));
+        }
+    }
+
+    let mut external = BTreeSet::new();
+    let mut events: BTreeMap<(String, String), Vec<String>> = BTreeMap::new();
+    for subscription in consumers::subscriptions(graph, consumers) {
+        let consumer = consumers::label(&subscription.consumer, &consumers[&subscription.consumer]);
+        let edge = (subscription.contract, consumer.clone());
+        edges.insert(edge.clone());
+        events.entry(edge).or_default().push(subscription.event);
+        external.insert(consumer);
+    }
+    labels.extend(external.iter().cloned());
+    let edge_labels = events
+        .into_iter()
+        .map(|(edge, events)| (edge, events.join(", ")))
+        .collect();
+    scene(
+        &labels.into_iter().collect::<Vec<_>>(),
+        &edges,
+        &external,
+        &edge_labels,
+    )
+}
+
+/// Function-level scene: one box per function, one arrow per call.
+pub fn call_graph_scene(graph: &CallGraph) -> Value {
+    let labels: BTreeSet<String> = graph
+        .nodes
+        .iter()
+        .filter(|n| is_callable(n))
+        .map(qualified_name)
+        .collect();
+    let edges: BTreeSet<(String, String)> = call_pairs(graph)
+        .map(|(source, target)| (qualified_name(source), qualified_name(target)))
+        .collect();
+    scene(
+        &labels.into_iter().collect::<Vec<_>>(),
+        &edges,
+        &BTreeSet::new(),
+        &BTreeMap::new(),
+    )
+}
+
+/// Writes the scenes into a staging directory that replaces `dir` once both
+/// are written, so nothing from an earlier export is left beside them.
+pub fn write_scenes(graph: &CallGraph, consumers: &Consumers, dir: &Path) -> Result<Vec<PathBuf>> {
+    let staging = StagingDir::new(dir)?;
+    let mut file_names = Vec::new();
+    for (file_name, scene) in [
+        (
+            "architecture.excalidraw",
+            architecture_scene(graph, consumers),
+        ),
+        ("call-graph.excalidraw", call_graph_scene(graph)),
+    ] {
+        let path = staging.path().join(file_name);
+        write_atomic(&path, serde_json::to_string_pretty(&scene)?)?;
+        file_names.push(file_name);
+    }
+    let dir = staging.commit()?;
+    Ok(file_names.into_iter().map(|name| dir.join(name)).collect())
+}
+
+fn contract_of(node: &Node) -> &str {
+    node.contract_name.as_deref().unwrap_or("Global")
+}
+
+fn qualified_name(node: &Node) -> String {
+    format!("{}.{}", contract_of(node), node.name)
+}
+
+fn call_pairs(graph: &CallGraph) -> impl Iterator<Item = (&Node, &Node)> {
+    graph.edges.iter().filter_map(|edge| {
+        if edge.edge_type != EdgeType::Call {
+            return None;
+        }
+        let source = graph.nodes.get(edge.source_node_id)?;
+        let target = graph.nodes.get(edge.target_node_id)?;
+        (is_callable(source) && is_callable(target)).then_some((source, target))
+    })
+}
+
+/// Assigns each label a column by BFS depth from the nodes nobody calls.
+fn layers(labels: &[String], edges: &BTreeSet<(String, String)>) -> BTreeMap<String, usize> {
+    let mut outgoing: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut has_incoming = BTreeSet::new();
+    for (from, to) in edges {
+        outgoing.entry(from).or_default().push(to);
+        if from != to {
+            has_incoming.insert(to.as_str());
+        }
+    }
+
+    let mut layer: BTreeMap<String, usize> = BTreeMap::new();
+    let roots = labels
+        .iter()
+        .filter(|l| !has_incoming.contains(l.as_str()))
+        .chain(labels.iter());
+    for root in roots {
+        if layer.contains_key(root) {
+            continue;
+        }
+        layer.insert(root.clone(), 0);
+        let mut queue = VecDeque::from([root.as_str()]);
+        while let Some(current) = queue.pop_front() {
+            let depth = layer[current];
+            for next in outgoing.get(current).into_iter().flatten() {
+                if !layer.contains_key(*next) {
+                    layer.insert(next.to_string(), depth + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    layer
+}
+
+/// Lays out `labels` as boxes and `edges` as arrows. Boxes in `external` are
+/// dashed; arrows in `edge_labels` carry a text label.
+fn scene(
+    labels: &[String],
+    edges: &BTreeSet<(String, String)>,
+    external: &BTreeSet<String>,
+    edge_labels: &BTreeMap<(String, String), String>,
+) -> Value {
+    let layer = layers(labels, edges);
+
+    let mut columns: BTreeMap<usize, Vec<&String>> = BTreeMap::new();
+    for label in labels {
+        columns.entry(layer[label]).or_default().push(label);
+    }
+
+    let mut column_x = Vec::new();
+    let mut x = 0.0;
+    for column in columns.values() {
+        column_x.push(x);
+        let widest = column.iter().map(|l| box_width(l)).fold(0.0, f64::max);
+        x += widest + COLUMN_GAP;
+    }
+
+    let mut boxes: BTreeMap<&str, (String, f64, f64, f64)> = BTreeMap::new();
+    for (index, column) in columns.values().enumerate() {
+        for (row, label) in column.iter().enumerate() {
+            let id = format!("node-{}", boxes.len());
+            let y = row as f64 * (BOX_HEIGHT + ROW_GAP);
+            boxes.insert(label.as_str(), (id, column_x[index], y, box_width(label)));
+        }
+    }
+
+    let mut bound: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    let mut arrows = Vec::new();
+    for (index, (from, to)) in edges.iter().enumerate() {
+        let (Some(source), Some(target)) = (boxes.get(from.as_str()), boxes.get(to.as_str()))
+        else {
+            continue;
+        };
+        let id = format!("edge-{}", index);
+        let start = (source.1 + source.3, source.2 + BOX_HEIGHT / 2.0);
+        let end = (target.1, target.2 + BOX_HEIGHT / 2.0);
+        let mut arrow = base_element(
+            &id,
+            "arrow",
+            start.0,
+            start.1,
+            end.0 - start.0,
+            end.1 - start.1,
+        );
+        arrow["points"] = json!([[0.0, 0.0], [end.0 - start.0, end.1 - start.1]]);
+        arrow["startBinding"] = json!({ "elementId": source.0, "focus": 0, "gap": 4 });
+        arrow["endBinding"] = json!({ "elementId": target.0, "focus": 0, "gap": 4 });
+        arrow["startArrowhead"] = Value::Null;
+        arrow["endArrowhead"] = json!("arrow");
+        arrow["lastCommittedPoint"] = Value::Null;
+        if let Some(label) = edge_labels.get(&(from.clone(), to.clone())) {
+            let text_id = format!("{}-label", id);
+            arrow["boundElements"] = json!([{ "id": text_id, "type": "text" }]);
+            arrows.push(arrow);
+            let mut text = base_element(
+                &text_id,
+                "text",
+                start.0 + (end.0 - start.0) / 2.0 - box_width(label) / 2.0,
+                start.1 + (end.1 - start.1) / 2.0 - 10.0,
+                box_width(label),
+                20.0,
+            );
+            style_text(&mut text, label, &id);
+            arrows.push(text);
+        } else {
+            arrows.push(arrow);
+        }
+        bound
+            .entry(from.as_str())
+            .or_default()
+            .push(json!({ "id": id, "type": "arrow" }));
+        bound
+            .entry(to.as_str())
+            .or_default()
+            .push(json!({ "id": id, "type": "arrow" }));
+    }
+
+    let mut elements = Vec::new();
+    for (label, (id, x, y, width)) in &boxes {
+        let text_id = format!("{}-label", id);
+        let mut bindings = vec![json!({ "id": text_id, "type": "text" })];
+        bindings.extend(bound.remove(label).unwrap_or_default());
+
+        let mut rect = base_element(id, "rectangle", *x, *y, *width, BOX_HEIGHT);
+        rect["roundness"] = json!({ "type": 3 });
+        rect["boundElements"] = json!(bindings);
+        if external.contains(*label) {
+            rect["strokeStyle"] = json!("dashed");
+            rect["backgroundColor"] = json!("#e9ecef");
+        }
+        elements.push(rect);
+
+        let mut text = base_element(
+            &text_id,
+            "text",
+            *x,
+            *y + BOX_HEIGHT / 2.0 - 10.0,
+            *width,
+            20.0,
+        );
+        style_text(&mut text, label, id);
+        elements.push(text);
+    }
+    elements.extend(arrows);
+
+    json!({
+        "type": "excalidraw",
+        "version": 2,
+        "source": "traverse-lsp",
+        "elements": elements,
+        "appState": { "viewBackgroundColor": "#ffffff", "gridSize": null },
+        "files": {},
+    })
+}
+
+fn style_text(text: &mut Value, label: &str, container_id: &str) {
+    text["text"] = json!(label);
+    text["originalText"] = json!(label);
+    text["fontSize"] = json!(16);
+    text["fontFamily"] = json!(1);
+    text["textAlign"] = json!("center");
+    text["verticalAlign"] = json!("middle");
+    text["containerId"] = json!(container_id);
+    text["lineHeight"] = json!(1.25);
+}
+
+fn box_width(label: &str) -> f64 {
+    (label.chars().count() as f64 * 9.0 + 40.0).max(160.0)
+}
+
+fn base_element(id: &str, kind: &str, x: f64, y: f64, width: f64, height: f64) -> Value {
+    // Seeds only drive the hand-drawn jitter; deriving them from the id keeps
+    // repeated exports byte-identical.
+    let seed = id
+        .bytes()
+        .fold(7u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
+    json!({
+        "id": id,
+        "type": kind,
+        "x": x,
+        "y": y,
+        "width": width,
+        "height": height,
+        "angle": 0,
+        "strokeColor": "#1e1e1e",
+        "backgroundColor": "transparent",
+        "fillStyle": "solid",
+        "strokeWidth": 1,
+        "strokeStyle": "solid",
+        "roughness": 1,
+        "opacity": 100,
+        "groupIds": [],
+        "frameId": null,
+        "roundness": null,
+        "seed": seed,
+        "version": 1,
+        "versionNonce": seed,
+        "isDeleted": false,
+        "boundElements": null,
+        "updated": 0,
+        "link": null,
+        "locked": false,
+    })
+}
@@ -0,0 +1,27 @@
+//! Call graph exports for tools outside the Graphviz/Mermaid pipeline.
+
+pub mod anonymize;
+pub mod cypher;
+pub mod excalidraw;
+pub mod obsidian;
+
+use serde::{Deserialize, Serialize};
+use traverse_graph::cg::{Node, NodeType};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    #[default]
+    Cypher,
+    Neo4jCsv,
+    Obsidian,
+    Excalidraw,
+}
+
+/// Functions, constructors and modifiers: the nodes exports draw as code.
+pub(crate) fn is_callable(node: &Node) -> bool {
+    matches!(
+        node.node_type,
+        NodeType::Function | NodeType::Constructor | NodeType::Modifier
+    )
+}
@@ -0,0 +1,165 @@
+//! Obsidian-compatible Markdown vault export.
+//!
+//! Writes one note per contract with an embedded Mermaid call diagram and
+//! `[[Contract#function]]` wiki-links to callers and callees, so the vault
+//! graph view mirrors the protocol's call graph.
+
+use super::is_callable;
+use crate::output::{write_atomic, StagingDir};
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use traverse_graph::cg::{CallGraph, EdgeType, Node};
+
+/// Under the output directory.
+pub const VAULT_DIR: &str = "vault";
+const GLOBAL_NOTE: &str = "Global";
+
+/// Renders the vault as a map from note file name to Markdown content.
+pub fn render_notes(graph: &CallGraph) -> BTreeMap<String, String> {
+    let mut callees: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    let mut callers: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    let mut reads: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    let mut writes: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+
+    for edge in &graph.edges {
+        let (Some(source), Some(target)) = (
+            graph.nodes.get(edge.source_node_id),
+            graph.nodes.get(edge.target_node_id),
+        ) else {
+            continue;
+        };
+        match edge.edge_type {
+            EdgeType::Call if is_callable(source) && is_callable(target) => {
+                callees.entry(source.id).or_default().insert(target.id);
+                callers.entry(target.id).or_default().insert(source.id);
+            }
+            EdgeType::StorageRead => {
+                reads.entry(source.id).or_default().insert(target.id);
+            }
+            EdgeType::StorageWrite => {
+                writes.entry(source.id).or_default().insert(target.id);
+            }
+            _ => {}
+        }
+    }
+
+    let mut by_contract: BTreeMap<&str, Vec<&Node>> = BTreeMap::new();
+    for node in graph.nodes.iter().filter(|n| is_callable(n)) {
+        by_contract.entry(note_name(node)).or_default().push(node);
+    }
+
+    let mut notes = BTreeMap::new();
+    for (contract, functions) in by_contract {
+        let mut md = format!("# {}\n\n", contract);
+
+        let mut related: BTreeSet<&str> = BTreeSet::new();
+        for function in &functions {
+            for id in callees.get(&function.id).into_iter().flatten() {
+                related.insert(note_name(&graph.nodes[*id]));
+            }
+            for id in callers.get(&function.id).into_iter().flatten() {
+                related.insert(note_name(&graph.nodes[*id]));
+            }
+        }
+        related.remove(contract);
+        if !related.is_empty() {
+            let links: Vec<String> = related.iter().map(|c| format!("[[{}]]", c)).collect();
+            md.push_str(&format!("**Related:** {}\n\n", links.join(", ")));
+        }
+
+        md.push_str("## Call Diagram\n\n```mermaid\nflowchart LR\n");
+        let mut diagram_edges = BTreeSet::new();
+        for function in &functions {
+            for id in callees.get(&function.id).into_iter().flatten() {
+                diagram_edges.insert((function.id, *id));
+            }
+            for id in callers.get(&function.id).into_iter().flatten() {
+                diagram_edges.insert((*id, function.id));
+            }
+        }
+        let mut declared = BTreeSet::new();
+        let endpoints = diagram_edges.iter().flat_map(|(from, to)| [*from, *to]);
+        for id in functions.iter().map(|f| f.id).chain(endpoints) {
+            if declared.insert(id) {
+                md.push_str(&format!(
+                    "    n{}[\"{}\"]\n",
+                    id,
+                    qualified_name(&graph.nodes[id]).replace('"', "'")
+                ));
+            }
+        }
+        for (from, to) in &diagram_edges {
+            md.push_str(&format!("    n{} --> n{}\n", from, to));
+        }
+        md.push_str("```\n\n## Functions\n\n");
+
+        for function in &functions {
+            md.push_str(&format!("### {}\n\n", function.name));
+            md.push_str(&format!(
+                "- **Kind:** {}\n- **Visibility:** {}\n",
+                format!("{:?}", function.node_type).to_lowercase(),
+                format!("{:?}", function.visibility).to_lowercase()
+            ));
+            push_links(&mut md, "Calls", callees.get(&function.id), graph);
+            push_links(&mut md, "Called by", callers.get(&function.id), graph);
+            push_names(&mut md, "Reads", reads.get(&function.id), graph);
+            push_names(&mut md, "Writes", writes.get(&function.id), graph);
+            md.push('\n');
+        }
+
+        notes.insert(format!("{}.md", contract), md);
+    }
+    notes
+}
+
+/// Writes the vault into a staging directory that replaces `dir` once every
+/// note is written, so notes of contracts no longer in the graph go with it.
+pub fn write_vault(graph: &CallGraph, dir: &Path) -> Result<Vec<PathBuf>> {
+    let staging = StagingDir::new(dir)?;
+    let mut file_names = Vec::new();
+    for (file_name, content) in render_notes(graph) {
+        write_atomic(&staging.path().join(&file_name), content)?;
+        file_names.push(file_name);
+    }
+    let dir = staging.commit()?;
+    Ok(file_names.into_iter().map(|name| dir.join(name)).collect())
+}
+
+fn note_name(node: &Node) -> &str {
+    node.contract_name.as_deref().unwrap_or(GLOBAL_NOTE)
+}
+
+fn qualified_name(node: &Node) -> String {
+    format!("{}.{}", note_name(node), node.name)
+}
+
+fn push_links(md: &mut String, label: &str, ids: Option<&BTreeSet<usize>>, graph: &CallGraph) {
+    let Some(ids) = ids else {
+        return;
+    };
+    let links: Vec<String> = ids
+        .iter()
+        .map(|id| {
+            let node = &graph.nodes[*id];
+            format!(
+                "[[{}#{}|{}]]",
+                note_name(node),
+                node.name,
+                qualified_name(node)
+            )
+        })
+        .collect();
+    md.push_str(&format!("- **{}:** {}\n", label, links.join(", ")));
+}
+
+fn push_names(md: &mut String, label: &str, ids: Option<&BTreeSet<usize>>, graph: &CallGraph) {
+    let Some(ids) = ids else {
+        return;
+    };
+    let names: Vec<String> = ids
+        .iter()
+        .map(|id| format!("`{}`", qualified_name(&graph.nodes[*id])))
+        .collect();
+    md.push_str(&format!("- **{}:** {}\n", label, names.join(", ")));
+}
@@ -0,0 +1,121 @@
+//! Include and exclude globs for workspace file discovery.
+//!
+//! Patterns are matched against paths relative to the workspace folder,
+//! with `/` separators, using `globset` syntax: `*` matches within one path
+//! segment, `**` across segments, `?` one character, `[...]` a character
+//! class and `{a,b}` either alternative. A pattern without a `/` matches a
+//! file or directory name at any depth, like in `.gitignore`; one with a
+//! `/` is anchored at the workspace folder. A pattern matching a directory
+//! matches everything under it, so `test`, `test/` and `test/**` are the
+//! same. Invalid patterns are logged and match nothing.
+
+use crate::config::FileFilter;
+use globset::{GlobBuilder, GlobMatcher};
+use lsp_types::Url;
+use std::path::{Component, Path};
+use tracing::warn;
+
+/// A compiled include or exclude pattern.
+struct Pattern {
+    matcher: GlobMatcher,
+    /// Contains a `/`, so matches whole paths rather than single names.
+    anchored: bool,
+}
+
+impl Pattern {
+    fn compile(pattern: &str) -> Option<Self> {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        let anchored = pattern.contains('/');
+        let glob = GlobBuilder::new(pattern.trim_start_matches('/'))
+            .literal_separator(true)
+            .build()
+            .map_err(|e| warn!("Ignoring invalid file pattern `{}`: {}", pattern, e))
+            .ok()?;
+        Some(Self {
+            matcher: glob.compile_matcher(),
+            anchored,
+        })
+    }
+
+    /// Whether the pattern matches the path of `segments` or one of its
+    /// parent directories.
+    fn matches(&self, segments: &[String]) -> bool {
+        if !self.anchored {
+            return segments
+                .iter()
+                .any(|segment| self.matcher.is_match(segment));
+        }
+        (1..=segments.len()).any(|len| self.matcher.is_match(segments[..len].join("/")))
+    }
+}
+
+/// `filter`'s patterns, compiled once for many paths.
+struct Compiled {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    /// Include patterns were given, even if none compiled.
+    restricted: bool,
+}
+
+impl Compiled {
+    fn new(filter: &FileFilter) -> Self {
+        let compile = |patterns: &[String]| -> Vec<Pattern> {
+            patterns
+                .iter()
+                .filter_map(|p| Pattern::compile(p))
+                .collect()
+        };
+        Self {
+            include: compile(&filter.include),
+            exclude: compile(&filter.exclude),
+            restricted: !filter.include.is_empty(),
+        }
+    }
+
+    fn allows(&self, workspace_folder: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(workspace_folder).unwrap_or(path);
+        let segments: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let matches_any = |patterns: &[Pattern]| patterns.iter().any(|p| p.matches(&segments));
+        (!self.restricted || matches_any(&self.include)) && !matches_any(&self.exclude)
+    }
+}
+
+/// Whether `path`, under `workspace_folder`, passes `filter`. Paths outside
+/// the folder are matched as given.
+#[allow(dead_code)]
+pub fn allows(filter: &FileFilter, workspace_folder: &Path, path: &Path) -> bool {
+    Compiled::new(filter).allows(workspace_folder, path)
+}
+
+/// Drops the files of `files` that `filter` rejects.
+pub fn retain(files: &mut Vec<Url>, filter: &FileFilter, workspace_folder: &Path) {
+    if filter.is_empty() {
+        return;
+    }
+    let compiled = Compiled::new(filter);
+    files.retain(|uri| {
+        uri.to_file_path()
+            .is_ok_and(|path| compiled.allows(workspace_folder, &path))
+    });
+}
+
+/// Whether `pattern` matches `path` or one of its parent directories, with
+/// a pattern containing `/` anchored at any directory rather than at the
+/// workspace folder, for settings that apply without one.
+pub fn matches_at_any_depth(pattern: &str, path: &Path) -> bool {
+    let Some(pattern) = Pattern::compile(pattern) else {
+        return false;
+    };
+    let segments: Vec<String> = path
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    (0..segments.len()).any(|start| pattern.matches(&segments[start..]))
+}
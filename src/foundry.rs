@@ -0,0 +1,170 @@
+//! Foundry project layout.
+//!
+//! A `foundry.toml` at the workspace root names the directories holding the
+//! project's contracts, tests, scripts and libraries. Its `remappings`, those
+//! in `remappings.txt`, and the ones Foundry derives for each library tell
+//! how imports like `@openzeppelin/...` map onto files. Only the `default`
+//! profile is read.
+
+use crate::analysis::imports::{self, Remapping};
+use anyhow::{Context, Result};
+use lsp_types::Url;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const FOUNDRY_CONFIG: &str = "foundry.toml";
+pub const REMAPPINGS_FILE: &str = "remappings.txt";
+
+#[derive(Debug, Clone)]
+pub struct FoundryProject {
+    pub root: PathBuf,
+    /// Contracts of the project, `src` by default.
+    pub src: PathBuf,
+    pub test: PathBuf,
+    pub script: PathBuf,
+    /// Dependency directories, `lib` by default.
+    pub libs: Vec<PathBuf>,
+    /// In precedence order: `foundry.toml`, `remappings.txt`, then one per
+    /// library.
+    pub remappings: Vec<Remapping>,
+}
+
+impl FoundryProject {
+    /// The project rooted at `root`, or `None` without a `foundry.toml`.
+    pub fn detect(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(FOUNDRY_CONFIG);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: toml::Value =
+            toml::from_str(&text).with_context(|| format!("Invalid {}", path.display()))?;
+        let profile = config.get("profile").and_then(|p| p.get("default"));
+        let setting = |key: &str, default: &str| {
+            root.join(
+                profile
+                    .and_then(|p| p.get(key))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or(default),
+            )
+        };
+        let strings = |key: &str| -> Option<Vec<String>> {
+            let values = profile?.get(key)?.as_array()?;
+            Some(
+                values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect(),
+            )
+        };
+
+        let libs: Vec<PathBuf> = strings("libs")
+            .unwrap_or_else(|| vec!["lib".to_string()])
+            .iter()
+            .map(|lib| root.join(lib))
+            .collect();
+        let mut remappings: Vec<Remapping> = strings("remappings")
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|line| Remapping::parse(line))
+            .collect();
+        add_missing(&mut remappings, remappings_file(root)?);
+        add_missing(&mut remappings, library_remappings(root, &libs));
+
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            src: setting("src", "src"),
+            test: setting("test", "test"),
+            script: setting("script", "script"),
+            libs,
+            remappings,
+        }))
+    }
+
+    /// The project's own contracts among `files`, and every file they
+    /// import, in `files` order. Tests, scripts and libraries the contracts
+    /// do not import are left out.
+    pub fn scope(&self, files: &[Url]) -> Result<Vec<Url>> {
+        let paths: Vec<PathBuf> = files
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect();
+        let outside = |path: &Path, dir: &Path| dir != self.root && path.starts_with(dir);
+        let roots: Vec<PathBuf> = paths
+            .iter()
+            .filter(|path| {
+                path.starts_with(&self.src)
+                    && !outside(path, &self.test)
+                    && !outside(path, &self.script)
+                    && !self.libs.iter().any(|lib| outside(path, lib))
+            })
+            .cloned()
+            .collect();
+        let scope = imports::import_closure(&self.root, &paths, &roots, &self.remappings)?;
+        Ok(files
+            .iter()
+            .filter(|uri| uri.to_file_path().is_ok_and(|path| scope.contains(&path)))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Remappings of the Foundry project at `root`, or of its `remappings.txt`
+/// when there is no `foundry.toml`.
+pub fn remappings(root: &Path) -> Result<Vec<Remapping>> {
+    match FoundryProject::detect(root)? {
+        Some(project) => Ok(project.remappings),
+        None => remappings_file(root),
+    }
+}
+
+fn remappings_file(root: &Path) -> Result<Vec<Remapping>> {
+    let path = root.join(REMAPPINGS_FILE);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(text.lines().filter_map(Remapping::parse).collect())
+}
+
+/// `name/=lib/name/src/` for each library with a `src` directory, and
+/// `name/=lib/name/` for the others, as Foundry derives them.
+fn library_remappings(root: &Path, libs: &[PathBuf]) -> Vec<Remapping> {
+    let mut remappings = Vec::new();
+    for lib in libs {
+        let Ok(entries) = fs::read_dir(lib) else {
+            continue;
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            let Some(name) = dir.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let target = if dir.join("src").is_dir() {
+                dir.join("src")
+            } else {
+                dir.clone()
+            };
+            let target = target.strip_prefix(root).unwrap_or(&target);
+            remappings.push(Remapping {
+                prefix: format!("{}/", name),
+                target: format!("{}/", target.to_string_lossy().replace('\\', "/")),
+            });
+        }
+    }
+    remappings
+}
+
+fn add_missing(remappings: &mut Vec<Remapping>, more: Vec<Remapping>) {
+    for remapping in more {
+        if !remappings.iter().any(|r| r.prefix == remapping.prefix) {
+            remappings.push(remapping);
+        }
+    }
+}
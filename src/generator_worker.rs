@@ -3,145 +3,1616 @@
 //! Prevents diagram generation from blocking the LSP message loop,
 //! ensuring the editor remains responsive during analysis.
 
-use crate::config::MermaidConfig;
-use crate::traverse_adapter::TraverseAdapter;
-use anyhow::Result;
-use lsp_types::Url;
-use std::path::PathBuf;
-use std::sync::mpsc;
+use crate::analysis::{
+    self, call_matrix, clones, cross_reference, dependencies, fuzz_targets, governance,
+    magic_numbers,
+    model::{parse_sources_with_profiles, SourceModel, SourceUnit},
+    overrides, pause_coverage,
+    pr_comment::{self, Snapshot},
+    privilege_paths::{self, Privileges},
+    storage_access::StorageAccessReport,
+    storage_compare, storage_heatmap, summary,
+    suppression::{self, IgnoreFile},
+    unresolved_calls, Confidence, Finding,
+};
+use crate::config::{Config, DotConfig, GraphFilter, MermaidConfig, SharedConfig};
+use crate::consumers;
+use crate::coverage;
+use crate::documents::DocumentStore;
+use crate::exporters::{
+    anonymize::{self, Pseudonyms},
+    cypher, excalidraw, obsidian, ExportFormat,
+};
+use crate::git;
+use crate::graph_cache::{GraphCache, GraphKind};
+use crate::graph_filter;
+use crate::output::envelope::{Artifact, ArtifactFormat, RunInfo};
+use crate::output::large_result;
+use crate::output::manifest::{self, Manifest, MANIFEST_FILE};
+use crate::output::preview;
+use crate::output::retention;
+use crate::output::settings::{OutputLocation, OutputSettings};
+use crate::output::template::{self, Template};
+use crate::profiles;
+use crate::renderers::{DiagramRenderer, RenderOptions};
+use crate::source_map::SourceMap;
+use crate::symbol_index;
+use crate::trace;
+use crate::traverse_adapter::{self, TraverseAdapter};
+use crate::watchdog::PhaseTracker;
+use anyhow::{anyhow, Context, Result};
+use lsp_types::{Location, TextDocumentPositionParams, Url};
+use sha2::{Digest, Sha256};
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::ops::Deref;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use tokio::sync::oneshot;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn, Span};
 use traverse_graph::cg::CallGraph;
 
+/// Receives a step message and a percentage from long-running requests.
+pub type ProgressCallback = Box<dyn Fn(&str, u32) + Send + Sync>;
+
+/// Set when the client cancels a request; the worker stops at the next
+/// phase boundary. It also carries the request's tracing span, so the
+/// worker logs under the request that queued the job.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    /// Set before `cancelled` when the server cancels the request because
+    /// it is shutting down.
+    shutting_down: Arc<AtomicBool>,
+    span: Option<Span>,
+}
+
+impl CancelToken {
+    /// Cancels the request because the server is shutting down.
+    pub fn shut_down(&self) {
+        self.shutting_down.store(true, Ordering::Relaxed);
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// What a request cancelled through this token fails with:
+    /// [`ShuttingDown`] if the server cancelled it on shutdown, and
+    /// [`RequestCancelled`] otherwise.
+    pub fn error(&self) -> anyhow::Error {
+        if self.cancelled.load(Ordering::Acquire) && self.shutting_down.load(Ordering::Relaxed) {
+            ShuttingDown.into()
+        } else {
+            RequestCancelled.into()
+        }
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// The span of the request, or a disabled one.
+    pub fn span(&self) -> Span {
+        self.span.clone().unwrap_or_else(Span::none)
+    }
+}
+
+impl Deref for CancelToken {
+    type Target = AtomicBool;
+
+    fn deref(&self) -> &AtomicBool {
+        &self.cancelled
+    }
+}
+
 pub enum GenerationRequest {
     Shutdown,
     GenerateCallGraphDiagram {
         uris: Vec<Url>,
         contract_name: Option<String>,
+        dot_config: DotConfig,
+        filter: GraphFilter,
+        /// lcov file whose function hits are overlaid on the graph.
+        coverage_file: Option<PathBuf>,
+        progress: Option<ProgressCallback>,
+        cancel: CancelToken,
         tx: oneshot::Sender<Result<String>>,
     },
     GenerateMermaidFlowchart {
         uris: Vec<Url>,
+        workspace_folder: PathBuf,
         contract_name: Option<String>,
-        no_chunk: bool,
+        config: MermaidConfig,
+        filter: GraphFilter,
+        progress: Option<ProgressCallback>,
+        cancel: CancelToken,
         tx: oneshot::Sender<Result<String>>,
     },
     GenerateAllDiagrams {
         uris: Vec<Url>,
+        workspace_folder: PathBuf,
         contract_name: Option<String>,
+        dot_config: DotConfig,
+        mermaid_config: MermaidConfig,
+        filter: GraphFilter,
+        progress: Option<ProgressCallback>,
+        cancel: CancelToken,
         tx: oneshot::Sender<Result<String>>,
     },
     GenerateStorageLayout {
         uris: Vec<Url>,
         contract_name: String,
+        /// Report template; the built-in Markdown table when `None`.
+        template: Option<PathBuf>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    GenerateAuditReport {
+        uris: Vec<Url>,
+        workspace_folder: PathBuf,
+        min_confidence: Confidence,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    SummarizeFindings {
+        uris: Vec<Url>,
+        workspace_folder: PathBuf,
+        min_confidence: Confidence,
+        top_contracts: usize,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    GeneratePrComment {
+        uris: Vec<Url>,
+        workspace_folder: PathBuf,
+        base_ref: String,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    ListFuzzTargets {
+        uris: Vec<Url>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    AuditOverrides {
+        uris: Vec<Url>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    CrossReference {
+        uris: Vec<Url>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    MagicNumbers {
+        uris: Vec<Url>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    ExportGraph {
+        uris: Vec<Url>,
+        workspace_folder: PathBuf,
+        format: ExportFormat,
+        filter: GraphFilter,
+        consumers_file: Option<PathBuf>,
+        /// Replaces identifiers with pseudonyms.
+        anonymize: bool,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// State variables colored by how many functions write them.
+    StorageHeatmap {
+        uris: Vec<Url>,
+        contract_name: Option<String>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Counts of calls between each pair of contracts.
+    CallMatrix {
+        uris: Vec<Url>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Who controls each contract's admin functions and how control is
+    /// handed over.
+    GovernanceReport {
+        uris: Vec<Url>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// State-changing entry points a pause does and does not stop.
+    PauseCoverage {
+        uris: Vec<Url>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Which functions depend on oracles, tokens, delegatecall or the
+    /// block environment.
+    ExternalDependencies {
+        uris: Vec<Url>,
+        contract_name: Option<String>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Call paths from unprivileged entry points into privileged functions.
+    PrivilegePaths {
+        uris: Vec<Url>,
+        privileges: Privileges,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Clusters of functions with near-identical bodies.
+    FindClones {
+        uris: Vec<Url>,
+        min_nodes: usize,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Sequence diagram of one function and its transitive callees.
+    GenerateFunctionDiagram {
+        uris: Vec<Url>,
+        workspace_folder: PathBuf,
+        contract_name: String,
+        function_name: String,
+        config: MermaidConfig,
+        filter: GraphFilter,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// [`GenerationRequest::GenerateFunctionDiagram`] for the function
+    /// enclosing a position in a document.
+    GenerateFunctionDiagramAt {
+        uris: Vec<Url>,
+        workspace_folder: PathBuf,
+        position: TextDocumentPositionParams,
+        config: MermaidConfig,
+        filter: GraphFilter,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Re-renders one chunk of a chunked sequence diagram, scoped to a
+    /// contract or, with `function_name`, to one function of it.
+    RegenerateChunk {
+        uris: Vec<Url>,
+        workspace_folder: PathBuf,
+        contract_name: Option<String>,
+        function_name: Option<String>,
+        chunk: usize,
+        config: MermaidConfig,
+        filter: GraphFilter,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Renders the call graph, scoped like the call graph diagram, with a
+    /// renderer from the registry.
+    RenderDiagram {
+        uris: Vec<Url>,
+        contract_name: Option<String>,
+        renderer: &'static dyn DiagramRenderer,
+        options: RenderOptions,
+        filter: GraphFilter,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    CompareStorage {
+        uris: Vec<Url>,
+        left_contract: String,
+        right_contract: String,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Renders a Foundry trace, given inline or as a file, as a sequence
+    /// diagram aligned with the workspace sources.
+    ImportTrace {
+        uris: Vec<Url>,
+        trace: Option<String>,
+        trace_file: Option<PathBuf>,
+        cancel: CancelToken,
+        tx: oneshot::Sender<Result<String>>,
+    },
+    /// Builds and caches the call graphs later commands need, reporting
+    /// each step with a message and percentage.
+    WarmCache {
+        uris: Vec<Url>,
+        progress: Option<ProgressCallback>,
+        cancel: CancelToken,
         tx: oneshot::Sender<Result<String>>,
     },
 }
 
+impl GenerationRequest {
+    /// Short name of the request for logs and errors.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GenerationRequest::Shutdown => "shutdown",
+            GenerationRequest::GenerateCallGraphDiagram { .. } => "call graph generation",
+            GenerationRequest::GenerateMermaidFlowchart { .. } => "sequence diagram generation",
+            GenerationRequest::GenerateAllDiagrams { .. } => "diagram generation",
+            GenerationRequest::GenerateStorageLayout { .. } => "storage analysis",
+            GenerationRequest::GenerateAuditReport { .. } => "audit report",
+            GenerationRequest::SummarizeFindings { .. } => "findings summary",
+            GenerationRequest::GeneratePrComment { .. } => "PR comment",
+            GenerationRequest::ListFuzzTargets { .. } => "fuzz target listing",
+            GenerationRequest::AuditOverrides { .. } => "override audit",
+            GenerationRequest::CrossReference { .. } => "cross-reference",
+            GenerationRequest::MagicNumbers { .. } => "magic number inventory",
+            GenerationRequest::ExportGraph { .. } => "graph export",
+            GenerationRequest::StorageHeatmap { .. } => "storage heatmap",
+            GenerationRequest::CallMatrix { .. } => "call matrix",
+            GenerationRequest::GovernanceReport { .. } => "governance report",
+            GenerationRequest::PauseCoverage { .. } => "pause coverage",
+            GenerationRequest::ExternalDependencies { .. } => "external dependencies",
+            GenerationRequest::PrivilegePaths { .. } => "privilege path search",
+            GenerationRequest::FindClones { .. } => "clone detection",
+            GenerationRequest::GenerateFunctionDiagram { .. }
+            | GenerationRequest::GenerateFunctionDiagramAt { .. } => "function diagram generation",
+            GenerationRequest::RegenerateChunk { .. } => "chunk regeneration",
+            GenerationRequest::RenderDiagram { .. } => "diagram rendering",
+            GenerationRequest::CompareStorage { .. } => "storage comparison",
+            GenerationRequest::ImportTrace { .. } => "trace import",
+            GenerationRequest::WarmCache { .. } => "cache warm-up",
+        }
+    }
+
+    /// Swaps in `tx` as the reply channel and returns the previous one;
+    /// `None` for `Shutdown`.
+    pub fn replace_reply(
+        &mut self,
+        tx: oneshot::Sender<Result<String>>,
+    ) -> Option<oneshot::Sender<Result<String>>> {
+        match self {
+            GenerationRequest::Shutdown => None,
+            GenerationRequest::GenerateCallGraphDiagram { tx: reply, .. }
+            | GenerationRequest::GenerateMermaidFlowchart { tx: reply, .. }
+            | GenerationRequest::GenerateAllDiagrams { tx: reply, .. }
+            | GenerationRequest::GenerateStorageLayout { tx: reply, .. }
+            | GenerationRequest::GenerateAuditReport { tx: reply, .. }
+            | GenerationRequest::SummarizeFindings { tx: reply, .. }
+            | GenerationRequest::GeneratePrComment { tx: reply, .. }
+            | GenerationRequest::ListFuzzTargets { tx: reply, .. }
+            | GenerationRequest::AuditOverrides { tx: reply, .. }
+            | GenerationRequest::CrossReference { tx: reply, .. }
+            | GenerationRequest::MagicNumbers { tx: reply, .. }
+            | GenerationRequest::ExportGraph { tx: reply, .. }
+            | GenerationRequest::StorageHeatmap { tx: reply, .. }
+            | GenerationRequest::CallMatrix { tx: reply, .. }
+            | GenerationRequest::GovernanceReport { tx: reply, .. }
+            | GenerationRequest::PauseCoverage { tx: reply, .. }
+            | GenerationRequest::ExternalDependencies { tx: reply, .. }
+            | GenerationRequest::PrivilegePaths { tx: reply, .. }
+            | GenerationRequest::FindClones { tx: reply, .. }
+            | GenerationRequest::GenerateFunctionDiagram { tx: reply, .. }
+            | GenerationRequest::GenerateFunctionDiagramAt { tx: reply, .. }
+            | GenerationRequest::RegenerateChunk { tx: reply, .. }
+            | GenerationRequest::RenderDiagram { tx: reply, .. }
+            | GenerationRequest::CompareStorage { tx: reply, .. }
+            | GenerationRequest::ImportTrace { tx: reply, .. }
+            | GenerationRequest::WarmCache { tx: reply, .. } => Some(std::mem::replace(reply, tx)),
+        }
+    }
+
+    /// Swaps in `cancel` as the token the worker checks and returns the
+    /// previous one; `None` for `Shutdown`.
+    pub fn replace_cancel_token(&mut self, cancel: CancelToken) -> Option<CancelToken> {
+        match self {
+            GenerationRequest::Shutdown => None,
+            GenerationRequest::GenerateCallGraphDiagram { cancel: token, .. }
+            | GenerationRequest::GenerateMermaidFlowchart { cancel: token, .. }
+            | GenerationRequest::GenerateAllDiagrams { cancel: token, .. }
+            | GenerationRequest::GenerateStorageLayout { cancel: token, .. }
+            | GenerationRequest::GenerateAuditReport { cancel: token, .. }
+            | GenerationRequest::SummarizeFindings { cancel: token, .. }
+            | GenerationRequest::GeneratePrComment { cancel: token, .. }
+            | GenerationRequest::ListFuzzTargets { cancel: token, .. }
+            | GenerationRequest::AuditOverrides { cancel: token, .. }
+            | GenerationRequest::CrossReference { cancel: token, .. }
+            | GenerationRequest::MagicNumbers { cancel: token, .. }
+            | GenerationRequest::ExportGraph { cancel: token, .. }
+            | GenerationRequest::StorageHeatmap { cancel: token, .. }
+            | GenerationRequest::CallMatrix { cancel: token, .. }
+            | GenerationRequest::GovernanceReport { cancel: token, .. }
+            | GenerationRequest::PauseCoverage { cancel: token, .. }
+            | GenerationRequest::ExternalDependencies { cancel: token, .. }
+            | GenerationRequest::PrivilegePaths { cancel: token, .. }
+            | GenerationRequest::FindClones { cancel: token, .. }
+            | GenerationRequest::GenerateFunctionDiagram { cancel: token, .. }
+            | GenerationRequest::GenerateFunctionDiagramAt { cancel: token, .. }
+            | GenerationRequest::RegenerateChunk { cancel: token, .. }
+            | GenerationRequest::RenderDiagram { cancel: token, .. }
+            | GenerationRequest::CompareStorage { cancel: token, .. }
+            | GenerationRequest::ImportTrace { cancel: token, .. }
+            | GenerationRequest::WarmCache { cancel: token, .. } => {
+                Some(std::mem::replace(token, cancel))
+            }
+        }
+    }
+
+    /// The token the client cancels the request with; `None` for
+    /// `Shutdown`.
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        match self {
+            GenerationRequest::Shutdown => None,
+            GenerationRequest::GenerateCallGraphDiagram { cancel, .. }
+            | GenerationRequest::GenerateMermaidFlowchart { cancel, .. }
+            | GenerationRequest::GenerateAllDiagrams { cancel, .. }
+            | GenerationRequest::GenerateStorageLayout { cancel, .. }
+            | GenerationRequest::GenerateAuditReport { cancel, .. }
+            | GenerationRequest::SummarizeFindings { cancel, .. }
+            | GenerationRequest::GeneratePrComment { cancel, .. }
+            | GenerationRequest::ListFuzzTargets { cancel, .. }
+            | GenerationRequest::AuditOverrides { cancel, .. }
+            | GenerationRequest::CrossReference { cancel, .. }
+            | GenerationRequest::MagicNumbers { cancel, .. }
+            | GenerationRequest::ExportGraph { cancel, .. }
+            | GenerationRequest::StorageHeatmap { cancel, .. }
+            | GenerationRequest::CallMatrix { cancel, .. }
+            | GenerationRequest::GovernanceReport { cancel, .. }
+            | GenerationRequest::PauseCoverage { cancel, .. }
+            | GenerationRequest::ExternalDependencies { cancel, .. }
+            | GenerationRequest::PrivilegePaths { cancel, .. }
+            | GenerationRequest::FindClones { cancel, .. }
+            | GenerationRequest::GenerateFunctionDiagram { cancel, .. }
+            | GenerationRequest::GenerateFunctionDiagramAt { cancel, .. }
+            | GenerationRequest::RegenerateChunk { cancel, .. }
+            | GenerationRequest::RenderDiagram { cancel, .. }
+            | GenerationRequest::CompareStorage { cancel, .. }
+            | GenerationRequest::ImportTrace { cancel, .. }
+            | GenerationRequest::WarmCache { cancel, .. } => Some(cancel),
+        }
+    }
+
+    /// Takes the callback the worker reports the request's progress to, if
+    /// the request has one.
+    pub fn take_progress(&mut self) -> Option<ProgressCallback> {
+        match self {
+            GenerationRequest::GenerateCallGraphDiagram { progress, .. }
+            | GenerationRequest::GenerateMermaidFlowchart { progress, .. }
+            | GenerationRequest::GenerateAllDiagrams { progress, .. }
+            | GenerationRequest::WarmCache { progress, .. } => progress.take(),
+            _ => None,
+        }
+    }
+
+    /// Everything the result depends on, so two requests with the same key
+    /// can share one run; `None` for `Shutdown`.
+    pub fn coalesce_key(&self) -> Option<String> {
+        let fields = match self {
+            GenerationRequest::Shutdown => return None,
+            GenerationRequest::GenerateCallGraphDiagram {
+                uris,
+                contract_name,
+                dot_config,
+                filter,
+                coverage_file,
+                ..
+            } => format!(
+                "{:?}",
+                (uris, contract_name, dot_config, filter, coverage_file)
+            ),
+            GenerationRequest::GenerateMermaidFlowchart {
+                uris,
+                workspace_folder,
+                contract_name,
+                config,
+                filter,
+                ..
+            } => format!(
+                "{:?}",
+                (uris, workspace_folder, contract_name, config, filter)
+            ),
+            GenerationRequest::GenerateAllDiagrams {
+                uris,
+                workspace_folder,
+                contract_name,
+                dot_config,
+                mermaid_config,
+                filter,
+                ..
+            } => format!(
+                "{:?}",
+                (
+                    uris,
+                    workspace_folder,
+                    contract_name,
+                    dot_config,
+                    mermaid_config,
+                    filter
+                )
+            ),
+            GenerationRequest::GenerateStorageLayout {
+                uris,
+                contract_name,
+                template,
+                ..
+            } => format!("{:?}", (uris, contract_name, template)),
+            GenerationRequest::GenerateAuditReport {
+                uris,
+                workspace_folder,
+                min_confidence,
+                ..
+            } => format!("{:?}", (uris, workspace_folder, min_confidence)),
+            GenerationRequest::SummarizeFindings {
+                uris,
+                workspace_folder,
+                min_confidence,
+                top_contracts,
+                ..
+            } => format!(
+                "{:?}",
+                (uris, workspace_folder, min_confidence, top_contracts)
+            ),
+            GenerationRequest::GeneratePrComment {
+                uris,
+                workspace_folder,
+                base_ref,
+                ..
+            } => format!("{:?}", (uris, workspace_folder, base_ref)),
+            GenerationRequest::ListFuzzTargets { uris, .. }
+            | GenerationRequest::AuditOverrides { uris, .. }
+            | GenerationRequest::CrossReference { uris, .. }
+            | GenerationRequest::MagicNumbers { uris, .. }
+            | GenerationRequest::WarmCache { uris, .. } => format!("{:?}", uris),
+            GenerationRequest::ExportGraph {
+                uris,
+                workspace_folder,
+                format,
+                filter,
+                consumers_file,
+                anonymize,
+                ..
+            } => format!(
+                "{:?}",
+                (
+                    uris,
+                    workspace_folder,
+                    format,
+                    filter,
+                    consumers_file,
+                    anonymize
+                )
+            ),
+            GenerationRequest::StorageHeatmap {
+                uris,
+                contract_name,
+                ..
+            }
+            | GenerationRequest::ExternalDependencies {
+                uris,
+                contract_name,
+                ..
+            } => format!("{:?}", (uris, contract_name)),
+            GenerationRequest::CallMatrix { uris, .. }
+            | GenerationRequest::GovernanceReport { uris, .. }
+            | GenerationRequest::PauseCoverage { uris, .. } => format!("{:?}", uris),
+            GenerationRequest::PrivilegePaths {
+                uris, privileges, ..
+            } => format!("{:?}", (uris, privileges)),
+            GenerationRequest::FindClones {
+                uris, min_nodes, ..
+            } => format!("{:?}", (uris, min_nodes)),
+            GenerationRequest::GenerateFunctionDiagram {
+                uris,
+                workspace_folder,
+                contract_name,
+                function_name,
+                config,
+                filter,
+                ..
+            } => format!(
+                "{:?}",
+                (
+                    uris,
+                    workspace_folder,
+                    contract_name,
+                    function_name,
+                    config,
+                    filter
+                )
+            ),
+            GenerationRequest::GenerateFunctionDiagramAt {
+                uris,
+                workspace_folder,
+                position,
+                config,
+                filter,
+                ..
+            } => format!("{:?}", (uris, workspace_folder, position, config, filter)),
+            GenerationRequest::RegenerateChunk {
+                uris,
+                workspace_folder,
+                contract_name,
+                function_name,
+                chunk,
+                config,
+                filter,
+                ..
+            } => format!(
+                "{:?}",
+                (
+                    uris,
+                    workspace_folder,
+                    contract_name,
+                    function_name,
+                    chunk,
+                    config,
+                    filter
+                )
+            ),
+            GenerationRequest::RenderDiagram {
+                uris,
+                contract_name,
+                renderer,
+                options,
+                filter,
+                ..
+            } => format!(
+                "{:?}",
+                (uris, contract_name, renderer.name(), options, filter)
+            ),
+            GenerationRequest::CompareStorage {
+                uris,
+                left_contract,
+                right_contract,
+                ..
+            } => format!("{:?}", (uris, left_contract, right_contract)),
+            GenerationRequest::ImportTrace {
+                uris,
+                trace,
+                trace_file,
+                ..
+            } => format!("{:?}", (uris, trace, trace_file)),
+        };
+        Some(format!("{}: {}", self.kind(), fields))
+    }
+
+    /// The workspace folder of a diagram generation covering every
+    /// contract; a newer one for the same folder supersedes an older one.
+    pub fn workspace_scope(&self) -> Option<(&'static str, &Path)> {
+        match self {
+            GenerationRequest::GenerateMermaidFlowchart {
+                workspace_folder,
+                contract_name: None,
+                ..
+            }
+            | GenerationRequest::GenerateAllDiagrams {
+                workspace_folder,
+                contract_name: None,
+                ..
+            } => Some((self.kind(), workspace_folder)),
+            _ => None,
+        }
+    }
+
+    /// The reply channel; `None` for `Shutdown`.
+    pub fn into_reply(self) -> Option<oneshot::Sender<Result<String>>> {
+        match self {
+            GenerationRequest::Shutdown => None,
+            GenerationRequest::GenerateCallGraphDiagram { tx, .. }
+            | GenerationRequest::GenerateMermaidFlowchart { tx, .. }
+            | GenerationRequest::GenerateAllDiagrams { tx, .. }
+            | GenerationRequest::GenerateStorageLayout { tx, .. }
+            | GenerationRequest::GenerateAuditReport { tx, .. }
+            | GenerationRequest::SummarizeFindings { tx, .. }
+            | GenerationRequest::GeneratePrComment { tx, .. }
+            | GenerationRequest::ListFuzzTargets { tx, .. }
+            | GenerationRequest::AuditOverrides { tx, .. }
+            | GenerationRequest::CrossReference { tx, .. }
+            | GenerationRequest::MagicNumbers { tx, .. }
+            | GenerationRequest::ExportGraph { tx, .. }
+            | GenerationRequest::StorageHeatmap { tx, .. }
+            | GenerationRequest::CallMatrix { tx, .. }
+            | GenerationRequest::GovernanceReport { tx, .. }
+            | GenerationRequest::PauseCoverage { tx, .. }
+            | GenerationRequest::ExternalDependencies { tx, .. }
+            | GenerationRequest::PrivilegePaths { tx, .. }
+            | GenerationRequest::FindClones { tx, .. }
+            | GenerationRequest::GenerateFunctionDiagram { tx, .. }
+            | GenerationRequest::GenerateFunctionDiagramAt { tx, .. }
+            | GenerationRequest::RegenerateChunk { tx, .. }
+            | GenerationRequest::RenderDiagram { tx, .. }
+            | GenerationRequest::CompareStorage { tx, .. }
+            | GenerationRequest::ImportTrace { tx, .. }
+            | GenerationRequest::WarmCache { tx, .. } => Some(tx),
+        }
+    }
+}
+
+/// Error for requests the worker will not run because the server is
+/// shutting down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuttingDown;
+
+impl fmt::Display for ShuttingDown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Server is shutting down")
+    }
+}
+
+impl std::error::Error for ShuttingDown {}
+
+/// Error for requests the client cancelled before they finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestCancelled;
+
+impl fmt::Display for RequestCancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Request was cancelled")
+    }
+}
+
+impl std::error::Error for RequestCancelled {}
+
+/// Error for a request whose handling panicked. The worker survives and
+/// takes the next request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerPanicked {
+    pub request: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for WorkerPanicked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} failed with an internal error: {}",
+            self.request, self.message
+        )
+    }
+}
+
+impl std::error::Error for WorkerPanicked {}
+
+/// The message a panic was raised with.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// `config` with the input hash of `sources`, so a chunk directory is
+/// written with its manifest.
+fn with_input_hash(
+    config: &MermaidConfig,
+    sources: &[SourceUnit],
+    workspace_folder: &Path,
+) -> MermaidConfig {
+    MermaidConfig {
+        input_hash: Some(manifest::input_hash(sources, workspace_folder)),
+        ..config.clone()
+    }
+}
+
 pub struct GeneratorWorker {
     adapter: TraverseAdapter,
+    config: Config,
+    /// Where `config` is refreshed from before each request.
+    shared_config: Arc<SharedConfig>,
+    phases: PhaseTracker,
+    graphs: Arc<GraphCache>,
+    documents: Arc<DocumentStore>,
+    output: Arc<OutputSettings>,
+    /// Threads that parse workspace files concurrently.
+    parse_pool: Arc<rayon::ThreadPool>,
+    /// Token of the running request.
+    cancel: CancelToken,
+    /// Where the running request's progress goes, if anywhere.
+    progress: Option<ProgressCallback>,
+    /// Files the running request could not read.
+    skipped: Mutex<BTreeSet<PathBuf>>,
+    /// What the running request produced, with its format.
+    artifacts: Vec<Artifact>,
 }
 
 impl GeneratorWorker {
-    pub fn new() -> Result<Self> {
+    pub fn new(config: Config) -> Result<Self> {
+        let parse_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.parse_threads.unwrap_or(0))
+            .thread_name(|index| format!("traverse-parse-{}", index))
+            .build()
+            .context("Failed to start parse threads")?;
+        let graphs = Arc::new(GraphCache::new(config.cache_budget_bytes()));
         Ok(GeneratorWorker {
-            adapter: TraverseAdapter::new()?,
+            adapter: TraverseAdapter::new()?
+                .with_config(config.clone())
+                .with_graph_cache(graphs.clone()),
+            parse_pool: Arc::new(parse_pool),
+            cancel: CancelToken::default(),
+            progress: None,
+            graphs,
+            output: Arc::new(OutputSettings::new(OutputLocation::from_config(&config))),
+            shared_config: Arc::new(SharedConfig::new(config.clone())),
+            config,
+            phases: PhaseTracker::default(),
+            documents: Arc::new(DocumentStore::new()),
+            skipped: Mutex::default(),
+            artifacts: Vec::new(),
         })
     }
 
+    /// Shares `graphs` and the parse trees kept with them with other
+    /// workers, so a restarted worker keeps what its predecessor built.
+    pub fn with_graph_cache(mut self, graphs: Arc<GraphCache>) -> Self {
+        self.adapter = self.adapter.with_graph_cache(graphs.clone());
+        self.graphs = graphs;
+        self
+    }
+
+    /// Reads sources through `documents`, so unsaved edits are analyzed.
+    pub fn with_documents(mut self, documents: Arc<DocumentStore>) -> Self {
+        self.documents = documents;
+        self
+    }
+
+    /// Reads output locations from `output`, so configuration changes
+    /// apply to the next request.
+    pub fn with_output_settings(mut self, output: Arc<OutputSettings>) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Reads settings from `config` when each request starts, so
+    /// configuration changes apply to the next request.
+    pub fn with_shared_config(mut self, config: Arc<SharedConfig>) -> Self {
+        self.shared_config = config;
+        self
+    }
+
+    /// Tracks the phase of the running request for the watchdog.
+    pub fn phases(&self) -> PhaseTracker {
+        self.phases.clone()
+    }
+
     pub fn run(mut self, rx: mpsc::Receiver<GenerationRequest>) {
         info!("Generator worker started");
 
-        for request in rx.iter() {
-            match request {
-                GenerationRequest::Shutdown => {
-                    info!("Generator worker shutting down");
-                    break;
+        let pool = Arc::clone(&self.parse_pool);
+        for mut request in rx.iter() {
+            self.phases.start(request.kind());
+            if let GenerationRequest::Shutdown = request {
+                // Answer everything still queued so no caller is left
+                // waiting on a dropped reply channel.
+                let pending: Vec<_> = rx
+                    .try_iter()
+                    .filter_map(GenerationRequest::into_reply)
+                    .collect();
+                info!(
+                    "Generator worker shutting down, rejecting {} pending requests",
+                    pending.len()
+                );
+                for tx in pending {
+                    let _ = tx.send(Err(ShuttingDown.into()));
                 }
-                GenerationRequest::GenerateCallGraphDiagram {
-                    uris,
-                    contract_name,
-                    tx,
-                } => {
-                    debug!(
-                        "Generating call graph diagram (DOT) for {:?} in {} files",
-                        contract_name,
-                        uris.len()
-                    );
-                    let result = self.generate_call_graph_diagram(&uris, contract_name.as_deref());
-                    let _ = tx.send(result);
+                break;
+            }
+            if let Some(cancel) = request
+                .cancel_token()
+                .filter(|cancel| cancel.load(Ordering::Relaxed))
+                .cloned()
+            {
+                debug!("Skipping cancelled {}", request.kind());
+                if let Some(tx) = request.into_reply() {
+                    let _ = tx.send(Err(cancel.error()));
                 }
-                GenerationRequest::GenerateMermaidFlowchart {
-                    uris,
-                    contract_name,
-                    no_chunk,
-                    tx,
-                } => {
-                    debug!(
-                        "Generating Mermaid flowchart for {:?} in {} files (no_chunk: {})",
-                        contract_name,
-                        uris.len(),
-                        no_chunk
-                    );
-                    let result =
-                        self.generate_mermaid_flowchart(&uris, contract_name.as_deref(), no_chunk);
-                    let _ = tx.send(result);
+                self.phases.finish();
+                continue;
+            }
+            self.config = self.shared_config.current();
+            self.adapter.set_config(self.config.clone());
+            self.cancel = request.cancel_token().cloned().unwrap_or_default();
+            self.progress = request.take_progress();
+            // The reply is held here so a panicking request still gets one.
+            let kind = request.kind();
+            let (inner_tx, mut inner_rx) = oneshot::channel();
+            let reply = request.replace_reply(inner_tx);
+            // Parses started while handling the request run on the pool.
+            let span = self.cancel.span();
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                pool.install(|| span.in_scope(|| self.handle(request)))
+            }));
+            let result = match outcome {
+                Ok(()) => inner_rx
+                    .try_recv()
+                    .unwrap_or_else(|_| Err(anyhow!("{} produced no result", kind))),
+                Err(payload) => {
+                    let panicked = WorkerPanicked {
+                        request: kind,
+                        message: panic_message(payload.as_ref()),
+                    };
+                    error!("{}", panicked);
+                    Err(panicked.into())
                 }
-                GenerationRequest::GenerateAllDiagrams {
-                    uris,
+            };
+            let result = result.map(|text| self.fit_result(kind, text));
+            let artifacts = std::mem::take(&mut self.artifacts);
+            let run = RunInfo {
+                timings: self.phases.timings(),
+                skipped_files: std::mem::take(&mut *self.skipped_files())
+                    .into_iter()
+                    .collect(),
+                artifacts,
+            };
+            let result = result.map(|text| run.attach(text));
+            self.apply_retention();
+            if let Some(reply) = reply {
+                let _ = reply.send(result);
+            }
+            self.progress = None;
+            self.phases.finish();
+        }
+    }
+
+    /// Removes the oldest runs from the output directory once it holds more
+    /// than the configured retention allows.
+    fn apply_retention(&self) {
+        if self.config.retention.is_unlimited() {
+            return;
+        }
+        let dir = self.output.current().dir;
+        match retention::clean(&dir, &self.config.retention) {
+            Ok(cleanup) if !cleanup.removed.is_empty() => info!(
+                "Removed {} old runs ({} bytes) from {}",
+                cleanup.removed.len(),
+                cleanup.freed_bytes,
+                dir.display()
+            ),
+            Ok(_) => {}
+            Err(e) => warn!("Cleaning {} failed: {:#}", dir.display(), e),
+        }
+    }
+
+    /// Replaces a result past `max_response_bytes` with a
+    /// `{"result_file": ...}` pointing to where it was written, which is
+    /// then its only artifact, or keeps it if the file cannot be written.
+    fn fit_result(&mut self, kind: &str, text: String) -> String {
+        let Some(limit) = self.config.max_response_bytes() else {
+            return text;
+        };
+        if text.len() <= limit {
+            return text;
+        }
+        let dir = self.output.current().dir.join(large_result::RESULTS_DIR);
+        match large_result::write(&text, &dir, kind) {
+            Ok(file) => {
+                info!(
+                    "Wrote {} result of {} bytes to {}",
+                    kind,
+                    file.bytes,
+                    file.path.display()
+                );
+                self.artifacts = vec![Artifact::file(
+                    "result_file",
+                    ArtifactFormat::Json,
+                    &file.path,
+                )];
+                serde_json::json!({ "result_file": file }).to_string()
+            }
+            Err(e) => {
+                warn!("Failed to write large {} result: {}", kind, e);
+                text
+            }
+        }
+    }
+
+    fn handle(&mut self, request: GenerationRequest) {
+        match request {
+            GenerationRequest::Shutdown => {}
+            GenerationRequest::GenerateCallGraphDiagram {
+                uris,
+                contract_name,
+                dot_config,
+                filter,
+                coverage_file,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Generating call graph diagram (DOT) for {:?} in {} files",
                     contract_name,
-                    tx,
-                } => {
-                    debug!(
-                        "Generating all diagrams for {:?} in {} files",
-                        contract_name,
-                        uris.len()
-                    );
-                    let result = self.generate_all_diagrams(&uris, contract_name.as_deref());
-                    let _ = tx.send(result);
-                }
-                GenerationRequest::GenerateStorageLayout {
-                    uris,
+                    uris.len()
+                );
+                let result = self.generate_call_graph_diagram(
+                    &uris,
+                    contract_name.as_deref(),
+                    &dot_config,
+                    &filter,
+                    coverage_file.as_deref(),
+                );
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GenerateMermaidFlowchart {
+                uris,
+                workspace_folder,
+                contract_name,
+                config,
+                filter,
+                tx,
+                ..
+            } => {
+                let config = config.with_output(&self.output.current());
+                debug!(
+                    "Generating Mermaid flowchart for {:?} in {} files (no_chunk: {})",
                     contract_name,
-                    tx,
-                } => {
-                    debug!(
-                        "Generating storage layout for {} in {} files",
-                        contract_name,
-                        uris.len()
-                    );
-                    let result = self.generate_storage_layout(&uris, &contract_name);
-                    let _ = tx.send(result);
-                }
+                    uris.len(),
+                    config.no_chunk
+                );
+                let result = self.generate_mermaid_flowchart(
+                    &uris,
+                    &workspace_folder,
+                    contract_name.as_deref(),
+                    &config,
+                    &filter,
+                );
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GenerateAllDiagrams {
+                uris,
+                workspace_folder,
+                contract_name,
+                dot_config,
+                mermaid_config,
+                filter,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Generating all diagrams for {:?} in {} files",
+                    contract_name,
+                    uris.len()
+                );
+                let result = self.generate_all_diagrams(
+                    &uris,
+                    &workspace_folder,
+                    contract_name.as_deref(),
+                    &dot_config,
+                    &mermaid_config.with_output(&self.output.current()),
+                    &filter,
+                );
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GenerateStorageLayout {
+                uris,
+                contract_name,
+                template,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Generating storage layout for {} in {} files",
+                    contract_name,
+                    uris.len()
+                );
+                let result =
+                    self.generate_storage_layout(&uris, &contract_name, template.as_deref());
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GenerateAuditReport {
+                uris,
+                workspace_folder,
+                min_confidence,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Generating audit report for {} files (min confidence: {})",
+                    uris.len(),
+                    min_confidence.as_str()
+                );
+                let result = self.generate_audit_report(&uris, &workspace_folder, min_confidence);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::SummarizeFindings {
+                uris,
+                workspace_folder,
+                min_confidence,
+                top_contracts,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Summarizing findings for {} files (min confidence: {})",
+                    uris.len(),
+                    min_confidence.as_str()
+                );
+                let result = self.summarize_findings(
+                    &uris,
+                    &workspace_folder,
+                    min_confidence,
+                    top_contracts,
+                );
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GeneratePrComment {
+                uris,
+                workspace_folder,
+                base_ref,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Generating PR comment for {} files against {}",
+                    uris.len(),
+                    base_ref
+                );
+                let result = self.generate_pr_comment(&uris, &workspace_folder, &base_ref);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::ListFuzzTargets { uris, tx, .. } => {
+                debug!("Listing fuzz targets in {} files", uris.len());
+                let result = self.list_fuzz_targets(&uris);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::AuditOverrides { uris, tx, .. } => {
+                debug!("Auditing overrides in {} files", uris.len());
+                let result = self.audit_overrides(&uris);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::CrossReference { uris, tx, .. } => {
+                debug!(
+                    "Cross-referencing enums and constants in {} files",
+                    uris.len()
+                );
+                let result = self.cross_reference(&uris);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::StorageHeatmap {
+                uris,
+                contract_name,
+                tx,
+                ..
+            } => {
+                debug!("Building storage heatmap for {} files", uris.len());
+                let result = self.storage_heatmap(&uris, contract_name.as_deref());
+                let _ = tx.send(result);
+            }
+            GenerationRequest::CallMatrix { uris, tx, .. } => {
+                debug!("Counting calls between contracts in {} files", uris.len());
+                let result = self.call_matrix(&uris);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GovernanceReport { uris, tx, .. } => {
+                debug!("Analyzing admin controls in {} files", uris.len());
+                let result = self.governance_report(&uris);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::PauseCoverage { uris, tx, .. } => {
+                debug!("Checking pause coverage in {} files", uris.len());
+                let result = self.pause_coverage(&uris);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::ExternalDependencies {
+                uris,
+                contract_name,
+                tx,
+                ..
+            } => {
+                debug!("Finding external dependencies in {} files", uris.len());
+                let result = self.external_dependencies(&uris, contract_name.as_deref());
+                let _ = tx.send(result);
+            }
+            GenerationRequest::PrivilegePaths {
+                uris,
+                privileges,
+                tx,
+                ..
+            } => {
+                debug!("Finding privilege paths in {} files", uris.len());
+                let result = self.privilege_paths(&uris, &privileges);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::FindClones {
+                uris,
+                min_nodes,
+                tx,
+                ..
+            } => {
+                debug!("Finding function clones in {} files", uris.len());
+                let result = self.find_clones(&uris, min_nodes);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::MagicNumbers { uris, tx, .. } => {
+                debug!("Listing magic numbers in {} files", uris.len());
+                let result = self.magic_numbers(&uris);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::ExportGraph {
+                uris,
+                workspace_folder,
+                format,
+                filter,
+                consumers_file,
+                anonymize,
+                tx,
+                ..
+            } => {
+                debug!("Exporting graph as {:?} for {} files", format, uris.len());
+                let result = self.export_graph(
+                    &uris,
+                    &workspace_folder,
+                    format,
+                    &filter,
+                    consumers_file.as_deref(),
+                    anonymize,
+                );
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GenerateFunctionDiagram {
+                uris,
+                workspace_folder,
+                contract_name,
+                function_name,
+                config,
+                filter,
+                tx,
+                ..
+            } => {
+                let config = config.with_output(&self.output.current());
+                debug!(
+                    "Generating function diagram for {}.{} in {} files",
+                    contract_name,
+                    function_name,
+                    uris.len()
+                );
+                let result = self.generate_function_diagram(
+                    &uris,
+                    &workspace_folder,
+                    &contract_name,
+                    &function_name,
+                    &config,
+                    &filter,
+                );
+                let _ = tx.send(result);
+            }
+            GenerationRequest::GenerateFunctionDiagramAt {
+                uris,
+                workspace_folder,
+                position,
+                config,
+                filter,
+                tx,
+                ..
+            } => {
+                let config = config.with_output(&self.output.current());
+                debug!(
+                    "Generating function diagram at {}:{} in {} files",
+                    position.text_document.uri,
+                    position.position.line,
+                    uris.len()
+                );
+                let result = self
+                    .function_at(&position)
+                    .and_then(|(contract, function)| {
+                        self.generate_function_diagram(
+                            &uris,
+                            &workspace_folder,
+                            &contract,
+                            &function,
+                            &config,
+                            &filter,
+                        )
+                    });
+                let _ = tx.send(result);
+            }
+            GenerationRequest::RegenerateChunk {
+                uris,
+                workspace_folder,
+                contract_name,
+                function_name,
+                chunk,
+                config,
+                filter,
+                tx,
+                ..
+            } => {
+                let config = config.with_output(&self.output.current());
+                debug!("Regenerating chunk {} from {} files", chunk, uris.len());
+                let result = self.read_sources(&uris).and_then(|sources| {
+                    let call_graph = self.scoped_call_graph(
+                        &sources,
+                        contract_name.as_deref(),
+                        function_name.as_deref(),
+                        &filter,
+                    )?;
+                    self.regenerate_chunk(&sources, &workspace_folder, &call_graph, chunk, &config)
+                });
+                let _ = tx.send(result);
+            }
+            GenerationRequest::RenderDiagram {
+                uris,
+                contract_name,
+                renderer,
+                options,
+                filter,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Rendering {} diagram for {:?} in {} files",
+                    renderer.name(),
+                    contract_name,
+                    uris.len()
+                );
+                let result = self.render_diagram(
+                    &uris,
+                    contract_name.as_deref(),
+                    renderer,
+                    &options,
+                    &filter,
+                );
+                let _ = tx.send(result);
+            }
+            GenerationRequest::CompareStorage {
+                uris,
+                left_contract,
+                right_contract,
+                tx,
+                ..
+            } => {
+                debug!(
+                    "Comparing storage of {} and {} in {} files",
+                    left_contract,
+                    right_contract,
+                    uris.len()
+                );
+                let result = self.compare_storage(&uris, &left_contract, &right_contract);
+                let _ = tx.send(result);
+            }
+            GenerationRequest::ImportTrace {
+                uris,
+                trace,
+                trace_file,
+                tx,
+                ..
+            } => {
+                debug!("Importing trace against {} files", uris.len());
+                let result = self.import_trace(&uris, trace, trace_file.as_deref());
+                let _ = tx.send(result);
+            }
+            GenerationRequest::WarmCache { uris, tx, .. } => {
+                debug!("Warming cache for {} files", uris.len());
+                let result = self.warm_cache(&uris);
+                let _ = tx.send(result);
             }
         }
     }
 
-    fn get_or_build_call_graph(&mut self, uris: &[Url]) -> Result<CallGraph> {
-        let mut combined_source = String::new();
+    /// Records that the running request entered `phase`, unless the client
+    /// has cancelled it.
+    fn enter(&self, phase: &'static str) -> Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            debug!("Cancelled before {}", phase);
+            return Err(self.cancel.error());
+        }
+        self.phases.enter(phase);
+        Ok(())
+    }
 
+    /// Reports progress of the running request, if it asked for reports.
+    fn report(&self, message: &str, percentage: u32) {
+        if let Some(progress) = &self.progress {
+            progress(message, percentage);
+        }
+    }
+
+    /// The sources of `uris`. Files that cannot be read, e.g. because they
+    /// were deleted since the command listed them, are left out and
+    /// reported with the result; the request fails only if none can be read.
+    fn read_sources(&self, uris: &[Url]) -> Result<Vec<SourceUnit>> {
+        self.enter("reading sources")?;
+        let mut sources = Vec::with_capacity(uris.len());
         for uri in uris {
             let path = uri
                 .to_file_path()
                 .map_err(|_| anyhow::anyhow!("Invalid URI"))?;
-            let content = std::fs::read_to_string(&path)?;
-            combined_source.push_str(&content);
-            combined_source.push('\n');
+            let content = match self.documents.read(uri) {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Skipping {}: {}", path.display(), e);
+                    self.skipped_files().insert(path);
+                    continue;
+                }
+            };
+            let mut unit = SourceUnit { path, content };
+            profiles::apply(&self.config.profiles, &mut unit);
+            sources.push(unit);
+        }
+        if sources.is_empty() && !uris.is_empty() {
+            return Err(anyhow!("None of the {} files could be read", uris.len()));
+        }
+        Ok(sources)
+    }
+
+    fn skipped_files(&self) -> std::sync::MutexGuard<'_, BTreeSet<PathBuf>> {
+        self.skipped.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Writes `manifest.json` into `dir` describing `files`, rendered from
+    /// `sources`, and returns its path.
+    fn write_manifest(
+        &self,
+        sources: &[SourceUnit],
+        workspace_folder: &Path,
+        dir: &Path,
+        files: &[PathBuf],
+    ) -> Result<PathBuf> {
+        self.enter("writing manifest")?;
+        let input_hash = manifest::input_hash(sources, workspace_folder);
+        Manifest::build(dir, files, &input_hash)?.write(dir)
+    }
+
+    fn get_or_build_call_graph(&mut self, uris: &[Url]) -> Result<CallGraph> {
+        self.call_graph(&self.read_sources(uris)?)
+    }
+
+    fn call_graph(&self, sources: &[SourceUnit]) -> Result<CallGraph> {
+        self.graphs.get_or_build(GraphKind::Plain, sources, || {
+            self.enter("building call graph")?;
+            let total = sources.len();
+            // Parsing is most of the build, so it fills the first 60%.
+            self.adapter
+                .build_workspace_call_graph_with_progress(sources, &|parsed| {
+                    if parsed < total {
+                        let message = format!("Parsing files ({}/{})", parsed, total);
+                        self.report(&message, (parsed * 60 / total) as u32);
+                    } else {
+                        self.report("Building call graph", 60);
+                    }
+                })
+        })
+    }
+
+    /// Call graph with unresolved calls as placeholder nodes.
+    fn call_graph_with_placeholders(&self, sources: &[SourceUnit]) -> Result<CallGraph> {
+        self.graphs
+            .get_or_build(GraphKind::WithPlaceholders, sources, || {
+                let mut call_graph = self.call_graph(sources)?;
+                self.enter("finding unresolved calls")?;
+                self.report("Finding unresolved calls", 80);
+                let unresolved = unresolved_calls::find(sources, &call_graph)?;
+                unresolved_calls::add_placeholders(&mut call_graph, &unresolved);
+                Ok(call_graph)
+            })
+    }
+
+    /// Call graph for rendering: unresolved calls become placeholder nodes,
+    /// the graph is narrowed to `contract_name` and what it calls, or cut
+    /// at `max_call_depth` calls from its entry points, then the request's
+    /// filter is applied. The result is cached per scope.
+    fn get_diagram_call_graph(
+        &mut self,
+        uris: &[Url],
+        contract_name: Option<&str>,
+        filter: &GraphFilter,
+    ) -> Result<CallGraph> {
+        let sources = self.read_sources(uris)?;
+        self.diagram_call_graph(&sources, contract_name, filter)
+    }
+
+    /// [`Self::get_diagram_call_graph`] of sources already read.
+    fn diagram_call_graph(
+        &mut self,
+        sources: &[SourceUnit],
+        contract_name: Option<&str>,
+        filter: &GraphFilter,
+    ) -> Result<CallGraph> {
+        let max_depth = self.config.max_call_depth;
+        if contract_name.is_none() && filter.is_empty() && max_depth.is_none() {
+            return self.call_graph_with_placeholders(sources);
         }
+        let scope = Sha256::digest(format!("{:?}", (contract_name, filter, max_depth))).into();
+        self.graphs
+            .get_or_build(GraphKind::Scoped(scope), sources, || {
+                let mut call_graph = self.call_graph_with_placeholders(sources)?;
+                if let Some(contract) = contract_name {
+                    call_graph = graph_filter::contract_subgraph(&call_graph, contract, max_depth)
+                        .ok_or_else(|| {
+                            anyhow!("Contract {} not found in the call graph", contract)
+                        })?;
+                } else if let Some(max_depth) = max_depth {
+                    call_graph = graph_filter::limit_depth(&call_graph, max_depth);
+                }
+                if filter.is_empty() {
+                    return Ok(call_graph);
+                }
 
-        self.adapter.build_call_graph(&combined_source)
+                self.enter("filtering call graph")?;
+                self.report("Filtering call graph", 85);
+                // Mutability is only known from the sources, so parse them
+                // only when a filter needs it.
+                if !filter.hide_view_functions {
+                    return Ok(graph_filter::apply(&call_graph, filter, None));
+                }
+                let parsed = parse_sources_with_profiles(sources, &self.config.profiles)?;
+                let model = SourceModel::from_parsed(&parsed);
+                Ok(graph_filter::apply(&call_graph, filter, Some(&model)))
+            })
     }
 
     fn generate_call_graph_diagram(
         &mut self,
         uris: &[Url],
-        _contract_name: Option<&str>,
+        contract_name: Option<&str>,
+        dot_config: &DotConfig,
+        filter: &GraphFilter,
+        coverage_file: Option<&Path>,
     ) -> Result<String> {
-        let call_graph = self.get_or_build_call_graph(uris)?;
+        // Read once: the graph, the source map and the coverage overlay
+        // must all see the same text.
+        let sources = self.read_sources(uris)?;
+        let call_graph = self.diagram_call_graph(&sources, contract_name, filter)?;
 
-        let dot_diagram = self.adapter.generate_dot_diagram(&call_graph)?;
+        self.enter("rendering DOT")?;
+        self.report("Rendering DOT", 90);
+        let dot_diagram = self
+            .adapter
+            .generate_dot_with_config(&call_graph, dot_config)?;
+        // Keyed by DOT node name, so clients can jump from a node to its
+        // definition.
+        let locations: BTreeMap<String, Location> = SourceMap::new(&sources)
+            .origins(&call_graph)
+            .into_iter()
+            .map(|(id, location)| (format!("n{}", id), location))
+            .collect();
+        let Some(coverage_file) = coverage_file else {
+            self.artifacts.push(Artifact::inline(
+                "dot",
+                ArtifactFormat::Dot,
+                dot_diagram.as_str(),
+            ));
+            return Ok(serde_json::json!({
+                "preview": preview::head(&dot_diagram),
+                "dot": dot_diagram,
+                "locations": locations,
+            })
+            .to_string());
+        };
+
+        self.enter("overlaying coverage")?;
+        self.report("Overlaying coverage", 95);
+        let records = coverage::load(coverage_file)?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        let coverage = coverage::overlay(&call_graph, &records, &model);
+        let dot_diagram = coverage::color_dot(&dot_diagram, &coverage);
+        self.artifacts.push(Artifact::inline(
+            "dot",
+            ArtifactFormat::Dot,
+            dot_diagram.as_str(),
+        ));
+        Ok(serde_json::json!({
+            "preview": preview::head(&dot_diagram),
+            "dot": dot_diagram,
+            "locations": locations,
+            "coverage": coverage,
+        })
+        .to_string())
+    }
+
+    fn render_diagram(
+        &mut self,
+        uris: &[Url],
+        contract_name: Option<&str>,
+        renderer: &dyn DiagramRenderer,
+        options: &RenderOptions,
+        filter: &GraphFilter,
+    ) -> Result<String> {
+        let call_graph = self.get_diagram_call_graph(uris, contract_name, filter)?;
+
+        self.enter("rendering diagram")?;
+        let diagram = renderer.render(&call_graph, options)?;
+        self.artifacts.push(Artifact::inline(
+            "diagram",
+            renderer.format(),
+            diagram.as_str(),
+        ));
         Ok(serde_json::json!({
-            "dot": dot_diagram
+            "renderer": renderer.name(),
+            "extension": renderer.extension(),
+            "preview": preview::head(&diagram),
+            "diagram": diagram,
         })
         .to_string())
     }
@@ -149,30 +1620,119 @@ impl GeneratorWorker {
     fn generate_mermaid_flowchart(
         &mut self,
         uris: &[Url],
-        _contract_name: Option<&str>,
-        no_chunk: bool,
+        workspace_folder: &Path,
+        contract_name: Option<&str>,
+        config: &MermaidConfig,
+        filter: &GraphFilter,
     ) -> Result<String> {
-        let call_graph = self.get_or_build_call_graph(uris)?;
+        let sources = self.read_sources(uris)?;
+        let call_graph = self.diagram_call_graph(&sources, contract_name, filter)?;
+        self.render_mermaid(&sources, workspace_folder, &call_graph, config)
+    }
+
+    fn generate_function_diagram(
+        &mut self,
+        uris: &[Url],
+        workspace_folder: &Path,
+        contract_name: &str,
+        function_name: &str,
+        config: &MermaidConfig,
+        filter: &GraphFilter,
+    ) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        let call_graph =
+            self.function_call_graph(&sources, contract_name, function_name, filter)?;
+        self.render_mermaid(&sources, workspace_folder, &call_graph, config)
+    }
 
-        let config = MermaidConfig {
-            no_chunk,
-            chunk_dir: PathBuf::from("./traverse-output/sequence-diagrams/chunks/"),
+    /// `contract_name.function_name` and its callees, filtered, with the
+    /// function as the only entry point.
+    fn function_call_graph(
+        &mut self,
+        sources: &[SourceUnit],
+        contract_name: &str,
+        function_name: &str,
+        filter: &GraphFilter,
+    ) -> Result<CallGraph> {
+        let call_graph = self.call_graph_with_placeholders(sources)?;
+        self.enter("filtering call graph")?;
+        let call_graph = graph_filter::function_subgraph(
+            &call_graph,
+            contract_name,
+            function_name,
+            self.config.max_call_depth,
+        )
+        .ok_or_else(|| {
+            anyhow!(
+                "Function {}.{} not found in the call graph",
+                contract_name,
+                function_name
+            )
+        })?;
+        let mut call_graph = if filter.hide_view_functions {
+            let parsed = parse_sources_with_profiles(sources, &self.config.profiles)?;
+            let model = SourceModel::from_parsed(&parsed);
+            graph_filter::apply(&call_graph, filter, Some(&model))
+        } else {
+            graph_filter::apply(&call_graph, filter, None)
         };
+        graph_filter::single_entry_point(&mut call_graph, contract_name, function_name);
+        Ok(call_graph)
+    }
 
+    /// The contract and name of the function enclosing `position`, read as
+    /// the file's dialect reads it.
+    fn function_at(&self, position: &TextDocumentPositionParams) -> Result<(String, String)> {
+        let unit = self
+            .read_sources(std::slice::from_ref(&position.text_document.uri))?
+            .remove(0);
+        symbol_index::enclosing_function(&unit.content, position.position).ok_or_else(|| {
+            anyhow!(
+                "No function at {}:{}:{}",
+                unit.path.display(),
+                position.position.line + 1,
+                position.position.character + 1
+            )
+        })
+    }
+
+    fn render_mermaid(
+        &mut self,
+        sources: &[SourceUnit],
+        workspace_folder: &Path,
+        call_graph: &CallGraph,
+        config: &MermaidConfig,
+    ) -> Result<String> {
+        self.enter("rendering Mermaid")?;
+        self.report("Emitting Mermaid", 90);
+        let config = with_input_hash(config, sources, workspace_folder);
         let result = self
             .adapter
-            .generate_mermaid_with_config(&call_graph, &config)?;
+            .generate_mermaid_with_config(call_graph, &config)?;
+        self.artifacts.push(Artifact::inline(
+            "mermaid",
+            ArtifactFormat::Mermaid,
+            result.content.as_str(),
+        ));
 
-        if result.is_chunked {
+        if let Some(chunk_dir) = &result.chunk_dir {
+            let manifest = chunk_dir.join(MANIFEST_FILE);
+            self.artifacts.extend([
+                Artifact::file("chunk_dir", ArtifactFormat::Mermaid, chunk_dir),
+                Artifact::file("manifest", ArtifactFormat::Json, &manifest),
+            ]);
             Ok(serde_json::json!({
+                "preview": preview::head(&result.content),
                 "mermaid": result.content,
                 "is_chunked": true,
                 "chunks": result.chunks,
                 "chunk_dir": result.chunk_dir,
+                "manifest": manifest,
             })
             .to_string())
         } else {
             Ok(serde_json::json!({
+                "preview": preview::head(&result.content),
                 "mermaid": result.content,
                 "is_chunked": false,
             })
@@ -180,94 +1740,680 @@ impl GeneratorWorker {
         }
     }
 
+    /// The call graph a sequence diagram of the workspace, a contract or,
+    /// with `function_name`, one function of the contract is rendered from.
+    fn scoped_call_graph(
+        &mut self,
+        sources: &[SourceUnit],
+        contract_name: Option<&str>,
+        function_name: Option<&str>,
+        filter: &GraphFilter,
+    ) -> Result<CallGraph> {
+        match (contract_name, function_name) {
+            (Some(contract), Some(function)) => {
+                self.function_call_graph(sources, contract, function, filter)
+            }
+            (None, Some(function)) => Err(anyhow!(
+                "Function {} needs the contract it belongs to",
+                function
+            )),
+            (contract, None) => self.diagram_call_graph(sources, contract, filter),
+        }
+    }
+
+    /// Re-renders chunk `chunk` of the diagram of `call_graph`, leaving the
+    /// rest of the chunk directory as it is, and updates the manifest.
+    fn regenerate_chunk(
+        &mut self,
+        sources: &[SourceUnit],
+        workspace_folder: &Path,
+        call_graph: &CallGraph,
+        chunk: usize,
+        config: &MermaidConfig,
+    ) -> Result<String> {
+        self.enter("rendering Mermaid")?;
+        let config = with_input_hash(config, sources, workspace_folder);
+        let (path, content) = traverse_adapter::regenerate_chunk(call_graph, &config, chunk)?;
+        let manifest = config.chunk_dir.join(MANIFEST_FILE);
+        self.artifacts.extend([
+            Artifact::inline("mermaid", ArtifactFormat::Mermaid, content.as_str()),
+            Artifact::file("file", ArtifactFormat::Mermaid, &path),
+            Artifact::file("chunk_dir", ArtifactFormat::Mermaid, &config.chunk_dir),
+            Artifact::file("manifest", ArtifactFormat::Json, &manifest),
+        ]);
+        Ok(serde_json::json!({
+            "chunk": chunk,
+            "file": path,
+            "mermaid": content,
+            "chunk_dir": config.chunk_dir,
+            "manifest": manifest,
+        })
+        .to_string())
+    }
+
     fn generate_all_diagrams(
         &mut self,
         uris: &[Url],
-        _contract_name: Option<&str>,
+        workspace_folder: &Path,
+        contract_name: Option<&str>,
+        dot_config: &DotConfig,
+        mermaid_config: &MermaidConfig,
+        filter: &GraphFilter,
     ) -> Result<String> {
-        let call_graph = self.get_or_build_call_graph(uris)?;
+        let sources = self.read_sources(uris)?;
+        let call_graph = self.diagram_call_graph(&sources, contract_name, filter)?;
+        let origins = SourceMap::new(&sources).origins(&call_graph);
 
-        let dot_diagram = self.adapter.generate_dot_diagram(&call_graph)?;
-        let mermaid_config = MermaidConfig::default();
+        self.enter("rendering DOT")?;
+        self.report("Rendering DOT", 90);
+        let dot_diagram = self
+            .adapter
+            .generate_dot_with_config(&call_graph, dot_config)?;
+        self.enter("rendering Mermaid")?;
+        self.report("Emitting Mermaid", 95);
+        let mermaid_config = with_input_hash(mermaid_config, &sources, workspace_folder);
         let mermaid_result = self
             .adapter
             .generate_mermaid_with_config(&call_graph, &mermaid_config)?;
 
+        let manifest = mermaid_result
+            .chunk_dir
+            .as_ref()
+            .map(|chunk_dir| chunk_dir.join(MANIFEST_FILE));
+        self.artifacts.extend([
+            Artifact::inline("dot", ArtifactFormat::Dot, dot_diagram.as_str()),
+            Artifact::inline(
+                "mermaid",
+                ArtifactFormat::Mermaid,
+                mermaid_result.content.as_str(),
+            ),
+        ]);
+        if let (Some(chunk_dir), Some(manifest)) = (&mermaid_result.chunk_dir, &manifest) {
+            self.artifacts.extend([
+                Artifact::file("chunk_dir", ArtifactFormat::Mermaid, chunk_dir),
+                Artifact::file("manifest", ArtifactFormat::Json, manifest),
+            ]);
+        }
+
         Ok(serde_json::json!({
+            "preview": {
+                "dot": preview::head(&dot_diagram),
+                "mermaid": preview::head(&mermaid_result.content),
+            },
             "dot": dot_diagram,
             "mermaid": mermaid_result.content,
             "is_chunked": mermaid_result.is_chunked,
-            "chunk_dir": mermaid_result.chunk_dir
+            "chunk_dir": mermaid_result.chunk_dir,
+            "manifest": manifest,
+            "origins": origins,
         })
         .to_string())
     }
 
-    fn generate_storage_layout(&mut self, uris: &[Url], _contract_name: &str) -> Result<String> {
+    fn generate_storage_layout(
+        &mut self,
+        uris: &[Url],
+        _contract_name: &str,
+        template: Option<&Path>,
+    ) -> Result<String> {
+        // A broken template fails before the graph is built.
+        let template = template
+            .map(|path| {
+                let source = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read template {}", path.display()))?;
+                Template::parse(&source, template::is_html(path))
+                    .with_context(|| format!("Invalid template {}", path.display()))
+            })
+            .transpose()?;
         let call_graph = self.get_or_build_call_graph(uris)?;
 
-        let storage_summary_map =
-            traverse_graph::storage_access::analyze_storage_access(&call_graph);
-        let mut md = String::from("# Storage Access Analysis\n\n");
-        md.push_str(&format!(
-            "**Files analyzed:** {} Solidity files\n\n",
-            uris.len()
+        self.enter("analyzing storage access")?;
+        StorageAccessReport::new(&call_graph, uris.len()).render(template.as_ref())
+    }
+
+    fn generate_audit_report(
+        &mut self,
+        uris: &[Url],
+        workspace_folder: &Path,
+        min_confidence: Confidence,
+    ) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        let (findings, suppressed) =
+            self.audit_findings(&sources, workspace_folder, min_confidence)?;
+        let report = analysis::render_report(&findings, sources.len());
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
         ));
-        md.push_str("| Endpoint | Reads | Writes |\n");
-        md.push_str("|----------|-------|--------|\n");
-
-        let mut sorted_entries: Vec<_> = storage_summary_map.iter().collect();
-        sorted_entries.sort_by_key(|(node_id, _)| {
-            call_graph
-                .nodes
-                .get(**node_id)
-                .map_or_else(String::new, |n| {
-                    format!(
-                        "{}.{}",
-                        n.contract_name.as_deref().unwrap_or("Global"),
-                        n.name
-                    )
-                })
-        });
-
-        for (func_node_id, summary) in sorted_entries {
-            if let Some(func_node) = call_graph.nodes.get(*func_node_id) {
-                let endpoint_name = format!(
-                    "{}.{}",
-                    func_node.contract_name.as_deref().unwrap_or("Global"),
-                    func_node.name
-                );
 
-                let reads_vec: Vec<String> = summary
-                    .reads
-                    .iter()
-                    .map(|id| {
-                        call_graph.nodes.get(*id).map_or_else(
-                            || format!("UnknownVar({})", id),
-                            |n| format!("{}.{}", n.contract_name.as_deref().unwrap_or("?"), n.name),
-                        )
-                    })
-                    .collect();
+        Ok(serde_json::json!({
+            "findings": findings,
+            "suppressed": suppressed,
+            "preview": preview::head(&report),
+            "report": report,
+        })
+        .to_string())
+    }
 
-                let writes_vec: Vec<String> = summary
-                    .writes
-                    .iter()
-                    .map(|id| {
-                        call_graph.nodes.get(*id).map_or_else(
-                            || format!("UnknownVar({})", id),
-                            |n| format!("{}.{}", n.contract_name.as_deref().unwrap_or("?"), n.name),
-                        )
-                    })
-                    .collect();
+    fn summarize_findings(
+        &mut self,
+        uris: &[Url],
+        workspace_folder: &Path,
+        min_confidence: Confidence,
+        top_contracts: usize,
+    ) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        let (findings, suppressed) =
+            self.audit_findings(&sources, workspace_folder, min_confidence)?;
+        let summary = summary::summarize(&findings, sources.len(), suppressed, top_contracts);
+        let report = summary::render_summary(&summary);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "summary": summary,
+            "preview": preview::head(&report),
+            "report": report,
+        })
+        .to_string())
+    }
+
+    /// Audit findings at or above `min_confidence` that are not suppressed,
+    /// and the number that were.
+    fn audit_findings(
+        &self,
+        sources: &[SourceUnit],
+        workspace_folder: &Path,
+        min_confidence: Confidence,
+    ) -> Result<(Vec<Finding>, usize)> {
+        self.enter("running audit checks")?;
+        let mut findings = analysis::run_configured_audit(sources, &self.config)?;
+        findings.retain(|f| f.confidence >= min_confidence);
+        let ignore = IgnoreFile::load(workspace_folder);
+        let suppressed = suppression::apply(&mut findings, sources, &ignore, workspace_folder);
+        Ok((findings, suppressed))
+    }
+
+    fn generate_pr_comment(
+        &mut self,
+        uris: &[Url],
+        workspace_folder: &Path,
+        base_ref: &str,
+    ) -> Result<String> {
+        let head_sources = self.read_sources(uris)?;
+        self.enter("reading base revision")?;
+        let mut base_sources = git::sources_at(workspace_folder, base_ref)?;
+        for unit in &mut base_sources {
+            profiles::apply(&self.config.profiles, unit);
+        }
+
+        self.enter("parsing sources")?;
+        let head_parsed = parse_sources_with_profiles(&head_sources, &self.config.profiles)?;
+        let head_model = SourceModel::from_parsed(&head_parsed);
+        let head_graph = self.call_graph_with_placeholders(&head_sources)?;
+        let base_parsed = parse_sources_with_profiles(&base_sources, &self.config.profiles)?;
+        let base_model = SourceModel::from_parsed(&base_parsed);
+        let base_graph = self.call_graph_with_placeholders(&base_sources)?;
+
+        self.enter("comparing revisions")?;
+        let changes = pr_comment::compare(
+            &Snapshot {
+                parsed: &base_parsed,
+                model: &base_model,
+                graph: &base_graph,
+            },
+            &Snapshot {
+                parsed: &head_parsed,
+                model: &head_model,
+                graph: &head_graph,
+            },
+        );
+        let report = pr_comment::render_pr_comment(base_ref, &changes);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "base_ref": base_ref,
+            "changes": changes,
+            "preview": preview::head(&report),
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn warm_cache(&mut self, uris: &[Url]) -> Result<String> {
+        self.report(&format!("Reading {} files", uris.len()), 0);
+        let sources = self.read_sources(uris)?;
+        let call_graph = self.call_graph_with_placeholders(&sources)?;
+
+        Ok(serde_json::json!({
+            "files": uris.len(),
+            "nodes": call_graph.nodes.len(),
+            "edges": call_graph.edges.len(),
+            "cache": self.graphs.stats(),
+        })
+        .to_string())
+    }
+
+    fn list_fuzz_targets(&mut self, uris: &[Url]) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        let targets = fuzz_targets::extract(&model);
+
+        Ok(serde_json::json!({
+            "targets": targets,
+        })
+        .to_string())
+    }
+
+    fn audit_overrides(&mut self, uris: &[Url]) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        self.enter("checking overrides")?;
+        let issues = overrides::find(&model);
+        let report = overrides::render_override_report(&issues);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "issues": issues,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn cross_reference(&mut self, uris: &[Url]) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        self.enter("finding uses")?;
+        let symbols = cross_reference::build(&parsed);
+        let report = cross_reference::render_cross_reference(&symbols);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "symbols": symbols,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn magic_numbers(&mut self, uris: &[Url]) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        self.enter("collecting literals")?;
+        let inventory = magic_numbers::inventory(&parsed);
+        let report = magic_numbers::render_magic_numbers(&inventory);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
 
-                md.push_str(&format!(
-                    "| {} | {} | {} |\n",
-                    endpoint_name,
-                    reads_vec.join(", "),
-                    writes_vec.join(", ")
+        Ok(serde_json::json!({
+            "literals": inventory.literals,
+            "near_misses": inventory.near_misses,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn storage_heatmap(&mut self, uris: &[Url], contract_name: Option<&str>) -> Result<String> {
+        let call_graph = self.get_or_build_call_graph(uris)?;
+        self.enter("counting storage writes")?;
+        let variables = storage_heatmap::heatmap(&call_graph, contract_name)?;
+        let dot = storage_heatmap::render_dot(&variables);
+        let report = storage_heatmap::render_heatmap(&variables);
+        self.artifacts.extend([
+            Artifact::inline("dot", ArtifactFormat::Dot, dot.as_str()),
+            Artifact::inline("report", ArtifactFormat::Markdown, report.as_str()),
+        ]);
+
+        Ok(serde_json::json!({
+            "variables": variables,
+            "preview": preview::head(&dot),
+            "dot": dot,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn call_matrix(&mut self, uris: &[Url]) -> Result<String> {
+        let call_graph = self.get_or_build_call_graph(uris)?;
+        self.enter("counting calls")?;
+        let matrix = call_matrix::build(&call_graph);
+        let csv = matrix.to_csv();
+        let report = call_matrix::render_call_matrix(&matrix);
+        self.artifacts.extend([
+            Artifact::inline("csv", ArtifactFormat::Csv, csv.as_str()),
+            Artifact::inline("report", ArtifactFormat::Markdown, report.as_str()),
+        ]);
+
+        Ok(serde_json::json!({
+            "contracts": matrix.contracts,
+            "matrix": matrix.counts,
+            "couplings": matrix.couplings(),
+            "preview": preview::head(&csv),
+            "csv": csv,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn governance_report(&mut self, uris: &[Url]) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        self.enter("finding admin controls")?;
+        let contracts = governance::analyze(&parsed, &model);
+        let mermaid = governance::render_mermaid(&contracts);
+        let report = governance::render_governance(&contracts);
+        self.artifacts.extend([
+            Artifact::inline("mermaid", ArtifactFormat::Mermaid, mermaid.as_str()),
+            Artifact::inline("report", ArtifactFormat::Markdown, report.as_str()),
+        ]);
+
+        Ok(serde_json::json!({
+            "contracts": contracts,
+            "preview": preview::head(&mermaid),
+            "mermaid": mermaid,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn pause_coverage(&mut self, uris: &[Url]) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        self.enter("checking pause guards")?;
+        let contracts = pause_coverage::analyze(&parsed, &model);
+        let report = pause_coverage::render_pause_coverage(&contracts);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "contracts": contracts,
+            "uncovered": contracts.iter().map(|c| c.uncovered().count()).sum::<usize>(),
+            "preview": preview::head(&report),
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn external_dependencies(
+        &mut self,
+        uris: &[Url],
+        contract_name: Option<&str>,
+    ) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        self.enter("finding external dependencies")?;
+        let functions: Vec<_> = dependencies::analyze(&parsed, &model)
+            .into_iter()
+            .filter(|f| contract_name.is_none_or(|name| f.contract == name))
+            .collect();
+        let report = dependencies::render_dependencies(&functions);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "functions": functions.iter().filter(|f| !f.badges.is_empty()).collect::<Vec<_>>(),
+            "preview": preview::head(&report),
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn privilege_paths(&mut self, uris: &[Url], privileges: &Privileges) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        let call_graph = self.call_graph(&sources)?;
+        self.enter("following calls")?;
+        let paths = privilege_paths::find(&call_graph, &model, privileges);
+        let mermaid = privilege_paths::render_mermaid(&call_graph, &paths);
+        let report = privilege_paths::render_privilege_paths(&paths);
+        self.artifacts.extend([
+            Artifact::inline("mermaid", ArtifactFormat::Mermaid, mermaid.as_str()),
+            Artifact::inline("report", ArtifactFormat::Markdown, report.as_str()),
+        ]);
+
+        Ok(serde_json::json!({
+            "paths": paths,
+            "preview": preview::head(&mermaid),
+            "mermaid": mermaid,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn find_clones(&mut self, uris: &[Url], min_nodes: usize) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        self.enter("comparing function bodies")?;
+        let clusters = clones::find(&parsed, min_nodes);
+        let report = clones::render_clones(&clusters);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "clusters": clusters,
+            "preview": preview::head(&report),
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn compare_storage(&mut self, uris: &[Url], left: &str, right: &str) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        let rows = storage_compare::compare(&model, left, right)?;
+        let report = storage_compare::render_comparison(left, right, &rows);
+        self.artifacts.push(Artifact::inline(
+            "report",
+            ArtifactFormat::Markdown,
+            report.as_str(),
+        ));
+
+        Ok(serde_json::json!({
+            "rows": rows,
+            "report": report,
+        })
+        .to_string())
+    }
+
+    fn import_trace(
+        &mut self,
+        uris: &[Url],
+        trace: Option<String>,
+        trace_file: Option<&Path>,
+    ) -> Result<String> {
+        self.enter("parsing trace")?;
+        let mut steps = match (trace, trace_file) {
+            (Some(text), _) => trace::read(&text)?,
+            (None, Some(path)) => trace::load(path)?,
+            (None, None) => anyhow::bail!("No trace given"),
+        };
+        let sources = self.read_sources(uris)?;
+        self.enter("parsing sources")?;
+        let parsed = parse_sources_with_profiles(&sources, &self.config.profiles)?;
+        let model = SourceModel::from_parsed(&parsed);
+        let call_graph = self.call_graph(&sources)?;
+
+        self.enter("aligning trace")?;
+        trace::resolve_selectors(&mut steps, &model);
+        let unmatched = trace::align(&mut steps, &call_graph, &model, &SourceMap::new(&sources));
+        let mermaid = trace::render_mermaid(&steps);
+
+        // What the source says the executed functions can do, for
+        // comparison with what they did.
+        self.enter("rendering Mermaid")?;
+        let reached = steps.iter().filter_map(|s| s.node_id).collect();
+        let static_graph = graph_filter::reachable_subgraph(&call_graph, &reached);
+        let static_mermaid = self.adapter.generate_mermaid_with_config(
+            &static_graph,
+            &MermaidConfig {
+                no_chunk: true,
+                ..MermaidConfig::default()
+            },
+        )?;
+        self.artifacts.extend([
+            Artifact::inline("mermaid", ArtifactFormat::Mermaid, mermaid.as_str()),
+            Artifact::inline(
+                "static_mermaid",
+                ArtifactFormat::Mermaid,
+                static_mermaid.content.as_str(),
+            ),
+        ]);
+
+        Ok(serde_json::json!({
+            "mermaid": mermaid,
+            "static_mermaid": static_mermaid.content,
+            "steps": steps,
+            "unmatched": unmatched,
+        })
+        .to_string())
+    }
+
+    fn export_graph(
+        &mut self,
+        uris: &[Url],
+        workspace_folder: &Path,
+        format: ExportFormat,
+        filter: &GraphFilter,
+        consumers_file: Option<&Path>,
+        anonymize: bool,
+    ) -> Result<String> {
+        let sources = self.read_sources(uris)?;
+        let mut call_graph = self.diagram_call_graph(&sources, None, filter)?;
+        // Anonymized exports must not name the files either.
+        let origins = (!anonymize).then(|| SourceMap::new(&sources).origins(&call_graph));
+        let mut mapping_file = None;
+        if anonymize {
+            self.enter("anonymizing graph")?;
+            let path = self.output.current().dir.join(anonymize::MAPPING_FILE);
+            call_graph = Pseudonyms::anonymize_with(&path, &call_graph)?;
+            mapping_file = Some(path);
+        }
+
+        self.enter("exporting graph")?;
+        let mut response = match format {
+            ExportFormat::Cypher => {
+                let cypher = cypher::to_cypher(&call_graph);
+                self.artifacts.push(Artifact::inline(
+                    "cypher",
+                    ArtifactFormat::Cypher,
+                    cypher.as_str(),
                 ));
+                serde_json::json!({
+                    "preview": preview::head(&cypher),
+                    "cypher": cypher,
+                })
+            }
+            ExportFormat::Neo4jCsv => {
+                let (nodes, relationships) = cypher::to_neo4j_csv(&call_graph);
+                self.artifacts.extend([
+                    Artifact::inline("nodes_csv", ArtifactFormat::Csv, nodes.as_str()),
+                    Artifact::inline(
+                        "relationships_csv",
+                        ArtifactFormat::Csv,
+                        relationships.as_str(),
+                    ),
+                ]);
+                serde_json::json!({
+                    "preview": {
+                        "nodes_csv": preview::head(&nodes),
+                        "relationships_csv": preview::head(&relationships),
+                    },
+                    "nodes_csv": nodes,
+                    "relationships_csv": relationships,
+                })
             }
+            ExportFormat::Obsidian => {
+                let vault_dir = self.output.current().dir.join(obsidian::VAULT_DIR);
+                let notes = obsidian::write_vault(&call_graph, &vault_dir)?;
+                let manifest =
+                    self.write_manifest(&sources, workspace_folder, &vault_dir, &notes)?;
+                self.artifacts.extend([
+                    Artifact::file("vault_dir", ArtifactFormat::Markdown, &vault_dir),
+                    Artifact::file("manifest", ArtifactFormat::Json, &manifest),
+                ]);
+                serde_json::json!({
+                    "vault_dir": vault_dir,
+                    "notes": notes,
+                    "preview": preview::contracts(&call_graph),
+                    "manifest": manifest,
+                })
+            }
+            ExportFormat::Excalidraw => {
+                // Consumers match events by their real names, and are named
+                // after internal components themselves.
+                let consumers = if anonymize {
+                    Default::default()
+                } else {
+                    consumers::load(workspace_folder, consumers_file)?
+                };
+                let scene_dir = self.output.current().dir.join(excalidraw::EXCALIDRAW_DIR);
+                let scenes = excalidraw::write_scenes(&call_graph, &consumers, &scene_dir)?;
+                let manifest =
+                    self.write_manifest(&sources, workspace_folder, &scene_dir, &scenes)?;
+                self.artifacts.extend([
+                    Artifact::file("scene_dir", ArtifactFormat::Excalidraw, &scene_dir),
+                    Artifact::file("manifest", ArtifactFormat::Json, &manifest),
+                ]);
+                serde_json::json!({
+                    "scene_dir": scene_dir,
+                    "scenes": scenes,
+                    "preview": preview::contracts(&call_graph),
+                    "manifest": manifest,
+                    "consumers": consumers::subscriptions(&call_graph, &consumers),
+                    "unmatched_consumers": consumers::unmatched(&call_graph, &consumers),
+                })
+            }
+        };
+        if let Some(path) = mapping_file {
+            response["mapping_file"] = serde_json::json!(path);
         }
-
-        Ok(md)
+        if let Some(origins) = origins {
+            response["origins"] = serde_json::json!(origins);
+        }
+        Ok(response.to_string())
     }
 }
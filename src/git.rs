@@ -0,0 +1,106 @@
+//! Reads other revisions of the workspace through the `git` CLI.
+
+use crate::analysis::model::SourceUnit;
+use crate::utils::SKIPPED_DIRS;
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Resolves `rev` to a commit id, rejecting anything git would read as an
+/// option.
+pub fn resolve_commit(workspace_folder: &Path, rev: &str) -> Result<String> {
+    if rev.is_empty() || rev.starts_with('-') {
+        bail!("Invalid git revision `{}`", rev);
+    }
+    let commit = git(
+        workspace_folder,
+        &[
+            "rev-parse",
+            "--verify",
+            "--end-of-options",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )?;
+    Ok(commit.trim().to_string())
+}
+
+/// The `.sol` files under `workspace_folder` as of `rev`, with paths under
+/// `workspace_folder` as if they were checked out.
+pub fn sources_at(workspace_folder: &Path, rev: &str) -> Result<Vec<SourceUnit>> {
+    let commit = resolve_commit(workspace_folder, rev)?;
+    let listing = git(
+        workspace_folder,
+        &["ls-tree", "-r", "--name-only", "-z", &commit],
+    )?;
+
+    let mut sources = Vec::new();
+    for relative in listing.split('\0') {
+        let skipped = relative
+            .split('/')
+            .any(|component| SKIPPED_DIRS.contains(&component));
+        if !relative.ends_with(".sol") || skipped {
+            continue;
+        }
+        let content = git(
+            workspace_folder,
+            &["show", &format!("{}:./{}", commit, relative)],
+        )?;
+        sources.push(SourceUnit {
+            path: workspace_folder.join(relative),
+            content,
+        });
+    }
+    Ok(sources)
+}
+
+/// Files under `workspace_folder` that differ from `rev` in the working
+/// tree, staged or not, plus untracked files that are not ignored. Deleted
+/// files are included; callers match the result against what exists.
+pub fn changed_files(workspace_folder: &Path, rev: &str) -> Result<Vec<PathBuf>> {
+    let commit = resolve_commit(workspace_folder, rev)?;
+    let modified = git(
+        workspace_folder,
+        &["diff", "--name-only", "-z", "--relative", &commit, "--"],
+    )?;
+    let untracked = git(
+        workspace_folder,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )?;
+    Ok(modified
+        .split('\0')
+        .chain(untracked.split('\0'))
+        .filter(|relative| !relative.is_empty())
+        .map(|relative| workspace_folder.join(relative))
+        .collect())
+}
+
+/// Branches and tags of the repository holding `workspace_folder`.
+pub fn refs(workspace_folder: &Path) -> Result<Vec<String>> {
+    let listing = git(
+        workspace_folder,
+        &[
+            "for-each-ref",
+            "--format=%(refname:short)",
+            "refs/heads",
+            "refs/tags",
+        ],
+    )?;
+    Ok(listing.lines().map(str::to_string).collect())
+}
+
+fn git(workspace_folder: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(workspace_folder)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).context("git output is not valid UTF-8")
+}
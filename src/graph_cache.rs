@@ -0,0 +1,394 @@
+//! Call graphs kept across generator requests, and the parse trees they
+//! were built from.
+//!
+//! Graphs are keyed by the paths and content hashes of the files they were
+//! built from, so repeated commands on an unchanged workspace skip the build
+//! and any edit, added or removed file builds a new graph. Parse trees are
+//! keyed by path; the next parse of a file edits its tree. Sizes are
+//! estimated from what each graph and tree owns, and both share one budget:
+//! once their total passes it, the least recently used are evicted.
+
+use crate::analysis::model::SourceUnit;
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use traverse_graph::cg::{CallGraph, Edge, Node, ParameterInfo};
+use tree_sitter::{InputEdit, Tree};
+
+/// Budget used when `cache_budget_mb` is not configured.
+pub const DEFAULT_CACHE_BUDGET_MB: u64 = 256;
+
+/// Estimated size of one parse tree node. Tree-sitter does not report what
+/// a tree holds, so trees are sized by their node count.
+const TREE_NODE_BYTES: usize = 64;
+
+/// Which post-processing a cached graph has had.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphKind {
+    /// As built by `traverse_graph`.
+    Plain,
+    /// With placeholder nodes for unresolved calls.
+    WithPlaceholders,
+    /// With placeholders, then narrowed to a contract or filtered for a
+    /// diagram; the hash identifies the contract and filter, so switching
+    /// between scopes reuses each one's graph.
+    Scoped([u8; 32]),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    /// Estimated size of the cached graphs.
+    pub bytes: usize,
+    pub budget_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Graphs dropped because a file they were built from changed.
+    pub invalidations: u64,
+    /// Parse trees kept for incremental parsing.
+    pub parse_trees: usize,
+    /// Estimated size of the parse trees and of the text each was parsed
+    /// from.
+    pub parse_tree_bytes: usize,
+}
+
+pub struct GraphCache {
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    entries: HashMap<(GraphKind, [u8; 32]), Entry>,
+    trees: HashMap<PathBuf, TreeEntry>,
+    /// Incremented on every access; orders entries by recency.
+    clock: u64,
+    stats: CacheStats,
+}
+
+struct Entry {
+    graph: CallGraph,
+    /// Paths of the files the graph was built from, with the length of the
+    /// content each was built from.
+    files: Vec<(PathBuf, usize)>,
+    bytes: usize,
+    last_used: u64,
+}
+
+/// The last parsed text of a file and its tree.
+struct TreeEntry {
+    text: String,
+    tree: Tree,
+    bytes: usize,
+    last_used: u64,
+}
+
+impl GraphCache {
+    /// A cache holding at most `budget_bytes` of graphs and parse trees;
+    /// `0` disables it.
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                entries: HashMap::new(),
+                trees: HashMap::new(),
+                clock: 0,
+                stats: CacheStats {
+                    budget_bytes,
+                    ..CacheStats::default()
+                },
+            }),
+        }
+    }
+
+    /// The cached graph for `sources`, or the result of `build`, which is
+    /// cached if it fits the budget. `build` runs without the lock held.
+    pub fn get_or_build(
+        &self,
+        kind: GraphKind,
+        sources: &[SourceUnit],
+        build: impl FnOnce() -> Result<CallGraph>,
+    ) -> Result<CallGraph> {
+        let key = (kind, fingerprint(sources));
+        {
+            let mut inner = self.lock();
+            inner.clock += 1;
+            let clock = inner.clock;
+            if let Some(entry) = inner.entries.get_mut(&key) {
+                entry.last_used = clock;
+                let graph = entry.graph.clone();
+                inner.stats.hits += 1;
+                return Ok(graph);
+            }
+            inner.stats.misses += 1;
+        }
+
+        let graph = build()?;
+        let bytes = estimate_size(&graph);
+        let mut inner = self.lock();
+        if bytes <= inner.stats.budget_bytes {
+            inner.clock += 1;
+            let last_used = inner.clock;
+            let previous = inner.entries.insert(
+                key,
+                Entry {
+                    graph: graph.clone(),
+                    files: sources
+                        .iter()
+                        .map(|unit| (unit.path.clone(), unit.content.len()))
+                        .collect(),
+                    bytes,
+                    last_used,
+                },
+            );
+            inner.stats.bytes += bytes;
+            inner.stats.bytes -= previous.map_or(0, |p| p.bytes);
+            inner.evict();
+        }
+        Ok(graph)
+    }
+
+    /// The most recently used graph built from `path` and not narrowed to a
+    /// scope, for requests that must answer without building one. Does not
+    /// count as a hit.
+    pub fn latest(&self, path: &Path) -> Option<CallGraph> {
+        self.latest_with_files(path).map(|(graph, _)| graph)
+    }
+
+    /// [`latest`](Self::latest), with the files the graph was built from
+    /// and the length of each, in the order their spans are laid out.
+    pub fn latest_with_files(&self, path: &Path) -> Option<(CallGraph, Vec<(PathBuf, usize)>)> {
+        let inner = self.lock();
+        inner
+            .entries
+            .iter()
+            .filter(|((kind, _), entry)| {
+                !matches!(kind, GraphKind::Scoped(_)) && entry.files.iter().any(|(f, _)| f == path)
+            })
+            .max_by_key(|(_, entry)| entry.last_used)
+            .map(|(_, entry)| (entry.graph.clone(), entry.files.clone()))
+    }
+
+    /// Drops every graph built from `path` or from a file under it, e.g.
+    /// after the file changed on disk or its workspace folder was closed,
+    /// and returns how many were dropped. Such graphs would never be
+    /// hit again, but would hold their share of the budget until evicted.
+    pub fn invalidate(&self, path: &Path) -> usize {
+        let mut inner = self.lock();
+        let stale: Vec<_> = inner
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.files.iter().any(|(file, _)| file.starts_with(path)))
+            .map(|(key, _)| *key)
+            .collect();
+        for key in &stale {
+            if let Some(entry) = inner.entries.remove(key) {
+                inner.stats.bytes -= entry.bytes;
+                inner.stats.invalidations += 1;
+            }
+        }
+        stale.len()
+    }
+
+    /// Drops every graph and parse tree, e.g. on `traverse.clearCache`,
+    /// and returns how many graphs were dropped.
+    pub fn clear(&self) -> usize {
+        let mut inner = self.lock();
+        let cleared = inner.entries.len();
+        inner.entries.clear();
+        inner.trees.clear();
+        inner.stats.bytes = 0;
+        inner.stats.parse_tree_bytes = 0;
+        cleared
+    }
+
+    /// Takes the last parsed text and tree of `path`, for the next parse to
+    /// edit. Files parsed concurrently each take their own.
+    pub fn take_tree(&self, path: &Path) -> Option<(String, Tree)> {
+        let mut inner = self.lock();
+        let entry = inner.trees.remove(path)?;
+        inner.stats.parse_tree_bytes -= entry.bytes;
+        Some((entry.text, entry.tree))
+    }
+
+    /// Keeps `tree`, parsed from `text`, as the tree of `path` if it fits
+    /// the budget.
+    pub fn store_tree(&self, path: PathBuf, text: String, tree: Tree) {
+        let bytes = tree_size(&text, &tree);
+        let mut inner = self.lock();
+        if bytes > inner.stats.budget_bytes {
+            return;
+        }
+        inner.clock += 1;
+        let last_used = inner.clock;
+        let previous = inner.trees.insert(
+            path,
+            TreeEntry {
+                text,
+                tree,
+                bytes,
+                last_used,
+            },
+        );
+        inner.stats.parse_tree_bytes += bytes;
+        inner.stats.parse_tree_bytes -= previous.map_or(0, |p| p.bytes);
+        inner.evict();
+    }
+
+    /// Drops the trees of `path` and of the files under it, e.g. after the
+    /// file changed on disk; the next parse of it starts from scratch.
+    pub fn forget_trees(&self, path: &Path) -> bool {
+        let mut inner = self.lock();
+        let stale: Vec<PathBuf> = inner
+            .trees
+            .keys()
+            .filter(|file| file.starts_with(path))
+            .cloned()
+            .collect();
+        for file in &stale {
+            if let Some(entry) = inner.trees.remove(file) {
+                inner.stats.parse_tree_bytes -= entry.bytes;
+            }
+        }
+        !stale.is_empty()
+    }
+
+    /// Applies `edits`, which turned `before` into `after`, to the tree of
+    /// `path`, so its next parse needs no diff. Skipped unless the tree was
+    /// parsed from `before`, e.g. when it was parsed from a profile's
+    /// rewrite of the text; that parse falls back to diffing the texts.
+    pub fn edit_tree(&self, path: &Path, before: &str, edits: &[InputEdit], after: &str) -> bool {
+        let mut inner = self.lock();
+        let Some(entry) = inner
+            .trees
+            .get_mut(path)
+            .filter(|entry| entry.text == before)
+        else {
+            return false;
+        };
+        for edit in edits {
+            entry.tree.edit(edit);
+        }
+        entry.text = after.to_string();
+        let bytes = tree_size(&entry.text, &entry.tree);
+        let previous = std::mem::replace(&mut entry.bytes, bytes);
+        inner.stats.parse_tree_bytes += bytes;
+        inner.stats.parse_tree_bytes -= previous;
+        inner.evict();
+        true
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            entries: inner.entries.len(),
+            parse_trees: inner.trees.len(),
+            ..inner.stats.clone()
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Inner {
+    /// Evicts the least recently used graphs and trees, whichever is older,
+    /// until both fit the budget together.
+    fn evict(&mut self) {
+        while self.stats.bytes + self.stats.parse_tree_bytes > self.stats.budget_bytes {
+            let graph = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, entry)| (*key, entry.last_used));
+            let tree = self
+                .trees
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(path, entry)| (path.clone(), entry.last_used));
+            let older_graph =
+                graph.filter(|(_, graph_used)| tree.as_ref().is_none_or(|(_, t)| graph_used < t));
+            if let Some((key, _)) = older_graph {
+                if let Some(entry) = self.entries.remove(&key) {
+                    self.stats.bytes -= entry.bytes;
+                }
+            } else if let Some((path, _)) = tree {
+                if let Some(entry) = self.trees.remove(&path) {
+                    self.stats.parse_tree_bytes -= entry.bytes;
+                }
+            } else {
+                break;
+            }
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+/// Hash of each file's path and content hash, in order.
+pub fn fingerprint(sources: &[SourceUnit]) -> [u8; 32] {
+    let mut keyed = Vec::new();
+    for unit in sources {
+        keyed.extend_from_slice(unit.path.to_string_lossy().as_bytes());
+        keyed.push(0);
+        keyed.extend_from_slice(&Sha256::digest(unit.content.as_bytes()));
+    }
+    Sha256::digest(&keyed).into()
+}
+
+/// Approximate heap and inline size of `graph`: its nodes, edges and the
+/// strings they own. The library's private name lookup is counted as one
+/// key and index per node.
+pub fn estimate_size(graph: &CallGraph) -> usize {
+    let nodes: usize = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            size_of::<Node>()
+                + node.name.capacity() * 2
+                + node.contract_name.as_ref().map_or(0, |c| c.capacity() * 2)
+                + option_len(&node.declared_return_type)
+                + option_len(&node.revert_message)
+                + option_len(&node.condition_expression)
+                + node
+                    .parameters
+                    .iter()
+                    .map(|p| {
+                        size_of::<ParameterInfo>()
+                            + p.name.capacity()
+                            + p.param_type.capacity()
+                            + option_len(&p.description)
+                    })
+                    .sum::<usize>()
+                + size_of::<(Option<String>, String, usize)>()
+        })
+        .sum();
+    let edges: usize = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            size_of::<Edge>()
+                + option_len(&edge.returned_value)
+                + option_len(&edge.event_name)
+                + option_len(&edge.declared_return_type)
+                + edge.argument_names.as_ref().map_or(0, |names| {
+                    names
+                        .iter()
+                        .map(|n| size_of::<String>() + n.capacity())
+                        .sum()
+                })
+        })
+        .sum();
+    size_of::<CallGraph>() + nodes + edges
+}
+
+/// Approximate size of `tree` and of the `text` it was parsed from.
+fn tree_size(text: &str, tree: &Tree) -> usize {
+    size_of::<TreeEntry>() + text.len() + tree.root_node().descendant_count() * TREE_NODE_BYTES
+}
+
+fn option_len(value: &Option<String>) -> usize {
+    value.as_ref().map_or(0, String::capacity)
+}
@@ -0,0 +1,308 @@
+//! Call graph pruning for diagram requests.
+//!
+//! Filters run on a copy of the graph before rendering, so every output
+//! format sees the same reduced graph and node ids stay dense.
+
+use crate::analysis::model::SourceModel;
+use crate::config::GraphFilter;
+use std::collections::{HashSet, VecDeque};
+use traverse_graph::cg::{CallGraph, EdgeType, Node, NodeType, Visibility};
+
+/// Returns the pruned graph. `model` supplies function mutability, which the
+/// call graph does not record; `hide_view_functions` is a no-op without it.
+pub fn apply(graph: &CallGraph, filter: &GraphFilter, model: Option<&SourceModel>) -> CallGraph {
+    let mut removed_nodes: HashSet<usize> = HashSet::new();
+    let mut removed_edges: HashSet<usize> = HashSet::new();
+
+    if filter.hide_view_functions {
+        if let Some(model) = model {
+            removed_nodes.extend(
+                graph
+                    .nodes
+                    .iter()
+                    .filter(|n| is_view(n, model))
+                    .map(|n| n.id),
+            );
+        }
+    }
+
+    if filter.hide_getters {
+        let mut getter_targets = HashSet::new();
+        for (index, edge) in graph.edges.iter().enumerate() {
+            let Some(target) = graph.nodes.get(edge.target_node_id) else {
+                continue;
+            };
+            if edge.edge_type == EdgeType::Call && target.node_type == NodeType::StorageVariable {
+                removed_edges.insert(index);
+                getter_targets.insert(target.id);
+            }
+        }
+        // Variables that were only ever reached through their getter.
+        let mut still_used = HashSet::new();
+        for (index, edge) in graph.edges.iter().enumerate() {
+            if !removed_edges.contains(&index) {
+                still_used.insert(edge.source_node_id);
+                still_used.insert(edge.target_node_id);
+            }
+        }
+        removed_nodes.extend(getter_targets.difference(&still_used));
+    }
+
+    if filter.only_external_entrypoints {
+        let reachable = reachable_from_entrypoints(graph, &removed_nodes, &removed_edges);
+        removed_nodes.extend(
+            graph
+                .nodes
+                .iter()
+                .map(|n| n.id)
+                .filter(|id| !reachable.contains(id)),
+        );
+    }
+
+    prune(graph, &removed_nodes, &removed_edges)
+}
+
+/// The part of `graph` reachable from `roots` along calls and other
+/// non-return edges.
+pub fn reachable_subgraph(graph: &CallGraph, roots: &HashSet<usize>) -> CallGraph {
+    reachable_within(graph, roots, None)
+}
+
+/// What the public and external functions of concrete contracts reach
+/// within `max_depth` calls.
+pub fn limit_depth(graph: &CallGraph, max_depth: usize) -> CallGraph {
+    reachable_within(graph, &entrypoints(graph, &HashSet::new()), Some(max_depth))
+}
+
+/// [`reachable_subgraph`] up to `max_depth` edges away from `roots`.
+fn reachable_within(
+    graph: &CallGraph,
+    roots: &HashSet<usize>,
+    max_depth: Option<usize>,
+) -> CallGraph {
+    let reachable = reachable_from(
+        graph,
+        roots.iter().copied(),
+        &HashSet::new(),
+        &HashSet::new(),
+        max_depth,
+    );
+    let removed_nodes: HashSet<usize> = graph
+        .nodes
+        .iter()
+        .map(|n| n.id)
+        .filter(|id| !reachable.contains(id))
+        .collect();
+    prune(graph, &removed_nodes, &HashSet::new())
+}
+
+/// The members of `contract` and everything they reach within `max_depth`
+/// calls, or `None` when the graph has no such contract.
+pub fn contract_subgraph(
+    graph: &CallGraph,
+    contract: &str,
+    max_depth: Option<usize>,
+) -> Option<CallGraph> {
+    let roots: HashSet<usize> = graph
+        .nodes
+        .iter()
+        .filter(|n| {
+            n.contract_name.as_deref() == Some(contract)
+                || matches!(n.node_type, NodeType::Interface | NodeType::Library)
+                    && n.name == contract
+        })
+        .map(|n| n.id)
+        .collect();
+    (!roots.is_empty()).then(|| reachable_within(graph, &roots, max_depth))
+}
+
+/// `contract.function`, every overload of it, and everything it reaches
+/// within `max_depth` calls, or `None` when the graph has no such function.
+/// `constructor` names the contract's constructor.
+pub fn function_subgraph(
+    graph: &CallGraph,
+    contract: &str,
+    function: &str,
+    max_depth: Option<usize>,
+) -> Option<CallGraph> {
+    let roots: HashSet<usize> = graph
+        .nodes
+        .iter()
+        .filter(|n| {
+            n.contract_name.as_deref() == Some(contract)
+                && match n.node_type {
+                    NodeType::Function | NodeType::Modifier => n.name == function,
+                    NodeType::Constructor => function == "constructor",
+                    _ => false,
+                }
+        })
+        .map(|n| n.id)
+        .collect();
+    (!roots.is_empty()).then(|| reachable_within(graph, &roots, max_depth))
+}
+
+/// Makes `contract.function` the only entry point of a sequence diagram,
+/// which starts from public and external functions: the function becomes
+/// public, whatever its visibility, and every other function internal. A
+/// constructor is shown as a function named `constructor`.
+pub fn single_entry_point(graph: &mut CallGraph, contract: &str, function: &str) {
+    for node in &mut graph.nodes {
+        let is_root = node.contract_name.as_deref() == Some(contract)
+            && match node.node_type {
+                NodeType::Function => node.name == function,
+                NodeType::Constructor => function == "constructor",
+                _ => false,
+            };
+        if is_root {
+            node.node_type = NodeType::Function;
+            node.name = function.to_string();
+            node.visibility = Visibility::Public;
+        } else if node.node_type == NodeType::Function {
+            node.visibility = Visibility::Internal;
+        }
+    }
+}
+
+/// Copy of `graph` without `removed_nodes`, `removed_edges` and the edges
+/// touching removed nodes, with node ids renumbered densely.
+fn prune(
+    graph: &CallGraph,
+    removed_nodes: &HashSet<usize>,
+    removed_edges: &HashSet<usize>,
+) -> CallGraph {
+    let mut pruned = CallGraph::new();
+    let mut new_ids = vec![None; graph.nodes.len()];
+    for node in graph
+        .nodes
+        .iter()
+        .filter(|n| !removed_nodes.contains(&n.id))
+    {
+        let id = pruned.add_node(
+            node.name.clone(),
+            node.node_type.clone(),
+            node.contract_name.clone(),
+            node.visibility.clone(),
+            node.span,
+        );
+        pruned.nodes[id] = Node { id, ..node.clone() };
+        new_ids[node.id] = Some(id);
+    }
+    for (index, edge) in graph.edges.iter().enumerate() {
+        if removed_edges.contains(&index) {
+            continue;
+        }
+        let (Some(Some(source)), Some(Some(target))) = (
+            new_ids.get(edge.source_node_id),
+            new_ids.get(edge.target_node_id),
+        ) else {
+            continue;
+        };
+        let mut edge = edge.clone();
+        edge.source_node_id = *source;
+        edge.target_node_id = *target;
+        pruned.edges.push(edge);
+    }
+    pruned
+}
+
+/// Ids of the nodes reachable from public and external functions of concrete
+/// contracts, ignoring `removed_nodes` and `removed_edges`.
+pub fn reachable_from_entrypoints(
+    graph: &CallGraph,
+    removed_nodes: &HashSet<usize>,
+    removed_edges: &HashSet<usize>,
+) -> HashSet<usize> {
+    let roots = entrypoints(graph, removed_nodes);
+    reachable_from(graph, roots, removed_nodes, removed_edges, None)
+}
+
+/// Ids of the public and external functions of concrete contracts, except
+/// `removed_nodes`.
+fn entrypoints(graph: &CallGraph, removed_nodes: &HashSet<usize>) -> HashSet<usize> {
+    let interfaces: HashSet<&str> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.node_type == NodeType::Interface)
+        .map(|n| n.name.as_str())
+        .collect();
+    graph
+        .nodes
+        .iter()
+        .filter(|n| !removed_nodes.contains(&n.id) && is_entrypoint(n, &interfaces))
+        .map(|n| n.id)
+        .collect()
+}
+
+fn reachable_from(
+    graph: &CallGraph,
+    roots: impl IntoIterator<Item = usize>,
+    removed_nodes: &HashSet<usize>,
+    removed_edges: &HashSet<usize>,
+    max_depth: Option<usize>,
+) -> HashSet<usize> {
+    // Return edges point back at callers, which would re-admit internal
+    // functions that are only reachable from elsewhere.
+    let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); graph.nodes.len()];
+    for (index, edge) in graph.edges.iter().enumerate() {
+        if edge.edge_type != EdgeType::Return
+            && !removed_edges.contains(&index)
+            && !removed_nodes.contains(&edge.source_node_id)
+            && !removed_nodes.contains(&edge.target_node_id)
+        {
+            if let Some(targets) = outgoing.get_mut(edge.source_node_id) {
+                targets.push(edge.target_node_id);
+            }
+        }
+    }
+
+    // Breadth first, so a node is first seen at its shortest depth.
+    let mut reachable: HashSet<usize> = HashSet::new();
+    let mut queue: VecDeque<(usize, usize)> = roots.into_iter().map(|id| (id, 0)).collect();
+    while let Some((id, depth)) = queue.pop_front() {
+        if !reachable.insert(id) || max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+        queue.extend(
+            outgoing
+                .get(id)
+                .into_iter()
+                .flatten()
+                .map(|&target| (target, depth + 1)),
+        );
+    }
+    reachable
+}
+
+fn is_entrypoint(node: &Node, interfaces: &HashSet<&str>) -> bool {
+    node.node_type == NodeType::Function
+        && matches!(
+            node.visibility,
+            Visibility::Public | Visibility::External | Visibility::Default
+        )
+        && node
+            .contract_name
+            .as_deref()
+            .is_some_and(|c| !interfaces.contains(c))
+}
+
+/// A function node is hidden only if every overload with its name is
+/// `view` or `pure`, since graph nodes do not distinguish overloads.
+fn is_view(node: &Node, model: &SourceModel) -> bool {
+    if node.node_type != NodeType::Function {
+        return false;
+    }
+    let Some(contract) = node
+        .contract_name
+        .as_deref()
+        .and_then(|c| model.contract(c))
+    else {
+        return false;
+    };
+    let mut overloads = contract
+        .functions
+        .iter()
+        .filter(|f| f.name == node.name)
+        .peekable();
+    overloads.peek().is_some()
+        && overloads.all(|f| matches!(f.mutability.as_deref(), Some("view" | "pure")))
+}
@@ -0,0 +1,43 @@
+use crate::output::retention::{self, Retention};
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::ExecuteCommandParams;
+use std::path::Path;
+use tracing::info;
+
+/// Applies the retention in the command's arguments, with the limits it
+/// leaves unset taken from `configured`, to `output_dir`.
+pub fn clean_outputs(
+    req: Request,
+    conn: &Connection,
+    output_dir: &Path,
+    configured: Retention,
+) -> Result<()> {
+    let (id, params) = req.extract::<ExecuteCommandParams>("workspace/executeCommand")?;
+    let requested = match params.arguments.first() {
+        Some(arguments) => match serde_json::from_value::<Retention>(arguments.clone()) {
+            Ok(requested) => requested,
+            Err(e) => {
+                let response = Response::new_err(id, -32602, format!("Invalid arguments: {}", e));
+                conn.sender.send(Message::Response(response))?;
+                return Ok(());
+            }
+        },
+        None => Retention::default(),
+    };
+
+    let response = match retention::clean(output_dir, &requested.or(configured)) {
+        Ok(cleanup) => {
+            info!(
+                "Removed {} runs ({} bytes) from {}",
+                cleanup.removed.len(),
+                cleanup.freed_bytes,
+                output_dir.display()
+            );
+            Response::new_ok(id, cleanup)
+        }
+        Err(e) => Response::new_err(id, -32603, format!("{:#}", e)),
+    };
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
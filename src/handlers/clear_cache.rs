@@ -0,0 +1,15 @@
+use crate::graph_cache::GraphCache;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::ExecuteCommandParams;
+use tracing::info;
+
+pub fn clear_cache(req: Request, conn: &Connection, graphs: &GraphCache) -> Result<()> {
+    let (id, _) = req.extract::<ExecuteCommandParams>("workspace/executeCommand")?;
+
+    let cleared = graphs.clear();
+    info!("Cleared {} cached call graphs", cleared);
+    let response = Response::new_ok(id, serde_json::json!({ "cleared": cleared }));
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
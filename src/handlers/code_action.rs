@@ -0,0 +1,85 @@
+//! `textDocument/codeAction`: diagram and storage commands for the contract
+//! or function at the start of the selection.
+
+use crate::commands;
+use crate::documents::DocumentStore;
+use crate::handlers::code_lens::folder_command;
+use crate::symbol_index;
+use crate::workspace_folders;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{CodeActionRequest, Request as _};
+use lsp_types::{CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Range};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+pub fn code_action(
+    req: Request,
+    conn: &Connection,
+    documents: &DocumentStore,
+    folders: &[PathBuf],
+) -> Result<()> {
+    let (id, params) = req.extract::<CodeActionParams>(CodeActionRequest::METHOD)?;
+    let uri = params.text_document.uri;
+
+    let wanted = params.context.only.as_deref().is_none_or(|only| {
+        only.iter()
+            .any(|kind| kind.as_str().is_empty() || *kind == CodeActionKind::SOURCE)
+    });
+    let actions = match (wanted, documents.read(&uri), uri.to_file_path()) {
+        (true, Ok(text), Ok(path)) => actions(
+            &text,
+            params.range,
+            &workspace_folders::folder_of(&path, folders),
+        ),
+        _ => Vec::new(),
+    };
+    let response = Response::new_ok(id, actions);
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// Source actions for the contract at the start of `range`, preceded by the
+/// function's sequence diagram when the start is inside one of its
+/// functions. Outside contracts there are none.
+pub fn actions(text: &str, range: Range, workspace_folder: &Path) -> Vec<CodeActionOrCommand> {
+    let Some(contract) = symbol_index::enclosing_contract(text, range.start) else {
+        return Vec::new();
+    };
+    let action = |title: String, command: &str, arguments| {
+        CodeActionOrCommand::CodeAction(CodeAction {
+            kind: Some(CodeActionKind::SOURCE),
+            command: Some(folder_command(&title, command, arguments, workspace_folder)),
+            title,
+            ..CodeAction::default()
+        })
+    };
+
+    let mut actions = Vec::new();
+    if let Some((owner, function)) = symbol_index::enclosing_function(text, range.start) {
+        if owner == contract {
+            actions.push(action(
+                format!("Generate sequence diagram for {}.{}", contract, function),
+                commands::GENERATE_FUNCTION_DIAGRAM,
+                json!({ "contract": contract, "function": function }),
+            ));
+        }
+    }
+    let scope = json!({ "contract": contract });
+    actions.push(action(
+        format!("Generate call graph for {}", contract),
+        commands::GENERATE_CALL_GRAPH_WORKSPACE,
+        scope.clone(),
+    ));
+    actions.push(action(
+        format!("Generate sequence diagram for {}", contract),
+        commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE,
+        scope.clone(),
+    ));
+    actions.push(action(
+        format!("Show storage access of {}", contract),
+        commands::STORAGE_HEATMAP_WORKSPACE,
+        scope,
+    ));
+    actions
+}
@@ -0,0 +1,156 @@
+//! `textDocument/codeLens`: diagram and storage commands above each
+//! contract and each public or external function, and the external
+//! dependencies of functions that have any.
+
+use crate::analysis::dependencies;
+use crate::commands;
+use crate::documents::DocumentStore;
+use crate::symbol_index;
+use crate::workspace_folders;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{CodeLensRequest, Request as _};
+use lsp_types::{CodeLens, CodeLensParams, Command, Range};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use traverse_graph::parser::{get_node_text, parse_solidity};
+use tree_sitter::Node as TsNode;
+
+pub fn code_lens(
+    req: Request,
+    conn: &Connection,
+    documents: &DocumentStore,
+    folders: &[PathBuf],
+) -> Result<()> {
+    let (id, params) = req.extract::<CodeLensParams>(CodeLensRequest::METHOD)?;
+    let uri = params.text_document.uri;
+
+    let lenses = match (documents.read(&uri), uri.to_file_path()) {
+        (Ok(text), Ok(path)) => lenses(&text, &workspace_folders::folder_of(&path, folders)),
+        _ => Vec::new(),
+    };
+    let response = Response::new_ok(id, lenses);
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// Lenses for `text`, whose commands run on `workspace_folder`. A contract
+/// gets its call graph, sequence diagram and storage heatmap; a function
+/// gets its own sequence diagram, and its contract's call graph and heatmap,
+/// plus its dependency badges opening the contract's dependency report.
+pub fn lenses(text: &str, workspace_folder: &Path) -> Vec<CodeLens> {
+    let Ok(parsed) = parse_solidity(text) else {
+        return Vec::new();
+    };
+    let dependencies = dependencies::for_source(text);
+    let lens = |node: TsNode, title: &str, command: &str, arguments: Value| {
+        let start = symbol_index::position(text, node.start_position());
+        CodeLens {
+            range: Range::new(start, start),
+            command: Some(folder_command(title, command, arguments, workspace_folder)),
+            data: None,
+        }
+    };
+
+    let mut lenses = Vec::new();
+    let root = parsed.tree.root_node();
+    let mut cursor = root.walk();
+    for declaration in root.named_children(&mut cursor) {
+        if declaration.kind() != "contract_declaration" {
+            continue;
+        }
+        let (Some(name), Some(body)) = (
+            declaration.child_by_field_name("name"),
+            declaration.child_by_field_name("body"),
+        ) else {
+            continue;
+        };
+        let contract = get_node_text(&name, text);
+        let scope = json!({ "contract": contract });
+        lenses.push(lens(
+            declaration,
+            "Generate call graph",
+            commands::GENERATE_CALL_GRAPH_WORKSPACE,
+            scope.clone(),
+        ));
+        lenses.push(lens(
+            declaration,
+            "Generate sequence diagram",
+            commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE,
+            scope.clone(),
+        ));
+        lenses.push(lens(
+            declaration,
+            "Show storage access",
+            commands::STORAGE_HEATMAP_WORKSPACE,
+            scope.clone(),
+        ));
+
+        let mut body_cursor = body.walk();
+        for member in body.named_children(&mut body_cursor) {
+            if member.kind() != "function_definition" || !is_entry_point(member, text) {
+                continue;
+            }
+            let Some(function) = member.child_by_field_name("name") else {
+                continue;
+            };
+            lenses.push(lens(
+                member,
+                "Generate call graph",
+                commands::GENERATE_CALL_GRAPH_WORKSPACE,
+                scope.clone(),
+            ));
+            lenses.push(lens(
+                member,
+                "Generate sequence diagram",
+                commands::GENERATE_FUNCTION_DIAGRAM,
+                json!({ "contract": contract, "function": get_node_text(&function, text) }),
+            ));
+            lenses.push(lens(
+                member,
+                "Show storage access",
+                commands::STORAGE_HEATMAP_WORKSPACE,
+                scope.clone(),
+            ));
+            if let Some(badged) = dependencies.iter().find(|f| {
+                f.contract == contract
+                    && f.line == member.start_position().row + 1
+                    && !f.badges.is_empty()
+            }) {
+                lenses.push(lens(
+                    member,
+                    &format!("Depends on: {}", badged.labels()),
+                    commands::EXTERNAL_DEPENDENCIES_WORKSPACE,
+                    scope.clone(),
+                ));
+            }
+        }
+    }
+    lenses
+}
+
+/// `command` with `arguments` and the `workspace_folder` it runs on.
+pub(crate) fn folder_command(
+    title: &str,
+    command: &str,
+    mut arguments: Value,
+    workspace_folder: &Path,
+) -> Command {
+    arguments["workspace_folder"] = Value::from(workspace_folder.to_string_lossy().as_ref());
+    Command {
+        title: title.to_string(),
+        command: command.to_string(),
+        arguments: Some(vec![arguments]),
+    }
+}
+
+/// Public or external; functions without a visibility predate 0.5, where
+/// the default was public.
+fn is_entry_point(function: TsNode, text: &str) -> bool {
+    let mut cursor = function.walk();
+    let visibility = function
+        .children(&mut cursor)
+        .find(|child| child.kind() == "visibility")
+        .map(|child| get_node_text(&child, text));
+    matches!(visibility, None | Some("public" | "external"))
+}
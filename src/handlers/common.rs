@@ -1,14 +1,68 @@
+use crate::generator_worker::ShuttingDown;
 use crate::utils::TOKIO_RUNTIME;
 use anyhow::Result;
+use lsp_server::Response;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc;
 use tokio::sync::oneshot;
+use tracing::Span;
 
-pub fn send_request_to_worker<TRequest, TResponse>(
+/// Identifies a request queued for the worker pool in logs and responses.
+pub type JobId = u64;
+
+/// A request queued for the worker pool.
+pub struct Job<T> {
+    pub id: JobId,
+    reply: oneshot::Receiver<T>,
+}
+
+impl<T> Job<T> {
+    /// Waits for the reply. Fails with [`ShuttingDown`] if the worker drops
+    /// the request.
+    pub fn wait(self) -> Result<T> {
+        Ok(TOKIO_RUNTIME
+            .block_on(self.reply)
+            .map_err(|_| ShuttingDown)?)
+    }
+}
+
+/// Queues a request for the worker pool, recording its id on the current
+/// span's `job` field. Fails with the error of `build_request`, or with
+/// [`ShuttingDown`] if the workers have stopped.
+pub fn submit_job<TRequest, TResponse>(
     tx: &mpsc::Sender<TRequest>,
-    build_request: impl FnOnce(oneshot::Sender<TResponse>) -> TRequest,
-) -> Result<TResponse, mpsc::SendError<TRequest>> {
+    build_request: impl FnOnce(oneshot::Sender<TResponse>) -> Result<TRequest>,
+) -> Result<Job<TResponse>> {
+    static NEXT_JOB: AtomicU64 = AtomicU64::new(1);
+    let id = NEXT_JOB.fetch_add(1, Ordering::Relaxed);
+    // Recorded before the worker can log anything for the job.
+    Span::current().record("job", id);
     let (response_tx, response_rx) = oneshot::channel();
-    let request = build_request(response_tx);
-    tx.send(request)?;
-    Ok(TOKIO_RUNTIME.block_on(response_rx).unwrap())
+    let request = build_request(response_tx)?;
+    tx.send(request).map_err(|_| ShuttingDown)?;
+    Ok(Job {
+        id,
+        reply: response_rx,
+    })
+}
+
+/// Adds the request id to an error response's `data`, so a failed editor
+/// action can be matched with the server log.
+pub fn with_request_id(response: Response) -> Response {
+    let id = serde_json::to_value(&response.id).unwrap_or_default();
+    with_error_data(response, "request_id", id)
+}
+
+/// Sets `key` in an error response's `data`; successful responses are
+/// returned as they are.
+pub fn with_error_data(mut response: Response, key: &str, value: serde_json::Value) -> Response {
+    if let Some(error) = response.error.as_mut() {
+        match error.data.as_mut() {
+            Some(serde_json::Value::Object(data)) => {
+                data.insert(key.to_string(), value);
+            }
+            _ => error.data = Some(serde_json::json!({ key: value })),
+        }
+    }
+    response
 }
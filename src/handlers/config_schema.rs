@@ -0,0 +1,11 @@
+use crate::config;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+
+pub fn config_schema(req: Request, conn: &Connection) -> Result<()> {
+    let (id, _) = req.extract::<serde_json::Value>(crate::commands::CONFIG_SCHEMA_REQUEST)?;
+
+    let response = Response::new_ok(id, config::schema());
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! `textDocument/definition`: from a call site to the function, constructor
+//! or public variable getter it calls, in whichever workspace file declares
+//! it.
+//!
+//! Calls are resolved by the latest call graph a command built from the
+//! file, so interface calls land on the implementation the graph bound
+//! them to. Until there is a graph, or after the file was edited to a new
+//! length, the request answers nothing rather than guess.
+
+use crate::analysis::model::SourceUnit;
+use crate::analysis::unresolved_calls::PLACEHOLDER_CONTRACT;
+use crate::documents::DocumentStore;
+use crate::graph_cache::GraphCache;
+use crate::source_map::SourceMap;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{GotoDefinition, Request as _};
+use lsp_types::{GotoDefinitionParams, GotoDefinitionResponse, Location, Position, Url};
+use std::path::{Path, PathBuf};
+use traverse_graph::cg::{CallGraph, EdgeType, Node, NodeType};
+
+pub fn definition(
+    req: Request,
+    conn: &Connection,
+    documents: &DocumentStore,
+    graphs: &GraphCache,
+) -> Result<()> {
+    let (id, params) = req.extract::<GotoDefinitionParams>(GotoDefinition::METHOD)?;
+    let position = params.text_document_position_params;
+
+    let locations = position
+        .text_document
+        .uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| {
+            let (graph, sources) = cached_graph(documents, graphs, &path)?;
+            Some(definitions(
+                &graph,
+                &SourceMap::new(&sources),
+                &path,
+                position.position,
+            ))
+        })
+        .unwrap_or_default();
+    let response = Response::new_ok(
+        id,
+        (!locations.is_empty()).then_some(GotoDefinitionResponse::Array(locations)),
+    );
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// The latest call graph built from `path` and the current text of each
+/// file it was built from, or `None` when there is no graph or a file can
+/// no longer be read or changed length, which moves the spans after it.
+/// Profiles rewrite files in place, so lengths still match.
+pub(crate) fn cached_graph(
+    documents: &DocumentStore,
+    graphs: &GraphCache,
+    path: &Path,
+) -> Option<(CallGraph, Vec<SourceUnit>)> {
+    let (graph, files) = graphs.latest_with_files(path)?;
+    let sources = current_sources(documents, &files)?;
+    Some((graph, sources))
+}
+
+fn current_sources(
+    documents: &DocumentStore,
+    files: &[(PathBuf, usize)],
+) -> Option<Vec<SourceUnit>> {
+    files
+        .iter()
+        .map(|(path, len)| {
+            let content = documents.read(&Url::from_file_path(path).ok()?).ok()?;
+            (content.len() == *len).then(|| SourceUnit {
+                path: path.clone(),
+                content,
+            })
+        })
+        .collect()
+}
+
+/// Declarations called at `position` of `path`: the target of the
+/// innermost call whose callee expression holds the position.
+pub fn definitions(
+    graph: &CallGraph,
+    map: &SourceMap,
+    path: &Path,
+    position: Position,
+) -> Vec<Location> {
+    let Some(index) = map.index_of(path) else {
+        return Vec::new();
+    };
+    let mut locations: Vec<Location> = called_at(graph, map, map.offset(index, position))
+        .into_iter()
+        .filter_map(|node| map.locate(name_span(map, node)))
+        .collect();
+    locations.dedup();
+    locations
+}
+
+/// Declarations called by the innermost call whose callee expression holds
+/// span offset `offset`.
+pub(crate) fn called_at<'g>(graph: &'g CallGraph, map: &SourceMap, offset: usize) -> Vec<&'g Node> {
+    let Some(span) = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.edge_type == EdgeType::Call)
+        .map(|edge| edge.call_site_span)
+        .filter(|&(start, end)| start <= offset && offset < callee_end(map, start, end))
+        .min_by_key(|&(start, end)| end - start)
+    else {
+        return Vec::new();
+    };
+
+    graph
+        .edges
+        .iter()
+        .filter(|edge| edge.edge_type == EdgeType::Call && edge.call_site_span == span)
+        .filter_map(|edge| graph.nodes.get(edge.target_node_id))
+        .filter(|node| is_declaration(node))
+        .collect()
+}
+
+/// End of the callee in the call at `start..end`: everything before the
+/// parenthesis opening its arguments, so the arguments' own calls win.
+pub(crate) fn callee_end(map: &SourceMap, start: usize, end: usize) -> usize {
+    let Some(call) = map.text((start, end)) else {
+        return end;
+    };
+    let mut depth = 0;
+    for (index, c) in call.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' => {
+                depth -= 1;
+                if depth == 0 {
+                    return start + index;
+                }
+            }
+            _ => {}
+        }
+    }
+    end
+}
+
+fn is_declaration(node: &Node) -> bool {
+    node.contract_name.as_deref() != Some(PLACEHOLDER_CONTRACT)
+        && matches!(
+            node.node_type,
+            NodeType::Function
+                | NodeType::Modifier
+                | NodeType::Constructor
+                | NodeType::StorageVariable
+        )
+}
+
+/// The span of `node`'s name within its declaration, or the whole
+/// declaration when the name is not found, e.g. for a default constructor.
+pub(crate) fn name_span(map: &SourceMap, node: &Node) -> (usize, usize) {
+    let (start, end) = node.span;
+    let Some(declaration) = map.text((start, end)) else {
+        return node.span;
+    };
+    let name = match node.node_type {
+        NodeType::Constructor => "constructor",
+        _ => node.name.as_str(),
+    };
+    word_offsets(declaration, name)
+        .next()
+        .map_or(node.span, |at| (start + at, start + at + name.len()))
+}
+
+/// Offsets of `name` in `text` where it is a whole identifier.
+pub(crate) fn word_offsets<'t>(text: &'t str, name: &'t str) -> impl Iterator<Item = usize> + 't {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    text.match_indices(name)
+        .map(|(at, _)| at)
+        .filter(move |&at| {
+            !text[..at].ends_with(is_ident) && !text[at + name.len()..].starts_with(is_ident)
+        })
+}
@@ -0,0 +1,14 @@
+use crate::doctor::Report;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::ExecuteCommandParams;
+
+/// Answers `traverse.doctor` with `report`, checked when the command came
+/// in.
+pub fn doctor(req: Request, conn: &Connection, report: Report) -> Result<()> {
+    let (id, _) = req.extract::<ExecuteCommandParams>("workspace/executeCommand")?;
+
+    let response = Response::new_ok(id, report);
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
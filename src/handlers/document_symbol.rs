@@ -0,0 +1,128 @@
+//! `textDocument/documentSymbol`: an outline of contracts and their
+//! members, read from the same tree-sitter parse as the rest of the server.
+
+use crate::documents::DocumentStore;
+use crate::symbol_index;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{DocumentSymbolRequest, Request as _};
+use lsp_types::{DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, Range, SymbolKind};
+use traverse_graph::parser::{get_node_text, parse_solidity};
+use tree_sitter::Node as TsNode;
+
+pub fn document_symbol(req: Request, conn: &Connection, documents: &DocumentStore) -> Result<()> {
+    let (id, params) = req.extract::<DocumentSymbolParams>(DocumentSymbolRequest::METHOD)?;
+
+    let symbols = documents
+        .read(&params.text_document.uri)
+        .map(|text| outline(&text))
+        .unwrap_or_default();
+    let response = Response::new_ok(id, DocumentSymbolResponse::Nested(symbols));
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// The declarations of `text`, with the members of contracts, interfaces
+/// and libraries nested under them, in source order. Details hold what
+/// follows the name: a function's parameters, modifiers and return types,
+/// an event's or error's parameters, a variable's type.
+pub fn outline(text: &str) -> Vec<DocumentSymbol> {
+    let Ok(parsed) = parse_solidity(text) else {
+        return Vec::new();
+    };
+    symbols(parsed.tree.root_node(), text)
+}
+
+fn symbols(parent: TsNode, text: &str) -> Vec<DocumentSymbol> {
+    let mut symbols = Vec::new();
+    let mut cursor = parent.walk();
+    for node in parent.named_children(&mut cursor) {
+        if let Some(symbol) = symbol(node, text) {
+            symbols.push(symbol);
+        }
+    }
+    symbols
+}
+
+fn symbol(node: TsNode, text: &str) -> Option<DocumentSymbol> {
+    let in_contract = node.parent().is_some_and(|p| p.kind() == "contract_body");
+    let function = if in_contract {
+        SymbolKind::METHOD
+    } else {
+        SymbolKind::FUNCTION
+    };
+    let (kind, detail) = match node.kind() {
+        "contract_declaration" => (SymbolKind::CLASS, None),
+        "interface_declaration" => (SymbolKind::INTERFACE, None),
+        "library_declaration" => (SymbolKind::MODULE, None),
+        "function_definition" => (function, signature(node, text)),
+        // LSP has no kinds for these, so the detail names them.
+        "modifier_definition" => (function, Some(prefixed("modifier", node, text))),
+        "constructor_definition" => (SymbolKind::CONSTRUCTOR, signature(node, text)),
+        "fallback_receive_definition" => (function, signature(node, text)),
+        "event_definition" => (SymbolKind::EVENT, signature(node, text)),
+        "error_declaration" => (SymbolKind::OBJECT, Some(prefixed("error", node, text))),
+        "state_variable_declaration" => (
+            SymbolKind::FIELD,
+            node.child_by_field_name("type")
+                .map(|t| get_node_text(&t, text).to_string()),
+        ),
+        "struct_declaration" => (SymbolKind::STRUCT, None),
+        "enum_declaration" => (SymbolKind::ENUM, None),
+        _ => return None,
+    };
+    // Constructors, fallback and receive functions are named by their
+    // keyword; `function()` is the fallback of old compilers.
+    let name_node = node.child_by_field_name("name").or_else(|| node.child(0))?;
+    let name = match get_node_text(&name_node, text) {
+        "function" => "fallback",
+        name => name,
+    };
+    let children = match node.kind() {
+        "contract_declaration" | "interface_declaration" | "library_declaration" => node
+            .child_by_field_name("body")
+            .map(|body| symbols(body, text)),
+        _ => None,
+    };
+
+    #[allow(deprecated)]
+    Some(DocumentSymbol {
+        name: name.to_string(),
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: range(node, text),
+        selection_range: range(name_node, text),
+        children,
+    })
+}
+
+/// What follows the name, or keyword, of a declaration up to its body, on
+/// one line.
+fn signature(node: TsNode, text: &str) -> Option<String> {
+    let name = node.child_by_field_name("name").or_else(|| node.child(0))?;
+    let end = node
+        .child_by_field_name("body")
+        .map_or(node.end_byte(), |body| body.start_byte());
+    let header = text.get(name.end_byte()..end)?.trim_end_matches(';');
+    let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+    // Parameters listed one per line would read `( uint a, uint b )`.
+    let header = header.replace("( ", "(").replace(" )", ")");
+    (!header.is_empty()).then_some(header)
+}
+
+/// `keyword` followed by the signature of `node`, if it has one.
+fn prefixed(keyword: &str, node: TsNode, text: &str) -> String {
+    match signature(node, text) {
+        Some(signature) => format!("{} {}", keyword, signature),
+        None => keyword.to_string(),
+    }
+}
+
+fn range(node: TsNode, text: &str) -> Range {
+    Range::new(
+        symbol_index::position(text, node.start_position()),
+        symbol_index::position(text, node.end_position()),
+    )
+}
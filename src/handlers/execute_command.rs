@@ -1,82 +1,823 @@
 use crate::{
-    commands, generator_worker::GenerationRequest, handlers::common::send_request_to_worker,
+    analysis::{
+        clones::DEFAULT_MIN_NODES,
+        privilege_paths::{Privileges, DEFAULT_PRIVILEGED_MODIFIERS},
+        summary, Confidence,
+    },
+    cancellation, commands,
+    config::{DotConfig, FileFilter, GraphFilter, MermaidConfig},
+    exporters::ExportFormat,
+    file_filter,
+    foundry::FoundryProject,
+    generator_worker::{
+        CancelToken, GenerationRequest, ProgressCallback, RequestCancelled, ShuttingDown,
+    },
+    handlers::common::{submit_job, with_error_data, with_request_id, JobId},
+    hardhat::HardhatProject,
+    output::envelope::{AnalysisResult, ArtifactFormat},
+    output::template,
+    query_history,
+    renderers::{self, RenderOptions},
+    telemetry::CommandEvent,
+    traverse_adapter::TraverseAdapter,
+    utils::{files_changed_since, find_source_files, TOKIO_RUNTIME},
+    watchdog::GenerationTimedOut,
 };
 use anyhow::Result;
-use lsp_server::{Connection, Message, Notification, Request, Response};
-use lsp_types::{ExecuteCommandParams, MessageType, ShowMessageParams, Url};
+use crossbeam_channel::Sender;
+use dashmap::DashMap;
+use lsp_server::{ErrorCode, Message, Notification, Request, RequestId, Response, ResponseError};
+use lsp_types::{
+    notification::{Notification as _, Progress},
+    request::{Request as _, WorkDoneProgressCreate},
+    ExecuteCommandParams, MessageType, ProgressParams, ProgressParamsValue, ProgressToken,
+    ShowMessageParams, TextDocumentPositionParams, Url, WorkDoneProgress, WorkDoneProgressBegin,
+    WorkDoneProgressCreateParams, WorkDoneProgressEnd, WorkDoneProgressReport,
+};
+use once_cell::sync::Lazy;
 use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc;
-use tracing::{debug, error, info};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, field, info, info_span, warn};
 
+/// Runs a workspace command and sends its response. `cancel` is set when
+/// the client cancels the request.
+#[allow(dead_code)]
 pub fn execute_command(
     req: Request,
-    conn: &Connection,
+    sender: &Sender<Message>,
     generator_tx: &mpsc::Sender<GenerationRequest>,
+    cancel: CancelToken,
 ) -> Result<()> {
+    execute_command_with_telemetry(req, sender, generator_tx, cancel, false)
+}
+
+/// [`execute_command`], followed by a `telemetry/event` notification about
+/// the command when `telemetry` is set.
+pub fn execute_command_with_telemetry(
+    req: Request,
+    sender: &Sender<Message>,
+    generator_tx: &mpsc::Sender<GenerationRequest>,
+    cancel: CancelToken,
+    telemetry: bool,
+) -> Result<()> {
+    let started = Instant::now();
     let (id, params) = req.extract::<ExecuteCommandParams>("workspace/executeCommand")?;
+    // Everything logged for the command, on this thread and on the worker
+    // running it, carries the request id and command name.
+    let span = info_span!(
+        "request",
+        id = %id,
+        command = %params.command,
+        job = field::Empty
+    );
+    let _entered = span.enter();
+    let cancel = cancel.with_span(span.clone());
     debug!("Executing command: {}", params.command);
 
+    let command = params.command.clone();
+    let response = match per_folder_params(&params) {
+        Some(folders) => per_folder_response(id.clone(), folders, |params| {
+            command_response(sender, generator_tx, cancel.clone(), id.clone(), params)
+        })?,
+        None => command_response(sender, generator_tx, cancel, id, params)?,
+    };
+
+    let response = with_request_id(response);
+    let event = telemetry.then(|| CommandEvent::new(&command, started.elapsed(), &response));
+    sender.send(Message::Response(response))?;
+    if let Some(event) = event {
+        sender.send(event.notification().into())?;
+    }
+    Ok(())
+}
+
+/// Runs a command on a single workspace folder and returns its response.
+fn command_response(
+    sender: &Sender<Message>,
+    generator_tx: &mpsc::Sender<GenerationRequest>,
+    cancel: CancelToken,
+    id: RequestId,
+    params: ExecuteCommandParams,
+) -> Result<Response> {
+    let command = params.command.clone();
+    let arguments = params.arguments.clone();
+
     let response = match params.command.as_str() {
         commands::GENERATE_CALL_GRAPH_WORKSPACE => {
-            workspace_command(conn, id.clone(), params, generator_tx, |uris, tx| {
-                show_message(
-                    conn,
-                    MessageType::INFO,
-                    format!("Analyzing {} files...", uris.len()),
-                )?;
-                Ok(GenerationRequest::GenerateCallGraphDiagram {
-                    uris,
-                    contract_name: None,
-                    tx,
-                })
-            })
+            let dot_config = dot_config(&params, &id);
+            let contract_name = contract_name(&params, &id);
+            let filter = graph_filter(&params, &id);
+            let coverage_file = coverage_file(&params, &id);
+            workspace_command_with_progress(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                "Generating call graph",
+                move |uris, tx, cancel, progress| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Analyzing {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::GenerateCallGraphDiagram {
+                        uris,
+                        contract_name,
+                        dot_config,
+                        filter,
+                        coverage_file,
+                        progress: Some(progress),
+                        cancel,
+                        tx,
+                    })
+                },
+            )
         }
         commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE => {
+            let config = mermaid_config(&params, &id);
+            let contract_name = contract_name(&params, &id);
+            let filter = graph_filter(&params, &id);
+            let workspace_folder = workspace_folder(&params, &id);
+            workspace_command_with_progress(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                "Generating sequence diagram",
+                move |uris, tx, cancel, progress| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Generating diagram for {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::GenerateMermaidFlowchart {
+                        uris,
+                        workspace_folder,
+                        contract_name,
+                        config,
+                        filter,
+                        progress: Some(progress),
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+        commands::GENERATE_ALL_WORKSPACE => {
+            let dot_config = dot_config(&params, &id);
+            let mermaid_config = mermaid_config(&params, &id);
+            let contract_name = contract_name(&params, &id);
+            let filter = graph_filter(&params, &id);
+            let workspace_folder = workspace_folder(&params, &id);
+            workspace_command_with_progress(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                "Generating diagrams",
+                move |uris, tx, cancel, progress| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Generating all for {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::GenerateAllDiagrams {
+                        uris,
+                        workspace_folder,
+                        contract_name,
+                        dot_config,
+                        mermaid_config,
+                        filter,
+                        progress: Some(progress),
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+        commands::ANALYZE_STORAGE_WORKSPACE => {
+            let template = match extract_args::<StorageLayoutArgs>(&params, &id) {
+                Ok(args) => args
+                    .template
+                    .map(|path| Path::new(&args.workspace_folder).join(path)),
+                Err(response) => return Ok(response),
+            };
+            // The report is the worker's whole answer, so its format is
+            // known here rather than listed by the worker.
+            let format = if template.as_deref().is_some_and(template::is_html) {
+                ArtifactFormat::Html
+            } else {
+                ArtifactFormat::Markdown
+            };
+            text_workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                format,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Analyzing storage for {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::GenerateStorageLayout {
+                        uris,
+                        contract_name: String::new(),
+                        template,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+        commands::GENERATE_AUDIT_REPORT_WORKSPACE => {
+            let args = extract_args::<WorkspaceArgs>(&params, &id);
+            let min_confidence = args.as_ref().map(|a| a.min_confidence).unwrap_or_default();
+            let workspace_folder = args
+                .map(|a| PathBuf::from(a.workspace_folder))
+                .unwrap_or_default();
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Auditing {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::GenerateAuditReport {
+                        uris,
+                        workspace_folder,
+                        min_confidence,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+        commands::SUMMARIZE_FINDINGS => {
             let args = extract_args::<WorkspaceArgs>(&params, &id);
-            let no_chunk = args.as_ref().map(|a| a.no_chunk).unwrap_or(false);
-            workspace_command(conn, id.clone(), params, generator_tx, move |uris, tx| {
+            let min_confidence = args.as_ref().map(|a| a.min_confidence).unwrap_or_default();
+            let top_contracts = args
+                .as_ref()
+                .ok()
+                .and_then(|a| a.top_contracts)
+                .unwrap_or(summary::DEFAULT_TOP_CONTRACTS);
+            let workspace_folder = workspace_folder(&params, &id);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Summarizing findings in {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::SummarizeFindings {
+                        uris,
+                        workspace_folder,
+                        min_confidence,
+                        top_contracts,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+        commands::GENERATE_PR_COMMENT => match extract_args::<PrCommentArgs>(&params, &id) {
+            // The base revision is always read in full, so a narrowed head
+            // would show every out-of-scope entry point as removed.
+            Ok(args) if args.changed_since.is_some() => Ok(Response::new_err(
+                id,
+                -32602,
+                "changed_since cannot be used with traverse.generatePrComment".into(),
+            )),
+            Ok(args) => {
+                let workspace_folder = workspace_folder(&params, &id);
+                workspace_command(
+                    sender,
+                    id.clone(),
+                    params,
+                    generator_tx,
+                    cancel,
+                    move |uris, tx, cancel| {
+                        show_message(
+                            sender,
+                            MessageType::INFO,
+                            format!(
+                                "Comparing {} files against {}...",
+                                uris.len(),
+                                args.base_ref
+                            ),
+                        )?;
+                        Ok(GenerationRequest::GeneratePrComment {
+                            uris,
+                            workspace_folder,
+                            base_ref: args.base_ref,
+                            cancel,
+                            tx,
+                        })
+                    },
+                )
+            }
+            Err(response) => Ok(response),
+        },
+        commands::WARM_CACHE => match workspace_files(sender, &id, &params)? {
+            Ok(uris) => warm_cache(sender, id.clone(), &params, uris, generator_tx),
+            Err(response) => Ok(response),
+        },
+        commands::LIST_FUZZ_TARGETS_WORKSPACE => workspace_command(
+            sender,
+            id.clone(),
+            params,
+            generator_tx,
+            cancel,
+            |uris, tx, cancel| {
                 show_message(
-                    conn,
+                    sender,
                     MessageType::INFO,
-                    format!("Generating diagram for {} files...", uris.len()),
+                    format!("Extracting fuzz targets from {} files...", uris.len()),
                 )?;
-                Ok(GenerationRequest::GenerateMermaidFlowchart {
-                    uris,
-                    contract_name: None,
-                    no_chunk,
-                    tx,
-                })
-            })
-        }
-        commands::GENERATE_ALL_WORKSPACE => {
-            workspace_command(conn, id.clone(), params, generator_tx, |uris, tx| {
+                Ok(GenerationRequest::ListFuzzTargets { uris, cancel, tx })
+            },
+        ),
+        commands::AUDIT_OVERRIDES_WORKSPACE => workspace_command(
+            sender,
+            id.clone(),
+            params,
+            generator_tx,
+            cancel,
+            |uris, tx, cancel| {
+                show_message(
+                    sender,
+                    MessageType::INFO,
+                    format!("Auditing overrides in {} files...", uris.len()),
+                )?;
+                Ok(GenerationRequest::AuditOverrides { uris, cancel, tx })
+            },
+        ),
+        commands::CROSS_REFERENCE_WORKSPACE => workspace_command(
+            sender,
+            id.clone(),
+            params,
+            generator_tx,
+            cancel,
+            |uris, tx, cancel| {
                 show_message(
-                    conn,
+                    sender,
                     MessageType::INFO,
-                    format!("Generating all for {} files...", uris.len()),
+                    format!(
+                        "Cross-referencing enums and constants in {} files...",
+                        uris.len()
+                    ),
                 )?;
-                Ok(GenerationRequest::GenerateAllDiagrams {
-                    uris,
-                    contract_name: None,
-                    tx,
-                })
-            })
+                Ok(GenerationRequest::CrossReference { uris, cancel, tx })
+            },
+        ),
+        commands::MAGIC_NUMBERS_WORKSPACE => workspace_command(
+            sender,
+            id.clone(),
+            params,
+            generator_tx,
+            cancel,
+            |uris, tx, cancel| {
+                show_message(
+                    sender,
+                    MessageType::INFO,
+                    format!("Collecting numeric literals in {} files...", uris.len()),
+                )?;
+                Ok(GenerationRequest::MagicNumbers { uris, cancel, tx })
+            },
+        ),
+        commands::EXPORT_GRAPH_WORKSPACE => {
+            let args = extract_args::<WorkspaceArgs>(&params, &id);
+            let format = args.as_ref().map(|a| a.format).unwrap_or_default();
+            let consumers_file = args.as_ref().ok().and_then(|a| a.consumers_file.clone());
+            let anonymize = args.as_ref().is_ok_and(|a| a.anonymize);
+            let filter = args.map(|a| a.filter).unwrap_or_default();
+            let workspace_folder = workspace_folder(&params, &id);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Exporting graph for {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::ExportGraph {
+                        uris,
+                        workspace_folder,
+                        format,
+                        filter,
+                        consumers_file,
+                        anonymize,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
         }
-        commands::ANALYZE_STORAGE_WORKSPACE => {
-            workspace_command(conn, id.clone(), params, generator_tx, |uris, tx| {
+        commands::IMPORT_TRACE => match extract_args::<ImportTraceArgs>(&params, &id) {
+            Ok(args) if args.trace.is_none() && args.trace_file.is_none() => Ok(Response::new_err(
+                id,
+                -32602,
+                "traverse.importTrace needs trace or trace_file".into(),
+            )),
+            Ok(args) => {
+                let workspace_folder = workspace_folder(&params, &id);
+                let trace_file = args.trace_file.map(|file| workspace_folder.join(file));
+                workspace_command(
+                    sender,
+                    id.clone(),
+                    params,
+                    generator_tx,
+                    cancel,
+                    move |uris, tx, cancel| {
+                        show_message(
+                            sender,
+                            MessageType::INFO,
+                            format!("Aligning trace with {} files...", uris.len()),
+                        )?;
+                        Ok(GenerationRequest::ImportTrace {
+                            uris,
+                            trace: args.trace,
+                            trace_file,
+                            cancel,
+                            tx,
+                        })
+                    },
+                )
+            }
+            Err(response) => Ok(response),
+        },
+
+        commands::STORAGE_HEATMAP_WORKSPACE => {
+            let contract_name = extract_args::<StorageHeatmapArgs>(&params, &id)
+                .ok()
+                .and_then(|args| args.contract);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Counting storage writes in {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::StorageHeatmap {
+                        uris,
+                        contract_name,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+
+        commands::CALL_MATRIX_WORKSPACE => workspace_command(
+            sender,
+            id.clone(),
+            params,
+            generator_tx,
+            cancel,
+            move |uris, tx, cancel| {
                 show_message(
-                    conn,
+                    sender,
                     MessageType::INFO,
-                    format!("Analyzing storage for {} files...", uris.len()),
+                    format!(
+                        "Counting calls between contracts in {} files...",
+                        uris.len()
+                    ),
                 )?;
-                Ok(GenerationRequest::GenerateStorageLayout {
-                    uris,
-                    contract_name: String::new(),
-                    tx,
-                })
-            })
+                Ok(GenerationRequest::CallMatrix { uris, cancel, tx })
+            },
+        ),
+
+        commands::GOVERNANCE_REPORT_WORKSPACE => workspace_command(
+            sender,
+            id.clone(),
+            params,
+            generator_tx,
+            cancel,
+            move |uris, tx, cancel| {
+                show_message(
+                    sender,
+                    MessageType::INFO,
+                    format!("Analyzing admin controls in {} files...", uris.len()),
+                )?;
+                Ok(GenerationRequest::GovernanceReport { uris, cancel, tx })
+            },
+        ),
+
+        commands::PAUSE_COVERAGE_WORKSPACE => workspace_command(
+            sender,
+            id.clone(),
+            params,
+            generator_tx,
+            cancel,
+            move |uris, tx, cancel| {
+                show_message(
+                    sender,
+                    MessageType::INFO,
+                    format!("Checking pause coverage in {} files...", uris.len()),
+                )?;
+                Ok(GenerationRequest::PauseCoverage { uris, cancel, tx })
+            },
+        ),
+
+        commands::EXTERNAL_DEPENDENCIES_WORKSPACE => {
+            let contract_name = extract_args::<ExternalDependenciesArgs>(&params, &id)
+                .ok()
+                .and_then(|args| args.contract);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Finding external dependencies in {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::ExternalDependencies {
+                        uris,
+                        contract_name,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+
+        commands::FIND_PRIVILEGE_PATHS_WORKSPACE => {
+            let privileges = match extract_args::<PrivilegePathsArgs>(&params, &id) {
+                Ok(args) => Privileges {
+                    modifiers: args.modifiers.unwrap_or_else(|| {
+                        DEFAULT_PRIVILEGED_MODIFIERS
+                            .iter()
+                            .map(|m| m.to_string())
+                            .collect()
+                    }),
+                    functions: args.functions,
+                },
+                Err(response) => return Ok(response),
+            };
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!(
+                            "Following calls to privileged functions in {} files...",
+                            uris.len()
+                        ),
+                    )?;
+                    Ok(GenerationRequest::PrivilegePaths {
+                        uris,
+                        privileges,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+
+        commands::FIND_CLONES_WORKSPACE => {
+            let min_nodes = match extract_args::<FindClonesArgs>(&params, &id) {
+                Ok(args) => args.min_nodes.unwrap_or(DEFAULT_MIN_NODES),
+                Err(response) => return Ok(response),
+            };
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Comparing function bodies in {} files...", uris.len()),
+                    )?;
+                    Ok(GenerationRequest::FindClones {
+                        uris,
+                        min_nodes,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+
+        commands::GENERATE_FUNCTION_DIAGRAM => {
+            let args = match extract_args::<FunctionDiagramArgs>(&params, &id) {
+                Ok(args) => args,
+                Err(response) => return Ok(response),
+            };
+            let config = mermaid_config(&params, &id);
+            let filter = graph_filter(&params, &id);
+            let workspace_folder = workspace_folder(&params, &id);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!(
+                            "Generating diagram for {}.{}...",
+                            args.contract, args.function
+                        ),
+                    )?;
+                    Ok(GenerationRequest::GenerateFunctionDiagram {
+                        uris,
+                        workspace_folder,
+                        contract_name: args.contract,
+                        function_name: args.function,
+                        config,
+                        filter,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+
+        commands::GENERATE_FUNCTION_DIAGRAM_AT_POSITION => {
+            let position = match extract_args::<PositionArgs>(&params, &id) {
+                Ok(args) => args.position,
+                Err(response) => return Ok(response),
+            };
+            let config = mermaid_config(&params, &id);
+            let filter = graph_filter(&params, &id);
+            let workspace_folder = workspace_folder(&params, &id);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        "Generating diagram for the function at the cursor...".to_string(),
+                    )?;
+                    Ok(GenerationRequest::GenerateFunctionDiagramAt {
+                        uris,
+                        workspace_folder,
+                        position,
+                        config,
+                        filter,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
         }
 
+        commands::REGENERATE_CHUNK => {
+            let args = match extract_args::<RegenerateChunkArgs>(&params, &id) {
+                Ok(args) => args,
+                Err(response) => return Ok(response),
+            };
+            let config = mermaid_config(&params, &id);
+            let contract_name = contract_name(&params, &id);
+            let filter = graph_filter(&params, &id);
+            let workspace_folder = workspace_folder(&params, &id);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Regenerating chunk {}...", args.chunk),
+                    )?;
+                    Ok(GenerationRequest::RegenerateChunk {
+                        uris,
+                        workspace_folder,
+                        contract_name,
+                        function_name: args.function,
+                        chunk: args.chunk,
+                        config,
+                        filter,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+        commands::RENDER_DIAGRAM => {
+            let args = match extract_args::<RenderDiagramArgs>(&params, &id) {
+                Ok(args) => args,
+                Err(response) => return Ok(response),
+            };
+            let Some(renderer) = renderers::renderer(&args.renderer) else {
+                return Ok(Response::new_err(
+                    id,
+                    -32602,
+                    format!(
+                        "Unknown renderer {}; expected one of {}",
+                        args.renderer,
+                        renderers::renderer_names().join(", ")
+                    ),
+                ));
+            };
+            let options = RenderOptions {
+                dot: dot_config(&params, &id),
+                mermaid: mermaid_config(&params, &id),
+            };
+            let contract_name = contract_name(&params, &id);
+            let filter = graph_filter(&params, &id);
+            workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!("Rendering {} diagram...", renderer.name()),
+                    )?;
+                    Ok(GenerationRequest::RenderDiagram {
+                        uris,
+                        contract_name,
+                        renderer,
+                        options,
+                        filter,
+                        cancel,
+                        tx,
+                    })
+                },
+            )
+        }
+
+        commands::COMPARE_STORAGE => match extract_args::<CompareStorageArgs>(&params, &id) {
+            Ok(args) => workspace_command(
+                sender,
+                id.clone(),
+                params,
+                generator_tx,
+                cancel,
+                move |uris, tx, cancel| {
+                    show_message(
+                        sender,
+                        MessageType::INFO,
+                        format!(
+                            "Comparing storage of {} and {}...",
+                            args.left_contract, args.right_contract
+                        ),
+                    )?;
+                    Ok(GenerationRequest::CompareStorage {
+                        uris,
+                        left_contract: args.left_contract,
+                        right_contract: args.right_contract,
+                        cancel,
+                        tx,
+                    })
+                },
+            ),
+            Err(response) => Ok(response),
+        },
+
         _ => Ok(Response::new_err(
             id,
             -32601,
@@ -84,76 +825,649 @@ pub fn execute_command(
         )),
     }?;
 
-    conn.sender.send(Message::Response(response))?;
-    Ok(())
+    if commands::QUERY_COMMANDS.contains(&command.as_str()) && response.error.is_none() {
+        record_query(&command, arguments);
+    }
+    Ok(response)
+}
+
+/// One copy of `params` per folder of a `workspace_folders` argument, each
+/// naming its folder as `workspace_folder`; `None` when the arguments name
+/// a single folder.
+fn per_folder_params(params: &ExecuteCommandParams) -> Option<Vec<(String, ExecuteCommandParams)>> {
+    let args = params.arguments.first()?.as_object()?;
+    if args.contains_key("workspace_folder") {
+        return None;
+    }
+    let folders = args.get("workspace_folders")?.as_array()?;
+    let per_folder = folders
+        .iter()
+        .filter_map(|folder| folder.as_str())
+        .map(|folder| {
+            let mut params = params.clone();
+            let args = params.arguments[0].as_object_mut().unwrap();
+            args.remove("workspace_folders");
+            args.insert("workspace_folder".to_string(), folder.into());
+            (folder.to_string(), params)
+        })
+        .collect();
+    Some(per_folder)
+}
+
+/// Runs a command on each folder in turn and answers with every folder's
+/// result or error under `roots`. A cancelled or abandoned folder ends the
+/// command with that response.
+fn per_folder_response(
+    id: RequestId,
+    folders: Vec<(String, ExecuteCommandParams)>,
+    mut run: impl FnMut(ExecuteCommandParams) -> Result<Response>,
+) -> Result<Response> {
+    let mut roots = Vec::new();
+    let mut success = true;
+    let mut files = 0;
+    for (folder, params) in folders {
+        let response = run(params)?;
+        match response.error {
+            Some(error)
+                if error.code == ErrorCode::RequestCanceled as i32
+                    || error.code == ErrorCode::ServerCancelled as i32 =>
+            {
+                return Ok(Response {
+                    id,
+                    result: None,
+                    error: Some(error),
+                });
+            }
+            Some(error) => {
+                success = false;
+                roots.push(serde_json::json!({
+                    "workspace_folder": folder,
+                    "error": error,
+                }));
+            }
+            None => {
+                let result = response.result.unwrap_or_default();
+                files += result["files"].as_u64().unwrap_or(0);
+                roots.push(serde_json::json!({
+                    "workspace_folder": folder,
+                    "result": result,
+                }));
+            }
+        }
+    }
+    Ok(Response::new_ok(
+        id,
+        serde_json::json!({
+            "success": success,
+            "files": files,
+            "roots": roots,
+        }),
+    ))
+}
+
+fn record_query(command: &str, arguments: Vec<serde_json::Value>) {
+    let Some(workspace_folder) = arguments
+        .first()
+        .and_then(|args| args.get("workspace_folder"))
+        .and_then(|folder| folder.as_str())
+    else {
+        return;
+    };
+    if let Err(e) = query_history::record(Path::new(workspace_folder), command, arguments.clone()) {
+        error!("Failed to record query history: {}", e);
+    }
 }
 
 fn workspace_command(
-    conn: &Connection,
+    sender: &Sender<Message>,
     id: lsp_server::RequestId,
     params: ExecuteCommandParams,
     generator_tx: &mpsc::Sender<GenerationRequest>,
+    cancel: CancelToken,
     build_request: impl FnOnce(
         Vec<Url>,
         tokio::sync::oneshot::Sender<Result<String>>,
+        CancelToken,
     ) -> Result<GenerationRequest>,
 ) -> Result<Response> {
-    let workspace_args = match extract_args::<WorkspaceArgs>(&params, &id) {
-        Ok(args) => args,
+    text_workspace_command(
+        sender,
+        id,
+        params,
+        generator_tx,
+        cancel,
+        ArtifactFormat::Text,
+        build_request,
+    )
+}
+
+/// [`workspace_command`] for requests whose worker answers with text in
+/// `format` instead of JSON.
+fn text_workspace_command(
+    sender: &Sender<Message>,
+    id: lsp_server::RequestId,
+    params: ExecuteCommandParams,
+    generator_tx: &mpsc::Sender<GenerationRequest>,
+    cancel: CancelToken,
+    format: ArtifactFormat,
+    build_request: impl FnOnce(
+        Vec<Url>,
+        tokio::sync::oneshot::Sender<Result<String>>,
+        CancelToken,
+    ) -> Result<GenerationRequest>,
+) -> Result<Response> {
+    let sol_files = match workspace_files(sender, &id, &params)? {
+        Ok(files) => files,
         Err(response) => return Ok(response),
     };
-    let sol_files = find_solidity_files(&workspace_args.workspace_folder)?;
+
+    let command = params.command.clone();
+    let files = Some(sol_files.len());
+    let job = match submit_job(generator_tx, |tx| build_request(sol_files, tx, cancel)) {
+        Ok(job) => job,
+        // Failed before the job ran, like a job that fails.
+        Err(e) => return generation_result(sender, id, &command, None, files, format, Ok(Err(e))),
+    };
+    debug!("Queued {} as job {}", command, job.id);
+    let job_id = job.id;
+    let result = job.wait();
+    debug!("Job {} finished", job_id);
+    generation_result(sender, id, &command, Some(job_id), files, format, result)
+}
+
+/// The Solidity files a workspace command runs on, or the response to send
+/// instead when the arguments are invalid or there are no files.
+fn workspace_files(
+    sender: &Sender<Message>,
+    id: &lsp_server::RequestId,
+    params: &ExecuteCommandParams,
+) -> Result<Result<Vec<Url>, Response>> {
+    let workspace_args = match extract_args::<WorkspaceArgs>(params, id) {
+        Ok(args) => args,
+        Err(response) => return Ok(Err(response)),
+    };
+    let folder = Path::new(&workspace_args.workspace_folder);
+    let mut sol_files = if !workspace_args.entry_files.is_empty() {
+        match import_closure(sender, folder, &workspace_args.entry_files)? {
+            Ok(files) => files,
+            Err(message) => return Ok(Err(Response::new_err(id.clone(), -32602, message))),
+        }
+    } else if workspace_args.files.include.is_empty() {
+        project_scope(
+            folder,
+            find_source_files(folder)?,
+            workspace_args.files.node_modules,
+        )
+    } else {
+        // `include` patterns choose the files themselves.
+        find_source_files(folder)?
+    };
+    file_filter::retain(
+        &mut sol_files,
+        &workspace_args.files,
+        Path::new(&workspace_args.workspace_folder),
+    );
+    if let Some(rev) = &workspace_args.changed_since {
+        let total = sol_files.len();
+        sol_files = match files_changed_since(&workspace_args.workspace_folder, &sol_files, rev) {
+            Ok(files) => files,
+            Err(e) => {
+                error!("Failed to find files changed since {}: {}", rev, e);
+                return Ok(Err(Response::new_err(id.clone(), -32602, e.to_string())));
+            }
+        };
+        info!(
+            "{} of {} Solidity files changed since {} or imported by a change",
+            sol_files.len(),
+            total,
+            rev
+        );
+    }
 
     if sol_files.is_empty() {
-        show_message(
-            conn,
-            MessageType::WARNING,
-            "No Solidity files found in workspace".into(),
-        )?;
-        return Ok(Response::new_ok(id, serde_json::json!(null)));
+        let message = match &workspace_args.changed_since {
+            Some(rev) => format!("No Solidity files changed since {}", rev),
+            None if workspace_args.files.has_patterns() => {
+                "No Solidity files in workspace match the include and exclude patterns".into()
+            }
+            None => "No Solidity files found in workspace".into(),
+        };
+        show_message(sender, MessageType::WARNING, message)?;
+        return Ok(Err(Response::new_ok(id.clone(), serde_json::json!(null))));
     }
 
     info!("Found {} Solidity files in workspace", sol_files.len());
+    Ok(Ok(sol_files))
+}
 
-    let result = send_request_to_worker(generator_tx, |tx| build_request(sol_files, tx).unwrap());
-    match result {
-        Ok(res) => generation_result(conn, id, Ok(res)),
-        Err(_) => Ok(Response::new_err(
-            id,
-            -32603,
-            "Failed to send request".into(),
-        )),
+/// The requested files and every file they import, warning about imports
+/// that resolve to nothing; `Err` with a message when a requested file is
+/// missing.
+fn import_closure(
+    sender: &Sender<Message>,
+    workspace_folder: &Path,
+    entries: &[PathBuf],
+) -> Result<Result<Vec<Url>, String>> {
+    let entries: Vec<PathBuf> = entries.iter().map(|e| workspace_folder.join(e)).collect();
+    if let Some(missing) = entries.iter().find(|e| !e.is_file()) {
+        return Ok(Err(format!("No such file: {}", missing.display())));
+    }
+    let closure = TraverseAdapter::new()?.resolve_imports(workspace_folder, &entries)?;
+    info!(
+        "{} files imported from {} requested",
+        closure.files.len(),
+        entries.len()
+    );
+
+    if !closure.unresolved.is_empty() {
+        let mut unresolved = Vec::new();
+        for import in &closure.unresolved {
+            let file = import
+                .file
+                .strip_prefix(workspace_folder)
+                .unwrap_or(&import.file);
+            warn!(
+                "Unresolved import {:?} in {}",
+                import.import,
+                file.display()
+            );
+            unresolved.push(format!("\"{}\" in {}", import.import, file.display()));
+        }
+        let shown = unresolved.len().min(5);
+        let mut message = match unresolved.len() {
+            1 => "1 import could not be resolved and is left out of the graph: ".to_string(),
+            n => format!(
+                "{} imports could not be resolved and are left out of the graph: ",
+                n
+            ),
+        };
+        message.push_str(&unresolved[..shown].join(", "));
+        if unresolved.len() > shown {
+            message.push_str(&format!(" and {} more", unresolved.len() - shown));
+        }
+        show_message(sender, MessageType::WARNING, message)?;
+    }
+
+    closure
+        .files
+        .iter()
+        .map(|path| Url::from_file_path(path).map_err(|_| anyhow::anyhow!("Invalid path")))
+        .collect::<Result<Vec<_>>>()
+        .map(Ok)
+}
+
+/// In a Foundry or Hardhat project, the contracts under its source
+/// directory and the files they import; otherwise `files` unchanged.
+fn project_scope(workspace_folder: &Path, files: Vec<Url>, node_modules: bool) -> Vec<Url> {
+    let project = match FoundryProject::detect(workspace_folder) {
+        Ok(Some(project)) => project,
+        Ok(None) => return hardhat_scope(workspace_folder, files, node_modules),
+        Err(e) => {
+            warn!("Ignoring Foundry settings: {:#}", e);
+            return files;
+        }
+    };
+    match project.scope(&files) {
+        Ok(scoped) => {
+            info!(
+                "Foundry project: {} of {} files are under {} or imported from there",
+                scoped.len(),
+                files.len(),
+                project.src.display()
+            );
+            scoped
+        }
+        Err(e) => {
+            warn!("Failed to follow imports of the Foundry project: {:#}", e);
+            files
+        }
+    }
+}
+
+fn hardhat_scope(workspace_folder: &Path, files: Vec<Url>, node_modules: bool) -> Vec<Url> {
+    let project = match HardhatProject::detect(workspace_folder) {
+        Ok(Some(project)) => project,
+        Ok(None) => return files,
+        Err(e) => {
+            warn!("Ignoring Hardhat settings: {:#}", e);
+            return files;
+        }
+    };
+    match project.scope(&files, node_modules) {
+        Ok(scoped) => {
+            info!(
+                "Hardhat project: {} of {} files are under {} or imported from there",
+                scoped.len(),
+                files.len(),
+                project.sources.display()
+            );
+            scoped
+        }
+        Err(e) => {
+            warn!("Failed to follow imports of the Hardhat project: {:#}", e);
+            files
+        }
+    }
+}
+
+/// [`workspace_command`] for requests that report work-done progress: on the
+/// client's token, or on one the server creates, ended once the response is
+/// ready.
+fn workspace_command_with_progress(
+    sender: &Sender<Message>,
+    id: lsp_server::RequestId,
+    params: ExecuteCommandParams,
+    generator_tx: &mpsc::Sender<GenerationRequest>,
+    cancel: CancelToken,
+    title: &str,
+    build_request: impl FnOnce(
+        Vec<Url>,
+        tokio::sync::oneshot::Sender<Result<String>>,
+        CancelToken,
+        ProgressCallback,
+    ) -> Result<GenerationRequest>,
+) -> Result<Response> {
+    let client_token = params.work_done_progress_params.work_done_token.clone();
+    let mut started = None;
+    let token = &mut started;
+    let response = workspace_command(
+        sender,
+        id,
+        params,
+        generator_tx,
+        cancel,
+        move |uris, tx, cancel| {
+            let (progress_token, progress) =
+                begin_progress(sender, client_token, title, uris.len(), Some(&cancel))?;
+            *token = progress_token;
+            build_request(uris, tx, cancel, progress)
+        },
+    )?;
+
+    if let Some(token) = started {
+        let message = match &response.error {
+            Some(error) if error.code == ErrorCode::RequestCanceled as i32 => "Cancelled",
+            Some(_) => "Failed",
+            None => "Done",
+        };
+        end_progress(sender, &token, message.to_string())?;
+    }
+    Ok(response)
+}
+
+/// Sends the `begin` notification for `files` files and returns the token
+/// with a callback that reports on it. With `cancel`, the progress is
+/// cancellable and cancelling it sets `cancel`, until [`end_progress`].
+/// Without a client token or one the client creates, there is no progress
+/// and the callback does nothing.
+fn begin_progress(
+    sender: &Sender<Message>,
+    token: Option<ProgressToken>,
+    title: &str,
+    files: usize,
+    cancel: Option<&CancelToken>,
+) -> Result<(Option<ProgressToken>, ProgressCallback)> {
+    let token = match token {
+        Some(token) => token,
+        None => match create_progress_token(sender)? {
+            Some(token) => token,
+            None => return Ok((None, Box::new(|_, _| {}))),
+        },
+    };
+    if let Some(cancel) = cancel {
+        cancellation::track_progress(token.clone(), cancel.clone());
+    }
+    let cancellable = Some(cancel.is_some());
+    sender.send(progress(
+        &token,
+        WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: title.to_string(),
+            cancellable,
+            message: Some(format!("{} files", files)),
+            percentage: Some(0),
+        }),
+    ))?;
+
+    let report_sender = sender.clone();
+    let report_token = token.clone();
+    let report: ProgressCallback = Box::new(move |message, percentage| {
+        let _ = report_sender.send(progress(
+            &report_token,
+            WorkDoneProgress::Report(WorkDoneProgressReport {
+                cancellable,
+                message: Some(message.to_string()),
+                percentage: Some(percentage),
+            }),
+        ));
+    });
+    Ok((Some(token), report))
+}
+
+fn end_progress(sender: &Sender<Message>, token: &ProgressToken, message: String) -> Result<()> {
+    cancellation::finish_progress(token);
+    sender.send(progress(
+        token,
+        WorkDoneProgress::End(WorkDoneProgressEnd {
+            message: Some(message),
+        }),
+    ))?;
+    Ok(())
+}
+
+/// Queues a cache warm-up and answers right away; the worker reports its
+/// steps as `$/progress` on the client's work-done token, or on one the
+/// server creates.
+fn warm_cache(
+    sender: &Sender<Message>,
+    id: lsp_server::RequestId,
+    params: &ExecuteCommandParams,
+    uris: Vec<Url>,
+    generator_tx: &mpsc::Sender<GenerationRequest>,
+) -> Result<Response> {
+    let files = uris.len();
+    // Answered before it runs, so there is no request left to cancel; only
+    // its progress is.
+    let cancel = CancelToken::default();
+    let (token, report) = begin_progress(
+        sender,
+        params.work_done_progress_params.work_done_token.clone(),
+        "Warming Traverse cache",
+        files,
+        Some(&cancel),
+    )?;
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let request = GenerationRequest::WarmCache {
+        uris,
+        cancel,
+        progress: Some(report),
+        tx,
+    };
+    if generator_tx.send(request).is_err() {
+        return Ok(shutting_down(id));
+    }
+
+    let sender = sender.clone();
+    thread::spawn(move || {
+        let message = match TOKIO_RUNTIME.block_on(rx) {
+            Ok(Ok(_)) => format!("Cached call graphs for {} files", files),
+            Ok(Err(e)) if e.is::<RequestCancelled>() => "Cancelled".to_string(),
+            Ok(Err(e)) => {
+                error!("Cache warm-up failed: {}", e);
+                format!("Warm-up failed: {e}")
+            }
+            Err(_) => ShuttingDown.to_string(),
+        };
+        if let Some(token) = token {
+            let _ = end_progress(&sender, &token, message);
+        }
+    });
+
+    Ok(Response::new_ok(
+        id,
+        CommandResult {
+            success: true,
+            job: None,
+            files: Some(files),
+            result: AnalysisResult::new(
+                commands::WARM_CACHE,
+                serde_json::json!({ "started": true, "files": files }),
+            ),
+        },
+    ))
+}
+
+/// Whether the client shows progress the server creates, from its
+/// `window.workDoneProgress` capability. Like the progress cancel tokens,
+/// it is read deep in command handling, so it is kept process-wide.
+static SERVER_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// `window/workDoneProgress/create` requests waiting for the client's
+/// answer, with whether it accepted them.
+static PENDING_PROGRESS: Lazy<DashMap<RequestId, mpsc::Sender<bool>>> = Lazy::new(DashMap::new);
+
+/// How long a command waits for the client to create its progress before
+/// running without.
+const PROGRESS_CREATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sets whether commands create their own progress when the client passes
+/// no work-done token; off until the client declares support.
+pub fn set_server_progress(supported: bool) {
+    SERVER_PROGRESS.store(supported, Ordering::Relaxed);
+}
+
+/// Whether `response` answers a `window/workDoneProgress/create` request a
+/// command is waiting on.
+pub fn is_progress_response(response: &Response) -> bool {
+    PENDING_PROGRESS.contains_key(&response.id)
+}
+
+/// Passes the client's answer to a `window/workDoneProgress/create`
+/// request to the command waiting on it.
+pub fn progress_created(response: Response) {
+    if let Some(error) = &response.error {
+        warn!("Client did not create progress: {}", error.message);
+    }
+    if let Some((_, created)) = PENDING_PROGRESS.remove(&response.id) {
+        let _ = created.send(response.error.is_none());
+    }
+}
+
+/// Asks the client to create a progress token and waits for its answer;
+/// `None` if the client does not support server-created progress, refuses
+/// it or does not answer in time.
+fn create_progress_token(sender: &Sender<Message>) -> Result<Option<ProgressToken>> {
+    static NEXT_TOKEN: AtomicUsize = AtomicUsize::new(1);
+    if !SERVER_PROGRESS.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    let name = format!(
+        "traverse/progress/{}",
+        NEXT_TOKEN.fetch_add(1, Ordering::Relaxed)
+    );
+    let id = RequestId::from(name.clone());
+    let request = Request::new(
+        id.clone(),
+        WorkDoneProgressCreate::METHOD.to_string(),
+        WorkDoneProgressCreateParams {
+            token: ProgressToken::String(name.clone()),
+        },
+    );
+    let (created_tx, created) = mpsc::channel();
+    PENDING_PROGRESS.insert(id.clone(), created_tx);
+    if let Err(e) = sender.send(Message::Request(request)) {
+        PENDING_PROGRESS.remove(&id);
+        return Err(e.into());
     }
+    let accepted = created
+        .recv_timeout(PROGRESS_CREATE_TIMEOUT)
+        .unwrap_or_else(|_| {
+            warn!(
+                "Client did not answer {} in time",
+                WorkDoneProgressCreate::METHOD
+            );
+            false
+        });
+    PENDING_PROGRESS.remove(&id);
+    Ok(accepted.then_some(ProgressToken::String(name)))
 }
 
+fn progress(token: &ProgressToken, value: WorkDoneProgress) -> Message {
+    Message::Notification(Notification::new(
+        Progress::METHOD.to_string(),
+        ProgressParams {
+            token: token.clone(),
+            value: ProgressParamsValue::WorkDone(value),
+        },
+    ))
+}
+
+/// The response to a command; responses name the number of `files` it ran
+/// on, and successful ones the `job` that ran it. Payloads that are not
+/// JSON are text in `text_format`.
 fn generation_result(
-    conn: &Connection,
+    sender: &Sender<Message>,
     id: lsp_server::RequestId,
+    command: &str,
+    job: Option<JobId>,
+    files: Option<usize>,
+    text_format: ArtifactFormat,
+    result: Result<Result<String>>,
+) -> Result<Response> {
+    let response = generation_response(sender, id, command, job, files, text_format, result)?;
+    Ok(with_error_data(response, "files", files.into()))
+}
+
+fn generation_response(
+    sender: &Sender<Message>,
+    id: lsp_server::RequestId,
+    command: &str,
+    job: Option<JobId>,
+    files: Option<usize>,
+    text_format: ArtifactFormat,
     result: Result<Result<String>>,
 ) -> Result<Response> {
     match result {
-        Ok(Ok(diagram_data)) => {
-            if let Ok(json_data) = serde_json::from_str::<serde_json::Value>(&diagram_data) {
-                Ok(Response::new_ok(
-                    id,
-                    serde_json::json!({
-                        "success": true,
-                        "data": json_data
-                    }),
-                ))
-            } else {
-                Ok(Response::new_ok(
-                    id,
-                    serde_json::json!({
-                        "success": true,
-                        "diagram": diagram_data
-                    }),
-                ))
-            }
+        Ok(Err(e)) | Err(e) if e.is::<ShuttingDown>() => Ok(shutting_down(id)),
+        Ok(Err(e)) if e.is::<RequestCancelled>() => {
+            info!("Request {} was cancelled", id);
+            Ok(Response::new_err(
+                id,
+                ErrorCode::RequestCanceled as i32,
+                e.to_string(),
+            ))
+        }
+        Ok(Ok(payload)) => {
+            // Payloads that are not JSON are passed on as text.
+            let result = match serde_json::from_str(&payload) {
+                Ok(data) => AnalysisResult::new(command, data),
+                Err(_) => AnalysisResult::text(command, payload, text_format),
+            };
+            Ok(Response::new_ok(
+                id,
+                CommandResult {
+                    success: true,
+                    job,
+                    files,
+                    result,
+                },
+            ))
+        }
+        Ok(Err(e)) if e.is::<GenerationTimedOut>() => {
+            error!("{}", e);
+            show_message(
+                sender,
+                MessageType::ERROR,
+                format!("Failed to generate: {e} (request {id})"),
+            )?;
+            Ok(timed_out(id, &e))
         }
         Ok(Err(e)) => {
             error!("Failed to generate diagram: {}", e);
-            show_message(conn, MessageType::ERROR, format!("Failed to generate: {e}"))?;
+            show_message(
+                sender,
+                MessageType::ERROR,
+                format!("Failed to generate: {e} (request {id})"),
+            )?;
             Ok(Response::new_err(id, -32603, e.to_string()))
         }
         Err(e) => {
@@ -163,6 +1477,48 @@ fn generation_result(
     }
 }
 
+/// A successful workspace command: the job that ran it and the number of
+/// `files` it ran on, around the command's [`AnalysisResult`].
+#[derive(serde::Serialize)]
+struct CommandResult {
+    success: bool,
+    job: Option<JobId>,
+    files: Option<usize>,
+    #[serde(flatten)]
+    result: AnalysisResult,
+}
+
+/// Distinguishes requests dropped during shutdown from generation failures.
+fn shutting_down(id: lsp_server::RequestId) -> Response {
+    Response {
+        id,
+        result: None,
+        error: Some(ResponseError {
+            code: ErrorCode::ServerCancelled as i32,
+            message: ShuttingDown.to_string(),
+            data: Some(serde_json::json!({ "reason": "shutting_down" })),
+        }),
+    }
+}
+
+/// Reports a request the watchdog gave up on, with the phase it was stuck in.
+fn timed_out(id: lsp_server::RequestId, error: &anyhow::Error) -> Response {
+    let mut data = error
+        .downcast_ref::<GenerationTimedOut>()
+        .and_then(|timeout| serde_json::to_value(timeout).ok())
+        .unwrap_or_else(|| serde_json::json!({}));
+    data["reason"] = "timeout".into();
+    Response {
+        id,
+        result: None,
+        error: Some(ResponseError {
+            code: ErrorCode::RequestFailed as i32,
+            message: error.to_string(),
+            data: Some(data),
+        }),
+    }
+}
+
 fn extract_args<T: DeserializeOwned>(
     params: &ExecuteCommandParams,
     id: &lsp_server::RequestId,
@@ -179,44 +1535,190 @@ fn extract_args<T: DeserializeOwned>(
         .map_err(|_| Response::new_err(id.clone(), -32602, "Invalid parameters".into()))
 }
 
-fn find_solidity_files(workspace_folder: &str) -> Result<Vec<Url>> {
-    use walkdir::WalkDir;
+/// Mermaid options from the command arguments; the top-level `no_chunk` flag
+/// is kept for compatibility alongside the `mermaid` block.
+fn mermaid_config(params: &ExecuteCommandParams, id: &lsp_server::RequestId) -> MermaidConfig {
+    extract_args::<WorkspaceArgs>(params, id)
+        .map(|args| MermaidConfig {
+            no_chunk: args.no_chunk || args.mermaid.no_chunk,
+            deterministic: args.deterministic,
+            ..args.mermaid
+        })
+        .unwrap_or_default()
+}
+
+fn dot_config(params: &ExecuteCommandParams, id: &lsp_server::RequestId) -> DotConfig {
+    extract_args::<WorkspaceArgs>(params, id)
+        .map(|args| args.dot)
+        .unwrap_or_default()
+}
 
-    let mut sol_files = Vec::new();
+fn workspace_folder(params: &ExecuteCommandParams, id: &lsp_server::RequestId) -> PathBuf {
+    extract_args::<WorkspaceArgs>(params, id)
+        .map(|args| PathBuf::from(args.workspace_folder))
+        .unwrap_or_default()
+}
 
-    for entry in WalkDir::new(workspace_folder)
-        .follow_links(true)
-        .into_iter()
-        .filter_entry(|e| {
-            !e.path().components().any(|c| {
-                matches!(
-                    c.as_os_str().to_str(),
-                    Some("node_modules" | "build" | "cache" | ".git")
-                )
-            })
-        })
-    {
-        let entry = entry?;
-        if entry.path().extension().and_then(|s| s.to_str()) == Some("sol") {
-            let uri =
-                Url::from_file_path(entry.path()).map_err(|_| anyhow::anyhow!("Invalid path"))?;
-            sol_files.push(uri);
-        }
-    }
+fn contract_name(params: &ExecuteCommandParams, id: &lsp_server::RequestId) -> Option<String> {
+    extract_args::<WorkspaceArgs>(params, id)
+        .ok()
+        .and_then(|args| args.contract)
+}
+
+fn graph_filter(params: &ExecuteCommandParams, id: &lsp_server::RequestId) -> GraphFilter {
+    extract_args::<WorkspaceArgs>(params, id)
+        .map(|args| args.filter)
+        .unwrap_or_default()
+}
 
-    Ok(sol_files)
+/// The `coverage_file` argument, relative to the workspace folder unless
+/// absolute.
+fn coverage_file(params: &ExecuteCommandParams, id: &lsp_server::RequestId) -> Option<PathBuf> {
+    let args = extract_args::<WorkspaceArgs>(params, id).ok()?;
+    let file = args.coverage_file?;
+    Some(Path::new(&args.workspace_folder).join(file))
 }
 
-fn show_message(conn: &Connection, typ: MessageType, message: String) -> Result<()> {
+fn show_message(sender: &Sender<Message>, typ: MessageType, message: String) -> Result<()> {
     let params = ShowMessageParams { typ, message };
     let notification = Notification::new("window/showMessage".to_string(), params);
-    conn.sender.send(Message::Notification(notification))?;
+    sender.send(Message::Notification(notification))?;
     Ok(())
 }
 
 #[derive(serde::Deserialize)]
 struct WorkspaceArgs {
     workspace_folder: String,
+    /// Files, relative to the workspace folder, whose import closure is
+    /// analyzed instead of the whole workspace.
+    #[serde(default, rename = "files")]
+    entry_files: Vec<PathBuf>,
     #[serde(default)]
     no_chunk: bool,
+    #[serde(default)]
+    deterministic: bool,
+    #[serde(default)]
+    format: ExportFormat,
+    #[serde(default)]
+    mermaid: MermaidConfig,
+    #[serde(default)]
+    dot: DotConfig,
+    #[serde(flatten)]
+    filter: GraphFilter,
+    /// Narrows the files analyzed by glob.
+    #[serde(flatten)]
+    files: FileFilter,
+    #[serde(default)]
+    min_confidence: Confidence,
+    /// Restricts analysis to files changed since this git ref and the files
+    /// they import.
+    #[serde(default, alias = "changedSince")]
+    changed_since: Option<String>,
+    /// Contracts listed in a findings summary.
+    #[serde(default)]
+    top_contracts: Option<usize>,
+    /// lcov file overlaid on the call graph, e.g. Foundry's `lcov.info`.
+    #[serde(default, alias = "coverageFile")]
+    coverage_file: Option<PathBuf>,
+    /// Event-to-consumer mapping, relative to the workspace folder.
+    #[serde(default, alias = "consumersFile")]
+    consumers_file: Option<PathBuf>,
+    /// Exports the graph with identifiers replaced by pseudonyms.
+    #[serde(default)]
+    anonymize: bool,
+    /// Limits diagrams to this contract and the functions it calls.
+    #[serde(default)]
+    contract: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct PrCommentArgs {
+    base_ref: String,
+    #[serde(default, alias = "changedSince")]
+    changed_since: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ImportTraceArgs {
+    /// Output of `forge test -vvvv` or `cast run`.
+    #[serde(default)]
+    trace: Option<String>,
+    /// File holding the trace, relative to the workspace folder.
+    #[serde(default, alias = "traceFile")]
+    trace_file: Option<PathBuf>,
+}
+
+#[derive(serde::Deserialize)]
+struct PrivilegePathsArgs {
+    /// Access-control modifiers; defaults to common ones like `onlyOwner`.
+    #[serde(default)]
+    modifiers: Option<Vec<String>>,
+    /// Functions privileged by name, as `function` or `Contract.function`.
+    #[serde(default)]
+    functions: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct FindClonesArgs {
+    /// Smallest body, in syntax nodes, worth comparing.
+    #[serde(default, alias = "minNodes")]
+    min_nodes: Option<usize>,
+}
+
+#[derive(serde::Deserialize)]
+struct StorageLayoutArgs {
+    workspace_folder: String,
+    /// Report template, relative to the workspace folder.
+    #[serde(default)]
+    template: Option<PathBuf>,
+}
+
+#[derive(serde::Deserialize)]
+struct StorageHeatmapArgs {
+    /// Limits the heatmap to one contract.
+    #[serde(default)]
+    contract: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ExternalDependenciesArgs {
+    /// Limits the report to one contract.
+    #[serde(default)]
+    contract: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct FunctionDiagramArgs {
+    contract: String,
+    /// Function name; every overload is included.
+    function: String,
+}
+
+#[derive(serde::Deserialize)]
+struct PositionArgs {
+    /// `textDocument` and `position`, as in `textDocument/hover`.
+    #[serde(flatten)]
+    position: TextDocumentPositionParams,
+}
+
+#[derive(serde::Deserialize)]
+struct RegenerateChunkArgs {
+    /// Number of the chunk, as in its `chunk_NNN.mmd` file name.
+    chunk: usize,
+    /// With `contract`, scopes the diagram to this function as
+    /// `generateFunctionDiagram` does.
+    #[serde(default)]
+    function: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct RenderDiagramArgs {
+    /// Name of a registered renderer, e.g. `plantuml`.
+    renderer: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CompareStorageArgs {
+    left_contract: String,
+    right_contract: String,
 }
@@ -0,0 +1,22 @@
+use crate::symbol_index::SymbolIndex;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+
+pub fn find_symbol(req: Request, conn: &Connection, index: &SymbolIndex) -> Result<()> {
+    let (id, params) = req.extract::<FindSymbolParams>(crate::commands::FIND_SYMBOL_REQUEST)?;
+    let lookup = index.find(&params.name, !params.allow_stale);
+
+    let response = Response::new_ok(id, lookup);
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+#[derive(serde::Deserialize)]
+struct FindSymbolParams {
+    /// `name` or `Container.name`.
+    name: String,
+    /// Answer from the current index without re-parsing edited documents;
+    /// out-of-date documents are listed under `stale`.
+    #[serde(default)]
+    allow_stale: bool,
+}
@@ -0,0 +1,221 @@
+//! `textDocument/hover`: the callers, callees and storage access of a
+//! function, and the functions accessing a state variable.
+//!
+//! Functions also get their external dependency badges, which come from the
+//! open file alone.
+//!
+//! Hovers must answer at once, so they read the latest call graph a command
+//! already built from the file instead of building one; until there is one,
+//! the hover says how to build it.
+
+use crate::analysis::dependencies::{self, Badge, Dependency, FunctionDependencies};
+use crate::documents::DocumentStore;
+use crate::graph_cache::GraphCache;
+use crate::symbol_index::{self, Declaration, SymbolKind};
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{HoverRequest, Request as _};
+use lsp_types::{Hover, HoverContents, HoverParams, MarkupContent, MarkupKind};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use traverse_graph::cg::{CallGraph, EdgeType, Node, NodeType};
+
+/// Names listed per line before the rest are counted.
+const MAX_NAMES: usize = 10;
+
+pub fn hover(
+    req: Request,
+    conn: &Connection,
+    documents: &DocumentStore,
+    graphs: &GraphCache,
+) -> Result<()> {
+    let (id, params) = req.extract::<HoverParams>(HoverRequest::METHOD)?;
+    let position = params.text_document_position_params;
+    let uri = position.text_document.uri;
+
+    let hover = match (documents.read(&uri), uri.to_file_path()) {
+        (Ok(text), Ok(path)) => {
+            symbol_index::declaration_at(&text, position.position).map(|declaration| {
+                hover_for(
+                    &declaration,
+                    graphs.latest(&path).as_ref(),
+                    &dependencies::for_source(&text),
+                )
+            })
+        }
+        _ => None,
+    };
+    let response = Response::new_ok(id, hover);
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// The hover of `declaration`, from `graph` when one has been built, with
+/// its badges from the file's `dependencies`.
+pub fn hover_for(
+    declaration: &Declaration,
+    graph: Option<&CallGraph>,
+    dependencies: &[FunctionDependencies],
+) -> Hover {
+    let mut body = match graph {
+        None => "Call graph not built yet; run any Traverse command, e.g. \
+                 `traverse.warmCache`, to see callers and storage access."
+            .to_string(),
+        Some(graph) => {
+            let ids: HashSet<usize> = graph
+                .nodes
+                .iter()
+                .filter(|node| declares(node, declaration))
+                .map(|node| node.id)
+                .collect();
+            if ids.is_empty() {
+                "Not in the call graph.".to_string()
+            } else if declaration.kind == SymbolKind::StateVariable {
+                variable_access(graph, &ids)
+            } else {
+                function_relations(graph, &ids)
+            }
+        }
+    };
+    if let Some(badges) = depends_on(declaration, dependencies) {
+        body.push_str(&format!("\n\n{}", badges));
+    }
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: format!(
+                "**{}.{}**\n\n{}",
+                declaration.container, declaration.name, body
+            ),
+        }),
+        range: Some(declaration.range),
+    }
+}
+
+/// Whether `node` is `declaration`, or an overload of it.
+pub(crate) fn declares(node: &Node, declaration: &Declaration) -> bool {
+    if node.contract_name.as_deref() != Some(declaration.container.as_str()) {
+        return false;
+    }
+    match node.node_type {
+        NodeType::StorageVariable => {
+            declaration.kind == SymbolKind::StateVariable && node.name == declaration.name
+        }
+        NodeType::Function | NodeType::Modifier => {
+            declaration.kind != SymbolKind::StateVariable && node.name == declaration.name
+        }
+        NodeType::Constructor => declaration.name == "constructor",
+        _ => false,
+    }
+}
+
+/// The `Depends on` line of a function or modifier, naming the expression
+/// behind each badge; `None` when it has none. Overloads share one line.
+fn depends_on(declaration: &Declaration, dependencies: &[FunctionDependencies]) -> Option<String> {
+    if declaration.kind == SymbolKind::StateVariable {
+        return None;
+    }
+    let mut badges: BTreeMap<Dependency, &Badge> = BTreeMap::new();
+    for function in dependencies
+        .iter()
+        .filter(|f| f.contract == declaration.container && f.function == declaration.name)
+    {
+        for badge in &function.badges {
+            badges.entry(badge.dependency).or_insert(badge);
+        }
+    }
+    if badges.is_empty() {
+        return None;
+    }
+    let badges: Vec<String> = badges.values().map(|b| b.describe()).collect();
+    Some(format!("**Depends on:** {}", badges.join(", ")))
+}
+
+/// Direct callers and callees of the nodes in `ids`, and the state
+/// variables they read and write. A call to a public variable's getter
+/// counts as a read.
+fn function_relations(graph: &CallGraph, ids: &HashSet<usize>) -> String {
+    let mut callers = BTreeSet::new();
+    let mut callees = BTreeSet::new();
+    let mut reads = BTreeSet::new();
+    let mut writes = BTreeSet::new();
+    for (source, edge_type, target) in edges(graph) {
+        if ids.contains(&target.id) && edge_type == EdgeType::Call {
+            callers.insert(label(source));
+        }
+        if !ids.contains(&source.id) {
+            continue;
+        }
+        let is_variable = target.node_type == NodeType::StorageVariable;
+        match edge_type {
+            EdgeType::StorageRead => reads.insert(label(target)),
+            EdgeType::StorageWrite => writes.insert(label(target)),
+            EdgeType::Call if is_variable => reads.insert(label(target)),
+            EdgeType::Call => callees.insert(label(target)),
+            _ => false,
+        };
+    }
+    [
+        ("Callers", callers),
+        ("Callees", callees),
+        ("Reads", reads),
+        ("Writes", writes),
+    ]
+    .into_iter()
+    .map(|(title, names)| line(title, names))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// The functions reading and writing the variables in `ids`.
+fn variable_access(graph: &CallGraph, ids: &HashSet<usize>) -> String {
+    let mut readers = BTreeSet::new();
+    let mut writers = BTreeSet::new();
+    for (source, edge_type, target) in edges(graph) {
+        if !ids.contains(&target.id) {
+            continue;
+        }
+        match edge_type {
+            EdgeType::StorageRead | EdgeType::Call => readers.insert(label(source)),
+            EdgeType::StorageWrite => writers.insert(label(source)),
+            _ => false,
+        };
+    }
+    [line("Read by", readers), line("Written by", writers)].join("\n")
+}
+
+fn edges(graph: &CallGraph) -> impl Iterator<Item = (&Node, EdgeType, &Node)> {
+    graph.edges.iter().filter_map(|edge| {
+        Some((
+            graph.nodes.get(edge.source_node_id)?,
+            edge.edge_type.clone(),
+            graph.nodes.get(edge.target_node_id)?,
+        ))
+    })
+}
+
+fn label(node: &Node) -> String {
+    let name = match node.node_type {
+        NodeType::Constructor => "constructor",
+        _ => node.name.as_str(),
+    };
+    match &node.contract_name {
+        Some(contract) => format!("{}.{}", contract, name),
+        None => name.to_string(),
+    }
+}
+
+/// A Markdown list item naming at most [`MAX_NAMES`] of `names`.
+fn line(title: &str, names: BTreeSet<String>) -> String {
+    if names.is_empty() {
+        return format!("- **{}:** none", title);
+    }
+    let mut listed: Vec<String> = names
+        .iter()
+        .take(MAX_NAMES)
+        .map(|name| format!("`{}`", name))
+        .collect();
+    if names.len() > MAX_NAMES {
+        listed.push(format!("and {} more", names.len() - MAX_NAMES));
+    }
+    format!("- **{}:** {}", title, listed.join(", "))
+}
@@ -0,0 +1,156 @@
+//! `textDocument/inlayHint`: how much state each function touches and how
+//! many functions call it, at the end of its signature.
+//!
+//! Like hovers, hints read the latest call graph a command built from the
+//! file. Public and external functions count the variables read and written
+//! by everything they reach, as the storage access report does; other
+//! functions count only their own.
+
+use super::definition::cached_graph;
+use crate::analysis::unresolved_calls::PLACEHOLDER_CONTRACT;
+use crate::documents::DocumentStore;
+use crate::graph_cache::GraphCache;
+use crate::source_map::SourceMap;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{InlayHintRequest, Request as _};
+use lsp_types::{
+    InlayHint, InlayHintLabel, InlayHintParams, InlayHintTooltip, MarkupContent, MarkupKind, Range,
+};
+use std::collections::BTreeSet;
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, EdgeType, Node, NodeType};
+use traverse_graph::storage_access::analyze_storage_access;
+
+pub fn inlay_hint(
+    req: Request,
+    conn: &Connection,
+    documents: &DocumentStore,
+    graphs: &GraphCache,
+    enabled: bool,
+) -> Result<()> {
+    let (id, params) = req.extract::<InlayHintParams>(InlayHintRequest::METHOD)?;
+
+    let hints = params
+        .text_document
+        .uri
+        .to_file_path()
+        .ok()
+        .filter(|_| enabled)
+        .and_then(|path| {
+            let (graph, sources) = cached_graph(documents, graphs, &path)?;
+            Some(hints(
+                &graph,
+                &SourceMap::new(&sources),
+                &path,
+                params.range,
+            ))
+        })
+        .unwrap_or_default();
+    let response = Response::new_ok(id, hints);
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// Hints for the functions, modifiers and constructors of `path` whose
+/// signature ends within `range`, like `reads: 2, writes: 1, callers: 5`.
+/// The tooltip names them.
+pub fn hints(graph: &CallGraph, map: &SourceMap, path: &Path, range: Range) -> Vec<InlayHint> {
+    let Some(index) = map.index_of(path) else {
+        return Vec::new();
+    };
+    let reachable = analyze_storage_access(graph);
+    let label = |id: &usize| {
+        graph.nodes.get(*id).map_or_else(String::new, |n| {
+            format!(
+                "{}.{}",
+                n.contract_name.as_deref().unwrap_or("Global"),
+                n.name
+            )
+        })
+    };
+
+    let mut hints = Vec::new();
+    for node in graph.nodes.iter().filter(|node| is_callable(node)) {
+        if map.file_index(node.span.0) != Some(index) {
+            continue;
+        }
+        let Some(position) = signature_end(map, node)
+            .and_then(|end| map.locate((end, end)))
+            .map(|location| location.range.start)
+            .filter(|position| range.start <= *position && *position <= range.end)
+        else {
+            continue;
+        };
+
+        let (reads, writes): (BTreeSet<String>, BTreeSet<String>) = match reachable.get(&node.id) {
+            Some(summary) => (
+                summary.reads.iter().map(label).collect(),
+                summary.writes.iter().map(label).collect(),
+            ),
+            None => {
+                let own = |edge_type| {
+                    graph
+                        .edges
+                        .iter()
+                        .filter(|e| e.source_node_id == node.id && e.edge_type == edge_type)
+                        .map(|e| label(&e.target_node_id))
+                        .collect()
+                };
+                (own(EdgeType::StorageRead), own(EdgeType::StorageWrite))
+            }
+        };
+        let callers: BTreeSet<String> = graph
+            .edges
+            .iter()
+            .filter(|e| e.target_node_id == node.id && e.edge_type == EdgeType::Call)
+            .map(|e| label(&e.source_node_id))
+            .collect();
+
+        let names = |names: &BTreeSet<String>| match names.is_empty() {
+            true => "none".to_string(),
+            false => names.iter().cloned().collect::<Vec<_>>().join(", "),
+        };
+        hints.push(InlayHint {
+            position,
+            label: InlayHintLabel::String(format!(
+                "reads: {}, writes: {}, callers: {}",
+                reads.len(),
+                writes.len(),
+                callers.len()
+            )),
+            kind: None,
+            text_edits: None,
+            tooltip: Some(InlayHintTooltip::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!(
+                    "**Reads:** {}\n\n**Writes:** {}\n\n**Callers:** {}",
+                    names(&reads),
+                    names(&writes),
+                    names(&callers)
+                ),
+            })),
+            padding_left: Some(true),
+            padding_right: Some(true),
+            data: None,
+        });
+    }
+    hints.sort_by_key(|hint| hint.position);
+    hints
+}
+
+fn is_callable(node: &Node) -> bool {
+    node.contract_name.as_deref() != Some(PLACEHOLDER_CONTRACT)
+        && matches!(
+            node.node_type,
+            NodeType::Function | NodeType::Modifier | NodeType::Constructor
+        )
+}
+
+/// Span offset just past the signature of `node`'s declaration: before the
+/// whitespace leading to its body. `None` for declarations without a body.
+fn signature_end(map: &SourceMap, node: &Node) -> Option<usize> {
+    let declaration = map.text(node.span)?;
+    let body = declaration.find('{')?;
+    Some(node.span.0 + declaration[..body].trim_end().len())
+}
@@ -0,0 +1,17 @@
+use crate::analysis::rule_settings;
+use crate::config::Config;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+
+pub fn list_rules(req: Request, conn: &Connection, config: &Config) -> Result<()> {
+    let (id, _) = req.extract::<serde_json::Value>(crate::commands::LIST_RULES_REQUEST)?;
+
+    let response = Response::new_ok(
+        id,
+        serde_json::json!({
+            "rules": rule_settings(config),
+        }),
+    );
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
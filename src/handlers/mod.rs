@@ -1,4 +1,42 @@
+pub mod clean_outputs;
+pub mod clear_cache;
+pub mod code_action;
+pub mod code_lens;
 mod common;
+pub mod config_schema;
+pub mod definition;
+pub mod doctor;
+pub mod document_symbol;
 pub mod execute_command;
+pub mod find_symbol;
+pub mod hover;
+pub mod inlay_hint;
+pub mod list_rules;
+pub mod query_history;
+pub mod references;
+pub mod resolve_arguments;
+pub mod semantic_tokens;
+pub mod status;
+pub mod workspace_symbol;
 
-pub use execute_command::execute_command;
+pub use clean_outputs::clean_outputs;
+pub use clear_cache::clear_cache;
+pub use code_action::code_action;
+pub use code_lens::code_lens;
+pub use common::with_request_id;
+pub use config_schema::config_schema;
+pub use definition::definition;
+pub use doctor::doctor;
+pub use document_symbol::document_symbol;
+#[allow(unused_imports)]
+pub use execute_command::{execute_command, execute_command_with_telemetry};
+pub use find_symbol::find_symbol;
+pub use hover::hover;
+pub use inlay_hint::inlay_hint;
+pub use list_rules::list_rules;
+pub use query_history::query_history;
+pub use references::references;
+pub use resolve_arguments::resolve_command_arguments;
+pub use semantic_tokens::semantic_tokens;
+pub use status::status;
+pub use workspace_symbol::workspace_symbol;
@@ -0,0 +1,23 @@
+use crate::query_history::QueryHistory;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use std::path::Path;
+
+pub fn query_history(req: Request, conn: &Connection) -> Result<()> {
+    let (id, params) = req.extract::<QueryHistoryParams>(crate::commands::QUERY_HISTORY_REQUEST)?;
+    let history = QueryHistory::load(Path::new(&params.workspace_folder));
+
+    let response = Response::new_ok(
+        id,
+        serde_json::json!({
+            "entries": history.entries,
+        }),
+    );
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+#[derive(serde::Deserialize)]
+struct QueryHistoryParams {
+    workspace_folder: String,
+}
@@ -0,0 +1,119 @@
+//! `textDocument/references`: the call sites of a function or
+//! constructor, and the read and write sites of a state variable.
+//!
+//! Works from a declaration's name or from a call to it, and reads the same
+//! cached call graph as definitions, so overloads are merged and a public
+//! variable's getter calls count as reads.
+
+use super::definition::{cached_graph, called_at, callee_end, name_span, word_offsets};
+use super::hover::declares;
+use crate::documents::DocumentStore;
+use crate::graph_cache::GraphCache;
+use crate::source_map::SourceMap;
+use crate::symbol_index;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{References, Request as _};
+use lsp_types::{Location, Position, ReferenceParams};
+use std::collections::BTreeSet;
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, EdgeType, Node, NodeType};
+
+pub fn references(
+    req: Request,
+    conn: &Connection,
+    documents: &DocumentStore,
+    graphs: &GraphCache,
+) -> Result<()> {
+    let (id, params) = req.extract::<ReferenceParams>(References::METHOD)?;
+    let position = params.text_document_position;
+
+    let locations = position
+        .text_document
+        .uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| {
+            let (graph, sources) = cached_graph(documents, graphs, &path)?;
+            Some(references_at(
+                &graph,
+                &SourceMap::new(&sources),
+                &path,
+                position.position,
+                params.context.include_declaration,
+            ))
+        });
+    let response = Response::new_ok(id, locations);
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// References to the declaration named at `position` of `path`, or to the
+/// declarations called there, in file and position order.
+pub fn references_at(
+    graph: &CallGraph,
+    map: &SourceMap,
+    path: &Path,
+    position: Position,
+    include_declaration: bool,
+) -> Vec<Location> {
+    let Some(index) = map.index_of(path) else {
+        return Vec::new();
+    };
+    let targets: Vec<&Node> = match symbol_index::declaration_at(map.content(index), position) {
+        Some(declaration) => graph
+            .nodes
+            .iter()
+            .filter(|node| declares(node, &declaration))
+            .collect(),
+        None => called_at(graph, map, map.offset(index, position)),
+    };
+    let ids: BTreeSet<usize> = targets.iter().map(|node| node.id).collect();
+
+    let mut spans = BTreeSet::new();
+    for edge in &graph.edges {
+        let Some(target) = graph.nodes.get(edge.target_node_id) else {
+            continue;
+        };
+        if !ids.contains(&target.id) {
+            continue;
+        }
+        if matches!(
+            edge.edge_type,
+            EdgeType::Call | EdgeType::StorageRead | EdgeType::StorageWrite
+        ) {
+            spans.insert(reference_span(map, edge.call_site_span, target));
+        }
+    }
+    if include_declaration {
+        spans.extend(targets.iter().map(|node| name_span(map, node)));
+    }
+    // Spans are laid out in file order, so sorting them sorts locations.
+    let mut locations: Vec<Location> = spans
+        .into_iter()
+        .filter_map(|span| map.locate(span))
+        .collect();
+    locations.dedup();
+    locations
+}
+
+/// The name of `target` at the site `span` of an edge into it, e.g.
+/// `transfer` in the call `token.transfer(to, amount)` or `total` in the
+/// write `total = 0`, or the whole site when the name is not there.
+fn reference_span(map: &SourceMap, span: (usize, usize), target: &Node) -> (usize, usize) {
+    let name = match target.node_type {
+        NodeType::Constructor => target.contract_name.as_deref().unwrap_or(&target.name),
+        _ => target.name.as_str(),
+    };
+    let found = match target.node_type {
+        NodeType::StorageVariable => map
+            .text(span)
+            .and_then(|site| word_offsets(site, name).next()),
+        // The last name before the arguments, as the callee's receiver
+        // may share it.
+        _ => map
+            .text((span.0, callee_end(map, span.0, span.1)))
+            .and_then(|callee| word_offsets(callee, name).last()),
+    };
+    found.map_or(span, |at| (span.0 + at, span.0 + at + name.len()))
+}
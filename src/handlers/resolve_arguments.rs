@@ -0,0 +1,216 @@
+//! Valid values for the arguments of a workspace command, so clients can
+//! build guided command palettes.
+//!
+//! The request takes the same `command` and `arguments` as
+//! `workspace/executeCommand`, with the argument object partly filled in,
+//! and lists the arguments still missing with the values they accept.
+
+use crate::analysis::Confidence;
+use crate::commands;
+use crate::exporters::ExportFormat;
+use crate::git;
+use crate::renderers;
+use crate::symbol_index::{SymbolIndex, SymbolKind};
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::ExecuteCommandParams;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+/// Flags of [`GraphFilter`](crate::config::GraphFilter), accepted by the
+/// diagram and export commands.
+const GRAPH_FILTERS: [&str; 3] = [
+    "only_external_entrypoints",
+    "hide_view_functions",
+    "hide_getters",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ArgumentKind {
+    /// A contract declared in the workspace.
+    Contract,
+    /// A branch or tag.
+    GitRef,
+    /// One of a fixed set of values.
+    Choice,
+    Boolean,
+}
+
+#[derive(Debug, Serialize)]
+struct ArgumentChoices {
+    name: &'static str,
+    kind: ArgumentKind,
+    required: bool,
+    values: Vec<Value>,
+}
+
+pub fn resolve_command_arguments(
+    req: Request,
+    conn: &Connection,
+    index: &SymbolIndex,
+) -> Result<()> {
+    let (id, params) =
+        req.extract::<ExecuteCommandParams>(commands::RESOLVE_COMMAND_ARGUMENTS_REQUEST)?;
+    if !commands::WORKSPACE_COMMANDS.contains(&params.command.as_str()) {
+        let response =
+            Response::new_err(id, -32602, format!("Unknown command: {}", params.command));
+        conn.sender.send(Message::Response(response))?;
+        return Ok(());
+    }
+
+    let filled = match params.arguments.first() {
+        Some(Value::Object(args)) => args.clone(),
+        _ => Map::new(),
+    };
+    let folders = folders(&filled);
+    let arguments: Vec<ArgumentChoices> = arguments(&params.command)
+        .into_iter()
+        .filter(|(name, ..)| !filled.contains_key(*name))
+        .map(|(name, kind, required)| ArgumentChoices {
+            name,
+            kind,
+            required,
+            values: values(name, kind, index, &folders),
+        })
+        .collect();
+
+    let response = Response::new_ok(
+        id,
+        serde_json::json!({
+            "command": params.command,
+            "arguments": arguments,
+        }),
+    );
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// Arguments of `command` that take a value from a known set, with whether
+/// the command requires them. `workspace_folder` is left out: the server
+/// fills it in from the open folders.
+fn arguments(command: &str) -> Vec<(&'static str, ArgumentKind, bool)> {
+    let mut arguments = Vec::new();
+    match command {
+        commands::GENERATE_CALL_GRAPH_WORKSPACE => {
+            arguments.push(("contract", ArgumentKind::Contract, false));
+        }
+        commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE | commands::GENERATE_ALL_WORKSPACE => {
+            arguments.push(("contract", ArgumentKind::Contract, false));
+            arguments.push(("no_chunk", ArgumentKind::Boolean, false));
+            arguments.push(("deterministic", ArgumentKind::Boolean, false));
+        }
+        commands::EXPORT_GRAPH_WORKSPACE => {
+            arguments.push(("format", ArgumentKind::Choice, false));
+            arguments.push(("anonymize", ArgumentKind::Boolean, false));
+        }
+        commands::GENERATE_AUDIT_REPORT_WORKSPACE | commands::SUMMARIZE_FINDINGS => {
+            arguments.push(("min_confidence", ArgumentKind::Choice, false));
+        }
+        commands::STORAGE_HEATMAP_WORKSPACE | commands::EXTERNAL_DEPENDENCIES_WORKSPACE => {
+            arguments.push(("contract", ArgumentKind::Contract, false));
+        }
+        commands::GENERATE_FUNCTION_DIAGRAM => {
+            arguments.push(("contract", ArgumentKind::Contract, true));
+            arguments.push(("no_chunk", ArgumentKind::Boolean, false));
+            arguments.push(("deterministic", ArgumentKind::Boolean, false));
+        }
+        commands::GENERATE_FUNCTION_DIAGRAM_AT_POSITION => {
+            arguments.push(("no_chunk", ArgumentKind::Boolean, false));
+            arguments.push(("deterministic", ArgumentKind::Boolean, false));
+        }
+        commands::REGENERATE_CHUNK => {
+            arguments.push(("contract", ArgumentKind::Contract, false));
+            arguments.push(("deterministic", ArgumentKind::Boolean, false));
+        }
+        commands::RENDER_DIAGRAM => {
+            arguments.push(("renderer", ArgumentKind::Choice, true));
+            arguments.push(("contract", ArgumentKind::Contract, false));
+        }
+        commands::COMPARE_STORAGE => {
+            arguments.push(("left_contract", ArgumentKind::Contract, true));
+            arguments.push(("right_contract", ArgumentKind::Contract, true));
+        }
+        commands::GENERATE_PR_COMMENT => {
+            arguments.push(("base_ref", ArgumentKind::GitRef, true));
+        }
+        _ => {}
+    }
+    if matches!(
+        command,
+        commands::GENERATE_CALL_GRAPH_WORKSPACE
+            | commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE
+            | commands::GENERATE_ALL_WORKSPACE
+            | commands::GENERATE_FUNCTION_DIAGRAM
+            | commands::GENERATE_FUNCTION_DIAGRAM_AT_POSITION
+            | commands::REGENERATE_CHUNK
+            | commands::RENDER_DIAGRAM
+            | commands::EXPORT_GRAPH_WORKSPACE
+    ) {
+        for filter in GRAPH_FILTERS {
+            arguments.push((filter, ArgumentKind::Boolean, false));
+        }
+    }
+    // A PR comment compares whole revisions, so it cannot narrow its files.
+    if command != commands::GENERATE_PR_COMMENT {
+        arguments.push(("changed_since", ArgumentKind::GitRef, false));
+    }
+    arguments
+}
+
+fn values(name: &str, kind: ArgumentKind, index: &SymbolIndex, folders: &[PathBuf]) -> Vec<Value> {
+    match kind {
+        ArgumentKind::Contract => index
+            .names(SymbolKind::Contract, folders)
+            .into_iter()
+            .map(Value::from)
+            .collect(),
+        ArgumentKind::GitRef => {
+            let mut refs: Vec<String> = folders
+                .iter()
+                .filter_map(|folder| git::refs(folder).ok())
+                .flatten()
+                .collect();
+            refs.sort();
+            refs.dedup();
+            refs.into_iter().map(Value::from).collect()
+        }
+        ArgumentKind::Boolean => vec![Value::Bool(false), Value::Bool(true)],
+        ArgumentKind::Choice => match name {
+            "format" => to_values(&[
+                ExportFormat::Cypher,
+                ExportFormat::Neo4jCsv,
+                ExportFormat::Obsidian,
+                ExportFormat::Excalidraw,
+            ]),
+            "renderer" => renderers::renderer_names()
+                .into_iter()
+                .map(Value::from)
+                .collect(),
+            "min_confidence" => to_values(&[Confidence::Low, Confidence::Medium, Confidence::High]),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn to_values<T: Serialize>(values: &[T]) -> Vec<Value> {
+    values
+        .iter()
+        .filter_map(|value| serde_json::to_value(value).ok())
+        .collect()
+}
+
+/// The folders named by `workspace_folder` or `workspace_folders`.
+fn folders(args: &Map<String, Value>) -> Vec<PathBuf> {
+    if let Some(folder) = args.get("workspace_folder").and_then(Value::as_str) {
+        return vec![PathBuf::from(folder)];
+    }
+    args.get("workspace_folders")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(PathBuf::from)
+        .collect()
+}
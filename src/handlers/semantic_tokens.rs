@@ -0,0 +1,270 @@
+//! `textDocument/semanticTokens/full`: flags the risky lines of a file.
+//! External calls, delegatecalls and writes to storage each get a token
+//! modifier of their own, so a client can style them apart from the rest
+//! of the code.
+//!
+//! Only the open file is parsed. A member call is external when its object
+//! is declared as a contract or interface, or as a type the file does not
+//! declare as a library, struct or enum; `call`, `staticcall`, `send` and
+//! `transfer` always are. A write is to storage when it assigns, updates or
+//! deletes a state variable of the contract or a base declared in the file,
+//! or a local `storage` reference.
+
+use crate::analysis::dependencies::DELEGATE_CALLS;
+use crate::analysis::model::{
+    for_each_callable, parse_sources, unwrap_expression, visit, ContractKind, SourceModel,
+    SourceUnit,
+};
+use crate::documents::DocumentStore;
+use crate::symbol_index;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{Request as _, SemanticTokensFullRequest};
+use lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens, SemanticTokensLegend,
+    SemanticTokensParams, SemanticTokensResult,
+};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use traverse_graph::parser::get_node_text;
+use tree_sitter::Node as TsNode;
+
+/// Token types, indexed by [`SemanticToken::token_type`].
+pub const TOKEN_TYPES: [&str; 3] = ["function", "property", "variable"];
+/// Token modifiers, one bit each in [`SemanticToken::token_modifiers_bitset`].
+pub const TOKEN_MODIFIERS: [&str; 3] = ["externalCall", "delegateCall", "storageWrite"];
+
+const FUNCTION: u32 = 0;
+const PROPERTY: u32 = 1;
+const VARIABLE: u32 = 2;
+const EXTERNAL_CALL: u32 = 1 << 0;
+const DELEGATE_CALL: u32 = 1 << 1;
+const STORAGE_WRITE: u32 = 1 << 2;
+
+/// Member calls that hand control to another address whatever its type.
+const LOW_LEVEL_CALLS: &[&str] = &["call", "staticcall", "send", "transfer"];
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES
+            .iter()
+            .map(|t| SemanticTokenType::new(t))
+            .collect(),
+        token_modifiers: TOKEN_MODIFIERS
+            .iter()
+            .map(|m| SemanticTokenModifier::new(m))
+            .collect(),
+    }
+}
+
+pub fn semantic_tokens(req: Request, conn: &Connection, documents: &DocumentStore) -> Result<()> {
+    let (id, params) = req.extract::<SemanticTokensParams>(SemanticTokensFullRequest::METHOD)?;
+
+    let data = documents
+        .read(&params.text_document.uri)
+        .map(|text| tokens(&text))
+        .unwrap_or_default();
+    let response = Response::new_ok(
+        id,
+        SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        }),
+    );
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// The tokens of `text`, relative to each other as LSP encodes them: the
+/// called member of each external call or delegatecall, and the variable
+/// each storage write writes.
+pub fn tokens(text: &str) -> Vec<SemanticToken> {
+    let unit = SourceUnit {
+        path: PathBuf::new(),
+        content: text.to_string(),
+    };
+    let Ok(parsed) = parse_sources(std::slice::from_ref(&unit)) else {
+        return Vec::new();
+    };
+    let model = SourceModel::from_parsed(&parsed);
+
+    let mut marks: Vec<(TsNode, u32, u32)> = Vec::new();
+    for_each_callable(parsed[0].tree.root_node(), text, |contract, _, node| {
+        let locals = locals(node, text);
+        let state: HashMap<&str, &str> = model
+            .contract(contract)
+            .into_iter()
+            .chain(model.ancestors(contract))
+            .flat_map(|c| c.state_vars.iter())
+            .filter(|v| v.occupies_storage())
+            .map(|v| (v.name.as_str(), v.type_name.as_str()))
+            .collect();
+        visit(node, &mut |n| {
+            if let Some((property, modifier)) = risky_call(n, text, &model, &locals, &state) {
+                marks.push((property, FUNCTION, modifier));
+            }
+            if let Some(target) = written(n, text) {
+                let name = get_node_text(&target, text);
+                match locals.get(name) {
+                    Some((_, true)) => marks.push((target, VARIABLE, STORAGE_WRITE)),
+                    Some((_, false)) => {}
+                    None if state.contains_key(name) => {
+                        marks.push((target, PROPERTY, STORAGE_WRITE))
+                    }
+                    None => {}
+                }
+            }
+        });
+    });
+    marks.sort_by_key(|(node, ..)| node.start_byte());
+    marks.dedup_by_key(|(node, ..)| node.start_byte());
+
+    let mut data = Vec::new();
+    let mut previous = lsp_types::Position::new(0, 0);
+    for (node, token_type, modifiers) in marks {
+        let start = symbol_index::position(text, node.start_position());
+        let delta_line = start.line - previous.line;
+        data.push(SemanticToken {
+            delta_line,
+            delta_start: if delta_line == 0 {
+                start.character - previous.character
+            } else {
+                start.character
+            },
+            length: get_node_text(&node, text).encode_utf16().count() as u32,
+            token_type,
+            token_modifiers_bitset: modifiers,
+        });
+        previous = start;
+    }
+    data
+}
+
+/// The called member and modifier of an external call or delegatecall.
+fn risky_call<'t>(
+    node: TsNode<'t>,
+    text: &str,
+    model: &SourceModel,
+    locals: &HashMap<String, (String, bool)>,
+    state: &HashMap<&str, &str>,
+) -> Option<(TsNode<'t>, u32)> {
+    let type_of = |name: &str| match locals.get(name) {
+        Some((type_name, _)) => Some(type_name.clone()),
+        None => state.get(name).map(|t| t.to_string()),
+    };
+    if node.kind() != "call_expression" {
+        return None;
+    }
+    let callee = unwrap_expression(node.child_by_field_name("function")?);
+    if callee.kind() != "member_expression" {
+        return None;
+    }
+    let property = callee.child_by_field_name("property")?;
+    let member = get_node_text(&property, text);
+    if DELEGATE_CALLS.contains(&member) {
+        return Some((property, DELEGATE_CALL));
+    }
+    if LOW_LEVEL_CALLS.contains(&member) {
+        return Some((property, EXTERNAL_CALL));
+    }
+
+    let object = unwrap_expression(callee.child_by_field_name("object")?);
+    let type_name = match object.kind() {
+        "identifier" if get_node_text(&object, text) == "this" => {
+            return Some((property, EXTERNAL_CALL))
+        }
+        "identifier" => type_of(get_node_text(&object, text))?,
+        // `vaults[id].deposit()`: the element type of an array or mapping.
+        "array_access" => {
+            let base = unwrap_expression(object.child_by_field_name("base")?);
+            if base.kind() != "identifier" {
+                return None;
+            }
+            element_type(&type_of(get_node_text(&base, text))?).to_string()
+        }
+        // A cast like `IPool(pool)`.
+        "call_expression" => {
+            let function = unwrap_expression(object.child_by_field_name("function")?);
+            get_node_text(&function, text).to_string()
+        }
+        _ => return None,
+    };
+    is_contract_type(model, &type_name).then_some((property, EXTERNAL_CALL))
+}
+
+/// `IVault` for `IVault[]` or `mapping(address => IVault)`.
+fn element_type(type_name: &str) -> &str {
+    let value = match type_name.rsplit_once("=>") {
+        Some((_, value)) => value.trim_end_matches(|c: char| c == ')' || c.is_whitespace()),
+        None => type_name,
+    };
+    value.split('[').next().unwrap_or(value).trim()
+}
+
+/// Whether values of `type_name` are contracts: a contract or interface of
+/// the file, or a capitalized type the file does not declare otherwise.
+/// Elementary types, and addresses until cast, have no external members.
+fn is_contract_type(model: &SourceModel, type_name: &str) -> bool {
+    let simple = type_name.rsplit('.').next().unwrap_or(type_name).trim();
+    match model.contract(simple) {
+        Some(contract) => contract.kind != ContractKind::Library,
+        None => {
+            simple.starts_with(|c: char| c.is_ascii_uppercase())
+                && model.find_struct(simple).is_none()
+                && !model.is_enum(simple)
+        }
+    }
+}
+
+/// The identifier an assignment, `++`, `--` or `delete` writes through:
+/// `balances` for `balances[user].amount = 0`.
+fn written<'t>(node: TsNode<'t>, text: &str) -> Option<TsNode<'t>> {
+    let target = match node.kind() {
+        "assignment_expression" | "augmented_assignment_expression" => {
+            node.child_by_field_name("left")?
+        }
+        "update_expression" => node.child_by_field_name("argument")?,
+        "unary_expression"
+            if node
+                .child(0)
+                .is_some_and(|o| get_node_text(&o, text) == "delete") =>
+        {
+            node.child_by_field_name("argument")?
+        }
+        _ => return None,
+    };
+    root_identifier(target)
+}
+
+fn root_identifier(node: TsNode) -> Option<TsNode> {
+    let node = unwrap_expression(node);
+    match node.kind() {
+        "identifier" => Some(node),
+        "member_expression" => root_identifier(node.child_by_field_name("object")?),
+        "array_access" => root_identifier(node.child_by_field_name("base")?),
+        _ => None,
+    }
+}
+
+/// Types of the parameters and locals declared in `callable`, by name, and
+/// whether each is a `storage` reference.
+fn locals(callable: TsNode, text: &str) -> HashMap<String, (String, bool)> {
+    let mut locals = HashMap::new();
+    visit(callable, &mut |n| {
+        if !matches!(n.kind(), "parameter" | "variable_declaration") {
+            return;
+        }
+        let Some(name) = n.child_by_field_name("name") else {
+            return;
+        };
+        let type_name = n
+            .child_by_field_name("type")
+            .map(|t| get_node_text(&t, text).to_string())
+            .unwrap_or_default();
+        let storage = n
+            .child_by_field_name("location")
+            .is_some_and(|l| get_node_text(&l, text) == "storage");
+        locals.insert(get_node_text(&name, text).to_string(), (type_name, storage));
+    });
+    locals
+}
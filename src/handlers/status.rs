@@ -0,0 +1,32 @@
+use crate::graph_cache::GraphCache;
+use crate::symbol_index::SymbolIndex;
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+
+pub fn status(
+    req: Request,
+    conn: &Connection,
+    index: &SymbolIndex,
+    graphs: &GraphCache,
+) -> Result<()> {
+    let (id, _) = req.extract::<serde_json::Value>(crate::commands::STATUS_REQUEST)?;
+
+    let call_graphs = graphs.stats();
+    let symbol_index_bytes = index.approximate_bytes();
+    let response = Response::new_ok(
+        id,
+        serde_json::json!({
+            "index": index.status(),
+            "memory": {
+                "total_bytes": call_graphs.bytes
+                    + call_graphs.parse_tree_bytes
+                    + symbol_index_bytes,
+                "symbol_index_bytes": symbol_index_bytes,
+                "parse_tree_bytes": call_graphs.parse_tree_bytes,
+                "call_graphs": call_graphs,
+            },
+        }),
+    );
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
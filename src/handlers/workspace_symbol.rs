@@ -0,0 +1,58 @@
+//! `workspace/symbol`: fuzzy search over the declarations of every indexed
+//! file, for jumping to a contract or function by name.
+
+use crate::symbol_index::{self, Symbol, SymbolIndex};
+use anyhow::Result;
+use lsp_server::{Connection, Message, Request, Response};
+use lsp_types::request::{Request as _, WorkspaceSymbolRequest};
+use lsp_types::{
+    Location, SymbolInformation, SymbolKind, WorkspaceSymbolParams, WorkspaceSymbolResponse,
+};
+
+/// Results returned for one query; clients narrow the query instead of
+/// paging.
+const MAX_RESULTS: usize = 200;
+
+pub fn workspace_symbol(req: Request, conn: &Connection, index: &SymbolIndex) -> Result<()> {
+    let (id, params) = req.extract::<WorkspaceSymbolParams>(WorkspaceSymbolRequest::METHOD)?;
+
+    let symbols = index
+        .search(&params.query, MAX_RESULTS)
+        .into_iter()
+        .map(information)
+        .collect();
+    let response = Response::new_ok(id, WorkspaceSymbolResponse::Flat(symbols));
+    conn.sender.send(Message::Response(response))?;
+    Ok(())
+}
+
+/// `symbol` as LSP reports it, with the kinds `textDocument/documentSymbol`
+/// uses.
+pub fn information(symbol: Symbol) -> SymbolInformation {
+    let function = if symbol.container.is_some() {
+        SymbolKind::METHOD
+    } else {
+        SymbolKind::FUNCTION
+    };
+    let kind = match symbol.kind {
+        symbol_index::SymbolKind::Contract => SymbolKind::CLASS,
+        symbol_index::SymbolKind::Interface => SymbolKind::INTERFACE,
+        symbol_index::SymbolKind::Library => SymbolKind::MODULE,
+        symbol_index::SymbolKind::Function | symbol_index::SymbolKind::Modifier => function,
+        symbol_index::SymbolKind::Event => SymbolKind::EVENT,
+        symbol_index::SymbolKind::Error => SymbolKind::OBJECT,
+        symbol_index::SymbolKind::StateVariable => SymbolKind::FIELD,
+        symbol_index::SymbolKind::Struct => SymbolKind::STRUCT,
+        symbol_index::SymbolKind::Enum => SymbolKind::ENUM,
+    };
+
+    #[allow(deprecated)]
+    SymbolInformation {
+        name: symbol.name,
+        kind,
+        tags: None,
+        deprecated: None,
+        location: Location::new(symbol.uri, symbol.range),
+        container_name: symbol.container,
+    }
+}
@@ -0,0 +1,129 @@
+//! Hardhat project layout.
+//!
+//! A `hardhat.config.*` at the workspace root marks a Hardhat project. Its
+//! contracts live under `paths.sources`, `contracts` by default. Hardhat
+//! resolves an import that is not relative against `node_modules` first and
+//! the project root second, so `@openzeppelin/contracts/...` comes from the
+//! installed package. The config is JavaScript or TypeScript, so only a
+//! literal `sources: "..."` is read from it.
+
+use crate::analysis::imports;
+use anyhow::{Context, Result};
+use lsp_types::Url;
+use std::collections::{BTreeSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const HARDHAT_CONFIGS: [&str; 4] = [
+    "hardhat.config.ts",
+    "hardhat.config.js",
+    "hardhat.config.cjs",
+    "hardhat.config.mjs",
+];
+pub const NODE_MODULES: &str = "node_modules";
+
+#[derive(Debug, Clone)]
+pub struct HardhatProject {
+    pub root: PathBuf,
+    /// Contracts of the project, `contracts` by default.
+    pub sources: PathBuf,
+}
+
+impl HardhatProject {
+    /// The project rooted at `root`, or `None` without a Hardhat config.
+    pub fn detect(root: &Path) -> Result<Option<Self>> {
+        let Some(path) = HARDHAT_CONFIGS
+            .iter()
+            .map(|name| root.join(name))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let sources = sources_path(&text).unwrap_or_else(|| "contracts".to_string());
+        Ok(Some(Self {
+            root: root.to_path_buf(),
+            sources: imports::normalize(&root.join(sources)),
+        }))
+    }
+
+    /// The project's contracts among `files`, and every file they import,
+    /// in `files` order. With `node_modules`, imported package files follow,
+    /// sorted by path; otherwise imports into packages are not followed.
+    pub fn scope(&self, files: &[Url], node_modules: bool) -> Result<Vec<Url>> {
+        let known: BTreeSet<PathBuf> = files
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect();
+        let packages = self.root.join(NODE_MODULES);
+        let mut included = BTreeSet::new();
+        let mut queue: VecDeque<PathBuf> = known
+            .iter()
+            .filter(|path| path.starts_with(&self.sources))
+            .cloned()
+            .collect();
+        while let Some(file) = queue.pop_front() {
+            if !included.insert(file.clone()) {
+                continue;
+            }
+            let content = fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            for import in imports::import_paths(&content)? {
+                let target = if import.starts_with("./") || import.starts_with("../") {
+                    file.parent()
+                        .map(|dir| imports::normalize(&dir.join(&import)))
+                } else {
+                    let package = imports::normalize(&packages.join(&import));
+                    if package.is_file() {
+                        node_modules.then_some(package)
+                    } else {
+                        Some(imports::normalize(&self.root.join(&import)))
+                    }
+                };
+                let Some(target) = target else {
+                    continue;
+                };
+                let in_packages = target.starts_with(&packages);
+                if (known.contains(&target) || in_packages && target.is_file())
+                    && !included.contains(&target)
+                {
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        let mut scope: Vec<Url> = files
+            .iter()
+            .filter(|uri| {
+                uri.to_file_path()
+                    .is_ok_and(|path| included.contains(&path))
+            })
+            .cloned()
+            .collect();
+        for path in included.iter().filter(|path| !known.contains(*path)) {
+            scope.push(Url::from_file_path(path).map_err(|_| anyhow::anyhow!("Invalid path"))?);
+        }
+        Ok(scope)
+    }
+}
+
+/// The string after `sources:` in a Hardhat config, if it is a literal.
+fn sources_path(config: &str) -> Option<String> {
+    let mut rest = config;
+    while let Some(index) = rest.find("sources") {
+        let after = rest[index + "sources".len()..].trim_start();
+        rest = &rest[index + "sources".len()..];
+        let Some(value) = after.strip_prefix(':') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if !matches!(quote, '"' | '\'' | '`') {
+            continue;
+        }
+        let end = value[1..].find(quote)?;
+        return Some(value[1..=end].to_string());
+    }
+    None
+}
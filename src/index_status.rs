@@ -0,0 +1,33 @@
+//! Periodic `traverse/indexStatus` notifications.
+//!
+//! Clients show these in a status bar item, so a notification is only sent
+//! when the summary changed since the previous one.
+
+use crate::symbol_index::{IndexStatus, SymbolIndex};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Publishes the index status every `interval` until `stop` receives a
+/// message or is disconnected.
+pub fn run(
+    index: Arc<SymbolIndex>,
+    interval: Duration,
+    stop: mpsc::Receiver<()>,
+    mut publish: impl FnMut(IndexStatus),
+) {
+    let mut last = None;
+    loop {
+        let status = index.status();
+        if last.as_ref() != Some(&status) {
+            publish(status.clone());
+            last = Some(status);
+        }
+        match stop.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => {}
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
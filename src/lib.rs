@@ -1,9 +1,38 @@
+pub mod analysis;
+pub mod cancellation;
 pub mod commands;
 pub mod config;
+pub mod consumers;
+pub mod coverage;
+pub mod diagnostics;
+pub mod doctor;
+pub mod documents;
+pub mod exporters;
+pub mod file_filter;
+pub mod foundry;
 pub mod generator_worker;
+pub mod git;
+pub mod graph_cache;
+pub mod graph_filter;
 pub mod handlers;
+pub mod hardhat;
+pub mod index_status;
+pub mod output;
+pub mod profiles;
+pub mod project_config;
+pub mod query_history;
+pub mod renderers;
+pub mod source_map;
+pub mod symbol_index;
+pub mod telemetry;
+pub mod trace;
 pub mod traverse_adapter;
 pub mod utils;
+#[cfg(feature = "vyper")]
+pub mod vyper;
+pub mod watchdog;
+pub mod workspace_folders;
+pub mod yul;
 
 pub use config::MermaidConfig;
 pub use generator_worker::{GenerationRequest, GeneratorWorker};
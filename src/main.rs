@@ -5,33 +5,102 @@
 //! to keep the main message loop responsive.
 
 use crate::{
+    cancellation::InFlightRequests,
+    config::{Config, FileFilter, SharedConfig, RESTART_SETTINGS},
+    diagnostics::{DiagnosticsEvent, DiagnosticsWorker},
+    documents::DocumentStore,
     generator_worker::{GenerationRequest, GeneratorWorker},
-    handlers::execute_command,
+    graph_cache::GraphCache,
+    handlers::{execute_command, execute_command_with_telemetry, with_request_id},
+    output::settings::{OutputLocation, OutputSettings},
+    project_config::ConfigSources,
+    symbol_index::SymbolIndex,
+    traverse_adapter::source_extensions,
+    workspace_folders::WorkspaceFolders,
 };
 use anyhow::Result;
-use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
-    request::{ExecuteCommand, Request as _},
-    CodeActionOptions, CompletionOptions, InitializeParams, ServerCapabilities,
-    TextDocumentSyncCapability, TextDocumentSyncKind,
+    notification::{
+        Cancel, DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles,
+        DidChangeWorkspaceFolders, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+        Exit, Notification as _, PublishDiagnostics, ShowMessage, WorkDoneProgressCancel,
+    },
+    request::{
+        CodeActionRequest, CodeLensRequest, DocumentSymbolRequest, ExecuteCommand, GotoDefinition,
+        HoverRequest, InlayHintRequest, References, RegisterCapability, Request as _,
+        SemanticTokensFullRequest, Shutdown, WorkspaceConfiguration, WorkspaceSymbolRequest,
+    },
+    CancelParams, ClientCapabilities, CodeActionOptions, CodeLensOptions, CompletionOptions,
+    ConfigurationItem, ConfigurationParams, DidChangeConfigurationParams,
+    DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWatchedFilesRegistrationOptions, DidChangeWorkspaceFoldersParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    ExecuteCommandParams, FileSystemWatcher, GlobPattern, HoverProviderCapability,
+    InitializeParams, MessageType, OneOf, Registration, RegistrationParams,
+    SemanticTokensFullOptions, SemanticTokensOptions, SemanticTokensServerCapabilities,
+    ServerCapabilities, ShowMessageParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions, WorkDoneProgressCancelParams,
+    WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
 };
-use std::{env, sync::mpsc, thread};
-use tracing::info;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{env, path::PathBuf, process::ExitCode, sync::mpsc, sync::Arc, thread};
+use tracing::{debug, error, info, warn};
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
+mod analysis;
+mod cancellation;
 mod commands;
 mod config;
+mod consumers;
+mod coverage;
+mod diagnostics;
+mod doctor;
+mod documents;
+mod exporters;
+mod file_filter;
+mod foundry;
 mod generator_worker;
+mod git;
+mod graph_cache;
+mod graph_filter;
 mod handlers;
+mod hardhat;
+mod index_status;
+mod output;
+mod profiles;
+mod project_config;
+mod query_history;
+mod renderers;
+mod source_map;
+mod symbol_index;
+mod telemetry;
+mod trace;
 mod traverse_adapter;
 mod utils;
+#[cfg(feature = "vyper")]
+mod vyper;
+mod watchdog;
+mod workspace_folders;
+mod yul;
 
-fn main() -> Result<()> {
+/// Id of the `client/registerCapability` request for file watching, which
+/// is also the registration id.
+const WATCH_FILES_REGISTRATION: &str = "traverse/watchSources";
+/// Prefix of the ids of `workspace/configuration` requests sent to the
+/// client.
+const CONFIGURATION_REQUEST: &str = "traverse/configuration";
+/// Settings section the server reads from the client.
+const CONFIGURATION_SECTION: &str = "traverse";
+
+static CONFIGURATION_PULLS: AtomicU64 = AtomicU64::new(0);
+
+fn main() -> Result<ExitCode> {
     // Handle command-line arguments
     let args: Vec<String> = env::args().collect();
     if args.len() > 1 && (args[1] == "--version" || args[1] == "-V") {
         println!("traverse-lsp {}", env!("CARGO_PKG_VERSION"));
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
     let subscriber = FmtSubscriber::builder()
@@ -45,69 +114,388 @@ fn main() -> Result<()> {
     let (connection, io_threads) = Connection::stdio();
 
     let server_capabilities = serde_json::to_value(ServerCapabilities {
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        text_document_sync: Some(TextDocumentSyncCapability::Options(
+            TextDocumentSyncOptions {
+                open_close: Some(true),
+                change: Some(TextDocumentSyncKind::INCREMENTAL),
+                save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                ..Default::default()
+            },
+        )),
         completion_provider: Some(CompletionOptions::default()),
-        hover_provider: None,
-        code_lens_provider: None,
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: handlers::semantic_tokens::legend(),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..Default::default()
+            },
+        )),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
         code_action_provider: Some(lsp_types::CodeActionProviderCapability::Options(
             CodeActionOptions {
                 ..Default::default()
             },
         )),
         execute_command_provider: None,
+        workspace: Some(WorkspaceServerCapabilities {
+            workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                supported: Some(true),
+                change_notifications: Some(OneOf::Left(true)),
+            }),
+            file_operations: None,
+        }),
         ..Default::default()
     })?;
 
     let init_params = connection.initialize(server_capabilities)?;
     let init_params: InitializeParams = serde_json::from_value(init_params)?;
 
-    main_loop(connection, init_params)?;
+    let shutdown_received = main_loop(connection, init_params)?;
 
     io_threads.join()?;
     info!("Shutting down Traverse LSP server");
-    Ok(())
+    Ok(if shutdown_received {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
 }
 
-fn main_loop(connection: Connection, _init_params: InitializeParams) -> Result<()> {
+/// Runs until the client's `exit` notification or until the connection
+/// closes, and returns whether `shutdown` was received first.
+fn main_loop(connection: Connection, init_params: InitializeParams) -> Result<bool> {
     info!("Starting main loop");
 
+    let folders = WorkspaceFolders::from_init(&init_params);
+    let workspace_root = folders.list().first().cloned();
+    let sources = ConfigSources {
+        project: load_project_config(workspace_root.as_deref()),
+        client: init_params.initialization_options.unwrap_or_default(),
+    };
+    let config = load_config(&sources);
+    let pull_config = init_params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.configuration)
+        == Some(true);
+    let shared_config = Arc::new(SharedConfig::new(config.clone()));
+    let worker_shared_config = shared_config.clone();
+    let worker_config = config.clone();
+    let diagnostics_config = config.clone();
+    let index = Arc::new(SymbolIndex::new());
+    let diagnostics_index = index.clone();
+
     let (generator_tx, generator_rx) = mpsc::channel::<GenerationRequest>();
 
+    let generation_timeout = config.generation_timeout();
+    let generation_workers = config.generation_workers();
+    let graphs = Arc::new(GraphCache::new(config.cache_budget_bytes()));
+    let worker_graphs = graphs.clone();
+    let documents = Arc::new(DocumentStore::new().with_parse_trees(graphs.clone()));
+    let worker_documents = documents.clone();
+    let in_flight = Arc::new(InFlightRequests::new());
+    let output = Arc::new(OutputSettings::new(OutputLocation::from_config(&config)));
+    let worker_output = output.clone();
     let generator_thread = thread::spawn(move || {
-        GeneratorWorker::new().unwrap().run(generator_rx);
+        watchdog::run_restarting(generator_rx, generation_timeout, generation_workers, || {
+            watchdog::spawn_worker(
+                GeneratorWorker::new(worker_config.clone())?
+                    .with_graph_cache(worker_graphs.clone())
+                    .with_documents(worker_documents.clone())
+                    .with_output_settings(worker_output.clone())
+                    .with_shared_config(worker_shared_config.clone()),
+            )
+        });
     });
 
+    let (diagnostics_tx, diagnostics_rx) = mpsc::channel::<DiagnosticsEvent>();
+    let publisher = connection.sender.clone();
+    let diagnostics_thread = thread::spawn(move || {
+        DiagnosticsWorker::new(
+            diagnostics_config,
+            workspace_root,
+            diagnostics_index,
+            move |params| {
+                let notification =
+                    Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+                let _ = publisher.send(notification.into());
+            },
+        )
+        .run(diagnostics_rx);
+    });
+
+    let (status_tx, status_rx) = mpsc::channel::<()>();
+    let status_index = index.clone();
+    let status_sender = connection.sender.clone();
+    let status_thread = thread::spawn(move || {
+        index_status::run(
+            status_index,
+            index_status::STATUS_INTERVAL,
+            status_rx,
+            |status| {
+                let notification =
+                    Notification::new(commands::INDEX_STATUS_NOTIFICATION.to_string(), status);
+                let _ = status_sender.send(notification.into());
+            },
+        );
+    });
+
+    register_file_watchers(&connection, &init_params.capabilities)?;
+    execute_command::set_server_progress(
+        init_params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            == Some(true),
+    );
+    log_self_check(&doctor::run(&doctor::Environment {
+        folders: &folders.list(),
+        output_dir: &output.current().dir,
+        cache: graphs.stats(),
+        capabilities: &init_params.capabilities,
+    }));
+    if pull_config {
+        request_configuration(&connection)?;
+    }
+
+    // Per the LSP lifecycle, `exit` is only expected after `shutdown`; the
+    // exit code tells the caller whether the client followed it.
+    let mut shutdown_received = false;
+    let mut workers = Some((generator_thread, diagnostics_thread, status_thread));
+    let mut stop_workers = || {
+        let Some(threads) = workers.take() else {
+            return;
+        };
+        in_flight.shut_down_all();
+        let _ = generator_tx.send(GenerationRequest::Shutdown);
+        let _ = diagnostics_tx.send(DiagnosticsEvent::Shutdown);
+        let _ = status_tx.send(());
+        let (generator, diagnostics, status) = threads;
+        for (name, thread) in [
+            ("generator", generator),
+            ("diagnostics", diagnostics),
+            ("index status", status),
+        ] {
+            if thread.join().is_err() {
+                warn!("The {} thread panicked during shutdown", name);
+            }
+        }
+    };
+
+    let mut live = LiveConfig {
+        config: &shared_config,
+        output: &output,
+        diagnostics_tx: &diagnostics_tx,
+        sources,
+    };
+
     for msg in &connection.receiver {
         match msg {
+            Message::Request(req) if req.method == Shutdown::METHOD => {
+                info!("Shutdown requested");
+                shutdown_received = true;
+                stop_workers();
+                connection
+                    .sender
+                    .send(Response::new_ok(req.id, ()).into())?;
+            }
+            Message::Request(req) if shutdown_received => {
+                let response = Response::new_err(
+                    req.id,
+                    ErrorCode::InvalidRequest as i32,
+                    "Server is shutting down".to_string(),
+                );
+                connection.sender.send(response.into())?;
+            }
             Message::Request(req) => {
-                if connection.handle_shutdown(&req)? {
-                    generator_tx.send(GenerationRequest::Shutdown)?;
-                    break;
+                let config = shared_config.current();
+                let req = fill_workspace_folders(req, &folders.list());
+                let req = fill_file_filter(req, &config.files);
+                let caches = WatchedCaches {
+                    documents: &documents,
+                    index: &index,
+                    graphs: &graphs,
+                };
+                let context = RequestContext {
+                    config: &config,
+                    caches: &caches,
+                    folders: &folders.list(),
+                    output: &output,
+                    capabilities: &init_params.capabilities,
+                };
+                process_request(&connection, req, &generator_tx, &context, &in_flight);
+            }
+            Message::Notification(not) if not.method == Exit::METHOD => break,
+            Message::Notification(_) if shutdown_received => {}
+            Message::Notification(not) if not.method == Cancel::METHOD => {
+                cancel_request(not, &in_flight);
+            }
+            Message::Notification(not) if not.method == WorkDoneProgressCancel::METHOD => {
+                cancel_progress(not);
+            }
+            Message::Notification(not) if not.method == DidChangeWorkspaceFolders::METHOD => {
+                let caches = WatchedCaches {
+                    documents: &documents,
+                    index: &index,
+                    graphs: &graphs,
+                };
+                change_workspace_folders(not, &folders, &caches);
+            }
+            Message::Notification(not) if not.method == DidChangeWatchedFiles::METHOD => {
+                let Ok(params) =
+                    not.extract::<DidChangeWatchedFilesParams>(DidChangeWatchedFiles::METHOD)
+                else {
+                    continue;
+                };
+                let root = folders.list().first().cloned();
+                let config_changed = params.changes.iter().any(|change| {
+                    change.uri.to_file_path().is_ok_and(|path| {
+                        project_config::is_project_config(&path)
+                            && root
+                                .as_deref()
+                                .is_some_and(|root| path.parent() == Some(root))
+                    })
+                });
+                if config_changed {
+                    reload_project_config(&mut live, root.as_deref(), &connection);
                 }
-
-                process_request(&connection, req, &generator_tx);
+                let caches = WatchedCaches {
+                    documents: &documents,
+                    index: &index,
+                    graphs: &graphs,
+                };
+                files_changed(params, &caches, &diagnostics_tx);
+            }
+            Message::Notification(not) if not.method == DidChangeConfiguration::METHOD => {
+                change_configuration(not, &mut live, &connection, pull_config)?;
             }
             Message::Notification(not) => {
-                process_notification(not);
+                process_notification(not, &diagnostics_tx, &index, &documents);
+            }
+            Message::Response(response) if is_configuration_response(&response) => {
+                configuration_received(response, &mut live, &connection);
+            }
+            Message::Response(response) if execute_command::is_progress_response(&response) => {
+                execute_command::progress_created(response);
+            }
+            Message::Response(response) => {
+                if let Some(error) = response.error {
+                    warn!("Client rejected request {}: {}", response.id, error.message);
+                }
             }
-            Message::Response(_) => {}
         }
     }
 
-    generator_thread.join().unwrap();
+    if !shutdown_received {
+        warn!("Client exited without a shutdown request");
+    }
+    // Cancelled jobs stop at their next phase boundary, and output that was
+    // still being staged is discarded rather than left half-written.
+    stop_workers();
 
-    Ok(())
+    Ok(shutdown_received)
+}
+
+/// Logs the checks of `traverse.doctor` that did not pass. They are not
+/// shown to the user, who may never run a command the problem affects.
+fn log_self_check(report: &doctor::Report) {
+    for check in &report.checks {
+        match check.status {
+            doctor::Status::Ok => debug!("Self-check {}: {}", check.name, check.detail),
+            doctor::Status::Warning => warn!("Self-check {}: {}", check.name, check.detail),
+            doctor::Status::Error => error!("Self-check {}: {}", check.name, check.detail),
+        }
+    }
+}
+
+/// What request handlers read from the main loop.
+struct RequestContext<'a> {
+    config: &'a Config,
+    caches: &'a WatchedCaches<'a>,
+    folders: &'a [PathBuf],
+    output: &'a OutputSettings,
+    capabilities: &'a ClientCapabilities,
+}
+
+impl RequestContext<'_> {
+    fn doctor(&self) -> doctor::Report {
+        doctor::run(&doctor::Environment {
+            folders: self.folders,
+            output_dir: &self.output.current().dir,
+            cache: self.caches.graphs.stats(),
+            capabilities: self.capabilities,
+        })
+    }
 }
 
 fn process_request(
     conn: &Connection,
     req: Request,
     generator_tx: &mpsc::Sender<GenerationRequest>,
+    context: &RequestContext,
+    in_flight: &Arc<InFlightRequests>,
 ) {
     let req_id = req.id.clone();
+    let RequestContext {
+        config,
+        caches,
+        folders,
+        ..
+    } = context;
 
     let result = match req.method.as_str() {
-        ExecuteCommand::METHOD => execute_command(req, conn, generator_tx),
+        ExecuteCommand::METHOD if is_command(&req, commands::CLEAR_CACHE) => {
+            handlers::clear_cache(req, conn, caches.graphs)
+        }
+        ExecuteCommand::METHOD if is_command(&req, commands::DOCTOR) => {
+            handlers::doctor(req, conn, context.doctor())
+        }
+        ExecuteCommand::METHOD if is_command(&req, commands::CLEAN_OUTPUTS) => {
+            handlers::clean_outputs(req, conn, &context.output.current().dir, config.retention)
+        }
+        ExecuteCommand::METHOD => {
+            spawn_command(
+                conn,
+                req,
+                generator_tx,
+                in_flight,
+                config.telemetry_enabled(),
+            );
+            Ok(())
+        }
+        commands::QUERY_HISTORY_REQUEST => handlers::query_history(req, conn),
+        commands::LIST_RULES_REQUEST => handlers::list_rules(req, conn, config),
+        commands::CONFIG_SCHEMA_REQUEST => handlers::config_schema(req, conn),
+        CodeActionRequest::METHOD => handlers::code_action(req, conn, caches.documents, folders),
+        CodeLensRequest::METHOD => handlers::code_lens(req, conn, caches.documents, folders),
+        DocumentSymbolRequest::METHOD => handlers::document_symbol(req, conn, caches.documents),
+        HoverRequest::METHOD => handlers::hover(req, conn, caches.documents, caches.graphs),
+        GotoDefinition::METHOD => handlers::definition(req, conn, caches.documents, caches.graphs),
+        References::METHOD => handlers::references(req, conn, caches.documents, caches.graphs),
+        InlayHintRequest::METHOD => handlers::inlay_hint(
+            req,
+            conn,
+            caches.documents,
+            caches.graphs,
+            config.inlay_hints_enabled(),
+        ),
+        SemanticTokensFullRequest::METHOD => handlers::semantic_tokens(req, conn, caches.documents),
+        WorkspaceSymbolRequest::METHOD => handlers::workspace_symbol(req, conn, caches.index),
+        commands::FIND_SYMBOL_REQUEST => handlers::find_symbol(req, conn, caches.index),
+        commands::STATUS_REQUEST => handlers::status(req, conn, caches.index, caches.graphs),
+        commands::RESOLVE_COMMAND_ARGUMENTS_REQUEST => {
+            handlers::resolve_command_arguments(req, conn, caches.index)
+        }
         _ => {
             info!("Received unhandled request: {}", req.method);
             Ok(())
@@ -116,8 +504,511 @@ fn process_request(
 
     if let Err(e) = result {
         let response = Response::new_err(req_id, -32603, e.to_string());
-        let _ = conn.sender.send(response.into());
+        let _ = conn.sender.send(with_request_id(response).into());
     }
 }
 
-fn process_notification(_not: Notification) {}
+/// Whether `req`, a `workspace/executeCommand` request, runs `command`.
+fn is_command(req: &Request, command: &str) -> bool {
+    req.params.get("command").and_then(|c| c.as_str()) == Some(command)
+}
+
+/// Names the open workspace folders in a workspace command that names none,
+/// or in the arguments of a `traverse/resolveCommandArguments` request.
+fn fill_workspace_folders(mut req: Request, folders: &[PathBuf]) -> Request {
+    if req.method != ExecuteCommand::METHOD
+        && req.method != commands::RESOLVE_COMMAND_ARGUMENTS_REQUEST
+    {
+        return req;
+    }
+    let Ok(mut params) = serde_json::from_value::<ExecuteCommandParams>(req.params.clone()) else {
+        return req;
+    };
+    if commands::WORKSPACE_COMMANDS.contains(&params.command.as_str()) {
+        workspace_folders::fill_folder_argument(&mut params.arguments, folders);
+        if let Ok(value) = serde_json::to_value(params) {
+            req.params = value;
+        }
+    }
+    req
+}
+
+/// Adds the configured `include` and `exclude` patterns to a workspace
+/// command whose arguments do not set them.
+fn fill_file_filter(mut req: Request, filter: &FileFilter) -> Request {
+    if req.method != ExecuteCommand::METHOD || filter.is_empty() {
+        return req;
+    }
+    let Ok(mut params) = serde_json::from_value::<ExecuteCommandParams>(req.params.clone()) else {
+        return req;
+    };
+    if !commands::WORKSPACE_COMMANDS.contains(&params.command.as_str()) {
+        return req;
+    }
+    if params.arguments.is_empty() {
+        params
+            .arguments
+            .push(serde_json::Value::Object(Default::default()));
+    }
+    let Some(args) = params.arguments[0].as_object_mut() else {
+        return req;
+    };
+    for (key, patterns) in [("include", &filter.include), ("exclude", &filter.exclude)] {
+        if !patterns.is_empty() && !args.contains_key(key) {
+            args.insert(key.to_string(), serde_json::json!(patterns));
+        }
+    }
+    if filter.node_modules
+        && !args.contains_key("node_modules")
+        && !args.contains_key("nodeModules")
+    {
+        args.insert("node_modules".to_string(), serde_json::json!(true));
+    }
+    if let Ok(value) = serde_json::to_value(params) {
+        req.params = value;
+    }
+    req
+}
+
+/// Runs a workspace command on its own thread, so the loop keeps reading
+/// messages, including a `$/cancelRequest` for the command.
+fn spawn_command(
+    conn: &Connection,
+    req: Request,
+    generator_tx: &mpsc::Sender<GenerationRequest>,
+    in_flight: &Arc<InFlightRequests>,
+    telemetry: bool,
+) {
+    let id = req.id.clone();
+    let cancel = in_flight.register(id.clone());
+    let sender = conn.sender.clone();
+    let generator_tx = generator_tx.clone();
+    let in_flight = in_flight.clone();
+    thread::spawn(move || {
+        if let Err(e) =
+            execute_command_with_telemetry(req, &sender, &generator_tx, cancel, telemetry)
+        {
+            let response = Response::new_err(id.clone(), -32603, e.to_string());
+            let _ = sender.send(with_request_id(response).into());
+        }
+        in_flight.finish(&id);
+    });
+}
+
+/// Stops the job behind a progress whose cancel button the user pressed.
+fn cancel_progress(not: Notification) {
+    let Ok(params) = not.extract::<WorkDoneProgressCancelParams>(WorkDoneProgressCancel::METHOD)
+    else {
+        return;
+    };
+    if cancellation::cancel_progress(&params.token) {
+        info!("Cancelling the job reporting progress {:?}", params.token);
+    }
+}
+
+fn cancel_request(not: Notification, in_flight: &InFlightRequests) {
+    let Ok(params) = not.extract::<CancelParams>(Cancel::METHOD) else {
+        return;
+    };
+    let id = cancellation::request_id(params.id);
+    if in_flight.cancel(&id) {
+        info!("Cancelling request {}", id);
+    }
+}
+
+/// Tracks the open workspace folders. Closing a folder drops what was
+/// cached for its files; a new folder is indexed in the background.
+fn change_workspace_folders(not: Notification, folders: &WorkspaceFolders, caches: &WatchedCaches) {
+    let Ok(params) =
+        not.extract::<DidChangeWorkspaceFoldersParams>(DidChangeWorkspaceFolders::METHOD)
+    else {
+        return;
+    };
+    let (added, removed) = folders.apply(&params.event);
+    for folder in removed {
+        info!("Workspace folder {} closed", folder.display());
+        caches.index.remove_folder(&folder);
+        caches.graphs.invalidate(&folder);
+        caches.graphs.forget_trees(&folder);
+    }
+    for folder in added {
+        info!("Workspace folder {} opened", folder.display());
+        let index = caches.index.clone();
+        thread::spawn(move || {
+            if let Err(e) = index.index_workspace(&folder) {
+                warn!("Failed to index {}: {}", folder.display(), e);
+            }
+        });
+    }
+}
+
+/// Asks the client to report source files created, changed or deleted
+/// outside the editor, such as by `git checkout`, if it can watch files.
+fn register_file_watchers(conn: &Connection, capabilities: &ClientCapabilities) -> Result<()> {
+    let supported = capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+        .and_then(|watched| watched.dynamic_registration)
+        == Some(true);
+    if !supported {
+        info!("Client cannot watch files; changes outside the editor are seen on the next read");
+        return Ok(());
+    }
+    let sources = source_extensions()
+        .into_iter()
+        .map(|extension| format!("**/*.{}", extension));
+    let project_configs = project_config::PROJECT_CONFIG_FILES
+        .iter()
+        .map(|name| format!("**/{}", name));
+    let watchers = sources
+        .chain(project_configs)
+        .map(|pattern| FileSystemWatcher {
+            glob_pattern: GlobPattern::String(pattern),
+            kind: None,
+        })
+        .collect();
+    let registration = Registration {
+        id: WATCH_FILES_REGISTRATION.to_string(),
+        method: DidChangeWatchedFiles::METHOD.to_string(),
+        register_options: Some(serde_json::to_value(
+            DidChangeWatchedFilesRegistrationOptions { watchers },
+        )?),
+    };
+    let request = Request::new(
+        RequestId::from(WATCH_FILES_REGISTRATION.to_string()),
+        RegisterCapability::METHOD.to_string(),
+        RegistrationParams {
+            registrations: vec![registration],
+        },
+    );
+    conn.sender.send(request.into())?;
+    Ok(())
+}
+
+/// Per-document and per-workspace state requests read, and which a file
+/// changed on disk can leave stale.
+struct WatchedCaches<'a> {
+    documents: &'a DocumentStore,
+    index: &'a Arc<SymbolIndex>,
+    graphs: &'a GraphCache,
+}
+
+/// Drops what was derived from files changed outside the editor. Files the
+/// editor has open are skipped, since its text wins over the disk.
+fn files_changed(
+    params: DidChangeWatchedFilesParams,
+    caches: &WatchedCaches,
+    diagnostics_tx: &mpsc::Sender<DiagnosticsEvent>,
+) {
+    let mut changed = 0;
+    for event in params.changes {
+        if caches.documents.is_open(&event.uri) {
+            continue;
+        }
+        let Ok(path) = event.uri.to_file_path() else {
+            continue;
+        };
+        if project_config::is_project_config(&path) {
+            continue;
+        }
+        debug!("{} changed on disk ({:?})", event.uri, event.typ);
+        caches.index.reload(&event.uri);
+        caches.graphs.invalidate(&path);
+        caches.graphs.forget_trees(&path);
+        changed += 1;
+    }
+    if changed > 0 {
+        info!("{} files changed outside the editor", changed);
+        let _ = diagnostics_tx.send(DiagnosticsEvent::FilesChanged);
+    }
+}
+
+/// Where a configuration change applies, and the settings it is merged
+/// with.
+struct LiveConfig<'a> {
+    config: &'a SharedConfig,
+    output: &'a OutputSettings,
+    diagnostics_tx: &'a mpsc::Sender<DiagnosticsEvent>,
+    sources: ConfigSources,
+}
+
+/// Applies `workspace/didChangeConfiguration`. Clients that answer
+/// `workspace/configuration` are asked for the new settings, since the
+/// notification may not carry them; others send them in the notification.
+fn change_configuration(
+    not: Notification,
+    live: &mut LiveConfig,
+    conn: &Connection,
+    pull_config: bool,
+) -> Result<()> {
+    if pull_config {
+        return request_configuration(conn);
+    }
+    let Ok(params) = not.extract::<DidChangeConfigurationParams>(DidChangeConfiguration::METHOD)
+    else {
+        return Ok(());
+    };
+    apply_client_settings(params.settings, live, conn);
+    Ok(())
+}
+
+/// Asks the client for the `traverse` settings section.
+fn request_configuration(conn: &Connection) -> Result<()> {
+    let pull = CONFIGURATION_PULLS.fetch_add(1, Ordering::Relaxed) + 1;
+    let request = Request::new(
+        RequestId::from(format!("{}/{}", CONFIGURATION_REQUEST, pull)),
+        WorkspaceConfiguration::METHOD.to_string(),
+        ConfigurationParams {
+            items: vec![ConfigurationItem {
+                scope_uri: None,
+                section: Some(CONFIGURATION_SECTION.to_string()),
+            }],
+        },
+    );
+    conn.sender.send(request.into())?;
+    Ok(())
+}
+
+fn is_configuration_response(response: &Response) -> bool {
+    serde_json::to_value(&response.id)
+        .ok()
+        .and_then(|id| id.as_str().map(|id| id.starts_with(CONFIGURATION_REQUEST)))
+        .unwrap_or(false)
+}
+
+fn configuration_received(response: Response, live: &mut LiveConfig, conn: &Connection) {
+    if let Some(error) = response.error {
+        warn!("Client did not send its configuration: {}", error.message);
+        return;
+    }
+    let settings = match response.result {
+        Some(serde_json::Value::Array(mut items)) if !items.is_empty() => items.swap_remove(0),
+        _ => return,
+    };
+    apply_client_settings(settings, live, conn);
+}
+
+/// Lays new client settings over the project settings.
+fn apply_client_settings(settings: serde_json::Value, live: &mut LiveConfig, conn: &Connection) {
+    // Settings pushed by editors are usually keyed by section.
+    let settings = match settings {
+        serde_json::Value::Object(mut map) if map.contains_key(CONFIGURATION_SECTION) => {
+            map.remove(CONFIGURATION_SECTION).unwrap_or_default()
+        }
+        settings => settings,
+    };
+    if settings.is_null() {
+        return;
+    }
+    let sources = ConfigSources {
+        client: settings,
+        ..live.sources.clone()
+    };
+    if let Err(e) = reconfigure(sources, live, conn) {
+        warn!("Ignoring invalid configuration: {}", e);
+    }
+}
+
+/// Reads the project config file of `root` again after it changed on disk.
+fn reload_project_config(live: &mut LiveConfig, root: Option<&std::path::Path>, conn: &Connection) {
+    let result = match root.map(project_config::load).transpose() {
+        Ok(loaded) => {
+            let project = loaded.flatten().map(|(_, settings)| settings);
+            let sources = ConfigSources {
+                project: project.unwrap_or_default(),
+                ..live.sources.clone()
+            };
+            reconfigure(sources, live, conn)
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        let message = format!("Ignoring the project config file: {:#}", e);
+        warn!("{}", message);
+        show_message(conn, MessageType::WARNING, message);
+    }
+}
+
+/// Applies the settings of `sources` to commands started from now on and
+/// tells the user what changed. Settings in [`RESTART_SETTINGS`] are stored
+/// but take effect after a restart. Nothing changes if the merged settings
+/// are invalid.
+fn reconfigure(sources: ConfigSources, live: &mut LiveConfig, conn: &Connection) -> Result<()> {
+    let config = sources.config()?;
+    live.sources = sources;
+    let Some(previous) = live.config.update(config.clone()) else {
+        return Ok(());
+    };
+
+    let mut changes = Vec::new();
+    if let Some(previous) = live.output.update(OutputLocation::from_config(&config)) {
+        let current = live.output.current();
+        if current.dir != previous.dir {
+            changes.push(format!(
+                "Output now goes to {}; earlier output stays in {}",
+                current.dir.display(),
+                previous.dir.display()
+            ));
+        }
+        if current.no_chunk != previous.no_chunk {
+            changes.push(format!(
+                "Mermaid chunking turned {}",
+                if current.no_chunk { "off" } else { "on" }
+            ));
+        }
+        if current.chunk_dir() != previous.chunk_dir() && current.dir == previous.dir {
+            changes.push(format!(
+                "Mermaid chunks now go to {}",
+                current.chunk_dir().display()
+            ));
+        }
+    }
+    if config.rules != previous.rules || config.files != previous.files {
+        let _ = live
+            .diagnostics_tx
+            .send(DiagnosticsEvent::ConfigChanged(config.clone()));
+    }
+    if config.rules != previous.rules {
+        changes.push("Audit rule settings updated".to_string());
+    }
+    if config.files != previous.files {
+        changes.push("Include and exclude patterns updated".to_string());
+    }
+    if config.telemetry_enabled() != previous.telemetry_enabled() {
+        changes.push(format!(
+            "Telemetry turned {}",
+            if config.telemetry_enabled() {
+                "on"
+            } else {
+                "off"
+            }
+        ));
+    }
+    let (current, previous) = (
+        serde_json::to_value(&config).unwrap_or_default(),
+        serde_json::to_value(&previous).unwrap_or_default(),
+    );
+    let restart: Vec<&str> = RESTART_SETTINGS
+        .iter()
+        .copied()
+        .filter(|name| current.get(name) != previous.get(name))
+        .collect();
+    if !restart.is_empty() {
+        changes.push(format!(
+            "{} take effect after a restart",
+            restart.join(", ")
+        ));
+    }
+    if !changes.is_empty() {
+        let message = changes.join(". ");
+        info!("{}", message);
+        show_message(conn, MessageType::INFO, message);
+    }
+    Ok(())
+}
+
+fn show_message(conn: &Connection, typ: MessageType, message: String) {
+    let params = ShowMessageParams { typ, message };
+    let notification = Notification::new(ShowMessage::METHOD.to_string(), params);
+    let _ = conn.sender.send(notification.into());
+}
+
+/// The project config file of `root`, or `null` when there is none or it
+/// cannot be read.
+fn load_project_config(root: Option<&std::path::Path>) -> serde_json::Value {
+    match root.map(project_config::load).transpose() {
+        Ok(Some(Some((path, settings)))) => {
+            info!("Loaded settings from {}", path.display());
+            settings
+        }
+        Ok(_) => serde_json::Value::Null,
+        Err(e) => {
+            warn!("Ignoring the project config file: {:#}", e);
+            serde_json::Value::Null
+        }
+    }
+}
+
+/// The server config: `initializationOptions` over the project config
+/// file, falling back to the defaults when they are malformed.
+fn load_config(sources: &ConfigSources) -> Config {
+    let config = sources.config().unwrap_or_else(|e| {
+        warn!("Ignoring invalid settings: {}", e);
+        Config::default()
+    });
+    for rule in config.rules.keys() {
+        if !analysis::RULES.iter().any(|r| r.id == rule) {
+            warn!("Unknown rule `{}` in config", rule);
+        }
+    }
+    config
+}
+
+fn process_notification(
+    not: Notification,
+    diagnostics_tx: &mpsc::Sender<DiagnosticsEvent>,
+    index: &SymbolIndex,
+    documents: &DocumentStore,
+) {
+    let event = match not.method.as_str() {
+        DidOpenTextDocument::METHOD => not
+            .extract::<DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+            .ok()
+            .map(|params| {
+                let document = params.text_document;
+                documents.update(
+                    document.uri.clone(),
+                    document.version,
+                    document.text.clone(),
+                );
+                DiagnosticsEvent::Changed {
+                    uri: document.uri,
+                    version: document.version,
+                    text: document.text,
+                }
+            }),
+        DidChangeTextDocument::METHOD => not
+            .extract::<DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)
+            .ok()
+            .and_then(|params| {
+                let uri = params.text_document.uri;
+                let version = params.text_document.version;
+                match documents.apply_changes(&uri, version, params.content_changes) {
+                    Ok(text) => text.map(|text| DiagnosticsEvent::Changed { uri, version, text }),
+                    Err(e) => {
+                        warn!("Ignoring change: {}", e);
+                        None
+                    }
+                }
+            }),
+        DidSaveTextDocument::METHOD => not
+            .extract::<DidSaveTextDocumentParams>(DidSaveTextDocument::METHOD)
+            .ok()
+            .map(|params| DiagnosticsEvent::Saved {
+                uri: params.text_document.uri,
+            }),
+        DidCloseTextDocument::METHOD => not
+            .extract::<DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+            .ok()
+            .map(|params| DiagnosticsEvent::Closed {
+                uri: params.text_document.uri,
+            }),
+        _ => None,
+    };
+
+    if let Some(event) = event {
+        // Record the text right away so lookups can tell the index is behind.
+        match &event {
+            DiagnosticsEvent::Changed { uri, version, text } => {
+                index.update(uri.clone(), Some(*version), text.clone());
+            }
+            DiagnosticsEvent::Closed { uri } => {
+                documents.close(uri);
+                index.close(uri);
+            }
+            _ => {}
+        }
+        let _ = diagnostics_tx.send(event);
+    }
+}
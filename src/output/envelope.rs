@@ -0,0 +1,159 @@
+//! The response shape shared by every workspace command.
+//!
+//! Workers answer with a JSON payload of their own. The payload stays
+//! under `data`, and the envelope around it lists what clients otherwise
+//! dig out per command: the artifacts produced, with their formats, the
+//! findings, the files that were skipped and where the time went. Workers
+//! record each artifact with its format as they produce it, and send them
+//! along in their [`RunInfo`].
+
+use crate::watchdog::PhaseTiming;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Key under which workers attach [`RunInfo`] to their payload; the
+/// envelope moves it out of `data`.
+pub const RUN_KEY: &str = "run";
+
+/// How a worker's run went, besides its result.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunInfo {
+    pub timings: Vec<PhaseTiming>,
+    /// Files that could not be read and were left out.
+    pub skipped_files: Vec<PathBuf>,
+    /// What the request produced, in the order the envelope lists it.
+    pub artifacts: Vec<Artifact>,
+}
+
+impl RunInfo {
+    /// `payload` with this run attached under [`RUN_KEY`]; payloads that
+    /// are not JSON objects are returned as they are.
+    pub fn attach(&self, payload: String) -> String {
+        let Ok(Value::Object(mut object)) = serde_json::from_str::<Value>(&payload) else {
+            return payload;
+        };
+        match serde_json::to_value(self) {
+            Ok(run) => {
+                object.insert(RUN_KEY.to_string(), run);
+                Value::Object(object).to_string()
+            }
+            Err(_) => payload,
+        }
+    }
+}
+
+/// What an artifact is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactFormat {
+    Dot,
+    Mermaid,
+    PlantUml,
+    Json,
+    Cypher,
+    Csv,
+    Markdown,
+    Html,
+    Excalidraw,
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Artifact {
+    /// Payload key the artifact is under, e.g. `dot` or `chunk_dir`.
+    pub name: String,
+    pub format: ArtifactFormat,
+    /// Text of an artifact returned inline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Path of an artifact written to disk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl Artifact {
+    /// Text returned in the payload under `name`.
+    pub fn inline(name: &str, format: ArtifactFormat, content: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            format,
+            content: Some(content.into()),
+            path: None,
+        }
+    }
+
+    /// A file or directory written to disk, whose path is under `name`.
+    pub fn file(name: &str, format: ArtifactFormat, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.to_string(),
+            format,
+            content: None,
+            path: Some(path.into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnalysisResult {
+    /// Command that produced the result.
+    pub kind: String,
+    /// Format of the first artifact; `json` when the result is only data.
+    pub format: ArtifactFormat,
+    pub artifacts: Vec<Artifact>,
+    pub findings: Vec<Value>,
+    pub skipped_files: Vec<PathBuf>,
+    pub timings: Vec<PhaseTiming>,
+    /// The command's own payload.
+    pub data: Value,
+}
+
+impl AnalysisResult {
+    /// The envelope of `data`, the payload `kind` answered with. Payloads
+    /// that are not JSON carry no [`RunInfo`]; they are one `text` artifact.
+    pub fn new(kind: &str, data: Value) -> Self {
+        match data {
+            Value::String(text) => Self::text(kind, text, ArtifactFormat::Text),
+            data => Self::json(kind, data),
+        }
+    }
+
+    /// The envelope of `text`, a payload in `format` that is not JSON.
+    pub fn text(kind: &str, text: String, format: ArtifactFormat) -> Self {
+        let run = RunInfo {
+            artifacts: vec![Artifact::inline("report", format, text.as_str())],
+            ..RunInfo::default()
+        };
+        Self::build(kind, run, Value::String(text))
+    }
+
+    fn json(kind: &str, mut data: Value) -> Self {
+        let run = data
+            .as_object_mut()
+            .and_then(|object| object.remove(RUN_KEY))
+            .and_then(|run| serde_json::from_value::<RunInfo>(run).ok())
+            .unwrap_or_default();
+        Self::build(kind, run, data)
+    }
+
+    fn build(kind: &str, run: RunInfo, data: Value) -> Self {
+        let findings = ["findings", "issues"]
+            .iter()
+            .filter_map(|key| data.get(key)?.as_array())
+            .flatten()
+            .cloned()
+            .collect();
+        Self {
+            kind: kind.to_string(),
+            format: run
+                .artifacts
+                .first()
+                .map_or(ArtifactFormat::Json, |a| a.format),
+            artifacts: run.artifacts,
+            findings,
+            skipped_files: run.skipped_files,
+            timings: run.timings,
+            data,
+        }
+    }
+}
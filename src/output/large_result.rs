@@ -0,0 +1,57 @@
+//! Results too large to send over JSON-RPC.
+//!
+//! Some clients fail on multi-megabyte messages, and a whole-workspace
+//! diagram can easily reach that. A result past the configured limit is
+//! written to a file under the output directory, and the command answers
+//! with the file's URI and the start of its content instead.
+
+use super::manifest::to_hex;
+use super::write_atomic;
+use anyhow::{anyhow, Result};
+use lsp_types::Url;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Limit used when `max_response_bytes` is not configured.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
+/// Where large results go, under the output directory.
+pub const RESULTS_DIR: &str = "results";
+/// Characters of the result kept in the response.
+pub const PREVIEW_CHARS: usize = 2000;
+
+/// Stands in for a result written to disk.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultFile {
+    pub uri: Url,
+    pub path: PathBuf,
+    /// Size of the full result.
+    pub bytes: usize,
+    /// The first [`PREVIEW_CHARS`] characters of the result.
+    pub preview: String,
+}
+
+/// Writes `result` to `dir`, in a file named after `kind` and its content.
+pub fn write(result: &str, dir: &Path, kind: &str) -> Result<ResultFile> {
+    let extension = if serde_json::from_str::<serde_json::Value>(result).is_ok() {
+        "json"
+    } else {
+        "txt"
+    };
+    let slug: String = kind
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let hash = to_hex(&Sha256::digest(result.as_bytes()));
+    let path = dir.join(format!("{}-{}.{}", slug, &hash[..12], extension));
+    write_atomic(&path, result)?;
+    let path = path.canonicalize()?;
+    let uri = Url::from_file_path(&path)
+        .map_err(|_| anyhow!("Cannot name {} as a URI", path.display()))?;
+    Ok(ResultFile {
+        uri,
+        path,
+        bytes: result.len(),
+        preview: result.chars().take(PREVIEW_CHARS).collect(),
+    })
+}
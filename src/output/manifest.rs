@@ -0,0 +1,109 @@
+//! `manifest.json` describing the files a command wrote.
+//!
+//! Lists every artifact with its size and SHA-256 next to the generator
+//! version and a hash of the Solidity inputs, so pipelines can verify the
+//! files and tell when they were generated from different sources.
+
+use super::write_atomic;
+use crate::analysis::model::SourceUnit;
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+const GENERATOR: &str = env!("CARGO_PKG_NAME");
+const GENERATOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    pub generator: String,
+    pub generator_version: String,
+    /// SHA-256 over the input sources; see [`input_hash`].
+    pub input_hash: String,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestEntry {
+    /// Relative to the manifest's directory, `/`-separated.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Describes `files`, which must live under `dir`.
+    pub fn build(dir: &Path, files: &[PathBuf], input_hash: &str) -> Result<Self> {
+        let mut entries = Vec::new();
+        for file in files {
+            let content = std::fs::read(file)?;
+            entries.push(ManifestEntry {
+                path: relative_path(file, dir),
+                size: content.len() as u64,
+                sha256: to_hex(&Sha256::digest(&content)),
+            });
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(Self {
+            generator: GENERATOR.to_string(),
+            generator_version: GENERATOR_VERSION.to_string(),
+            input_hash: input_hash.to_string(),
+            files: entries,
+        })
+    }
+
+    /// Writes `<dir>/manifest.json` and returns its path.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        let path = dir.join(MANIFEST_FILE);
+        write_atomic(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+}
+
+/// Regular files directly in `dir`, other than a previous manifest.
+pub fn list_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() && entry.file_name() != MANIFEST_FILE {
+            files.push(entry.path());
+        }
+    }
+    Ok(files)
+}
+
+/// Hash of the sources in path order, each as its path relative to
+/// `workspace_folder`, a NUL, its length and its content, so moving text
+/// between files changes the hash but moving the checkout does not.
+pub fn input_hash(sources: &[SourceUnit], workspace_folder: &Path) -> String {
+    let mut sorted: Vec<(String, &SourceUnit)> = sources
+        .iter()
+        .map(|unit| (relative_path(&unit.path, workspace_folder), unit))
+        .collect();
+    sorted.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut hasher = Sha256::new();
+    for (path, unit) in sorted {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update((unit.content.len() as u64).to_be_bytes());
+        hasher.update(unit.content.as_bytes());
+    }
+    to_hex(&hasher.finalize())
+}
+
+/// `path` relative to `base`, `/`-separated on every platform.
+fn relative_path(path: &Path, base: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Lowercase hex of `bytes`, as checksums are written.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,173 @@
+//! Writing generated artifacts.
+//!
+//! Single files are written to a temporary sibling and renamed into place.
+//! Directories of related files, like diagram chunks, are assembled in a
+//! staging directory and swapped in whole, so a crash mid-generation never
+//! leaves a half-written file or a mix of two runs behind.
+
+pub mod envelope;
+pub mod large_result;
+pub mod manifest;
+pub mod preview;
+pub mod retention;
+pub mod settings;
+pub mod template;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+static PATH_LOCKS: Lazy<DashMap<PathBuf, Arc<Mutex<()>>>> = Lazy::new(DashMap::new);
+
+/// The process-wide lock of `path`, held by threads that read, change and
+/// write back what is there. Paths are compared as given.
+pub fn path_lock(path: &Path) -> Arc<Mutex<()>> {
+    PATH_LOCKS.entry(path.to_path_buf()).or_default().clone()
+}
+
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    fs::create_dir_all(parent_dir(path))?;
+    let temp = temp_sibling(path, "tmp");
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_ref())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    Ok(result?)
+}
+
+/// A directory filled by one run and swapped over its target by
+/// [`StagingDir::commit`]. Dropping it uncommitted deletes what was written.
+pub struct StagingDir {
+    path: PathBuf,
+    target: PathBuf,
+    committed: bool,
+}
+
+impl StagingDir {
+    pub fn new(target: &Path) -> Result<Self> {
+        let parent = parent_dir(target);
+        fs::create_dir_all(&parent)?;
+        remove_leftovers(target);
+        let path = temp_sibling(target, "staging");
+        fs::create_dir(&path)?;
+        Ok(Self {
+            path,
+            target: target.to_path_buf(),
+            committed: false,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Replaces the target directory, and everything in it, with the staged
+    /// one and returns the target path.
+    pub fn commit(mut self) -> Result<PathBuf> {
+        let previous = temp_sibling(&self.target, "old");
+        let had_previous = match fs::rename(&self.target, &previous) {
+            Ok(()) => true,
+            Err(e) if e.kind() == ErrorKind::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+        if let Err(e) = fs::rename(&self.path, &self.target) {
+            if had_previous {
+                let _ = fs::rename(&previous, &self.target);
+            }
+            return Err(e.into());
+        }
+        self.committed = true;
+        if had_previous {
+            let _ = fs::remove_dir_all(&previous);
+        }
+        Ok(self.target.clone())
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .to_path_buf()
+}
+
+/// `.<name>.<tag>-<pid>-<n>` next to `path`.
+fn temp_sibling(path: &Path, tag: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    parent_dir(path).join(format!(
+        ".{}.{}-{}-{}",
+        name,
+        tag,
+        std::process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Leftovers of a process that cannot be told dead are kept this long, in
+/// case it is still writing them.
+const LEFTOVER_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Removes staging and swapped-out directories left by interrupted runs of
+/// other processes: those of processes no longer running, and any older
+/// than [`LEFTOVER_AGE`]. Another server writing to the same output
+/// directory keeps its own.
+fn remove_leftovers(target: &Path) {
+    let Some(name) = target.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(parent_dir(target)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        let pid = ["staging", "old"].iter().find_map(|tag| {
+            let rest = file_name.strip_prefix(&format!(".{}.{}-", name, tag))?;
+            rest.split('-').next()?.parse::<u32>().ok()
+        });
+        let Some(pid) = pid.filter(|pid| *pid != std::process::id()) else {
+            continue;
+        };
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > LEFTOVER_AGE);
+        if stale || process_running(pid) == Some(false) {
+            let _ = fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+/// Whether process `pid` is running, where that can be told.
+fn process_running(pid: u32) -> Option<bool> {
+    if cfg!(target_os = "linux") {
+        Some(Path::new("/proc").join(pid.to_string()).exists())
+    } else {
+        None
+    }
+}
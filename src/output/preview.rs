@@ -0,0 +1,41 @@
+//! Short previews sent alongside generated artifacts.
+//!
+//! Clients can show a preview while they load the full content or open the
+//! written files.
+
+use std::collections::BTreeMap;
+use traverse_graph::cg::{CallGraph, NodeType};
+
+/// Lines of an artifact kept in its preview.
+pub const PREVIEW_LINES: usize = 50;
+
+/// The first [`PREVIEW_LINES`] lines of `content`.
+pub fn head(content: &str) -> String {
+    content
+        .lines()
+        .take(PREVIEW_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// One line per contract of `graph` with its number of functions and
+/// modifiers, for artifacts written as many files.
+pub fn contracts(graph: &CallGraph) -> String {
+    let mut functions: BTreeMap<&str, usize> = BTreeMap::new();
+    for node in &graph.nodes {
+        if !matches!(node.node_type, NodeType::Function | NodeType::Modifier) {
+            continue;
+        }
+        if let Some(contract) = node.contract_name.as_deref() {
+            *functions.entry(contract).or_default() += 1;
+        }
+    }
+    let summary: Vec<String> = functions
+        .iter()
+        .map(|(contract, count)| match count {
+            1 => format!("{}: 1 function", contract),
+            _ => format!("{}: {} functions", contract, count),
+        })
+        .collect();
+    head(&summary.join("\n"))
+}
@@ -0,0 +1,158 @@
+//! Limits on how much generated output is kept.
+//!
+//! Each chunked diagram scope, export and oversized result leaves its own
+//! files under the output directory, and nothing removes them. A run is one
+//! of these: a file under `results/`, or a directory with a
+//! `manifest.json`, such as a chunk directory or an Obsidian vault. Runs are
+//! ordered by when they were last written, and the oldest are removed once
+//! there are more than `keep_runs` of them or they take more than
+//! `max_output_mb`. The newest run is always kept, and hidden entries, which
+//! include the staging directories of commands still running, are never
+//! touched.
+
+use super::large_result::RESULTS_DIR;
+use super::manifest::MANIFEST_FILE;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Runs kept; unlimited when unset.
+    pub keep_runs: Option<usize>,
+    /// Total size of the runs kept in MiB; unlimited when unset.
+    pub max_output_mb: Option<u64>,
+}
+
+impl Retention {
+    pub fn is_unlimited(&self) -> bool {
+        self.keep_runs.is_none() && self.max_output_mb.is_none()
+    }
+
+    /// `self`, with the limits it leaves unset taken from `defaults`.
+    pub fn or(self, defaults: Retention) -> Self {
+        Self {
+            keep_runs: self.keep_runs.or(defaults.keep_runs),
+            max_output_mb: self.max_output_mb.or(defaults.max_output_mb),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Cleanup {
+    /// Removed runs, oldest last.
+    pub removed: Vec<PathBuf>,
+    pub freed_bytes: u64,
+    /// Runs left in place.
+    pub kept: usize,
+    pub kept_bytes: u64,
+}
+
+struct Run {
+    path: PathBuf,
+    bytes: u64,
+    written: SystemTime,
+}
+
+/// Removes the runs under `dir` that `retention` does not keep.
+pub fn clean(dir: &Path, retention: &Retention) -> Result<Cleanup> {
+    let mut runs = Vec::new();
+    if dir.is_dir() {
+        collect_runs(dir, &mut runs)?;
+    }
+    runs.sort_by(|a, b| b.written.cmp(&a.written).then_with(|| a.path.cmp(&b.path)));
+
+    let max_bytes = retention
+        .max_output_mb
+        .map_or(u64::MAX, |mb| mb.saturating_mul(1024 * 1024));
+    let mut keep = runs
+        .len()
+        .min(retention.keep_runs.unwrap_or(usize::MAX).max(1));
+    let mut total = 0;
+    for (index, run) in runs.iter().enumerate().take(keep) {
+        total += run.bytes;
+        if index > 0 && total > max_bytes {
+            keep = index;
+            break;
+        }
+    }
+
+    let mut cleanup = Cleanup {
+        kept: keep,
+        kept_bytes: runs[..keep].iter().map(|run| run.bytes).sum(),
+        ..Cleanup::default()
+    };
+    for run in &runs[keep..] {
+        let removed = if run.path.is_dir() {
+            fs::remove_dir_all(&run.path)
+        } else {
+            fs::remove_file(&run.path)
+        };
+        match removed {
+            Ok(()) => {
+                cleanup.freed_bytes += run.bytes;
+                cleanup.removed.push(run.path.clone());
+                remove_empty_parents(&run.path, dir);
+            }
+            // Removed by another cleanup in the meantime.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(cleanup)
+}
+
+fn collect_runs(dir: &Path, runs: &mut Vec<Run>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if !metadata.is_dir() {
+            if dir.file_name().is_some_and(|name| name == RESULTS_DIR) {
+                runs.push(Run {
+                    path,
+                    bytes: metadata.len(),
+                    written: metadata.modified()?,
+                });
+            }
+            continue;
+        }
+        let manifest = path.join(MANIFEST_FILE);
+        match fs::metadata(&manifest) {
+            Ok(manifest) => runs.push(Run {
+                bytes: size(&path)?,
+                written: manifest.modified()?,
+                path,
+            }),
+            Err(_) => collect_runs(&path, runs)?,
+        }
+    }
+    Ok(())
+}
+
+fn size(path: &Path) -> Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        total += size(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+/// Removes the directories between `path` and `root` that are left empty.
+fn remove_empty_parents(path: &Path, root: &Path) {
+    for parent in path.ancestors().skip(1) {
+        if parent == root || !parent.starts_with(root) || fs::remove_dir(parent).is_err() {
+            break;
+        }
+    }
+}
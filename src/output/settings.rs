@@ -0,0 +1,81 @@
+//! Output locations that can change while the server runs.
+//!
+//! The client may move the output directory or turn chunking off with
+//! `workspace/didChangeConfiguration`. Workers read the settings when a
+//! command starts, so a running command finishes where it began and every
+//! later command writes to the new location. Earlier output is left where
+//! it is.
+
+use crate::config::Config;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Root of generated files unless `output_dir` is configured.
+pub const DEFAULT_OUTPUT_DIR: &str = "./traverse-output";
+/// Chunked sequence diagrams, under the output directory.
+pub const CHUNK_DIR: &str = "sequence-diagrams/chunks";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLocation {
+    pub dir: PathBuf,
+    /// Forces single-file Mermaid output, whatever the command asks for.
+    pub no_chunk: bool,
+    /// Chunked sequence diagrams, relative to `dir` unless absolute;
+    /// [`CHUNK_DIR`] when unset.
+    pub chunk_dir: Option<PathBuf>,
+}
+
+impl OutputLocation {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            dir: config
+                .output_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT_DIR)),
+            no_chunk: config.no_chunk == Some(true),
+            chunk_dir: config.chunk_dir.clone(),
+        }
+    }
+
+    pub fn chunk_dir(&self) -> PathBuf {
+        self.dir
+            .join(self.chunk_dir.as_deref().unwrap_or(Path::new(CHUNK_DIR)))
+    }
+}
+
+impl Default for OutputLocation {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// The current [`OutputLocation`], shared by the main loop and the workers.
+#[derive(Debug, Default)]
+pub struct OutputSettings {
+    current: RwLock<OutputLocation>,
+}
+
+impl OutputSettings {
+    pub fn new(location: OutputLocation) -> Self {
+        Self {
+            current: RwLock::new(location),
+        }
+    }
+
+    pub fn current(&self) -> OutputLocation {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Applies `location` to commands started from now on and returns the
+    /// previous location if it changed.
+    pub fn update(&self, location: OutputLocation) -> Option<OutputLocation> {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        if *current == location {
+            return None;
+        }
+        Some(std::mem::replace(&mut current, location))
+    }
+}
@@ -0,0 +1,45 @@
+//! Report templates in Handlebars.
+//!
+//! Templates are rendered by the `handlebars` crate, so everything it
+//! supports works: `each`, `if`, `unless`, `with`, `lookup`, inline
+//! partials, `@index`, `@first`, `@last`, `../` and comments. `{{path}}`
+//! escapes HTML in HTML templates only; Markdown is written as is.
+
+use anyhow::{anyhow, Result};
+use handlebars::Handlebars;
+use serde_json::Value;
+use std::path::Path;
+
+const NAME: &str = "report";
+
+/// Whether the template at `path` is HTML, by its `.html` or `.htm` name.
+pub fn is_html(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "html" || ext == "htm")
+}
+
+/// A parsed template.
+#[derive(Debug, Clone)]
+pub struct Template {
+    registry: Handlebars<'static>,
+}
+
+impl Template {
+    /// Parses `source`; `html` turns on escaping of `{{path}}`.
+    pub fn parse(source: &str, html: bool) -> Result<Self> {
+        let mut registry = Handlebars::new();
+        if !html {
+            registry.register_escape_fn(handlebars::no_escape);
+        }
+        registry
+            .register_template_string(NAME, source)
+            .map_err(|e| anyhow!("{}", e))?;
+        Ok(Self { registry })
+    }
+
+    pub fn render(&self, data: &Value) -> Result<String> {
+        self.registry
+            .render(NAME, data)
+            .map_err(|e| anyhow!("{}", e))
+    }
+}
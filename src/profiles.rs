@@ -0,0 +1,148 @@
+//! Per-directory Solidity versions for repositories mixing old and new code.
+//!
+//! A profile assigns a compiler version to the files under some paths, e.g.
+//! `0.4` to `legacy/`. Files without one are read as their own
+//! `pragma solidity` says. The version decides the [`Dialect`]: before 0.5 a
+//! function named after its contract is the constructor, and before 0.7
+//! `now` is `block.timestamp`.
+//!
+//! Function-named constructors are rewritten to `constructor` when sources
+//! are read, padded to the same length so every span still points at the
+//! original text; the call graph and the source model then see an ordinary
+//! constructor.
+
+use crate::analysis::model::SourceUnit;
+use crate::file_filter;
+use crate::traverse_adapter::frontend_for;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use traverse_graph::parser::{get_node_text, parse_solidity};
+use tree_sitter::Node as TsNode;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Profile {
+    /// Directories or files the profile applies to, as glob patterns
+    /// matched at any depth, e.g. `legacy` or `contracts/v1/**`.
+    pub paths: Vec<String>,
+    /// Compiler version the files are written for, e.g. `0.4` or `0.4.24`.
+    pub solidity: String,
+}
+
+/// Version-dependent meaning of source constructs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    /// A function named after its contract is the constructor.
+    pub named_constructors: bool,
+    /// `now` reads the block timestamp.
+    pub now_is_timestamp: bool,
+}
+
+impl Default for Dialect {
+    /// For files of unknown version: `now` is taken for the timestamp,
+    /// since a variable of that name is rare, and constructors are only
+    /// recognized by keyword.
+    fn default() -> Self {
+        Self {
+            named_constructors: false,
+            now_is_timestamp: true,
+        }
+    }
+}
+
+impl Dialect {
+    /// The dialect of `version`, read from its first `major.minor`, so
+    /// `0.4`, `^0.4.24` and `>=0.4.22 <0.6.0` all read as 0.4.
+    pub fn for_version(version: &str) -> Option<Self> {
+        let start = version.find(|c: char| c.is_ascii_digit())?;
+        let mut numbers = version[start..]
+            .split(|c: char| !c.is_ascii_digit())
+            .map(|n| n.parse::<u32>().ok());
+        let (Some(Some(major)), Some(Some(minor))) = (numbers.next(), numbers.next()) else {
+            return None;
+        };
+        let before = |m: u32| major == 0 && minor < m;
+        Some(Self {
+            named_constructors: before(5),
+            now_is_timestamp: before(7),
+        })
+    }
+}
+
+/// The dialect of the file at `path`: that of the first profile matching
+/// it, else that of its pragma, else the default.
+pub fn dialect(profiles: &[Profile], path: &Path, content: &str) -> Dialect {
+    let profile = profiles.iter().find(|profile| {
+        profile
+            .paths
+            .iter()
+            .any(|pattern| file_filter::matches_at_any_depth(pattern, path))
+    });
+    profile
+        .and_then(|profile| Dialect::for_version(&profile.solidity))
+        .or_else(|| pragma_version(content).and_then(Dialect::for_version))
+        .unwrap_or_default()
+}
+
+/// The version constraint of the first `pragma solidity`.
+fn pragma_version(content: &str) -> Option<&str> {
+    let start = content.find("pragma solidity")? + "pragma solidity".len();
+    let end = content[start..].find(';')?;
+    Some(&content[start..start + end])
+}
+
+/// `unit` as its dialect reads it: in files with function-named
+/// constructors, `function Name` becomes `constructor` padded with spaces.
+/// A one-letter contract name leaves no room and is kept as a function, as
+/// is one split across lines.
+pub fn apply(profiles: &[Profile], unit: &mut SourceUnit) {
+    if frontend_for(&unit.path).is_some()
+        || !dialect(profiles, &unit.path, &unit.content).named_constructors
+    {
+        return;
+    }
+    let Ok(ast) = parse_solidity(&unit.content) else {
+        return;
+    };
+    let mut ranges = Vec::new();
+    named_constructors(ast.tree.root_node(), &unit.content, &mut ranges);
+    for (start, end) in ranges {
+        let replacement = format!("{:<width$}", "constructor", width = end - start);
+        unit.content.replace_range(start..end, &replacement);
+    }
+}
+
+/// Byte ranges from `function` to the end of the name of each function
+/// named after its contract, long enough to hold `constructor`.
+fn named_constructors(root: TsNode, source: &str, ranges: &mut Vec<(usize, usize)>) {
+    let mut cursor = root.walk();
+    for declaration in root.named_children(&mut cursor) {
+        if declaration.kind() != "contract_declaration" {
+            continue;
+        }
+        let (Some(name), Some(body)) = (
+            declaration.child_by_field_name("name"),
+            declaration.child_by_field_name("body"),
+        ) else {
+            continue;
+        };
+        let contract = get_node_text(&name, source);
+        let mut body_cursor = body.walk();
+        for member in body.named_children(&mut body_cursor) {
+            if member.kind() != "function_definition" {
+                continue;
+            }
+            let Some(function) = member.child_by_field_name("name") else {
+                continue;
+            };
+            let range = (member.start_byte(), function.end_byte());
+            // Removing a line break would move the lines below.
+            if get_node_text(&function, source) == contract
+                && range.1 - range.0 >= "constructor".len()
+                && !source[range.0..range.1].contains('\n')
+            {
+                ranges.push(range);
+            }
+        }
+    }
+}
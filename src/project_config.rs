@@ -0,0 +1,83 @@
+//! Settings committed alongside the contracts.
+//!
+//! A `traverse.toml` or `.traverse.yml` at the workspace root holds the
+//! same keys as `initializationOptions`. The file is the base layer; keys
+//! the client sends in `initializationOptions` or
+//! `workspace/didChangeConfiguration` override it, so a developer can
+//! change a setting locally without editing the shared file.
+
+use crate::config::Config;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Looked up in this order; the first that exists is used.
+pub const PROJECT_CONFIG_FILES: [&str; 2] = ["traverse.toml", ".traverse.yml"];
+
+/// Whether `path` is named like a project config file.
+pub fn is_project_config(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| PROJECT_CONFIG_FILES.contains(&name))
+}
+
+/// The project config file under `root` and its settings, or `None` when
+/// there is no such file.
+pub fn load(root: &Path) -> Result<Option<(PathBuf, Value)>> {
+    let Some(path) = PROJECT_CONFIG_FILES
+        .iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file())
+    else {
+        return Ok(None);
+    };
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let settings = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str::<Value>(&text).with_context(|| format!("Invalid {}", path.display()))?
+    } else {
+        serde_yaml::from_str::<Value>(&text)
+            .with_context(|| format!("Invalid {}", path.display()))?
+    };
+    Ok(Some((path, settings)))
+}
+
+/// `overlay` laid over `base`: objects are merged key by key, anything
+/// else in `overlay` replaces what `base` has. `null` leaves `base` as is.
+pub fn merge(base: Value, overlay: Value) -> Value {
+    match (base, overlay) {
+        (base, Value::Null) => base,
+        (Value::Object(mut base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(previous) => merge(previous, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+/// The settings each source last provided.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigSources {
+    /// From the project config file.
+    pub project: Value,
+    /// From `initializationOptions` or the latest configuration change.
+    pub client: Value,
+}
+
+impl ConfigSources {
+    /// The client settings laid over the project settings.
+    pub fn config(&self) -> Result<Config> {
+        let settings = merge(self.project.clone(), self.client.clone());
+        if settings.is_null() {
+            return Ok(Config::default());
+        }
+        Ok(serde_json::from_value(settings)?)
+    }
+}
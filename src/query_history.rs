@@ -0,0 +1,77 @@
+//! Per-workspace history of custom query commands.
+//!
+//! Kept on disk under the workspace so client UIs can offer re-running or
+//! editing previous queries across editor sessions.
+
+use crate::output::{path_lock, write_atomic};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+const HISTORY_FILE: &str = ".traverse/query-history.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryHistoryEntry {
+    pub command: String,
+    pub arguments: Vec<serde_json::Value>,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryHistory {
+    pub entries: VecDeque<QueryHistoryEntry>,
+}
+
+impl QueryHistory {
+    pub fn path(workspace_folder: &Path) -> PathBuf {
+        workspace_folder.join(HISTORY_FILE)
+    }
+
+    /// Loads the history, treating a missing or unreadable file as empty.
+    pub fn load(workspace_folder: &Path) -> Self {
+        std::fs::read_to_string(Self::path(workspace_folder))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, workspace_folder: &Path) -> Result<()> {
+        write_atomic(
+            &Self::path(workspace_folder),
+            serde_json::to_string_pretty(self)?,
+        )
+    }
+
+    /// Appends a query, newest last, keeping at most `limit` entries.
+    pub fn push(&mut self, command: &str, arguments: Vec<serde_json::Value>, limit: usize) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.entries.push_back(QueryHistoryEntry {
+            command: command.to_string(),
+            arguments,
+            timestamp,
+        });
+        while self.entries.len() > limit {
+            self.entries.pop_front();
+        }
+    }
+}
+
+/// Appends a query to the workspace's history. Commands run on threads of
+/// their own, so recording is serialized per history file.
+pub fn record(
+    workspace_folder: &Path,
+    command: &str,
+    arguments: Vec<serde_json::Value>,
+) -> Result<()> {
+    let lock = path_lock(&QueryHistory::path(workspace_folder));
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let mut history = QueryHistory::load(workspace_folder);
+    history.push(command, arguments, DEFAULT_HISTORY_LIMIT);
+    history.save(workspace_folder)
+}
@@ -0,0 +1,119 @@
+//! Graphviz DOT call graphs.
+
+use super::{DiagramRenderer, RenderOptions};
+use crate::config::DotConfig;
+use crate::output::envelope::ArtifactFormat;
+use anyhow::Result;
+use std::collections::HashMap;
+use traverse_graph::cg::{CallGraph, EdgeType};
+use traverse_graph::cg_dot::{CgToDot, DotExportConfig};
+
+pub struct DotRenderer;
+
+impl DiagramRenderer for DotRenderer {
+    fn name(&self) -> &'static str {
+        "dot"
+    }
+
+    fn extension(&self) -> &'static str {
+        "dot"
+    }
+
+    fn format(&self) -> ArtifactFormat {
+        ArtifactFormat::Dot
+    }
+
+    fn render(&self, graph: &CallGraph, options: &RenderOptions) -> Result<String> {
+        Ok(render(graph, &options.dot))
+    }
+}
+
+/// DOT of `graph`, with repeated edges collapsed into one labelled with
+/// their count and the configured graph attributes applied.
+pub fn render(graph: &CallGraph, config: &DotConfig) -> String {
+    let export_config = DotExportConfig {
+        exclude_isolated_nodes: config.exclude_isolated_nodes,
+    };
+    let (collapsed, counts) = collapse_repeated_edges(graph);
+    let dot = collapsed.to_dot("call_graph", &export_config);
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut edge_index = 0;
+    for line in dot.lines() {
+        // Edge statements are emitted in `edges` order as `nA -> nB [...]`.
+        if !line.contains(" -> n") {
+            lines.push(line.to_string());
+            continue;
+        }
+        let count = counts.get(edge_index).copied().unwrap_or(1);
+        edge_index += 1;
+        lines.push(if count > 1 {
+            annotate_edge(line, count, config.scale_pen_width)
+        } else {
+            line.to_string()
+        });
+    }
+
+    // Insert right after the opening brace so these attributes follow
+    // (and override) the generator's own `graph [...]` defaults.
+    if let Some(attrs) = config.graph_attributes() {
+        let position = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with("graph ["))
+            .map_or(1, |i| i + 1)
+            .min(lines.len());
+        lines.insert(position, format!("    graph [{}];", attrs));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Merges edges of the same kind between the same pair of nodes, keeping the
+/// first occurrence, and returns how many edges each surviving one stands for.
+fn collapse_repeated_edges(graph: &CallGraph) -> (CallGraph, Vec<usize>) {
+    let mut collapsed = graph.clone();
+    collapsed.edges.clear();
+    let mut counts: Vec<usize> = Vec::new();
+    let mut seen: HashMap<(usize, usize, EdgeType, Option<&str>), usize> = HashMap::new();
+    for edge in &graph.edges {
+        let key = (
+            edge.source_node_id,
+            edge.target_node_id,
+            edge.edge_type.clone(),
+            edge.event_name.as_deref(),
+        );
+        match seen.get(&key) {
+            Some(&index) => counts[index] += 1,
+            None => {
+                seen.insert(key, collapsed.edges.len());
+                collapsed.edges.push(edge.clone());
+                counts.push(1);
+            }
+        }
+    }
+    (collapsed, counts)
+}
+
+/// Prefixes the edge label with its occurrence count and, if requested,
+/// widens the pen logarithmically.
+fn annotate_edge(line: &str, count: usize, scale_pen_width: bool) -> String {
+    let mut line = match ["[label=\"", " label=\""]
+        .iter()
+        .find_map(|marker| line.find(marker).map(|i| i + marker.len()))
+    {
+        Some(start) => {
+            let mut line = line.to_string();
+            line.insert_str(start, &format!("{}× ", count));
+            line
+        }
+        None => line.replacen(" [", &format!(" [label=\"{}×\", ", count), 1),
+    };
+    let mut extra = format!(", weight=\"{}\"", count);
+    if scale_pen_width {
+        let width = (1.0 + (count as f64).log2()).min(6.0);
+        extra.push_str(&format!(", penwidth=\"{:.1}\"", width));
+    }
+    if let Some(end) = line.rfind("];") {
+        line.insert_str(end, &extra);
+    }
+    line
+}
@@ -0,0 +1,34 @@
+//! The call graph's nodes and edges as JSON, for tools of one's own.
+
+use super::{DiagramRenderer, RenderOptions};
+use crate::output::envelope::ArtifactFormat;
+use anyhow::Result;
+use traverse_graph::cg::CallGraph;
+use traverse_graph::cg_json::{CgToJson, JsonExportConfig};
+
+pub struct JsonRenderer;
+
+impl DiagramRenderer for JsonRenderer {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn extension(&self) -> &'static str {
+        "json"
+    }
+
+    fn format(&self) -> ArtifactFormat {
+        ArtifactFormat::Json
+    }
+
+    fn render(&self, graph: &CallGraph, options: &RenderOptions) -> Result<String> {
+        // The library's metadata carries a timestamp; without it, the same
+        // graph always renders the same.
+        let config = JsonExportConfig {
+            exclude_isolated_nodes: options.dot.exclude_isolated_nodes,
+            pretty_print: true,
+            include_metadata: false,
+        };
+        Ok(graph.to_json("call_graph", &config) + "\n")
+    }
+}
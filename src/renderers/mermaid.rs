@@ -0,0 +1,35 @@
+//! Mermaid sequence diagrams of the call graph.
+
+use super::{DiagramRenderer, RenderOptions};
+use crate::output::envelope::ArtifactFormat;
+use anyhow::Result;
+use traverse_graph::cg::CallGraph;
+use traverse_graph::cg_mermaid::{MermaidGenerator, ToSequenceDiagram};
+
+pub struct MermaidRenderer;
+
+impl DiagramRenderer for MermaidRenderer {
+    fn name(&self) -> &'static str {
+        "mermaid"
+    }
+
+    fn extension(&self) -> &'static str {
+        "mmd"
+    }
+
+    fn format(&self) -> ArtifactFormat {
+        ArtifactFormat::Mermaid
+    }
+
+    /// The whole diagram, unchunked, with the configured directive.
+    fn render(&self, graph: &CallGraph, options: &RenderOptions) -> Result<String> {
+        Ok(options.mermaid.apply_directive(&sequence_diagram(graph)))
+    }
+}
+
+/// The sequence diagram of `graph`, without a directive, as chunked by
+/// `traverse_mermaid`.
+pub fn sequence_diagram(graph: &CallGraph) -> String {
+    let sequence_diagram = MermaidGenerator::new().to_sequence_diagram(graph);
+    traverse_mermaid::sequence_diagram_writer::write_diagram(&sequence_diagram)
+}
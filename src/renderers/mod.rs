@@ -0,0 +1,55 @@
+//! Diagram formats the call graph renders to.
+//!
+//! Each format is a [`DiagramRenderer`] in its own module, listed in
+//! [`RENDERERS`]; `traverse.renderDiagram` looks formats up by name, so a
+//! new format needs no changes to the worker or the command handlers.
+
+pub mod dot;
+pub mod json;
+pub mod mermaid;
+pub mod plantuml;
+
+use crate::config::{DotConfig, MermaidConfig};
+use crate::output::envelope::ArtifactFormat;
+use anyhow::Result;
+use traverse_graph::cg::CallGraph;
+
+/// Options of every renderer; each reads the ones for its format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderOptions {
+    pub dot: DotConfig,
+    pub mermaid: MermaidConfig,
+}
+
+pub trait DiagramRenderer: Sync {
+    /// Name the format is requested by.
+    fn name(&self) -> &'static str;
+
+    /// Extension of files holding the diagram, without the dot.
+    fn extension(&self) -> &'static str;
+
+    /// Format the diagram is listed with in command results.
+    fn format(&self) -> ArtifactFormat;
+
+    fn render(&self, graph: &CallGraph, options: &RenderOptions) -> Result<String>;
+}
+
+pub static RENDERERS: &[&dyn DiagramRenderer] = &[
+    &dot::DotRenderer,
+    &mermaid::MermaidRenderer,
+    &plantuml::PlantUmlRenderer,
+    &json::JsonRenderer,
+];
+
+/// The renderer named `name`.
+pub fn renderer(name: &str) -> Option<&'static dyn DiagramRenderer> {
+    RENDERERS
+        .iter()
+        .find(|renderer| renderer.name() == name)
+        .copied()
+}
+
+/// Names of every renderer, in registry order.
+pub fn renderer_names() -> Vec<&'static str> {
+    RENDERERS.iter().map(|renderer| renderer.name()).collect()
+}
@@ -0,0 +1,101 @@
+//! Where call graph spans point in the workspace files.
+//!
+//! The workspace call graph is built from each file on its own, with the
+//! spans of each file offset so they stay unique: file `i` starts one byte
+//! after file `i - 1` ends, as if the files were joined with newlines.
+//! `SourceMap` lays the files out the same way and maps spans back.
+//! Nodes are `traverse-graph`'s type, which has no room for a file, so their
+//! origins are looked up from their spans with [`SourceMap::origins`].
+
+use crate::analysis::model::SourceUnit;
+use crate::utils::{offset_at, position_at};
+use lsp_types::{Location, Position, Range, Url};
+use std::collections::BTreeMap;
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, NodeType};
+
+pub struct SourceMap<'a> {
+    sources: &'a [SourceUnit],
+    starts: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+    pub fn new(sources: &'a [SourceUnit]) -> Self {
+        let mut starts = Vec::with_capacity(sources.len());
+        let mut next = 0;
+        for unit in sources {
+            starts.push(next);
+            next += unit.content.len() + 1;
+        }
+        SourceMap { sources, starts }
+    }
+
+    /// Offset added to the spans of `sources[index]`.
+    pub fn start(&self, index: usize) -> usize {
+        self.starts[index]
+    }
+
+    /// Index of the file containing `offset`.
+    pub fn file_index(&self, offset: usize) -> Option<usize> {
+        let index = self.starts.partition_point(|&start| start <= offset);
+        let index = index.checked_sub(1)?;
+        (offset <= self.starts[index] + self.sources[index].content.len()).then_some(index)
+    }
+
+    /// The text of `sources[index]`.
+    pub fn content(&self, index: usize) -> &'a str {
+        &self.sources[index].content
+    }
+
+    /// Index of the file at `path`.
+    pub fn index_of(&self, path: &Path) -> Option<usize> {
+        self.sources.iter().position(|unit| unit.path == path)
+    }
+
+    /// The span offset of `position` in `sources[index]`, whose character
+    /// counts UTF-16 code units. Positions past the end of a line or of the
+    /// file are clamped.
+    pub fn offset(&self, index: usize, position: Position) -> usize {
+        self.starts[index] + offset_at(&self.sources[index].content, position)
+    }
+
+    /// The text of `span`, when it lies within one file.
+    pub fn text(&self, span: (usize, usize)) -> Option<&'a str> {
+        let index = self.file_index(span.0)?;
+        let start = span.0 - self.starts[index];
+        let end = span.1.checked_sub(self.starts[index])?;
+        self.sources[index].content.get(start..end)
+    }
+
+    /// The file and range of `span`, with UTF-16 columns as LSP expects.
+    /// `None` for spans outside every file or files without a path.
+    pub fn locate(&self, span: (usize, usize)) -> Option<Location> {
+        let index = self.file_index(span.0)?;
+        let unit = &self.sources[index];
+        let start = span.0 - self.starts[index];
+        let end = span
+            .1
+            .saturating_sub(self.starts[index])
+            .clamp(start, unit.content.len());
+        Some(Location {
+            uri: Url::from_file_path(&unit.path).ok()?,
+            range: Range {
+                start: position_at(&unit.content, start),
+                end: position_at(&unit.content, end),
+            },
+        })
+    }
+
+    /// The file and range each node of `graph` was declared at, by node id.
+    /// The synthetic EVM and event listener nodes are declared nowhere and
+    /// are left out; placeholders for unresolved calls point at their first
+    /// call.
+    pub fn origins(&self, graph: &CallGraph) -> BTreeMap<usize, Location> {
+        graph
+            .nodes
+            .iter()
+            .filter(|node| !matches!(node.node_type, NodeType::Evm | NodeType::EventListener))
+            .filter_map(|node| Some((node.id, self.locate(node.span)?)))
+            .collect()
+    }
+}
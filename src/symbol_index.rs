@@ -0,0 +1,581 @@
+//! Workspace-wide index of Solidity declarations.
+//!
+//! Every document is indexed from the exact text and version it was parsed
+//! from. Edits only record the new text, so rapid typing never blocks on
+//! parsing; a document whose symbols predate its latest edit is re-indexed
+//! on lookup, or reported as stale when the caller wants an immediate answer.
+//! Symbols are replaced per document, so renamed or deleted declarations
+//! never linger in the index.
+
+use crate::utils::{byte_column, find_solidity_files, utf16_column};
+use anyhow::Result;
+use dashmap::{mapref::entry::Entry, DashMap};
+use lsp_types::{Position, Range, Url};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use traverse_graph::parser::{get_node_text, parse_solidity};
+use tree_sitter::{Node as TsNode, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SymbolKind {
+    Contract,
+    Interface,
+    Library,
+    Function,
+    Modifier,
+    Event,
+    Error,
+    StateVariable,
+    Struct,
+    Enum,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Enclosing contract, interface or library.
+    pub container: Option<String>,
+    pub uri: Url,
+    /// Range of the declared name.
+    pub range: Range,
+    /// Document version the symbol was read from; `None` for files read from
+    /// disk.
+    pub version: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SymbolLookup {
+    pub symbols: Vec<Symbol>,
+    /// Documents whose symbols were computed from an older version than the
+    /// one the client last sent.
+    pub stale: Vec<Url>,
+}
+
+/// Summary of the index for client status displays.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IndexStatus {
+    pub files: usize,
+    /// Files edited since they were last indexed.
+    pub stale_files: usize,
+    /// Contracts, interfaces and libraries.
+    pub contracts: usize,
+    pub functions: usize,
+    /// Unix time in seconds of the last re-index, if any.
+    pub last_updated: Option<u64>,
+}
+
+struct IndexedDocument {
+    version: Option<i32>,
+    text: String,
+    symbols: Vec<Symbol>,
+    /// Whether `symbols` were computed from `text`.
+    fresh: bool,
+}
+
+impl IndexedDocument {
+    fn reindex(&mut self, uri: &Url) {
+        self.symbols = extract_symbols(uri, self.version, &self.text);
+        self.fresh = true;
+    }
+}
+
+#[derive(Default)]
+pub struct SymbolIndex {
+    documents: DashMap<Url, IndexedDocument>,
+    /// Unix time in seconds of the last re-index; 0 until the first one.
+    last_updated: AtomicU64,
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records new text for `uri`. Returns `false`, leaving the index
+    /// untouched, when `version` is older than the one already recorded.
+    pub fn update(&self, uri: Url, version: Option<i32>, text: String) -> bool {
+        match self.documents.entry(uri) {
+            Entry::Occupied(mut entry) => {
+                let document = entry.get_mut();
+                if let (Some(current), Some(version)) = (document.version, version) {
+                    if version < current {
+                        return false;
+                    }
+                }
+                if document.version != version || document.text != text {
+                    document.version = version;
+                    document.text = text;
+                    document.fresh = false;
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(IndexedDocument {
+                    version,
+                    text,
+                    symbols: Vec::new(),
+                    fresh: false,
+                });
+            }
+        }
+        true
+    }
+
+    /// Re-indexes `uri` if its symbols are out of date.
+    pub fn refresh(&self, uri: &Url) {
+        if let Some(mut document) = self.documents.get_mut(uri) {
+            if !document.fresh {
+                document.reindex(uri);
+                self.touch();
+            }
+        }
+    }
+
+    /// Falls back to the file on disk once the editor closes `uri`, or drops
+    /// it if the file no longer exists.
+    pub fn close(&self, uri: &Url) {
+        match uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+        {
+            Some(text) => {
+                self.documents.insert(
+                    uri.clone(),
+                    IndexedDocument {
+                        version: None,
+                        text,
+                        symbols: Vec::new(),
+                        fresh: false,
+                    },
+                );
+            }
+            None => {
+                self.documents.remove(uri);
+            }
+        }
+    }
+
+    /// Re-reads `uri` from disk after it changed outside the editor, or
+    /// drops it if it was deleted. Ignored while the editor has it open,
+    /// since the editor's text wins.
+    pub fn reload(&self, uri: &Url) {
+        if self
+            .documents
+            .get(uri)
+            .is_some_and(|document| document.version.is_some())
+        {
+            return;
+        }
+        self.close(uri);
+    }
+
+    /// Drops the files under `root` that the editor does not have open,
+    /// once the workspace folder is closed.
+    pub fn remove_folder(&self, root: &Path) {
+        let removed = self.documents.len();
+        self.documents.retain(|uri, document| {
+            document.version.is_some()
+                || !uri.to_file_path().is_ok_and(|path| path.starts_with(root))
+        });
+        if self.documents.len() < removed {
+            self.touch();
+        }
+    }
+
+    /// Indexes every `.sol` file under `root` that the client has not
+    /// already sent.
+    pub fn index_workspace(&self, root: &Path) -> Result<()> {
+        for uri in find_solidity_files(root)? {
+            if self.documents.contains_key(&uri) {
+                continue;
+            }
+            let Some(text) = uri
+                .to_file_path()
+                .ok()
+                .and_then(|path| std::fs::read_to_string(path).ok())
+            else {
+                continue;
+            };
+            let mut document = IndexedDocument {
+                version: None,
+                text,
+                symbols: Vec::new(),
+                fresh: false,
+            };
+            document.reindex(&uri);
+            self.touch();
+            // A didOpen may have arrived while the file was being parsed.
+            self.documents.entry(uri).or_insert(document);
+        }
+        Ok(())
+    }
+
+    /// Declarations named `name`, or `Container.name`, across all documents.
+    /// Out-of-date documents are re-indexed first when `recompute` is set and
+    /// listed in [`SymbolLookup::stale`] otherwise.
+    pub fn find(&self, name: &str, recompute: bool) -> SymbolLookup {
+        let (container, name) = match name.split_once('.') {
+            Some((container, name)) => (Some(container), name),
+            None => (None, name),
+        };
+
+        let mut lookup = SymbolLookup::default();
+        for mut entry in self.documents.iter_mut() {
+            let (uri, document) = entry.pair_mut();
+            if !document.fresh {
+                if recompute {
+                    document.reindex(uri);
+                    self.touch();
+                } else {
+                    lookup.stale.push(uri.clone());
+                }
+            }
+            lookup.symbols.extend(
+                document
+                    .symbols
+                    .iter()
+                    .filter(|s| {
+                        s.name == name
+                            && container.is_none_or(|c| s.container.as_deref() == Some(c))
+                    })
+                    .cloned(),
+            );
+        }
+        lookup.symbols.sort_by(|a, b| {
+            a.uri
+                .cmp(&b.uri)
+                .then_with(|| a.range.start.cmp(&b.range.start))
+        });
+        lookup.stale.sort();
+        lookup
+    }
+
+    /// Sorted, distinct names of `kind` declared in files under any of
+    /// `roots`, or anywhere when `roots` is empty. Out-of-date documents are
+    /// re-indexed first.
+    pub fn names(&self, kind: SymbolKind, roots: &[PathBuf]) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        for mut entry in self.documents.iter_mut() {
+            let (uri, document) = entry.pair_mut();
+            if !roots.is_empty() {
+                let Ok(path) = uri.to_file_path() else {
+                    continue;
+                };
+                if !roots.iter().any(|root| path.starts_with(root)) {
+                    continue;
+                }
+            }
+            if !document.fresh {
+                document.reindex(uri);
+                self.touch();
+            }
+            names.extend(
+                document
+                    .symbols
+                    .iter()
+                    .filter(|s| s.kind == kind)
+                    .map(|s| s.name.clone()),
+            );
+        }
+        names.into_iter().collect()
+    }
+
+    /// Declarations whose name, or `Container.name` when `query` has a dot,
+    /// contains the characters of `query` in order, ignoring case. Exact
+    /// matches rank first, then prefixes, substrings and scattered matches;
+    /// at most `limit` are returned. Out-of-date documents are re-indexed
+    /// first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<Symbol> {
+        let query = query.to_lowercase();
+        let mut matches = Vec::new();
+        for mut entry in self.documents.iter_mut() {
+            let (uri, document) = entry.pair_mut();
+            if !document.fresh {
+                document.reindex(uri);
+                self.touch();
+            }
+            for symbol in &document.symbols {
+                let candidate = match (&symbol.container, query.contains('.')) {
+                    (Some(container), true) => format!("{}.{}", container, symbol.name),
+                    _ => symbol.name.clone(),
+                };
+                if let Some(rank) = fuzzy_rank(&candidate.to_lowercase(), &query) {
+                    matches.push((rank, symbol.clone()));
+                }
+            }
+        }
+        matches.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then_with(|| a.name.len().cmp(&b.name.len()))
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.uri.cmp(&b.uri))
+                .then_with(|| a.range.start.cmp(&b.range.start))
+        });
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, symbol)| symbol)
+            .collect()
+    }
+
+    pub fn status(&self) -> IndexStatus {
+        let mut status = IndexStatus::default();
+        for document in self.documents.iter() {
+            status.files += 1;
+            if !document.fresh {
+                status.stale_files += 1;
+            }
+            for symbol in &document.symbols {
+                match symbol.kind {
+                    SymbolKind::Contract | SymbolKind::Interface | SymbolKind::Library => {
+                        status.contracts += 1
+                    }
+                    SymbolKind::Function => status.functions += 1,
+                    _ => {}
+                }
+            }
+        }
+        let last_updated = self.last_updated.load(Ordering::Relaxed);
+        status.last_updated = (last_updated > 0).then_some(last_updated);
+        status
+    }
+
+    /// Estimated memory held by the index: document texts and symbols.
+    pub fn approximate_bytes(&self) -> usize {
+        self.documents
+            .iter()
+            .map(|entry| {
+                let document = entry.value();
+                entry.key().as_str().len()
+                    + size_of::<IndexedDocument>()
+                    + document.text.capacity()
+                    + document
+                        .symbols
+                        .iter()
+                        .map(|s| {
+                            size_of::<Symbol>()
+                                + s.name.capacity()
+                                + s.container.as_ref().map_or(0, String::capacity)
+                                + s.uri.as_str().len()
+                        })
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+
+    fn touch(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.last_updated.store(now, Ordering::Relaxed);
+    }
+}
+
+fn extract_symbols(uri: &Url, version: Option<i32>, text: &str) -> Vec<Symbol> {
+    let Ok(parsed) = parse_solidity(text) else {
+        return Vec::new();
+    };
+    let mut symbols = Vec::new();
+    collect(
+        parsed.tree.root_node(),
+        None,
+        uri,
+        version,
+        text,
+        &mut symbols,
+    );
+    symbols
+}
+
+fn collect(
+    node: TsNode,
+    container: Option<&str>,
+    uri: &Url,
+    version: Option<i32>,
+    text: &str,
+    symbols: &mut Vec<Symbol>,
+) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let kind = match child.kind() {
+            "contract_declaration" => SymbolKind::Contract,
+            "interface_declaration" => SymbolKind::Interface,
+            "library_declaration" => SymbolKind::Library,
+            "function_definition" => SymbolKind::Function,
+            "modifier_definition" => SymbolKind::Modifier,
+            "event_definition" => SymbolKind::Event,
+            "error_declaration" => SymbolKind::Error,
+            "state_variable_declaration" => SymbolKind::StateVariable,
+            "struct_declaration" => SymbolKind::Struct,
+            "enum_declaration" => SymbolKind::Enum,
+            _ => continue,
+        };
+        // Constructors, fallback and receive functions have no name.
+        let Some(name_node) = child.child_by_field_name("name") else {
+            continue;
+        };
+        let name = get_node_text(&name_node, text).to_string();
+        symbols.push(Symbol {
+            name: name.clone(),
+            kind,
+            container: container.map(str::to_string),
+            uri: uri.clone(),
+            range: Range::new(
+                position(text, name_node.start_position()),
+                position(text, name_node.end_position()),
+            ),
+            version,
+        });
+        if matches!(
+            kind,
+            SymbolKind::Contract | SymbolKind::Interface | SymbolKind::Library
+        ) {
+            if let Some(body) = child.child_by_field_name("body") {
+                collect(body, Some(&name), uri, version, text, symbols);
+            }
+        }
+    }
+}
+
+/// How well `candidate` matches `query`, both lowercase: 0 for equal, 1
+/// for a prefix, 2 for a substring, 3 for the characters in order with
+/// gaps; `None` if they do not all appear in order.
+fn fuzzy_rank(candidate: &str, query: &str) -> Option<u8> {
+    if candidate == query {
+        return Some(0);
+    }
+    if candidate.starts_with(query) {
+        return Some(1);
+    }
+    if candidate.contains(query) {
+        return Some(2);
+    }
+    let mut rest = candidate.chars();
+    query
+        .chars()
+        .all(|c| rest.any(|candidate| candidate == c))
+        .then_some(3)
+}
+
+/// Converts a tree-sitter byte column to the UTF-16 column LSP expects.
+pub(crate) fn position(text: &str, point: Point) -> Position {
+    let line = text.lines().nth(point.row).unwrap_or_default();
+    Position::new(point.row as u32, utf16_column(line, point.column))
+}
+
+/// The contract and name of the function, modifier or constructor whose
+/// definition contains `position`; a constructor is named `constructor`.
+pub fn enclosing_function(text: &str, position: Position) -> Option<(String, String)> {
+    let parsed = parse_solidity(text).ok()?;
+    let function = enclosing(
+        parsed.tree.root_node(),
+        point(text, position),
+        &[
+            "function_definition",
+            "modifier_definition",
+            "constructor_definition",
+        ],
+    )?;
+    let name = match function.child_by_field_name("name") {
+        Some(name) => get_node_text(&name, text).to_string(),
+        None if function.kind() == "constructor_definition" => "constructor".to_string(),
+        None => return None,
+    };
+    Some((container(function, text)?, name))
+}
+
+/// A function, modifier, constructor or state variable whose declared name
+/// is at some position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// `Function` for constructors.
+    pub kind: SymbolKind,
+    /// Enclosing contract, interface or library.
+    pub container: String,
+    /// `constructor` for constructors.
+    pub name: String,
+    /// Range of the name, or of the `constructor` keyword.
+    pub range: Range,
+}
+
+/// The declaration whose name, or `constructor` keyword, `position` is on.
+pub fn declaration_at(text: &str, position: Position) -> Option<Declaration> {
+    let parsed = parse_solidity(text).ok()?;
+    let point = point(text, position);
+    let node = parsed
+        .tree
+        .root_node()
+        .descendant_for_point_range(point, point)?;
+    let declaration = node.parent()?;
+    let is_name = declaration.child_by_field_name("name") == Some(node);
+    let (kind, name) = match declaration.kind() {
+        "function_definition" if is_name => (SymbolKind::Function, get_node_text(&node, text)),
+        "modifier_definition" if is_name => (SymbolKind::Modifier, get_node_text(&node, text)),
+        "state_variable_declaration" if is_name => {
+            (SymbolKind::StateVariable, get_node_text(&node, text))
+        }
+        "constructor_definition" if node.kind() == "constructor" => {
+            (SymbolKind::Function, "constructor")
+        }
+        _ => return None,
+    };
+    Some(Declaration {
+        kind,
+        container: container(declaration, text)?,
+        name: name.to_string(),
+        range: Range::new(
+            self::position(text, node.start_position()),
+            self::position(text, node.end_position()),
+        ),
+    })
+}
+
+/// The name of the contract whose declaration contains `position`.
+/// Interfaces and libraries are not contracts here.
+pub fn enclosing_contract(text: &str, position: Position) -> Option<String> {
+    let parsed = parse_solidity(text).ok()?;
+    let contract = enclosing(
+        parsed.tree.root_node(),
+        point(text, position),
+        &["contract_declaration"],
+    )?;
+    Some(get_node_text(&contract.child_by_field_name("name")?, text).to_string())
+}
+
+/// The name of the contract, interface or library declaring `node`.
+fn container(node: TsNode, text: &str) -> Option<String> {
+    let container = std::iter::successors(node.parent(), |n| n.parent()).find(|n| {
+        matches!(
+            n.kind(),
+            "contract_declaration" | "interface_declaration" | "library_declaration"
+        )
+    })?;
+    Some(get_node_text(&container.child_by_field_name("name")?, text).to_string())
+}
+
+/// The innermost node of one of `kinds` containing `point`.
+fn enclosing<'t>(root: TsNode<'t>, point: Point, kinds: &[&str]) -> Option<TsNode<'t>> {
+    let mut node = root.descendant_for_point_range(point, point)?;
+    while !kinds.contains(&node.kind()) {
+        node = node.parent()?;
+    }
+    Some(node)
+}
+
+/// Converts an LSP position, whose column counts UTF-16 code units, to a
+/// tree-sitter point.
+fn point(text: &str, position: Position) -> Point {
+    let line = text.lines().nth(position.line as usize).unwrap_or_default();
+    Point::new(
+        position.line as usize,
+        byte_column(line, position.character),
+    )
+}
@@ -0,0 +1,60 @@
+//! Opt-in command telemetry.
+//!
+//! With `telemetry` enabled, every workspace command is followed by a
+//! `telemetry/event` notification saying how it went, so extension
+//! maintainers can see how the server performs on real projects. Events
+//! carry no paths, contract names or arguments: only the command name, how
+//! long it took, how many files it analyzed and whether it succeeded.
+
+use crate::commands;
+use lsp_server::{Notification, Response};
+use lsp_types::notification::{Notification as _, TelemetryEvent};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Reported in place of a command the server does not know, whose name
+/// could be anything.
+pub const UNKNOWN_COMMAND: &str = "unknown";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandEvent {
+    /// Always `command`.
+    pub event: &'static str,
+    /// One of [`commands::WORKSPACE_COMMANDS`], or [`UNKNOWN_COMMAND`].
+    pub command: &'static str,
+    pub duration_ms: u64,
+    /// Files the command analyzed, when it got as far as finding them.
+    pub files: Option<u64>,
+    pub success: bool,
+    /// LSP error code of a failed command.
+    pub error_code: Option<i32>,
+}
+
+impl CommandEvent {
+    /// The event for `command`, answered with `response` after `duration`.
+    pub fn new(command: &str, duration: Duration, response: &Response) -> Self {
+        let command = commands::WORKSPACE_COMMANDS
+            .iter()
+            .find(|known| **known == command)
+            .copied()
+            .unwrap_or(UNKNOWN_COMMAND);
+        let details = match &response.error {
+            Some(error) => error.data.as_ref(),
+            None => response.result.as_ref(),
+        };
+        CommandEvent {
+            event: "command",
+            command,
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            files: details
+                .and_then(|d| d.get("files"))
+                .and_then(|f| f.as_u64()),
+            success: response.error.is_none(),
+            error_code: response.error.as_ref().map(|error| error.code),
+        }
+    }
+
+    pub fn notification(&self) -> Notification {
+        Notification::new(TelemetryEvent::METHOD.to_string(), self)
+    }
+}
@@ -0,0 +1,560 @@
+//! Execution traces as sequence diagrams.
+//!
+//! Parses the call trees printed by `forge test -vvvv` and `cast run`,
+//! where each call line reads `[gas] Contract::function(args)` and is
+//! answered by a `← [Return] value` line one level deeper, and the JSON
+//! frames of `debug_traceTransaction` with the `callTracer`, and renders the
+//! calls that actually ran as a Mermaid sequence diagram. Calls are matched
+//! to call graph nodes by contract and function name, so each step can link
+//! back to the workspace source. JSON frames only carry addresses and
+//! calldata, so their contracts and functions are first recovered from the
+//! selectors of the workspace ABIs.
+
+use crate::analysis::model::{ContractKind, SourceModel};
+use crate::analysis::selector_collision::{selector, to_hex};
+use crate::source_map::SourceMap;
+use anyhow::{bail, Context, Result};
+use lsp_types::Location;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, NodeType};
+
+/// Participant that makes the outermost call of each trace.
+pub const ROOT_CALLER: &str = "Caller";
+
+/// Labels Foundry gives the cheatcode and console addresses. Their calls
+/// are test plumbing rather than contract behaviour, so they are not drawn.
+const HIDDEN_CONTRACTS: [&str; 2] = ["VM", "console"];
+
+/// Arguments longer than this are cut short in diagram labels.
+const MAX_LABEL_ARGUMENTS: usize = 80;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepKind {
+    #[default]
+    Call,
+    StaticCall,
+    DelegateCall,
+    CallCode,
+    Create,
+    Event,
+}
+
+/// One call or event of a trace, in the order the trace printed it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TraceStep {
+    pub kind: StepKind,
+    /// Nesting below the outermost call of its trace, which is 0.
+    pub depth: usize,
+    /// The contract making the call, or emitting the event.
+    pub caller: String,
+    /// The called contract; the emitting contract for events.
+    pub contract: String,
+    /// Function or event name; `constructor` for contract creations.
+    pub name: String,
+    /// Everything after the name, including the parentheses.
+    pub arguments: String,
+    pub gas: Option<u64>,
+    /// Returned value or revert reason, when the trace shows one.
+    pub result: Option<String>,
+    pub reverted: bool,
+    /// The 4-byte selector of a call read from transaction calldata.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Declaration of the called function in the workspace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    /// Call graph node of the called function.
+    #[serde(skip)]
+    pub node_id: Option<usize>,
+}
+
+impl TraceStep {
+    fn is_hidden(&self) -> bool {
+        HIDDEN_CONTRACTS.contains(&self.contract.as_str())
+            || HIDDEN_CONTRACTS.contains(&self.caller.as_str())
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<TraceStep>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read trace file {}", path.display()))?;
+    read(&text)
+}
+
+/// Steps of `text`, read as `callTracer` JSON if it is a JSON object and as
+/// Foundry output otherwise.
+pub fn read(text: &str) -> Result<Vec<TraceStep>> {
+    if text.trim_start().starts_with('{') {
+        parse_call_tracer(text)
+    } else {
+        Ok(parse(text))
+    }
+}
+
+/// Steps of every trace in `text`. Lines that are not part of a call tree,
+/// such as test results and logs, are skipped.
+pub fn parse(text: &str) -> Vec<TraceStep> {
+    let mut steps: Vec<TraceStep> = Vec::new();
+    // Column and step index of each call still open at the current line.
+    let mut open: Vec<(usize, usize)> = Vec::new();
+
+    for line in text.lines() {
+        let rest = line.trim_start_matches([' ', '│', '├', '└', '─']);
+        let column = line[..line.len() - rest.len()].chars().count();
+        let rest = rest.trim_end();
+
+        if let Some(result) = rest.strip_prefix('←') {
+            while open.last().is_some_and(|(c, _)| *c >= column) {
+                open.pop();
+            }
+            if let Some((_, index)) = open.last() {
+                let (result, reverted) = parse_result(result.trim());
+                steps[*index].result = result;
+                steps[*index].reverted = reverted;
+            }
+            continue;
+        }
+
+        let Some(mut step) = parse_step(rest) else {
+            continue;
+        };
+        while open.last().is_some_and(|(c, _)| *c >= column) {
+            open.pop();
+        }
+        step.depth = open.len();
+        if let Some((_, parent)) = open.last() {
+            step.caller = steps[*parent].contract.clone();
+        }
+        if step.kind == StepKind::Event {
+            step.contract = step.caller.clone();
+        } else {
+            open.push((column, steps.len()));
+        }
+        steps.push(step);
+    }
+
+    steps
+}
+
+/// A call line after its tree prefix, `[gas] Contract::name(args)`,
+/// `[gas] → new Contract@address` or `emit Event(args)`.
+fn parse_step(line: &str) -> Option<TraceStep> {
+    let mut step = TraceStep {
+        caller: ROOT_CALLER.to_string(),
+        ..TraceStep::default()
+    };
+
+    if let Some(event) = line.strip_prefix("emit ") {
+        let (name, arguments) = split_name(event);
+        step.kind = StepKind::Event;
+        step.name = name.to_string();
+        step.arguments = arguments.to_string();
+        return Some(step);
+    }
+
+    let (gas, call) = line.strip_prefix('[')?.split_once("] ")?;
+    step.gas = Some(gas.parse().ok()?);
+
+    if let Some(created) = call.strip_prefix("→ new ") {
+        step.kind = StepKind::Create;
+        step.contract = created.split('@').next().unwrap_or(created).to_string();
+        step.name = "constructor".to_string();
+        return Some(step);
+    }
+
+    let (contract, call) = call.split_once("::")?;
+    let mut call = call;
+    for (suffix, kind) in [
+        (" [staticcall]", StepKind::StaticCall),
+        (" [delegatecall]", StepKind::DelegateCall),
+        (" [callcode]", StepKind::CallCode),
+    ] {
+        if let Some(stripped) = call.strip_suffix(suffix) {
+            call = stripped;
+            step.kind = kind;
+        }
+    }
+    let (name, arguments) = split_name(call);
+    step.contract = contract.to_string();
+    step.name = name.to_string();
+    step.arguments = arguments.to_string();
+    Some(step)
+}
+
+/// Splits `name(args)` or `name{value: 1}(args)` at the end of the name.
+fn split_name(call: &str) -> (&str, &str) {
+    let end = call.find(['(', '{']).unwrap_or(call.len());
+    (&call[..end], &call[end..])
+}
+
+/// The value and revert flag of a return line after its `←`. Foundry tags
+/// results as `[Return]`, `[Stop]` or `[Revert]`, and older versions
+/// print the bare value.
+fn parse_result(result: &str) -> (Option<String>, bool) {
+    let (tag, value) = match result.strip_prefix('[').and_then(|r| r.split_once(']')) {
+        Some((tag, value)) => (Some(tag), value.trim()),
+        None => (None, result),
+    };
+    let value = (!value.is_empty() && value != "()").then(|| value.to_string());
+    match tag {
+        None | Some("Return") | Some("Stop") => (value, false),
+        Some(tag) => (value.or_else(|| Some(tag.to_string())), true),
+    }
+}
+
+/// A frame of the `callTracer` output of `debug_traceTransaction`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CallFrame {
+    #[serde(rename = "type")]
+    kind: String,
+    from: String,
+    #[serde(default)]
+    to: Option<String>,
+    #[serde(default)]
+    input: String,
+    #[serde(default)]
+    output: Option<String>,
+    #[serde(default)]
+    gas_used: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    revert_reason: Option<String>,
+    #[serde(default)]
+    calls: Vec<CallFrame>,
+}
+
+/// The trace as returned by the node, or the whole JSON-RPC response.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CallTrace {
+    Response { result: CallFrame },
+    Frame(CallFrame),
+}
+
+/// Steps of a `callTracer` trace. Callers and contracts are addresses and
+/// names are selectors until [`resolve_selectors`] names them.
+pub fn parse_call_tracer(json: &str) -> Result<Vec<TraceStep>> {
+    let trace: CallTrace =
+        serde_json::from_str(json).context("Trace is not a callTracer call frame")?;
+    let root = match trace {
+        CallTrace::Response { result } => result,
+        CallTrace::Frame(frame) => frame,
+    };
+    let mut steps = Vec::new();
+    push_frame(root, 0, &mut steps)?;
+    Ok(steps)
+}
+
+fn push_frame(frame: CallFrame, depth: usize, steps: &mut Vec<TraceStep>) -> Result<()> {
+    let kind = match frame.kind.to_ascii_uppercase().as_str() {
+        "STATICCALL" => StepKind::StaticCall,
+        "DELEGATECALL" => StepKind::DelegateCall,
+        "CALLCODE" => StepKind::CallCode,
+        "CREATE" | "CREATE2" => StepKind::Create,
+        _ => StepKind::Call,
+    };
+    let input = frame.input.trim_start_matches("0x");
+    let selector = match input.get(..8) {
+        _ if kind == StepKind::Create || input.len() < 8 => None,
+        Some(selector) if selector.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Some(format!("0x{}", selector.to_ascii_lowercase()))
+        }
+        _ => bail!("Call input {} is not hex", frame.input),
+    };
+    let name = match (&selector, kind) {
+        (_, StepKind::Create) => "constructor".to_string(),
+        (Some(selector), _) => selector.clone(),
+        (None, _) => "fallback".to_string(),
+    };
+    let arguments = match kind {
+        StepKind::Create => String::new(),
+        _ if input.len() > 8 => "(…)".to_string(),
+        _ => "()".to_string(),
+    };
+    let reverted = frame.error.is_some();
+    let result = if reverted {
+        frame.revert_reason.or(frame.error)
+    } else {
+        frame
+            .output
+            .filter(|output| !output.trim_start_matches("0x").is_empty())
+    };
+    steps.push(TraceStep {
+        kind,
+        depth,
+        caller: frame.from.to_ascii_lowercase(),
+        contract: frame.to.unwrap_or_default().to_ascii_lowercase(),
+        name,
+        arguments,
+        gas: frame
+            .gas_used
+            .and_then(|gas| u64::from_str_radix(gas.trim_start_matches("0x"), 16).ok()),
+        result,
+        reverted,
+        selector,
+        location: None,
+        node_id: None,
+    });
+    for call in frame.calls {
+        push_frame(call, depth + 1, steps)?;
+    }
+    Ok(())
+}
+
+/// Names the addresses and selectors of steps read from calldata. An
+/// address is named after the only deployable workspace contract that
+/// implements every selector called on it; a contract deployed at several
+/// addresses gets an `@address` suffix on each. A selector is named after
+/// the function of the address's contract, or after the function every
+/// workspace contract agrees on when the address stays unknown.
+pub fn resolve_selectors(steps: &mut [TraceStep], model: &SourceModel) {
+    let mut implementers: HashMap<String, HashMap<&str, &str>> = HashMap::new();
+    for contract in model
+        .contracts
+        .iter()
+        .filter(|c| c.kind == ContractKind::Contract && !c.is_abstract)
+    {
+        for (_, function) in model.external_functions(contract) {
+            implementers
+                .entry(to_hex(&selector(&model.signature(function))))
+                .or_default()
+                .insert(contract.name.as_str(), function.name.as_str());
+        }
+    }
+
+    let mut candidates: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for step in steps.iter() {
+        let Some(selector) = &step.selector else {
+            continue;
+        };
+        let implementing: HashSet<&str> = implementers
+            .get(selector)
+            .map(|contracts| contracts.keys().copied().collect())
+            .unwrap_or_default();
+        candidates
+            .entry(step.contract.as_str())
+            .and_modify(|c| c.retain(|name| implementing.contains(name)))
+            .or_insert(implementing);
+    }
+    let contracts: HashMap<String, &str> = candidates
+        .into_iter()
+        .filter(|(_, names)| names.len() == 1)
+        .filter_map(|(address, names)| Some((address.to_string(), *names.iter().next()?)))
+        .collect();
+
+    let mut addresses_per_contract: HashMap<&str, usize> = HashMap::new();
+    for name in contracts.values() {
+        *addresses_per_contract.entry(name).or_default() += 1;
+    }
+    let label = |address: &str| match contracts.get(address) {
+        Some(name) if addresses_per_contract[name] == 1 => name.to_string(),
+        Some(name) => format!("{}@{}", name, short_address(address)),
+        None => short_address(address),
+    };
+
+    for step in steps.iter_mut() {
+        if let Some(selector) = &step.selector {
+            let functions = implementers.get(selector);
+            let name = match contracts.get(&step.contract) {
+                Some(contract) => functions.and_then(|f| f.get(contract)).copied(),
+                None => functions.and_then(|f| {
+                    let names: BTreeSet<&str> = f.values().copied().collect();
+                    (names.len() == 1)
+                        .then(|| names.into_iter().next())
+                        .flatten()
+                }),
+            };
+            if let Some(name) = name {
+                step.name = name.to_string();
+            }
+        }
+        if step.caller.starts_with("0x") {
+            step.caller = label(&step.caller);
+        }
+        if step.contract.starts_with("0x") {
+            step.contract = label(&step.contract);
+        }
+    }
+}
+
+/// `0x5615…b72f` for a 20-byte hex address.
+fn short_address(address: &str) -> String {
+    match (address.get(..6), address.len().checked_sub(4)) {
+        (Some(start), Some(end)) if address.len() > 12 => match address.get(end..) {
+            Some(last) => format!("{}…{}", start, last),
+            None => address.to_string(),
+        },
+        _ => address.to_string(),
+    }
+}
+
+/// Sets the node and location of every call matching a function,
+/// constructor or modifier node of `graph`, declared in the called contract
+/// or the nearest base of it in `model`. Returns the `Contract::name` of
+/// calls that match none.
+pub fn align(
+    steps: &mut [TraceStep],
+    graph: &CallGraph,
+    model: &SourceModel,
+    map: &SourceMap,
+) -> Vec<String> {
+    let mut nodes: HashMap<(&str, &str), usize> = HashMap::new();
+    for node in &graph.nodes {
+        let Some(contract) = node.contract_name.as_deref() else {
+            continue;
+        };
+        let name = match node.node_type {
+            NodeType::Constructor => "constructor",
+            NodeType::Function | NodeType::Modifier => node.name.as_str(),
+            _ => continue,
+        };
+        nodes.entry((contract, name)).or_insert(node.id);
+    }
+
+    let mut unmatched = BTreeSet::new();
+    for step in steps.iter_mut() {
+        if step.kind == StepKind::Event || step.is_hidden() {
+            continue;
+        }
+        // Several deployments of one contract are told apart by address.
+        let contract = step.contract.split('@').next().unwrap_or_default();
+        let node = std::iter::once(contract)
+            .chain(
+                model
+                    .ancestors(contract)
+                    .into_iter()
+                    .rev()
+                    .map(|c| c.name.as_str()),
+            )
+            .find_map(|owner| nodes.get(&(owner, step.name.as_str())));
+        match node {
+            Some(&id) => {
+                step.node_id = Some(id);
+                step.location = map.locate(graph.nodes[id].span);
+            }
+            None => {
+                unmatched.insert(format!("{}::{}", step.contract, step.name));
+            }
+        }
+    }
+    unmatched.into_iter().collect()
+}
+
+/// Mermaid sequence diagram of `steps`, with a return arrow closing each
+/// call and events as notes over the emitting contract.
+pub fn render_mermaid(steps: &[TraceStep]) -> String {
+    let steps: Vec<&TraceStep> = steps.iter().filter(|s| !s.is_hidden()).collect();
+
+    let mut out = String::from("sequenceDiagram\n");
+    // Callers of outermost calls are accounts rather than contracts.
+    let mut participants: Vec<(&str, &str)> = Vec::new();
+    for step in &steps {
+        let caller = if step.depth == 0 {
+            "actor"
+        } else {
+            "participant"
+        };
+        for (name, keyword) in [(&step.caller, caller), (&step.contract, "participant")] {
+            if !participants.iter().any(|(p, _)| p == name) {
+                participants.push((name, keyword));
+            }
+        }
+    }
+    for (participant, keyword) in participants {
+        let id = participant_id(participant);
+        if id == participant {
+            out.push_str(&format!("    {} {}\n", keyword, id));
+        } else {
+            out.push_str(&format!(
+                "    {} {} as {}\n",
+                keyword,
+                id,
+                escape(participant)
+            ));
+        }
+    }
+
+    let mut open: Vec<&TraceStep> = Vec::new();
+    for step in &steps {
+        while open.last().is_some_and(|o| o.depth >= step.depth) {
+            push_return(&mut out, open.pop().unwrap());
+        }
+        if step.kind == StepKind::Event {
+            out.push_str(&format!(
+                "    Note over {}: emit {}{}\n",
+                participant_id(&step.contract),
+                escape(&step.name),
+                escape(&shorten(&step.arguments))
+            ));
+            continue;
+        }
+        out.push_str(&format!(
+            "    {}->>{}: {}\n",
+            participant_id(&step.caller),
+            participant_id(&step.contract),
+            call_label(step)
+        ));
+        open.push(step);
+    }
+    while let Some(step) = open.pop() {
+        push_return(&mut out, step);
+    }
+
+    out
+}
+
+fn push_return(out: &mut String, step: &TraceStep) {
+    let (arrow, label) = match (&step.result, step.reverted) {
+        (Some(result), true) => ("--x", format!("revert {}", escape(&shorten(result)))),
+        (None, true) => ("--x", "revert".to_string()),
+        (Some(result), false) => ("-->>", escape(&shorten(result))),
+        (None, false) => ("-->>", "return".to_string()),
+    };
+    out.push_str(&format!(
+        "    {}{}{}: {}\n",
+        participant_id(&step.contract),
+        arrow,
+        participant_id(&step.caller),
+        label
+    ));
+}
+
+fn call_label(step: &TraceStep) -> String {
+    let call = format!(
+        "{}{}",
+        escape(&step.name),
+        escape(&shorten(&step.arguments))
+    );
+    match step.kind {
+        StepKind::Create => format!("new {}", escape(&step.contract)),
+        StepKind::StaticCall => format!("staticcall {}", call),
+        StepKind::DelegateCall => format!("delegatecall {}", call),
+        StepKind::CallCode => format!("callcode {}", call),
+        StepKind::Call | StepKind::Event => call,
+    }
+}
+
+/// A Mermaid identifier for `name`, which may be an address or a label
+/// with spaces.
+fn participant_id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn shorten(text: &str) -> String {
+    match text.char_indices().nth(MAX_LABEL_ARGUMENTS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Escapes the characters that end or alter a Mermaid message.
+fn escape(text: &str) -> String {
+    text.replace('#', "#35;").replace(';', "#59;")
+}
@@ -3,46 +3,281 @@
 //! Isolates Traverse-specific logic from the LSP protocol layer,
 //! making it easier to upgrade or swap analysis engines.
 
-use crate::config::MermaidConfig;
-use anyhow::Result;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::analysis::imports::{self, ImportClosure};
+use crate::analysis::model::SourceUnit;
+use crate::config::{Config, DotConfig, MermaidConfig};
+use crate::foundry;
+use crate::graph_cache::GraphCache;
+use crate::output::manifest::{self, Manifest};
+use crate::output::{path_lock, write_atomic, StagingDir};
+use crate::renderers::{dot, mermaid};
+use crate::source_map::SourceMap;
+use crate::utils::find_source_files;
+#[cfg(feature = "vyper")]
+use crate::vyper;
+use crate::yul;
+use anyhow::{anyhow, bail, Context, Result};
+use rayon::prelude::*;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use traverse_graph::cg::{
-    CallGraph, CallGraphGeneratorContext, CallGraphGeneratorInput, CallGraphGeneratorPipeline,
+    CallGraph, CallGraphGeneratorContext, CallGraphGeneratorInput, CallGraphGeneratorStep,
 };
-use traverse_graph::cg_dot::{CgToDot, DotExportConfig};
-use traverse_graph::cg_mermaid::{MermaidGenerator, ToSequenceDiagram};
-use traverse_graph::parser::{get_solidity_language, parse_solidity};
+use traverse_graph::parser::get_solidity_language;
 use traverse_graph::steps::{CallsHandling, ContractHandling};
+use traverse_mermaid::mermaid_chunker::ChunkingResult;
+use tree_sitter::{InputEdit, Parser, Point, Tree};
 
-pub struct TraverseAdapter {}
+/// Reads a language other than Solidity into the call graph.
+///
+/// Front ends run after every Solidity file is in the graph, so their calls
+/// can target Solidity declarations, such as the interfaces a Vyper
+/// contract calls through.
+pub trait Frontend: Sync {
+    /// File extensions handled, without the dot.
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Adds the declarations and calls of `source` to `graph`, with spans
+    /// relative to `source`.
+    fn add_to_graph(&self, graph: &mut CallGraph, path: &Path, source: &str) -> Result<()>;
+}
+
+static FRONTENDS: &[&dyn Frontend] = &[
+    &yul::YulFrontend,
+    #[cfg(feature = "vyper")]
+    &vyper::VyperFrontend,
+];
+
+/// The front end reading `path`; `None` for Solidity.
+pub fn frontend_for(path: &Path) -> Option<&'static dyn Frontend> {
+    let extension = path.extension()?.to_str()?;
+    FRONTENDS
+        .iter()
+        .find(|frontend| frontend.extensions().contains(&extension))
+        .copied()
+}
+
+/// Extensions of every source file workspace commands analyze.
+pub fn source_extensions() -> Vec<&'static str> {
+    let mut extensions = vec!["sol"];
+    for frontend in FRONTENDS {
+        extensions.extend(frontend.extensions());
+    }
+    extensions
+}
+
+pub struct TraverseAdapter {
+    /// Holds the last parsed text and tree of each file, which the next
+    /// parse of that file edits.
+    previous: Arc<GraphCache>,
+    config: Config,
+}
 
 impl TraverseAdapter {
     pub fn new() -> Result<Self> {
-        Ok(TraverseAdapter {})
+        Ok(TraverseAdapter {
+            previous: Arc::new(GraphCache::new(usize::MAX)),
+            config: Config::default(),
+        })
+    }
+
+    /// Renders with the server settings in `config`, such as
+    /// `chunk_threshold_nodes`.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
     }
 
+    /// Replaces the server settings, e.g. once they changed mid-session.
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    /// Keeps parse trees in `graphs`, within its budget, instead of a store
+    /// of its own. Workers can share one, so a restarted worker keeps what
+    /// its predecessor parsed.
+    pub fn with_graph_cache(mut self, graphs: Arc<GraphCache>) -> Self {
+        self.previous = graphs;
+        self
+    }
+
+    /// Call graph of a single source.
+    #[allow(dead_code)]
     pub fn build_call_graph(&self, source: &str) -> Result<CallGraph> {
-        let parsed = parse_solidity(source)?;
+        self.build_workspace_call_graph(&[SourceUnit {
+            path: PathBuf::new(),
+            content: source.to_string(),
+        }])
+    }
+
+    /// `entries` and every file they import, so a call graph can be built
+    /// from their dependency closure instead of every file under
+    /// `workspace_folder`. Imports go through the project's remappings and
+    /// may lead into `lib` or `node_modules`; those that name no file are
+    /// returned in `unresolved`.
+    pub fn resolve_imports(
+        &self,
+        workspace_folder: &Path,
+        entries: &[PathBuf],
+    ) -> Result<ImportClosure> {
+        let workspace_files: Vec<PathBuf> = find_source_files(workspace_folder)?
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect();
+        let remappings = foundry::remappings(workspace_folder)?;
+        imports::import_closure_on_disk(workspace_folder, &workspace_files, entries, &remappings)
+    }
+
+    /// Call graph of `sources`, each parsed on its own. Definitions of every
+    /// file are collected before calls are resolved, so calls across files
+    /// resolve. Spans are offset per file as laid out by [`SourceMap`].
+    ///
+    /// The library looks up a definition's body in the tree of the file
+    /// being processed, so return types of functions declared in another
+    /// file are not inferred.
+    pub fn build_workspace_call_graph(&self, sources: &[SourceUnit]) -> Result<CallGraph> {
+        self.build_workspace_call_graph_with_progress(sources, &|_| {})
+    }
+
+    /// [`build_workspace_call_graph`](Self::build_workspace_call_graph),
+    /// calling `parsed` with the number of files parsed so far after each
+    /// file.
+    pub fn build_workspace_call_graph_with_progress(
+        &self,
+        sources: &[SourceUnit],
+        parsed: &(dyn Fn(usize) + Sync),
+    ) -> Result<CallGraph> {
         let solidity_lang = get_solidity_language();
-        let input = CallGraphGeneratorInput {
-            source: source.to_string(),
-            tree: parsed.tree,
-            solidity_lang,
-        };
+        // Counted under a lock so `parsed` sees the counts in order.
+        let done = Mutex::new(0);
+        let inputs = sources
+            .par_iter()
+            .map(|unit| {
+                // Files of other languages are added after the Solidity
+                // ones; the library sees them as empty files so file indices
+                // stay aligned.
+                let source = if frontend_for(&unit.path).is_some() {
+                    ""
+                } else {
+                    unit.content.as_str()
+                };
+                let tree = self.parse(&unit.path, source)?;
+                let mut done = done.lock().unwrap();
+                *done += 1;
+                parsed(*done);
+                Ok(CallGraphGeneratorInput {
+                    source: source.to_string(),
+                    tree,
+                    solidity_lang: solidity_lang.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         let mut ctx = CallGraphGeneratorContext::default();
         let mut graph = CallGraph::new();
         let config: HashMap<String, String> = HashMap::new();
+        let mut contracts = ContractHandling::default();
+        contracts.config(&config);
+        let mut calls = CallsHandling::default();
+        calls.config(&config);
+
+        // File index of every node and edge, for offsetting spans at the end.
+        let mut node_files: Vec<usize> = Vec::new();
+        let mut edge_files: Vec<usize> = Vec::new();
+        let mut definitions = Vec::with_capacity(inputs.len());
+        let mut all_contracts = HashMap::new();
+        for (index, input) in inputs.iter().enumerate() {
+            // The step adds a default constructor for every contract it
+            // knows, so it only sees this file's.
+            let first_definition = ctx.definition_nodes_info.len();
+            contracts.generate(input.clone(), &mut ctx, &mut graph)?;
+            for (name, info) in std::mem::take(&mut ctx.all_contracts) {
+                all_contracts.entry(name).or_insert(info);
+            }
+            definitions.push(ctx.definition_nodes_info.split_off(first_definition));
+            node_files.resize(graph.nodes.len(), index);
+            edge_files.resize(graph.edges.len(), index);
+        }
+        ctx.all_contracts = all_contracts;
+        // Interfaces declared in later files were unknown when earlier files
+        // recorded what their contracts implement.
+        ctx.contract_implements = ctx
+            .contract_inherits
+            .iter()
+            .map(|(contract, bases)| {
+                let interfaces = bases
+                    .iter()
+                    .filter(|base| ctx.all_interfaces.contains_key(*base))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (contract.clone(), interfaces)
+            })
+            .filter(|(_, interfaces)| !interfaces.is_empty())
+            .collect();
+
+        for (index, input) in inputs.into_iter().enumerate() {
+            // Bodies are found by span, which only means something in this
+            // file's tree.
+            ctx.definition_nodes_info = std::mem::take(&mut definitions[index]);
+            calls.generate(input, &mut ctx, &mut graph)?;
+            node_files.resize(graph.nodes.len(), index);
+            edge_files.resize(graph.edges.len(), index);
+        }
 
-        let mut pipeline = CallGraphGeneratorPipeline::new();
-        pipeline.add_step(Box::new(ContractHandling::default()));
-        pipeline.add_step(Box::new(CallsHandling::default()));
-        pipeline.run(input, &mut ctx, &mut graph, &config)?;
+        for (index, unit) in sources.iter().enumerate() {
+            if let Some(frontend) = frontend_for(&unit.path) {
+                frontend.add_to_graph(&mut graph, &unit.path, &unit.content)?;
+                node_files.resize(graph.nodes.len(), index);
+                edge_files.resize(graph.edges.len(), index);
+            }
+        }
 
+        let map = SourceMap::new(sources);
+        let shift = |span: (usize, usize), index: usize| {
+            let start = map.start(index);
+            (span.0 + start, span.1 + start)
+        };
+        for (node, &index) in graph.nodes.iter_mut().zip(&node_files) {
+            node.span = shift(node.span, index);
+        }
+        for (edge, &index) in graph.edges.iter_mut().zip(&edge_files) {
+            edge.call_site_span = shift(edge.call_site_span, index);
+            edge.return_site_span = edge.return_site_span.map(|span| shift(span, index));
+        }
         Ok(graph)
     }
 
+    /// Parses `source` as the new text of `path`, reusing the previous tree
+    /// of that file for the parts that did not change. Editor changes have
+    /// already been applied to that tree; other changes, such as edits on
+    /// disk, are found by diffing the texts. Different files can be parsed
+    /// concurrently.
+    ///
+    /// Trees are not dropped when a build leaves their file out, since the
+    /// cache is shared by every worker and workspace folder; the budget,
+    /// changes on disk and closed folders drop them.
+    pub fn parse(&self, path: &Path, source: &str) -> Result<Tree> {
+        let old_tree = self.previous.take_tree(path).map(|(old_source, mut tree)| {
+            if let Some(edit) = source_edit(&old_source, source) {
+                tree.edit(&edit);
+            }
+            tree
+        });
+
+        let mut parser = Parser::new();
+        parser
+            .set_language(&get_solidity_language())
+            .context("Failed to set language for Solidity parser")?;
+        let tree = parser
+            .parse(source, old_tree.as_ref())
+            .context("Failed to parse Solidity source")?;
+        self.previous
+            .store_tree(path.to_path_buf(), source.to_string(), tree.clone());
+        Ok(tree)
+    }
+
     #[allow(dead_code)]
     pub fn generate_mermaid_flowchart(&self, graph: &CallGraph) -> Result<String> {
         let config = MermaidConfig::default();
@@ -50,10 +285,17 @@ impl TraverseAdapter {
             .map(|result| result.content)
     }
 
+    #[allow(dead_code)]
     pub fn generate_dot_diagram(&self, graph: &CallGraph) -> Result<String> {
-        let config = DotExportConfig::default();
-        let dot = graph.to_dot("call_graph", &config);
-        Ok(dot)
+        self.generate_dot_with_config(graph, &DotConfig::default())
+    }
+
+    pub fn generate_dot_with_config(
+        &self,
+        graph: &CallGraph,
+        config: &DotConfig,
+    ) -> Result<String> {
+        Ok(dot::render(graph, config))
     }
 
     pub fn generate_mermaid_with_config(
@@ -61,38 +303,34 @@ impl TraverseAdapter {
         graph: &CallGraph,
         config: &MermaidConfig,
     ) -> Result<ChunkedMermaidResult> {
-        let generator = MermaidGenerator::new();
-        let sequence_diagram = generator.to_sequence_diagram(graph);
-        let output = traverse_mermaid::sequence_diagram_writer::write_diagram(&sequence_diagram);
-
-        if !config.no_chunk {
-            let chunk_dir = Some(config.chunk_dir.as_path());
+        let output = mermaid::sequence_diagram(graph);
+        let below_threshold = self
+            .config
+            .chunk_threshold_nodes
+            .is_some_and(|threshold| graph.nodes.len() <= threshold);
 
-            match traverse_mermaid::mermaid_chunker::chunk_mermaid_diagram(&output, chunk_dir) {
-                Ok(chunking_result) => {
-                    let first_chunk_path = chunking_result.output_dir.join("chunk_001.mmd");
-                    let first_chunk_content = std::fs::read_to_string(&first_chunk_path)
-                        .unwrap_or_else(|_| output.clone());
+        if !config.no_chunk && !below_threshold {
+            match write_chunks(&output, config) {
+                Ok((chunk_dir, chunk_count, first_chunk)) => {
+                    let first_chunk_content =
+                        first_chunk.unwrap_or_else(|| config.apply_directive(&output));
 
                     Ok(ChunkedMermaidResult {
                         is_chunked: true,
                         content: first_chunk_content,
                         chunks: Some(vec![MermaidChunk {
                             id: 1,
-                            content: output.clone(),
-                            filename: Some(format!(
-                                "{} chunks generated",
-                                chunking_result.chunk_count
-                            )),
+                            content: config.apply_directive(&output),
+                            filename: Some(format!("{} chunks generated", chunk_count)),
                         }]),
-                        chunk_dir: Some(chunking_result.output_dir),
+                        chunk_dir: Some(chunk_dir),
                     })
                 }
                 Err(e) => {
                     eprintln!("Chunking failed: {}, returning as single diagram", e);
                     Ok(ChunkedMermaidResult {
                         is_chunked: false,
-                        content: output,
+                        content: config.apply_directive(&output),
                         chunks: None,
                         chunk_dir: None,
                     })
@@ -101,7 +339,7 @@ impl TraverseAdapter {
         } else {
             Ok(ChunkedMermaidResult {
                 is_chunked: false,
-                content: output,
+                content: config.apply_directive(&output),
                 chunks: None,
                 chunk_dir: None,
             })
@@ -109,6 +347,239 @@ impl TraverseAdapter {
     }
 }
 
+/// Chunks `diagram` into a staging directory that replaces the configured
+/// chunk directory only once every file, and the manifest if the config
+/// has an input hash, is written. Returns the chunk directory, the number
+/// of chunks and the first chunk's content.
+fn write_chunks(diagram: &str, config: &MermaidConfig) -> Result<(PathBuf, usize, Option<String>)> {
+    // Generations sharing the directory would otherwise swap it out from
+    // under each other.
+    let lock = path_lock(&config.chunk_dir);
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let staging = StagingDir::new(&config.chunk_dir)?;
+    let result =
+        traverse_mermaid::mermaid_chunker::chunk_mermaid_diagram(diagram, Some(staging.path()))?;
+    if config.deterministic {
+        canonicalize_chunks(&result, diagram)?;
+    }
+    apply_directive_to_chunks(&result, config)?;
+    if let Some(input_hash) = &config.input_hash {
+        write_manifest(staging.path(), input_hash)?;
+    }
+    let first_chunk = std::fs::read_to_string(staging.path().join("chunk_001.mmd")).ok();
+    Ok((staging.commit()?, result.chunk_count, first_chunk))
+}
+
+/// Writes `manifest.json` describing the files in chunk directory `dir`.
+fn write_manifest(dir: &Path, input_hash: &str) -> Result<()> {
+    Manifest::build(dir, &manifest::list_files(dir)?, input_hash)?.write(dir)?;
+    Ok(())
+}
+
+/// Re-renders chunk `chunk` of the diagram of `graph` and writes it over its
+/// file in the configured chunk directory, leaving the other chunks, the
+/// index and the metadata as they are. Fails unless the directory holds a
+/// chunking in which that chunk covers the same lines of the diagram, since
+/// its continuation notes must still match its neighbours. The manifest is
+/// rewritten if the config has an input hash. Returns the chunk's path and
+/// content.
+pub fn regenerate_chunk(
+    graph: &CallGraph,
+    config: &MermaidConfig,
+    chunk: usize,
+) -> Result<(PathBuf, String)> {
+    let lock = path_lock(&config.chunk_dir);
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let existing = chunk_lines(&config.chunk_dir, chunk).with_context(|| {
+        format!(
+            "No chunk {} in {}; generate the diagram first",
+            chunk,
+            config.chunk_dir.display()
+        )
+    })?;
+    let diagram = mermaid::sequence_diagram(graph);
+
+    // Chunked in full, since a chunk's boundaries depend on those before it,
+    // but only the requested file leaves the staging directory.
+    let staging = StagingDir::new(&config.chunk_dir)?;
+    let result =
+        traverse_mermaid::mermaid_chunker::chunk_mermaid_diagram(&diagram, Some(staging.path()))?;
+    if config.deterministic {
+        canonicalize_chunks(&result, &diagram)?;
+    }
+    apply_directive_to_chunks(&result, config)?;
+    if chunk_lines(staging.path(), chunk).ok() != Some(existing) {
+        bail!(
+            "Chunk {} no longer covers the same part of the diagram; regenerate the whole diagram",
+            chunk
+        );
+    }
+    let file = format!("chunk_{:03}.mmd", chunk);
+    let content = std::fs::read_to_string(staging.path().join(&file))?;
+    let path = config.chunk_dir.join(file);
+    write_atomic(&path, &content)?;
+    if let Some(input_hash) = &config.input_hash {
+        write_manifest(&config.chunk_dir, input_hash)?;
+    }
+    Ok((path, content))
+}
+
+/// The diagram lines chunk `chunk` covers, from the chunker's
+/// `metadata.json` in `dir`.
+fn chunk_lines(dir: &Path, chunk: usize) -> Result<(u64, u64)> {
+    let metadata: Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("metadata.json"))?)?;
+    metadata["chunks"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|c| c["id"].as_u64() == Some(chunk as u64))
+        .and_then(|c| Some((c["lines"][0].as_u64()?, c["lines"][1].as_u64()?)))
+        .ok_or_else(|| anyhow!("metadata lists no chunk {}", chunk))
+}
+
+/// Prepends the configured `init` directive to every chunk and the index, since
+/// each file is rendered on its own.
+fn apply_directive_to_chunks(result: &ChunkingResult, config: &MermaidConfig) -> Result<()> {
+    if config.init_directive().is_none() {
+        return Ok(());
+    }
+    let chunk_files = (1..=result.chunk_count)
+        .map(|n| result.output_dir.join(format!("chunk_{:03}.mmd", n)))
+        .chain(std::iter::once(result.index_file.clone()));
+    for path in chunk_files {
+        let content = std::fs::read_to_string(&path)?;
+        std::fs::write(&path, config.apply_directive(&content))?;
+    }
+    Ok(())
+}
+
+/// Removes what the chunker leaves to chance: `metadata.json` loses its
+/// `generated` timestamp, and each chunk declares its participants, and
+/// re-activates those still active from the previous chunk, in the order the
+/// full diagram declares them instead of hash-set order.
+fn canonicalize_chunks(result: &ChunkingResult, diagram: &str) -> Result<()> {
+    let lines: Vec<&str> = diagram.lines().collect();
+    let order: Vec<&str> = lines
+        .iter()
+        .filter_map(|line| participant_name(line))
+        .collect();
+    let rank = |name: &str| {
+        let position = order.iter().position(|p| *p == name);
+        (position.unwrap_or(order.len()), name.to_string())
+    };
+
+    let mut metadata: Value =
+        serde_json::from_str(&std::fs::read_to_string(&result.metadata_file)?)?;
+    if let Some(object) = metadata.as_object_mut() {
+        object.remove("generated");
+    }
+    for chunk in metadata["chunks"].as_array_mut().into_iter().flatten() {
+        let path = result
+            .output_dir
+            .join(chunk["file"].as_str().unwrap_or_default());
+        let start = chunk["lines"][0].as_u64().unwrap_or_default() as usize;
+        let active = active_participants(&lines[..start.min(lines.len())]);
+
+        let mut chunk_lines: Vec<String> = std::fs::read_to_string(&path)?
+            .lines()
+            .map(str::to_string)
+            .collect();
+        let participants = sort_chunk_header(&mut chunk_lines, &active, rank);
+        std::fs::write(&path, chunk_lines.join("\n"))?;
+        chunk["participants"] = participants.into();
+    }
+    std::fs::write(
+        &result.metadata_file,
+        serde_json::to_string_pretty(&metadata)?,
+    )?;
+    Ok(())
+}
+
+/// Sorts the participant declarations the chunker put at the top of a chunk
+/// and the `activate` lines it added for `active` participants, re-anchors
+/// its continuation notes on the new first participant, and returns the
+/// sorted participants.
+fn sort_chunk_header(
+    lines: &mut [String],
+    active: &BTreeSet<String>,
+    rank: impl Fn(&str) -> (usize, String),
+) -> Vec<String> {
+    let start = lines
+        .iter()
+        .position(|line| participant_name(line).is_some())
+        .unwrap_or(lines.len());
+    let end = start
+        + lines[start..]
+            .iter()
+            .take_while(|line| participant_name(line).is_some())
+            .count();
+    lines[start..end].sort_by_key(|line| rank(participant_name(line).unwrap_or_default()));
+    let participants: Vec<String> = lines[start..end]
+        .iter()
+        .filter_map(|line| participant_name(line).map(str::to_string))
+        .collect();
+
+    if let Some(first) = participants.first() {
+        for line in lines.iter_mut() {
+            let Some(rest) = line.trim_start().strip_prefix("Note over ") else {
+                continue;
+            };
+            let Some(colon) = rest.find(": ") else {
+                continue;
+            };
+            let note = &rest[colon..];
+            if note.starts_with(": Continued from Chunk ")
+                || note.starts_with(": Continues in Chunk ")
+            {
+                *line = format!("    Note over {}{}", first, note);
+            }
+        }
+    }
+
+    let mut resumed: Vec<&String> = participants
+        .iter()
+        .filter(|p| active.contains(*p))
+        .collect();
+    resumed.sort_by_key(|p| rank(p));
+    if let Some(at) = lines[end..]
+        .iter()
+        .position(|line| line.trim_start().starts_with("activate "))
+        .map(|i| end + i)
+    {
+        if at + resumed.len() <= lines.len() {
+            for (line, participant) in lines[at..at + resumed.len()].iter_mut().zip(resumed) {
+                *line = format!("    activate {}", participant);
+            }
+        }
+    }
+    participants
+}
+
+/// Participants still activated after `lines`, tracked the way the chunker
+/// does.
+fn active_participants(lines: &[&str]) -> BTreeSet<String> {
+    let mut active = BTreeSet::new();
+    for line in lines {
+        let line = line.trim();
+        if let Some(participant) = line.strip_prefix("activate ") {
+            active.insert(participant.to_string());
+        } else if let Some(participant) = line.strip_prefix("deactivate ") {
+            active.remove(participant);
+        }
+    }
+    active
+}
+
+/// Name declared by a `participant <name> [as <alias>]` line.
+fn participant_name(line: &str) -> Option<&str> {
+    let declaration = line.trim().strip_prefix("participant ")?;
+    let name = declaration
+        .split_once(" as ")
+        .map_or(declaration, |(name, _)| name);
+    Some(name.trim())
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ChunkedMermaidResult {
     pub is_chunked: bool,
@@ -123,3 +594,48 @@ pub struct MermaidChunk {
     pub content: String,
     pub filename: Option<String>,
 }
+
+/// The single edit turning `old` into `new`: the span between their common
+/// prefix and common suffix. `None` if they are equal.
+fn source_edit(old: &str, new: &str) -> Option<InputEdit> {
+    if old == new {
+        return None;
+    }
+    let mut prefix = old
+        .bytes()
+        .zip(new.bytes())
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+
+    let old_end_byte = old.len() - suffix;
+    let new_end_byte = new.len() - suffix;
+    Some(InputEdit {
+        start_byte: prefix,
+        old_end_byte,
+        new_end_byte,
+        start_position: point_at(old, prefix),
+        old_end_position: point_at(old, old_end_byte),
+        new_end_position: point_at(new, new_end_byte),
+    })
+}
+
+fn point_at(text: &str, byte: usize) -> Point {
+    let before = &text[..byte];
+    let row = before.matches('\n').count();
+    let column = byte - before.rfind('\n').map_or(0, |i| i + 1);
+    Point { row, column }
+}
@@ -1,5 +1,13 @@
+use crate::analysis::imports;
+use crate::foundry;
+use crate::git;
+use crate::traverse_adapter::source_extensions;
+use anyhow::Result;
+use lsp_types::{Position, Url};
 use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
 use tokio::runtime;
+use walkdir::WalkDir;
 
 pub static TOKIO_RUNTIME: Lazy<runtime::Runtime> = Lazy::new(|| {
     runtime::Builder::new_multi_thread()
@@ -8,3 +16,134 @@ pub static TOKIO_RUNTIME: Lazy<runtime::Runtime> = Lazy::new(|| {
         .build()
         .expect("Failed to build Tokio runtime")
 });
+
+/// Dependency and build directories that are never analyzed.
+pub const SKIPPED_DIRS: &[&str] = &["node_modules", "build", "cache", ".git"];
+
+/// All `.sol` files under `workspace_folder`, skipping dependency and build
+/// directories. Directories are walked in name order so the combined source,
+/// and everything generated from it, does not depend on the file system.
+pub fn find_solidity_files(workspace_folder: impl AsRef<Path>) -> Result<Vec<Url>> {
+    find_files(workspace_folder, &["sol"])
+}
+
+/// Solidity files and the files of every other language the call graph
+/// reads, which workspace commands analyze.
+pub fn find_source_files(workspace_folder: impl AsRef<Path>) -> Result<Vec<Url>> {
+    find_files(workspace_folder, &source_extensions())
+}
+
+fn find_files(workspace_folder: impl AsRef<Path>, extensions: &[&str]) -> Result<Vec<Url>> {
+    let mut files = Vec::new();
+
+    for entry in WalkDir::new(workspace_folder.as_ref())
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            !e.path()
+                .components()
+                .any(|c| SKIPPED_DIRS.iter().any(|dir| c.as_os_str() == *dir))
+        })
+    {
+        let entry = entry?;
+        let extension = entry.path().extension().and_then(|s| s.to_str());
+        if extension.is_some_and(|e| extensions.contains(&e)) {
+            let uri =
+                Url::from_file_path(entry.path()).map_err(|_| anyhow::anyhow!("Invalid path"))?;
+            files.push(uri);
+        }
+    }
+
+    Ok(files)
+}
+
+/// The files in `sol_files` changed since `rev`, plus every workspace file
+/// they import, keeping `sol_files` order.
+pub fn files_changed_since(
+    workspace_folder: impl AsRef<Path>,
+    sol_files: &[Url],
+    rev: &str,
+) -> Result<Vec<Url>> {
+    let workspace_folder = workspace_folder.as_ref();
+    let paths: Vec<PathBuf> = sol_files
+        .iter()
+        .filter_map(|uri| uri.to_file_path().ok())
+        .collect();
+    let changed = git::changed_files(workspace_folder, rev)?;
+    let remappings = foundry::remappings(workspace_folder)?;
+    let scope = imports::import_closure(workspace_folder, &paths, &changed, &remappings)?;
+    Ok(sol_files
+        .iter()
+        .filter(|uri| uri.to_file_path().is_ok_and(|path| scope.contains(&path)))
+        .cloned()
+        .collect())
+}
+
+/// The byte offset of `position` in `text`, whose character counts UTF-16
+/// code units as LSP positions do. Positions past the end of a line or of
+/// the text are clamped, as in [`byte_column`].
+pub fn offset_at(text: &str, position: Position) -> usize {
+    let line_start = match position.line.checked_sub(1) {
+        None => 0,
+        Some(previous) => match text.match_indices('\n').nth(previous as usize) {
+            Some((index, _)) => index + 1,
+            None => return text.len(),
+        },
+    };
+    let line = text[line_start..]
+        .split_inclusive('\n')
+        .next()
+        .unwrap_or("");
+    line_start + byte_column(line, position.character)
+}
+
+/// The LSP position of the byte offset `offset` in `text`, clamped as in
+/// [`utf16_column`].
+pub fn position_at(text: &str, offset: usize) -> Position {
+    let offset = offset.min(text.len());
+    let before = &text.as_bytes()[..offset];
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |index| index + 1);
+    let line = text[line_start..]
+        .split_inclusive('\n')
+        .next()
+        .unwrap_or("");
+    Position::new(
+        before.iter().filter(|&&b| b == b'\n').count() as u32,
+        utf16_column(line, offset - line_start),
+    )
+}
+
+/// The byte column of the UTF-16 column `character` in `line`. Columns
+/// past the end of the line, before its `\n` or `\r\n` ending, are clamped
+/// to it, and columns inside a character to its start.
+pub fn byte_column(line: &str, character: u32) -> usize {
+    let line = without_line_ending(line);
+    let mut units = 0;
+    for (index, c) in line.char_indices() {
+        units += c.len_utf16();
+        if units > character as usize {
+            return index;
+        }
+    }
+    line.len()
+}
+
+/// The UTF-16 column of the byte column `column` in `line`, clamped like
+/// [`byte_column`].
+pub fn utf16_column(line: &str, column: usize) -> u32 {
+    let line = without_line_ending(line);
+    let mut column = column.min(line.len());
+    while !line.is_char_boundary(column) {
+        column -= 1;
+    }
+    line[..column].encode_utf16().count() as u32
+}
+
+fn without_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
@@ -0,0 +1,635 @@
+//! Vyper sources, behind the `vyper` feature.
+//!
+//! A line-based reader covering what a call graph needs: `def`s with their
+//! decorators, `interface` blocks, storage variables and the calls in each
+//! body. A file is one contract named after the file, as Vyper has it.
+//! `__init__` becomes the contract's constructor, `@external` functions are
+//! external and the rest internal.
+//!
+//! Calls through an interface (`IERC20(addr).transfer(...)`,
+//! `extcall self.token.transfer(...)` or a local or parameter of interface
+//! type) resolve to any declaration of that name already in the graph, so a
+//! Vyper contract calling a Solidity interface or contract joins the same
+//! call graph. Interfaces only declared in Vyper get nodes of their own.
+//! `log Event(...)` is drawn as an emit, like Solidity's.
+
+use crate::traverse_adapter::Frontend;
+use crate::yul::split_arguments;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, EdgeType, NodeType, ParameterInfo, Visibility};
+
+pub struct VyperFrontend;
+
+impl Frontend for VyperFrontend {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["vy"]
+    }
+
+    fn add_to_graph(&self, graph: &mut CallGraph, path: &Path, source: &str) -> Result<()> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Vyper");
+        add_to_graph(graph, source, name);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VyperModule {
+    pub interfaces: Vec<VyperInterface>,
+    /// Type of each storage variable, with `public(...)` and the like
+    /// stripped.
+    pub storage: HashMap<String, String>,
+    pub functions: Vec<VyperFunction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VyperInterface {
+    pub name: String,
+    pub span: (usize, usize),
+    /// Declared methods, without bodies or calls.
+    pub functions: Vec<VyperFunction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VyperFunction {
+    pub name: String,
+    /// From the `def` keyword to the end of the body.
+    pub span: (usize, usize),
+    /// Decorator names without the `@`, e.g. `external` or `view`.
+    pub decorators: Vec<String>,
+    /// `(name, type)` pairs.
+    pub parameters: Vec<(String, String)>,
+    pub returns: Option<String>,
+    pub calls: Vec<VyperCall>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VyperCall {
+    pub target: CallTarget,
+    /// From the callee to the closing parenthesis.
+    pub span: (usize, usize),
+    pub arguments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallTarget {
+    /// `self.function(...)`
+    Internal(String),
+    /// A call through a value of interface or contract type.
+    External { interface: String, function: String },
+    /// `log Event(...)`
+    Event(String),
+}
+
+/// Declarations and calls of `source`.
+pub fn parse(source: &str) -> VyperModule {
+    let cleaned = strip_comments(source);
+    let lines = logical_lines(&cleaned);
+    let mut module = VyperModule::default();
+    let mut decorators = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let text = line.text(&cleaned).trim();
+        i += 1;
+        if line.indent > 0 || text.is_empty() {
+            continue;
+        }
+        // Lines indented under this one, up to the next top-level line.
+        let mut body_end = i;
+        while body_end < lines.len()
+            && (lines[body_end].indent > 0 || lines[body_end].text(&cleaned).trim().is_empty())
+        {
+            body_end += 1;
+        }
+        let body = &lines[i..body_end];
+        let end = body
+            .iter()
+            .rev()
+            .find(|l| !l.text(&cleaned).trim().is_empty())
+            .map_or(line.end, |l| l.end);
+
+        if let Some(decorator) = text.strip_prefix('@') {
+            let name = decorator.split(['(', ' ']).next().unwrap_or_default();
+            decorators.push(name.to_string());
+            continue;
+        }
+        if text.starts_with("def ") {
+            let mut function = signature(text, (line.start, end));
+            function.decorators = std::mem::take(&mut decorators);
+            let mut types = module.storage.clone();
+            types.extend(function.parameters.iter().cloned());
+            for body_line in body {
+                collect_calls(
+                    &cleaned,
+                    body_line,
+                    &mut types,
+                    &module.storage,
+                    &mut function.calls,
+                );
+            }
+            module.functions.push(function);
+        } else if let Some(name) = text
+            .strip_prefix("interface ")
+            .and_then(|rest| rest.strip_suffix(':'))
+        {
+            let functions = body
+                .iter()
+                .map(|l| (l, l.text(&cleaned).trim()))
+                .filter(|(_, t)| t.starts_with("def "))
+                .map(|(l, t)| signature(t, (l.start, l.end)))
+                .collect();
+            module.interfaces.push(VyperInterface {
+                name: name.trim().to_string(),
+                span: (line.start, end),
+                functions,
+            });
+        } else if let Some((name, ty)) = annotation(text) {
+            module.storage.insert(name, ty);
+        }
+        decorators.clear();
+        i = body_end;
+    }
+    module
+}
+
+/// Adds the contract in `source` to `graph` as `contract` and returns how
+/// many nodes were added. Spans are relative to `source`.
+pub fn add_to_graph(graph: &mut CallGraph, source: &str, contract: &str) -> usize {
+    let first = graph.nodes.len();
+    let module = parse(source);
+    for interface in &module.interfaces {
+        if find_node(graph, &interface.name, &interface.name).is_some() {
+            continue;
+        }
+        graph.add_node(
+            interface.name.clone(),
+            NodeType::Interface,
+            Some(interface.name.clone()),
+            Visibility::Default,
+            interface.span,
+        );
+        for function in &interface.functions {
+            add_function(
+                graph,
+                function,
+                &interface.name,
+                NodeType::Function,
+                Visibility::External,
+            );
+        }
+    }
+
+    let ids: Vec<usize> = module
+        .functions
+        .iter()
+        .map(|function| {
+            let (node_type, visibility) = if function.name == "__init__" {
+                (NodeType::Constructor, Visibility::Public)
+            } else if function.decorators.iter().any(|d| d == "external") {
+                (NodeType::Function, Visibility::External)
+            } else {
+                (NodeType::Function, Visibility::Internal)
+            };
+            add_function(graph, function, contract, node_type, visibility)
+        })
+        .collect();
+    let internal: HashMap<&str, usize> = module
+        .functions
+        .iter()
+        .zip(&ids)
+        .map(|(function, &id)| (function.name.as_str(), id))
+        .collect();
+
+    for (function, &caller) in module.functions.iter().zip(&ids) {
+        let mut sequence = 0;
+        for call in &function.calls {
+            let (callee, event) = match &call.target {
+                CallTarget::Internal(name) => match internal.get(name.as_str()) {
+                    Some(&id) => (id, None),
+                    None => continue,
+                },
+                CallTarget::External {
+                    interface,
+                    function,
+                } => match find_node(graph, interface, function) {
+                    Some(id) => (id, None),
+                    None => continue,
+                },
+                CallTarget::Event(event) => {
+                    (synthetic_node(graph, "EVM", NodeType::Evm), Some(event))
+                }
+            };
+            sequence += 1;
+            graph.add_edge(
+                caller,
+                callee,
+                EdgeType::Call,
+                call.span,
+                None,
+                sequence,
+                None,
+                Some(call.arguments.clone()),
+                event.cloned(),
+                None,
+            );
+            if event.is_some() {
+                let listener = synthetic_node(graph, "EventListener", NodeType::EventListener);
+                sequence += 1;
+                graph.add_edge(
+                    callee,
+                    listener,
+                    EdgeType::Call,
+                    call.span,
+                    None,
+                    sequence,
+                    None,
+                    Some(call.arguments.clone()),
+                    event.cloned(),
+                    None,
+                );
+            }
+        }
+    }
+    graph.nodes.len() - first
+}
+
+fn add_function(
+    graph: &mut CallGraph,
+    function: &VyperFunction,
+    contract: &str,
+    node_type: NodeType,
+    visibility: Visibility,
+) -> usize {
+    // Constructors are named after their contract, as in Solidity.
+    let name = if node_type == NodeType::Constructor {
+        contract.to_string()
+    } else {
+        function.name.clone()
+    };
+    let id = graph.add_node(
+        name,
+        node_type,
+        Some(contract.to_string()),
+        visibility,
+        function.span,
+    );
+    let node = &mut graph.nodes[id];
+    node.parameters = function
+        .parameters
+        .iter()
+        .map(|(name, ty)| ParameterInfo {
+            name: name.clone(),
+            param_type: ty.clone(),
+            description: None,
+        })
+        .collect();
+    node.has_explicit_return = function.returns.is_some();
+    node.declared_return_type = function.returns.clone();
+    id
+}
+
+/// A callable or interface node named `name` in `contract`.
+fn find_node(graph: &CallGraph, contract: &str, name: &str) -> Option<usize> {
+    graph
+        .nodes
+        .iter()
+        .find(|node| {
+            node.name == name
+                && node.contract_name.as_deref() == Some(contract)
+                && matches!(
+                    node.node_type,
+                    NodeType::Function | NodeType::Constructor | NodeType::Interface
+                )
+        })
+        .map(|node| node.id)
+}
+
+/// The shared EVM or event listener node, created on first use.
+fn synthetic_node(graph: &mut CallGraph, name: &str, node_type: NodeType) -> usize {
+    match graph
+        .nodes
+        .iter()
+        .find(|node| node.node_type == node_type && node.contract_name.is_none())
+    {
+        Some(node) => node.id,
+        None => graph.add_node(
+            name.to_string(),
+            node_type,
+            None,
+            Visibility::Default,
+            (0, 0),
+        ),
+    }
+}
+
+/// `def name(a: T, b: U = 1) -> R:` with `span` covering the function.
+fn signature(text: &str, span: (usize, usize)) -> VyperFunction {
+    let rest = text.trim_start_matches("def ").trim();
+    let open = rest.find('(').unwrap_or(rest.len());
+    let close = matching_paren(rest, open).unwrap_or(rest.len());
+    let parameters = if open < close {
+        split_arguments(rest[open + 1..close].trim())
+            .into_iter()
+            .filter_map(|parameter| {
+                let parameter = parameter.split('=').next().unwrap_or_default();
+                let (name, ty) = parameter.split_once(':')?;
+                Some((name.trim().to_string(), ty.trim().to_string()))
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let returns = rest
+        .get(close + 1..)
+        .and_then(|tail| tail.trim().strip_prefix("->"))
+        .map(|tail| {
+            // Interface methods end in `: view` and the like.
+            let ty = tail.rsplit_once(':').map_or(tail, |(ty, _)| ty);
+            ty.trim().to_string()
+        })
+        .filter(|ty| !ty.is_empty());
+    VyperFunction {
+        name: rest[..open].trim().to_string(),
+        span,
+        decorators: Vec::new(),
+        parameters,
+        returns,
+        calls: Vec::new(),
+    }
+}
+
+/// `name: T` or `name: public(T) = ...`, as `(name, T)`.
+fn annotation(text: &str) -> Option<(String, String)> {
+    let (name, ty) = text.split_once(':')?;
+    let name = name.trim();
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') || name.is_empty() {
+        return None;
+    }
+    let mut ty = ty.split('=').next().unwrap_or_default().trim();
+    for wrapper in ["public", "immutable", "constant", "transient"] {
+        if let Some(inner) = ty
+            .strip_prefix(wrapper)
+            .and_then(|t| t.strip_prefix('('))
+            .and_then(|t| t.strip_suffix(')'))
+        {
+            ty = inner.trim();
+        }
+    }
+    Some((name.to_string(), ty.to_string()))
+}
+
+/// Records the calls on `line` in source order. Annotated locals are added
+/// to `types` so later calls through them resolve.
+fn collect_calls(
+    source: &str,
+    line: &Line,
+    types: &mut HashMap<String, String>,
+    storage: &HashMap<String, String>,
+    calls: &mut Vec<VyperCall>,
+) {
+    let text = line.text(source);
+    if let Some((name, ty)) = annotation(text.trim()) {
+        types.insert(name, ty);
+    }
+    let tokens = tokenize(text, line.start);
+    for (i, token) in tokens.iter().enumerate() {
+        let opens = |j: usize| tokens.get(j).is_some_and(|t| t.text == "(");
+        if !opens(i + 1) || !is_name(&token.text) {
+            continue;
+        }
+        let Some(close) = closing(&tokens, i + 1) else {
+            continue;
+        };
+        let previous = i.checked_sub(1).map(|p| tokens[p].text.as_str());
+        let parts: Vec<&str> = token.text.split('.').collect();
+        let target = match parts.as_slice() {
+            [event] if previous == Some("log") => CallTarget::Event(event.to_string()),
+            ["self", function] => CallTarget::Internal(function.to_string()),
+            ["self", variable, function] => match storage.get(*variable) {
+                Some(ty) => CallTarget::External {
+                    interface: ty.clone(),
+                    function: function.to_string(),
+                },
+                None => continue,
+            },
+            [variable, function] => match types.get(*variable) {
+                Some(ty) => CallTarget::External {
+                    interface: ty.clone(),
+                    function: function.to_string(),
+                },
+                None => continue,
+            },
+            // `Interface(address).function(...)`
+            [interface] => match tokens.get(close + 1) {
+                Some(next) if next.text.starts_with('.') && opens(close + 2) => {
+                    let Some(end) = closing(&tokens, close + 2) else {
+                        continue;
+                    };
+                    calls.push(VyperCall {
+                        target: CallTarget::External {
+                            interface: interface.to_string(),
+                            function: next.text[1..].to_string(),
+                        },
+                        span: (token.start, tokens[end].end),
+                        arguments: arguments(text, line.start, &tokens, close + 2, end),
+                    });
+                    continue;
+                }
+                _ => continue,
+            },
+            _ => continue,
+        };
+        calls.push(VyperCall {
+            target,
+            span: (token.start, tokens[close].end),
+            arguments: arguments(text, line.start, &tokens, i + 1, close),
+        });
+    }
+}
+
+fn arguments(text: &str, base: usize, tokens: &[Token], open: usize, close: usize) -> Vec<String> {
+    let inner = &text[tokens[open].end - base..tokens[close].start - base];
+    // Vyper allows a trailing comma.
+    let mut arguments = split_arguments(inner.trim());
+    arguments.retain(|argument| !argument.is_empty());
+    arguments
+}
+
+fn is_name(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+}
+
+/// Index of the `)` closing the `(` at `open`.
+fn closing(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.text.as_str() {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn matching_paren(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices().skip_while(|(i, _)| *i < open) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+/// Dotted names such as `self.token.transfer` are one token, as is a
+/// `.name` following a closing parenthesis. Offsets are shifted by `base`.
+fn tokenize(text: &str, base: usize) -> Vec<Token> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        let name_start = |j: usize| {
+            bytes
+                .get(j)
+                .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'_')
+        };
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == b'"' || c == b'\'' {
+            i += 1;
+            while i < bytes.len() && bytes[i] != c {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(bytes.len());
+        } else if c.is_ascii_alphanumeric() || c == b'_' || (c == b'.' && name_start(i + 1)) {
+            i += 1;
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphanumeric()
+                    || bytes[i] == b'_'
+                    || (bytes[i] == b'.' && name_start(i + 1)))
+            {
+                i += 1;
+            }
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+        tokens.push(Token {
+            text: text[start..i].to_string(),
+            start: base + start,
+            end: base + i,
+        });
+    }
+    tokens
+}
+
+/// A statement, which may run over several physical lines inside brackets.
+#[derive(Debug, Clone)]
+struct Line {
+    indent: usize,
+    start: usize,
+    end: usize,
+}
+
+impl Line {
+    fn text<'a>(&self, source: &'a str) -> &'a str {
+        &source[self.start..self.end]
+    }
+}
+
+fn logical_lines(source: &str) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let mut depth = 0i32;
+    let mut offset = 0;
+    for physical in source.split_inclusive('\n') {
+        let start = offset;
+        offset += physical.len();
+        let end = start + physical.trim_end().len();
+        let continued = depth > 0;
+        for c in physical.chars() {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        depth = depth.max(0);
+        match lines.last_mut() {
+            Some(last) if continued => last.end = end.max(last.end),
+            _ => lines.push(Line {
+                indent: physical.len() - physical.trim_start_matches([' ', '\t']).len(),
+                start,
+                end,
+            }),
+        }
+    }
+    lines
+}
+
+/// `source` with comments and docstrings blanked out, byte for byte, so
+/// offsets still match the original.
+fn strip_comments(source: &str) -> String {
+    let mut bytes = source.as_bytes().to_vec();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    bytes[i] = b' ';
+                    i += 1;
+                }
+            }
+            quote @ (b'"' | b'\'') => {
+                let triple = [quote; 3];
+                if bytes[i..].starts_with(&triple) {
+                    let end = source[i + 3..]
+                        .find(std::str::from_utf8(&triple).unwrap_or_default())
+                        .map_or(bytes.len(), |n| i + n + 6);
+                    for byte in &mut bytes[i..end] {
+                        if *byte != b'\n' {
+                            *byte = b' ';
+                        }
+                    }
+                    i = end;
+                } else {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != quote && bytes[i] != b'\n' {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    i += 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    // Only whole characters were blanked, so this cannot fail.
+    String::from_utf8(bytes).unwrap_or_default()
+}
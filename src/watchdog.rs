@@ -0,0 +1,612 @@
+//! Supervises the generator workers so one pathological request cannot wedge
+//! the server.
+//!
+//! Requests run on a small pool of workers, each handed one request at a
+//! time, so a slow command does not hold up the next one. When a request
+//! runs past the wall-clock limit, the phase it was in is logged, the caller
+//! gets a [`GenerationTimedOut`] error and a fresh worker takes the stuck
+//! one's place. Threads cannot be killed, so the stuck worker is abandoned:
+//! its channel is dropped and it exits once the request finishes.
+//! A request that panics fails with
+//! [`WorkerPanicked`](crate::generator_worker::WorkerPanicked) and its
+//! worker carries on; if the pool itself fails, [`run_restarting`] starts a
+//! new one.
+//!
+//! Waiting requests are deduplicated: identical ones share one run, and a
+//! newer workspace-wide diagram generation cancels an older one for the
+//! same folder, so "Generate All" twice in a row runs once. A shared run
+//! is cancelled only once every caller waiting for it has cancelled; the
+//! others still get its result.
+
+use crate::generator_worker::{
+    panic_message, CancelToken, GenerationRequest, GeneratorWorker, RequestCancelled, ShuttingDown,
+    WorkerPanicked,
+};
+use crate::utils::TOKIO_RUNTIME;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tracing::{debug, error, info, warn, Span};
+
+/// Pool size used when `generation_workers` is not configured.
+pub const DEFAULT_GENERATION_WORKERS: usize = 2;
+
+/// Limit used when `generation_timeout_secs` is not configured.
+pub const DEFAULT_GENERATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Error returned for a request that exceeded the generation time limit.
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationTimedOut {
+    pub request: &'static str,
+    pub limit_secs: u64,
+    /// Phase the worker was in when it was abandoned.
+    pub phase: Option<&'static str>,
+}
+
+impl fmt::Display for GenerationTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} did not finish within {}s",
+            self.request, self.limit_secs
+        )?;
+        if let Some(phase) = self.phase {
+            write!(f, " (stuck while {})", phase)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GenerationTimedOut {}
+
+/// Time a request spent in one phase.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub ms: u64,
+}
+
+/// What the worker is doing, shared with the watchdog.
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTracker(Arc<Mutex<Option<Job>>>);
+
+#[derive(Debug)]
+struct Job {
+    request: &'static str,
+    started: Instant,
+    phases: Vec<(&'static str, Instant)>,
+}
+
+impl PhaseTracker {
+    pub fn start(&self, request: &'static str) {
+        *self.lock() = Some(Job {
+            request,
+            started: Instant::now(),
+            phases: Vec::new(),
+        });
+    }
+
+    pub fn enter(&self, phase: &'static str) {
+        if let Some(job) = self.lock().as_mut() {
+            job.phases.push((phase, Instant::now()));
+        }
+    }
+
+    pub fn finish(&self) {
+        *self.lock() = None;
+    }
+
+    /// The current phase of the running request, if any.
+    pub fn current(&self) -> Option<&'static str> {
+        self.lock()
+            .as_ref()
+            .and_then(|job| job.phases.last().map(|(phase, _)| *phase))
+    }
+
+    /// Time the running request spent in each phase so far, in the order
+    /// the phases were first entered; a phase entered again adds to its
+    /// first entry.
+    pub fn timings(&self) -> Vec<PhaseTiming> {
+        let guard = self.lock();
+        let Some(job) = guard.as_ref() else {
+            return Vec::new();
+        };
+        let now = Instant::now();
+        let mut timings: Vec<PhaseTiming> = Vec::new();
+        for (i, (phase, entered)) in job.phases.iter().enumerate() {
+            let left = job.phases.get(i + 1).map_or(now, |(_, next)| *next);
+            let ms = (left - *entered).as_millis() as u64;
+            match timings.iter_mut().find(|timing| timing.phase == *phase) {
+                Some(timing) => timing.ms += ms,
+                None => timings.push(PhaseTiming {
+                    phase: phase.to_string(),
+                    ms,
+                }),
+            }
+        }
+        timings
+    }
+
+    /// One line per phase of the running request with the time spent in it.
+    pub fn dump(&self) -> String {
+        let guard = self.lock();
+        let Some(job) = guard.as_ref() else {
+            return "no request running".to_string();
+        };
+        let now = Instant::now();
+        let mut dump = format!("{} running for {:.1?}", job.request, now - job.started);
+        for (i, (phase, entered)) in job.phases.iter().enumerate() {
+            let left = job.phases.get(i + 1).map_or(now, |(_, next)| *next);
+            let marker = if i + 1 == job.phases.len() {
+                " <- current"
+            } else {
+                ""
+            };
+            dump.push_str(&format!("\n  {}: {:.1?}{}", phase, left - *entered, marker));
+        }
+        dump
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Job>> {
+        // A worker that panicked mid-update leaves a usable state behind.
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A running worker thread as seen by the watchdog.
+pub struct WorkerHandle {
+    pub tx: mpsc::Sender<GenerationRequest>,
+    pub phases: PhaseTracker,
+    pub thread: JoinHandle<()>,
+}
+
+/// Starts `worker` on its own thread.
+pub fn spawn_worker(worker: GeneratorWorker) -> Result<WorkerHandle> {
+    let phases = worker.phases();
+    let (tx, rx) = mpsc::channel();
+    let thread = thread::Builder::new()
+        .name("generator-worker".into())
+        .spawn(move || worker.run(rx))?;
+    Ok(WorkerHandle { tx, phases, thread })
+}
+
+/// Pause before [`run_restarting`] starts a new pool.
+pub const RESTART_DELAY: Duration = Duration::from_millis(500);
+
+/// How often the dispatcher looks for new requests while every worker is
+/// busy, and supervisors for callers that have cancelled.
+const QUEUE_POLL: Duration = Duration::from_millis(20);
+
+/// A request waiting for a worker, with the callers of identical requests
+/// coalesced into it.
+struct Queued {
+    request: GenerationRequest,
+    followers: Vec<Caller>,
+}
+
+impl Queued {
+    /// Whether the request and every request coalesced into it were
+    /// cancelled.
+    fn cancelled(&self) -> bool {
+        is_cancelled(&self.request)
+            && self
+                .followers
+                .iter()
+                .all(|caller| caller.cancel.load(Ordering::Relaxed))
+    }
+}
+
+type Reply = oneshot::Sender<Result<String>>;
+
+/// One caller waiting for a possibly shared result, with the token it
+/// cancels its own request with.
+struct Caller {
+    reply: Reply,
+    cancel: CancelToken,
+}
+
+/// A workspace-wide generation running on a worker.
+struct Running {
+    scope: (&'static str, PathBuf),
+    cancel: CancelToken,
+}
+
+/// Forwards requests from `rx` to a pool of `workers` workers created by
+/// `spawn`, replacing a worker whenever its request exceeds `limit`.
+/// Returns after `Shutdown`, once every worker has finished.
+///
+/// Requests that arrive while every worker is busy wait in a queue, where
+/// one identical to a request already waiting shares that request's
+/// result. A workspace-wide diagram generation cancels an older one of the
+/// same kind for the same workspace folder, waiting or running.
+#[allow(dead_code)]
+pub fn run(
+    rx: mpsc::Receiver<GenerationRequest>,
+    limit: Option<Duration>,
+    workers: usize,
+    spawn: impl FnMut() -> Result<WorkerHandle>,
+) -> Result<()> {
+    dispatch(&rx, limit, workers, spawn)
+}
+
+/// [`run`], starting over with a fresh pool whenever the pool cannot be
+/// started or the dispatcher panics. Requests waiting at that point fail
+/// with the reason rather than hang; later ones go to the new pool.
+pub fn run_restarting(
+    rx: mpsc::Receiver<GenerationRequest>,
+    limit: Option<Duration>,
+    workers: usize,
+    mut spawn: impl FnMut() -> Result<WorkerHandle>,
+) {
+    loop {
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            dispatch(&rx, limit, workers, &mut spawn)
+        }));
+        let reason = match outcome {
+            Ok(Ok(())) => return,
+            Ok(Err(error)) => format!("{:#}", error),
+            Err(payload) => format!("panicked: {}", panic_message(payload.as_ref())),
+        };
+        error!(
+            "Generation watchdog failed ({}), restarting in {:?}",
+            reason, RESTART_DELAY
+        );
+        // Collected first, so only requests sent before the failure are
+        // failed with it.
+        let pending: Vec<_> = rx.try_iter().collect();
+        let shutdown = pending
+            .iter()
+            .any(|request| matches!(request, GenerationRequest::Shutdown));
+        for request in pending {
+            if let Some(reply) = request.into_reply() {
+                let _ = reply.send(Err(anyhow!("Generator workers failed: {}", reason)));
+            }
+        }
+        if shutdown {
+            return;
+        }
+        thread::sleep(RESTART_DELAY);
+    }
+}
+
+fn dispatch(
+    rx: &mpsc::Receiver<GenerationRequest>,
+    limit: Option<Duration>,
+    workers: usize,
+    mut spawn: impl FnMut() -> Result<WorkerHandle>,
+) -> Result<()> {
+    let mut pool = Vec::new();
+    for _ in 0..workers.max(1) {
+        pool.push(spawn()?);
+    }
+    info!(
+        "Generation watchdog started ({} workers, limit: {:?})",
+        pool.len(),
+        limit
+    );
+
+    // Supervisors report a slot here once its request is done, and whether
+    // its worker has to be replaced first.
+    let (done_tx, done_rx) = mpsc::channel::<(usize, bool)>();
+    let mut idle: Vec<usize> = (0..pool.len()).rev().collect();
+    let mut busy = 0;
+    let mut running: Vec<Option<Running>> = pool.iter().map(|_| None).collect();
+    let mut queue = VecDeque::new();
+    loop {
+        let slot = match idle.pop() {
+            Some(slot) => slot,
+            None => loop {
+                match done_rx.recv_timeout(QUEUE_POLL) {
+                    Ok((slot, replace)) => {
+                        busy -= 1;
+                        running[slot] = None;
+                        if replace {
+                            pool[slot] = spawn()?;
+                        }
+                        break slot;
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        for request in rx.try_iter() {
+                            admit(request, &mut queue, &running);
+                        }
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        return Err(anyhow!("Generation supervisors stopped"));
+                    }
+                }
+            },
+        };
+        if queue.is_empty() {
+            let Ok(request) = rx.recv() else {
+                break;
+            };
+            admit(request, &mut queue, &running);
+        }
+        let Some(Queued {
+            mut request,
+            mut followers,
+        }) = queue.pop_front()
+        else {
+            idle.push(slot);
+            continue;
+        };
+        if let GenerationRequest::Shutdown = request {
+            let pending = queue
+                .drain(..)
+                .chain(rx.try_iter().map(|request| Queued {
+                    request,
+                    followers: Vec::new(),
+                }))
+                .flat_map(|queued| {
+                    queued
+                        .request
+                        .into_reply()
+                        .into_iter()
+                        .chain(queued.followers.into_iter().map(|caller| caller.reply))
+                });
+            for pending in pending {
+                let _ = pending.send(Err(ShuttingDown.into()));
+            }
+            // Running requests finish or time out first; workers that timed
+            // out are abandoned rather than joined.
+            let mut abandoned = vec![false; pool.len()];
+            for _ in 0..busy {
+                let (slot, replace) = done_rx.recv()?;
+                abandoned[slot] = replace;
+            }
+            for (worker, abandoned) in pool.into_iter().zip(abandoned) {
+                if !abandoned {
+                    let _ = worker.tx.send(GenerationRequest::Shutdown);
+                    let _ = worker.thread.join();
+                }
+            }
+            break;
+        }
+
+        let kind = request.kind();
+        let scope = request
+            .workspace_scope()
+            .map(|(kind, folder)| (kind, folder.to_path_buf()));
+        // The worker checks a token of its own, set once every caller has
+        // cancelled, so one caller cancelling leaves the others a result.
+        let job = CancelToken::default().with_span(
+            request
+                .cancel_token()
+                .map(CancelToken::span)
+                .unwrap_or_else(Span::none),
+        );
+        let cancel = request
+            .replace_cancel_token(job.clone())
+            .unwrap_or_default();
+        let (inner_tx, inner_rx) = oneshot::channel();
+        let Some(reply) = request.replace_reply(inner_tx) else {
+            idle.push(slot);
+            continue;
+        };
+        followers.insert(0, Caller { reply, cancel });
+        answer_cancelled(&mut followers);
+        if followers.is_empty() {
+            debug!("Skipping {}, cancelled by every caller", kind);
+            idle.push(slot);
+            continue;
+        }
+        if let Err(mpsc::SendError(request)) = pool[slot].tx.send(request) {
+            warn!("Generator worker is gone, starting a new one");
+            pool[slot] = spawn()?;
+            if pool[slot].tx.send(request).is_err() {
+                for caller in followers {
+                    let _ = caller
+                        .reply
+                        .send(Err(anyhow!("Generator worker is not running")));
+                }
+                idle.push(slot);
+                continue;
+            }
+        }
+
+        let span = job.span();
+        running[slot] = scope.map(|scope| Running {
+            scope,
+            cancel: job.clone(),
+        });
+        let phases = pool[slot].phases.clone();
+        let done_tx = done_tx.clone();
+        busy += 1;
+        thread::Builder::new()
+            .name("generator-supervisor".into())
+            .spawn(move || {
+                let _entered = span.enter();
+                let replace = supervise(kind, inner_rx, followers, &job, limit, &phases);
+                let _ = done_tx.send((slot, replace));
+            })?;
+    }
+
+    info!("Generation watchdog stopped");
+    Ok(())
+}
+
+/// Queues `request`, coalescing it into an identical waiting request and
+/// cancelling the older generations it supersedes.
+fn admit(
+    mut request: GenerationRequest,
+    queue: &mut VecDeque<Queued>,
+    running: &[Option<Running>],
+) {
+    let span = request.cancel_token().map(CancelToken::span);
+    let _entered = span.as_ref().map(|span| span.enter());
+    // Requests after a shutdown are rejected, not run.
+    let shutting_down = queue
+        .iter()
+        .any(|queued| matches!(queued.request, GenerationRequest::Shutdown));
+    if let Some(key) = request.coalesce_key().filter(|_| !shutting_down) {
+        let identical = queue.iter_mut().find(|queued| {
+            !queued.cancelled() && queued.request.coalesce_key().as_ref() == Some(&key)
+        });
+        if let Some(queued) = identical {
+            debug!(
+                "Coalescing {} with an identical queued request",
+                request.kind()
+            );
+            // Its own progress is never reported; the client ends it when
+            // the shared result arrives.
+            drop(request.take_progress());
+            let cancel = request.cancel_token().cloned().unwrap_or_default();
+            if let Some(reply) = request.into_reply() {
+                queued.followers.push(Caller { reply, cancel });
+            }
+            return;
+        }
+    }
+
+    if let Some((kind, folder)) = request.workspace_scope() {
+        let superseded = |scope: Option<(&'static str, &Path)>| scope == Some((kind, folder));
+        queue.retain_mut(|queued| {
+            if !superseded(queued.request.workspace_scope()) {
+                return true;
+            }
+            info!("Superseding queued {} for {}", kind, folder.display());
+            let followers = std::mem::take(&mut queued.followers);
+            let request = std::mem::replace(&mut queued.request, GenerationRequest::Shutdown);
+            let replies = followers.into_iter().map(|caller| caller.reply);
+            for reply in request.into_reply().into_iter().chain(replies) {
+                let _ = reply.send(Err(RequestCancelled.into()));
+            }
+            false
+        });
+        for job in running.iter().flatten() {
+            if superseded(Some((job.scope.0, &job.scope.1))) {
+                info!("Superseding running {} for {}", kind, folder.display());
+                job.cancel.store(true, Ordering::Relaxed);
+            }
+        }
+    }
+
+    queue.push_back(Queued {
+        request,
+        followers: Vec::new(),
+    });
+}
+
+fn is_cancelled(request: &GenerationRequest) -> bool {
+    request
+        .cancel_token()
+        .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+}
+
+/// A copy of `error` for another caller sharing the result, keeping the
+/// error types callers check for.
+fn share_error(error: &anyhow::Error) -> anyhow::Error {
+    if let Some(timeout) = error.downcast_ref::<GenerationTimedOut>() {
+        timeout.clone().into()
+    } else if let Some(panicked) = error.downcast_ref::<WorkerPanicked>() {
+        panicked.clone().into()
+    } else if let Some(cancelled) = error.downcast_ref::<RequestCancelled>() {
+        (*cancelled).into()
+    } else if let Some(shutting_down) = error.downcast_ref::<ShuttingDown>() {
+        (*shutting_down).into()
+    } else {
+        anyhow!("{:#}", error)
+    }
+}
+
+/// Answers the callers in `callers` that have cancelled and keeps the
+/// others.
+fn answer_cancelled(callers: &mut Vec<Caller>) {
+    let (cancelled, waiting): (Vec<_>, Vec<_>) = std::mem::take(callers)
+        .into_iter()
+        .partition(|caller| caller.cancel.load(Ordering::Relaxed));
+    for caller in cancelled {
+        let _ = caller.reply.send(Err(caller.cancel.error()));
+    }
+    *callers = waiting;
+}
+
+/// Sends `result` to every caller in `callers`.
+fn reply_all(callers: Vec<Caller>, result: Result<String>) {
+    let mut replies = callers.into_iter().map(|caller| caller.reply);
+    let Some(first) = replies.next() else {
+        return;
+    };
+    for reply in replies {
+        let shared = match &result {
+            Ok(text) => Ok(text.clone()),
+            Err(error) => Err(share_error(error)),
+        };
+        let _ = reply.send(shared);
+    }
+    let _ = first.send(result);
+}
+
+/// Waits for one request's result and passes it on to every caller
+/// waiting for it; `true` if the worker stopped or timed out and has to be
+/// replaced. Callers that cancel meanwhile are answered at once, and `job`
+/// is cancelled when none is left.
+fn supervise(
+    kind: &'static str,
+    mut result: oneshot::Receiver<Result<String>>,
+    mut replies: Vec<Caller>,
+    job: &CancelToken,
+    limit: Option<Duration>,
+    phases: &PhaseTracker,
+) -> bool {
+    let started = Instant::now();
+    let outcome = TOKIO_RUNTIME.block_on(async {
+        loop {
+            let wait = match limit {
+                Some(limit) => match limit.checked_sub(started.elapsed()) {
+                    Some(left) => left.min(QUEUE_POLL),
+                    None => return Err(()),
+                },
+                None => QUEUE_POLL,
+            };
+            if let Ok(received) = tokio::time::timeout(wait, &mut result).await {
+                return Ok(received);
+            }
+            if !replies.is_empty() {
+                answer_cancelled(&mut replies);
+                if replies.is_empty() {
+                    debug!("Every caller cancelled {}", kind);
+                    job.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    });
+    match outcome {
+        Ok(Ok(result)) => {
+            reply_all(replies, result);
+            false
+        }
+        Ok(Err(_)) => {
+            error!("Generator worker stopped while running {}", kind);
+            reply_all(
+                replies,
+                Err(anyhow!("Generator worker stopped unexpectedly")),
+            );
+            true
+        }
+        Err(_) => {
+            let limit = limit.unwrap_or_default();
+            error!(
+                "{} exceeded {:?}, restarting generator worker\n{}",
+                kind,
+                limit,
+                phases.dump()
+            );
+            let timeout = GenerationTimedOut {
+                request: kind,
+                limit_secs: limit.as_secs(),
+                phase: phases.current(),
+            };
+            reply_all(replies, Err(timeout.into()));
+            true
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! The workspace folders the client has open.
+//!
+//! Folders come from `initialize` and change with
+//! `workspace/didChangeWorkspaceFolders`. A workspace command that names
+//! no `workspace_folder` runs on every folder: with one folder open the
+//! argument is filled in, with several the command gets the whole list as
+//! `workspace_folders` and answers per folder.
+
+use lsp_types::{InitializeParams, Url, WorkspaceFoldersChangeEvent};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+#[derive(Debug, Default)]
+pub struct WorkspaceFolders {
+    folders: RwLock<Vec<PathBuf>>,
+}
+
+impl WorkspaceFolders {
+    pub fn new(folders: Vec<PathBuf>) -> Self {
+        Self {
+            folders: RwLock::new(folders),
+        }
+    }
+
+    /// The folders of `initialize`, or its root for clients without
+    /// workspace folders.
+    #[allow(deprecated)]
+    pub fn from_init(init_params: &InitializeParams) -> Self {
+        let folders = match &init_params.workspace_folders {
+            Some(folders) => folders.iter().filter_map(|f| to_path(&f.uri)).collect(),
+            None => init_params.root_uri.iter().filter_map(to_path).collect(),
+        };
+        Self::new(folders)
+    }
+
+    pub fn list(&self) -> Vec<PathBuf> {
+        self.folders
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Applies `event` and returns the folders that were added and removed.
+    pub fn apply(&self, event: &WorkspaceFoldersChangeEvent) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let mut folders = self.folders.write().unwrap_or_else(|e| e.into_inner());
+        let removed: Vec<PathBuf> = event
+            .removed
+            .iter()
+            .filter_map(|f| to_path(&f.uri))
+            .filter(|path| folders.contains(path))
+            .collect();
+        folders.retain(|path| !removed.contains(path));
+        let mut added = Vec::new();
+        for path in event.added.iter().filter_map(|f| to_path(&f.uri)) {
+            if !folders.contains(&path) {
+                folders.push(path.clone());
+                added.push(path);
+            }
+        }
+        (added, removed)
+    }
+}
+
+/// Fills in the folder of a workspace command's arguments when it names
+/// none: `workspace_folder` for a single folder, `workspace_folders` for
+/// several. Arguments that name a folder are left alone.
+pub fn fill_folder_argument(arguments: &mut Vec<Value>, folders: &[PathBuf]) {
+    if folders.is_empty() {
+        return;
+    }
+    if arguments.is_empty() {
+        arguments.push(Value::Object(Default::default()));
+    }
+    let Some(args) = arguments[0].as_object_mut() else {
+        return;
+    };
+    if args.contains_key("workspace_folder") || args.contains_key("workspace_folders") {
+        return;
+    }
+    match folders {
+        [folder] => {
+            args.insert("workspace_folder".to_string(), path_value(folder));
+        }
+        _ => {
+            let folders = folders.iter().map(|folder| path_value(folder)).collect();
+            args.insert("workspace_folders".to_string(), Value::Array(folders));
+        }
+    }
+}
+
+/// The innermost of `folders` containing `path`, or the directory of
+/// `path` when none does.
+pub fn folder_of(path: &Path, folders: &[PathBuf]) -> PathBuf {
+    folders
+        .iter()
+        .filter(|folder| path.starts_with(folder))
+        .max_by_key(|folder| folder.components().count())
+        .cloned()
+        .unwrap_or_else(|| path.parent().unwrap_or(path).to_path_buf())
+}
+
+fn to_path(uri: &Url) -> Option<PathBuf> {
+    uri.to_file_path().ok()
+}
+
+fn path_value(path: &Path) -> Value {
+    Value::from(path.to_string_lossy().into_owned())
+}
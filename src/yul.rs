@@ -0,0 +1,413 @@
+//! Standalone Yul sources.
+//!
+//! tree-sitter-solidity only knows Yul inside `assembly` blocks, so `.yul`
+//! files are read by a small parser of their own. Each `object` becomes a
+//! contract of the call graph holding its Yul functions, plus a `code` node
+//! for the object's top-level code, which is where execution enters. Calls
+//! between functions of the same object become call edges; calls to
+//! builtins such as `sstore` or `verbatim_1i_1o` are not drawn. A file
+//! without an `object` is one code block named after the file.
+
+use crate::traverse_adapter::Frontend;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use traverse_graph::cg::{CallGraph, EdgeType, NodeType, ParameterInfo, Visibility};
+
+/// Name of the node for an object's top-level code.
+pub const CODE_NODE: &str = "code";
+
+pub struct YulFrontend;
+
+impl Frontend for YulFrontend {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["yul"]
+    }
+
+    fn add_to_graph(&self, graph: &mut CallGraph, path: &Path, source: &str) -> Result<()> {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Yul");
+        add_to_graph(graph, source, name);
+        Ok(())
+    }
+}
+
+/// A Yul `object`, or the bare code block of a file without one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YulObject {
+    pub name: String,
+    /// Span of the `code` block, if the object has one.
+    pub code: Option<(usize, usize)>,
+    pub functions: Vec<YulFunction>,
+    /// Calls from the top-level code, outside every function.
+    pub calls: Vec<YulCall>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YulFunction {
+    pub name: String,
+    /// From the `function` keyword to the end of the body.
+    pub span: (usize, usize),
+    pub parameters: Vec<String>,
+    pub returns: Vec<String>,
+    pub calls: Vec<YulCall>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YulCall {
+    pub callee: String,
+    /// From the callee name to the closing parenthesis.
+    pub span: (usize, usize),
+    pub arguments: Vec<String>,
+}
+
+/// Objects of `source`, including nested ones, in source order. Objects of
+/// a file without an `object` declaration are named `default_name`.
+pub fn parse(source: &str, default_name: &str) -> Vec<YulObject> {
+    let tokens = tokenize(source);
+    let mut parser = Parser {
+        source,
+        tokens,
+        pos: 0,
+        objects: Vec::new(),
+    };
+    if parser.peek_is("object") {
+        while parser.peek_is("object") {
+            parser.object();
+        }
+    } else if parser.peek_is("{") {
+        let start = parser.tokens[parser.pos].start;
+        let index = parser.push_object(default_name.to_string());
+        let end = parser.block(index, None);
+        parser.objects[index].code = Some((start, end));
+    }
+    parser.objects
+}
+
+/// Adds the objects of `source` to `graph` and returns how many nodes were
+/// added. Spans are relative to `source`.
+pub fn add_to_graph(graph: &mut CallGraph, source: &str, default_name: &str) -> usize {
+    let first = graph.nodes.len();
+    for object in parse(source, default_name) {
+        let contract = Some(object.name.clone());
+        let mut ids = HashMap::new();
+        let code = object.code.map(|span| {
+            graph.add_node(
+                CODE_NODE.to_string(),
+                NodeType::Function,
+                contract.clone(),
+                Visibility::External,
+                span,
+            )
+        });
+        for function in &object.functions {
+            let id = graph.add_node(
+                function.name.clone(),
+                NodeType::Function,
+                contract.clone(),
+                Visibility::Internal,
+                function.span,
+            );
+            let node = &mut graph.nodes[id];
+            node.parameters = function
+                .parameters
+                .iter()
+                .map(|name| ParameterInfo {
+                    name: name.clone(),
+                    param_type: String::new(),
+                    description: None,
+                })
+                .collect();
+            node.has_explicit_return = !function.returns.is_empty();
+            ids.insert(function.name.as_str(), id);
+        }
+
+        let callers = code.map(|id| (id, &object.calls)).into_iter().chain(
+            object
+                .functions
+                .iter()
+                .map(|f| (ids[f.name.as_str()], &f.calls)),
+        );
+        for (caller, calls) in callers {
+            let resolved = calls
+                .iter()
+                .filter_map(|call| Some((ids.get(call.callee.as_str())?, call)));
+            for (sequence, (&callee, call)) in resolved.enumerate() {
+                graph.add_edge(
+                    caller,
+                    callee,
+                    EdgeType::Call,
+                    call.span,
+                    None,
+                    sequence + 1,
+                    None,
+                    Some(call.arguments.clone()),
+                    None,
+                    None,
+                );
+            }
+        }
+    }
+    graph.nodes.len() - first
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if source[i..].starts_with("//") {
+            i = source[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            continue;
+        }
+        if source[i..].starts_with("/*") {
+            i = source[i + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |n| i + n + 4);
+            continue;
+        }
+        if c == b'"' || c == b'\'' {
+            i = string_end(bytes, i);
+        } else if source[i..].starts_with("hex\"") || source[i..].starts_with("hex'") {
+            i = string_end(bytes, i + 3);
+        } else if c.is_ascii_alphanumeric() || c == b'_' || c == b'$' {
+            while i < bytes.len()
+                && (bytes[i].is_ascii_alphanumeric() || matches!(bytes[i], b'_' | b'$' | b'.'))
+            {
+                i += 1;
+            }
+        } else if source[i..].starts_with("->") || source[i..].starts_with(":=") {
+            i += 2;
+        } else {
+            i += source[i..].chars().next().map_or(1, char::len_utf8);
+        }
+        tokens.push(Token {
+            text: source[start..i].to_string(),
+            start,
+            end: i,
+        });
+    }
+    tokens
+}
+
+/// End of the string literal whose opening quote is at `open`.
+fn string_end(bytes: &[u8], open: usize) -> usize {
+    let quote = bytes[open];
+    let mut i = open + 1;
+    while i < bytes.len() && bytes[i] != quote {
+        i += if bytes[i] == b'\\' { 2 } else { 1 };
+    }
+    (i + 1).min(bytes.len())
+}
+
+fn is_identifier(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    objects: Vec<YulObject>,
+}
+
+impl Parser<'_> {
+    fn peek_is(&self, text: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|t| t.text == text)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn push_object(&mut self, name: String) -> usize {
+        self.objects.push(YulObject {
+            name,
+            code: None,
+            functions: Vec::new(),
+            calls: Vec::new(),
+        });
+        self.objects.len() - 1
+    }
+
+    /// `object "Name" { code { ... } object ... data "name" hex"..." }`
+    fn object(&mut self) {
+        self.pos += 1;
+        let name = self
+            .next()
+            .map(|t| t.text.trim_matches('"').to_string())
+            .unwrap_or_default();
+        let index = self.push_object(name);
+        if !self.peek_is("{") {
+            return;
+        }
+        self.pos += 1;
+        while let Some(token) = self.tokens.get(self.pos) {
+            match token.text.as_str() {
+                "}" => {
+                    self.pos += 1;
+                    return;
+                }
+                "code" => {
+                    let start = token.start;
+                    self.pos += 1;
+                    let end = self.block(index, None);
+                    self.objects[index].code = Some((start, end));
+                }
+                "object" => self.object(),
+                // `data "name" hex"..."`
+                "data" => self.pos += 3,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Reads the block starting at the current `{` and returns the offset
+    /// just past its closing brace. Calls are recorded on `function`, the
+    /// index of the enclosing function, or on the object's code.
+    fn block(&mut self, object: usize, function: Option<usize>) -> usize {
+        if !self.peek_is("{") {
+            return self
+                .tokens
+                .get(self.pos)
+                .map_or(self.source.len(), |t| t.start);
+        }
+        self.pos += 1;
+        while let Some(token) = self.tokens.get(self.pos) {
+            match token.text.as_str() {
+                "}" => {
+                    self.pos += 1;
+                    return token.end;
+                }
+                "{" => {
+                    self.block(object, function);
+                }
+                "function" => self.function(object),
+                text if is_identifier(text) && self.is_call() => {
+                    let call = self.call();
+                    match function {
+                        Some(f) => self.objects[object].functions[f].calls.push(call),
+                        None => self.objects[object].calls.push(call),
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+        self.source.len()
+    }
+
+    fn is_call(&self) -> bool {
+        self.tokens.get(self.pos + 1).is_some_and(|t| t.text == "(")
+    }
+
+    /// `function name(a, b) -> r { ... }`
+    fn function(&mut self, object: usize) {
+        let start = self.tokens[self.pos].start;
+        self.pos += 1;
+        let Some(name) = self.next().map(|t| t.text.clone()) else {
+            return;
+        };
+        let mut parameters = Vec::new();
+        let mut returns = Vec::new();
+        let mut in_returns = false;
+        while let Some(token) = self.tokens.get(self.pos) {
+            match token.text.as_str() {
+                "{" => break,
+                "->" => in_returns = true,
+                text if is_identifier(text) => {
+                    let previous = &self.tokens[self.pos - 1].text;
+                    // `a:u256` names `a` with type `u256`.
+                    if previous != ":" {
+                        if in_returns {
+                            returns.push(text.to_string());
+                        } else {
+                            parameters.push(text.to_string());
+                        }
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        let functions = &mut self.objects[object].functions;
+        functions.push(YulFunction {
+            name,
+            span: (start, start),
+            parameters,
+            returns,
+            calls: Vec::new(),
+        });
+        let index = functions.len() - 1;
+        let end = self.block(object, Some(index));
+        self.objects[object].functions[index].span.1 = end;
+    }
+
+    /// A call starting at the callee name; nested calls in its arguments
+    /// are recorded after it.
+    fn call(&mut self) -> YulCall {
+        let callee = self.tokens[self.pos].clone();
+        let open = self.tokens[self.pos + 1].end;
+        let (mut close, mut end) = (self.source.len(), self.source.len());
+        let mut depth = 0;
+        for token in &self.tokens[self.pos + 1..] {
+            match token.text.as_str() {
+                "(" => depth += 1,
+                ")" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        (close, end) = (token.start, token.end);
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        // Step inside the parentheses so nested calls are seen too.
+        self.pos += 2;
+        YulCall {
+            callee: callee.text,
+            span: (callee.start, end),
+            arguments: split_arguments(self.source[open..close].trim()),
+        }
+    }
+}
+
+/// Splits call arguments at top-level commas.
+pub(crate) fn split_arguments(text: &str) -> Vec<String> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(text[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(text[start..].trim().to_string());
+    arguments
+}
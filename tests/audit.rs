@@ -0,0 +1,751 @@
+use std::path::PathBuf;
+use traverse_lsp::analysis::{
+    model::SourceUnit, render_report, run_audit, Confidence, Finding, Severity,
+};
+
+const UPGRADEABLE_BASES: &str = r#"
+pragma solidity ^0.8.0;
+
+abstract contract Initializable {
+    bool private _initialized;
+}
+
+abstract contract GoodUpgradeable is Initializable {
+    address owner;
+    uint96 fee;
+    uint256 total;
+    uint256[48] private __gap;
+}
+
+abstract contract OverUpgradeable is Initializable {
+    uint256 a;
+    uint256 b;
+    uint256[49] private __gap;
+}
+
+abstract contract NoGapUpgradeable is Initializable {
+    uint256 a;
+}
+
+contract Vault is GoodUpgradeable, OverUpgradeable, NoGapUpgradeable {
+    uint256 x;
+}
+"#;
+
+fn audit(source: &str) -> Vec<Finding> {
+    let unit = SourceUnit {
+        path: PathBuf::from("Test.sol"),
+        content: source.to_string(),
+    };
+    run_audit(&[unit]).expect("Failed to run audit")
+}
+
+fn findings_for<'a>(findings: &'a [Finding], rule: &str, contract: &str) -> Vec<&'a Finding> {
+    findings
+        .iter()
+        .filter(|f| f.rule == rule && f.contract.as_deref() == Some(contract))
+        .collect()
+}
+
+#[test]
+fn test_storage_gap_within_reserved_space() {
+    let findings = audit(UPGRADEABLE_BASES);
+    assert!(findings_for(&findings, "storage-gap", "GoodUpgradeable").is_empty());
+    assert!(findings_for(&findings, "storage-gap", "Vault").is_empty());
+}
+
+#[test]
+fn test_storage_gap_exceeding_reserved_space() {
+    let findings = audit(UPGRADEABLE_BASES);
+    let over = findings_for(&findings, "storage-gap", "OverUpgradeable");
+
+    assert_eq!(over.len(), 1);
+    assert_eq!(over[0].severity, Severity::High);
+    assert!(over[0].message.contains("= 51 slots"));
+    assert_eq!(over[0].line, Some(18));
+}
+
+#[test]
+fn test_storage_gap_missing() {
+    let findings = audit(UPGRADEABLE_BASES);
+    let missing = findings_for(&findings, "storage-gap", "NoGapUpgradeable");
+
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].severity, Severity::Medium);
+    assert_eq!(missing[0].confidence, Confidence::Medium);
+    assert!(missing[0].message.contains("no `__gap`"));
+}
+
+#[test]
+fn test_slot_count_of_fixed_byte_arrays() {
+    use traverse_lsp::analysis::model::{parse_sources, SourceModel};
+    use traverse_lsp::analysis::storage_layout::slot_count;
+
+    let parsed = parse_sources(&[]).expect("Failed to parse sources");
+    let model = SourceModel::from_parsed(&parsed);
+    assert_eq!(slot_count(&model, &["bytes16[3]"]), 2);
+    assert_eq!(slot_count(&model, &["bytes32[3]", "bytes1"]), 4);
+    // Not a Solidity type: a slot per element rather than a division by zero.
+    assert_eq!(slot_count(&model, &["bytes64[3]"]), 3);
+    assert_eq!(slot_count(&model, &["bytes0", "uint8"]), 2);
+}
+
+const BALANCE_CHECKS: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Game {
+    function play() external payable {
+        require(address(this).balance <= 10 ether, "Game over");
+    }
+
+    function settle() external {
+        uint256 pot = address(this).balance;
+        if (pot == 10 ether) {
+            payable(msg.sender).transfer(pot);
+        }
+    }
+
+    function pot() external view returns (uint256 amount) {
+        assembly {
+            amount := selfbalance()
+        }
+    }
+}
+"#;
+
+#[test]
+fn test_self_balance_branching() {
+    let findings = audit(BALANCE_CHECKS);
+
+    let play = findings_for(&findings, "self-balance", "Game")
+        .into_iter()
+        .filter(|f| f.function.as_deref() == Some("play"))
+        .collect::<Vec<_>>();
+    assert_eq!(play.len(), 1);
+    assert_eq!(play[0].severity, Severity::Low);
+
+    let settle = findings_for(&findings, "self-balance", "Game")
+        .into_iter()
+        .filter(|f| f.function.as_deref() == Some("settle"))
+        .collect::<Vec<_>>();
+    assert!(settle.iter().any(|f| f.severity == Severity::Medium));
+    assert!(settle.iter().any(|f| f.severity == Severity::Info));
+}
+
+#[test]
+fn test_finding_confidence_threshold() {
+    let findings = audit(BALANCE_CHECKS);
+    let settle: Vec<&Finding> = findings_for(&findings, "self-balance", "Game")
+        .into_iter()
+        .filter(|f| f.function.as_deref() == Some("settle"))
+        .collect();
+
+    // The direct read is certain; the branch through the `pot` local relies
+    // on local dataflow.
+    assert!(settle
+        .iter()
+        .any(|f| f.severity == Severity::Info && f.confidence == Confidence::High));
+    assert!(settle
+        .iter()
+        .any(|f| f.severity == Severity::Medium && f.confidence == Confidence::Medium));
+
+    let confident: Vec<Finding> = findings
+        .iter()
+        .filter(|f| f.confidence >= Confidence::High)
+        .cloned()
+        .collect();
+    assert!(confident.len() < findings.len());
+    let report = render_report(&confident, 1);
+    assert!(report.contains("| Severity | Confidence |"));
+    assert!(!report.contains("| medium | medium |"));
+}
+
+#[test]
+fn test_self_balance_assembly_read() {
+    let findings = audit(BALANCE_CHECKS);
+    let pot = findings_for(&findings, "self-balance", "Game")
+        .into_iter()
+        .filter(|f| f.function.as_deref() == Some("pot"))
+        .collect::<Vec<_>>();
+
+    assert_eq!(pot.len(), 1);
+    assert_eq!(pot[0].severity, Severity::Info);
+}
+
+const DIAMOND: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Diamond {
+    fallback() external payable {}
+}
+
+contract TokenFacet {
+    function burn(uint256 amount) external {}
+    function owner() external view returns (address) {}
+}
+
+contract StorageFacet {
+    function collate_propagate_storage(bytes16 key) external {}
+}
+
+contract AdminFacet {
+    address public owner;
+}
+"#;
+
+#[test]
+fn test_selector_collision_across_facets() {
+    let findings = audit(DIAMOND);
+    let collisions: Vec<&Finding> = findings
+        .iter()
+        .filter(|f| f.rule == "selector-collision")
+        .collect();
+
+    assert_eq!(collisions.len(), 2);
+
+    let burn = collisions
+        .iter()
+        .find(|f| f.message.contains("0x42966c68"))
+        .expect("burn(uint256) collision not reported");
+    assert_eq!(burn.severity, Severity::High);
+    assert!(burn.message.contains("collate_propagate_storage(bytes16)"));
+
+    let owner = collisions
+        .iter()
+        .find(|f| f.message.contains("owner()"))
+        .expect("duplicate owner() not reported");
+    assert_eq!(owner.severity, Severity::Medium);
+}
+
+const TOKEN_INTERFACE: &str = r#"
+pragma solidity ^0.8.0;
+
+interface IToken {
+    function totalSupply() external view returns (uint256);
+    function transfer(address to, uint256 amount) external returns (bool);
+    function approve(address spender, uint256 amount) external returns (bool);
+    function balanceOf(address account) external view returns (uint256);
+    function burn(uint256 amount) external;
+}
+"#;
+
+const TOKEN: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Token {
+    uint256 public totalSupply;
+
+    function transfer(address to, uint128 amount) external returns (bool) {
+        return true;
+    }
+
+    function approve(address spender, uint256 amount) external returns (uint256) {
+        return amount;
+    }
+
+    function mint(address to, uint256 amount) external {
+    }
+
+    function burn(uint256 amount) external {
+    }
+}
+"#;
+
+#[test]
+fn test_interface_drift() {
+    let units = [
+        SourceUnit {
+            path: PathBuf::from("IToken.sol"),
+            content: TOKEN_INTERFACE.to_string(),
+        },
+        SourceUnit {
+            path: PathBuf::from("Token.sol"),
+            content: TOKEN.to_string(),
+        },
+    ];
+    let findings = run_audit(&units).expect("Failed to run audit");
+    let drift = findings_for(&findings, "interface-drift", "Token");
+
+    let messages = |function: &str| -> Vec<&str> {
+        drift
+            .iter()
+            .filter(|f| f.function.as_deref() == Some(function))
+            .map(|f| f.message.as_str())
+            .collect()
+    };
+    assert_eq!(
+        messages("transfer"),
+        vec!["`IToken.transfer(address,uint256)` is implemented as `transfer(address,uint128)` in `Token`"; 2]
+    );
+    assert_eq!(
+        messages("approve"),
+        vec!["`IToken.approve(address,uint256)` returns (bool) but `Token` returns (uint256)"; 2]
+    );
+    assert_eq!(
+        messages("balanceOf"),
+        vec!["`IToken.balanceOf(address)` has no implementation in `Token`"; 2]
+    );
+    assert_eq!(
+        messages("mint"),
+        vec!["External `Token.mint(address,uint256)` is missing from `IToken`"; 2]
+    );
+    // The public getter implements `totalSupply()`.
+    assert!(messages("totalSupply").is_empty());
+    assert!(messages("burn").is_empty());
+
+    // Each drift is reported on both files.
+    for function in ["transfer", "approve", "balanceOf", "mint"] {
+        let mut files: Vec<_> = drift
+            .iter()
+            .filter(|f| f.function.as_deref() == Some(function))
+            .filter_map(|f| f.file.as_ref())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![&PathBuf::from("IToken.sol"), &PathBuf::from("Token.sol")]
+        );
+    }
+    assert!(drift.iter().all(|f| f.confidence == Confidence::Medium));
+}
+
+const OVERRIDES: &str = r#"
+pragma solidity ^0.8.0;
+
+interface IHook {
+    function onTransfer(address to) external;
+}
+
+abstract contract Base {
+    function fee() public view virtual returns (uint256) {
+        return 1;
+    }
+
+    function hook() internal virtual {}
+
+    function settle() internal virtual;
+}
+
+abstract contract Pausable {
+    function fee() public view virtual returns (uint256) {
+        return 0;
+    }
+
+    function paused() public view virtual returns (bool) {
+        return false;
+    }
+}
+
+contract Token is Base, Pausable, IHook {
+    function fee() public view override(Base) returns (uint256) {
+        return 2;
+    }
+
+    function hook() internal {}
+
+    function onTransfer(address to) external {}
+}
+"#;
+
+#[test]
+fn test_override_completeness() {
+    use traverse_lsp::analysis::model::{parse_sources, SourceModel};
+    use traverse_lsp::analysis::overrides::{self, IssueKind};
+
+    let units = [SourceUnit {
+        path: PathBuf::from("Token.sol"),
+        content: OVERRIDES.to_string(),
+    }];
+    let parsed = parse_sources(&units).expect("Failed to parse sources");
+    let model = SourceModel::from_parsed(&parsed);
+    let issues = overrides::find(&model);
+    let messages = |kind: IssueKind| -> Vec<&str> {
+        issues
+            .iter()
+            .filter(|i| i.kind == kind)
+            .map(|i| i.message.as_str())
+            .collect()
+    };
+
+    assert_eq!(
+        messages(IssueKind::MissingOverride),
+        vec![
+            "`Token.fee()` must list `Pausable` in `override(...)`",
+            "`Token.hook()` overrides `Base` but is not marked `override`",
+        ]
+    );
+    assert_eq!(
+        messages(IssueKind::Unimplemented),
+        vec!["`Token` does not implement `Base.settle()`"]
+    );
+    // Every `fee` and `hook` is overridden by `Token`, even where the
+    // specifier is incomplete.
+    assert_eq!(
+        messages(IssueKind::NeverOverridden),
+        vec!["`Pausable.paused()` is virtual but never overridden"]
+    );
+
+    let report = overrides::render_override_report(&issues);
+    assert!(report.contains("## Incomplete `override` Specifiers (2)"));
+    assert!(report.contains("## Unimplemented Functions (1)"));
+
+    let findings = audit(OVERRIDES);
+    let token = findings_for(&findings, "override-completeness", "Token");
+    assert_eq!(token.len(), 3);
+    assert!(token.iter().all(|f| f.severity == Severity::Medium));
+    let hooks = findings_for(&findings, "override-completeness", "Pausable");
+    assert_eq!(hooks[0].severity, Severity::Info);
+    assert_eq!(hooks[0].confidence, Confidence::Low);
+}
+
+const UNIT_MISMATCH: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Staking {
+    uint256 constant WAD = 1e18;
+    uint256 public unlockAt;
+    uint256 public rate;
+
+    function lock(uint256 lockDays) external {
+        unlockAt = block.timestamp + lockDays;
+    }
+
+    function extend() external {
+        unlockAt = block.timestamp + 7;
+    }
+
+    function window() external pure returns (uint256) {
+        return 2 days * 86400;
+    }
+
+    function setRate(uint256 amount) external {
+        rate = amount * WAD + 5;
+    }
+
+    function fine(uint256 amount, uint256 lockDays) external {
+        unlockAt = block.timestamp + lockDays * 1 days;
+        rate = amount * 1e18 / WAD + 5;
+        rate = amount * 1e18 + 1;
+        unlockAt = block.timestamp + 30 days;
+    }
+}
+"#;
+
+#[test]
+fn test_unit_mismatch() {
+    let findings = audit(UNIT_MISMATCH);
+    let staking = findings_for(&findings, "unit-mismatch", "Staking");
+    let by_function: Vec<(&str, &str)> = staking
+        .iter()
+        .map(|f| (f.function.as_deref().unwrap(), f.message.as_str()))
+        .collect();
+
+    assert_eq!(by_function.len(), 4, "{:?}", by_function);
+    assert!(by_function.contains(&(
+        "lock",
+        "`block.timestamp + lockDays` uses `lockDays`, which looks like a count of days, against a timestamp in seconds"
+    )));
+    assert!(by_function.contains(&(
+        "extend",
+        "`block.timestamp + 7` uses 7 seconds against a timestamp; a unit such as `days` may be missing"
+    )));
+    assert!(by_function.contains(&(
+        "window",
+        "`2 days * 86400` converts a duration to seconds twice"
+    )));
+    assert!(by_function.contains(&(
+        "setRate",
+        "`amount * WAD + 5` mixes a 1e18-scaled value with the unscaled literal 5"
+    )));
+    assert!(staking
+        .iter()
+        .all(|f| f.severity == Severity::Low && f.confidence == Confidence::Low));
+}
+
+const READ_ONLY_REENTRANCY: &str = r#"
+pragma solidity ^0.8.0;
+
+interface IPool {
+    function getVirtualPrice() external view returns (uint256);
+}
+
+contract Pool is IPool {
+    uint256 totalSupply;
+    uint256 reserves;
+    mapping(address => uint256) balances;
+
+    function removeLiquidity(uint256 amount) external {
+        uint256 share = amount * reserves / totalSupply;
+        (bool ok, ) = msg.sender.call{value: share}("");
+        require(ok);
+        balances[msg.sender] -= amount;
+        totalSupply -= amount;
+    }
+
+    function addLiquidity(uint256 amount) external {
+        totalSupply += amount;
+        reserves += amount;
+    }
+
+    function getVirtualPrice() external view returns (uint256) {
+        return reserves * 1e18 / totalSupply;
+    }
+
+    function getReserves() external view returns (uint256) {
+        return reserves;
+    }
+
+    function balanceOf(address user) external view returns (uint256) {
+        return balances[user];
+    }
+}
+
+contract Lender {
+    IPool pool;
+    mapping(address => uint256) debt;
+
+    function borrow(uint256 collateral) external {
+        uint256 price = pool.getVirtualPrice();
+        debt[msg.sender] += collateral * price / 1e18;
+    }
+
+    function quote() external view returns (uint256) {
+        return pool.getVirtualPrice();
+    }
+}
+"#;
+
+#[test]
+fn test_read_only_reentrancy() {
+    let findings = audit(READ_ONLY_REENTRANCY);
+
+    let lender = findings_for(&findings, "read-only-reentrancy", "Lender");
+    assert_eq!(lender.len(), 1, "{:?}", lender);
+    assert_eq!(lender[0].function.as_deref(), Some("borrow"));
+    assert_eq!(lender[0].line, Some(44));
+    assert_eq!(lender[0].severity, Severity::Medium);
+    assert_eq!(
+        lender[0].message,
+        "`Lender.borrow` calls `Pool.getVirtualPrice`, which reads `totalSupply`. `Pool.removeLiquidity` writes `totalSupply` after its external call on line 15, so a callback from that call reads a stale value"
+    );
+
+    // `getReserves` reads nothing written late; `balanceOf` is not consumed
+    // in the workspace, so it is reported on its own.
+    let pool = findings_for(&findings, "read-only-reentrancy", "Pool");
+    let functions: Vec<&str> = pool
+        .iter()
+        .map(|f| f.function.as_deref().unwrap())
+        .collect();
+    assert_eq!(functions, vec!["balanceOf"]);
+    assert_eq!(pool[0].severity, Severity::Low);
+    assert!(pool[0].message.contains("reads `balances`"));
+}
+
+#[test]
+fn test_keccak_selectors() {
+    use traverse_lsp::analysis::selector_collision::{selector, to_hex};
+
+    assert_eq!(to_hex(&selector("transfer(address,uint256)")), "0xa9059cbb");
+    assert_eq!(to_hex(&selector("balanceOf(address)")), "0x70a08231");
+}
+
+const STORAGE_VERSIONS: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Ownable {
+    address owner;
+}
+
+contract VaultV1 is Ownable {
+    bool paused;
+    uint256 fee;
+    mapping(address => uint256) balances;
+}
+
+contract VaultV2 is Ownable {
+    bool paused;
+    uint256 rate;
+    uint128 cap;
+}
+"#;
+
+#[test]
+fn test_compare_storage_layouts() {
+    use traverse_lsp::analysis::model::{parse_sources, SourceModel};
+    use traverse_lsp::analysis::storage_compare::{compare, SlotStatus};
+
+    let units = [SourceUnit {
+        path: PathBuf::from("Vault.sol"),
+        content: STORAGE_VERSIONS.to_string(),
+    }];
+    let parsed = parse_sources(&units).expect("Failed to parse sources");
+    let model = SourceModel::from_parsed(&parsed);
+    let rows = compare(&model, "VaultV1", "VaultV2").expect("Failed to compare layouts");
+
+    let statuses: Vec<(usize, usize, SlotStatus)> =
+        rows.iter().map(|r| (r.slot, r.offset, r.status)).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            (0, 0, SlotStatus::Same),
+            (0, 20, SlotStatus::Same),
+            (1, 0, SlotStatus::Renamed),
+            (2, 0, SlotStatus::TypeChanged),
+        ]
+    );
+
+    assert!(compare(&model, "VaultV1", "Missing").is_err());
+}
+
+#[test]
+fn test_suppression_comments_and_ignore_file() {
+    use std::path::Path;
+    use traverse_lsp::analysis::suppression::{apply, IgnoreFile};
+
+    let source = r#"
+pragma solidity ^0.8.0;
+
+contract Game {
+    function play() external payable {
+        // traverse-ignore: self-balance
+        require(address(this).balance <= 10 ether, "Game over");
+    }
+
+    function settle() external {
+        uint256 pot = address(this).balance; // traverse-ignore: storage-gap, self-balance
+        if (pot == 10 ether) {
+            payable(msg.sender).transfer(pot);
+        }
+    }
+}
+"#;
+    let unit = SourceUnit {
+        path: PathBuf::from("/work/src/Game.sol"),
+        content: source.to_string(),
+    };
+    let root = Path::new("/work");
+    let sources = std::slice::from_ref(&unit);
+    let mut findings = run_audit(sources).unwrap();
+    assert_eq!(findings.len(), 3);
+
+    let suppressed = apply(&mut findings, sources, &IgnoreFile::default(), root);
+    assert_eq!(suppressed, 2);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].severity, Severity::Medium);
+
+    let ignore = IgnoreFile::parse("# acknowledged\nself-balance Game.settle\n");
+    assert_eq!(apply(&mut findings, sources, &ignore, root), 1);
+    assert!(findings.is_empty());
+
+    let mut findings = run_audit(sources).unwrap();
+    let by_path = IgnoreFile::parse("* src/Game.sol");
+    assert_eq!(apply(&mut findings, sources, &by_path, root), 3);
+}
+
+#[test]
+fn test_rule_config_disables_rules_and_overrides_severity() {
+    use traverse_lsp::analysis::{rule_settings, run_configured_audit, RULES};
+    use traverse_lsp::config::Config;
+
+    let config: Config = serde_json::from_value(serde_json::json!({
+        "rules": {
+            "selector-collision": { "enabled": false },
+            "storage-gap": { "severity": "low" }
+        }
+    }))
+    .unwrap();
+    let unit = SourceUnit {
+        path: PathBuf::from("Test.sol"),
+        content: UPGRADEABLE_BASES.to_string(),
+    };
+    let sources = std::slice::from_ref(&unit);
+
+    let findings = run_configured_audit(sources, &config).unwrap();
+    assert!(!findings.is_empty());
+    assert!(findings
+        .iter()
+        .all(|f| f.rule == "storage-gap" && f.severity == Severity::Low));
+
+    let disabled: Config = serde_json::from_value(
+        serde_json::json!({ "rules": { "storage-gap": { "enabled": false } } }),
+    )
+    .unwrap();
+    assert!(run_configured_audit(sources, &disabled).unwrap().is_empty());
+
+    let settings = rule_settings(&config);
+    assert_eq!(settings.len(), RULES.len());
+    let collision = settings
+        .iter()
+        .find(|s| s.info.id == "selector-collision")
+        .unwrap();
+    assert!(!collision.enabled);
+    let gap = settings
+        .iter()
+        .find(|s| s.info.id == "storage-gap")
+        .unwrap();
+    assert!(gap.enabled);
+    assert_eq!(gap.severity, Some(Severity::Low));
+}
+
+#[test]
+fn test_findings_summary() {
+    use traverse_lsp::analysis::summary::{render_summary, summarize};
+
+    let finding = |rule: &'static str, severity: Severity, contract: Option<&str>| Finding {
+        rule,
+        severity,
+        confidence: Confidence::High,
+        contract: contract.map(str::to_string),
+        function: None,
+        message: String::new(),
+        file: None,
+        line: None,
+    };
+    let findings = vec![
+        finding("self-balance", Severity::Medium, Some("Game")),
+        finding("self-balance", Severity::Info, Some("Game")),
+        finding("storage-gap", Severity::High, Some("BaseUpgradeable")),
+        finding("selector-collision", Severity::Medium, Some("Diamond")),
+        finding("self-balance", Severity::Info, Some("Lottery")),
+        finding("self-balance", Severity::Low, None),
+    ];
+
+    let summary = summarize(&findings, 4, 2, 2);
+    assert_eq!(summary.total, 6);
+    assert_eq!(summary.by_severity.high, 1);
+    assert_eq!(summary.by_severity.medium, 2);
+    assert_eq!(summary.by_severity.info, 2);
+
+    let rules: Vec<(&str, usize)> = summary
+        .by_rule
+        .iter()
+        .map(|r| (r.rule.as_str(), r.counts.total()))
+        .collect();
+    assert_eq!(
+        rules,
+        vec![
+            ("storage-gap", 1),
+            ("self-balance", 4),
+            ("selector-collision", 1)
+        ]
+    );
+
+    // Game outranks Diamond on its extra info finding; Lottery is cut off.
+    let contracts: Vec<&str> = summary
+        .top_contracts
+        .iter()
+        .map(|c| c.contract.as_str())
+        .collect();
+    assert_eq!(contracts, vec!["BaseUpgradeable", "Game"]);
+
+    let report = render_summary(&summary);
+    assert!(report.contains("**6 findings** in 4 Solidity files (2 suppressed)"));
+    assert!(report.contains("| 1 | 2 | 1 | 2 |"));
+    assert!(report.contains("| `self-balance` | 0 | 1 | 1 | 2 | 4 |"));
+    assert!(report.contains("| `Game` | 0 | 1 | 0 | 1 | 2 |"));
+    assert!(!report.contains("Lottery"));
+
+    let clean = render_summary(&summarize(&[], 3, 0, 5));
+    assert!(clean.contains("No findings in 3 Solidity files."));
+    assert!(!clean.contains('|'));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::run_worker_request;
+use lsp_types::Url;
+use traverse_lsp::analysis::call_matrix::{build, render_call_matrix};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::GenerationRequest;
+
+const VAULT: &str = r#"
+interface IToken {
+    function transfer(address to, uint256 amount) external returns (bool);
+}
+
+contract Oracle {
+    function price() external view returns (uint256) {
+        return 1;
+    }
+}
+
+contract Vault {
+    IToken token;
+    Oracle oracle;
+
+    function withdraw(uint256 amount) external {
+        if (amount > 0) {
+            token.transfer(msg.sender, amount);
+        }
+        token.transfer(msg.sender, value(amount));
+    }
+
+    function value(uint256 amount) internal view returns (uint256) {
+        uint256 price = oracle.price();
+        return amount * price;
+    }
+}
+"#;
+
+#[test]
+fn test_counts_calls_between_contracts() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(VAULT)
+        .unwrap();
+    let matrix = build(&graph);
+    assert_eq!(matrix.contracts, vec!["IToken", "Oracle", "Vault"]);
+    assert_eq!(matrix.counts[2], vec![2, 1, 1]);
+    assert_eq!(matrix.counts[0], vec![0, 0, 0]);
+    assert_eq!(matrix.counts[1], vec![0, 0, 0]);
+
+    let couplings = matrix.couplings();
+    assert_eq!(couplings.len(), 2);
+    assert_eq!(
+        (couplings[0].caller.as_str(), couplings[0].callee.as_str()),
+        ("Vault", "IToken")
+    );
+    assert_eq!(couplings[0].calls, 2);
+
+    assert_eq!(
+        matrix.to_csv(),
+        "caller,IToken,Oracle,Vault\nIToken,0,0,0\nOracle,0,0,0\nVault,2,1,1\n"
+    );
+    let report = render_call_matrix(&matrix);
+    assert!(report.contains("| Vault | IToken | 2 |"));
+    assert!(report.contains("| Vault | Oracle | 1 |"));
+}
+
+#[test]
+fn test_call_matrix_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Vault.sol");
+    std::fs::write(&path, VAULT).unwrap();
+
+    let response = run_worker_request(|tx| GenerationRequest::CallMatrix {
+        uris: vec![Url::from_file_path(&path).unwrap()],
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(
+        response["contracts"],
+        serde_json::json!(["IToken", "Oracle", "Vault"])
+    );
+    assert_eq!(response["matrix"][2], serde_json::json!([2, 1, 1]));
+    assert_eq!(response["couplings"][0]["callee"], "IToken");
+    assert!(response["csv"]
+        .as_str()
+        .unwrap()
+        .starts_with("caller,IToken,Oracle,Vault\n"));
+    assert!(response["report"]
+        .as_str()
+        .unwrap()
+        .starts_with("# Contract Call Matrix"));
+}
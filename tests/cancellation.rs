@@ -0,0 +1,149 @@
+mod common;
+
+use common::{command_request, InMemoryServer};
+use lsp_server::{ErrorCode, Message, RequestId};
+use lsp_types::{NumberOrString, ProgressParams, ProgressParamsValue, WorkDoneProgress};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::thread;
+use tokio::sync::oneshot;
+use traverse_lsp::cancellation::{self, InFlightRequests};
+use traverse_lsp::commands;
+use traverse_lsp::config::Config;
+use traverse_lsp::generator_worker::{CancelToken, RequestCancelled};
+use traverse_lsp::handlers::execute_command;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+contract Vault {
+    function deposit(uint256 amount) external {}
+}
+"#;
+
+#[test]
+fn test_in_flight_requests() {
+    let in_flight = InFlightRequests::new();
+    let id = cancellation::request_id(NumberOrString::Number(7));
+    let token = in_flight.register(id.clone());
+    assert!(!token.load(Ordering::Relaxed));
+
+    assert!(in_flight.cancel(&RequestId::from(7)));
+    assert!(token.load(Ordering::Relaxed));
+
+    in_flight.finish(&id);
+    assert!(!in_flight.cancel(&id));
+}
+
+#[test]
+fn test_worker_skips_cancelled_request() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(&source, VAULT).unwrap();
+    let uris = vec![lsp_types::Url::from_file_path(&source).unwrap()];
+
+    let (tx, rx) = mpsc::channel();
+    let cancelled = CancelToken::default();
+    cancelled.store(true, Ordering::Relaxed);
+    let mut replies = Vec::new();
+    for cancel in [cancelled, CancelToken::default()] {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(GenerationRequest::ListFuzzTargets {
+            uris: uris.clone(),
+            cancel,
+            tx: reply_tx,
+        })
+        .unwrap();
+        replies.push(reply_rx);
+    }
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+
+    let mut replies = replies.into_iter().map(|r| r.blocking_recv().unwrap());
+    let error = replies.next().unwrap().unwrap_err();
+    assert!(error.is::<RequestCancelled>());
+    let targets = replies.next().unwrap().unwrap();
+    assert!(targets.contains("deposit"));
+}
+
+#[test]
+fn test_cancelled_command_fails_with_request_cancelled() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), VAULT).unwrap();
+
+    let server = InMemoryServer::start();
+    let request = command_request(
+        3,
+        commands::LIST_FUZZ_TARGETS_WORKSPACE,
+        vec![serde_json::json!({
+            "workspace_folder": workspace.path().to_str().unwrap()
+        })],
+        None,
+    );
+    let cancel = CancelToken::default();
+    cancel.store(true, Ordering::Relaxed);
+    execute_command(request, &server.server.sender, &server.generator_tx, cancel).unwrap();
+
+    let response = server.response();
+    assert_eq!(response.id, RequestId::from(3));
+    let error = response.error.unwrap();
+    assert_eq!(error.code, ErrorCode::RequestCanceled as i32);
+    server.finish();
+}
+
+#[test]
+fn test_cancelling_progress_cancels_the_command() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), VAULT).unwrap();
+    let mut server = InMemoryServer::new();
+
+    let token = NumberOrString::String("cancel-me".to_string());
+    let request = command_request(
+        4,
+        commands::GENERATE_CALL_GRAPH_WORKSPACE,
+        vec![serde_json::json!({
+            "workspace_folder": workspace.path().to_str().unwrap()
+        })],
+        Some("cancel-me"),
+    );
+    let command = {
+        let sender = server.server.sender.clone();
+        let generator_tx = server.generator_tx.clone();
+        thread::spawn(move || {
+            execute_command(request, &sender, &generator_tx, Default::default()).unwrap();
+        })
+    };
+
+    let next_progress = |server: &InMemoryServer| loop {
+        match server.next_message() {
+            Message::Notification(n) if n.method == "$/progress" => {
+                let params: ProgressParams = serde_json::from_value(n.params).unwrap();
+                let ProgressParamsValue::WorkDone(value) = params.value;
+                break value;
+            }
+            Message::Response(response) => panic!("unexpected response {:?}", response),
+            _ => continue,
+        }
+    };
+    // The job is queued but no worker runs yet, so it is cancelled first.
+    let WorkDoneProgress::Begin(begin) = next_progress(&server) else {
+        panic!("progress did not begin");
+    };
+    assert_eq!(begin.cancellable, Some(true));
+    assert!(cancellation::cancel_progress(&token));
+    server.run(GeneratorWorker::new(Config::default()).unwrap());
+    let end = next_progress(&server);
+    command.join().unwrap();
+
+    let WorkDoneProgress::End(end) = end else {
+        panic!("progress did not end");
+    };
+    assert_eq!(end.message.as_deref(), Some("Cancelled"));
+    assert_eq!(
+        server.response().error.unwrap().code,
+        ErrorCode::RequestCanceled as i32
+    );
+    // The progress ended, so its token no longer cancels anything.
+    assert!(!cancellation::cancel_progress(&token));
+    server.finish();
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{names, run_git, write};
+use traverse_lsp::analysis::imports::import_paths;
+use traverse_lsp::utils::{files_changed_since, find_solidity_files};
+
+#[test]
+fn test_import_paths() {
+    let source = r#"pragma solidity ^0.8.0;
+import "./A.sol";
+import {B, C as D} from '../lib/B.sol';
+import * as E from "@oz/contracts/E.sol";
+// import "./Commented.sol";
+contract X {}
+"#;
+    assert_eq!(
+        import_paths(source).unwrap(),
+        vec!["./A.sol", "../lib/B.sol", "@oz/contracts/E.sol"]
+    );
+}
+
+#[test]
+fn test_changed_files_and_import_closure() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    write(
+        root,
+        "src/Vault.sol",
+        "import \"./lib/Math.sol\";\nimport \"@oz/contracts/Ownable.sol\";\ncontract Vault {}\n",
+    );
+    write(
+        root,
+        "src/lib/Math.sol",
+        "import \"src/lib/Units.sol\";\nlibrary Math {}\n",
+    );
+    write(root, "src/lib/Units.sol", "library Units {}\n");
+    write(
+        root,
+        "lib/openzeppelin/contracts/Ownable.sol",
+        "contract Ownable {}\n",
+    );
+    write(root, "src/Unrelated.sol", "contract Unrelated {}\n");
+    write(
+        root,
+        "src/Token.sol",
+        "import \"./Vault.sol\";\ncontract Token {}\n",
+    );
+    run_git(root, &["init", "-q"]);
+    run_git(root, &["add", "-A"]);
+    run_git(root, &["commit", "-q", "-m", "base"]);
+
+    let all = find_solidity_files(root).unwrap();
+    assert!(files_changed_since(root, &all, "HEAD").unwrap().is_empty());
+
+    write(
+        root,
+        "src/Vault.sol",
+        "import \"./lib/Math.sol\";\nimport \"@oz/contracts/Ownable.sol\";\ncontract Vault { uint256 x; }\n",
+    );
+    write(root, "src/New.sol", "contract New {}\n");
+    let scope = files_changed_since(root, &all, "HEAD").unwrap();
+    assert_eq!(
+        names(root, &scope),
+        vec![
+            "lib/openzeppelin/contracts/Ownable.sol",
+            "src/Vault.sol",
+            "src/lib/Math.sol",
+            "src/lib/Units.sol",
+        ]
+    );
+
+    // New files are in scope once the workspace is rescanned.
+    let all = find_solidity_files(root).unwrap();
+    let scope = files_changed_since(root, &all, "HEAD").unwrap();
+    assert!(names(root, &scope).contains(&"src/New.sol".to_string()));
+
+    assert!(files_changed_since(root, &all, "-p").is_err());
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use common::run_worker_request;
+use lsp_types::Url;
+use traverse_lsp::analysis::clones::{find, render_clones, DEFAULT_MIN_NODES};
+use traverse_lsp::analysis::model::{parse_sources, SourceUnit};
+use traverse_lsp::GenerationRequest;
+
+const TOKEN: &str = r#"
+contract Token {
+    mapping(address => uint256) balances;
+
+    function transfer(address to, uint256 amount) public returns (bool) {
+        require(balances[msg.sender] >= amount, "balance");
+        balances[msg.sender] -= amount;
+        balances[to] += amount;
+        return true;
+    }
+}
+"#;
+
+const FORK: &str = r#"
+contract Fork {
+    mapping(address => uint256) holdings;
+
+    // Copied from Token.
+    function send(address recipient, uint256 value) public returns (bool) {
+        require(holdings[msg.sender] >= value, "balance");
+        holdings[msg.sender] -= value;
+        holdings[recipient] += value;
+        return true;
+    }
+
+    function burn(address from, uint256 amount) public returns (bool) {
+        require(holdings[from] >= amount, "balance");
+        holdings[from] -= amount;
+        holdings[address(0)] += amount;
+        return true;
+    }
+}
+
+contract Copy {
+    mapping(address => uint256) balances;
+
+    function transfer(address to, uint256 amount) public returns (bool) {
+        require(balances[msg.sender] >= amount, "balance");
+        balances[msg.sender] -= amount;
+        balances[to] += amount;
+        return true;
+    }
+}
+"#;
+
+fn sources() -> Vec<SourceUnit> {
+    vec![
+        SourceUnit {
+            path: "Token.sol".into(),
+            content: TOKEN.to_string(),
+        },
+        SourceUnit {
+            path: "Vault.sol".into(),
+            content: FORK.to_string(),
+        },
+    ]
+}
+
+#[test]
+fn test_clusters_renamed_and_identical_bodies() {
+    let sources = sources();
+    let parsed = parse_sources(&sources).unwrap();
+    let clusters = find(&parsed, DEFAULT_MIN_NODES);
+
+    assert_eq!(clusters.len(), 1);
+    let members: Vec<(&str, &str, bool)> = clusters[0]
+        .members
+        .iter()
+        .map(|m| (m.contract.as_str(), m.function.as_str(), m.identical))
+        .collect();
+    // `burn` has the same statements but a different shape.
+    assert_eq!(
+        members,
+        vec![
+            ("Token", "transfer", true),
+            ("Fork", "send", false),
+            ("Copy", "transfer", true),
+        ]
+    );
+    assert!(clusters[0].nodes >= DEFAULT_MIN_NODES);
+
+    let report = render_clones(&clusters);
+    assert!(report.contains("## `Token.transfer` and 2 more"));
+    assert!(report.contains("| Fork | send | Vault.sol:6 | renamed |"));
+    assert!(report.contains("| Copy | transfer | Vault.sol:24 | identical |"));
+}
+
+#[test]
+fn test_small_bodies_are_skipped() {
+    let sources = sources();
+    let parsed = parse_sources(&sources).unwrap();
+    let clusters = find(&parsed, 1000);
+    assert!(clusters.is_empty());
+    assert!(render_clones(&clusters).contains("No duplicated function bodies"));
+}
+
+#[test]
+fn test_find_clones_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut uris = Vec::new();
+    for source in sources() {
+        let path = dir.path().join(&source.path);
+        std::fs::write(&path, &source.content).unwrap();
+        uris.push(Url::from_file_path(&path).unwrap());
+    }
+
+    let response = run_worker_request(|tx| GenerationRequest::FindClones {
+        uris,
+        min_nodes: DEFAULT_MIN_NODES,
+        cancel: Default::default(),
+        tx,
+    });
+    let clusters = response["clusters"].as_array().unwrap();
+    assert_eq!(clusters.len(), 1);
+    assert_eq!(clusters[0]["members"].as_array().unwrap().len(), 3);
+    assert!(response["report"]
+        .as_str()
+        .unwrap()
+        .starts_with("# Function Clones"));
+}
@@ -0,0 +1,87 @@
+use lsp_types::{CodeActionKind, CodeActionOrCommand, Position, Range};
+use serde_json::json;
+use std::path::Path;
+use traverse_lsp::handlers::code_action::actions;
+use traverse_lsp::symbol_index::enclosing_contract;
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+interface IVault {
+    function deposit() external;
+}
+
+contract Vault is IVault {
+    uint256 total;
+
+    function deposit() external {
+        total += 1;
+    }
+}
+"#;
+
+fn at(line: u32, character: u32) -> Range {
+    Range::new(
+        Position::new(line, character),
+        Position::new(line + 1, character),
+    )
+}
+
+fn titles(range: Range) -> Vec<String> {
+    actions(VAULT, range, Path::new("/ws"))
+        .into_iter()
+        .map(|action| match action {
+            CodeActionOrCommand::CodeAction(action) => {
+                assert_eq!(action.kind, Some(CodeActionKind::SOURCE));
+                action.title
+            }
+            CodeActionOrCommand::Command(command) => panic!("bare command {:?}", command),
+        })
+        .collect()
+}
+
+#[test]
+fn test_actions_in_a_function() {
+    assert_eq!(
+        titles(at(10, 8)),
+        vec![
+            "Generate sequence diagram for Vault.deposit",
+            "Generate call graph for Vault",
+            "Generate sequence diagram for Vault",
+            "Show storage access of Vault",
+        ]
+    );
+    let CodeActionOrCommand::CodeAction(action) = &actions(VAULT, at(10, 8), Path::new("/ws"))[0]
+    else {
+        unreachable!();
+    };
+    let command = action.command.as_ref().unwrap();
+    assert_eq!(command.command, "traverse.generateFunctionDiagram");
+    assert_eq!(
+        command.arguments,
+        Some(vec![json!({
+            "workspace_folder": "/ws",
+            "contract": "Vault",
+            "function": "deposit",
+        })])
+    );
+}
+
+#[test]
+fn test_actions_outside_functions() {
+    // A state variable: the contract's actions only.
+    assert_eq!(
+        titles(at(7, 4)),
+        vec![
+            "Generate call graph for Vault",
+            "Generate sequence diagram for Vault",
+            "Show storage access of Vault",
+        ]
+    );
+    // Interfaces and the space between declarations have none.
+    assert!(titles(at(3, 8)).is_empty());
+    assert!(titles(at(1, 0)).is_empty());
+    assert_eq!(
+        enclosing_contract(VAULT, Position::new(6, 0)),
+        Some("Vault".into())
+    );
+}
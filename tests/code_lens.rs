@@ -0,0 +1,136 @@
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use traverse_lsp::handlers::code_lens::lenses;
+use traverse_lsp::workspace_folders::folder_of;
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+interface IVault {
+    function deposit() external;
+}
+
+contract Vault is IVault {
+    uint256 total;
+
+    function deposit() external {
+        _credit(1);
+    }
+
+    function balance() public view returns (uint256) {
+        return total;
+    }
+
+    function _credit(uint256 amount) internal {
+        total += amount;
+    }
+
+    function sweep() private {}
+}
+"#;
+
+#[test]
+fn test_lenses_above_contracts_and_entry_points() {
+    let lenses = lenses(VAULT, Path::new("/ws"));
+    let summary: Vec<(u32, &str, &str)> = lenses
+        .iter()
+        .map(|lens| {
+            let command = lens.command.as_ref().unwrap();
+            (
+                lens.range.start.line,
+                command.title.as_str(),
+                command.command.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                6,
+                "Generate call graph",
+                "traverse.generateCallGraph.workspace"
+            ),
+            (
+                6,
+                "Generate sequence diagram",
+                "traverse.generateSequenceDiagram.workspace"
+            ),
+            (
+                6,
+                "Show storage access",
+                "traverse.storageHeatmap.workspace"
+            ),
+            (
+                9,
+                "Generate call graph",
+                "traverse.generateCallGraph.workspace"
+            ),
+            (
+                9,
+                "Generate sequence diagram",
+                "traverse.generateFunctionDiagram"
+            ),
+            (
+                9,
+                "Show storage access",
+                "traverse.storageHeatmap.workspace"
+            ),
+            (
+                13,
+                "Generate call graph",
+                "traverse.generateCallGraph.workspace"
+            ),
+            (
+                13,
+                "Generate sequence diagram",
+                "traverse.generateFunctionDiagram"
+            ),
+            (
+                13,
+                "Show storage access",
+                "traverse.storageHeatmap.workspace"
+            ),
+        ]
+    );
+
+    let arguments = |index: usize| lenses[index].command.as_ref().unwrap().arguments.clone();
+    assert_eq!(
+        arguments(0),
+        Some(vec![
+            json!({ "workspace_folder": "/ws", "contract": "Vault" })
+        ])
+    );
+    assert_eq!(
+        arguments(4),
+        Some(vec![json!({
+            "workspace_folder": "/ws",
+            "contract": "Vault",
+            "function": "deposit"
+        })])
+    );
+}
+
+#[test]
+fn test_lenses_for_legacy_and_broken_sources() {
+    // Functions without a visibility were public before 0.5.
+    let legacy = "contract Old {\n    function run() {}\n}\n";
+    assert_eq!(lenses(legacy, Path::new("/ws")).len(), 6);
+    assert!(lenses("", Path::new("/ws")).is_empty());
+}
+
+#[test]
+fn test_folder_of_file() {
+    let folders = vec![PathBuf::from("/ws"), PathBuf::from("/ws/packages/core")];
+    assert_eq!(
+        folder_of(Path::new("/ws/packages/core/src/Vault.sol"), &folders),
+        PathBuf::from("/ws/packages/core")
+    );
+    assert_eq!(
+        folder_of(Path::new("/ws/src/Vault.sol"), &folders),
+        PathBuf::from("/ws")
+    );
+    assert_eq!(
+        folder_of(Path::new("/elsewhere/Vault.sol"), &folders),
+        PathBuf::from("/elsewhere")
+    );
+}
@@ -0,0 +1,173 @@
+//! Helpers shared by the integration tests. Each test crate uses only some
+//! of them.
+#![allow(dead_code)]
+
+use lsp_server::{Connection, Message, Request, RequestId, Response};
+use lsp_types::{
+    ExecuteCommandParams, NumberOrString, ProgressParams, ProgressParamsValue, Url,
+    WorkDoneProgress, WorkDoneProgressParams,
+};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use traverse_lsp::config::Config;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+/// Writes `content` to `relative` under `root`, creating its directories.
+pub fn write(root: &Path, relative: &str, content: &str) {
+    let path = root.join(relative);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// Paths of `files` relative to `root`, with `/` separators.
+pub fn names(root: &Path, files: &[Url]) -> Vec<String> {
+    let paths: Vec<PathBuf> = files
+        .iter()
+        .map(|uri| uri.to_file_path().unwrap())
+        .collect();
+    relative_names(root, &paths)
+}
+
+/// `paths` relative to `root`, with `/` separators.
+pub fn relative_names(root: &Path, paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| {
+            path.strip_prefix(root)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect()
+}
+
+/// Runs `git` in `dir` as a fixed test identity and asserts it succeeds.
+pub fn run_git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?}", args);
+}
+
+/// Runs the request `build` makes on a fresh worker with the default
+/// config and returns its result as JSON.
+pub fn run_worker_request(
+    build: impl FnOnce(oneshot::Sender<anyhow::Result<String>>) -> GenerationRequest,
+) -> serde_json::Value {
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(build(reply_tx)).unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+    serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap()
+}
+
+/// Both ends of an in-memory connection, with a generator worker behind the
+/// server side once [`InMemoryServer::run`] starts one.
+pub struct InMemoryServer {
+    pub server: Connection,
+    pub client: Connection,
+    pub generator_tx: mpsc::Sender<GenerationRequest>,
+    generator_rx: Option<mpsc::Receiver<GenerationRequest>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl InMemoryServer {
+    /// A server whose generation requests wait for [`InMemoryServer::run`].
+    pub fn new() -> Self {
+        let (server, client) = Connection::memory();
+        let (generator_tx, generator_rx) = mpsc::channel();
+        Self {
+            server,
+            client,
+            generator_tx,
+            generator_rx: Some(generator_rx),
+            worker: None,
+        }
+    }
+
+    /// A server with a worker running on the default config.
+    pub fn start() -> Self {
+        let mut server = Self::new();
+        server.run(GeneratorWorker::new(Config::default()).unwrap());
+        server
+    }
+
+    /// Starts `worker` on the generation requests, including those sent
+    /// before.
+    pub fn run(&mut self, worker: GeneratorWorker) {
+        let generator_rx = self.generator_rx.take().expect("worker already started");
+        self.worker = Some(thread::spawn(move || worker.run(generator_rx)));
+    }
+
+    /// The next message sent to the client.
+    pub fn next_message(&self) -> Message {
+        self.client
+            .receiver
+            .recv_timeout(Duration::from_secs(30))
+            .unwrap()
+    }
+
+    /// The next response sent to the client, skipping other messages.
+    pub fn response(&self) -> Response {
+        loop {
+            if let Message::Response(response) = self.next_message() {
+                return response;
+            }
+        }
+    }
+
+    /// Stops the worker once it has finished the requests sent to it, and
+    /// returns the messages sent to the client that were not received yet.
+    pub fn finish(self) -> Vec<Message> {
+        self.generator_tx.send(GenerationRequest::Shutdown).unwrap();
+        if let Some(worker) = self.worker {
+            worker.join().unwrap();
+        }
+        drop(self.server);
+        self.client.receiver.iter().collect()
+    }
+}
+
+/// A `workspace/executeCommand` request running `command` with `arguments`.
+pub fn command_request(
+    id: i32,
+    command: &str,
+    arguments: Vec<serde_json::Value>,
+    work_done_token: Option<&str>,
+) -> Request {
+    Request::new(
+        RequestId::from(id),
+        "workspace/executeCommand".to_string(),
+        ExecuteCommandParams {
+            command: command.to_string(),
+            arguments,
+            work_done_progress_params: WorkDoneProgressParams {
+                work_done_token: work_done_token.map(|t| NumberOrString::String(t.to_string())),
+            },
+        },
+    )
+}
+
+/// The `$/progress` values in `messages` with their tokens.
+pub fn progress_values(messages: &[Message]) -> Vec<(NumberOrString, WorkDoneProgress)> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::Notification(n) if n.method == "$/progress" => {
+                let params: ProgressParams = serde_json::from_value(n.params.clone()).unwrap();
+                let ProgressParamsValue::WorkDone(value) = params.value;
+                Some((params.token, value))
+            }
+            _ => None,
+        })
+        .collect()
+}
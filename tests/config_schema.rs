@@ -0,0 +1,62 @@
+use serde_json::{json, Value};
+use traverse_lsp::analysis::RULES;
+use traverse_lsp::config::{self, Config};
+
+fn properties() -> serde_json::Map<String, Value> {
+    config::schema()["properties"].as_object().unwrap().clone()
+}
+
+#[test]
+fn test_schema_covers_every_setting() {
+    let properties = properties();
+    let settings = serde_json::to_value(Config::default()).unwrap();
+    let mut keys: Vec<&String> = settings.as_object().unwrap().keys().collect();
+    let mut documented: Vec<&String> = properties.keys().collect();
+    keys.sort();
+    documented.sort();
+    assert_eq!(keys, documented);
+    for (name, property) in &properties {
+        assert!(property.get("type").is_some(), "{} has no type", name);
+    }
+}
+
+#[test]
+fn test_schema_defaults_are_accepted() {
+    let defaults: serde_json::Map<String, Value> = properties()
+        .into_iter()
+        .filter_map(|(name, property)| Some((name, property.get("default")?.clone())))
+        .collect();
+    let config: Config = serde_json::from_value(Value::Object(defaults)).unwrap();
+    assert_eq!(
+        config.generation_timeout(),
+        Config::default().generation_timeout()
+    );
+    assert_eq!(
+        config.cache_budget_bytes(),
+        Config::default().cache_budget_bytes()
+    );
+    assert_eq!(
+        config.max_response_bytes(),
+        Config::default().max_response_bytes()
+    );
+    assert_eq!(
+        config.generation_workers(),
+        Config::default().generation_workers()
+    );
+}
+
+#[test]
+fn test_schema_lists_rule_ids() {
+    let rules = &properties()["rules"];
+    let ids: Vec<&str> = rules["propertyNames"]["enum"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|id| id.as_str().unwrap())
+        .collect();
+    assert_eq!(ids, RULES.iter().map(|r| r.id).collect::<Vec<_>>());
+    assert_eq!(
+        rules["additionalProperties"]["properties"]["severity"]["enum"],
+        json!(["info", "low", "medium", "high", null])
+    );
+}
@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+use traverse_lsp::coverage::{self, FunctionHits};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const VAULT: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Vault {
+    uint256 total;
+
+    function deposit(uint256 amount) external {
+        _credit(amount);
+    }
+
+    function withdraw(uint256 amount) external {
+        total -= amount;
+    }
+
+    function _credit(uint256 amount) internal {
+        total += amount;
+    }
+
+    function _unused() internal {
+        total = 0;
+    }
+}
+"#;
+
+// Foundry qualifies names; plain lcov does not and uses the lcov 2 form.
+const LCOV: &str = "\
+TN:
+SF:./src/Vault.sol
+FN:7,Vault.deposit
+FNDA:3,Vault.deposit
+FN:11,Vault.withdraw
+FNDA:0,Vault.withdraw
+end_of_record
+SF:src/Vault.sol
+FN:15,17,_credit
+FN:19,21,_unused
+FNDA:3,_credit
+FNDA:0,_unused
+end_of_record
+";
+
+#[test]
+fn test_parse_lcov() {
+    let records = coverage::parse_lcov(LCOV);
+    assert_eq!(records.len(), 4);
+    assert_eq!(
+        records[0],
+        FunctionHits {
+            file: PathBuf::from("./src/Vault.sol"),
+            contract: Some("Vault".to_string()),
+            function: "deposit".to_string(),
+            line: 7,
+            hits: 3,
+        }
+    );
+    assert_eq!(records[2].contract, None);
+    assert_eq!(records[2].function, "_credit");
+    assert_eq!(records[2].line, 15);
+    assert_eq!(records[3].hits, 0);
+}
+
+#[test]
+fn test_coverage_overlay_colors_uncovered_reachable_functions() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(VAULT)
+        .expect("Failed to build call graph");
+    let unit = SourceUnit {
+        path: PathBuf::from("/workspace/src/Vault.sol"),
+        content: VAULT.to_string(),
+    };
+    let parsed = parse_sources(std::slice::from_ref(&unit)).unwrap();
+    let model = SourceModel::from_parsed(&parsed);
+
+    let overlay = coverage::overlay(&graph, &coverage::parse_lcov(LCOV), &model);
+    let find = |name: &str| overlay.iter().find(|c| c.function == name).unwrap();
+    assert_eq!(find("deposit").hits, Some(3));
+    assert_eq!(find("_credit").hits, Some(3));
+    assert!(find("_credit").externally_reachable);
+    assert_eq!(find("withdraw").hits, Some(0));
+    assert!(find("withdraw").externally_reachable);
+    assert_eq!(find("_unused").hits, Some(0));
+    assert!(!find("_unused").externally_reachable);
+
+    let dot = adapter.generate_dot_diagram(&graph).unwrap();
+    let colored = coverage::color_dot(&dot, &overlay);
+    let node_line = |name: &str| {
+        let id = find(name).node_id;
+        colored
+            .lines()
+            .find(|l| l.trim_start().starts_with(&format!("n{} [", id)))
+            .unwrap()
+            .to_string()
+    };
+    assert!(node_line("deposit").contains("fillcolor=\"palegreen\""));
+    assert!(node_line("withdraw").contains("fillcolor=\"red\""));
+    assert!(node_line("_unused").contains("fillcolor=\"lightblue\""));
+    assert_eq!(colored.lines().count(), dot.lines().count());
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+use traverse_lsp::analysis::cross_reference::{self, CrossReference, SymbolKind, UsageKind};
+use traverse_lsp::analysis::model::{parse_sources, SourceUnit};
+
+const AUCTION: &str = r#"pragma solidity ^0.8.0;
+
+uint256 constant BPS = 10_000;
+
+contract Base {
+    uint256 constant MAX_BIDS = 100;
+}
+
+contract Auction is Base {
+    enum State { Open, Closed, Cancelled }
+
+    State public state;
+    uint256 public fee = BPS / 100;
+
+    function bid(uint256 count) external {
+        require(state == State.Open);
+        require(count <= MAX_BIDS);
+        emitFee(Base.MAX_BIDS);
+    }
+
+    function close() external {
+        if (state != State.Open) {
+            return;
+        }
+        state = State.Closed;
+    }
+
+    function limit() external pure returns (uint256) {
+        return MAX_BIDS;
+    }
+
+    function emitFee(uint256 amount) internal {}
+}
+
+contract Other {
+    uint256 constant MAX_BIDS = 5;
+}
+"#;
+
+fn build() -> Vec<CrossReference> {
+    let units = [SourceUnit {
+        path: PathBuf::from("Auction.sol"),
+        content: AUCTION.to_string(),
+    }];
+    let parsed = parse_sources(&units).unwrap();
+    cross_reference::build(&parsed)
+}
+
+fn usages(
+    references: &[CrossReference],
+    name: &str,
+    declared_in: Option<&str>,
+) -> Vec<(UsageKind, usize)> {
+    references
+        .iter()
+        .find(|r| r.name == name && r.declared_in.as_deref() == declared_in)
+        .unwrap_or_else(|| panic!("{} not found", name))
+        .usages
+        .iter()
+        .map(|u| (u.kind, u.line))
+        .collect()
+}
+
+#[test]
+fn test_enum_value_uses() {
+    let references = build();
+    assert_eq!(
+        usages(&references, "State.Open", Some("Auction")),
+        vec![(UsageKind::Comparison, 16), (UsageKind::Comparison, 22)]
+    );
+    assert_eq!(
+        usages(&references, "State.Closed", Some("Auction")),
+        vec![(UsageKind::Assignment, 25)]
+    );
+    assert!(usages(&references, "State.Cancelled", Some("Auction")).is_empty());
+
+    let closed = references
+        .iter()
+        .find(|r| r.name == "State.Closed")
+        .unwrap();
+    assert_eq!(closed.kind, SymbolKind::EnumValue);
+    let usage = &closed.usages[0];
+    assert_eq!(usage.function.as_deref(), Some("close"));
+    assert_eq!(usage.code, "state = State.Closed;");
+}
+
+#[test]
+fn test_constant_uses() {
+    let references = build();
+    // Bare uses resolve through inheritance; `Other.MAX_BIDS` is unrelated.
+    assert_eq!(
+        usages(&references, "MAX_BIDS", Some("Base")),
+        vec![
+            (UsageKind::Comparison, 17),
+            (UsageKind::Argument, 18),
+            (UsageKind::Return, 29),
+        ]
+    );
+    assert!(usages(&references, "MAX_BIDS", Some("Other")).is_empty());
+
+    let bps = references.iter().find(|r| r.name == "BPS").unwrap();
+    assert_eq!(bps.value.as_deref(), Some("10_000"));
+    assert_eq!(bps.declared_in, None);
+    assert_eq!(
+        usages(&references, "BPS", None),
+        vec![(UsageKind::Assignment, 13)]
+    );
+    assert_eq!(bps.usages[0].function, None);
+}
+
+#[test]
+fn test_cross_reference_report() {
+    let report = cross_reference::render_cross_reference(&build());
+    assert!(report.starts_with("# Enum and Constant Cross-Reference"));
+    assert!(report.contains("| `State.Open` | enum value | Auction | 2 | 0 | 0 |"));
+    assert!(report.contains("| `BPS` | constant | (file level) | 0 | 1 | 0 |"));
+    assert!(report.contains("## `State.Cancelled`\n\n_Never used._"));
+    assert!(report
+        .contains("| Auction.sol:25 | Auction.close | assignment | `state = State.Closed;` |"));
+}
@@ -0,0 +1,86 @@
+use lsp_types::{Position, Range, Url};
+use std::path::PathBuf;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::handlers::definition::definitions;
+use traverse_lsp::source_map::SourceMap;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const TOKEN: &str = r#"interface IToken {
+    function transfer(address to, uint256 amount) external returns (bool);
+}
+
+contract Token is IToken {
+    function transfer(address to, uint256 amount) external returns (bool) {
+        return true;
+    }
+}
+"#;
+
+const VAULT: &str = r#"import "./Token.sol";
+
+contract Vault {
+    IToken token;
+
+    function withdraw(uint256 amount) external {
+        token.transfer(msg.sender, scaled(amount));
+    }
+
+    function scaled(uint256 amount) internal pure returns (uint256) {
+        return amount * 2;
+    }
+}
+"#;
+
+fn sources() -> Vec<SourceUnit> {
+    vec![
+        SourceUnit {
+            path: PathBuf::from("/workspace/Token.sol"),
+            content: TOKEN.to_string(),
+        },
+        SourceUnit {
+            path: PathBuf::from("/workspace/Vault.sol"),
+            content: VAULT.to_string(),
+        },
+    ]
+}
+
+fn range(line: u32, start: u32, end: u32) -> Range {
+    Range::new(Position::new(line, start), Position::new(line, end))
+}
+
+#[test]
+fn test_interface_call_resolves_to_implementation_in_other_file() {
+    let sources = sources();
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    let map = SourceMap::new(&sources);
+    let vault = &sources[1].path;
+
+    // On `transfer` of `token.transfer(...)`.
+    let found = definitions(&graph, &map, vault, Position::new(6, 16));
+    assert_eq!(found.len(), 1);
+    assert_eq!(
+        found[0].uri,
+        Url::from_file_path("/workspace/Token.sol").unwrap()
+    );
+    assert_eq!(found[0].range, range(5, 13, 21));
+
+    // The call in the arguments wins over the call around it.
+    let found = definitions(&graph, &map, vault, Position::new(6, 36));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].uri, Url::from_file_path(vault).unwrap());
+    assert_eq!(found[0].range, range(9, 13, 19));
+
+    // Arguments that are not calls resolve to nothing.
+    assert!(definitions(&graph, &map, vault, Position::new(6, 43)).is_empty());
+    // Neither does a file the graph was not built from.
+    assert!(definitions(
+        &graph,
+        &map,
+        &PathBuf::from("/workspace/Other.sol"),
+        Position::new(6, 16)
+    )
+    .is_empty());
+}
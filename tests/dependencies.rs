@@ -0,0 +1,186 @@
+mod common;
+
+use common::run_worker_request;
+use lsp_types::{HoverContents, Position};
+use std::path::{Path, PathBuf};
+use traverse_lsp::analysis::dependencies::{self, FunctionDependencies};
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+use traverse_lsp::handlers::code_lens::lenses;
+use traverse_lsp::handlers::hover::hover_for;
+use traverse_lsp::symbol_index::declaration_at;
+use traverse_lsp::GenerationRequest;
+
+const SOURCES: &str = r#"pragma solidity ^0.8.0;
+
+interface IAggregator {
+    function latestRoundData() external view returns (uint80, int256, uint256, uint256, uint80);
+}
+
+interface IERC20 {
+    function transfer(address to, uint256 amount) external returns (bool);
+}
+
+contract Lender {
+    IAggregator feed;
+    IERC20 token;
+    address implementation;
+    uint256 deadline;
+
+    modifier fresh() {
+        require(block.number > 0);
+        _;
+    }
+
+    function borrow(uint256 amount) external {
+        (, int256 price, , , ) = feed.latestRoundData();
+        require(_open() && price > 0);
+        token.transfer(msg.sender, amount);
+    }
+
+    function refund(address to) external {
+        payable(to).transfer(1);
+    }
+
+    function upgrade(bytes calldata data) external fresh {
+        implementation.delegatecall(data);
+    }
+
+    function _open() internal view returns (bool) {
+        return block.timestamp < deadline;
+    }
+
+    function ping() external pure returns (uint256) {
+        return 1;
+    }
+}
+"#;
+
+fn analyze() -> Vec<FunctionDependencies> {
+    let units = [SourceUnit {
+        path: PathBuf::from("Lender.sol"),
+        content: SOURCES.to_string(),
+    }];
+    let parsed = parse_sources(&units).unwrap();
+    let model = SourceModel::from_parsed(&parsed);
+    dependencies::analyze(&parsed, &model)
+}
+
+fn badges(functions: &[FunctionDependencies]) -> Vec<(&str, Vec<String>)> {
+    functions
+        .iter()
+        .map(|f| {
+            (
+                f.function.as_str(),
+                f.badges.iter().map(|b| b.describe()).collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_direct_and_inherited_badges() {
+    let functions = analyze();
+    assert!(functions.iter().all(|f| f.contract == "Lender"));
+    assert_eq!(
+        badges(&functions),
+        vec![
+            (
+                "fresh",
+                vec!["block environment (`block.number`)".to_string()]
+            ),
+            (
+                "borrow",
+                vec![
+                    "oracle (`feed.latestRoundData()`)".to_string(),
+                    "token (`token.transfer()`)".to_string(),
+                    "block environment (via `_open()`)".to_string(),
+                ]
+            ),
+            ("refund", vec![]),
+            (
+                "upgrade",
+                vec![
+                    "delegatecall (`implementation.delegatecall()`)".to_string(),
+                    "block environment (via `fresh()`)".to_string(),
+                ]
+            ),
+            (
+                "_open",
+                vec!["block environment (`block.timestamp`)".to_string()]
+            ),
+            ("ping", vec![]),
+        ]
+    );
+
+    let report = dependencies::render_dependencies(&functions);
+    assert!(report.contains("**Functions with external dependencies:** 4 of 6"));
+    assert!(report.contains("**oracle:** 1 | **token:** 1 | **delegatecall:** 1"));
+    assert!(report.contains("| `upgrade` | 32 | delegatecall"));
+    assert!(!report.contains("`ping`"));
+}
+
+#[test]
+fn test_badges_in_hover_and_code_lenses() {
+    let borrow = declaration_at(SOURCES, Position::new(21, 15)).unwrap();
+    let hover = hover_for(&borrow, None, &dependencies::for_source(SOURCES));
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("{:?}", hover.contents);
+    };
+    assert!(markup.value.ends_with(
+        "**Depends on:** oracle (`feed.latestRoundData()`), token (`token.transfer()`), \
+         block environment (via `_open()`)"
+    ));
+
+    let ping = declaration_at(SOURCES, Position::new(39, 15)).unwrap();
+    let hover = hover_for(&ping, None, &dependencies::for_source(SOURCES));
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("{:?}", hover.contents);
+    };
+    assert!(!markup.value.contains("Depends on"));
+
+    let badged: Vec<(u32, String)> = lenses(SOURCES, Path::new("/ws"))
+        .into_iter()
+        .filter_map(|lens| {
+            let command = lens.command?;
+            (command.command == "traverse.externalDependencies.workspace")
+                .then_some((lens.range.start.line, command.title))
+        })
+        .collect();
+    assert_eq!(
+        badged,
+        vec![
+            (
+                21,
+                "Depends on: oracle, token, block environment".to_string()
+            ),
+            (
+                31,
+                "Depends on: delegatecall, block environment".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_external_dependencies_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Lender.sol");
+    std::fs::write(&path, SOURCES).unwrap();
+
+    let response = run_worker_request(|tx| GenerationRequest::ExternalDependencies {
+        uris: vec![lsp_types::Url::from_file_path(&path).unwrap()],
+        contract_name: Some("Lender".to_string()),
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(response["functions"].as_array().unwrap().len(), 4);
+    assert_eq!(response["functions"][1]["function"], "borrow");
+    assert_eq!(
+        response["functions"][1]["badges"][0]["dependency"],
+        "oracle"
+    );
+    assert!(response["report"]
+        .as_str()
+        .unwrap()
+        .starts_with("# External Dependencies"));
+}
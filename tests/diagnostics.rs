@@ -0,0 +1,216 @@
+use lsp_types::{DiagnosticSeverity, NumberOrString, PublishDiagnosticsParams, Url};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use traverse_lsp::analysis::{model::SourceUnit, quick_checks};
+use traverse_lsp::config::Config;
+use traverse_lsp::diagnostics::{DiagnosticsEvent, DiagnosticsWorker, DEBOUNCE};
+use traverse_lsp::symbol_index::SymbolIndex;
+
+const VAULT: &str = r#"
+pragma solidity ^0.8.0;
+
+interface IERC20 {
+    function transfer(address to, uint256 amount) external returns (bool);
+}
+
+contract Base {
+    address public owner;
+    uint256 private secret;
+}
+
+contract Vault is Base {
+    uint256 total;
+    IERC20 token;
+
+    function deposit(uint256 total) external {
+        uint256 secret = total;
+        for (uint256 i; i < secret; i++) {}
+    }
+
+    function sweep(address payable to) external returns (bool owner) {
+        to.call{value: 1}("");
+        token.transfer(to, 1);
+        to.transfer(1);
+        (bool ok, ) = to.call("");
+        require(ok);
+    }
+}
+"#;
+
+fn unit(content: &str) -> SourceUnit {
+    SourceUnit {
+        path: PathBuf::from("Vault.sol"),
+        content: content.to_string(),
+    }
+}
+
+#[test]
+fn test_quick_checks() {
+    let findings = quick_checks::check(&unit(VAULT), &Config::default()).unwrap();
+    let summary: Vec<(&str, usize)> = findings.iter().map(|f| (f.rule, f.line.unwrap())).collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("shadowing", 17),
+            ("shadowing", 22),
+            ("unchecked-return", 23),
+            ("unchecked-return", 24),
+        ]
+    );
+    assert!(findings[1].message.contains("`Base.owner`"));
+    assert!(findings[2].message.contains("low-level `call`"));
+
+    let broken = "contract A {\n    function f() external {\n        uint256 x = ;\n    }\n}\n";
+    let findings = quick_checks::check(&unit(broken), &Config::default()).unwrap();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].rule, "parse-error");
+    assert_eq!(findings[0].line, Some(3));
+
+    let config: Config = serde_json::from_value(
+        serde_json::json!({ "rules": { "shadowing": { "enabled": false } } }),
+    )
+    .unwrap();
+    let findings = quick_checks::check(&unit(VAULT), &config).unwrap();
+    assert!(findings.iter().all(|f| f.rule == "unchecked-return"));
+}
+
+fn spawn_worker(
+    workspace_root: Option<PathBuf>,
+) -> (
+    mpsc::Sender<DiagnosticsEvent>,
+    mpsc::Receiver<PublishDiagnosticsParams>,
+    thread::JoinHandle<()>,
+) {
+    let (event_tx, event_rx) = mpsc::channel();
+    let (publish_tx, publish_rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        DiagnosticsWorker::new(
+            Config::default(),
+            workspace_root,
+            Arc::new(SymbolIndex::new()),
+            move |params| {
+                let _ = publish_tx.send(params);
+            },
+        )
+        .run(event_rx);
+    });
+    (event_tx, publish_rx, handle)
+}
+
+#[test]
+fn test_changes_are_debounced() {
+    let (tx, published, handle) = spawn_worker(None);
+    let uri = Url::parse("file:///work/Vault.sol").unwrap();
+
+    for version in 1..=3 {
+        tx.send(DiagnosticsEvent::Changed {
+            uri: uri.clone(),
+            version,
+            text: VAULT.to_string(),
+        })
+        .unwrap();
+    }
+    let params = published.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(params.version, Some(3));
+    assert_eq!(params.diagnostics.len(), 4);
+    let low_level = &params.diagnostics[2];
+    assert_eq!(low_level.severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(
+        low_level.code,
+        Some(NumberOrString::String("unchecked-return".to_string()))
+    );
+    assert_eq!(low_level.range.start.line, 22);
+    assert_eq!(low_level.range.start.character, 8);
+    assert!(published.recv_timeout(DEBOUNCE * 3).is_err());
+
+    tx.send(DiagnosticsEvent::Closed { uri }).unwrap();
+    let params = published.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert!(params.diagnostics.is_empty());
+    assert_eq!(params.version, None);
+
+    tx.send(DiagnosticsEvent::Shutdown).unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_save_runs_workspace_audit() {
+    let workspace = tempfile::tempdir().unwrap();
+    let base = workspace.path().join("Base.sol");
+    std::fs::write(
+        &base,
+        "pragma solidity ^0.8.0;\n\nabstract contract Initializable {}\n\nabstract contract BaseUpgradeable is Initializable {\n    uint256 a;\n}\n",
+    )
+    .unwrap();
+    let game = workspace.path().join("Game.sol");
+    let game_source = "pragma solidity ^0.8.0;\n\ncontract Game {\n    function play() external payable {\n        require(address(this).balance <= 10 ether);\n    }\n}\n";
+    std::fs::write(&game, game_source).unwrap();
+
+    let (tx, published, handle) = spawn_worker(Some(workspace.path().to_path_buf()));
+    let game_uri = Url::from_file_path(&game).unwrap();
+    tx.send(DiagnosticsEvent::Changed {
+        uri: game_uri.clone(),
+        version: 1,
+        text: game_source.to_string(),
+    })
+    .unwrap();
+    tx.send(DiagnosticsEvent::Saved {
+        uri: game_uri.clone(),
+    })
+    .unwrap();
+
+    let mut by_uri = std::collections::HashMap::new();
+    while let Ok(params) = published.recv_timeout(Duration::from_secs(2)) {
+        by_uri.insert(params.uri.clone(), params);
+    }
+    let rules = |uri: &Url| -> Vec<String> {
+        by_uri[uri]
+            .diagnostics
+            .iter()
+            .filter_map(|d| match &d.code {
+                Some(NumberOrString::String(code)) => Some(code.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+    assert_eq!(rules(&game_uri), vec!["self-balance"]);
+    assert_eq!(
+        rules(&Url::from_file_path(&base).unwrap()),
+        vec!["storage-gap"]
+    );
+
+    tx.send(DiagnosticsEvent::Shutdown).unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_rule_changes_recheck_open_documents() {
+    let (tx, published, handle) = spawn_worker(None);
+    let uri = Url::parse("file:///work/Vault.sol").unwrap();
+    tx.send(DiagnosticsEvent::Changed {
+        uri: uri.clone(),
+        version: 1,
+        text: VAULT.to_string(),
+    })
+    .unwrap();
+    let params = published.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(params.diagnostics.len(), 4);
+
+    let config: Config = serde_json::from_value(
+        serde_json::json!({ "rules": { "unchecked-return": { "enabled": false } } }),
+    )
+    .unwrap();
+    tx.send(DiagnosticsEvent::ConfigChanged(config)).unwrap();
+    let params = published.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(params.uri, uri);
+    assert_eq!(params.version, Some(1));
+    let codes: Vec<_> = params.diagnostics.iter().map(|d| d.code.clone()).collect();
+    assert_eq!(
+        codes,
+        vec![Some(NumberOrString::String("shadowing".to_string())); 2]
+    );
+
+    tx.send(DiagnosticsEvent::Shutdown).unwrap();
+    handle.join().unwrap();
+}
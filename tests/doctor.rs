@@ -0,0 +1,40 @@
+use lsp_types::ClientCapabilities;
+use std::path::PathBuf;
+use traverse_lsp::doctor::{self, Environment, Status};
+use traverse_lsp::graph_cache::GraphCache;
+
+#[test]
+fn test_failing_checks() {
+    let workspace = tempfile::tempdir().unwrap();
+    let file = workspace.path().join("not-a-dir");
+    std::fs::write(&file, "").unwrap();
+    let capabilities = ClientCapabilities::default();
+    let check = |folders: &[PathBuf], output_dir: &PathBuf| {
+        doctor::run(&Environment {
+            folders,
+            output_dir,
+            cache: GraphCache::new(1024 * 1024).stats(),
+            capabilities: &capabilities,
+        })
+    };
+
+    let report = check(&[], &workspace.path().join("out"));
+    assert!(!report.ok);
+    assert_eq!(report.checks[0].status, Status::Error);
+    assert_eq!(report.checks[2].status, Status::Ok);
+    assert_eq!(report.checks[3].status, Status::Ok);
+    assert_eq!(report.checks[4].status, Status::Warning);
+    assert!(report.checks[4]
+        .detail
+        .starts_with("window.workDoneProgress: commands report progress only on a workDoneToken"));
+
+    let report = check(std::slice::from_ref(&file), &file.join("out"));
+    let failed: Vec<&str> = report
+        .checks
+        .iter()
+        .filter(|c| c.status == Status::Error)
+        .map(|c| c.name)
+        .collect();
+    assert_eq!(failed, vec!["workspace", "output_dir"]);
+    assert!(file.is_file());
+}
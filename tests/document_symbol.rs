@@ -0,0 +1,141 @@
+use lsp_types::{DocumentSymbol, Position, SymbolKind};
+use traverse_lsp::handlers::document_symbol::outline;
+
+const SOURCE: &str = r#"pragma solidity ^0.8.0;
+
+interface IVault {
+    event Deposited(address indexed from, uint256 amount);
+
+    function deposit(uint256 amount) external;
+}
+
+library Math {
+    function min(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a < b ? a : b;
+    }
+}
+
+error Unauthorized(address caller);
+
+contract Vault is IVault {
+    struct Account {
+        uint256 balance;
+    }
+
+    enum State { Open, Closed }
+
+    address public owner;
+    mapping(address => Account) accounts;
+
+    modifier onlyOwner() {
+        _;
+    }
+
+    constructor(address owner_) {
+        owner = owner_;
+    }
+
+    function deposit(
+        uint256 amount
+    ) external {
+        accounts[msg.sender].balance += amount;
+    }
+
+    receive() external payable {}
+}
+
+function helper() pure returns (uint256) {
+    return 1;
+}
+"#;
+
+fn summary(symbols: &[DocumentSymbol]) -> Vec<(String, SymbolKind, Option<String>)> {
+    symbols
+        .iter()
+        .map(|s| (s.name.clone(), s.kind, s.detail.clone()))
+        .collect()
+}
+
+fn entry(
+    name: &str,
+    kind: SymbolKind,
+    detail: Option<&str>,
+) -> (String, SymbolKind, Option<String>) {
+    (name.to_string(), kind, detail.map(str::to_string))
+}
+
+#[test]
+fn test_outline_nests_members_under_contracts() {
+    let symbols = outline(SOURCE);
+    assert_eq!(
+        summary(&symbols),
+        vec![
+            entry("IVault", SymbolKind::INTERFACE, None),
+            entry("Math", SymbolKind::MODULE, None),
+            entry(
+                "Unauthorized",
+                SymbolKind::OBJECT,
+                Some("error (address caller)")
+            ),
+            entry("Vault", SymbolKind::CLASS, None),
+            entry(
+                "helper",
+                SymbolKind::FUNCTION,
+                Some("() pure returns (uint256)")
+            ),
+        ]
+    );
+    let interface = symbols[0].children.as_deref().unwrap();
+    assert_eq!(
+        summary(interface),
+        vec![
+            entry(
+                "Deposited",
+                SymbolKind::EVENT,
+                Some("(address indexed from, uint256 amount)")
+            ),
+            entry(
+                "deposit",
+                SymbolKind::METHOD,
+                Some("(uint256 amount) external")
+            ),
+        ]
+    );
+
+    let vault = &symbols[3];
+    assert_eq!(
+        summary(vault.children.as_deref().unwrap()),
+        vec![
+            entry("Account", SymbolKind::STRUCT, None),
+            entry("State", SymbolKind::ENUM, None),
+            entry("owner", SymbolKind::FIELD, Some("address")),
+            entry(
+                "accounts",
+                SymbolKind::FIELD,
+                Some("mapping(address => Account)")
+            ),
+            entry("onlyOwner", SymbolKind::METHOD, Some("modifier ()")),
+            entry(
+                "constructor",
+                SymbolKind::CONSTRUCTOR,
+                Some("(address owner_)")
+            ),
+            entry(
+                "deposit",
+                SymbolKind::METHOD,
+                Some("(uint256 amount) external")
+            ),
+            entry("receive", SymbolKind::METHOD, Some("() external payable")),
+        ]
+    );
+    // The range covers the declaration and the selection its name.
+    assert_eq!(vault.range.start, Position::new(16, 0));
+    assert_eq!(vault.range.end, Position::new(41, 1));
+    assert_eq!(vault.selection_range.start, Position::new(16, 9));
+    assert_eq!(vault.selection_range.end, Position::new(16, 14));
+}
+
+#[test]
+fn test_outline_of_empty_text_is_empty() {
+    assert!(outline("").is_empty());
+}
@@ -0,0 +1,188 @@
+use lsp_types::{Position, Range, TextDocumentContentChangeEvent, Url};
+use std::sync::{mpsc, Arc};
+use tokio::sync::oneshot;
+use traverse_lsp::config::{Config, DotConfig, GraphFilter};
+use traverse_lsp::documents::DocumentStore;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const SAVED: &str = r#"pragma solidity ^0.8.0;
+
+contract Vault {
+    function deposit() external {}
+}
+"#;
+
+const EDITED: &str = r#"pragma solidity ^0.8.0;
+
+contract Vault {
+    function deposit() external {}
+
+    function withdraw() external {}
+}
+"#;
+
+fn call_graph(
+    tx: &mpsc::Sender<GenerationRequest>,
+    uri: &Url,
+) -> oneshot::Receiver<anyhow::Result<String>> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateCallGraphDiagram {
+        uris: vec![uri.clone()],
+        contract_name: None,
+        dot_config: DotConfig::default(),
+        filter: GraphFilter::default(),
+        coverage_file: None,
+        progress: None,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    reply_rx
+}
+
+#[test]
+fn test_document_store_versions_and_disk_fallback() {
+    let workspace = tempfile::tempdir().unwrap();
+    let path = workspace.path().join("Vault.sol");
+    std::fs::write(&path, SAVED).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let documents = DocumentStore::new();
+    assert_eq!(documents.read(&uri).unwrap(), SAVED);
+
+    assert!(documents.update(uri.clone(), 1, EDITED.to_string()));
+    assert_eq!(documents.read(&uri).unwrap(), EDITED);
+    // A change older than the current version is dropped.
+    assert!(!documents.update(uri.clone(), 0, SAVED.to_string()));
+    assert_eq!(documents.read(&uri).unwrap(), EDITED);
+
+    documents.close(&uri);
+    assert_eq!(documents.read(&uri).unwrap(), SAVED);
+
+    let missing = Url::from_file_path(workspace.path().join("Missing.sol")).unwrap();
+    assert!(documents.read(&missing).is_err());
+}
+
+#[test]
+fn test_worker_reads_unsaved_documents() {
+    let workspace = tempfile::tempdir().unwrap();
+    let path = workspace.path().join("Vault.sol");
+    std::fs::write(&path, SAVED).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let documents = Arc::new(DocumentStore::new());
+    documents.update(uri.clone(), 1, EDITED.to_string());
+
+    let (tx, rx) = mpsc::channel();
+    let edited = call_graph(&tx, &uri);
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_documents(documents.clone())
+        .run(rx);
+    let dot = edited.blocking_recv().unwrap().unwrap();
+    assert!(dot.contains("withdraw"));
+
+    documents.close(&uri);
+    let (tx, rx) = mpsc::channel();
+    let saved = call_graph(&tx, &uri);
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_documents(documents)
+        .run(rx);
+    let dot = saved.blocking_recv().unwrap().unwrap();
+    assert!(dot.contains("deposit"));
+    assert!(!dot.contains("withdraw"));
+}
+
+fn edit(start: (u32, u32), end: (u32, u32), text: &str) -> TextDocumentContentChangeEvent {
+    TextDocumentContentChangeEvent {
+        range: Some(Range::new(
+            Position::new(start.0, start.1),
+            Position::new(end.0, end.1),
+        )),
+        range_length: None,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn test_incremental_changes() {
+    let uri = Url::parse("file:///workspace/Vault.sol").unwrap();
+    let documents = DocumentStore::new();
+    documents.update(uri.clone(), 1, SAVED.to_string());
+
+    // Insert a function before the closing brace, then rename `deposit`.
+    let text = documents
+        .apply_changes(
+            &uri,
+            2,
+            vec![
+                edit((4, 0), (4, 0), "\n    function withdraw() external {}\n"),
+                edit((3, 13), (3, 20), "deposit"),
+            ],
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(text, EDITED);
+
+    // Edits on later lines see the line index updated by earlier ones.
+    let text = documents
+        .apply_changes(&uri, 3, vec![edit((5, 13), (5, 21), "exit")])
+        .unwrap()
+        .unwrap();
+    assert!(text.contains("function exit() external {}"));
+    assert_eq!(documents.read(&uri).unwrap(), text);
+
+    // Stale versions are dropped.
+    assert!(documents
+        .apply_changes(&uri, 3, vec![edit((0, 0), (0, 0), "//")])
+        .unwrap()
+        .is_none());
+
+    // A change without a range replaces the whole text.
+    let full = TextDocumentContentChangeEvent {
+        range: None,
+        range_length: None,
+        text: SAVED.to_string(),
+    };
+    assert_eq!(
+        documents
+            .apply_changes(&uri, 4, vec![full])
+            .unwrap()
+            .unwrap(),
+        SAVED
+    );
+
+    let unopened = Url::parse("file:///workspace/Other.sol").unwrap();
+    assert!(documents.apply_changes(&unopened, 1, Vec::new()).is_err());
+}
+
+#[test]
+fn test_incremental_changes_count_utf16_and_clamp() {
+    let uri = Url::parse("file:///workspace/Notes.sol").unwrap();
+    let documents = DocumentStore::new();
+    // `é` is one UTF-16 unit but two bytes; `𝄞` is two units and four bytes.
+    documents.update(uri.clone(), 1, "// é𝄞 x\r\nuint a;\n".to_string());
+
+    let text = documents
+        .apply_changes(&uri, 2, vec![edit((0, 7), (0, 8), "y")])
+        .unwrap()
+        .unwrap();
+    assert_eq!(text, "// é𝄞 y\r\nuint a;\n");
+
+    // Characters past the line end stop before its line break.
+    let text = documents
+        .apply_changes(&uri, 3, vec![edit((0, 99), (0, 99), "!")])
+        .unwrap()
+        .unwrap();
+    assert_eq!(text, "// é𝄞 y!\r\nuint a;\n");
+
+    // Lines past the end append.
+    let text = documents
+        .apply_changes(&uri, 4, vec![edit((9, 0), (9, 0), "uint b;\n")])
+        .unwrap()
+        .unwrap();
+    assert_eq!(text, "// é𝄞 y!\r\nuint a;\nuint b;\n");
+}
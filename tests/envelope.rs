@@ -0,0 +1,232 @@
+use lsp_types::Url;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_lsp::config::{Config, DotConfig, GraphFilter};
+use traverse_lsp::output::envelope::{AnalysisResult, Artifact, ArtifactFormat, RunInfo};
+use traverse_lsp::watchdog::{PhaseTiming, PhaseTracker};
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+#[test]
+fn test_run_is_lifted_from_the_payload() {
+    let run = RunInfo {
+        timings: vec![PhaseTiming {
+            phase: "building call graph".to_string(),
+            ms: 12,
+        }],
+        skipped_files: vec![PathBuf::from("/work/Gone.sol")],
+        artifacts: vec![
+            Artifact::inline("dot", ArtifactFormat::Dot, "digraph {}"),
+            Artifact::inline("mermaid", ArtifactFormat::Mermaid, "sequenceDiagram"),
+            Artifact::file("chunk_dir", ArtifactFormat::Mermaid, "/out/chunks"),
+        ],
+    };
+    let payload = run.attach(
+        json!({
+            "preview": "digraph",
+            "dot": "digraph {}",
+            "mermaid": "sequenceDiagram",
+            "chunk_dir": "/out/chunks",
+        })
+        .to_string(),
+    );
+    let result = AnalysisResult::new(
+        "traverse.generateAll.workspace",
+        serde_json::from_str(&payload).unwrap(),
+    );
+    assert_eq!(
+        serde_json::to_value(&result).unwrap(),
+        json!({
+            "kind": "traverse.generateAll.workspace",
+            "format": "dot",
+            "artifacts": [
+                { "name": "dot", "format": "dot", "content": "digraph {}" },
+                { "name": "mermaid", "format": "mermaid", "content": "sequenceDiagram" },
+                { "name": "chunk_dir", "format": "mermaid", "path": "/out/chunks" },
+            ],
+            "findings": [],
+            "skipped_files": ["/work/Gone.sol"],
+            "timings": [{ "phase": "building call graph", "ms": 12 }],
+            "data": {
+                "preview": "digraph",
+                "dot": "digraph {}",
+                "mermaid": "sequenceDiagram",
+                "chunk_dir": "/out/chunks",
+            },
+        })
+    );
+}
+
+#[test]
+fn test_data_only_results_are_json() {
+    // Formats come from the worker's run, never from the payload's keys.
+    let result = AnalysisResult::new(
+        "traverse.generateAuditReport.workspace",
+        json!({ "findings": [{ "rule": "tx-origin" }], "report": "# Audit" }),
+    );
+    assert_eq!(result.format, ArtifactFormat::Json);
+    assert!(result.artifacts.is_empty());
+    assert_eq!(result.findings, vec![json!({ "rule": "tx-origin" })]);
+
+    let result = AnalysisResult::new("traverse.findClones.workspace", json!({ "clusters": [] }));
+    assert_eq!(result.format, ArtifactFormat::Json);
+    assert!(result.artifacts.is_empty());
+    assert!(result.timings.is_empty());
+
+    let result = AnalysisResult::text(
+        "traverse.analyzeStorage.workspace",
+        "<table></table>".to_string(),
+        ArtifactFormat::Html,
+    );
+    assert_eq!(result.format, ArtifactFormat::Html);
+    assert_eq!(result.data, json!("<table></table>"));
+    // Text that is not JSON is left alone.
+    assert_eq!(RunInfo::default().attach("digraph".to_string()), "digraph");
+}
+
+/// The envelope of what the worker answers `request` with.
+fn worker_result(
+    request: impl FnOnce(oneshot::Sender<anyhow::Result<String>>) -> GenerationRequest,
+) -> AnalysisResult {
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(request(reply_tx)).unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+    let payload = reply_rx.blocking_recv().unwrap().unwrap();
+    AnalysisResult::new("", serde_json::from_str(&payload).unwrap())
+}
+
+#[test]
+fn test_worker_lists_artifacts_with_their_formats() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    std::fs::write(
+        &source,
+        "contract Token {\n    function mint() external {}\n}\n",
+    )
+    .unwrap();
+    let uris = vec![Url::from_file_path(&source).unwrap()];
+    let formats = |result: &AnalysisResult| -> Vec<(String, ArtifactFormat)> {
+        result
+            .artifacts
+            .iter()
+            .map(|a| (a.name.clone(), a.format))
+            .collect()
+    };
+
+    let result = worker_result(|tx| GenerationRequest::GenerateAuditReport {
+        uris: uris.clone(),
+        workspace_folder: workspace.path().to_path_buf(),
+        min_confidence: Default::default(),
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(
+        formats(&result),
+        vec![("report".to_string(), ArtifactFormat::Markdown)]
+    );
+    assert_eq!(
+        result.artifacts[0].content,
+        result.data["report"].as_str().map(String::from)
+    );
+
+    let result = worker_result(|tx| GenerationRequest::CallMatrix {
+        uris: uris.clone(),
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(
+        formats(&result),
+        vec![
+            ("csv".to_string(), ArtifactFormat::Csv),
+            ("report".to_string(), ArtifactFormat::Markdown),
+        ]
+    );
+    assert_eq!(result.format, ArtifactFormat::Csv);
+
+    let result = worker_result(|tx| GenerationRequest::RenderDiagram {
+        uris: uris.clone(),
+        contract_name: None,
+        renderer: traverse_lsp::renderers::renderer("plantuml").unwrap(),
+        options: Default::default(),
+        filter: GraphFilter::default(),
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(result.format, ArtifactFormat::PlantUml);
+    assert_eq!(
+        serde_json::to_value(&result).unwrap()["artifacts"][0]["format"],
+        "plantuml"
+    );
+}
+
+#[test]
+fn test_phase_timings_merge_repeated_phases() {
+    let phases = PhaseTracker::default();
+    assert!(phases.timings().is_empty());
+    phases.start("call graph generation");
+    phases.enter("reading sources");
+    phases.enter("building call graph");
+    phases.enter("reading sources");
+    let names: Vec<String> = phases.timings().into_iter().map(|t| t.phase).collect();
+    assert_eq!(names, vec!["reading sources", "building call graph"]);
+}
+
+#[test]
+fn test_worker_skips_unreadable_files() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    std::fs::write(
+        &source,
+        "contract Token {\n    function mint() external {}\n}\n",
+    )
+    .unwrap();
+    let missing = workspace.path().join("Gone.sol");
+    let (tx, rx) = mpsc::channel();
+    let worker = GeneratorWorker::new(Config::default()).unwrap();
+    let thread = std::thread::spawn(move || worker.run(rx));
+
+    let generate = |uris: Vec<Url>| {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(GenerationRequest::GenerateCallGraphDiagram {
+            uris,
+            contract_name: None,
+            dot_config: DotConfig::default(),
+            filter: GraphFilter::default(),
+            coverage_file: None,
+            progress: None,
+            cancel: Default::default(),
+            tx: reply_tx,
+        })
+        .unwrap();
+        reply_rx.blocking_recv().unwrap()
+    };
+    let payload = generate(vec![
+        Url::from_file_path(&source).unwrap(),
+        Url::from_file_path(&missing).unwrap(),
+    ])
+    .unwrap();
+    let result = AnalysisResult::new(
+        "traverse.generateCallGraph.workspace",
+        serde_json::from_str::<Value>(&payload).unwrap(),
+    );
+    assert_eq!(result.skipped_files, vec![missing.clone()]);
+    assert!(result.data["dot"].as_str().unwrap().contains("mint"));
+    assert!(result
+        .timings
+        .iter()
+        .any(|timing| timing.phase == "rendering DOT"));
+
+    // Skipped files are reported by the request that skipped them only.
+    let payload = generate(vec![Url::from_file_path(&source).unwrap()]).unwrap();
+    let result = AnalysisResult::new("", serde_json::from_str(&payload).unwrap());
+    assert!(result.skipped_files.is_empty());
+
+    let error = generate(vec![Url::from_file_path(&missing).unwrap()]).unwrap_err();
+    assert_eq!(error.to_string(), "None of the 1 files could be read");
+
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    thread.join().unwrap();
+}
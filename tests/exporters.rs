@@ -0,0 +1,481 @@
+use lsp_types::Url;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use tokio::sync::oneshot;
+use traverse_lsp::config::Config;
+use traverse_lsp::consumers::{self, Consumers};
+use traverse_lsp::exporters::anonymize::{self, Pseudonyms};
+use traverse_lsp::exporters::cypher::{to_cypher, to_neo4j_csv};
+use traverse_lsp::exporters::{excalidraw, obsidian, ExportFormat};
+use traverse_lsp::output::settings::{OutputLocation, OutputSettings};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const TOKEN: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Token {
+    mapping(address => uint256) private balances;
+
+    function transfer(address to, uint256 amount) public returns (bool) {
+        _move(msg.sender, to, amount);
+        return true;
+    }
+
+    function _move(address from, address to, uint256 amount) internal {
+        balances[from] -= amount;
+        balances[to] += amount;
+    }
+}
+"#;
+
+#[test]
+fn test_cypher_export() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(TOKEN)
+        .expect("Failed to build call graph");
+    let cypher = to_cypher(&graph);
+
+    assert!(cypher.contains("CREATE (:Node:Function {"));
+    assert!(cypher.contains("name: 'transfer'"));
+    assert!(cypher.contains("contract: 'Token'"));
+    assert!(cypher.contains("-[:CALLS {sequence: 1}]->"));
+    assert!(cypher.contains("-[:WRITES"));
+    assert_eq!(
+        cypher.lines().filter(|l| l.starts_with("MATCH")).count(),
+        graph.edges.len()
+    );
+}
+
+#[test]
+fn test_neo4j_csv_export() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(TOKEN)
+        .expect("Failed to build call graph");
+    let (nodes, relationships) = to_neo4j_csv(&graph);
+
+    assert!(nodes.starts_with("id:ID,name,contract,visibility,:LABEL\n"));
+    assert_eq!(nodes.lines().count(), graph.nodes.len() + 1);
+    assert!(nodes.contains(",transfer,Token,public,Node;Function"));
+    assert_eq!(relationships.lines().count(), graph.edges.len() + 1);
+    assert!(relationships.contains(",CALLS,"));
+}
+
+#[test]
+fn test_obsidian_notes() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(TOKEN)
+        .expect("Failed to build call graph");
+    let notes = traverse_lsp::exporters::obsidian::render_notes(&graph);
+
+    let token = notes.get("Token.md").expect("Missing Token note");
+    assert!(token.starts_with("# Token\n"));
+    assert!(token.contains("```mermaid\nflowchart LR\n"));
+    assert!(token.contains("### transfer"));
+    assert!(token.contains("- **Calls:** [[Token#_move|Token._move]]"));
+    assert!(token.contains("- **Called by:** [[Token#transfer|Token.transfer]]"));
+    assert!(token.contains("- **Writes:** `Token.balances`"));
+}
+
+#[test]
+fn test_obsidian_vault_replaces_stale_notes() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(TOKEN)
+        .expect("Failed to build call graph");
+    let dir = tempfile::tempdir().unwrap();
+    let vault_dir = dir.path().join(obsidian::VAULT_DIR);
+    std::fs::create_dir_all(&vault_dir).unwrap();
+    std::fs::write(vault_dir.join("Removed.md"), "# Removed\n").unwrap();
+
+    let notes = obsidian::write_vault(&graph, &vault_dir).unwrap();
+
+    assert_eq!(notes, vec![vault_dir.join("Token.md")]);
+    assert!(vault_dir.join("Token.md").exists());
+    assert!(!vault_dir.join("Removed.md").exists());
+}
+
+#[test]
+fn test_excalidraw_scenes() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(TOKEN)
+        .expect("Failed to build call graph");
+    let scene = traverse_lsp::exporters::excalidraw::call_graph_scene(&graph);
+
+    assert_eq!(scene["type"], "excalidraw");
+    let elements = scene["elements"].as_array().expect("Missing elements");
+    let label_x = |label: &str| {
+        let text = elements
+            .iter()
+            .find(|e| e["type"] == "text" && e["text"] == label)
+            .unwrap_or_else(|| panic!("Missing {label}"));
+        text["x"].as_f64().unwrap()
+    };
+    assert!(label_x("Token.transfer") < label_x("Token._move"));
+
+    let arrows: Vec<_> = elements.iter().filter(|e| e["type"] == "arrow").collect();
+    assert_eq!(arrows.len(), 1);
+    assert!(arrows[0]["startBinding"]["elementId"].is_string());
+}
+
+#[test]
+fn test_excalidraw_scenes_replace_directory() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(TOKEN)
+        .expect("Failed to build call graph");
+    let dir = tempfile::tempdir().unwrap();
+    let scene_dir = dir.path().join(excalidraw::EXCALIDRAW_DIR);
+    std::fs::create_dir_all(&scene_dir).unwrap();
+    std::fs::write(scene_dir.join("stale.excalidraw"), "{}").unwrap();
+
+    let scenes = excalidraw::write_scenes(&graph, &Consumers::default(), &scene_dir).unwrap();
+
+    assert_eq!(
+        scenes,
+        vec![
+            scene_dir.join("architecture.excalidraw"),
+            scene_dir.join("call-graph.excalidraw"),
+        ]
+    );
+    assert!(scenes.iter().all(|scene| scene.exists()));
+    assert!(!scene_dir.join("stale.excalidraw").exists());
+}
+
+const VAULT: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Vault {
+    event Deposited(address indexed user, uint256 amount);
+    event Liquidatable(address indexed user);
+
+    mapping(address => uint256) private balances;
+
+    function deposit(uint256 amount) external {
+        balances[msg.sender] += amount;
+        emit Deposited(msg.sender, amount);
+    }
+
+    function check(address user) external {
+        if (balances[user] == 0) {
+            emit Liquidatable(user);
+        }
+    }
+}
+"#;
+
+#[test]
+fn test_excalidraw_architecture_consumers() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(VAULT)
+        .expect("Failed to build call graph");
+    let consumers: Consumers = serde_json::from_value(serde_json::json!({
+        "VaultSubgraph": { "kind": "subgraph", "events": ["Vault.Deposited", "Vault.Liquidatable"] },
+        "Keeper": { "events": ["Liquidatable", "Paused"] },
+    }))
+    .unwrap();
+
+    let subscriptions = consumers::subscriptions(&graph, &consumers);
+    let pairs: Vec<_> = subscriptions
+        .iter()
+        .map(|s| (s.event.as_str(), s.consumer.as_str()))
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            ("Deposited", "VaultSubgraph"),
+            ("Liquidatable", "Keeper"),
+            ("Liquidatable", "VaultSubgraph"),
+        ]
+    );
+    assert_eq!(
+        consumers::unmatched(&graph, &consumers),
+        vec!["Keeper: Paused"]
+    );
+
+    let scene = excalidraw::architecture_scene(&graph, &consumers);
+    let elements = scene["elements"].as_array().expect("Missing elements");
+    let rect = |label: &str| {
+        let text = elements
+            .iter()
+            .find(|e| e["type"] == "text" && e["text"] == label)
+            .unwrap_or_else(|| panic!("Missing {label}"));
+        elements
+            .iter()
+            .find(|e| e["id"] == text["containerId"])
+            .unwrap()
+    };
+    assert_eq!(rect("Vault")["strokeStyle"], "solid");
+    assert_eq!(rect("VaultSubgraph (subgraph)")["strokeStyle"], "dashed");
+    assert_eq!(rect("Keeper")["strokeStyle"], "dashed");
+    assert!(rect("Vault")["x"].as_f64() < rect("Keeper")["x"].as_f64());
+
+    let arrows = elements.iter().filter(|e| e["type"] == "arrow").count();
+    assert_eq!(arrows, 2);
+    let event_label = elements
+        .iter()
+        .find(|e| e["text"] == "Deposited, Liquidatable")
+        .expect("Missing event label");
+    assert!(event_label["containerId"]
+        .as_str()
+        .unwrap()
+        .starts_with("edge-"));
+}
+
+#[test]
+fn test_load_consumers() {
+    let workspace = tempfile::tempdir().unwrap();
+    assert!(consumers::load(workspace.path(), None).unwrap().is_empty());
+
+    std::fs::create_dir(workspace.path().join(".traverse")).unwrap();
+    std::fs::write(
+        workspace.path().join(consumers::CONSUMERS_FILE),
+        r#"{"Indexer": {"kind": "bot", "events": ["Deposited"]}}"#,
+    )
+    .unwrap();
+    let loaded = consumers::load(workspace.path(), None).unwrap();
+    assert_eq!(loaded["Indexer"].kind.as_deref(), Some("bot"));
+
+    let missing = consumers::load(workspace.path(), Some(Path::new("consumers.json")));
+    assert!(missing.is_err());
+}
+
+#[test]
+fn test_anonymized_graph() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(VAULT)
+        .expect("Failed to build call graph");
+    let mut pseudonyms = Pseudonyms::default();
+    let anonymized = pseudonyms.anonymize(&graph);
+
+    assert_eq!(anonymized.nodes.len(), graph.nodes.len());
+    assert_eq!(anonymized.edges.len(), graph.edges.len());
+    assert_eq!(pseudonyms.contracts["Vault"], "Contract1");
+    assert_eq!(pseudonyms.events["Deposited"], "Event1");
+    let cypher = to_cypher(&anonymized);
+    for name in ["Vault", "deposit", "check", "balances", "Deposited", "user"] {
+        assert!(!cypher.contains(name), "{name} leaked");
+    }
+    assert!(cypher.contains("contract: 'Contract1'"));
+    assert!(anonymized
+        .edges
+        .iter()
+        .all(|e| e.argument_names.is_none() && e.returned_value.is_none()));
+
+    // Names keep their pseudonyms once saved, and new ones get fresh ones.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(anonymize::MAPPING_FILE);
+    pseudonyms.save(&path).unwrap();
+    let mut reloaded = Pseudonyms::load(&path).unwrap();
+    assert_eq!(reloaded, pseudonyms);
+    let token = adapter.build_call_graph(TOKEN).unwrap();
+    let both = reloaded.anonymize(&token);
+    assert_eq!(reloaded.contracts["Vault"], "Contract1");
+    assert_eq!(reloaded.contracts["Token"], "Contract2");
+    assert!(both
+        .nodes
+        .iter()
+        .any(|n| n.contract_name.as_deref() == Some("Contract2")));
+}
+
+#[test]
+fn test_anonymized_export_command() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(&source, VAULT).unwrap();
+    let output = workspace.path().join("out");
+    let settings = Arc::new(OutputSettings::new(OutputLocation {
+        dir: output.clone(),
+        no_chunk: false,
+        chunk_dir: None,
+    }));
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::ExportGraph {
+        uris: vec![Url::from_file_path(&source).unwrap()],
+        workspace_folder: workspace.path().to_path_buf(),
+        format: ExportFormat::Cypher,
+        filter: Default::default(),
+        consumers_file: None,
+        anonymize: true,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_output_settings(settings)
+        .run(rx);
+
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap();
+    assert!(!response["cypher"].as_str().unwrap().contains("Vault"));
+    assert!(response.get("origins").is_none());
+    let mapping_file = PathBuf::from(response["mapping_file"].as_str().unwrap());
+    assert_eq!(mapping_file, output.join(anonymize::MAPPING_FILE));
+    let mapping = Pseudonyms::load(&mapping_file).unwrap();
+    assert_eq!(mapping.contracts["Vault"], "Contract1");
+}
+
+#[test]
+fn test_export_records_node_origins() {
+    let workspace = tempfile::tempdir().unwrap();
+    let vault = workspace.path().join("Vault.sol");
+    let token = workspace.path().join("Token.sol");
+    std::fs::write(&vault, VAULT).unwrap();
+    std::fs::write(&token, TOKEN).unwrap();
+    let uris = vec![
+        Url::from_file_path(&vault).unwrap(),
+        Url::from_file_path(&token).unwrap(),
+    ];
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::ExportGraph {
+        uris: uris.clone(),
+        workspace_folder: workspace.path().to_path_buf(),
+        format: ExportFormat::Cypher,
+        filter: Default::default(),
+        consumers_file: None,
+        anonymize: false,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_output_settings(Arc::new(OutputSettings::new(OutputLocation {
+            dir: workspace.path().join("out"),
+            no_chunk: false,
+            chunk_dir: None,
+        })))
+        .run(rx);
+
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap();
+    let cypher = response["cypher"].as_str().unwrap();
+    let origins = response["origins"].as_object().unwrap();
+    assert!(!origins.is_empty());
+    for (id, origin) in origins {
+        let uri = Url::parse(origin["uri"].as_str().unwrap()).unwrap();
+        let node = cypher
+            .lines()
+            .find(|line| line.contains(&format!("{{id: {}, ", id)))
+            .unwrap();
+        let file = if node.contains("contract: 'Vault'") {
+            &uris[0]
+        } else {
+            &uris[1]
+        };
+        assert_eq!(&uri, file, "{}", node);
+    }
+    // Lines count from the start of Token.sol, not of the merged sources.
+    let transfer = cypher
+        .lines()
+        .find(|line| line.contains("name: 'transfer'"))
+        .unwrap();
+    let id = transfer
+        .split("id: ")
+        .nth(1)
+        .unwrap()
+        .split(',')
+        .next()
+        .unwrap();
+    assert_eq!(origins[id]["range"]["start"]["line"], 6);
+}
+
+#[test]
+fn test_anonymized_export_replaces_plain_export() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(&source, VAULT).unwrap();
+    let output = workspace.path().join("out");
+
+    let (tx, rx) = mpsc::channel();
+    let mut replies = Vec::new();
+    for format in [ExportFormat::Obsidian, ExportFormat::Excalidraw] {
+        for anonymize in [false, true] {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            tx.send(GenerationRequest::ExportGraph {
+                uris: vec![Url::from_file_path(&source).unwrap()],
+                workspace_folder: workspace.path().to_path_buf(),
+                format,
+                filter: Default::default(),
+                consumers_file: None,
+                anonymize,
+                cancel: Default::default(),
+                tx: reply_tx,
+            })
+            .unwrap();
+            replies.push(reply_rx);
+        }
+    }
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_output_settings(Arc::new(OutputSettings::new(OutputLocation {
+            dir: output.clone(),
+            no_chunk: false,
+            chunk_dir: None,
+        })))
+        .run(rx);
+    for reply in replies {
+        reply.blocking_recv().unwrap().unwrap();
+    }
+
+    for dir in [obsidian::VAULT_DIR, excalidraw::EXCALIDRAW_DIR] {
+        let files: Vec<_> = std::fs::read_dir(output.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert!(!files.is_empty(), "{}", dir);
+        for file in files {
+            let content = std::fs::read_to_string(&file).unwrap();
+            for name in ["Vault", "deposit", "balances", "Deposited"] {
+                assert!(
+                    !file.file_name().unwrap().to_string_lossy().contains(name)
+                        && !content.contains(name),
+                    "{} in {}",
+                    name,
+                    file.display()
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_concurrent_anonymized_exports_share_the_mapping() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graphs = [TOKEN, VAULT].map(|source| {
+        adapter
+            .build_call_graph(source)
+            .expect("Failed to build call graph")
+    });
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(anonymize::MAPPING_FILE);
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            for graph in &graphs {
+                let path = &path;
+                scope.spawn(move || Pseudonyms::anonymize_with(path, graph).unwrap());
+            }
+        }
+    });
+
+    let mapping = Pseudonyms::load(&path).unwrap();
+    assert!(mapping.contracts.contains_key("Token"));
+    assert!(mapping.contracts.contains_key("Vault"));
+    let pseudonyms: std::collections::BTreeSet<_> = mapping.contracts.values().collect();
+    assert_eq!(pseudonyms.len(), mapping.contracts.len());
+}
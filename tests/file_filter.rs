@@ -0,0 +1,91 @@
+use std::path::Path;
+use traverse_lsp::config::FileFilter;
+use traverse_lsp::file_filter::{allows, retain};
+use traverse_lsp::utils::find_solidity_files;
+
+fn filter(include: &[&str], exclude: &[&str]) -> FileFilter {
+    FileFilter {
+        include: include.iter().map(|p| p.to_string()).collect(),
+        exclude: exclude.iter().map(|p| p.to_string()).collect(),
+        ..FileFilter::default()
+    }
+}
+
+fn allowed(filter: &FileFilter, relative: &str) -> bool {
+    allows(filter, Path::new("/w"), &Path::new("/w").join(relative))
+}
+
+#[test]
+fn test_patterns() {
+    let empty = FileFilter::default();
+    assert!(allowed(&empty, "src/Vault.sol"));
+
+    // Directory names match at any depth, with or without a trailing slash.
+    let no_tests = filter(&[], &["test/", "script"]);
+    assert!(allowed(&no_tests, "src/Vault.sol"));
+    assert!(!allowed(&no_tests, "test/Vault.t.sol"));
+    assert!(!allowed(&no_tests, "packages/core/script/Deploy.s.sol"));
+
+    // Patterns with a slash are anchored at the workspace folder.
+    let anchored = filter(&[], &["lib/**", "src/*.t.sol"]);
+    assert!(!allowed(&anchored, "lib/forge-std/Test.sol"));
+    assert!(allowed(&anchored, "src/lib/Math.sol"));
+    assert!(!allowed(&anchored, "src/Vault.t.sol"));
+    assert!(allowed(&anchored, "src/nested/Vault.t.sol"));
+
+    let deep = filter(&["src/**/*.sol"], &["**/mocks/**", "*.t.sol"]);
+    assert!(allowed(&deep, "src/Vault.sol"));
+    assert!(allowed(&deep, "src/core/Vault.sol"));
+    assert!(!allowed(&deep, "contracts/Vault.sol"));
+    assert!(!allowed(&deep, "src/mocks/MockToken.sol"));
+    assert!(!allowed(&deep, "src/core/Vault.t.sol"));
+
+    let single = filter(&["src/Vault?.sol"], &[]);
+    assert!(allowed(&single, "src/Vault2.sol"));
+    assert!(!allowed(&single, "src/Vault.sol"));
+
+    let alternation = filter(&["{src,contracts}/**"], &["{test,script}", "*.[ts].sol"]);
+    assert!(allowed(&alternation, "src/Vault.sol"));
+    assert!(allowed(&alternation, "contracts/core/Vault.sol"));
+    assert!(!allowed(&alternation, "lib/Vault.sol"));
+    assert!(!allowed(&alternation, "contracts/script/Deploy.sol"));
+    assert!(!allowed(&alternation, "src/Vault.t.sol"));
+    assert!(!allowed(&alternation, "src/Deploy.s.sol"));
+
+    let classes = filter(&["src/V[0-9].sol"], &[]);
+    assert!(allowed(&classes, "src/V2.sol"));
+    assert!(!allowed(&classes, "src/VX.sol"));
+
+    // An invalid include pattern matches nothing rather than everything.
+    assert!(!allowed(&filter(&["src/[a"], &[]), "src/a.sol"));
+}
+
+#[test]
+fn test_retain_discovered_files() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    for relative in [
+        "src/Vault.sol",
+        "src/mocks/MockToken.sol",
+        "test/Vault.t.sol",
+        "vendor/Ownable.sol",
+    ] {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, "contract C {}\n").unwrap();
+    }
+
+    let mut files = find_solidity_files(root).unwrap();
+    retain(&mut files, &filter(&["src"], &["mocks"]), root);
+    let names: Vec<String> = files
+        .iter()
+        .map(|uri| {
+            let path = uri.to_file_path().unwrap();
+            path.strip_prefix(root)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+    assert_eq!(names, vec!["src/Vault.sol"]);
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use common::{names, write};
+use traverse_lsp::analysis::imports::{remap, Remapping};
+use traverse_lsp::foundry::{remappings, FoundryProject};
+use traverse_lsp::utils::find_solidity_files;
+
+fn remapping(prefix: &str, target: &str) -> Remapping {
+    Remapping {
+        prefix: prefix.to_string(),
+        target: target.to_string(),
+    }
+}
+
+#[test]
+fn test_parse_and_apply_remappings() {
+    assert_eq!(
+        Remapping::parse("@openzeppelin/=lib/openzeppelin-contracts/"),
+        Some(remapping("@openzeppelin/", "lib/openzeppelin-contracts/"))
+    );
+    assert_eq!(
+        Remapping::parse("src/:ds-test/=lib/ds-test/src/"),
+        Some(remapping("ds-test/", "lib/ds-test/src/"))
+    );
+    assert_eq!(Remapping::parse("# comment"), None);
+
+    let remappings = [
+        remapping("@openzeppelin/", "lib/openzeppelin-contracts/"),
+        remapping(
+            "@openzeppelin/contracts-upgradeable/",
+            "lib/openzeppelin-contracts-upgradeable/contracts/",
+        ),
+    ];
+    assert_eq!(
+        remap(&remappings, "@openzeppelin/contracts/access/Ownable.sol").as_deref(),
+        Some("lib/openzeppelin-contracts/contracts/access/Ownable.sol")
+    );
+    // The longest prefix wins.
+    assert_eq!(
+        remap(
+            &remappings,
+            "@openzeppelin/contracts-upgradeable/proxy/utils/Initializable.sol"
+        )
+        .as_deref(),
+        Some("lib/openzeppelin-contracts-upgradeable/contracts/proxy/utils/Initializable.sol")
+    );
+    assert_eq!(remap(&remappings, "solmate/tokens/ERC20.sol"), None);
+}
+
+#[test]
+fn test_detect_project_settings() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    assert!(FoundryProject::detect(root).unwrap().is_none());
+
+    write(
+        root,
+        "foundry.toml",
+        "[profile.default]\nsrc = \"contracts\"\nlibs = [\"lib\", \"node_modules\"]\nremappings = [\"@oz/=lib/openzeppelin-contracts/contracts/\"]\n",
+    );
+    write(
+        root,
+        "remappings.txt",
+        "@oz/=lib/elsewhere/\nsolmate/=lib/solmate/src/\n",
+    );
+    write(root, "lib/forge-std/src/Test.sol", "contract Test {}\n");
+    write(
+        root,
+        "lib/solmate/src/tokens/ERC20.sol",
+        "contract ERC20 {}\n",
+    );
+
+    let project = FoundryProject::detect(root).unwrap().unwrap();
+    assert_eq!(project.src, root.join("contracts"));
+    assert_eq!(project.test, root.join("test"));
+    assert_eq!(
+        project.libs,
+        vec![root.join("lib"), root.join("node_modules")]
+    );
+    // foundry.toml wins over remappings.txt, which wins over the ones
+    // derived from the library directories.
+    assert_eq!(
+        project.remappings,
+        vec![
+            remapping("@oz/", "lib/openzeppelin-contracts/contracts/"),
+            remapping("solmate/", "lib/solmate/src/"),
+            remapping("forge-std/", "lib/forge-std/src/"),
+        ]
+    );
+    assert_eq!(remappings(root).unwrap(), project.remappings);
+
+    write(root, "foundry.toml", "[profile.default\n");
+    assert!(FoundryProject::detect(root).is_err());
+}
+
+#[test]
+fn test_scope_follows_remapped_imports() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    write(root, "foundry.toml", "[profile.default]\n");
+    write(
+        root,
+        "remappings.txt",
+        "@openzeppelin/=lib/openzeppelin-contracts/\n",
+    );
+    write(
+        root,
+        "src/Vault.sol",
+        "import \"@openzeppelin/contracts/access/Ownable.sol\";\ncontract Vault is Ownable {}\n",
+    );
+    // Both end in `contracts/access/Ownable.sol`; only the remapping tells
+    // them apart.
+    write(
+        root,
+        "lib/openzeppelin-contracts/contracts/access/Ownable.sol",
+        "contract Ownable {}\n",
+    );
+    write(
+        root,
+        "lib/openzeppelin-contracts-upgradeable/contracts/access/Ownable.sol",
+        "contract OwnableUpgradeable {}\n",
+    );
+    write(
+        root,
+        "test/Vault.t.sol",
+        "import \"forge-std/Test.sol\";\nimport \"../src/Vault.sol\";\ncontract VaultTest is Test {}\n",
+    );
+    write(root, "lib/forge-std/src/Test.sol", "contract Test {}\n");
+    write(root, "script/Deploy.s.sol", "contract Deploy {}\n");
+
+    let project = FoundryProject::detect(root).unwrap().unwrap();
+    let files = find_solidity_files(root).unwrap();
+    assert_eq!(
+        names(root, &project.scope(&files).unwrap()),
+        vec![
+            "lib/openzeppelin-contracts/contracts/access/Ownable.sol",
+            "src/Vault.sol",
+        ]
+    );
+}
@@ -0,0 +1,230 @@
+use lsp_types::{Position, TextDocumentIdentifier, TextDocumentPositionParams, Url};
+use std::path::Path;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_lsp::config::{Config, GraphFilter, MermaidConfig};
+use traverse_lsp::graph_filter;
+use traverse_lsp::symbol_index::enclosing_function;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const VAULT: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Vault {
+    uint256 total;
+
+    constructor() {
+        _reset();
+    }
+
+    function deposit(uint256 amount) external {
+        _credit(amount);
+    }
+
+    function deposit(uint256 amount, address to) external {
+        _credit(amount);
+        _notify(to);
+    }
+
+    function withdraw(uint256 amount) external {
+        _debit(amount);
+    }
+
+    function _credit(uint256 amount) internal {
+        total += _scale(amount);
+    }
+
+    function _scale(uint256 amount) internal pure returns (uint256) {
+        return amount * 2;
+    }
+
+    function _notify(address to) internal {}
+
+    function _debit(uint256 amount) internal {
+        total -= amount;
+    }
+
+    function _reset() internal {
+        total = 0;
+    }
+}
+"#;
+
+fn function_diagram(dir: &Path, contract: &str, function: &str) -> anyhow::Result<String> {
+    let path = dir.join("Vault.sol");
+    std::fs::write(&path, VAULT).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateFunctionDiagram {
+        uris: vec![Url::from_file_path(&path).unwrap()],
+        workspace_folder: dir.to_path_buf(),
+        contract_name: contract.to_string(),
+        function_name: function.to_string(),
+        config: MermaidConfig {
+            no_chunk: true,
+            ..MermaidConfig::default()
+        },
+        filter: GraphFilter::default(),
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap()?).unwrap();
+    Ok(response["mermaid"].as_str().unwrap().to_string())
+}
+
+fn function_diagram_at(dir: &Path, line: u32, character: u32) -> anyhow::Result<String> {
+    let path = dir.join("Vault.sol");
+    std::fs::write(&path, VAULT).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateFunctionDiagramAt {
+        uris: vec![uri.clone()],
+        workspace_folder: dir.to_path_buf(),
+        position: TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position: Position::new(line, character),
+        },
+        config: MermaidConfig {
+            no_chunk: true,
+            ..MermaidConfig::default()
+        },
+        filter: GraphFilter::default(),
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap()?).unwrap();
+    Ok(response["mermaid"].as_str().unwrap().to_string())
+}
+
+/// The zero-based line of the first line of `VAULT` containing `text`.
+fn line_of(text: &str) -> u32 {
+    VAULT.lines().position(|l| l.contains(text)).unwrap() as u32
+}
+
+#[test]
+fn test_enclosing_function() {
+    let at = |line: u32, character: u32| enclosing_function(VAULT, Position::new(line, character));
+    let named = |contract: &str, function: &str| Some((contract.to_string(), function.to_string()));
+
+    assert_eq!(
+        at(line_of("_debit(amount);"), 8),
+        named("Vault", "withdraw")
+    );
+    assert_eq!(
+        at(line_of("function withdraw"), 4),
+        named("Vault", "withdraw")
+    );
+    assert_eq!(at(line_of("_reset();"), 0), named("Vault", "constructor"));
+    assert_eq!(at(line_of("uint256 total;"), 6), None);
+    assert_eq!(at(line_of("contract Vault"), 0), None);
+    assert_eq!(at(1000, 0), None);
+}
+
+#[test]
+fn test_function_diagram_at_position() {
+    let dir = tempfile::tempdir().unwrap();
+    let mermaid = function_diagram_at(dir.path(), line_of("total += _scale"), 12).unwrap();
+    assert!(mermaid.contains("User->>Vault: call _credit()"));
+    assert!(mermaid.contains("_scale"));
+    assert!(!mermaid.contains("deposit"));
+
+    let error = function_diagram_at(dir.path(), line_of("uint256 total;"), 4).unwrap_err();
+    assert!(
+        error.to_string().starts_with("No function at "),
+        "{}",
+        error
+    );
+    assert!(error.to_string().ends_with("Vault.sol:5:5"), "{}", error);
+}
+
+#[test]
+fn test_function_subgraph() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(VAULT)
+        .unwrap();
+    let names = |contract: &str, function: &str| {
+        let mut names: Vec<String> =
+            graph_filter::function_subgraph(&graph, contract, function, None)
+                .unwrap()
+                .nodes
+                .iter()
+                .filter(|n| n.contract_name.as_deref() == Some("Vault"))
+                .map(|n| n.name.clone())
+                .collect();
+        names.sort();
+        names.dedup();
+        names
+    };
+
+    // Both overloads are roots.
+    assert_eq!(
+        names("Vault", "deposit"),
+        vec!["_credit", "_notify", "_scale", "deposit", "total"]
+    );
+    assert_eq!(names("Vault", "_scale"), vec!["_scale"]);
+    assert!(names("Vault", "constructor").contains(&"_reset".to_string()));
+    assert!(graph_filter::function_subgraph(&graph, "Vault", "missing", None).is_none());
+    assert!(graph_filter::function_subgraph(&graph, "Other", "deposit", None).is_none());
+}
+
+#[test]
+fn test_function_subgraph_max_depth() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(VAULT)
+        .unwrap();
+    let names = |max_depth: Option<usize>| {
+        let mut names: Vec<String> =
+            graph_filter::function_subgraph(&graph, "Vault", "deposit", max_depth)
+                .unwrap()
+                .nodes
+                .iter()
+                .filter(|n| n.contract_name.as_deref() == Some("Vault"))
+                .map(|n| n.name.clone())
+                .collect();
+        names.sort();
+        names.dedup();
+        names
+    };
+
+    assert_eq!(names(Some(0)), vec!["deposit"]);
+    assert_eq!(names(Some(1)), vec!["_credit", "_notify", "deposit"]);
+    assert_eq!(names(Some(2)), names(None));
+}
+
+#[test]
+fn test_function_diagram_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let mermaid = function_diagram(dir.path(), "Vault", "withdraw").unwrap();
+    assert!(mermaid.contains("withdraw"));
+    assert!(mermaid.contains("_debit"));
+    assert!(!mermaid.contains("deposit"));
+    assert!(!mermaid.contains("_credit"));
+
+    // Internal functions and constructors are diagrams of their own too.
+    let mermaid = function_diagram(dir.path(), "Vault", "_credit").unwrap();
+    assert!(mermaid.contains("User->>Vault: call _credit()"));
+    assert!(mermaid.contains("_scale"));
+    let mermaid = function_diagram(dir.path(), "Vault", "constructor").unwrap();
+    assert!(mermaid.contains("User->>Vault: call constructor()"));
+    assert!(mermaid.contains("_reset"));
+
+    let error = function_diagram(dir.path(), "Vault", "missing").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Function Vault.missing not found in the call graph"
+    );
+}
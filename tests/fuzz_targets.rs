@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+use traverse_lsp::analysis::fuzz_targets::{extract, FuzzTarget};
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+
+const VAULT: &str = r#"
+pragma solidity ^0.8.0;
+
+interface IToken {
+    function transfer(address to, uint amount) external returns (bool);
+}
+
+abstract contract Base {
+    enum Mode { Open, Closed }
+    struct Order { address maker; uint96 amount; }
+
+    function setMode(Mode mode) external virtual {}
+    function fill(Order calldata order, IToken token) public payable {}
+    function peek() external view returns (uint) { return 1; }
+}
+
+contract Vault is Base {
+    function deposit(uint amount, bytes32[] memory proofs) external {}
+    function setMode(Mode mode) external override {}
+    function _settle() internal {}
+}
+"#;
+
+fn targets(source: &str) -> Vec<FuzzTarget> {
+    let units = [SourceUnit {
+        path: PathBuf::from("Vault.sol"),
+        content: source.to_string(),
+    }];
+    let parsed = parse_sources(&units).expect("Failed to parse sources");
+    extract(&SourceModel::from_parsed(&parsed))
+}
+
+#[test]
+fn test_fuzz_targets_only_deployable_contracts() {
+    let targets = targets(VAULT);
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].contract, "Vault");
+}
+
+#[test]
+fn test_fuzz_targets_signatures() {
+    let targets = targets(VAULT);
+    let signatures: Vec<&str> = targets[0]
+        .functions
+        .iter()
+        .map(|f| f.signature.as_str())
+        .collect();
+
+    assert_eq!(
+        signatures,
+        vec![
+            "deposit(uint256,bytes32[])",
+            "setMode(uint8)",
+            "fill((address,uint96),address)"
+        ]
+    );
+
+    let set_mode = &targets[0].functions[1];
+    assert_eq!(set_mode.declared_in, "Vault");
+    let fill = &targets[0].functions[2];
+    assert!(fill.payable);
+    assert_eq!(fill.declared_in, "Base");
+}
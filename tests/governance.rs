@@ -0,0 +1,323 @@
+mod common;
+
+use common::run_worker_request;
+use std::path::PathBuf;
+use traverse_lsp::analysis::governance::{
+    self, AdminAction, AdminModel, Controller, Governance, RoleAdmin, Timelock, TimelockKind,
+};
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+use traverse_lsp::GenerationRequest;
+
+const SOURCES: &str = r#"pragma solidity ^0.8.0;
+
+import "@openzeppelin/contracts/access/Ownable.sol";
+import "@openzeppelin/contracts/access/AccessControl.sol";
+
+contract Vault is Ownable {
+    address public guardian;
+
+    function setFee(uint256 fee) external onlyOwner {}
+
+    function pause() external {
+        require(msg.sender == guardian, "not guardian");
+    }
+
+    function renounceOwnership() public override onlyOwner {
+        revert("disabled");
+    }
+
+    function deposit() external payable {}
+}
+
+contract Token is AccessControl {
+    bytes32 public constant MINTER_ROLE = keccak256("MINTER");
+    bytes32 public constant MANAGER_ROLE = keccak256("MANAGER");
+
+    constructor() {
+        _grantRole(DEFAULT_ADMIN_ROLE, msg.sender);
+        _setRoleAdmin(MINTER_ROLE, MANAGER_ROLE);
+    }
+
+    function mint(address to) external onlyRole(MINTER_ROLE) {}
+}
+
+contract Registry {
+    address public admin;
+
+    function setAdmin(address next) external {
+        require(msg.sender == admin);
+        admin = next;
+    }
+}
+
+contract Plain {
+    function ping() external {}
+}
+"#;
+
+const TIMELOCKED: &str = r#"pragma solidity ^0.8.0;
+
+import "@openzeppelin/contracts/access/Ownable.sol";
+
+contract Treasury is Ownable {
+    uint256 public constant DELAY = 2 days;
+    address public timelock;
+    uint256 public fee;
+    uint256 public pendingFee;
+    uint256 public feeReadyAt;
+
+    modifier onlyTimelock() {
+        require(msg.sender == timelock, "not timelock");
+        _;
+    }
+
+    function setLimit(uint256 limit) external onlyTimelock {}
+
+    function queueFee(uint256 next) external onlyOwner {
+        pendingFee = next;
+        feeReadyAt = block.timestamp + DELAY;
+    }
+
+    function applyFee() external onlyOwner {
+        _requireReady();
+        fee = pendingFee;
+    }
+
+    function _requireReady() internal view {
+        require(feeReadyAt != 0 && block.timestamp >= feeReadyAt, "not ready");
+    }
+
+    function sweep(address to) external onlyOwner {}
+}
+"#;
+
+fn analyze() -> Vec<Governance> {
+    analyze_source(SOURCES)
+}
+
+fn analyze_source(content: &str) -> Vec<Governance> {
+    let units = [SourceUnit {
+        path: PathBuf::from("Vault.sol"),
+        content: content.to_string(),
+    }];
+    let parsed = parse_sources(&units).unwrap();
+    let model = SourceModel::from_parsed(&parsed);
+    governance::analyze(&parsed, &model)
+}
+
+fn contract<'a>(contracts: &'a [Governance], name: &str) -> &'a Governance {
+    contracts.iter().find(|c| c.contract == name).unwrap()
+}
+
+#[test]
+fn test_ownable_with_disabled_renounce() {
+    let contracts = analyze();
+    let names: Vec<&str> = contracts.iter().map(|c| c.contract.as_str()).collect();
+    assert_eq!(names, vec!["Vault", "Token", "Registry"]);
+
+    let vault = contract(&contracts, "Vault");
+    assert_eq!(vault.models, vec![AdminModel::Ownable, AdminModel::Custom]);
+    let functions: Vec<(&str, &[Controller])> = vault
+        .functions
+        .iter()
+        .map(|f| (f.function.as_str(), f.controllers.as_slice()))
+        .collect();
+    assert_eq!(
+        functions,
+        vec![
+            ("Vault.setFee", &[Controller::Owner][..]),
+            ("Vault.pause", &[Controller::Address("guardian".into())][..]),
+            ("Vault.renounceOwnership", &[Controller::Owner][..]),
+        ]
+    );
+
+    let paths: Vec<(AdminAction, &str, bool, bool)> = vault
+        .admin_paths
+        .iter()
+        .map(|p| (p.action, p.function.as_str(), p.assumed, p.disabled))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            (
+                AdminAction::TransferOwnership,
+                "Ownable.transferOwnership",
+                true,
+                false
+            ),
+            (
+                AdminAction::RenounceOwnership,
+                "Vault.renounceOwnership",
+                false,
+                true
+            ),
+        ]
+    );
+    assert_eq!(
+        vault.notes,
+        vec!["Ownership is transferred in one step; a wrong address loses control."]
+    );
+}
+
+#[test]
+fn test_access_control_roles_and_custom_owner() {
+    let contracts = analyze();
+
+    let token = contract(&contracts, "Token");
+    assert_eq!(token.models, vec![AdminModel::AccessControl]);
+    assert_eq!(
+        token.functions[0].controllers,
+        vec![Controller::Role("MINTER_ROLE".into())]
+    );
+    assert_eq!(
+        token.role_admins,
+        vec![RoleAdmin {
+            role: "MINTER_ROLE".into(),
+            admin: "MANAGER_ROLE".into(),
+        }]
+    );
+    let actions: Vec<AdminAction> = token.admin_paths.iter().map(|p| p.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            AdminAction::GrantRole,
+            AdminAction::RevokeRole,
+            AdminAction::RenounceRole
+        ]
+    );
+    assert_eq!(
+        token.admin_paths[0].controllers,
+        vec![Controller::RoleAdmin]
+    );
+    assert!(token.notes.is_empty());
+
+    // A hand-written admin check is a custom model, not ownership.
+    let registry = contract(&contracts, "Registry");
+    assert_eq!(registry.models, vec![AdminModel::Custom]);
+    let paths: Vec<(AdminAction, &str)> = registry
+        .admin_paths
+        .iter()
+        .map(|p| (p.action, p.function.as_str()))
+        .collect();
+    assert_eq!(paths, vec![(AdminAction::SetAdmin, "Registry.setAdmin")]);
+    assert_eq!(
+        registry.functions[0].controllers,
+        vec![Controller::Address("admin".into())]
+    );
+}
+
+#[test]
+fn test_timelocked_and_direct_functions() {
+    let contracts = analyze_source(TIMELOCKED);
+    let treasury = &contracts[0];
+    assert_eq!(
+        treasury.models,
+        vec![
+            AdminModel::Ownable,
+            AdminModel::Timelock,
+            AdminModel::Custom
+        ]
+    );
+    let functions: Vec<(&str, Option<&str>)> = treasury
+        .functions
+        .iter()
+        .map(|f| (f.function.as_str(), f.timelock.as_deref()))
+        .collect();
+    assert_eq!(
+        functions,
+        vec![
+            ("Treasury.setLimit", Some("`onlyTimelock`")),
+            ("Treasury.queueFee", None),
+            ("Treasury.applyFee", Some("feeReadyAt")),
+            ("Treasury.sweep", None),
+        ]
+    );
+    assert_eq!(
+        treasury.timelocks,
+        vec![
+            Timelock {
+                kind: TimelockKind::Controller,
+                name: "`onlyTimelock`".to_string(),
+                delays: vec!["DELAY = 2 days".to_string()],
+                queued_by: vec![],
+                functions: vec!["Treasury.setLimit".to_string()],
+            },
+            Timelock {
+                kind: TimelockKind::Queue,
+                name: "feeReadyAt".to_string(),
+                delays: vec!["DELAY = 2 days".to_string()],
+                queued_by: vec!["Treasury.queueFee".to_string()],
+                functions: vec!["Treasury.applyFee".to_string()],
+            },
+        ]
+    );
+    assert!(treasury.notes.contains(
+        &"Restricted functions callable without a timelock delay: `Treasury.sweep`.".to_string()
+    ));
+
+    let report = governance::render_governance(&contracts);
+    assert!(report.contains("| `Treasury.sweep` | owner | **direct** |"));
+    assert!(report.contains("| `Treasury.applyFee` | owner | `feeReadyAt` queue |"));
+    assert!(report.contains(
+        "| `feeReadyAt` queue | `DELAY = 2 days` | `Treasury.queueFee` | `Treasury.applyFee` |"
+    ));
+    let mermaid = governance::render_mermaid(&contracts);
+    assert!(mermaid.contains("class c0f0 timelocked"));
+    assert!(mermaid.contains("-- delayed --> c0f2"));
+
+    // Contracts without timelocks keep the two-column table.
+    let report = governance::render_governance(&analyze());
+    assert!(report.contains("| Function | Who |\n"));
+    assert!(!report.contains("### Timelocks"));
+}
+
+#[test]
+fn test_report_and_diagram() {
+    let contracts = analyze();
+    let report = governance::render_governance(&contracts);
+    assert!(report.contains("## Vault (Ownable, custom)"));
+    assert!(
+        report.contains("| renounce ownership | `Vault.renounceOwnership` (disabled) | owner |")
+    );
+    assert!(
+        report.contains("| transfer ownership | `Ownable.transferOwnership` (inherited) | owner |")
+    );
+    assert!(report.contains("| MINTER_ROLE | MANAGER_ROLE |"));
+
+    let mermaid = governance::render_mermaid(&contracts);
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("subgraph c1[\"Token\"]"));
+    assert!(mermaid.contains("c1k0([\"MINTER_ROLE\"])"));
+    assert!(mermaid.contains("c1k1 -. administers .-> c1k0"));
+    assert!(mermaid.contains("class c0f2 admin"));
+}
+
+#[test]
+fn test_governance_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Vault.sol");
+    std::fs::write(&path, SOURCES).unwrap();
+
+    let response = run_worker_request(|tx| GenerationRequest::GovernanceReport {
+        uris: vec![lsp_types::Url::from_file_path(&path).unwrap()],
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(response["contracts"].as_array().unwrap().len(), 3);
+    assert_eq!(
+        response["contracts"][1]["models"],
+        serde_json::json!(["access_control"])
+    );
+    assert_eq!(
+        response["contracts"][1]["functions"][0]["controllers"],
+        serde_json::json!([{ "kind": "role", "name": "MINTER_ROLE" }])
+    );
+    assert!(response["mermaid"]
+        .as_str()
+        .unwrap()
+        .starts_with("flowchart LR"));
+    assert!(response["report"]
+        .as_str()
+        .unwrap()
+        .starts_with("# Governance"));
+}
@@ -0,0 +1,307 @@
+use lsp_types::Url;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use tokio::sync::oneshot;
+use traverse_graph::cg::CallGraph;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::config::{Config, DotConfig, GraphFilter};
+use traverse_lsp::graph_cache::{estimate_size, fingerprint, GraphCache, GraphKind};
+use traverse_lsp::symbol_index::SymbolIndex;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+fn contract(name: &str, functions: usize) -> String {
+    let mut source = format!("contract {} {{\n    uint256 total;\n", name);
+    for i in 0..functions {
+        source.push_str(&format!(
+            "    function f{}(uint256 amount) external {{ total += amount; }}\n",
+            i
+        ));
+    }
+    source.push_str("}\n");
+    source
+}
+
+fn units(path: &str, content: &str) -> Vec<SourceUnit> {
+    vec![SourceUnit {
+        path: PathBuf::from(path),
+        content: content.to_string(),
+    }]
+}
+
+#[test]
+fn test_cache_hits_and_lru_eviction() {
+    let adapter = TraverseAdapter::new().unwrap();
+    let sources: Vec<Vec<SourceUnit>> = ["A", "B", "C"]
+        .iter()
+        .map(|n| units(&format!("{}.sol", n), &contract(n, 5)))
+        .collect();
+    let size = estimate_size(&adapter.build_call_graph(&sources[0][0].content).unwrap());
+    assert!(size > 0);
+
+    // Room for two graphs of this size.
+    let cache = GraphCache::new(size * 2 + size / 2);
+    let mut builds = 0;
+    let mut get = |source: &[SourceUnit]| {
+        cache
+            .get_or_build(GraphKind::Plain, source, || {
+                builds += 1;
+                adapter.build_call_graph(&source[0].content)
+            })
+            .unwrap()
+    };
+
+    let first = get(&sources[0]);
+    let again = get(&sources[0]);
+    assert_eq!(first.nodes.len(), again.nodes.len());
+    get(&sources[1]);
+    // A was used before B, so adding C evicts A.
+    get(&sources[0]);
+    get(&sources[2]);
+    get(&sources[0]);
+    get(&sources[1]);
+    assert_eq!(builds, 4);
+
+    let stats = cache.stats();
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.hits, 3);
+    assert_eq!(stats.misses, 4);
+    assert_eq!(stats.evictions, 2);
+    assert!(stats.bytes <= stats.budget_bytes);
+}
+
+#[test]
+fn test_cache_keys_on_kind_files_and_disabled_budget() {
+    let adapter = TraverseAdapter::new().unwrap();
+    let source = contract("A", 2);
+    let build = || adapter.build_call_graph(&source);
+
+    let cache = GraphCache::new(usize::MAX);
+    let a = units("A.sol", &source);
+    cache.get_or_build(GraphKind::Plain, &a, build).unwrap();
+    cache
+        .get_or_build(GraphKind::WithPlaceholders, &a, build)
+        .unwrap();
+    assert_eq!(cache.stats().entries, 2);
+    assert_eq!(cache.stats().hits, 0);
+
+    // The same content under another path, or an edit, is a different key.
+    let moved = units("B.sol", &source);
+    assert_ne!(fingerprint(&a), fingerprint(&moved));
+    let edited = units("A.sol", &contract("A", 3));
+    assert_ne!(fingerprint(&a), fingerprint(&edited));
+    assert_eq!(fingerprint(&a), fingerprint(&units("A.sol", &source)));
+    cache.get_or_build(GraphKind::Plain, &moved, build).unwrap();
+    assert_eq!(cache.stats().entries, 3);
+
+    let disabled = GraphCache::new(0);
+    for _ in 0..2 {
+        disabled.get_or_build(GraphKind::Plain, &a, build).unwrap();
+    }
+    let stats = disabled.stats();
+    assert_eq!((stats.entries, stats.bytes, stats.misses), (0, 0, 2));
+}
+
+#[test]
+fn test_worker_reuses_cached_graph() {
+    let workspace = tempfile::tempdir().unwrap();
+    let path = workspace.path().join("Vault.sol");
+    std::fs::write(&path, contract("Vault", 3)).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let graphs = Arc::new(GraphCache::new(Config::default().cache_budget_bytes()));
+    let (tx, rx) = mpsc::channel();
+    let mut replies = Vec::new();
+    for _ in 0..2 {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(GenerationRequest::GenerateCallGraphDiagram {
+            uris: vec![uri.clone()],
+            contract_name: None,
+            dot_config: DotConfig::default(),
+            filter: GraphFilter::default(),
+            coverage_file: None,
+            progress: None,
+            cancel: Default::default(),
+            tx: reply_tx,
+        })
+        .unwrap();
+        replies.push(reply_rx);
+    }
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_graph_cache(graphs.clone())
+        .run(rx);
+
+    // Compared by diagram, since each reply carries its own timings.
+    let dots: Vec<serde_json::Value> = replies
+        .into_iter()
+        .map(|reply| {
+            let reply: serde_json::Value =
+                serde_json::from_str(&reply.blocking_recv().unwrap().unwrap()).unwrap();
+            reply["dot"].clone()
+        })
+        .collect();
+    assert_eq!(dots[0], dots[1]);
+    let stats = graphs.stats();
+    // The diagram graph is built on the plain graph; both are cached.
+    assert_eq!((stats.entries, stats.hits, stats.misses), (2, 1, 2));
+}
+
+#[test]
+fn test_worker_caches_each_scope() {
+    let workspace = tempfile::tempdir().unwrap();
+    let path = workspace.path().join("Vault.sol");
+    std::fs::write(&path, contract("Vault", 3) + &contract("Pool", 2)).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let graphs = Arc::new(GraphCache::new(Config::default().cache_budget_bytes()));
+    let (tx, rx) = mpsc::channel();
+    let mut replies = Vec::new();
+    for contract_name in [None, Some("Pool"), None, Some("Pool")] {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(GenerationRequest::GenerateCallGraphDiagram {
+            uris: vec![uri.clone()],
+            contract_name: contract_name.map(str::to_string),
+            dot_config: DotConfig::default(),
+            filter: GraphFilter::default(),
+            coverage_file: None,
+            progress: None,
+            cancel: Default::default(),
+            tx: reply_tx,
+        })
+        .unwrap();
+        replies.push(reply_rx);
+    }
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_graph_cache(graphs.clone())
+        .run(rx);
+
+    // Compared by diagram, since each reply carries its own timings.
+    let dots: Vec<serde_json::Value> = replies
+        .into_iter()
+        .map(|reply| {
+            let reply: serde_json::Value =
+                serde_json::from_str(&reply.blocking_recv().unwrap().unwrap()).unwrap();
+            reply["dot"].clone()
+        })
+        .collect();
+    assert_eq!(dots[0], dots[2]);
+    assert_eq!(dots[1], dots[3]);
+    assert_ne!(dots[0], dots[1]);
+    let stats = graphs.stats();
+    // The plain graph, the one with placeholders and the one for `Pool`,
+    // each built once.
+    assert_eq!((stats.entries, stats.hits, stats.misses), (3, 3, 3));
+
+    assert_eq!(graphs.clear(), 3);
+    let stats = graphs.stats();
+    assert_eq!((stats.entries, stats.bytes), (0, 0));
+    assert_eq!(graphs.clear(), 0);
+}
+
+#[test]
+fn test_symbol_index_memory_estimate() {
+    let index = SymbolIndex::new();
+    assert_eq!(index.approximate_bytes(), 0);
+
+    let uri = Url::parse("file:///workspace/Vault.sol").unwrap();
+    let text = contract("Vault", 3);
+    index.update(uri.clone(), Some(1), text.clone());
+    let unindexed = index.approximate_bytes();
+    assert!(unindexed >= text.len());
+
+    index.refresh(&uri);
+    assert!(index.approximate_bytes() > unindexed);
+}
+
+#[test]
+fn test_cache_budget_config() {
+    let config: Config = serde_json::from_value(serde_json::json!({})).unwrap();
+    assert_eq!(config.cache_budget_bytes(), 256 * 1024 * 1024);
+    let config: Config =
+        serde_json::from_value(serde_json::json!({ "cache_budget_mb": 0 })).unwrap();
+    assert_eq!(config.cache_budget_bytes(), 0);
+    let config: Config =
+        serde_json::from_value(serde_json::json!({ "cache_enabled": false })).unwrap();
+    assert_eq!(config.cache_budget_bytes(), 0);
+}
+
+#[test]
+fn test_invalidate_drops_graphs_built_from_a_file() {
+    let adapter = TraverseAdapter::new().unwrap();
+    let a = contract("A", 2);
+    let b = contract("B", 2);
+    let both = vec![
+        SourceUnit {
+            path: PathBuf::from("/workspace/A.sol"),
+            content: a.clone(),
+        },
+        SourceUnit {
+            path: PathBuf::from("/workspace/B.sol"),
+            content: b.clone(),
+        },
+    ];
+    let only_b = units("/workspace/B.sol", &b);
+
+    let cache = GraphCache::new(usize::MAX);
+    cache
+        .get_or_build(GraphKind::Plain, &both, || {
+            adapter.build_workspace_call_graph(&both)
+        })
+        .unwrap();
+    cache
+        .get_or_build(GraphKind::Plain, &only_b, || adapter.build_call_graph(&b))
+        .unwrap();
+    let bytes = cache.stats().bytes;
+
+    assert_eq!(cache.invalidate(Path::new("/workspace/A.sol")), 1);
+    let stats = cache.stats();
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.invalidations, 1);
+    assert!(stats.bytes < bytes);
+    assert_eq!(cache.invalidate(Path::new("/workspace/A.sol")), 0);
+
+    // A closed workspace folder drops every graph built from its files.
+    assert_eq!(cache.invalidate(Path::new("/workspace")), 1);
+    let stats = cache.stats();
+    assert_eq!(stats.entries, 0);
+    assert_eq!(stats.bytes, 0);
+}
+
+#[test]
+fn test_latest_skips_scoped_graphs_and_counts_no_hit() {
+    let adapter = TraverseAdapter::new().unwrap();
+    let a = contract("A", 2);
+    let one = units("/workspace/A.sol", &a);
+    let two = units("/workspace/A.sol", &contract("A", 3));
+
+    let cache = GraphCache::new(usize::MAX);
+    assert!(cache.latest(Path::new("/workspace/A.sol")).is_none());
+    cache
+        .get_or_build(GraphKind::Plain, &one, || adapter.build_call_graph(&a))
+        .unwrap();
+    cache
+        .get_or_build(GraphKind::WithPlaceholders, &two, || {
+            adapter.build_call_graph(&two[0].content)
+        })
+        .unwrap();
+    cache
+        .get_or_build(GraphKind::Scoped([1; 32]), &one, || Ok(CallGraph::new()))
+        .unwrap();
+    let stats = cache.stats();
+
+    let latest = cache.latest(Path::new("/workspace/A.sol")).unwrap();
+    assert!(latest.nodes.iter().any(|n| n.name == "f2"));
+    assert_eq!(cache.stats(), stats);
+    let (_, files) = cache
+        .latest_with_files(Path::new("/workspace/A.sol"))
+        .unwrap();
+    assert_eq!(
+        files,
+        vec![(PathBuf::from("/workspace/A.sol"), two[0].content.len())]
+    );
+    assert!(cache.latest(Path::new("/workspace/B.sol")).is_none());
+}
@@ -0,0 +1,238 @@
+use lsp_types::Url;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_graph::cg::{CallGraph, EdgeType, NodeType};
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+use traverse_lsp::config::{Config, DotConfig, GraphFilter};
+use traverse_lsp::graph_filter;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const VAULT: &str = r#"
+pragma solidity ^0.8.0;
+
+interface IOracle {
+    function price() external view returns (uint256);
+}
+
+contract Oracle is IOracle {
+    uint256 public override price;
+
+    function update(uint256 value) external {
+        price = value;
+    }
+}
+
+contract Vault {
+    IOracle oracle;
+    uint256 total;
+
+    function deposit(uint256 amount) external {
+        uint256 rate = oracle.price();
+        _credit(amount * rate);
+    }
+
+    function preview(uint256 amount) public view returns (uint256) {
+        return _scale(amount);
+    }
+
+    function _credit(uint256 amount) internal {
+        total += amount;
+    }
+
+    function _scale(uint256 amount) internal pure returns (uint256) {
+        return amount * 2;
+    }
+
+    function _unused() internal {
+        total = 0;
+    }
+}
+"#;
+
+fn build() -> (CallGraph, SourceModel) {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(VAULT)
+        .expect("Failed to build call graph");
+    let unit = SourceUnit {
+        path: PathBuf::from("Vault.sol"),
+        content: VAULT.to_string(),
+    };
+    let parsed = parse_sources(std::slice::from_ref(&unit)).unwrap();
+    let model = SourceModel::from_parsed(&parsed);
+    (graph, model)
+}
+
+fn has_function(graph: &CallGraph, contract: &str, name: &str) -> bool {
+    graph.nodes.iter().any(|n| {
+        n.node_type == NodeType::Function
+            && n.contract_name.as_deref() == Some(contract)
+            && n.name == name
+    })
+}
+
+fn assert_consistent(graph: &CallGraph) {
+    for (index, node) in graph.nodes.iter().enumerate() {
+        assert_eq!(node.id, index);
+    }
+    for edge in &graph.edges {
+        assert!(edge.source_node_id < graph.nodes.len());
+        assert!(edge.target_node_id < graph.nodes.len());
+    }
+}
+
+#[test]
+fn test_empty_filter_keeps_graph() {
+    let (graph, model) = build();
+    let pruned = graph_filter::apply(&graph, &GraphFilter::default(), Some(&model));
+    assert_eq!(pruned.nodes.len(), graph.nodes.len());
+    assert_eq!(pruned.edges.len(), graph.edges.len());
+}
+
+#[test]
+fn test_only_external_entrypoints() {
+    let (graph, model) = build();
+    assert!(has_function(&graph, "Vault", "_unused"));
+
+    let filter = GraphFilter {
+        only_external_entrypoints: true,
+        ..GraphFilter::default()
+    };
+    let pruned = graph_filter::apply(&graph, &filter, Some(&model));
+    assert_consistent(&pruned);
+
+    assert!(has_function(&pruned, "Vault", "deposit"));
+    assert!(has_function(&pruned, "Vault", "_credit"));
+    assert!(has_function(&pruned, "Vault", "_scale"));
+    assert!(!has_function(&pruned, "Vault", "_unused"));
+}
+
+#[test]
+fn test_hide_view_functions() {
+    let (graph, model) = build();
+    let filter = GraphFilter {
+        hide_view_functions: true,
+        ..GraphFilter::default()
+    };
+    let pruned = graph_filter::apply(&graph, &filter, Some(&model));
+    assert_consistent(&pruned);
+
+    assert!(!has_function(&pruned, "Vault", "preview"));
+    assert!(!has_function(&pruned, "Vault", "_scale"));
+    assert!(has_function(&pruned, "Vault", "deposit"));
+
+    // Without the source model mutability is unknown, so nothing is hidden.
+    let unfiltered = graph_filter::apply(&graph, &filter, None);
+    assert!(has_function(&unfiltered, "Vault", "preview"));
+}
+
+#[test]
+fn test_hide_getters() {
+    let (graph, model) = build();
+    let getter_calls = |g: &CallGraph| {
+        g.edges
+            .iter()
+            .filter(|e| {
+                e.edge_type == EdgeType::Call
+                    && g.nodes[e.target_node_id].node_type == NodeType::StorageVariable
+            })
+            .count()
+    };
+    assert!(getter_calls(&graph) > 0);
+
+    let filter = GraphFilter {
+        hide_getters: true,
+        ..GraphFilter::default()
+    };
+    let pruned = graph_filter::apply(&graph, &filter, Some(&model));
+    assert_consistent(&pruned);
+    assert_eq!(getter_calls(&pruned), 0);
+    assert!(has_function(&pruned, "Vault", "deposit"));
+}
+
+#[test]
+fn test_limit_depth() {
+    let (graph, _) = build();
+    let entrypoints = graph_filter::limit_depth(&graph, 0);
+    assert_consistent(&entrypoints);
+    assert!(has_function(&entrypoints, "Vault", "deposit"));
+    assert!(has_function(&entrypoints, "Oracle", "update"));
+    assert!(!has_function(&entrypoints, "Vault", "_credit"));
+
+    let one_call = graph_filter::limit_depth(&graph, 1);
+    assert_consistent(&one_call);
+    assert!(has_function(&one_call, "Vault", "_credit"));
+    assert!(has_function(&one_call, "Vault", "_scale"));
+    assert!(!has_function(&one_call, "Vault", "_unused"));
+}
+
+#[test]
+fn test_contract_subgraph() {
+    let (graph, _) = build();
+    let oracle = graph_filter::contract_subgraph(&graph, "Oracle", None).unwrap();
+    assert_consistent(&oracle);
+    assert!(has_function(&oracle, "Oracle", "update"));
+    assert!(!has_function(&oracle, "Vault", "deposit"));
+
+    let vault = graph_filter::contract_subgraph(&graph, "Vault", None).unwrap();
+    assert_consistent(&vault);
+    assert!(has_function(&vault, "Vault", "_unused"));
+    assert!(!has_function(&vault, "Oracle", "update"));
+    assert!(vault
+        .nodes
+        .iter()
+        .all(|n| n.contract_name.as_deref() != Some("Oracle") || n.name == "price"));
+
+    assert!(graph_filter::contract_subgraph(&graph, "Missing", None).is_none());
+
+    let members = graph_filter::contract_subgraph(&graph, "Vault", Some(0)).unwrap();
+    assert_consistent(&members);
+    assert!(has_function(&members, "Vault", "_unused"));
+    assert!(members
+        .nodes
+        .iter()
+        .all(|n| n.contract_name.as_deref() == Some("Vault")));
+}
+
+fn call_graph_dot(contract_name: Option<&str>) -> anyhow::Result<String> {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Vault.sol");
+    std::fs::write(&path, VAULT).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateCallGraphDiagram {
+        uris: vec![Url::from_file_path(&path).unwrap()],
+        contract_name: contract_name.map(str::to_string),
+        dot_config: DotConfig::default(),
+        filter: GraphFilter::default(),
+        coverage_file: None,
+        progress: None,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap()?).unwrap();
+    Ok(response["dot"].as_str().unwrap().to_string())
+}
+
+#[test]
+fn test_diagram_limited_to_contract() {
+    let all = call_graph_dot(None).unwrap();
+    assert!(all.contains("deposit") && all.contains("update"));
+
+    let oracle = call_graph_dot(Some("Oracle")).unwrap();
+    assert!(oracle.contains("update"));
+    assert!(!oracle.contains("deposit"));
+
+    let error = call_graph_dot(Some("Missing")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Contract Missing not found in the call graph"
+    );
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::{names, write};
+use std::path::Path;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::hardhat::HardhatProject;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::utils::find_solidity_files;
+
+const VAULT: &str = r#"
+import "@openzeppelin/contracts/utils/math/Math.sol";
+import "./lib/Fees.sol";
+
+contract Vault {
+    function share(uint256 a, uint256 b) public pure returns (uint256) {
+        return Math.max(a, b) - Fees.fee(a);
+    }
+}
+"#;
+
+const MATH: &str = r#"
+import "../../access/Ownable.sol";
+
+library Math {
+    function max(uint256 a, uint256 b) internal pure returns (uint256) {
+        return a >= b ? a : b;
+    }
+}
+"#;
+
+fn hardhat_workspace(root: &Path, config: &str) {
+    write(root, "hardhat.config.ts", config);
+    write(root, "contracts/Vault.sol", VAULT);
+    write(
+        root,
+        "contracts/lib/Fees.sol",
+        "library Fees { function fee(uint256 a) internal pure returns (uint256) { return a / 100; } }\n",
+    );
+    write(root, "contracts/Unused.sol", "contract Unused {}\n");
+    write(root, "test/VaultHelper.sol", "contract VaultHelper {}\n");
+    write(
+        root,
+        "node_modules/@openzeppelin/contracts/utils/math/Math.sol",
+        MATH,
+    );
+    write(
+        root,
+        "node_modules/@openzeppelin/contracts/access/Ownable.sol",
+        "contract Ownable {}\n",
+    );
+    write(
+        root,
+        "node_modules/@openzeppelin/contracts/token/ERC20/ERC20.sol",
+        "contract ERC20 {}\n",
+    );
+}
+
+#[test]
+fn test_detect_sources_path() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    assert!(HardhatProject::detect(root).unwrap().is_none());
+
+    write(
+        root,
+        "hardhat.config.js",
+        "module.exports = { solidity: \"0.8.20\" };\n",
+    );
+    let project = HardhatProject::detect(root).unwrap().unwrap();
+    assert_eq!(project.sources, root.join("contracts"));
+
+    write(
+        root,
+        "hardhat.config.js",
+        "module.exports = {\n  paths: {\n    sources: './src/contracts',\n    tests: \"./test\",\n  },\n};\n",
+    );
+    let project = HardhatProject::detect(root).unwrap().unwrap();
+    assert_eq!(project.sources, root.join("src/contracts"));
+}
+
+#[test]
+fn test_scope_without_node_modules() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    hardhat_workspace(root, "export default { solidity: \"0.8.20\" };\n");
+
+    let project = HardhatProject::detect(root).unwrap().unwrap();
+    let files = find_solidity_files(root).unwrap();
+    assert_eq!(
+        names(root, &project.scope(&files, false).unwrap()),
+        vec![
+            "contracts/Unused.sol",
+            "contracts/Vault.sol",
+            "contracts/lib/Fees.sol",
+        ]
+    );
+}
+
+#[test]
+fn test_scope_resolves_node_modules_imports() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    hardhat_workspace(root, "export default { solidity: \"0.8.20\" };\n");
+
+    let project = HardhatProject::detect(root).unwrap().unwrap();
+    let files = find_solidity_files(root).unwrap();
+    let scope = project.scope(&files, true).unwrap();
+    // Package files come after the project's, and only imported ones.
+    assert_eq!(
+        names(root, &scope),
+        vec![
+            "contracts/Unused.sol",
+            "contracts/Vault.sol",
+            "contracts/lib/Fees.sol",
+            "node_modules/@openzeppelin/contracts/access/Ownable.sol",
+            "node_modules/@openzeppelin/contracts/utils/math/Math.sol",
+        ]
+    );
+
+    let sources: Vec<SourceUnit> = scope
+        .iter()
+        .map(|uri| {
+            let path = uri.to_file_path().unwrap();
+            SourceUnit {
+                content: std::fs::read_to_string(&path).unwrap(),
+                path,
+            }
+        })
+        .collect();
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    assert!(graph
+        .nodes
+        .iter()
+        .any(|n| n.contract_name.as_deref() == Some("Math") && n.name == "max"));
+}
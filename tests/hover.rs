@@ -0,0 +1,129 @@
+use lsp_types::{HoverContents, Position};
+use std::path::PathBuf;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::handlers::hover::hover_for;
+use traverse_lsp::symbol_index::{declaration_at, SymbolKind};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const SOURCE: &str = r#"pragma solidity ^0.8.0;
+
+contract Token {
+    uint256 public total;
+    mapping(address => uint256) balances;
+
+    constructor() {
+        total = 1;
+    }
+
+    function mint(address to, uint256 amount) external {
+        _add(to, amount);
+    }
+
+    function _add(address to, uint256 amount) internal {
+        balances[to] += amount;
+        total += amount;
+    }
+
+    function supply() external view returns (uint256) {
+        return total;
+    }
+}
+
+contract Vault {
+    Token token;
+
+    function deposit(uint256 amount) external {
+        token.mint(msg.sender, amount);
+    }
+}
+"#;
+
+fn hover_text(line: u32, character: u32, built: bool) -> Option<String> {
+    let declaration = declaration_at(SOURCE, Position::new(line, character))?;
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&[SourceUnit {
+            path: PathBuf::from("/ws/Token.sol"),
+            content: SOURCE.to_string(),
+        }])
+        .unwrap();
+    let hover = hover_for(&declaration, built.then_some(&graph), &[]);
+    assert_eq!(hover.range, Some(declaration.range));
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("{:?}", hover.contents);
+    };
+    Some(markup.value)
+}
+
+#[test]
+fn test_declaration_at() {
+    let mint = declaration_at(SOURCE, Position::new(10, 14)).unwrap();
+    assert_eq!(mint.kind, SymbolKind::Function);
+    assert_eq!(
+        (mint.container.as_str(), mint.name.as_str()),
+        ("Token", "mint")
+    );
+    assert_eq!(mint.range.start, Position::new(10, 13));
+    assert_eq!(mint.range.end, Position::new(10, 17));
+
+    let total = declaration_at(SOURCE, Position::new(3, 22)).unwrap();
+    assert_eq!(total.kind, SymbolKind::StateVariable);
+    assert_eq!(total.name, "total");
+    let constructor = declaration_at(SOURCE, Position::new(6, 6)).unwrap();
+    assert_eq!(constructor.name, "constructor");
+
+    // Bodies, parameters and keywords other than `constructor` are not
+    // declaration names.
+    assert!(declaration_at(SOURCE, Position::new(11, 10)).is_none());
+    assert!(declaration_at(SOURCE, Position::new(10, 26)).is_none());
+    assert!(declaration_at(SOURCE, Position::new(10, 6)).is_none());
+}
+
+#[test]
+fn test_function_hover() {
+    assert_eq!(
+        hover_text(10, 14, true).unwrap(),
+        "**Token.mint**\n\n\
+         - **Callers:** `Vault.deposit`\n\
+         - **Callees:** `Token._add`\n\
+         - **Reads:** none\n\
+         - **Writes:** none"
+    );
+    let add = hover_text(14, 14, true).unwrap();
+    assert!(add.contains("- **Callers:** `Token.mint`"), "{}", add);
+    assert!(
+        add.contains("- **Writes:** `Token.balances`, `Token.total`"),
+        "{}",
+        add
+    );
+    let constructor = hover_text(6, 6, true).unwrap();
+    assert!(constructor.starts_with("**Token.constructor**"));
+    assert!(
+        constructor.contains("- **Writes:** `Token.total`"),
+        "{}",
+        constructor
+    );
+}
+
+#[test]
+fn test_state_variable_hover() {
+    let total = hover_text(3, 22, true).unwrap();
+    assert!(total.starts_with("**Token.total**\n\n"), "{}", total);
+    assert!(
+        total.contains("- **Read by:** `Token._add`, `Token.supply`"),
+        "{}",
+        total
+    );
+    assert!(
+        total.contains("- **Written by:** `Token._add`, `Token.constructor`"),
+        "{}",
+        total
+    );
+}
+
+#[test]
+fn test_hover_without_a_graph() {
+    let text = hover_text(10, 14, false).unwrap();
+    assert!(text.contains("`traverse.warmCache`"), "{}", text);
+    assert!(hover_text(0, 3, false).is_none());
+}
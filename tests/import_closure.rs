@@ -0,0 +1,66 @@
+mod common;
+
+use common::{relative_names, write};
+use traverse_lsp::analysis::imports::UnresolvedImport;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+#[test]
+fn test_resolve_imports_follows_the_dependency_closure() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = workspace.path();
+    write(root, "remappings.txt", "solmate/=lib/solmate/src/\n");
+    write(
+        root,
+        "src/Vault.sol",
+        r#"
+import "./Math.sol";
+import {ERC20} from "solmate/tokens/ERC20.sol";
+import "@openzeppelin/contracts/access/Ownable.sol";
+import "./Missing.sol";
+contract Vault is ERC20, Ownable {}
+"#,
+    );
+    write(
+        root,
+        "src/Math.sol",
+        "import \"../src/Vault.sol\";\nlibrary Math {}\n",
+    );
+    write(root, "src/Unrelated.sol", "contract Unrelated {}\n");
+    write(
+        root,
+        "lib/solmate/src/tokens/ERC20.sol",
+        "import \"forge-std/Gone.sol\";\ncontract ERC20 {}\n",
+    );
+    write(
+        root,
+        "node_modules/@openzeppelin/contracts/access/Ownable.sol",
+        "contract Ownable {}\n",
+    );
+
+    let closure = TraverseAdapter::new()
+        .unwrap()
+        .resolve_imports(root, &[root.join("src/Vault.sol")])
+        .unwrap();
+    assert_eq!(
+        relative_names(root, &closure.files),
+        vec![
+            "lib/solmate/src/tokens/ERC20.sol",
+            "node_modules/@openzeppelin/contracts/access/Ownable.sol",
+            "src/Math.sol",
+            "src/Vault.sol",
+        ]
+    );
+    assert_eq!(
+        closure.unresolved,
+        vec![
+            UnresolvedImport {
+                file: root.join("src/Vault.sol"),
+                import: "./Missing.sol".to_string(),
+            },
+            UnresolvedImport {
+                file: root.join("lib/solmate/src/tokens/ERC20.sol"),
+                import: "forge-std/Gone.sol".to_string(),
+            },
+        ]
+    );
+}
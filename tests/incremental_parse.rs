@@ -0,0 +1,260 @@
+use lsp_types::{Position, Range, TextDocumentContentChangeEvent, Url};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use traverse_graph::parser::parse_solidity;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::documents::DocumentStore;
+use traverse_lsp::graph_cache::GraphCache;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const TOKEN: &str = r#"pragma solidity ^0.8.0;
+
+contract Token {
+    function mint(uint256 amount) external {
+        _mint(amount);
+    }
+
+    function _mint(uint256 amount) internal {}
+}
+"#;
+
+const VAULT: &str = r#"
+contract Vault {
+    function deposit() external {}
+}
+"#;
+
+fn workspace(token: &str, vault: &str) -> Vec<SourceUnit> {
+    vec![
+        SourceUnit {
+            path: PathBuf::from("/workspace/Token.sol"),
+            content: token.to_string(),
+        },
+        SourceUnit {
+            path: PathBuf::from("/workspace/Vault.sol"),
+            content: vault.to_string(),
+        },
+    ]
+}
+
+fn node_names(adapter: &TraverseAdapter, sources: &[SourceUnit]) -> Vec<String> {
+    let mut names: Vec<String> = adapter
+        .build_workspace_call_graph(sources)
+        .unwrap()
+        .nodes
+        .iter()
+        .map(|n| format!("{}.{}", n.contract_name.as_deref().unwrap_or(""), n.name))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_incremental_parse_matches_fresh_parse() {
+    let adapter = TraverseAdapter::new().unwrap();
+    let path = Path::new("/workspace/Token.sol");
+    adapter.parse(path, TOKEN).unwrap();
+
+    let edits = [
+        // Rename inside the file.
+        TOKEN.replace("_mint", "_mintTo"),
+        // Add a function.
+        TOKEN.replace(
+            "    function _mint",
+            "    function burn() external {}\n\n    function _mint",
+        ),
+        // Multi-byte text.
+        format!("{}// café ☕\n", TOKEN),
+        // Back to an earlier version.
+        TOKEN.to_string(),
+    ];
+    for source in &edits {
+        let tree = adapter.parse(path, source).unwrap();
+        let fresh = parse_solidity(source).unwrap().tree;
+        assert_eq!(tree.root_node().to_sexp(), fresh.root_node().to_sexp());
+        assert!(!tree.root_node().has_error());
+    }
+}
+
+#[test]
+fn test_call_graph_after_edit() {
+    let adapter = TraverseAdapter::new().unwrap();
+    let sources = workspace(TOKEN, VAULT);
+    assert!(node_names(&adapter, &sources).contains(&"Vault.deposit".to_string()));
+
+    let edited = workspace(TOKEN, &VAULT.replace("deposit", "withdraw"));
+    let names = node_names(&adapter, &edited);
+    assert!(names.contains(&"Vault.withdraw".to_string()));
+    assert!(!names.contains(&"Vault.deposit".to_string()));
+    assert_eq!(names, node_names(&TraverseAdapter::new().unwrap(), &edited));
+}
+
+#[test]
+fn test_parallel_parse_matches_sequential_parse() {
+    let sources: Vec<SourceUnit> = (0..24)
+        .map(|i| SourceUnit {
+            path: PathBuf::from(format!("/workspace/Token{}.sol", i)),
+            content: TOKEN.replace("Token", &format!("Token{}", i)),
+        })
+        .collect();
+    let build = |threads: usize| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let graph = pool
+            .install(|| {
+                TraverseAdapter::new()
+                    .unwrap()
+                    .build_workspace_call_graph(&sources)
+            })
+            .unwrap();
+        let nodes: Vec<_> = graph
+            .nodes
+            .iter()
+            .map(|n| (n.id, n.name.clone(), n.contract_name.clone(), n.span))
+            .collect();
+        let edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|e| (e.source_node_id, e.target_node_id, e.call_site_span))
+            .collect();
+        (nodes, edges)
+    };
+    assert_eq!(build(1), build(4));
+}
+
+#[test]
+fn test_shared_parse_trees_can_be_forgotten() {
+    let trees = Arc::new(GraphCache::new(usize::MAX));
+    let first = TraverseAdapter::new()
+        .unwrap()
+        .with_graph_cache(trees.clone());
+    let second = TraverseAdapter::new()
+        .unwrap()
+        .with_graph_cache(trees.clone());
+    let path = Path::new("/workspace/Token.sol");
+
+    first.parse(path, TOKEN).unwrap();
+    // The second adapter edits the tree the first one parsed.
+    let edited = TOKEN.replace("_mint", "_mintTo");
+    let tree = second.parse(path, &edited).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        parse_solidity(&edited).unwrap().tree.root_node().to_sexp()
+    );
+
+    assert!(trees.forget_trees(path));
+    assert!(!trees.forget_trees(path));
+    let tree = first.parse(path, TOKEN).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        parse_solidity(TOKEN).unwrap().tree.root_node().to_sexp()
+    );
+}
+
+#[test]
+fn test_parse_trees_count_against_the_cache_budget() {
+    let sized = Arc::new(GraphCache::new(usize::MAX));
+    let path = Path::new("/workspace/Token.sol");
+    TraverseAdapter::new()
+        .unwrap()
+        .with_graph_cache(sized.clone())
+        .parse(path, TOKEN)
+        .unwrap();
+    let stats = sized.stats();
+    assert_eq!(stats.parse_trees, 1);
+    assert!(stats.parse_tree_bytes > TOKEN.len());
+
+    // Room for one tree: parsing a second file evicts the first.
+    let cache = Arc::new(GraphCache::new(stats.parse_tree_bytes + 8));
+    let adapter = TraverseAdapter::new()
+        .unwrap()
+        .with_graph_cache(cache.clone());
+    adapter.parse(path, TOKEN).unwrap();
+    adapter
+        .parse(Path::new("/workspace/Other.sol"), TOKEN)
+        .unwrap();
+    let stats = cache.stats();
+    assert_eq!((stats.parse_trees, stats.evictions), (1, 1));
+    assert!(stats.parse_tree_bytes <= stats.budget_bytes);
+    assert!(!cache.forget_trees(path));
+
+    let disabled = Arc::new(GraphCache::new(0));
+    TraverseAdapter::new()
+        .unwrap()
+        .with_graph_cache(disabled.clone())
+        .parse(path, TOKEN)
+        .unwrap();
+    assert_eq!(disabled.stats().parse_trees, 0);
+
+    assert_eq!(sized.clear(), 0);
+    assert_eq!(sized.stats().parse_tree_bytes, 0);
+}
+
+#[test]
+fn test_editor_changes_edit_the_cached_tree() {
+    let trees = Arc::new(GraphCache::new(usize::MAX));
+    let adapter = TraverseAdapter::new()
+        .unwrap()
+        .with_graph_cache(trees.clone());
+    let documents = DocumentStore::new().with_parse_trees(trees.clone());
+    let path = Path::new("/workspace/Token.sol");
+    let uri = Url::from_file_path(path).unwrap();
+    documents.update(uri.clone(), 1, TOKEN.to_string());
+    adapter.parse(path, TOKEN).unwrap();
+
+    // Rename `_mint` in its declaration, then append a multi-byte comment.
+    let changes = vec![
+        TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(7, 13), Position::new(7, 18))),
+            range_length: None,
+            text: "_mintTo".to_string(),
+        },
+        TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(9, 0), Position::new(9, 0))),
+            range_length: None,
+            text: "// café ☕\n".to_string(),
+        },
+    ];
+    let text = documents.apply_changes(&uri, 2, changes).unwrap().unwrap();
+    let (cached, tree) = trees.take_tree(path).unwrap();
+    assert_eq!(cached, text);
+    assert!(tree.root_node().has_changes());
+    trees.store_tree(path.to_path_buf(), cached, tree);
+
+    let tree = adapter.parse(path, &text).unwrap();
+    assert_eq!(
+        tree.root_node().to_sexp(),
+        parse_solidity(&text).unwrap().tree.root_node().to_sexp()
+    );
+
+    // A tree parsed from other text is left for the diff.
+    adapter.parse(path, VAULT).unwrap();
+    let full = TextDocumentContentChangeEvent {
+        range: None,
+        range_length: None,
+        text: TOKEN.to_string(),
+    };
+    documents.apply_changes(&uri, 3, vec![full]).unwrap();
+    let (cached, tree) = trees.take_tree(path).unwrap();
+    assert_eq!(cached, VAULT);
+    assert!(!tree.root_node().has_changes());
+}
+
+#[test]
+fn test_building_one_root_keeps_another_roots_trees() {
+    let trees = Arc::new(GraphCache::new(usize::MAX));
+    let adapter = TraverseAdapter::new()
+        .unwrap()
+        .with_graph_cache(trees.clone());
+    adapter
+        .build_workspace_call_graph(&workspace(TOKEN, VAULT))
+        .unwrap();
+    let other = [SourceUnit {
+        path: PathBuf::from("/other/Token.sol"),
+        content: TOKEN.to_string(),
+    }];
+    adapter.build_workspace_call_graph(&other).unwrap();
+    assert_eq!(trees.stats().parse_trees, 3);
+}
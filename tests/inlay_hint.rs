@@ -0,0 +1,122 @@
+use lsp_types::{InlayHintLabel, InlayHintTooltip, Position, Range};
+use std::path::PathBuf;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::config::Config;
+use traverse_lsp::handlers::inlay_hint::hints;
+use traverse_lsp::source_map::SourceMap;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const VAULT: &str = r#"contract Vault {
+    uint256 total;
+    uint256 fee;
+    mapping(address => uint256) balances;
+
+    function deposit(uint256 amount) external {
+        balances[msg.sender] += amount;
+        _account(amount);
+    }
+
+    function withdraw(uint256 amount) external {
+        balances[msg.sender] -= amount;
+        _account(amount);
+    }
+
+    function _account(uint256 amount) internal {
+        total += amount - fee;
+    }
+
+    function setFee(
+        uint256 next
+    ) external {
+        fee = next;
+    }
+}
+"#;
+
+fn whole_file() -> Range {
+    Range::new(Position::new(0, 0), Position::new(100, 0))
+}
+
+fn labels(range: Range) -> Vec<(Position, String)> {
+    let sources = vec![SourceUnit {
+        path: PathBuf::from("/workspace/Vault.sol"),
+        content: VAULT.to_string(),
+    }];
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    hints(&graph, &SourceMap::new(&sources), &sources[0].path, range)
+        .into_iter()
+        .map(|hint| match hint.label {
+            InlayHintLabel::String(label) => (hint.position, label),
+            InlayHintLabel::LabelParts(_) => panic!("expected a plain label"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_hints_count_storage_access_and_callers() {
+    assert_eq!(
+        labels(whole_file()),
+        vec![
+            (
+                Position::new(5, 45),
+                "reads: 3, writes: 2, callers: 0".to_string()
+            ),
+            (
+                Position::new(10, 46),
+                "reads: 3, writes: 2, callers: 0".to_string()
+            ),
+            (
+                Position::new(15, 46),
+                "reads: 2, writes: 1, callers: 2".to_string()
+            ),
+            (
+                Position::new(21, 14),
+                "reads: 0, writes: 1, callers: 0".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_hints_outside_the_range_are_left_out() {
+    let range = Range::new(Position::new(10, 0), Position::new(16, 0));
+    let found = labels(range);
+    assert_eq!(found.len(), 2);
+    assert_eq!(found[0].0, Position::new(10, 46));
+}
+
+#[test]
+fn test_tooltip_names_callers() {
+    let sources = vec![SourceUnit {
+        path: PathBuf::from("/workspace/Vault.sol"),
+        content: VAULT.to_string(),
+    }];
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    let found = hints(
+        &graph,
+        &SourceMap::new(&sources),
+        &sources[0].path,
+        whole_file(),
+    );
+    let Some(InlayHintTooltip::MarkupContent(tooltip)) = &found[2].tooltip else {
+        panic!("expected a Markdown tooltip");
+    };
+    assert!(tooltip
+        .value
+        .contains("**Callers:** Vault.deposit, Vault.withdraw"));
+    assert!(tooltip.value.contains("**Writes:** Vault.total"));
+}
+
+#[test]
+fn test_inlay_hints_are_on_by_default() {
+    assert!(Config::default().inlay_hints_enabled());
+    let config: Config =
+        serde_json::from_value(serde_json::json!({ "inlay_hints": false })).unwrap();
+    assert!(!config.inlay_hints_enabled());
+}
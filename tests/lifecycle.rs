@@ -0,0 +1,843 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+struct Server {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Server {
+    fn start() -> Self {
+        Self::start_with(json!({}))
+    }
+
+    fn start_with(capabilities: Value) -> Self {
+        Self::initialize(
+            json!({ "processId": null, "rootUri": null, "capabilities": capabilities }),
+        )
+    }
+
+    /// Starts the server and initializes it with `params`.
+    fn initialize(params: Value) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_traverse-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut server = Server {
+            child,
+            stdin,
+            stdout,
+        };
+        server.send(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": params,
+        }));
+        server.response(1);
+        server.send(json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }));
+        server
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        let stdin = self.stdin.as_mut().unwrap();
+        write!(stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    /// Reads messages until the response to `id`, skipping notifications.
+    fn response(&mut self, id: i64) -> Value {
+        loop {
+            let message = self.message();
+            if message["id"] == id {
+                return message;
+            }
+        }
+    }
+
+    fn message(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn exit_code(mut self) -> Option<i32> {
+        self.stdin.take();
+        self.child.wait().unwrap().code()
+    }
+}
+
+#[test]
+fn test_exit_after_shutdown_succeeds() {
+    let mut server = Server::start();
+    server.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }));
+    assert_eq!(server.response(2)["result"], Value::Null);
+
+    // Requests between shutdown and exit are refused.
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "textDocument/completion",
+        "params": {
+            "textDocument": { "uri": "file:///workspace/Vault.sol" },
+            "position": { "line": 0, "character": 0 },
+        },
+    }));
+    assert_eq!(server.response(3)["error"]["code"], -32600);
+
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_exit_without_shutdown_fails() {
+    let mut server = Server::start();
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(1));
+}
+
+#[test]
+fn test_closed_connection_without_shutdown_fails() {
+    let server = Server::start();
+    assert_eq!(server.exit_code(), Some(1));
+}
+
+#[test]
+fn test_registers_source_file_watchers() {
+    let mut server = Server::start_with(json!({
+        "workspace": { "didChangeWatchedFiles": { "dynamicRegistration": true } },
+    }));
+    let request = loop {
+        let message = server.message();
+        if message["id"].is_string() {
+            break message;
+        }
+    };
+    assert_eq!(request["method"], "client/registerCapability");
+    let registration = &request["params"]["registrations"][0];
+    assert_eq!(registration["method"], "workspace/didChangeWatchedFiles");
+    let patterns: Vec<_> = registration["registerOptions"]["watchers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|watcher| watcher["globPattern"].as_str().unwrap())
+        .collect();
+    assert!(patterns.contains(&"**/*.sol"));
+
+    server.send(json!({ "jsonrpc": "2.0", "id": request["id"], "result": null }));
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "method": "workspace/didChangeWatchedFiles",
+        "params": { "changes": [{ "uri": "file:///workspace/Vault.sol", "type": 3 }] },
+    }));
+    server.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }));
+    server.response(2);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_initialization_options_reach_workers() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(
+        workspace.path().join("Vault.sol"),
+        "contract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    let output = workspace.path().join("out");
+    let mut server = Server::initialize(json!({
+        "processId": null,
+        "rootUri": null,
+        "capabilities": {},
+        "initializationOptions": { "output_dir": output, "max_response_bytes": 1 },
+    }));
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "traverse.listFuzzTargets.workspace",
+            "arguments": [{ "workspace_folder": workspace.path() }],
+        },
+    }));
+    let response = server.response(2);
+    let path = response["result"]["data"]["result_file"]["path"]
+        .as_str()
+        .unwrap();
+    assert!(std::path::Path::new(path).starts_with(output.canonicalize().unwrap()));
+    assert_eq!(
+        response["result"]["artifacts"],
+        json!([{ "name": "result_file", "format": "json", "path": path }])
+    );
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }));
+    server.response(3);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_project_config_file_is_loaded_and_reloaded() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(
+        workspace.path().join("Vault.sol"),
+        "contract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    let output = workspace.path().join("out");
+    let project_file = workspace.path().join("traverse.toml");
+    std::fs::write(
+        &project_file,
+        format!(
+            "output_dir = {:?}\nmax_response_bytes = 1\n",
+            output.to_str().unwrap()
+        ),
+    )
+    .unwrap();
+    let root = lsp_types::Url::from_file_path(workspace.path()).unwrap();
+    let mut server = Server::initialize(json!({
+        "processId": null,
+        "rootUri": root,
+        "capabilities": {},
+    }));
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "traverse.listFuzzTargets.workspace",
+            "arguments": [{ "workspace_folder": workspace.path() }],
+        },
+    }));
+    let response = server.response(2);
+    let path = response["result"]["data"]["result_file"]["path"]
+        .as_str()
+        .unwrap();
+    assert!(std::path::Path::new(path).starts_with(output.canonicalize().unwrap()));
+
+    std::fs::write(&project_file, "no_chunk = true\n").unwrap();
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "method": "workspace/didChangeWatchedFiles",
+        "params": { "changes": [{ "uri": lsp_types::Url::from_file_path(&project_file).unwrap(), "type": 2 }] },
+    }));
+    let message = loop {
+        let message = server.message();
+        if message["method"] == "window/showMessage" {
+            break message["params"]["message"].as_str().unwrap().to_string();
+        }
+    };
+    assert!(message.contains("Mermaid chunking turned off"));
+
+    // A broken file is reported and the previous settings stay.
+    std::fs::write(&project_file, "no_chunk = \n").unwrap();
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "method": "workspace/didChangeWatchedFiles",
+        "params": { "changes": [{ "uri": lsp_types::Url::from_file_path(&project_file).unwrap(), "type": 2 }] },
+    }));
+    let message = loop {
+        let message = server.message();
+        if message["method"] == "window/showMessage" {
+            break message["params"].clone();
+        }
+    };
+    assert_eq!(message["type"], 2);
+    assert!(message["message"]
+        .as_str()
+        .unwrap()
+        .contains("traverse.toml"));
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }));
+    server.response(3);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_command_arguments_override_configured_file_patterns() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::create_dir(workspace.path().join("src")).unwrap();
+    std::fs::write(
+        workspace.path().join("src/Vault.sol"),
+        "contract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    let mut server = Server::initialize(json!({
+        "processId": null,
+        "rootUri": null,
+        "capabilities": {},
+        "initializationOptions": { "exclude": ["src"] },
+    }));
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "traverse.listFuzzTargets.workspace",
+            "arguments": [{ "workspace_folder": workspace.path() }],
+        },
+    }));
+    let response = server.response(2);
+    assert!(response["result"].is_null());
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "traverse.listFuzzTargets.workspace",
+            "arguments": [{ "workspace_folder": workspace.path(), "exclude": [] }],
+        },
+    }));
+    let response = server.response(3);
+    assert_eq!(response["result"]["files"], 1);
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 4, "method": "shutdown" }));
+    server.response(4);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_requested_files_are_analyzed_with_their_imports() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::create_dir(workspace.path().join("src")).unwrap();
+    std::fs::write(
+        workspace.path().join("src/Vault.sol"),
+        "import \"./Math.sol\";\nimport \"./Gone.sol\";\ncontract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    std::fs::write(workspace.path().join("src/Math.sol"), "library Math {}\n").unwrap();
+    std::fs::write(
+        workspace.path().join("src/Other.sol"),
+        "contract Other {}\n",
+    )
+    .unwrap();
+    let mut server = Server::start();
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "traverse.listFuzzTargets.workspace",
+            "arguments": [{ "workspace_folder": workspace.path(), "files": ["src/Vault.sol"] }],
+        },
+    }));
+    let mut warnings = Vec::new();
+    let response = loop {
+        let message = server.message();
+        if message["method"] == "window/showMessage" && message["params"]["type"] == 2 {
+            warnings.push(message["params"]["message"].as_str().unwrap().to_string());
+        }
+        if message["id"] == 2 {
+            break message;
+        }
+    };
+    assert_eq!(response["result"]["files"], 2);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("1 import could not be resolved"));
+    assert!(warnings[0].contains("\"./Gone.sol\" in src/Vault.sol"));
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "traverse.listFuzzTargets.workspace",
+            "arguments": [{ "workspace_folder": workspace.path(), "files": ["src/Nope.sol"] }],
+        },
+    }));
+    let response = server.response(3);
+    assert_eq!(response["error"]["code"], -32602);
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 4, "method": "shutdown" }));
+    server.response(4);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_configuration_pushed_by_the_client() {
+    let mut server = Server::start();
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "method": "workspace/didChangeConfiguration",
+        "params": { "settings": { "traverse": { "no_chunk": true, "parse_threads": 2 } } },
+    }));
+    let message = loop {
+        let message = server.message();
+        if message["method"] == "window/showMessage" {
+            break message["params"]["message"].as_str().unwrap().to_string();
+        }
+    };
+    assert!(message.contains("Mermaid chunking turned off"));
+    assert!(message.contains("parse_threads take effect after a restart"));
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }));
+    server.response(2);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_configuration_pulled_from_the_client() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(
+        workspace.path().join("Vault.sol"),
+        "contract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    let output = workspace.path().join("out");
+    let mut server = Server::start_with(json!({ "workspace": { "configuration": true } }));
+    // Asked once at startup, and again when the client reports a change
+    // without the settings.
+    let id = loop {
+        let message = server.message();
+        if message["method"] == "workspace/configuration" {
+            assert_eq!(message["params"]["items"][0]["section"], "traverse");
+            break message["id"].clone();
+        }
+    };
+    server.send(json!({ "jsonrpc": "2.0", "id": id, "result": [null] }));
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "method": "workspace/didChangeConfiguration",
+        "params": { "settings": null },
+    }));
+    let id = loop {
+        let message = server.message();
+        if message["method"] == "workspace/configuration" {
+            break message["id"].clone();
+        }
+    };
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "result": [{ "output_dir": output, "max_response_bytes": 1 }],
+    }));
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": {
+            "command": "traverse.listFuzzTargets.workspace",
+            "arguments": [{ "workspace_folder": workspace.path() }],
+        },
+    }));
+    let response = server.response(2);
+    let path = response["result"]["data"]["result_file"]["path"]
+        .as_str()
+        .unwrap();
+    assert!(std::path::Path::new(path).starts_with(output.canonicalize().unwrap()));
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }));
+    server.response(3);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_doctor_clear_cache_and_code_lenses() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(
+        &source,
+        "contract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        workspace.path().join("remappings.txt"),
+        "solmate/=lib/solmate/src/\n",
+    )
+    .unwrap();
+    let output = workspace.path().join("out").join("diagrams");
+    let root = lsp_types::Url::from_file_path(workspace.path()).unwrap();
+    let mut server = Server::initialize(json!({
+        "processId": null,
+        "rootUri": root,
+        "capabilities": { "window": { "workDoneProgress": true } },
+        "initializationOptions": { "output_dir": output, "cache_budget_mb": 0 },
+    }));
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": { "command": "traverse.doctor", "arguments": [] },
+    }));
+    let report = server.response(2)["result"].clone();
+    let statuses: Vec<(&str, &str)> = report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| (c["name"].as_str().unwrap(), c["status"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("workspace", "ok"),
+            ("remappings", "warning"),
+            ("output_dir", "ok"),
+            ("cache", "warning"),
+            ("client_capabilities", "warning"),
+        ]
+    );
+    assert_eq!(report["ok"], true);
+    assert!(report["checks"][1]["detail"]
+        .as_str()
+        .unwrap()
+        .starts_with("solmate/=lib/solmate/src/"));
+    // The probe leaves no directory behind.
+    assert!(!workspace.path().join("out").exists());
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "workspace/executeCommand",
+        "params": { "command": "traverse.clearCache" },
+    }));
+    assert_eq!(server.response(3)["result"], json!({ "cleared": 0 }));
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 30,
+        "method": "workspace/executeCommand",
+        "params": { "command": "traverse.cleanOutputs", "arguments": [{ "keep_runs": 1 }] },
+    }));
+    assert_eq!(
+        server.response(30)["result"],
+        json!({ "removed": [], "freed_bytes": 0, "kept": 0, "kept_bytes": 0 })
+    );
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 4,
+        "method": "textDocument/codeLens",
+        "params": { "textDocument": { "uri": lsp_types::Url::from_file_path(&source).unwrap() } },
+    }));
+    let lenses = server.response(4)["result"].clone();
+    assert_eq!(lenses.as_array().unwrap().len(), 6);
+    assert_eq!(
+        lenses[4]["command"]["arguments"][0],
+        json!({
+            "workspace_folder": workspace.path(),
+            "contract": "Vault",
+            "function": "deposit",
+        })
+    );
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }));
+    server.response(5);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_code_actions_honor_requested_kinds() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(
+        &source,
+        "contract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    let mut server = Server::start();
+    let uri = lsp_types::Url::from_file_path(&source).unwrap();
+    let mut titles = |id: i64, only: Value| {
+        server.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "textDocument/codeAction",
+            "params": {
+                "textDocument": { "uri": uri },
+                "range": {
+                    "start": { "line": 1, "character": 8 },
+                    "end": { "line": 1, "character": 8 },
+                },
+                "context": { "diagnostics": [], "only": only },
+            },
+        }));
+        server.response(id)["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|action| action["title"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(titles(2, Value::Null).len(), 4);
+    assert_eq!(
+        titles(3, json!(["source"]))[0],
+        "Generate sequence diagram for Vault.deposit"
+    );
+    assert!(titles(4, json!(["quickfix"])).is_empty());
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }));
+    server.response(5);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_hover_reads_the_cached_call_graph() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(
+        &source,
+        "contract Vault {\n    uint256 total;\n\n    function deposit(uint256 amount) external {\n        total += amount;\n    }\n}\n",
+    )
+    .unwrap();
+    let root = lsp_types::Url::from_file_path(workspace.path()).unwrap();
+    let mut server = Server::initialize(json!({
+        "processId": null,
+        "rootUri": root,
+        "capabilities": {},
+    }));
+    let uri = lsp_types::Url::from_file_path(&source).unwrap();
+    let hover = |server: &mut Server, id: i64| {
+        server.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "textDocument/hover",
+            "params": {
+                "textDocument": { "uri": uri },
+                "position": { "line": 3, "character": 14 },
+            },
+        }));
+        server.response(id)["result"]["contents"]["value"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    assert!(hover(&mut server, 2).contains("not built yet"));
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "workspace/executeCommand",
+        "params": { "command": "traverse.generateCallGraph.workspace", "arguments": [] },
+    }));
+    let result = server.response(3)["result"].clone();
+    assert_eq!(result["success"], true);
+    assert_eq!(result["kind"], "traverse.generateCallGraph.workspace");
+    assert_eq!(result["format"], "dot");
+    assert_eq!(result["artifacts"][0]["content"], result["data"]["dot"]);
+    assert!(result["data"].get("run").is_none());
+    assert!(hover(&mut server, 4).contains("- **Writes:** `Vault.total`"));
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }));
+    server.response(5);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_definition_and_references_follow_the_cached_graph() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(
+        &source,
+        "contract Vault {\n    function deposit() external {\n        settle();\n    }\n\n    function settle() internal {}\n}\n",
+    )
+    .unwrap();
+    let root = lsp_types::Url::from_file_path(workspace.path()).unwrap();
+    let mut server = Server::initialize(json!({
+        "processId": null,
+        "rootUri": root,
+        "capabilities": {},
+    }));
+    let uri = lsp_types::Url::from_file_path(&source).unwrap();
+    let definition = |server: &mut Server, id: i64| {
+        server.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "textDocument/definition",
+            "params": {
+                "textDocument": { "uri": uri },
+                "position": { "line": 2, "character": 10 },
+            },
+        }));
+        server.response(id)["result"].clone()
+    };
+    assert_eq!(definition(&mut server, 2), serde_json::Value::Null);
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "workspace/executeCommand",
+        "params": { "command": "traverse.generateCallGraph.workspace", "arguments": [] },
+    }));
+    assert_eq!(server.response(3)["result"]["success"], true);
+    assert_eq!(
+        definition(&mut server, 4),
+        json!([{
+            "uri": uri,
+            "range": {
+                "start": { "line": 5, "character": 13 },
+                "end": { "line": 5, "character": 19 },
+            },
+        }])
+    );
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 6,
+        "method": "textDocument/references",
+        "params": {
+            "textDocument": { "uri": uri },
+            "position": { "line": 5, "character": 15 },
+            "context": { "includeDeclaration": false },
+        },
+    }));
+    assert_eq!(
+        server.response(6)["result"],
+        json!([{
+            "uri": uri,
+            "range": {
+                "start": { "line": 2, "character": 8 },
+                "end": { "line": 2, "character": 14 },
+            },
+        }])
+    );
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }));
+    server.response(5);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_workspace_symbol_searches_open_documents() {
+    let workspace = tempfile::tempdir().unwrap();
+    let root = lsp_types::Url::from_file_path(workspace.path()).unwrap();
+    let mut server = Server::initialize(json!({
+        "processId": null,
+        "rootUri": root,
+        "capabilities": {},
+    }));
+    let uri = lsp_types::Url::from_file_path(workspace.path().join("Vault.sol")).unwrap();
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {
+            "textDocument": {
+                "uri": uri,
+                "languageId": "solidity",
+                "version": 1,
+                "text": "contract Vault {\n    function deposit() external {}\n}\n",
+            },
+        },
+    }));
+
+    server.send(json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/symbol",
+        "params": { "query": "dep" },
+    }));
+    assert_eq!(
+        server.response(2)["result"],
+        json!([{
+            "name": "deposit",
+            "kind": 6,
+            "location": {
+                "uri": uri,
+                "range": {
+                    "start": { "line": 1, "character": 13 },
+                    "end": { "line": 1, "character": 20 },
+                },
+            },
+            "containerName": "Vault",
+        }])
+    );
+
+    server.send(json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }));
+    server.response(3);
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
+
+#[test]
+fn test_commands_pending_at_shutdown_report_shutting_down() {
+    let workspace = tempfile::tempdir().unwrap();
+    for i in 0..50 {
+        std::fs::write(
+            workspace.path().join(format!("Vault{}.sol", i)),
+            format!(
+                "contract Vault{} {{\n    function deposit(uint256 amount) external {{ withdraw(amount); }}\n    function withdraw(uint256 amount) public {{}}\n}}\n",
+                i
+            ),
+        )
+        .unwrap();
+    }
+    let mut server = Server::start();
+    let commands = [
+        "traverse.listFuzzTargets.workspace",
+        "traverse.generateCallGraph.workspace",
+        "traverse.generateSequenceDiagram.workspace",
+        "traverse.analyzeStorage.workspace",
+        "traverse.storageHeatmap.workspace",
+        "traverse.findClones.workspace",
+    ];
+    for (id, command) in (2..).zip(commands) {
+        server.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "workspace/executeCommand",
+            "params": {
+                "command": command,
+                "arguments": [{ "workspace_folder": workspace.path() }],
+            },
+        }));
+    }
+    // Each command announces itself just before it is queued.
+    let mut queued = 0;
+    while queued < commands.len() {
+        if server.message()["method"] == "window/showMessage" {
+            queued += 1;
+        }
+    }
+    server.send(json!({ "jsonrpc": "2.0", "id": 10, "method": "shutdown" }));
+
+    // Commands the shutdown stopped may be answered after the shutdown.
+    let mut errors = Vec::new();
+    let mut pending = commands.len() + 1;
+    while pending > 0 {
+        let message = server.message();
+        match message["id"].as_i64() {
+            Some(10) => assert!(message["result"].is_null()),
+            Some(_) if !message["error"].is_null() => errors.push(message["error"].clone()),
+            Some(_) => {}
+            None => continue,
+        }
+        pending -= 1;
+    }
+    assert!(!errors.is_empty());
+    for error in errors {
+        assert_eq!(error["code"], -32802);
+        assert_eq!(error["data"]["reason"], "shutting_down");
+    }
+
+    server.send(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    assert_eq!(server.exit_code(), Some(0));
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+use traverse_lsp::analysis::magic_numbers::{self, Inventory, LiteralContext};
+use traverse_lsp::analysis::model::{parse_sources, SourceUnit};
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+contract Vault {
+    uint256 public lastClaim;
+
+    function deposit(uint256 amount) external {
+        require(amount >= 0.1 ether);
+        require(amount <= 1_000_000e18);
+        uint256 fee = amount * 250 / 10_000;
+        lastClaim = block.timestamp;
+    }
+
+    function claim() external {
+        require(block.timestamp >= lastClaim + 7 days);
+        if (lastClaim == 0) {
+            return;
+        }
+        uint256 reward = address(this).balance / 2;
+    }
+}
+
+contract Staking {
+    function unstake(uint256 amount, uint256 start) external {
+        uint256 penalty = amount * 300 / 9_999;
+        require(block.timestamp > start + 604_800);
+        uint256 maturity = start + 365 days;
+        uint256 other = start + 360 days;
+    }
+}
+"#;
+
+fn inventory() -> Inventory {
+    let units = [SourceUnit {
+        path: PathBuf::from("Vault.sol"),
+        content: VAULT.to_string(),
+    }];
+    magic_numbers::inventory(&parse_sources(&units).unwrap())
+}
+
+#[test]
+fn test_literals_by_context() {
+    let inventory = inventory();
+    let found: Vec<(&str, &str, &str, LiteralContext)> = inventory
+        .literals
+        .iter()
+        .map(|l| {
+            (
+                l.function.as_str(),
+                l.literal.as_str(),
+                l.value.as_str(),
+                l.context,
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (
+                "deposit",
+                "0.1 ether",
+                "100000000000000000",
+                LiteralContext::Bound
+            ),
+            (
+                "deposit",
+                "1_000_000e18",
+                "1000000000000000000000000",
+                LiteralContext::Bound
+            ),
+            ("deposit", "250", "250", LiteralContext::FeeMath),
+            ("deposit", "10_000", "10000", LiteralContext::FeeMath),
+            ("claim", "7 days", "604800", LiteralContext::Time),
+            ("claim", "2", "2", LiteralContext::FeeMath),
+            ("unstake", "300", "300", LiteralContext::FeeMath),
+            ("unstake", "9_999", "9999", LiteralContext::FeeMath),
+            ("unstake", "604_800", "604800", LiteralContext::Time),
+            ("unstake", "365 days", "31536000", LiteralContext::Time),
+            ("unstake", "360 days", "31104000", LiteralContext::Time),
+        ]
+    );
+}
+
+#[test]
+fn test_near_misses() {
+    let inventory = inventory();
+    let misses: Vec<(LiteralContext, Vec<&str>, usize)> = inventory
+        .near_misses
+        .iter()
+        .map(|m| {
+            (
+                m.context,
+                m.values.iter().map(String::as_str).collect(),
+                m.sites.len(),
+            )
+        })
+        .collect();
+    // `7 days` and `604_800` are equal, so they are not a near miss.
+    assert_eq!(
+        misses,
+        vec![
+            (LiteralContext::FeeMath, vec!["9999", "10000"], 2),
+            (LiteralContext::Time, vec!["31104000", "31536000"], 2),
+        ]
+    );
+
+    let report = magic_numbers::render_magic_numbers(&inventory);
+    assert!(report.contains("## Near-Identical Values (2)"));
+    assert!(report.contains("| fee math | 9999, 10000 |"));
+    assert!(report.contains("### `Staking.unstake`"));
+}
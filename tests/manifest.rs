@@ -0,0 +1,205 @@
+use lsp_types::Url;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::config::{Config, GraphFilter, MermaidConfig};
+use traverse_lsp::output::manifest::{input_hash, to_hex, Manifest, MANIFEST_FILE};
+use traverse_lsp::watchdog;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+#[test]
+fn test_input_hash() {
+    let unit = |root: &str, path: &str, content: &str| SourceUnit {
+        path: Path::new(root).join(path),
+        content: content.to_string(),
+    };
+    let a = unit("/work", "A.sol", "contract A {}");
+    let b = unit("/work", "src/B.sol", "contract B {}");
+
+    let root = Path::new("/work");
+    let hash = input_hash(&[a.clone(), b.clone()], root);
+    assert_eq!(hash, input_hash(&[b.clone(), a.clone()], root));
+    assert_ne!(hash, input_hash(std::slice::from_ref(&a), root));
+    assert_ne!(
+        hash,
+        input_hash(
+            &[
+                unit("/work", "A.sol", "contract A {}contract B {}"),
+                unit("/work", "src/B.sol", "")
+            ],
+            root
+        )
+    );
+
+    // The same checkout elsewhere hashes the same.
+    let moved = [
+        unit("/home/ci/repo", "A.sol", "contract A {}"),
+        unit("/home/ci/repo", "src/B.sol", "contract B {}"),
+    ];
+    assert_eq!(hash, input_hash(&moved, Path::new("/home/ci/repo")));
+}
+
+#[test]
+fn test_manifest_lists_relative_paths_and_checksums() {
+    let dir = tempfile::tempdir().unwrap();
+    let note = dir.path().join("Vault.md");
+    std::fs::write(&note, "# Vault\n").unwrap();
+
+    let manifest = Manifest::build(dir.path(), std::slice::from_ref(&note), "feed").unwrap();
+    let path = manifest.write(dir.path()).unwrap();
+    assert_eq!(path, dir.path().join(MANIFEST_FILE));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(json["generator"], "traverse-lsp");
+    assert_eq!(json["generator_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(json["input_hash"], "feed");
+    assert_eq!(json["files"][0]["path"], "Vault.md");
+    assert_eq!(json["files"][0]["size"], 8);
+    assert_eq!(
+        json["files"][0]["sha256"],
+        to_hex(&Sha256::digest(b"# Vault\n")).as_str()
+    );
+}
+
+#[test]
+fn test_chunked_diagram_writes_manifest() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    std::fs::write(
+        &source,
+        "pragma solidity ^0.8.0;\n\ncontract Token {\n    uint256 total;\n\n    function mint(uint256 amount) external {\n        _add(amount);\n    }\n\n    function _add(uint256 amount) internal {\n        total += amount;\n    }\n}\n",
+    )
+    .unwrap();
+    let chunk_dir = workspace.path().join("chunks");
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateMermaidFlowchart {
+        uris: vec![Url::from_file_path(&source).unwrap()],
+        workspace_folder: workspace.path().to_path_buf(),
+        contract_name: None,
+        config: MermaidConfig {
+            chunk_dir: chunk_dir.clone(),
+            ..MermaidConfig::default()
+        },
+        filter: GraphFilter::default(),
+        progress: None,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap();
+    assert_eq!(response["is_chunked"], true);
+    let manifest_path = PathBuf::from(response["manifest"].as_str().unwrap());
+    assert_eq!(manifest_path, chunk_dir.join(MANIFEST_FILE));
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    let files = manifest["files"].as_array().unwrap();
+    assert!(files.iter().any(|f| f["path"] == "chunk_001.mmd"));
+    assert!(files.iter().any(|f| f["path"] == "index.mmd"));
+    for file in files {
+        let content = std::fs::read(chunk_dir.join(file["path"].as_str().unwrap())).unwrap();
+        assert_eq!(file["sha256"], to_hex(&Sha256::digest(&content)).as_str());
+    }
+
+    let sources = [SourceUnit {
+        path: source.clone(),
+        content: std::fs::read_to_string(&source).unwrap(),
+    }];
+    assert_eq!(
+        manifest["input_hash"],
+        input_hash(&sources, workspace.path()).as_str()
+    );
+}
+
+#[test]
+fn test_concurrent_chunked_diagrams_keep_the_manifest_in_step() {
+    let output = tempfile::tempdir().unwrap();
+    let chunk_dir = output.path().join("chunks");
+    // Two workspaces with diagrams of different sizes, rendered into the
+    // same chunk directory.
+    let workspaces: Vec<_> = [2, 40]
+        .into_iter()
+        .map(|functions| {
+            let workspace = tempfile::tempdir().unwrap();
+            let bodies: String = (0..functions)
+                .map(|i| format!("    function f{i}() external {{ g{i}(); }}\n    function g{i}() internal {{}}\n"))
+                .collect();
+            let source = workspace.path().join("Token.sol");
+            std::fs::write(&source, format!("contract Token {{\n{}}}\n", bodies)).unwrap();
+            (workspace, Url::from_file_path(&source).unwrap())
+        })
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    let pool = std::thread::spawn(move || {
+        watchdog::run(rx, None, 2, || {
+            watchdog::spawn_worker(GeneratorWorker::new(Config::default())?)
+        })
+        .unwrap()
+    });
+    for _ in 0..10 {
+        let replies: Vec<_> = workspaces
+            .iter()
+            .map(|(workspace, uri)| {
+                let (reply_tx, reply_rx) = oneshot::channel();
+                tx.send(GenerationRequest::GenerateMermaidFlowchart {
+                    uris: vec![uri.clone()],
+                    workspace_folder: workspace.path().to_path_buf(),
+                    contract_name: None,
+                    config: MermaidConfig {
+                        chunk_dir: chunk_dir.clone(),
+                        ..MermaidConfig::default()
+                    },
+                    filter: GraphFilter::default(),
+                    progress: None,
+                    cancel: Default::default(),
+                    tx: reply_tx,
+                })
+                .unwrap();
+                reply_rx
+            })
+            .collect();
+        for reply in replies {
+            let response: serde_json::Value =
+                serde_json::from_str(&reply.blocking_recv().unwrap().unwrap()).unwrap();
+            assert_eq!(response["is_chunked"], true);
+        }
+
+        // The manifest describes exactly the chunks of the last run.
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(chunk_dir.join(MANIFEST_FILE)).unwrap())
+                .unwrap();
+        let mut listed: Vec<_> = manifest["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| {
+                let content =
+                    std::fs::read(chunk_dir.join(file["path"].as_str().unwrap())).unwrap();
+                assert_eq!(file["sha256"], to_hex(&Sha256::digest(&content)).as_str());
+                file["path"].as_str().unwrap().to_string()
+            })
+            .collect();
+        listed.push(MANIFEST_FILE.to_string());
+        listed.sort();
+        let mut present: Vec<_> = std::fs::read_dir(&chunk_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        present.sort();
+        assert_eq!(listed, present);
+        // Nothing staged or swapped out is left behind.
+        assert_eq!(std::fs::read_dir(output.path()).unwrap().count(), 1);
+    }
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    pool.join().unwrap();
+}
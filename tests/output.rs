@@ -0,0 +1,316 @@
+use lsp_types::Url;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use tokio::sync::oneshot;
+use traverse_lsp::config::{Config, GraphFilter, MermaidConfig, SharedConfig};
+use traverse_lsp::output::large_result;
+use traverse_lsp::output::settings::{OutputLocation, OutputSettings};
+use traverse_lsp::output::{write_atomic, StagingDir};
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+#[test]
+fn test_write_atomic_replaces_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested/report.json");
+
+    write_atomic(&path, "first").unwrap();
+    write_atomic(&path, "second").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+    assert_eq!(
+        std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+        1
+    );
+}
+
+#[test]
+fn test_staging_dir_commit_and_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("chunks");
+    std::fs::create_dir(&target).unwrap();
+    std::fs::write(target.join("old.mmd"), "old").unwrap();
+
+    // Dropped without committing: the target is untouched.
+    let staging = StagingDir::new(&target).unwrap();
+    std::fs::write(staging.path().join("partial.mmd"), "partial").unwrap();
+    drop(staging);
+    assert!(target.join("old.mmd").exists());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    let staging = StagingDir::new(&target).unwrap();
+    std::fs::write(staging.path().join("new.mmd"), "new").unwrap();
+    assert_eq!(staging.commit().unwrap(), target);
+    assert!(target.join("new.mmd").exists());
+    assert!(!target.join("old.mmd").exists());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+// Whether a process has exited is only read on Linux; elsewhere its
+// leftovers wait until they are a day old.
+#[cfg(target_os = "linux")]
+#[test]
+fn test_staging_dir_removes_leftovers_of_interrupted_runs() {
+    let mut child = std::process::Command::new("true").spawn().unwrap();
+    let pid = child.id();
+    child.wait().unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("chunks");
+    let leftover = dir.path().join(format!(".chunks.staging-{}-0", pid));
+    std::fs::create_dir(&leftover).unwrap();
+    std::fs::write(leftover.join("chunk_001.mmd"), "half").unwrap();
+
+    let staging = StagingDir::new(&target).unwrap();
+    assert!(!leftover.exists());
+    staging.commit().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_staging_dir_keeps_directories_of_running_servers() {
+    let mut other = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let target = dir.path().join("chunks");
+    let in_progress = dir.path().join(format!(".chunks.staging-{}-0", other.id()));
+    std::fs::create_dir(&in_progress).unwrap();
+
+    let staging = StagingDir::new(&target).unwrap();
+    staging.commit().unwrap();
+    assert!(in_progress.exists());
+    other.kill().unwrap();
+    other.wait().unwrap();
+}
+
+#[test]
+fn test_output_settings_apply_to_later_requests() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    std::fs::write(
+        &source,
+        "pragma solidity ^0.8.0;\n\ncontract Token {\n    uint256 total;\n\n    function mint(uint256 amount) external {\n        _add(amount);\n    }\n\n    function _add(uint256 amount) internal {\n        total += amount;\n    }\n}\n",
+    )
+    .unwrap();
+    let first = OutputLocation {
+        dir: workspace.path().join("first"),
+        no_chunk: false,
+        chunk_dir: None,
+    };
+    let second = OutputLocation {
+        dir: workspace.path().join("second"),
+        no_chunk: false,
+        chunk_dir: None,
+    };
+    let settings = Arc::new(OutputSettings::new(first.clone()));
+
+    let (tx, rx) = mpsc::channel();
+    let worker = GeneratorWorker::new(Config::default())
+        .unwrap()
+        .with_output_settings(settings.clone());
+    let thread = std::thread::spawn(move || worker.run(rx));
+    let generate = || {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(GenerationRequest::GenerateMermaidFlowchart {
+            uris: vec![Url::from_file_path(&source).unwrap()],
+            workspace_folder: workspace.path().to_path_buf(),
+            contract_name: None,
+            config: MermaidConfig::default(),
+            filter: GraphFilter::default(),
+            progress: None,
+            cancel: Default::default(),
+            tx: reply_tx,
+        })
+        .unwrap();
+        let response = reply_rx.blocking_recv().unwrap().unwrap();
+        serde_json::from_str::<serde_json::Value>(&response).unwrap()
+    };
+
+    let response = generate();
+    assert_eq!(response["chunk_dir"], first.chunk_dir().to_str().unwrap());
+
+    assert_eq!(settings.update(second.clone()), Some(first.clone()));
+    assert_eq!(settings.update(second.clone()), None);
+    let response = generate();
+    assert_eq!(response["chunk_dir"], second.chunk_dir().to_str().unwrap());
+    // Earlier output stays where it was written.
+    assert!(first.chunk_dir().join("chunk_001.mmd").exists());
+    assert!(second.chunk_dir().join("chunk_001.mmd").exists());
+
+    settings.update(OutputLocation {
+        no_chunk: true,
+        ..second
+    });
+    let response = generate();
+    assert_eq!(response["is_chunked"], false);
+
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    thread.join().unwrap();
+}
+
+#[test]
+fn test_chunk_dir_and_chunk_threshold_settings() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    std::fs::write(
+        &source,
+        "contract Token {\n    function mint(uint256 amount) external {\n        _add(amount);\n    }\n\n    function _add(uint256 amount) internal {}\n}\n",
+    )
+    .unwrap();
+    let config = Config {
+        output_dir: Some(workspace.path().join("out")),
+        chunk_dir: Some(PathBuf::from("diagrams")),
+        ..Config::default()
+    };
+    let shared = Arc::new(SharedConfig::new(config.clone()));
+
+    let (tx, rx) = mpsc::channel();
+    let worker = GeneratorWorker::new(config.clone())
+        .unwrap()
+        .with_shared_config(shared.clone());
+    let thread = std::thread::spawn(move || worker.run(rx));
+    let generate = || {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(GenerationRequest::GenerateMermaidFlowchart {
+            uris: vec![Url::from_file_path(&source).unwrap()],
+            workspace_folder: workspace.path().to_path_buf(),
+            contract_name: None,
+            config: MermaidConfig::default(),
+            filter: GraphFilter::default(),
+            progress: None,
+            cancel: Default::default(),
+            tx: reply_tx,
+        })
+        .unwrap();
+        let response = reply_rx.blocking_recv().unwrap().unwrap();
+        serde_json::from_str::<serde_json::Value>(&response).unwrap()
+    };
+
+    let chunk_dir = workspace.path().join("out/diagrams");
+    assert_eq!(OutputLocation::from_config(&config).chunk_dir(), chunk_dir);
+    let response = generate();
+    assert_eq!(response["is_chunked"], true);
+    assert_eq!(response["chunk_dir"], chunk_dir.to_str().unwrap());
+
+    // A small graph is written as one file below `chunk_threshold_nodes`.
+    shared.update(Config {
+        chunk_threshold_nodes: Some(100),
+        ..config.clone()
+    });
+    let response = generate();
+    assert_eq!(response["is_chunked"], false);
+    shared.update(Config {
+        chunk_threshold_nodes: Some(1),
+        ..config
+    });
+    assert_eq!(generate()["is_chunked"], true);
+
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    thread.join().unwrap();
+}
+
+#[test]
+fn test_explicit_chunk_dir_ignores_output_dir() {
+    let location = OutputLocation {
+        dir: PathBuf::from("/tmp/out"),
+        no_chunk: false,
+        chunk_dir: None,
+    };
+    let config = MermaidConfig::default().with_output(&location);
+    assert_eq!(config.chunk_dir, location.chunk_dir());
+
+    let config = MermaidConfig {
+        chunk_dir: PathBuf::from("/tmp/custom"),
+        ..MermaidConfig::default()
+    }
+    .with_output(&location);
+    assert_eq!(config.chunk_dir, PathBuf::from("/tmp/custom"));
+}
+
+#[test]
+fn test_chunk_dir_setting_is_relative_to_output_dir() {
+    let config = |chunk_dir: &str| Config {
+        output_dir: Some(PathBuf::from("/tmp/out")),
+        chunk_dir: Some(PathBuf::from(chunk_dir)),
+        ..Config::default()
+    };
+    let location = OutputLocation::from_config(&config("diagrams"));
+    assert_eq!(location.chunk_dir(), PathBuf::from("/tmp/out/diagrams"));
+    let location = OutputLocation::from_config(&config("/tmp/chunks"));
+    assert_eq!(location.chunk_dir(), PathBuf::from("/tmp/chunks"));
+    assert_eq!(
+        MermaidConfig::default().with_output(&location).chunk_dir,
+        PathBuf::from("/tmp/chunks")
+    );
+}
+
+#[test]
+fn test_large_result_is_written_to_a_file() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    std::fs::write(
+        &source,
+        "contract Token {\n    function mint(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    let output = workspace.path().join("out");
+    let config = Config {
+        max_response_bytes: Some(64),
+        ..Config::default()
+    };
+    let settings = Arc::new(OutputSettings::new(OutputLocation {
+        dir: output.clone(),
+        no_chunk: false,
+        chunk_dir: None,
+    }));
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::ListFuzzTargets {
+        uris: vec![Url::from_file_path(&source).unwrap()],
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(config)
+        .unwrap()
+        .with_output_settings(settings)
+        .run(rx);
+
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap();
+    let file = &response["result_file"];
+    let path = PathBuf::from(file["path"].as_str().unwrap());
+    assert!(path.starts_with(
+        output
+            .join(large_result::RESULTS_DIR)
+            .canonicalize()
+            .unwrap()
+    ));
+    assert_eq!(path.extension().unwrap(), "json");
+    assert_eq!(
+        Url::parse(file["uri"].as_str().unwrap()).unwrap(),
+        Url::from_file_path(&path).unwrap()
+    );
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.len() > 64);
+    assert_eq!(file["bytes"], content.len());
+    assert!(content.starts_with(file["preview"].as_str().unwrap()));
+    assert!(content.contains("mint"));
+}
+
+#[test]
+fn test_large_result_file_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let text = large_result::write("digraph {}", dir.path(), "call graph generation").unwrap();
+    let name = text.path.file_name().unwrap().to_str().unwrap();
+    assert!(name.starts_with("call-graph-generation-"));
+    assert!(name.ends_with(".txt"));
+
+    let long = "x".repeat(large_result::PREVIEW_CHARS * 2);
+    let json = large_result::write(&format!("\"{}\"", long), dir.path(), "audit report").unwrap();
+    assert!(json.path.to_str().unwrap().ends_with(".json"));
+    assert_eq!(json.preview.chars().count(), large_result::PREVIEW_CHARS);
+    assert_eq!(json.bytes, long.len() + 2);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::run_worker_request;
+use std::path::PathBuf;
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+use traverse_lsp::analysis::pause_coverage::{self, PauseCoverage};
+use traverse_lsp::GenerationRequest;
+
+const SOURCES: &str = r#"pragma solidity ^0.8.0;
+
+import "@openzeppelin/contracts/security/Pausable.sol";
+import "@openzeppelin/contracts/access/Ownable.sol";
+
+contract Market is Pausable, Ownable {
+    function pause() external onlyOwner {
+        _pause();
+    }
+
+    function unpause() external onlyOwner {
+        _unpause();
+    }
+
+    function buy() external whenNotPaused {}
+
+    function sell(uint256 amount) external {
+        _settle(amount);
+    }
+
+    function _settle(uint256 amount) internal whenNotPaused {}
+
+    function claim() external {}
+
+    function setFee(uint256 fee) external onlyOwner {}
+
+    function rescue() external whenPaused onlyOwner {}
+
+    function price() external view returns (uint256) {
+        return 1;
+    }
+}
+
+contract Bridge {
+    bool public halted;
+
+    function halt() external {
+        halted = true;
+    }
+
+    function relay() external {}
+
+    function send() external {
+        require(!halted, "halted");
+    }
+}
+
+contract Plain {
+    function ping() external {}
+}
+"#;
+
+fn analyze() -> Vec<PauseCoverage> {
+    let units = [SourceUnit {
+        path: PathBuf::from("Market.sol"),
+        content: SOURCES.to_string(),
+    }];
+    let parsed = parse_sources(&units).unwrap();
+    let model = SourceModel::from_parsed(&parsed);
+    pause_coverage::analyze(&parsed, &model)
+}
+
+fn entry_points(coverage: &PauseCoverage) -> Vec<(&str, Option<&str>, Vec<&str>)> {
+    coverage
+        .entry_points
+        .iter()
+        .map(|e| {
+            (
+                e.function.as_str(),
+                e.covered_by.as_deref(),
+                e.restricted_by.iter().map(String::as_str).collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_pausable_inheritance_and_guarded_helpers() {
+    let contracts = analyze();
+    let names: Vec<&str> = contracts.iter().map(|c| c.contract.as_str()).collect();
+    assert_eq!(names, vec!["Market", "Bridge"]);
+
+    let market = &contracts[0];
+    assert_eq!(
+        market.pause_functions,
+        vec!["Market.pause", "Market.unpause"]
+    );
+    assert_eq!(market.paused_only, vec!["Market.rescue"]);
+    assert_eq!(
+        entry_points(market),
+        vec![
+            ("Market.claim", None, vec![]),
+            ("Market.setFee", None, vec!["onlyOwner"]),
+            ("Market.buy", Some("whenNotPaused"), vec![]),
+            ("Market.sell", Some("_settle()"), vec![]),
+        ]
+    );
+    let uncovered: Vec<&str> = market.uncovered().map(|e| e.function.as_str()).collect();
+    assert_eq!(uncovered, vec!["Market.claim", "Market.setFee"]);
+}
+
+#[test]
+fn test_custom_halt_flag() {
+    let contracts = analyze();
+    let bridge = &contracts[1];
+    assert_eq!(bridge.pause_functions, vec!["Bridge.halt"]);
+    assert!(bridge.paused_only.is_empty());
+    assert_eq!(
+        entry_points(bridge),
+        vec![
+            ("Bridge.relay", None, vec![]),
+            ("Bridge.send", Some("!halted"), vec![]),
+        ]
+    );
+
+    let report = pause_coverage::render_pause_coverage(&contracts);
+    assert!(
+        report.contains("**Pausable contracts:** 2 | **Entry points a pause does not stop:** 3")
+    );
+    assert!(report.contains("## Market (2 of 4 entry points covered)"));
+    assert!(report.contains("| `Market.claim` | 23 | **not covered** | anyone |"));
+    assert!(report.contains("| `Market.sell` | 17 | `_settle()` | anyone |"));
+    assert!(report.contains("- **Only while paused:** `Market.rescue`"));
+}
+
+#[test]
+fn test_pause_coverage_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Market.sol");
+    std::fs::write(&path, SOURCES).unwrap();
+
+    let response = run_worker_request(|tx| GenerationRequest::PauseCoverage {
+        uris: vec![lsp_types::Url::from_file_path(&path).unwrap()],
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(response["contracts"].as_array().unwrap().len(), 2);
+    assert_eq!(response["uncovered"], 3);
+    assert_eq!(
+        response["contracts"][0]["entry_points"][0]["function"],
+        "Market.claim"
+    );
+    assert!(response["report"]
+        .as_str()
+        .unwrap()
+        .starts_with("# Pause Coverage"));
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use common::run_git;
+use lsp_types::Url;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_lsp::config::Config;
+use traverse_lsp::git;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const BASE_VAULT: &str = r#"pragma solidity ^0.8.0;
+
+contract Vault {
+    uint256 public total;
+    address public owner;
+
+    function deposit(uint256 amount) external {
+        total += amount;
+    }
+
+    function withdraw(uint256 amount) external {
+        total -= amount;
+    }
+
+    function sweep() external {
+        total = 0;
+    }
+}
+"#;
+
+const HEAD_VAULT: &str = r#"pragma solidity ^0.8.0;
+
+interface IOracle {
+    function price() external view returns (uint256);
+}
+
+contract Oracle is IOracle {
+    function price() external view returns (uint256) {
+        return 1;
+    }
+}
+
+contract Vault {
+    uint256 public total;
+    uint128 public fee;
+    address public owner;
+    Oracle oracle;
+
+    function deposit(uint256 amount) external {
+        uint256 price = oracle.price();
+        total += amount * price;
+    }
+
+    function withdraw(uint256 amount) external {
+        total -= amount;
+    }
+
+    function pause() external {
+        owner = address(0);
+    }
+}
+"#;
+
+#[test]
+fn test_pr_comment_against_base_ref() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Vault.sol");
+    std::fs::write(&source, BASE_VAULT).unwrap();
+    run_git(workspace.path(), &["init", "-q"]);
+    run_git(workspace.path(), &["add", "-A"]);
+    run_git(workspace.path(), &["commit", "-q", "-m", "base"]);
+    run_git(workspace.path(), &["tag", "base"]);
+    std::fs::write(&source, HEAD_VAULT).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GeneratePrComment {
+        uris: vec![Url::from_file_path(&source).unwrap()],
+        workspace_folder: workspace.path().to_path_buf(),
+        base_ref: "base".to_string(),
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap();
+    let changes = &response["changes"];
+
+    let entry_points: Vec<(&str, &str, &str)> = changes["entry_points"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["contract"].as_str().unwrap(),
+                e["signature"].as_str().unwrap(),
+                e["change"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert!(entry_points.contains(&("Vault", "deposit(uint256)", "modified")));
+    assert!(entry_points.contains(&("Vault", "pause()", "added")));
+    assert!(entry_points.contains(&("Vault", "sweep()", "removed")));
+    assert!(entry_points.contains(&("Oracle", "price()", "added")));
+    assert!(!entry_points.iter().any(|e| e.1 == "withdraw(uint256)"));
+
+    let calls = changes["new_external_calls"].as_array().unwrap();
+    assert!(calls
+        .iter()
+        .any(|c| c["caller"] == "Vault.deposit" && c["callee"] == "Oracle.price"));
+
+    let storage = changes["storage"].as_array().unwrap();
+    assert_eq!(storage.len(), 1);
+    assert_eq!(storage[0]["contract"], "Vault");
+
+    let report = response["report"].as_str().unwrap();
+    assert!(report.starts_with("## Traverse: changes since `base`"));
+    assert!(report.contains("| `Vault` | `pause()` | added |"));
+    assert!(report.contains("- `Vault.deposit` → `Oracle.price`"));
+    assert!(report.contains("### Storage Layout"));
+}
+
+#[test]
+fn test_pr_comment_without_changes() {
+    use traverse_lsp::analysis::pr_comment::{render_pr_comment, PrChanges};
+
+    let report = render_pr_comment("main", &PrChanges::default());
+    assert!(report.contains("No changes to entry points, external calls or storage layout."));
+    assert!(!report.contains('|'));
+}
+
+#[test]
+fn test_git_rejects_option_like_refs() {
+    let workspace = tempfile::tempdir().unwrap();
+    run_git(workspace.path(), &["init", "-q"]);
+
+    let error = git::sources_at(workspace.path(), "--output=/tmp/x").unwrap_err();
+    assert!(error.to_string().contains("Invalid git revision"));
+    assert!(git::resolve_commit(workspace.path(), "no-such-branch").is_err());
+}
@@ -0,0 +1,76 @@
+use lsp_types::Url;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_lsp::config::{Config, DotConfig, GraphFilter};
+use traverse_lsp::output::preview::{self, PREVIEW_LINES};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+#[test]
+fn test_head_keeps_the_first_lines() {
+    let short = "digraph {\n  a -> b\n}";
+    assert_eq!(preview::head(short), short);
+
+    let long: Vec<String> = (0..PREVIEW_LINES * 2)
+        .map(|i| format!("line {}", i))
+        .collect();
+    let head = preview::head(&long.join("\n"));
+    assert_eq!(head.lines().count(), PREVIEW_LINES);
+    assert_eq!(
+        head.lines().last(),
+        Some(format!("line {}", PREVIEW_LINES - 1).as_str())
+    );
+}
+
+#[test]
+fn test_contract_summary() {
+    let source = "contract Vault {\n    modifier onlyOwner() { _; }\n    function deposit() external {}\n    function withdraw() external onlyOwner {}\n}\ncontract Oracle {\n    function price() external returns (uint256) { return 1; }\n}\n";
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(source)
+        .unwrap();
+    assert_eq!(
+        preview::contracts(&graph),
+        "Oracle: 1 function\nVault: 3 functions"
+    );
+}
+
+#[test]
+fn test_diagram_response_carries_preview() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Vault.sol");
+    let mut source = String::from("contract Vault {\n");
+    for i in 0..40 {
+        source.push_str(&format!(
+            "    function f{}() public {{ f{}(); }}\n",
+            i,
+            i + 1
+        ));
+    }
+    source.push_str("    function f40() public {}\n}\n");
+    std::fs::write(&path, source).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateCallGraphDiagram {
+        uris: vec![Url::from_file_path(&path).unwrap()],
+        contract_name: None,
+        dot_config: DotConfig::default(),
+        filter: GraphFilter::default(),
+        coverage_file: None,
+        progress: None,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+
+    let response: serde_json::Value =
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap();
+    let dot = response["dot"].as_str().unwrap();
+    let head = response["preview"].as_str().unwrap();
+    assert!(dot.lines().count() > PREVIEW_LINES);
+    assert_eq!(head.lines().count(), PREVIEW_LINES);
+    assert!(dot.starts_with(head));
+}
@@ -0,0 +1,137 @@
+mod common;
+
+use common::run_worker_request;
+use lsp_types::Url;
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+use traverse_lsp::analysis::privilege_paths::{
+    find, render_mermaid, render_privilege_paths, PathKind, Privileges,
+    DEFAULT_PRIVILEGED_MODIFIERS,
+};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::GenerationRequest;
+
+const TREASURY: &str = r#"
+contract Treasury {
+    address owner;
+
+    modifier onlyOwner() {
+        require(msg.sender == owner);
+        _;
+    }
+
+    function withdraw(uint256 amount) public onlyOwner {
+        _send(amount);
+    }
+
+    function emergency() public {
+        withdraw(1);
+    }
+
+    function sweep() external {
+        _collect();
+    }
+
+    function _collect() internal {
+        _send(1);
+    }
+
+    function _send(uint256 amount) internal {}
+}
+
+contract Keeper {
+    Treasury treasury;
+
+    function poke() external {
+        treasury.withdraw(1);
+    }
+}
+"#;
+
+fn privileges(functions: &[&str]) -> Privileges {
+    Privileges {
+        modifiers: DEFAULT_PRIVILEGED_MODIFIERS
+            .iter()
+            .map(|m| m.to_string())
+            .collect(),
+        functions: functions.iter().map(|f| f.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_paths_into_privileged_functions() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(TREASURY)
+        .unwrap();
+    let sources = vec![SourceUnit {
+        path: "Treasury.sol".into(),
+        content: TREASURY.to_string(),
+    }];
+    let model = SourceModel::from_parsed(&parse_sources(&sources).unwrap());
+    let paths = find(&graph, &model, &privileges(&["Treasury._send"]));
+
+    let found: Vec<(PathKind, &str, &str)> = paths
+        .iter()
+        .map(|p| (p.kind, p.entry_point.as_str(), p.target.as_str()))
+        .collect();
+    // `emergency` calls a guarded function of its own contract, whose
+    // modifier still checks the original caller.
+    assert_eq!(
+        found,
+        vec![
+            (PathKind::Unguarded, "Treasury.sweep", "Treasury._send"),
+            (PathKind::CrossContract, "Keeper.poke", "Treasury.withdraw"),
+        ]
+    );
+    assert_eq!(
+        paths[0].path,
+        vec!["Treasury.sweep", "Treasury._collect", "Treasury._send"]
+    );
+    assert!(paths[0].target_guards.is_empty());
+    assert_eq!(paths[1].target_guards, vec!["onlyOwner"]);
+
+    let mermaid = render_mermaid(&graph, &paths);
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("[\"Treasury._collect\"]"));
+    assert!(mermaid.contains("classDef privileged"));
+
+    let report = render_privilege_paths(&paths);
+    assert!(report.contains(
+        "| unguarded | Treasury.sweep | Treasury._send | - | Treasury.sweep → Treasury._collect → Treasury._send |"
+    ));
+}
+
+#[test]
+fn test_no_paths_without_privileged_functions() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(TREASURY)
+        .unwrap();
+    let paths = find(&graph, &SourceModel::default(), &privileges(&[]));
+    assert!(paths.is_empty());
+    assert!(render_privilege_paths(&paths).contains("No unprivileged entry point"));
+}
+
+#[test]
+fn test_privilege_paths_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Treasury.sol");
+    std::fs::write(&path, TREASURY).unwrap();
+
+    let response = run_worker_request(|tx| GenerationRequest::PrivilegePaths {
+        uris: vec![Url::from_file_path(&path).unwrap()],
+        privileges: privileges(&["_send"]),
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(response["paths"].as_array().unwrap().len(), 2);
+    assert_eq!(response["paths"][0]["kind"], "unguarded");
+    assert!(response["mermaid"]
+        .as_str()
+        .unwrap()
+        .starts_with("flowchart LR"));
+    assert!(response["report"]
+        .as_str()
+        .unwrap()
+        .starts_with("# Privilege Escalation Paths"));
+}
@@ -0,0 +1,150 @@
+use std::path::{Path, PathBuf};
+use traverse_graph::cg::NodeType;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::analysis::{run_configured_audit, unit_mismatch};
+use traverse_lsp::config::Config;
+use traverse_lsp::profiles::{self, Dialect, Profile};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const LEGACY_VAULT: &str = r#"pragma solidity ^0.4.24;
+
+contract Vault {
+    address owner;
+    uint256 unlockAt;
+
+    function Vault() public {
+        owner = msg.sender;
+        unlockAt = now + 7;
+    }
+
+    function lock() public {
+        unlockAt = now + 30;
+    }
+}
+"#;
+
+const CLOCK: &str = r#"
+contract Clock {
+    uint256 unlockAt;
+
+    function set(uint256 now) external {
+        unlockAt = now + 7;
+    }
+}
+"#;
+
+fn legacy_profile() -> Vec<Profile> {
+    vec![Profile {
+        paths: vec!["legacy".to_string()],
+        solidity: "0.4".to_string(),
+    }]
+}
+
+fn unit(path: &str, content: &str) -> SourceUnit {
+    SourceUnit {
+        path: PathBuf::from(path),
+        content: content.to_string(),
+    }
+}
+
+#[test]
+fn test_dialect_for_version() {
+    let legacy = Dialect::for_version("^0.4.24").unwrap();
+    assert!(legacy.named_constructors && legacy.now_is_timestamp);
+    let middle = Dialect::for_version(">=0.5.0 <0.7.0").unwrap();
+    assert!(!middle.named_constructors && middle.now_is_timestamp);
+    let modern = Dialect::for_version("0.8").unwrap();
+    assert!(!modern.named_constructors && !modern.now_is_timestamp);
+    assert_eq!(Dialect::for_version("latest"), None);
+}
+
+#[test]
+fn test_dialect_of_file() {
+    let profiles = vec![
+        Profile {
+            paths: vec!["src/v1/**".to_string()],
+            solidity: "0.5".to_string(),
+        },
+        Profile {
+            paths: vec!["legacy".to_string()],
+            solidity: "0.4".to_string(),
+        },
+    ];
+    let dialect =
+        |path: &str, content: &str| profiles::dialect(&profiles, Path::new(path), content);
+
+    // The profile wins over the pragma.
+    assert!(dialect("/ws/legacy/token/Token.sol", "pragma solidity ^0.8.0;").named_constructors);
+    let v1 = dialect("/ws/packages/core/src/v1/Pool.sol", "");
+    assert!(!v1.named_constructors && v1.now_is_timestamp);
+    assert!(!dialect("/ws/src/v2/Pool.sol", "pragma solidity 0.8.20;").now_is_timestamp);
+    assert_eq!(dialect("/ws/src/Pool.sol", ""), Dialect::default());
+}
+
+#[test]
+fn test_named_constructor_becomes_constructor() {
+    let mut vault = unit("/ws/legacy/Vault.sol", LEGACY_VAULT);
+    profiles::apply(&legacy_profile(), &mut vault);
+    assert_eq!(vault.content.len(), LEGACY_VAULT.len());
+    assert_eq!(vault.content.lines().count(), LEGACY_VAULT.lines().count());
+    assert!(vault
+        .content
+        .contains("    constructor   () public {\n        owner = msg.sender;"));
+
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(std::slice::from_ref(&vault))
+        .unwrap();
+    let vault_nodes: Vec<(&NodeType, &str)> = graph
+        .nodes
+        .iter()
+        .filter(|n| n.contract_name.as_deref() == Some("Vault"))
+        .map(|n| (&n.node_type, n.name.as_str()))
+        .filter(|(kind, _)| matches!(kind, NodeType::Function | NodeType::Constructor))
+        .collect();
+    assert_eq!(vault_nodes.len(), 2, "{:?}", vault_nodes);
+    assert!(vault_nodes.contains(&(&NodeType::Constructor, "Vault")));
+    assert!(vault_nodes.contains(&(&NodeType::Function, "lock")));
+
+    // Outside the profile, and without a pragma, the function stays.
+    let mut elsewhere = unit(
+        "/ws/src/Vault.sol",
+        &LEGACY_VAULT.replace("pragma solidity ^0.4.24;", ""),
+    );
+    let before = elsewhere.content.clone();
+    profiles::apply(&legacy_profile(), &mut elsewhere);
+    assert_eq!(elsewhere.content, before);
+}
+
+#[test]
+fn test_now_follows_the_dialect() {
+    let config = Config {
+        profiles: legacy_profile(),
+        ..Config::default()
+    };
+    let findings = |units: &[SourceUnit]| {
+        run_configured_audit(units, &config)
+            .unwrap()
+            .into_iter()
+            .filter(|f| f.rule == unit_mismatch::RULE)
+            .map(|f| (f.contract.unwrap(), f.function.unwrap()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut vault = unit("/ws/legacy/Vault.sol", LEGACY_VAULT);
+    profiles::apply(&config.profiles, &mut vault);
+    let mut legacy = findings(&[vault]);
+    legacy.sort();
+    assert_eq!(
+        legacy,
+        vec![
+            ("Vault".to_string(), "constructor".to_string()),
+            ("Vault".to_string(), "lock".to_string()),
+        ]
+    );
+
+    // `now` is an ordinary name in 0.7 and later.
+    let modern = format!("pragma solidity ^0.8.0;\n{}", CLOCK);
+    assert!(findings(&[unit("/ws/src/Clock.sol", &modern)]).is_empty());
+    assert_eq!(findings(&[unit("/ws/legacy/Clock.sol", CLOCK)]).len(), 1);
+}
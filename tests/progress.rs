@@ -0,0 +1,174 @@
+mod common;
+
+use common::{command_request, progress_values, InMemoryServer};
+use lsp_server::{Message, Response};
+use lsp_types::{NumberOrString, WorkDoneProgress};
+use std::thread;
+use std::time::Duration;
+use traverse_lsp::commands;
+use traverse_lsp::handlers::execute_command;
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+import "./Token.sol";
+
+contract Vault {
+    Token token;
+
+    function deposit(uint256 amount) external {
+        token.transfer(msg.sender, amount);
+    }
+}
+"#;
+
+const TOKEN: &str = r#"pragma solidity ^0.8.0;
+
+contract Token {
+    function transfer(address to, uint256 amount) external returns (bool) {
+        return true;
+    }
+}
+"#;
+
+/// Messages sent to the client up to and including the response. A
+/// `window/workDoneProgress/create` request is answered with success when
+/// `accept` is set and with an error otherwise, after checking that no
+/// progress begins before the answer.
+fn until_response(server: &InMemoryServer, accept: bool) -> Vec<Message> {
+    let mut messages = Vec::new();
+    loop {
+        let message = server.next_message();
+        if let Message::Request(request) = &message {
+            assert_eq!(request.method, "window/workDoneProgress/create");
+            thread::sleep(Duration::from_millis(100));
+            assert!(server.client.receiver.is_empty());
+            let response = if accept {
+                Response::new_ok(request.id.clone(), ())
+            } else {
+                Response::new_err(request.id.clone(), -32603, "no progress UI".to_string())
+            };
+            assert!(execute_command::is_progress_response(&response));
+            execute_command::progress_created(response);
+        }
+        let done = matches!(message, Message::Response(_));
+        messages.push(message);
+        if done {
+            return messages;
+        }
+    }
+}
+
+fn run_command(command: &str, token: Option<&str>, accept: bool) -> Vec<Message> {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), VAULT).unwrap();
+    std::fs::write(workspace.path().join("Token.sol"), TOKEN).unwrap();
+
+    let server = InMemoryServer::start();
+    let request = command_request(
+        1,
+        command,
+        vec![serde_json::json!({
+            "workspace_folder": workspace.path().to_str().unwrap(),
+            "no_chunk": true,
+        })],
+        token,
+    );
+    let command = {
+        let sender = server.server.sender.clone();
+        let generator_tx = server.generator_tx.clone();
+        thread::spawn(move || {
+            execute_command(request, &sender, &generator_tx, Default::default()).unwrap();
+        })
+    };
+    let messages = until_response(&server, accept);
+    command.join().unwrap();
+    server.finish();
+    messages
+}
+
+#[test]
+fn test_call_graph_reports_progress_on_client_token() {
+    let messages = run_command(commands::GENERATE_CALL_GRAPH_WORKSPACE, Some("cg-1"), true);
+    let progress = progress_values(&messages);
+    assert!(progress
+        .iter()
+        .all(|(token, _)| *token == NumberOrString::String("cg-1".into())));
+
+    let WorkDoneProgress::Begin(begin) = &progress[0].1 else {
+        panic!("progress did not begin");
+    };
+    assert_eq!(begin.title, "Generating call graph");
+    assert_eq!(begin.message.as_deref(), Some("2 files"));
+
+    let reports: Vec<_> = progress
+        .iter()
+        .filter_map(|(_, value)| match value {
+            WorkDoneProgress::Report(r) => Some((r.message.clone().unwrap(), r.percentage)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        reports,
+        vec![
+            ("Parsing files (1/2)".to_string(), Some(30)),
+            ("Building call graph".to_string(), Some(60)),
+            ("Finding unresolved calls".to_string(), Some(80)),
+            ("Rendering DOT".to_string(), Some(90)),
+        ]
+    );
+
+    let WorkDoneProgress::End(end) = &progress.last().unwrap().1 else {
+        panic!("progress did not end");
+    };
+    assert_eq!(end.message.as_deref(), Some("Done"));
+    let Some(Message::Response(response)) = messages.last() else {
+        panic!("missing response");
+    };
+    assert_eq!(response.result.as_ref().unwrap()["success"], true);
+}
+
+fn create_requests(messages: &[Message]) -> usize {
+    messages
+        .iter()
+        .filter(|m| matches!(m, Message::Request(_)))
+        .count()
+}
+
+// The client's capability is process-wide, so the cases run in sequence.
+#[test]
+fn test_sequence_diagram_creates_progress_token() {
+    // Without the `window.workDoneProgress` capability there is no progress.
+    let messages = run_command(commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE, None, true);
+    assert_eq!(create_requests(&messages), 0);
+    assert!(progress_values(&messages).is_empty());
+    assert!(matches!(messages.last(), Some(Message::Response(r)) if r.error.is_none()));
+
+    execute_command::set_server_progress(true);
+    // A client that refuses the token gets no progress on it either.
+    let messages = run_command(commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE, None, false);
+    assert_eq!(create_requests(&messages), 1);
+    assert!(progress_values(&messages).is_empty());
+    assert!(matches!(messages.last(), Some(Message::Response(r)) if r.error.is_none()));
+
+    let messages = run_command(commands::GENERATE_SEQUENCE_DIAGRAM_WORKSPACE, None, true);
+    execute_command::set_server_progress(false);
+    let create = messages
+        .iter()
+        .find_map(|m| match m {
+            Message::Request(r) if r.method == "window/workDoneProgress/create" => Some(r),
+            _ => None,
+        })
+        .expect("missing window/workDoneProgress/create");
+    let token: NumberOrString = serde_json::from_value(create.params["token"].clone()).unwrap();
+
+    let progress = progress_values(&messages);
+    assert!(progress.iter().all(|(t, _)| *t == token));
+    assert!(progress.iter().any(|(_, value)| matches!(
+        value,
+        WorkDoneProgress::Report(r) if r.message.as_deref() == Some("Emitting Mermaid")
+    )));
+    assert!(matches!(
+        progress.last().unwrap().1,
+        WorkDoneProgress::End(_)
+    ));
+}
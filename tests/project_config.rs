@@ -0,0 +1,77 @@
+use serde_json::json;
+use std::path::Path;
+use traverse_lsp::project_config::{self, ConfigSources};
+
+#[test]
+fn test_load_toml_and_yaml() {
+    let dir = tempfile::tempdir().unwrap();
+    assert!(project_config::load(dir.path()).unwrap().is_none());
+
+    std::fs::write(
+        dir.path().join(".traverse.yml"),
+        "no_chunk: true\nrules:\n  magic-number:\n    enabled: false\n",
+    )
+    .unwrap();
+    let (path, settings) = project_config::load(dir.path()).unwrap().unwrap();
+    assert!(path.ends_with(".traverse.yml"));
+    assert_eq!(settings["no_chunk"], true);
+
+    // traverse.toml wins over .traverse.yml.
+    std::fs::write(
+        dir.path().join("traverse.toml"),
+        "max_response_bytes = 1024\n\n[rules.magic-number]\nenabled = true\n",
+    )
+    .unwrap();
+    let (path, settings) = project_config::load(dir.path()).unwrap().unwrap();
+    assert!(path.ends_with("traverse.toml"));
+    assert_eq!(settings["max_response_bytes"], 1024);
+    assert_eq!(settings["rules"]["magic-number"]["enabled"], true);
+}
+
+#[test]
+fn test_invalid_file_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("traverse.toml"), "no_chunk = \n").unwrap();
+    let error = project_config::load(dir.path()).unwrap_err();
+    assert!(format!("{:#}", error).contains("traverse.toml"));
+}
+
+#[test]
+fn test_client_settings_override_the_project() {
+    let sources = ConfigSources {
+        project: json!({ "no_chunk": true, "max_response_bytes": 1024 }),
+        client: json!({ "max_response_bytes": 2048 }),
+    };
+    let config = sources.config().unwrap();
+    assert_eq!(config.no_chunk, Some(true));
+    assert_eq!(config.max_response_bytes, Some(2048));
+
+    let merged = project_config::merge(
+        json!({ "rules": { "a": { "enabled": false }, "b": { "enabled": false } } }),
+        json!({ "rules": { "b": { "enabled": true } } }),
+    );
+    assert_eq!(
+        merged,
+        json!({ "rules": { "a": { "enabled": false }, "b": { "enabled": true } } })
+    );
+    assert_eq!(
+        project_config::merge(json!({ "no_chunk": true }), serde_json::Value::Null),
+        json!({ "no_chunk": true })
+    );
+}
+
+#[test]
+fn test_is_project_config() {
+    assert!(project_config::is_project_config(Path::new(
+        "/w/traverse.toml"
+    )));
+    assert!(project_config::is_project_config(Path::new(
+        "/w/.traverse.yml"
+    )));
+    assert!(!project_config::is_project_config(Path::new(
+        "/w/Cargo.toml"
+    )));
+    assert!(!project_config::is_project_config(Path::new(
+        "/w/Vault.sol"
+    )));
+}
@@ -0,0 +1,50 @@
+use serde_json::json;
+use traverse_lsp::query_history::{record, QueryHistory};
+
+#[test]
+fn test_query_history_keeps_last_entries() {
+    let mut history = QueryHistory::default();
+    for i in 0..5 {
+        history.push("traverse.compareStorage", vec![json!({ "n": i })], 3);
+    }
+
+    assert_eq!(history.entries.len(), 3);
+    assert_eq!(history.entries[0].arguments, vec![json!({ "n": 2 })]);
+    assert_eq!(history.entries[2].arguments, vec![json!({ "n": 4 })]);
+}
+
+#[test]
+fn test_query_history_persists_per_workspace() {
+    let workspace = tempfile::tempdir().expect("Failed to create temp dir");
+    let other = tempfile::tempdir().expect("Failed to create temp dir");
+    let args = vec![json!({
+        "workspace_folder": workspace.path(),
+        "left_contract": "VaultV1",
+        "right_contract": "VaultV2",
+    })];
+
+    record(workspace.path(), "traverse.compareStorage", args.clone())
+        .expect("Failed to record query");
+
+    let history = QueryHistory::load(workspace.path());
+    assert_eq!(history.entries.len(), 1);
+    assert_eq!(history.entries[0].command, "traverse.compareStorage");
+    assert_eq!(history.entries[0].arguments, args);
+    assert!(QueryHistory::load(other.path()).entries.is_empty());
+}
+
+#[test]
+fn test_concurrent_records_keep_every_entry() {
+    let workspace = tempfile::tempdir().expect("Failed to create temp dir");
+    std::thread::scope(|scope| {
+        for i in 0..8 {
+            let workspace = workspace.path();
+            scope.spawn(move || {
+                record(workspace, "traverse.findClones", vec![json!({ "n": i })])
+                    .expect("Failed to record query");
+            });
+        }
+    });
+
+    assert_eq!(QueryHistory::load(workspace.path()).entries.len(), 8);
+}
@@ -0,0 +1,101 @@
+use lsp_types::{Location, Position, Range, Url};
+use std::path::PathBuf;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::handlers::references::references_at;
+use traverse_lsp::source_map::SourceMap;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const POOL: &str = r#"contract Pool {
+    uint256 public reserve;
+
+    function deposit(uint256 amount) external {
+        reserve += amount;
+        sync();
+    }
+
+    function sync() public {
+        reserve = reserve;
+    }
+}
+"#;
+
+const ROUTER: &str = r#"import "./Pool.sol";
+
+contract Router {
+    Pool pool;
+
+    function route() external {
+        pool.sync();
+    }
+}
+"#;
+
+fn sources() -> Vec<SourceUnit> {
+    vec![
+        SourceUnit {
+            path: PathBuf::from("/workspace/Pool.sol"),
+            content: POOL.to_string(),
+        },
+        SourceUnit {
+            path: PathBuf::from("/workspace/Router.sol"),
+            content: ROUTER.to_string(),
+        },
+    ]
+}
+
+fn at(file: &str, line: u32, start: u32, end: u32) -> Location {
+    Location {
+        uri: Url::from_file_path(format!("/workspace/{}", file)).unwrap(),
+        range: Range::new(Position::new(line, start), Position::new(line, end)),
+    }
+}
+
+#[test]
+fn test_function_references_are_call_sites() {
+    let sources = sources();
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    let map = SourceMap::new(&sources);
+    let pool = &sources[0].path;
+
+    // On the declaration of `sync`.
+    let expected = vec![at("Pool.sol", 5, 8, 12), at("Router.sol", 6, 13, 17)];
+    assert_eq!(
+        references_at(&graph, &map, pool, Position::new(8, 14), false),
+        expected
+    );
+    // On a call to it, with the declaration.
+    let router = &sources[1].path;
+    assert_eq!(
+        references_at(&graph, &map, router, Position::new(6, 14), true),
+        vec![
+            at("Pool.sol", 5, 8, 12),
+            at("Pool.sol", 8, 13, 17),
+            at("Router.sol", 6, 13, 17),
+        ]
+    );
+}
+
+#[test]
+fn test_variable_references_are_reads_and_writes() {
+    let sources = sources();
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    let map = SourceMap::new(&sources);
+
+    let found = references_at(&graph, &map, &sources[0].path, Position::new(1, 21), false);
+    assert_eq!(
+        found,
+        vec![
+            at("Pool.sol", 4, 8, 15),
+            at("Pool.sol", 9, 8, 15),
+            at("Pool.sol", 9, 18, 25),
+        ]
+    );
+    // Nothing is declared or called on an empty line.
+    assert!(references_at(&graph, &map, &sources[0].path, Position::new(2, 0), true).is_empty());
+}
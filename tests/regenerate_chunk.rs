@@ -0,0 +1,198 @@
+use lsp_types::Url;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_lsp::config::{Config, GraphFilter, MermaidConfig};
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const SOURCE: &str = r#"pragma solidity ^0.8.0;
+
+contract Token {
+    uint256 total;
+
+    function mint(uint256 amount) external {
+        _add(amount);
+    }
+
+    function _add(uint256 amount) internal {
+        total += amount;
+    }
+}
+
+contract Vault {
+    Token token;
+
+    function deposit(uint256 amount) external {
+        token.mint(amount);
+    }
+}
+"#;
+
+struct Session {
+    tx: mpsc::Sender<GenerationRequest>,
+    thread: std::thread::JoinHandle<()>,
+    uris: Vec<Url>,
+    workspace: PathBuf,
+}
+
+impl Session {
+    fn start(workspace: &Path) -> Self {
+        let source = workspace.join("Token.sol");
+        std::fs::write(&source, SOURCE).unwrap();
+        let (tx, rx) = mpsc::channel();
+        let worker = GeneratorWorker::new(Config::default()).unwrap();
+        Self {
+            tx,
+            thread: std::thread::spawn(move || worker.run(rx)),
+            uris: vec![Url::from_file_path(&source).unwrap()],
+            workspace: workspace.to_path_buf(),
+        }
+    }
+
+    fn generate(&self, contract: Option<&str>, config: MermaidConfig) -> Value {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.tx
+            .send(GenerationRequest::GenerateMermaidFlowchart {
+                uris: self.uris.clone(),
+                workspace_folder: self.workspace.clone(),
+                contract_name: contract.map(str::to_string),
+                config,
+                filter: GraphFilter::default(),
+                progress: None,
+                cancel: Default::default(),
+                tx: reply_tx,
+            })
+            .unwrap();
+        serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap()
+    }
+
+    fn regenerate(
+        &self,
+        contract: Option<&str>,
+        function: Option<&str>,
+        chunk: usize,
+        config: MermaidConfig,
+    ) -> anyhow::Result<Value> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.tx
+            .send(GenerationRequest::RegenerateChunk {
+                uris: self.uris.clone(),
+                workspace_folder: self.workspace.clone(),
+                contract_name: contract.map(str::to_string),
+                function_name: function.map(str::to_string),
+                chunk,
+                config,
+                filter: GraphFilter::default(),
+                cancel: Default::default(),
+                tx: reply_tx,
+            })
+            .unwrap();
+        let response = reply_rx.blocking_recv().unwrap()?;
+        Ok(serde_json::from_str(&response).unwrap())
+    }
+
+    fn stop(self) {
+        self.tx.send(GenerationRequest::Shutdown).unwrap();
+        self.thread.join().unwrap();
+    }
+}
+
+fn config(chunk_dir: &Path) -> MermaidConfig {
+    MermaidConfig {
+        chunk_dir: chunk_dir.to_path_buf(),
+        ..MermaidConfig::default()
+    }
+}
+
+#[test]
+fn test_regenerate_chunk_rewrites_only_that_chunk() {
+    let workspace = tempfile::tempdir().unwrap();
+    let chunk_dir = workspace.path().join("chunks");
+    let session = Session::start(workspace.path());
+
+    let generated = session.generate(Some("Token"), config(&chunk_dir));
+    assert_eq!(generated["is_chunked"], true);
+    let chunk_file = chunk_dir.join("chunk_001.mmd");
+    let original = std::fs::read_to_string(&chunk_file).unwrap();
+    let index = std::fs::read_to_string(chunk_dir.join("index.mmd")).unwrap();
+    let manifest = std::fs::read_to_string(chunk_dir.join("manifest.json")).unwrap();
+
+    let themed = MermaidConfig {
+        theme: Some("dark".to_string()),
+        ..config(&chunk_dir)
+    };
+    let response = session.regenerate(Some("Token"), None, 1, themed).unwrap();
+    assert_eq!(response["chunk"], 1);
+    assert_eq!(response["file"], chunk_file.to_str().unwrap());
+    let content = std::fs::read_to_string(&chunk_file).unwrap();
+    assert_eq!(response["mermaid"], content.as_str());
+    assert!(content.starts_with("%%{init: {\"theme\":\"dark\"}}%%\n"));
+    assert!(content.ends_with(&original));
+    // The index keeps its options, and the manifest records the new chunk.
+    assert_eq!(
+        std::fs::read_to_string(chunk_dir.join("index.mmd")).unwrap(),
+        index
+    );
+    assert_ne!(
+        std::fs::read_to_string(chunk_dir.join("manifest.json")).unwrap(),
+        manifest
+    );
+    assert!(
+        !std::fs::read_dir(workspace.path())
+            .unwrap()
+            .any(|entry| entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(".chunks")),
+        "staging directory left behind"
+    );
+
+    session.stop();
+}
+
+#[test]
+fn test_regenerate_chunk_requires_a_matching_chunking() {
+    let workspace = tempfile::tempdir().unwrap();
+    let chunk_dir = workspace.path().join("chunks");
+    let session = Session::start(workspace.path());
+
+    let error = session
+        .regenerate(Some("Token"), None, 1, config(&chunk_dir))
+        .unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("generate the diagram first"),
+        "{:#}",
+        error
+    );
+
+    session.generate(Some("Token"), config(&chunk_dir));
+    let original = std::fs::read_to_string(chunk_dir.join("chunk_001.mmd")).unwrap();
+    assert!(session
+        .regenerate(Some("Token"), None, 2, config(&chunk_dir))
+        .is_err());
+    // The workspace diagram is longer, so its first chunk ends elsewhere.
+    let error = session
+        .regenerate(None, None, 1, config(&chunk_dir))
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("no longer covers"),
+        "{:#}",
+        error
+    );
+    let error = session
+        .regenerate(None, Some("mint"), 1, config(&chunk_dir))
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("needs the contract"),
+        "{:#}",
+        error
+    );
+    assert_eq!(
+        std::fs::read_to_string(chunk_dir.join("chunk_001.mmd")).unwrap(),
+        original
+    );
+
+    session.stop();
+}
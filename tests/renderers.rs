@@ -0,0 +1,144 @@
+use lsp_types::Url;
+use serde_json::Value;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_graph::cg::CallGraph;
+use traverse_lsp::config::{Config, DotConfig, GraphFilter, MermaidConfig};
+use traverse_lsp::renderers::{self, RenderOptions};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const SOURCE: &str = r#"pragma solidity ^0.8.0;
+
+contract Token {
+    uint256 total;
+
+    function mint(uint256 amount) external {
+        total += amount;
+    }
+}
+
+contract Vault {
+    Token token;
+
+    function deposit(uint256 amount) external {
+        token.mint(amount);
+    }
+}
+"#;
+
+fn graph() -> CallGraph {
+    TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(SOURCE)
+        .unwrap()
+}
+
+fn render(name: &str, graph: &CallGraph, options: &RenderOptions) -> String {
+    renderers::renderer(name)
+        .unwrap()
+        .render(graph, options)
+        .unwrap()
+}
+
+#[test]
+fn test_registry_lists_every_format() {
+    assert_eq!(
+        renderers::renderer_names(),
+        vec!["dot", "mermaid", "plantuml", "json"]
+    );
+    assert_eq!(renderers::renderer("plantuml").unwrap().extension(), "puml");
+    assert!(renderers::renderer("svg").is_none());
+}
+
+#[test]
+fn test_dot_and_mermaid_match_the_adapter() {
+    let adapter = TraverseAdapter::new().unwrap();
+    let graph = graph();
+    let options = RenderOptions {
+        dot: DotConfig {
+            rankdir: Some("LR".to_string()),
+            ..DotConfig::default()
+        },
+        mermaid: MermaidConfig {
+            theme: Some("dark".to_string()),
+            ..MermaidConfig::default()
+        },
+    };
+    assert_eq!(
+        render("dot", &graph, &options),
+        adapter
+            .generate_dot_with_config(&graph, &options.dot)
+            .unwrap()
+    );
+    let mermaid = render("mermaid", &graph, &options);
+    assert!(mermaid.starts_with("%%{init: {\"theme\":\"dark\"}}%%\nsequenceDiagram"));
+}
+
+#[test]
+fn test_plantuml_groups_functions_by_contract() {
+    let graph = graph();
+    let plantuml = render("plantuml", &graph, &RenderOptions::default());
+    assert!(plantuml.starts_with("@startuml\n"));
+    assert!(plantuml.ends_with("@enduml\n"));
+    assert!(plantuml.contains("package \"Token\" {\n"));
+    assert!(plantuml.contains("  database \"total\" as n"));
+    assert!(plantuml.contains("  rectangle \"deposit\" as n"));
+    assert!(plantuml.contains(" : write\n"));
+    assert!(!plantuml.contains("left to right direction"));
+
+    let options = RenderOptions {
+        dot: DotConfig {
+            rankdir: Some("LR".to_string()),
+            ..DotConfig::default()
+        },
+        ..RenderOptions::default()
+    };
+    assert!(render("plantuml", &graph, &options).contains("left to right direction\n"));
+}
+
+#[test]
+fn test_json_is_deterministic() {
+    let graph = graph();
+    let json = render("json", &graph, &RenderOptions::default());
+    assert_eq!(json, render("json", &graph, &RenderOptions::default()));
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert!(value.get("metadata").is_none());
+    assert!(value["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|node| node["name"] == "mint"));
+}
+
+#[test]
+fn test_worker_renders_a_scoped_diagram() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    std::fs::write(&source, SOURCE).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let worker = GeneratorWorker::new(Config::default()).unwrap();
+    let thread = std::thread::spawn(move || worker.run(rx));
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::RenderDiagram {
+        uris: vec![Url::from_file_path(&source).unwrap()],
+        contract_name: Some("Token".to_string()),
+        renderer: renderers::renderer("plantuml").unwrap(),
+        options: RenderOptions::default(),
+        filter: GraphFilter::default(),
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    let result: Value = serde_json::from_str(&reply_rx.blocking_recv().unwrap().unwrap()).unwrap();
+    assert_eq!(result["renderer"], "plantuml");
+    assert_eq!(result["extension"], "puml");
+    let diagram = result["diagram"].as_str().unwrap();
+    assert!(diagram.contains("\"mint\""));
+    assert!(!diagram.contains("\"deposit\""));
+    assert!(result["preview"].as_str().unwrap().starts_with("@startuml"));
+
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    thread.join().unwrap();
+}
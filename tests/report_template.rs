@@ -0,0 +1,119 @@
+use lsp_types::Url;
+use serde_json::json;
+use std::path::Path;
+use std::sync::mpsc;
+use tokio::sync::oneshot;
+use traverse_lsp::config::Config;
+use traverse_lsp::output::template::Template;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const VAULT: &str = r#"
+contract Vault {
+    uint256 total;
+    address owner;
+
+    function deposit(uint256 amount) public {
+        total += amount;
+    }
+
+    function owned() public view returns (address) {
+        return owner;
+    }
+}
+"#;
+
+fn render(source: &str, html: bool, data: serde_json::Value) -> String {
+    Template::parse(source, html)
+        .unwrap()
+        .render(&data)
+        .unwrap()
+}
+
+fn storage_report(dir: &Path, template: Option<&Path>) -> anyhow::Result<String> {
+    let path = dir.join("Vault.sol");
+    std::fs::write(&path, VAULT).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateStorageLayout {
+        uris: vec![Url::from_file_path(&path).unwrap()],
+        contract_name: String::new(),
+        template: template.map(Path::to_path_buf),
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+    reply_rx.blocking_recv().unwrap()
+}
+
+#[test]
+fn test_blocks_and_helpers() {
+    let data = json!({
+        "title": "Vault",
+        "items": [
+            {"name": "a", "tags": ["x", "y"]},
+            {"name": "b", "tags": []},
+        ],
+        "owner": {"name": "admin"},
+        "labels": ["low", "high"],
+    });
+    let source = "# {{title}}\n{{#each items}}\n- {{@index}} {{name}} ({{../title}}){{#if tags}}: {{#each tags}}{{this}}{{#unless @last}}, {{/unless}}{{/each}}{{else}}: none{{/if}}{{#unless @last}};{{/unless}}\n{{/each}}\n{{#with owner}}{{name}}{{/with}} {{lookup labels 1}}\n{{! not rendered }}\n";
+    assert_eq!(
+        render(source, false, data),
+        "# Vault\n- 0 a (Vault): x, y;\n- 1 b (Vault): none\nadmin high\n"
+    );
+}
+
+#[test]
+fn test_escaping_in_html_only() {
+    let data = json!({"name": "a < b & \"c\""});
+    assert_eq!(
+        render("{{name}}|{{{name}}}", true, data.clone()),
+        "a &lt; b &amp; &quot;c&quot;|a < b & \"c\""
+    );
+    assert_eq!(render("{{name}}", false, data), "a < b & \"c\"");
+}
+
+#[test]
+fn test_parse_errors() {
+    let error = Template::parse("a\n{{#each items}}{{/if}}\n", false).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("\"each\" was opened, but \"if\" is closing"));
+    assert!(error.to_string().contains(":2:"));
+    assert!(Template::parse("a\n{{#each items}}\nb\n", false).is_err());
+    assert!(Template::parse("{{/each}}", false).is_err());
+    assert!(Template::parse("{{name", false).is_err());
+}
+
+#[test]
+fn test_default_storage_report() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = storage_report(dir.path(), None).unwrap();
+    assert!(report.starts_with(
+        "# Storage Access Analysis\n\n**Files analyzed:** 1 Solidity files\n\n| Endpoint | Reads | Writes |\n|----------|-------|--------|\n"
+    ));
+    assert!(report.contains("| Vault.deposit | Vault.total | Vault.total |\n"));
+    assert!(report.contains("| Vault.owned | Vault.owner |  |\n"));
+}
+
+#[test]
+fn test_custom_storage_report_template() {
+    let dir = tempfile::tempdir().unwrap();
+    let template = dir.path().join("storage.html");
+    std::fs::write(
+        &template,
+        "<ul>\n{{#each endpoints}}\n<li>{{contract}}::{{function}} writes {{#each writes}}{{this}} {{/each}}</li>\n{{/each}}\n</ul>\n",
+    )
+    .unwrap();
+    let report = storage_report(dir.path(), Some(&template)).unwrap();
+    assert!(report.starts_with("<ul>\n<li>"));
+    assert!(report.contains("<li>Vault::deposit writes Vault.total </li>\n"));
+    assert!(report.contains("<li>Vault::owned writes </li>\n"));
+
+    std::fs::write(&template, "{{#if endpoints}}{{/each}}").unwrap();
+    let error = storage_report(dir.path(), Some(&template)).unwrap_err();
+    assert!(format!("{:#}", error).contains("\"if\" was opened, but \"each\" is closing"));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use common::{command_request, InMemoryServer};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use traverse_lsp::commands;
+use traverse_lsp::generator_worker::CancelToken;
+use traverse_lsp::handlers::execute_command;
+
+/// Collects formatted log output in memory.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn run_command(id: i32, command: &str, arguments: Vec<serde_json::Value>) -> lsp_server::Response {
+    let server = InMemoryServer::start();
+    execute_command(
+        command_request(id, command, arguments, None),
+        &server.server.sender,
+        &server.generator_tx,
+        CancelToken::default(),
+    )
+    .unwrap();
+    let response = server.response();
+    server.finish();
+    response
+}
+
+#[test]
+fn test_worker_logs_carry_request_context() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .init();
+
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(
+        workspace.path().join("Vault.sol"),
+        "pragma solidity ^0.8.0;\n\ncontract Vault {\n    function deposit() external {}\n}\n",
+    )
+    .unwrap();
+    let response = run_command(
+        42,
+        commands::LIST_FUZZ_TARGETS_WORKSPACE,
+        vec![serde_json::json!({
+            "workspace_folder": workspace.path().to_str().unwrap()
+        })],
+    );
+    let job = response.result.unwrap()["job"].as_u64().unwrap();
+
+    let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let worker_line = logs
+        .lines()
+        .find(|line| line.contains("Listing fuzz targets"))
+        .expect("worker log line");
+    assert!(
+        worker_line.contains(&format!(
+            "request{{id=42 command={} job={}}}",
+            commands::LIST_FUZZ_TARGETS_WORKSPACE,
+            job
+        )),
+        "{}",
+        worker_line
+    );
+
+    // Errors name the request they answer.
+    let response = run_command(43, "traverse.noSuchCommand", Vec::new());
+    assert_eq!(response.error.unwrap().data.unwrap()["request_id"], 43);
+}
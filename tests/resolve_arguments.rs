@@ -0,0 +1,101 @@
+mod common;
+
+use common::{run_git, InMemoryServer};
+use lsp_server::{Message, Request, RequestId};
+use serde_json::{json, Value};
+use traverse_lsp::commands;
+use traverse_lsp::handlers::resolve_command_arguments;
+use traverse_lsp::symbol_index::SymbolIndex;
+
+fn resolve(index: &SymbolIndex, command: &str, arguments: Value) -> Value {
+    let server = InMemoryServer::new();
+    let request = Request::new(
+        RequestId::from(1),
+        commands::RESOLVE_COMMAND_ARGUMENTS_REQUEST.to_string(),
+        json!({ "command": command, "arguments": [arguments] }),
+    );
+    resolve_command_arguments(request, &server.server, index).unwrap();
+    match server.next_message() {
+        Message::Response(response) => serde_json::to_value(response).unwrap(),
+        message => panic!("unexpected message {:?}", message),
+    }
+}
+
+fn argument<'a>(response: &'a Value, name: &str) -> Option<&'a Value> {
+    response["result"]["arguments"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|argument| argument["name"] == name)
+}
+
+#[test]
+fn test_contracts_and_refs_of_the_folder() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(
+        workspace.path().join("Vault.sol"),
+        "contract VaultV1 { uint256 total; }\ncontract VaultV2 { uint256 total; }\ninterface IVault {}\n",
+    )
+    .unwrap();
+    run_git(workspace.path(), &["init", "-q", "-b", "main"]);
+    run_git(workspace.path(), &["add", "-A"]);
+    run_git(workspace.path(), &["commit", "-q", "-m", "base"]);
+    run_git(workspace.path(), &["tag", "v1.0"]);
+    let elsewhere = tempfile::tempdir().unwrap();
+    std::fs::write(elsewhere.path().join("Other.sol"), "contract Other {}\n").unwrap();
+    let index = SymbolIndex::new();
+    index.index_workspace(workspace.path()).unwrap();
+    index.index_workspace(elsewhere.path()).unwrap();
+
+    let folder = workspace.path().to_str().unwrap();
+    let response = resolve(
+        &index,
+        commands::COMPARE_STORAGE,
+        json!({ "workspace_folder": folder, "left_contract": "VaultV1" }),
+    );
+    assert!(argument(&response, "left_contract").is_none());
+    let right = argument(&response, "right_contract").unwrap();
+    assert_eq!(right["kind"], "contract");
+    assert_eq!(right["required"], true);
+    assert_eq!(right["values"], json!(["VaultV1", "VaultV2"]));
+
+    let changed_since = argument(&response, "changed_since").unwrap();
+    assert_eq!(changed_since["kind"], "git_ref");
+    assert_eq!(changed_since["required"], false);
+    assert_eq!(changed_since["values"], json!(["main", "v1.0"]));
+}
+
+#[test]
+fn test_fixed_choices() {
+    let index = SymbolIndex::new();
+    let response = resolve(&index, commands::EXPORT_GRAPH_WORKSPACE, json!({}));
+    assert_eq!(
+        argument(&response, "format").unwrap()["values"],
+        json!(["cypher", "neo4j-csv", "obsidian", "excalidraw"])
+    );
+    assert_eq!(
+        argument(&response, "hide_getters").unwrap()["values"],
+        json!([false, true])
+    );
+    // Without a folder there are no refs to offer.
+    assert_eq!(
+        argument(&response, "changed_since").unwrap()["values"],
+        json!([])
+    );
+
+    let response = resolve(&index, commands::SUMMARIZE_FINDINGS, json!({}));
+    assert_eq!(
+        argument(&response, "min_confidence").unwrap()["values"],
+        json!(["low", "medium", "high"])
+    );
+
+    let response = resolve(&index, commands::GENERATE_PR_COMMENT, json!({}));
+    assert_eq!(argument(&response, "base_ref").unwrap()["required"], true);
+    assert!(argument(&response, "changed_since").is_none());
+}
+
+#[test]
+fn test_unknown_command() {
+    let response = resolve(&SymbolIndex::new(), "traverse.unknown", json!({}));
+    assert_eq!(response["error"]["code"], -32602);
+}
@@ -0,0 +1,153 @@
+use lsp_types::Url;
+use std::fs;
+use std::path::Path;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, SystemTime};
+use tokio::sync::oneshot;
+use traverse_lsp::config::{Config, GraphFilter, MermaidConfig};
+use traverse_lsp::output::retention::{clean, Retention};
+use traverse_lsp::output::settings::{OutputLocation, OutputSettings};
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+/// Writes `bytes` bytes to `path`, last modified `age` seconds ago.
+fn write_aged(path: &Path, bytes: usize, age: u64) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, vec![b'x'; bytes]).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(age))
+        .unwrap();
+}
+
+/// Output with three runs, newest first: a chunk directory, a large result
+/// and an Obsidian vault, next to files that are not runs.
+fn output_dir(root: &Path) {
+    write_aged(&root.join("sequence-diagrams/chunks/chunk_001.mmd"), 10, 10);
+    write_aged(&root.join("sequence-diagrams/chunks/manifest.json"), 10, 10);
+    write_aged(&root.join("results/audit-report-0123.json"), 100, 20);
+    write_aged(&root.join("obsidian/Vault.md"), 1000, 30);
+    write_aged(&root.join("obsidian/manifest.json"), 10, 30);
+    write_aged(&root.join("anonymize-mapping.json"), 10, 40);
+    write_aged(&root.join(".chunks.staging-1-0/chunk_001.mmd"), 10, 50);
+}
+
+#[test]
+fn test_keep_runs_removes_the_oldest() {
+    let root = tempfile::tempdir().unwrap();
+    output_dir(root.path());
+
+    let unlimited = clean(root.path(), &Retention::default()).unwrap();
+    assert!(unlimited.removed.is_empty());
+    assert_eq!(unlimited.kept, 3);
+    assert_eq!(unlimited.kept_bytes, 1130);
+
+    let retention = Retention {
+        keep_runs: Some(2),
+        ..Retention::default()
+    };
+    let cleanup = clean(root.path(), &retention).unwrap();
+    assert_eq!(cleanup.removed, vec![root.path().join("obsidian")]);
+    assert_eq!(cleanup.freed_bytes, 1010);
+    assert_eq!(cleanup.kept, 2);
+    assert!(root.path().join("results/audit-report-0123.json").exists());
+    // Files that are not runs, and hidden ones, stay.
+    assert!(root.path().join("anonymize-mapping.json").exists());
+    assert!(root.path().join(".chunks.staging-1-0").exists());
+}
+
+#[test]
+fn test_max_output_mb_keeps_the_newest_run() {
+    let root = tempfile::tempdir().unwrap();
+    output_dir(root.path());
+
+    let retention = Retention {
+        max_output_mb: Some(0),
+        ..Retention::default()
+    };
+    let cleanup = clean(root.path(), &retention).unwrap();
+    assert_eq!(
+        cleanup.removed,
+        vec![
+            root.path().join("results/audit-report-0123.json"),
+            root.path().join("obsidian"),
+        ]
+    );
+    assert_eq!(cleanup.kept, 1);
+    assert!(root.path().join("sequence-diagrams/chunks").exists());
+    // The emptied results directory goes too.
+    assert!(!root.path().join("results").exists());
+
+    assert!(clean(&root.path().join("missing"), &retention)
+        .unwrap()
+        .removed
+        .is_empty());
+}
+
+#[test]
+fn test_retention_arguments_fall_back_to_the_configuration() {
+    let configured: Config =
+        serde_json::from_value(serde_json::json!({ "keep_runs": 5, "max_output_mb": 100 }))
+            .unwrap();
+    let requested: Retention =
+        serde_json::from_value(serde_json::json!({ "keep_runs": 1 })).unwrap();
+    assert_eq!(
+        requested.or(configured.retention),
+        Retention {
+            keep_runs: Some(1),
+            max_output_mb: Some(100),
+        }
+    );
+    assert!(Config::default().retention.is_unlimited());
+}
+
+#[test]
+fn test_worker_applies_retention_after_each_command() {
+    let workspace = tempfile::tempdir().unwrap();
+    let source = workspace.path().join("Token.sol");
+    fs::write(
+        &source,
+        "contract Token {\n    uint256 total;\n\n    function mint(uint256 amount) external {\n        total += amount;\n    }\n}\n",
+    )
+    .unwrap();
+    let output = workspace.path().join("out");
+    write_aged(&output.join("results/old.json"), 10, 60);
+
+    let config = Config {
+        retention: Retention {
+            keep_runs: Some(1),
+            ..Retention::default()
+        },
+        ..Config::default()
+    };
+    let location = OutputLocation {
+        dir: output.clone(),
+        no_chunk: false,
+        chunk_dir: None,
+    };
+    let (tx, rx) = mpsc::channel();
+    let worker = GeneratorWorker::new(config)
+        .unwrap()
+        .with_output_settings(Arc::new(OutputSettings::new(location.clone())));
+    let thread = std::thread::spawn(move || worker.run(rx));
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateMermaidFlowchart {
+        uris: vec![Url::from_file_path(&source).unwrap()],
+        workspace_folder: workspace.path().to_path_buf(),
+        contract_name: None,
+        config: MermaidConfig::default(),
+        filter: GraphFilter::default(),
+        progress: None,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    reply_rx.blocking_recv().unwrap().unwrap();
+    assert!(location.chunk_dir().join("chunk_001.mmd").exists());
+    assert!(!output.join("results").exists());
+
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    thread.join().unwrap();
+}
@@ -0,0 +1,105 @@
+use traverse_lsp::handlers::semantic_tokens::{legend, tokens, TOKEN_MODIFIERS, TOKEN_TYPES};
+
+const SOURCE: &str = r#"pragma solidity ^0.8.0;
+
+interface IOracle {
+    function price() external view returns (uint256);
+}
+
+library MathLib {
+    function double(uint256 x) internal pure returns (uint256) {
+        return x * 2;
+    }
+}
+
+contract Vault {
+    using MathLib for uint256;
+
+    struct Position {
+        uint256 amount;
+    }
+
+    IOracle public oracle;
+    IERC20 public token;
+    address public implementation;
+    uint256 public total;
+    uint256 public constant FEE = 5;
+    mapping(address => Position) public positions;
+
+    function deposit(uint256 amount) external {
+        uint256 value = oracle.price();
+        token.transferFrom(msg.sender, address(this), amount);
+        total += amount.double();
+        Position storage position = positions[msg.sender];
+        position.amount = value;
+        uint256 scratch = FEE;
+        scratch++;
+    }
+
+    function upgrade(bytes calldata data) external {
+        (bool ok, ) = implementation.delegatecall(data);
+        require(ok);
+        delete total;
+    }
+
+    function withdraw() external {
+        payable(msg.sender).transfer(total);
+        IOracle(address(oracle)).price();
+    }
+}
+"#;
+
+/// The text, token type and modifiers of each token, decoded from LSP's
+/// relative positions.
+fn decode(text: &str) -> Vec<(String, &'static str, Vec<&'static str>)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let (mut line, mut start) = (0, 0);
+    tokens(text)
+        .into_iter()
+        .map(|token| {
+            if token.delta_line > 0 {
+                line += token.delta_line as usize;
+                start = 0;
+            }
+            start += token.delta_start as usize;
+            let word = lines[line][start..start + token.length as usize].to_string();
+            let modifiers = TOKEN_MODIFIERS
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| token.token_modifiers_bitset & (1 << bit) != 0)
+                .map(|(_, m)| *m)
+                .collect();
+            (word, TOKEN_TYPES[token.token_type as usize], modifiers)
+        })
+        .collect()
+}
+
+#[test]
+fn test_external_calls_delegatecalls_and_storage_writes() {
+    let decoded = decode(SOURCE);
+    let decoded: Vec<(&str, &str, Vec<&str>)> = decoded
+        .iter()
+        .map(|(word, kind, modifiers)| (word.as_str(), *kind, modifiers.clone()))
+        .collect();
+    assert_eq!(
+        decoded,
+        vec![
+            ("price", "function", vec!["externalCall"]),
+            ("transferFrom", "function", vec!["externalCall"]),
+            ("total", "property", vec!["storageWrite"]),
+            ("position", "variable", vec!["storageWrite"]),
+            ("delegatecall", "function", vec!["delegateCall"]),
+            ("total", "property", vec!["storageWrite"]),
+            ("transfer", "function", vec!["externalCall"]),
+            ("price", "function", vec!["externalCall"]),
+        ]
+    );
+}
+
+#[test]
+fn test_legend_and_unparsable_text() {
+    let legend = legend();
+    assert_eq!(legend.token_types.len(), TOKEN_TYPES.len());
+    assert_eq!(legend.token_modifiers[2].as_str(), "storageWrite");
+    assert!(tokens("contract {").is_empty());
+}
@@ -0,0 +1,212 @@
+use lsp_types::{Position, Url};
+use std::path::PathBuf;
+use traverse_graph::cg::{CallGraph, NodeType};
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::source_map::SourceMap;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::utils::{offset_at, position_at};
+
+const ORACLE: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.0;
+
+import "./IOracle.sol";
+
+contract Oracle is IOracle {
+    uint256 last;
+
+    function price() external view returns (uint256) {
+        return last;
+    }
+
+    function update(uint256 value) external {
+        last = value;
+    }
+}
+"#;
+
+const VAULT: &str = r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "./Oracle.sol";
+
+contract Vault {
+    Oracle oracle;
+    uint256 total;
+
+    function deposit(uint256 amount) external {
+        oracle.update(amount);
+        _log();
+    }
+
+    function _log() internal {}
+}
+"#;
+
+const IORACLE: &str = r#"pragma solidity ^0.8.0;
+
+interface IOracle {
+    function price() external view returns (uint256);
+}
+"#;
+
+fn workspace() -> Vec<SourceUnit> {
+    [
+        ("Oracle.sol", ORACLE),
+        ("Vault.sol", VAULT),
+        ("IOracle.sol", IORACLE),
+    ]
+    .into_iter()
+    .map(|(name, content)| SourceUnit {
+        path: PathBuf::from("/workspace").join(name),
+        content: content.to_string(),
+    })
+    .collect()
+}
+
+fn edges_of(graph: &CallGraph) -> Vec<String> {
+    let name = |id: usize| {
+        let node = &graph.nodes[id];
+        format!(
+            "{}.{}",
+            node.contract_name.as_deref().unwrap_or(""),
+            node.name
+        )
+    };
+    let mut edges: Vec<String> = graph
+        .edges
+        .iter()
+        .map(|e| {
+            format!(
+                "{} -{:?}-> {} @{:?}",
+                name(e.source_node_id),
+                e.edge_type,
+                name(e.target_node_id),
+                e.call_site_span
+            )
+        })
+        .collect();
+    edges.sort();
+    edges
+}
+
+#[test]
+fn test_per_file_graph_matches_concatenated_graph() {
+    let sources = workspace();
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+
+    let combined: String = sources.iter().map(|s| format!("{}\n", s.content)).collect();
+    let concatenated = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(&combined)
+        .unwrap();
+
+    let edges = edges_of(&graph);
+    assert!(edges
+        .iter()
+        .any(|e| e.starts_with("Vault.deposit -Call-> Oracle.update")));
+    assert_eq!(edges, edges_of(&concatenated));
+    let mut spans: Vec<_> = graph.nodes.iter().map(|n| n.span).collect();
+    let mut expected: Vec<_> = concatenated.nodes.iter().map(|n| n.span).collect();
+    spans.sort();
+    expected.sort();
+    assert_eq!(spans, expected);
+}
+
+#[test]
+fn test_nodes_locate_to_their_file() {
+    let sources = workspace();
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    let map = SourceMap::new(&sources);
+
+    let locate = |contract: &str, name: &str| {
+        let node = graph
+            .nodes
+            .iter()
+            .find(|n| n.contract_name.as_deref() == Some(contract) && n.name == name)
+            .unwrap();
+        map.locate(node.span).unwrap()
+    };
+
+    let deposit = locate("Vault", "deposit");
+    assert_eq!(
+        deposit.uri,
+        Url::from_file_path("/workspace/Vault.sol").unwrap()
+    );
+    assert_eq!(deposit.range.start, Position::new(9, 4));
+    assert_eq!(deposit.range.end, Position::new(12, 5));
+
+    let price = locate("Oracle", "price");
+    assert_eq!(
+        price.uri,
+        Url::from_file_path("/workspace/Oracle.sol").unwrap()
+    );
+    assert_eq!(price.range.start.line, 8);
+
+    let interface = locate("IOracle", "price");
+    assert_eq!(
+        interface.uri,
+        Url::from_file_path("/workspace/IOracle.sol").unwrap()
+    );
+    assert_eq!(interface.range.start.line, 3);
+
+    // The newline between files belongs to the earlier one.
+    assert_eq!(map.file_index(ORACLE.len()), Some(0));
+    assert_eq!(map.file_index(ORACLE.len() + 1), Some(1));
+    assert_eq!(map.file_index(usize::MAX), None);
+}
+
+#[test]
+fn test_origins_cover_every_declared_node() {
+    let sources = workspace();
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_workspace_call_graph(&sources)
+        .unwrap();
+    let map = SourceMap::new(&sources);
+    let origins = map.origins(&graph);
+
+    for node in &graph.nodes {
+        match node.node_type {
+            NodeType::Evm | NodeType::EventListener => assert!(!origins.contains_key(&node.id)),
+            _ => assert_eq!(origins.get(&node.id), map.locate(node.span).as_ref()),
+        }
+    }
+    let log = graph
+        .nodes
+        .iter()
+        .find(|n| n.contract_name.as_deref() == Some("Vault") && n.name == "_log")
+        .unwrap();
+    assert_eq!(
+        origins[&log.id].uri,
+        Url::from_file_path("/workspace/Vault.sol").unwrap()
+    );
+}
+
+#[test]
+fn test_offsets_and_positions_count_utf16_and_clamp() {
+    // `é` is one UTF-16 unit but two bytes; `𝄞` is two units and four bytes.
+    let text = "// é𝄞 x\r\nuint a;\n";
+    assert_eq!(offset_at(text, Position::new(0, 6)), 9);
+    assert_eq!(position_at(text, 9), Position::new(0, 6));
+    // Inside `𝄞`, at its start.
+    assert_eq!(offset_at(text, Position::new(0, 5)), 5);
+    assert_eq!(position_at(text, 7), Position::new(0, 4));
+    // Past the end of a line, before its `\r\n`, and of the text.
+    assert_eq!(offset_at(text, Position::new(0, 99)), 11);
+    assert_eq!(position_at(text, 12), Position::new(0, 8));
+    assert_eq!(offset_at(text, Position::new(5, 0)), text.len());
+    assert_eq!(position_at(text, 99), Position::new(2, 0));
+
+    let sources = [SourceUnit {
+        path: PathBuf::from("/work/A.sol"),
+        content: text.to_string(),
+    }];
+    let map = SourceMap::new(&sources);
+    assert_eq!(map.offset(0, Position::new(1, 4)), 17);
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use common::run_worker_request;
+use lsp_types::Url;
+use traverse_lsp::analysis::storage_heatmap::{heatmap, render_dot, render_heatmap};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::GenerationRequest;
+
+const POOL: &str = r#"
+contract Pool {
+    uint256 reserve;
+    uint256 fee;
+    address owner;
+
+    function deposit(uint256 amount) external {
+        reserve += amount;
+    }
+
+    function withdraw(uint256 amount) external {
+        if (amount > 0) {
+            reserve -= amount;
+        }
+    }
+
+    function skim() external {
+        reserve = 0;
+    }
+
+    function setFee(uint256 value) external {
+        require(msg.sender == owner);
+        fee = value;
+    }
+}
+
+contract Registry {
+    address pool;
+
+    function register(address value) external {
+        pool = value;
+    }
+}
+"#;
+
+#[test]
+fn test_variables_ranked_by_writers() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(POOL)
+        .unwrap();
+    let entries = heatmap(&graph, None).unwrap();
+    let ranked: Vec<(&str, &str, usize)> = entries
+        .iter()
+        .map(|e| (e.contract.as_str(), e.variable.as_str(), e.writers.len()))
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            ("Pool", "reserve", 3),
+            ("Pool", "fee", 1),
+            ("Registry", "pool", 1),
+            ("Pool", "owner", 0),
+        ]
+    );
+    assert_eq!(
+        entries[0].writers,
+        vec!["Pool.deposit", "Pool.skim", "Pool.withdraw"]
+    );
+    assert_eq!(entries[3].readers, vec!["Pool.setFee"]);
+    // Compound assignments read the variable too.
+    assert_eq!(entries[0].readers, vec!["Pool.deposit", "Pool.withdraw"]);
+
+    let dot = render_dot(&entries);
+    assert!(dot.contains("label=\"Pool\""));
+    assert!(dot
+        .contains("v0 [label=\"reserve\\n3 writers\", fillcolor=\"#cb181d\", fontcolor=\"white\""));
+    assert!(dot.contains("v3 [label=\"owner\\n0 writers\", fillcolor=\"#fff5f0\""));
+
+    let report = render_heatmap(&entries);
+    assert!(report.contains("| Pool | reserve | 3 | 2 | Pool.deposit, Pool.skim, Pool.withdraw |"));
+}
+
+#[test]
+fn test_single_contract() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(POOL)
+        .unwrap();
+    let entries = heatmap(&graph, Some("Registry")).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].variable, "pool");
+    assert!(heatmap(&graph, Some("Missing")).is_err());
+}
+
+#[test]
+fn test_heatmap_command() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Pool.sol");
+    std::fs::write(&path, POOL).unwrap();
+
+    let response = run_worker_request(|tx| GenerationRequest::StorageHeatmap {
+        uris: vec![Url::from_file_path(&path).unwrap()],
+        contract_name: Some("Pool".to_string()),
+        cancel: Default::default(),
+        tx,
+    });
+    assert_eq!(response["variables"].as_array().unwrap().len(), 3);
+    assert_eq!(response["variables"][0]["variable"], "reserve");
+    assert!(response["dot"]
+        .as_str()
+        .unwrap()
+        .starts_with("digraph storage_heatmap {"));
+    assert!(response["report"]
+        .as_str()
+        .unwrap()
+        .starts_with("# Storage Write Heatmap"));
+}
@@ -0,0 +1,189 @@
+use lsp_types::Url;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use traverse_lsp::index_status;
+use traverse_lsp::symbol_index::{IndexStatus, SymbolIndex, SymbolKind};
+
+const TOKEN_V1: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Token {
+    uint256 public totalSupply;
+
+    function mint(uint256 amount) external {}
+}
+"#;
+
+const TOKEN_V2: &str = r#"
+pragma solidity ^0.8.0;
+
+contract Coin {
+    uint256 public totalSupply;
+
+    function mint(uint256 amount) external {}
+
+    function burn(uint256 amount) external {}
+}
+"#;
+
+#[test]
+fn test_versioned_lookups() {
+    let index = SymbolIndex::new();
+    let uri = Url::parse("file:///work/Token.sol").unwrap();
+    assert!(index.update(uri.clone(), Some(1), TOKEN_V1.to_string()));
+
+    let lookup = index.find("Token.mint", true);
+    assert!(lookup.stale.is_empty());
+    assert_eq!(lookup.symbols.len(), 1);
+    let mint = &lookup.symbols[0];
+    assert_eq!(mint.kind, SymbolKind::Function);
+    assert_eq!(mint.version, Some(1));
+    assert_eq!((mint.range.start.line, mint.range.start.character), (6, 13));
+
+    // The contract is renamed; an answer without recomputing is flagged.
+    assert!(index.update(uri.clone(), Some(2), TOKEN_V2.to_string()));
+    let lookup = index.find("Token", false);
+    assert_eq!(lookup.stale, vec![uri.clone()]);
+    assert_eq!(lookup.symbols[0].version, Some(1));
+
+    // Recomputing drops the old name entirely.
+    let lookup = index.find("Token", true);
+    assert!(lookup.stale.is_empty());
+    assert!(lookup.symbols.is_empty());
+    let lookup = index.find("Coin.burn", true);
+    assert_eq!(lookup.symbols.len(), 1);
+    assert_eq!(lookup.symbols[0].version, Some(2));
+
+    // Out-of-order text for an older version is ignored.
+    assert!(!index.update(uri.clone(), Some(1), TOKEN_V1.to_string()));
+    assert!(index.find("Token", false).symbols.is_empty());
+}
+
+#[test]
+fn test_workspace_index_and_close() {
+    let workspace = tempfile::tempdir().unwrap();
+    let path = workspace.path().join("Token.sol");
+    std::fs::write(&path, TOKEN_V1).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let index = SymbolIndex::new();
+    index.index_workspace(workspace.path()).unwrap();
+    let lookup = index.find("totalSupply", false);
+    assert_eq!(lookup.symbols.len(), 1);
+    assert_eq!(lookup.symbols[0].kind, SymbolKind::StateVariable);
+    assert_eq!(lookup.symbols[0].container.as_deref(), Some("Token"));
+    assert_eq!(lookup.symbols[0].version, None);
+
+    // Unsaved edits win while open; closing reverts to the file on disk.
+    index.update(uri.clone(), Some(1), TOKEN_V2.to_string());
+    assert_eq!(index.find("burn", true).symbols.len(), 1);
+    index.close(&uri);
+    assert!(index.find("burn", true).symbols.is_empty());
+
+    std::fs::remove_file(&path).unwrap();
+    index.close(&uri);
+    assert!(index.find("totalSupply", true).symbols.is_empty());
+}
+
+#[test]
+fn test_reload_after_change_on_disk() {
+    let workspace = tempfile::tempdir().unwrap();
+    let path = workspace.path().join("Token.sol");
+    std::fs::write(&path, TOKEN_V1).unwrap();
+    let uri = Url::from_file_path(&path).unwrap();
+
+    let index = SymbolIndex::new();
+    index.index_workspace(workspace.path()).unwrap();
+    std::fs::write(&path, TOKEN_V2).unwrap();
+    assert!(index.find("burn", true).symbols.is_empty());
+    index.reload(&uri);
+    assert_eq!(index.find("burn", true).symbols.len(), 1);
+
+    // The editor's text wins over the disk while the file is open.
+    index.update(uri.clone(), Some(1), TOKEN_V1.to_string());
+    index.reload(&uri);
+    assert!(index.find("burn", true).symbols.is_empty());
+    index.close(&uri);
+
+    std::fs::remove_file(&path).unwrap();
+    index.reload(&uri);
+    assert!(index.find("totalSupply", true).symbols.is_empty());
+}
+
+#[test]
+fn test_index_status() {
+    let index = SymbolIndex::new();
+    assert_eq!(index.status(), IndexStatus::default());
+
+    let uri = Url::parse("file:///work/Token.sol").unwrap();
+    index.update(uri.clone(), Some(1), TOKEN_V2.to_string());
+    let status = index.status();
+    assert_eq!((status.files, status.stale_files), (1, 1));
+    assert_eq!(status.last_updated, None);
+
+    index.refresh(&uri);
+    let status = index.status();
+    assert_eq!(status.stale_files, 0);
+    assert_eq!((status.contracts, status.functions), (1, 2));
+    assert!(status.last_updated.is_some());
+}
+
+#[test]
+fn test_status_reporter_sends_changes_only() {
+    let index = Arc::new(SymbolIndex::new());
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let (status_tx, status_rx) = mpsc::channel();
+    let reporter_index = index.clone();
+    let handle = thread::spawn(move || {
+        index_status::run(reporter_index, Duration::from_millis(10), stop_rx, |s| {
+            status_tx.send(s).unwrap()
+        });
+    });
+
+    let first = status_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+    assert_eq!(first.files, 0);
+    assert!(status_rx.recv_timeout(Duration::from_millis(50)).is_err());
+
+    let uri = Url::parse("file:///work/Token.sol").unwrap();
+    index.update(uri.clone(), Some(1), TOKEN_V1.to_string());
+    index.refresh(&uri);
+    // A tick may land between the update and the refresh.
+    let second = loop {
+        let status = status_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        if status.stale_files == 0 {
+            break status;
+        }
+    };
+    assert_eq!(
+        (second.files, second.contracts, second.functions),
+        (1, 1, 1)
+    );
+
+    stop_tx.send(()).unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_search_ranks_fuzzy_matches() {
+    let index = SymbolIndex::new();
+    let uri = Url::parse("file:///work/Coin.sol").unwrap();
+    index.update(uri.clone(), Some(1), TOKEN_V2.to_string());
+
+    let names = |query: &str| -> Vec<String> {
+        index
+            .search(query, 10)
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect()
+    };
+    // The index re-parses the edited document before searching.
+    assert_eq!(names("coin"), vec!["Coin"]);
+    assert_eq!(names("BURN"), vec!["burn"]);
+    // Prefix before substring before scattered characters.
+    assert_eq!(names("t"), vec!["totalSupply", "mint"]);
+    assert_eq!(names("tsp"), vec!["totalSupply"]);
+    assert_eq!(names("Coin.mt"), vec!["mint"]);
+    assert!(names("xyz").is_empty());
+    assert_eq!(index.search("", 2).len(), 2);
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use common::{command_request, InMemoryServer};
+use lsp_server::{Message, RequestId, Response, ResponseError};
+use std::time::Duration;
+use traverse_lsp::commands;
+use traverse_lsp::config::Config;
+use traverse_lsp::generator_worker::CancelToken;
+use traverse_lsp::handlers::execute_command_with_telemetry;
+use traverse_lsp::telemetry::{CommandEvent, UNKNOWN_COMMAND};
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+contract Vault {
+    function deposit(uint256 amount) external {}
+}
+"#;
+
+/// Messages sent for one command, up to and including its telemetry event
+/// if there is one.
+fn run_command(command: &str, telemetry: bool) -> (tempfile::TempDir, Vec<Message>) {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), VAULT).unwrap();
+    std::fs::write(
+        workspace.path().join("Token.sol"),
+        VAULT.replace("Vault", "Token"),
+    )
+    .unwrap();
+
+    let server = InMemoryServer::start();
+    let request = command_request(
+        1,
+        command,
+        vec![serde_json::json!({
+            "workspace_folder": workspace.path().to_str().unwrap(),
+        })],
+        None,
+    );
+    execute_command_with_telemetry(
+        request,
+        &server.server.sender,
+        &server.generator_tx,
+        CancelToken::default(),
+        telemetry,
+    )
+    .unwrap();
+    let messages = server.finish();
+    (workspace, messages)
+}
+
+fn telemetry_events(messages: &[Message]) -> Vec<serde_json::Value> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            Message::Notification(n) if n.method == "telemetry/event" => Some(n.params.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_command_emits_anonymized_event() {
+    let (workspace, messages) = run_command(commands::LIST_FUZZ_TARGETS_WORKSPACE, true);
+    let events = telemetry_events(&messages);
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event["event"], "command");
+    assert_eq!(event["command"], commands::LIST_FUZZ_TARGETS_WORKSPACE);
+    assert_eq!(event["files"], 2);
+    assert_eq!(event["success"], true);
+    assert_eq!(event["error_code"], serde_json::Value::Null);
+    assert!(event["duration_ms"].is_u64());
+
+    let text = event.to_string();
+    let folder = workspace.path().file_name().unwrap().to_str().unwrap();
+    assert!(!text.contains(folder));
+    assert!(!text.contains("Vault"));
+
+    // The event follows the response.
+    assert!(matches!(
+        messages
+            .iter()
+            .rev()
+            .find(|m| !matches!(m, Message::Notification(_))),
+        Some(Message::Response(_))
+    ));
+}
+
+#[test]
+fn test_telemetry_is_off_by_default() {
+    assert!(!Config::default().telemetry_enabled());
+    let (_workspace, messages) = run_command(commands::LIST_FUZZ_TARGETS_WORKSPACE, false);
+    assert!(telemetry_events(&messages).is_empty());
+    assert!(messages.iter().any(|m| matches!(m, Message::Response(_))));
+
+    let config: Config = serde_json::from_value(serde_json::json!({ "telemetry": true })).unwrap();
+    assert!(config.telemetry_enabled());
+}
+
+#[test]
+fn test_failed_and_unknown_commands() {
+    let (_workspace, messages) = run_command("my.private.command /home/me/project", true);
+    let events = telemetry_events(&messages);
+    assert_eq!(events[0]["command"], UNKNOWN_COMMAND);
+    assert_eq!(events[0]["success"], false);
+    assert_eq!(events[0]["error_code"], -32601);
+
+    let response = Response {
+        id: RequestId::from(2),
+        result: None,
+        error: Some(ResponseError {
+            code: -32803,
+            message: "timed out".to_string(),
+            data: Some(serde_json::json!({ "files": 12, "request_id": 2 })),
+        }),
+    };
+    let event = CommandEvent::new(
+        commands::GENERATE_ALL_WORKSPACE,
+        Duration::from_secs(3),
+        &response,
+    );
+    assert_eq!(event.command, commands::GENERATE_ALL_WORKSPACE);
+    assert_eq!(event.duration_ms, 3000);
+    assert_eq!(event.files, Some(12));
+    assert_eq!(event.error_code, Some(-32803));
+}
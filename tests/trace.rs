@@ -0,0 +1,348 @@
+use std::path::PathBuf;
+use traverse_lsp::analysis::model::{parse_sources, SourceModel, SourceUnit};
+use traverse_lsp::source_map::SourceMap;
+use traverse_lsp::trace::{self, StepKind};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+contract Token {
+    mapping(address => uint256) balances;
+
+    function transferFrom(address from, address to, uint256 amount) external returns (bool) {
+        balances[from] -= amount;
+        balances[to] += amount;
+        return true;
+    }
+}
+
+contract Vault {
+    Token token;
+    uint256 total;
+
+    constructor(Token _token) {
+        token = _token;
+    }
+
+    function deposit(uint256 amount) external {
+        token.transferFrom(msg.sender, address(this), amount);
+        total += amount;
+    }
+}
+"#;
+
+const FORGE_TRACE: &str = "\
+Ran 1 test for test/Vault.t.sol:VaultTest
+[PASS] test_Deposit() (gas: 51234)
+Traces:
+  [51234] VaultTest::test_Deposit()
+    ├─ [0] VM::prank(0x0000000000000000000000000000000000000001)
+    │   └─ ← [Return]
+    ├─ [29876] Vault::deposit(100)
+    │   ├─ [24000] Token::transferFrom(0x0000000000000000000000000000000000000001, Vault: [0x5615deB798BB3E4dFa0139dFa1b3D433Cc23b72f], 100)
+    │   │   ├─ emit Transfer(from: 0x0000000000000000000000000000000000000001, to: Vault: [0x5615deB798BB3E4dFa0139dFa1b3D433Cc23b72f], value: 100)
+    │   │   └─ ← [Return] true
+    │   ├─ [2500] Oracle::price() [staticcall]
+    │   │   └─ ← [Return] 42
+    │   └─ ← [Stop]
+    ├─ [1200] Vault::withdraw(500)
+    │   └─ ← [Revert] revert: insufficient balance
+    └─ ← [Stop]
+
+Suite result: ok. 1 passed; 0 failed; 0 skipped; finished in 1.23ms
+";
+
+fn model(sources: &[SourceUnit]) -> SourceModel {
+    SourceModel::from_parsed(&parse_sources(sources).unwrap())
+}
+
+#[test]
+fn test_parse_forge_trace() {
+    let steps = trace::parse(FORGE_TRACE);
+    let names: Vec<_> = steps
+        .iter()
+        .map(|s| {
+            (
+                s.depth,
+                s.caller.as_str(),
+                s.contract.as_str(),
+                s.name.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            (0, "Caller", "VaultTest", "test_Deposit"),
+            (1, "VaultTest", "VM", "prank"),
+            (1, "VaultTest", "Vault", "deposit"),
+            (2, "Vault", "Token", "transferFrom"),
+            (3, "Token", "Token", "Transfer"),
+            (2, "Vault", "Oracle", "price"),
+            (1, "VaultTest", "Vault", "withdraw"),
+        ]
+    );
+    assert_eq!(steps[2].arguments, "(100)");
+    assert_eq!(steps[2].gas, Some(29876));
+    assert_eq!(steps[2].result, None);
+    assert_eq!(steps[3].result.as_deref(), Some("true"));
+    assert_eq!(steps[4].kind, StepKind::Event);
+    assert_eq!(steps[5].kind, StepKind::StaticCall);
+    assert_eq!(steps[5].arguments, "()");
+    assert!(steps[6].reverted);
+    assert_eq!(
+        steps[6].result.as_deref(),
+        Some("revert: insufficient balance")
+    );
+}
+
+#[test]
+fn test_parse_cast_run_creation_and_delegatecall() {
+    let trace = "\
+Traces:
+  [120000] → new Proxy@0x5FbDB2315678afecb367f032d93F642f64180aa3
+    ├─ [80000] Implementation::initialize(7) [delegatecall]
+    │   └─ ← [Stop]
+    └─ ← [Return] 1234 bytes of code
+";
+    let steps = trace::parse(trace);
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[0].kind, StepKind::Create);
+    assert_eq!(steps[0].contract, "Proxy");
+    assert_eq!(steps[0].name, "constructor");
+    assert_eq!(steps[0].result.as_deref(), Some("1234 bytes of code"));
+    assert_eq!(steps[1].kind, StepKind::DelegateCall);
+    assert_eq!(steps[1].caller, "Proxy");
+}
+
+#[test]
+fn test_render_trace_as_sequence_diagram() {
+    let mermaid = trace::render_mermaid(&trace::parse(FORGE_TRACE));
+    let expected = "\
+sequenceDiagram
+    actor Caller
+    participant VaultTest
+    participant Vault
+    participant Token
+    participant Oracle
+    Caller->>VaultTest: test_Deposit()
+    VaultTest->>Vault: deposit(100)
+    Vault->>Token: transferFrom(0x0000000000000000000000000000000000000001, Vault: [0x5615deB798BB3E4dFa0139dFa…
+    Note over Token: emit Transfer(from: 0x0000000000000000000000000000000000000001, to: Vault: [0x5615deB798BB3E4…
+    Token-->>Vault: true
+    Vault->>Oracle: staticcall price()
+    Oracle-->>Vault: 42
+    Vault-->>VaultTest: return
+    VaultTest->>Vault: withdraw(500)
+    Vault--xVaultTest: revert revert: insufficient balance
+    VaultTest-->>Caller: return
+";
+    assert_eq!(mermaid, expected);
+}
+
+#[test]
+fn test_align_trace_with_workspace_sources() {
+    let unit = SourceUnit {
+        path: PathBuf::from("/workspace/src/Vault.sol"),
+        content: VAULT.to_string(),
+    };
+    let sources = vec![unit];
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_workspace_call_graph(&sources)
+        .expect("Failed to build call graph");
+
+    let mut steps = trace::parse(FORGE_TRACE);
+    let unmatched = trace::align(
+        &mut steps,
+        &graph,
+        &model(&sources),
+        &SourceMap::new(&sources),
+    );
+    assert_eq!(
+        unmatched,
+        vec![
+            "Oracle::price",
+            "Vault::withdraw",
+            "VaultTest::test_Deposit"
+        ]
+    );
+
+    let deposit = steps[2]
+        .location
+        .as_ref()
+        .expect("deposit is in the workspace");
+    assert!(deposit.uri.path().ends_with("/src/Vault.sol"));
+    assert_eq!(deposit.range.start.line, 20);
+    let transfer = steps[3].location.as_ref().unwrap();
+    assert_eq!(transfer.range.start.line, 5);
+    assert!(steps[1].location.is_none(), "cheatcodes are not aligned");
+    assert!(steps[4].location.is_none(), "events are not aligned");
+}
+
+#[test]
+fn test_align_constructor() {
+    let sources = vec![SourceUnit {
+        path: PathBuf::from("/workspace/src/Vault.sol"),
+        content: VAULT.to_string(),
+    }];
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter.build_workspace_call_graph(&sources).unwrap();
+    let mut steps =
+        trace::parse("  [90000] → new Vault@0x5FbDB2315678afecb367f032d93F642f64180aa3\n");
+    let unmatched = trace::align(
+        &mut steps,
+        &graph,
+        &model(&sources),
+        &SourceMap::new(&sources),
+    );
+    assert!(unmatched.is_empty());
+    assert_eq!(steps[0].location.as_ref().unwrap().range.start.line, 16);
+}
+
+const TOKEN_VAULT: &str = r#"pragma solidity ^0.8.0;
+
+abstract contract ERC20 {
+    mapping(address => uint256) balances;
+
+    function transferFrom(address from, address to, uint256 amount) external returns (bool) {
+        balances[from] -= amount;
+        balances[to] += amount;
+        return true;
+    }
+}
+
+contract Token is ERC20 {}
+
+contract Vault {
+    Token token;
+
+    function deposit(uint256 amount) external {
+        token.transferFrom(msg.sender, address(this), amount);
+    }
+}
+"#;
+
+// deposit(uint256) is 0xb6b55f25, transferFrom(address,address,uint256) is
+// 0x23b872dd.
+const CALL_TRACER: &str = r#"{
+  "jsonrpc": "2.0",
+  "id": 1,
+  "result": {
+    "type": "CALL",
+    "from": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+    "to": "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+    "gas": "0x1e8480",
+    "gasUsed": "0xb2a4",
+    "input": "0xb6b55f250000000000000000000000000000000000000000000000000000000000000064",
+    "calls": [
+      {
+        "type": "CALL",
+        "from": "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+        "to": "0xe7f1725e7734ce288f8367e1bb143e90bb3f0512",
+        "gasUsed": "0x5208",
+        "input": "0x23b872dd000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+        "output": "0x0000000000000000000000000000000000000000000000000000000000000001"
+      },
+      {
+        "type": "STATICCALL",
+        "from": "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+        "to": "0x9999999999999999999999999999999999999999",
+        "input": "0x12345678",
+        "error": "execution reverted",
+        "revertReason": "paused"
+      }
+    ]
+  }
+}"#;
+
+#[test]
+fn test_import_call_tracer_json() {
+    let sources = vec![SourceUnit {
+        path: PathBuf::from("/workspace/src/Vault.sol"),
+        content: TOKEN_VAULT.to_string(),
+    }];
+    let model = model(&sources);
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter.build_workspace_call_graph(&sources).unwrap();
+
+    let mut steps = trace::read(CALL_TRACER).unwrap();
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[0].selector.as_deref(), Some("0xb6b55f25"));
+    assert_eq!(steps[0].gas, Some(0xb2a4));
+    assert_eq!(steps[2].kind, StepKind::StaticCall);
+    assert!(steps[2].reverted);
+    assert_eq!(steps[2].result.as_deref(), Some("paused"));
+
+    trace::resolve_selectors(&mut steps, &model);
+    let calls: Vec<_> = steps
+        .iter()
+        .map(|s| (s.caller.as_str(), s.contract.as_str(), s.name.as_str()))
+        .collect();
+    assert_eq!(
+        calls,
+        vec![
+            ("0xf39f…2266", "Vault", "deposit"),
+            ("Vault", "Token", "transferFrom"),
+            ("Vault", "0x9999…9999", "0x12345678"),
+        ]
+    );
+
+    let unmatched = trace::align(&mut steps, &graph, &model, &SourceMap::new(&sources));
+    assert_eq!(unmatched, vec!["0x9999…9999::0x12345678"]);
+    // Token inherits transferFrom, so the step points into ERC20.
+    let transfer = steps[1].location.as_ref().unwrap();
+    assert_eq!(transfer.range.start.line, 5);
+    assert_eq!(steps[0].location.as_ref().unwrap().range.start.line, 17);
+
+    let mermaid = trace::render_mermaid(&steps);
+    assert!(mermaid.starts_with(
+        "sequenceDiagram\n    actor 0xf39f_2266 as 0xf39f…2266\n    participant Vault\n"
+    ));
+    assert!(mermaid.contains("    0xf39f_2266->>Vault: deposit(…)\n"));
+    assert!(mermaid.contains("    Vault->>0x9999_9999: staticcall 0x12345678()\n"));
+    assert!(mermaid.contains("    0x9999_9999--xVault: revert paused\n"));
+
+    let reached = steps.iter().filter_map(|s| s.node_id).collect();
+    let executed = traverse_lsp::graph_filter::reachable_subgraph(&graph, &reached);
+    let mut names: Vec<_> = executed.nodes.iter().map(|n| n.name.as_str()).collect();
+    names.sort();
+    names.dedup();
+    assert!(names.contains(&"deposit"));
+    assert!(names.contains(&"transferFrom"));
+    assert!(!names.contains(&"Token"));
+}
+
+#[test]
+fn test_call_tracer_without_envelope() {
+    let steps = trace::read(
+        r#"{"type": "CREATE2", "from": "0xaa", "to": "0xbb", "input": "0x6080", "output": "0x6080"}"#,
+    )
+    .unwrap();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].kind, StepKind::Create);
+    assert_eq!(steps[0].name, "constructor");
+    assert_eq!(steps[0].selector, None);
+    assert!(trace::read("{\"calls\": []}").is_err());
+}
+
+#[test]
+fn test_call_tracer_with_non_ascii_strings() {
+    let error =
+        trace::read(r#"{"type": "CALL", "from": "0xaa", "to": "0xbb", "input": "0xabcdefé0"}"#)
+            .unwrap_err();
+    assert!(error.to_string().contains("is not hex"), "{}", error);
+    // The eighth byte falls inside a character.
+    assert!(trace::read(
+        r#"{"type": "CALL", "from": "0xaa", "to": "0xbb", "input": "0xabcdefaé"}"#
+    )
+    .is_err());
+
+    // Addresses are shortened without splitting a character.
+    let mut steps = trace::read(
+        r#"{"type": "CALL", "from": "0xaaaaaaaaaaaaé123", "to": "0xbb", "input": "0x"}"#,
+    )
+    .unwrap();
+    trace::resolve_selectors(&mut steps, &model(&[]));
+    assert_eq!(steps[0].caller, "0xaaaaaaaaaaaaé123");
+}
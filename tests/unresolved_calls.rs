@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+use traverse_graph::cg::{EdgeType, Visibility};
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::analysis::unresolved_calls::{
+    add_placeholders, find, UnresolvedReason, PLACEHOLDER_CONTRACT,
+};
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+
+const ROUTER: &str = r#"
+pragma solidity ^0.8.0;
+
+import "./IPool.sol";
+
+interface IOracle {
+    function price() external view returns (uint256);
+}
+
+library Math {
+    function twice(uint256 x) internal pure returns (uint256) {
+        return x * 2;
+    }
+}
+
+contract Router {
+    using Math for uint256;
+
+    IOracle oracle;
+    address pool;
+    uint256[] history;
+
+    function swap(uint256 amount) external {
+        oracle.price();
+        IPool(pool).swap(amount);
+        pool.call(abi.encodeWithSignature("sync()"));
+        amount.twice();
+        Math.twice(amount);
+        require(amount > 0, "zero");
+        history.push(amount);
+        payable(pool).transfer(1);
+        _settle();
+    }
+
+    function _settle() internal {}
+}
+"#;
+
+fn router() -> Vec<SourceUnit> {
+    vec![SourceUnit {
+        path: PathBuf::from("/workspace/Router.sol"),
+        content: ROUTER.to_string(),
+    }]
+}
+
+#[test]
+fn test_unresolved_calls_have_reasons() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let sources = router();
+    let graph = adapter
+        .build_workspace_call_graph(&sources)
+        .expect("Failed to build call graph");
+    let calls = find(&sources, &graph).expect("Failed to find unresolved calls");
+
+    let found: Vec<(&str, UnresolvedReason)> = calls
+        .iter()
+        .map(|c| (c.expression.as_str(), c.reason))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("IPool(pool).swap", UnresolvedReason::MissingDefinition),
+            ("pool.call", UnresolvedReason::LowLevelCall),
+            ("amount.twice", UnresolvedReason::UnresolvedMember),
+        ]
+    );
+    assert!(calls.iter().all(|c| graph.nodes[c.caller].name == "swap"));
+}
+
+#[test]
+fn test_placeholders_are_added_to_graph() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let sources = router();
+    let mut graph = adapter
+        .build_workspace_call_graph(&sources)
+        .expect("Failed to build call graph");
+    let calls = find(&sources, &graph).unwrap();
+    let node_count = graph.nodes.len();
+    add_placeholders(&mut graph, &calls);
+
+    let placeholders: Vec<_> = graph.nodes[node_count..].iter().collect();
+    assert_eq!(placeholders.len(), 3);
+    assert!(placeholders
+        .iter()
+        .all(|n| n.contract_name.as_deref() == Some(PLACEHOLDER_CONTRACT)
+            && n.visibility == Visibility::Private));
+    assert!(placeholders
+        .iter()
+        .any(|n| n.name == "pool.call [low-level-call]"));
+
+    for (index, node) in graph.nodes.iter().enumerate() {
+        assert_eq!(node.id, index);
+    }
+    let placeholder_edges = graph
+        .edges
+        .iter()
+        .filter(|e| e.edge_type == EdgeType::Call && e.target_node_id >= node_count)
+        .count();
+    assert_eq!(placeholder_edges, 3);
+
+    let dot = adapter.generate_dot_diagram(&graph).unwrap();
+    assert!(dot.contains("Unresolved.IPool(pool).swap [missing-definition]"));
+}
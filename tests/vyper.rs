@@ -0,0 +1,192 @@
+#![cfg(feature = "vyper")]
+
+use std::path::PathBuf;
+use traverse_graph::cg::{EdgeType, NodeType, Visibility};
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::source_map::SourceMap;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::utils::find_source_files;
+use traverse_lsp::vyper::{self, CallTarget};
+
+const IERC20: &str = r#"pragma solidity ^0.8.0;
+
+interface IERC20 {
+    function transferFrom(address from, address to, uint256 amount) external returns (bool);
+}
+"#;
+
+const POOL: &str = r#"# @version 0.3.10
+"""
+@title Pool
+@notice Takes deposits: in one token.
+"""
+
+interface IERC20:
+    def transferFrom(sender: address, to: address, amount: uint256) -> bool: nonpayable
+
+interface IOracle:
+    def price() -> uint256: view
+
+event Deposit:
+    sender: indexed(address)
+    amount: uint256
+
+token: public(IERC20)
+oracle: IOracle
+balances: HashMap[address, uint256]
+
+@external
+def __init__(_token: address, _oracle: address):
+    self.token = IERC20(_token)
+    self.oracle = IOracle(_oracle)
+
+@external
+def deposit(amount: uint256):
+    # Pull first, then credit.
+    self._pull(
+        msg.sender,
+        amount,
+    )
+    self.balances[msg.sender] += amount
+    log Deposit(msg.sender, amount)
+
+@external
+@view
+def value(owner: address) -> uint256:
+    return self.balances[owner] * IOracle(self.oracle.address).price()
+
+@internal
+def _pull(sender: address, amount: uint256):
+    assert self.token.transferFrom(sender, self, amount)
+"#;
+
+#[test]
+fn test_parse_vyper_module() {
+    let module = vyper::parse(POOL);
+    let interfaces: Vec<_> = module.interfaces.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(interfaces, vec!["IERC20", "IOracle"]);
+    assert_eq!(module.interfaces[1].functions[0].name, "price");
+    assert_eq!(
+        module.interfaces[1].functions[0].returns.as_deref(),
+        Some("uint256")
+    );
+    assert_eq!(module.storage["token"], "IERC20");
+    assert_eq!(module.storage["oracle"], "IOracle");
+
+    let functions: Vec<_> = module.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(functions, vec!["__init__", "deposit", "value", "_pull"]);
+    let value = &module.functions[2];
+    assert_eq!(value.decorators, vec!["external", "view"]);
+    assert_eq!(
+        value.parameters,
+        vec![("owner".to_string(), "address".to_string())]
+    );
+    assert_eq!(value.returns.as_deref(), Some("uint256"));
+
+    let deposit = &module.functions[1];
+    let body = &POOL[deposit.span.0..deposit.span.1];
+    assert!(body.starts_with("def deposit(amount: uint256):"));
+    assert!(body.ends_with("log Deposit(msg.sender, amount)"));
+    let targets: Vec<_> = deposit.calls.iter().map(|c| c.target.clone()).collect();
+    assert_eq!(
+        targets,
+        vec![
+            CallTarget::Internal("_pull".to_string()),
+            CallTarget::Event("Deposit".to_string()),
+        ]
+    );
+    assert_eq!(deposit.calls[0].arguments, vec!["msg.sender", "amount"]);
+
+    let pull = &module.functions[3].calls[0];
+    assert_eq!(
+        pull.target,
+        CallTarget::External {
+            interface: "IERC20".to_string(),
+            function: "transferFrom".to_string(),
+        }
+    );
+    assert_eq!(
+        &POOL[pull.span.0..pull.span.1],
+        "self.token.transferFrom(sender, self, amount)"
+    );
+    assert_eq!(
+        module.functions[2].calls[0].target,
+        CallTarget::External {
+            interface: "IOracle".to_string(),
+            function: "price".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_vyper_calls_resolve_to_solidity() {
+    let sources = vec![
+        SourceUnit {
+            path: PathBuf::from("/workspace/src/IERC20.sol"),
+            content: IERC20.to_string(),
+        },
+        SourceUnit {
+            path: PathBuf::from("/workspace/vyper/Pool.vy"),
+            content: POOL.to_string(),
+        },
+    ];
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter.build_workspace_call_graph(&sources).unwrap();
+
+    let node = |contract: &str, name: &str| {
+        graph
+            .nodes
+            .iter()
+            .find(|n| n.contract_name.as_deref() == Some(contract) && n.name == name)
+            .unwrap_or_else(|| panic!("Missing {contract}.{name}"))
+    };
+    let constructor = node("Pool", "Pool");
+    assert_eq!(constructor.node_type, NodeType::Constructor);
+    let deposit = node("Pool", "deposit");
+    assert_eq!(deposit.visibility, Visibility::External);
+    let pull = node("Pool", "_pull");
+    assert_eq!(pull.visibility, Visibility::Internal);
+    let transfer_from = node("IERC20", "transferFrom");
+    // The Solidity declaration is used, not a second one from Vyper.
+    let declarations = graph
+        .nodes
+        .iter()
+        .filter(|n| n.name == "transferFrom")
+        .count();
+    assert_eq!(declarations, 1);
+    // Only declared in Vyper.
+    let price = node("IOracle", "price");
+    let value = node("Pool", "value");
+
+    let calls: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|e| e.edge_type == EdgeType::Call)
+        .map(|e| (e.source_node_id, e.target_node_id))
+        .collect();
+    assert!(calls.contains(&(deposit.id, pull.id)));
+    assert!(calls.contains(&(pull.id, transfer_from.id)));
+    assert!(calls.contains(&(value.id, price.id)));
+    assert!(graph
+        .edges
+        .iter()
+        .any(|e| e.source_node_id == deposit.id && e.event_name.as_deref() == Some("Deposit")));
+
+    let location = SourceMap::new(&sources).locate(pull.span).unwrap();
+    assert!(location.uri.path().ends_with("/vyper/Pool.vy"));
+    assert_eq!(location.range.start.line, 41);
+}
+
+#[test]
+fn test_find_source_files_includes_vyper() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), "").unwrap();
+    std::fs::write(workspace.path().join("Pool.vy"), "").unwrap();
+
+    let files = find_source_files(workspace.path()).unwrap();
+    let names: Vec<_> = files
+        .iter()
+        .map(|uri| uri.path().rsplit('/').next().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["Pool.vy", "Vault.sol"]);
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use common::{command_request, progress_values, InMemoryServer};
+use lsp_server::{Message, Request, Response};
+use lsp_types::{NumberOrString, WorkDoneProgress};
+use std::sync::Arc;
+use std::thread;
+use traverse_lsp::commands;
+use traverse_lsp::config::{Config, DotConfig, GraphFilter};
+use traverse_lsp::graph_cache::GraphCache;
+use traverse_lsp::handlers::execute_command;
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+const VAULT: &str = r#"pragma solidity ^0.8.0;
+
+contract Vault {
+    uint256 total;
+
+    function deposit(uint256 amount) external {
+        _add(amount);
+    }
+
+    function _add(uint256 amount) internal {
+        total += amount;
+    }
+}
+"#;
+
+fn warm_cache_request(workspace_folder: &str, token: Option<&str>) -> Request {
+    command_request(
+        1,
+        commands::WARM_CACHE,
+        vec![serde_json::json!({ "workspace_folder": workspace_folder })],
+        token,
+    )
+}
+
+/// Messages sent to the client until a work-done progress ends.
+fn until_progress_end(server: &InMemoryServer) -> Vec<Message> {
+    let mut messages = Vec::new();
+    loop {
+        let message = server.next_message();
+        let ended = matches!(
+            &message,
+            Message::Notification(n) if n.method == "$/progress"
+                && n.params["value"]["kind"] == "end"
+        );
+        messages.push(message);
+        if ended {
+            return messages;
+        }
+    }
+}
+
+#[test]
+fn test_warm_cache_reports_progress_and_fills_cache() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), VAULT).unwrap();
+    let folder = workspace.path().to_str().unwrap();
+
+    let graphs = Arc::new(GraphCache::new(Config::default().cache_budget_bytes()));
+    let mut server = InMemoryServer::new();
+    server.run(
+        GeneratorWorker::new(Config::default())
+            .unwrap()
+            .with_graph_cache(graphs.clone()),
+    );
+
+    execute_command(
+        warm_cache_request(folder, Some("warm-1")),
+        &server.server.sender,
+        &server.generator_tx,
+        Default::default(),
+    )
+    .unwrap();
+    let messages = until_progress_end(&server);
+
+    let response = messages
+        .iter()
+        .find_map(|m| match m {
+            Message::Response(r) => Some(r.clone()),
+            _ => None,
+        })
+        .unwrap();
+    let result = response.result.unwrap();
+    assert_eq!(result["data"]["started"], true);
+    assert_eq!(result["data"]["files"], 1);
+
+    let progress = progress_values(&messages);
+    assert!(progress
+        .iter()
+        .all(|(token, _)| *token == NumberOrString::String("warm-1".into())));
+    assert!(matches!(progress[0].1, WorkDoneProgress::Begin(_)));
+    assert!(progress.iter().any(|(_, value)| matches!(
+        value,
+        WorkDoneProgress::Report(r) if r.message.as_deref() == Some("Building call graph")
+    )));
+    let WorkDoneProgress::End(end) = &progress.last().unwrap().1 else {
+        panic!("progress did not end");
+    };
+    assert_eq!(
+        end.message.as_deref(),
+        Some("Cached call graphs for 1 files")
+    );
+    let warmed = graphs.stats();
+    assert_eq!(warmed.entries, 2);
+
+    // A later command is answered from the cache.
+    let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+    server
+        .generator_tx
+        .send(GenerationRequest::GenerateCallGraphDiagram {
+            uris: vec![lsp_types::Url::from_file_path(workspace.path().join("Vault.sol")).unwrap()],
+            contract_name: None,
+            dot_config: DotConfig::default(),
+            filter: GraphFilter::default(),
+            coverage_file: None,
+            progress: None,
+            cancel: Default::default(),
+            tx: reply_tx,
+        })
+        .unwrap();
+    reply_rx.blocking_recv().unwrap().unwrap();
+    let stats = graphs.stats();
+    assert_eq!(stats.misses, warmed.misses);
+    assert_eq!(stats.hits, warmed.hits + 1);
+    server.finish();
+}
+
+#[test]
+fn test_warm_cache_creates_progress_token() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), VAULT).unwrap();
+    let folder = workspace.path().to_str().unwrap();
+
+    let server = InMemoryServer::start();
+
+    execute_command::set_server_progress(true);
+    let request = warm_cache_request(folder, None);
+    let command = {
+        let sender = server.server.sender.clone();
+        let generator_tx = server.generator_tx.clone();
+        thread::spawn(move || {
+            execute_command(request, &sender, &generator_tx, Default::default()).unwrap();
+        })
+    };
+    let Message::Request(create) = server.next_message() else {
+        panic!("expected a progress token request first");
+    };
+    assert_eq!(create.method, "window/workDoneProgress/create");
+    execute_command::progress_created(Response::new_ok(create.id.clone(), ()));
+    let messages = until_progress_end(&server);
+    command.join().unwrap();
+    execute_command::set_server_progress(false);
+
+    let token: NumberOrString = serde_json::from_value(create.params["token"].clone()).unwrap();
+    assert!(progress_values(&messages).iter().all(|(t, _)| *t == token));
+    server.finish();
+}
@@ -0,0 +1,422 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use traverse_lsp::config::Config;
+use traverse_lsp::generator_worker::{CancelToken, RequestCancelled, ShuttingDown, WorkerPanicked};
+use traverse_lsp::watchdog::{self, GenerationTimedOut, PhaseTracker, WorkerHandle};
+use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+fn list_fuzz_targets(
+    tx: &mpsc::Sender<GenerationRequest>,
+) -> oneshot::Receiver<anyhow::Result<String>> {
+    cancellable_fuzz_targets(tx, CancelToken::default())
+}
+
+fn cancellable_fuzz_targets(
+    tx: &mpsc::Sender<GenerationRequest>,
+    cancel: CancelToken,
+) -> oneshot::Receiver<anyhow::Result<String>> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::ListFuzzTargets {
+        uris: Vec::new(),
+        cancel,
+        tx: reply_tx,
+    })
+    .unwrap();
+    reply_rx
+}
+
+/// A worker whose first instance hangs on its first request.
+fn fake_worker(spawned: &AtomicUsize) -> anyhow::Result<WorkerHandle> {
+    let instance = spawned.fetch_add(1, Ordering::SeqCst);
+    let phases = PhaseTracker::default();
+    let worker_phases = phases.clone();
+    let (tx, rx) = mpsc::channel::<GenerationRequest>();
+    let thread = thread::spawn(move || {
+        for request in rx.iter() {
+            worker_phases.start(request.kind());
+            worker_phases.enter("building call graph");
+            if instance == 0 {
+                thread::sleep(Duration::from_secs(2));
+            }
+            match request.into_reply() {
+                Some(reply) => {
+                    let _ = reply.send(Ok(format!("worker {}", instance)));
+                }
+                None => break,
+            }
+            worker_phases.finish();
+        }
+    });
+    Ok(WorkerHandle { tx, phases, thread })
+}
+
+#[test]
+fn test_stuck_request_times_out_and_worker_is_replaced() {
+    let spawned = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    let stuck = list_fuzz_targets(&tx);
+    let queued = list_fuzz_targets(&tx);
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    let pending = list_fuzz_targets(&tx);
+
+    let counter = spawned.clone();
+    watchdog::run(rx, Some(Duration::from_millis(200)), 1, move || {
+        fake_worker(&counter)
+    })
+    .unwrap();
+
+    let error = stuck.blocking_recv().unwrap().unwrap_err();
+    let timeout = error.downcast_ref::<GenerationTimedOut>().unwrap();
+    assert_eq!(timeout.request, "fuzz target listing");
+    assert_eq!(timeout.phase, Some("building call graph"));
+    assert!(error
+        .to_string()
+        .contains("stuck while building call graph"));
+
+    assert_eq!(queued.blocking_recv().unwrap().unwrap(), "worker 1");
+    assert!(pending
+        .blocking_recv()
+        .unwrap()
+        .unwrap_err()
+        .is::<ShuttingDown>());
+    assert_eq!(spawned.load(Ordering::SeqCst), 2);
+}
+
+/// A worker that answers only once `arrived` shows every request has
+/// reached a worker, or after a few seconds.
+fn rendezvous_worker(arrived: Arc<AtomicUsize>, expected: usize) -> anyhow::Result<WorkerHandle> {
+    let phases = PhaseTracker::default();
+    let (tx, rx) = mpsc::channel::<GenerationRequest>();
+    let thread = thread::spawn(move || {
+        for request in rx.iter() {
+            let Some(reply) = request.into_reply() else {
+                break;
+            };
+            arrived.fetch_add(1, Ordering::SeqCst);
+            let mut waited = Duration::ZERO;
+            while arrived.load(Ordering::SeqCst) < expected && waited < Duration::from_secs(5) {
+                thread::sleep(Duration::from_millis(10));
+                waited += Duration::from_millis(10);
+            }
+            let together = arrived.load(Ordering::SeqCst) >= expected;
+            let _ = reply.send(Ok(if together { "together" } else { "alone" }.to_string()));
+        }
+    });
+    Ok(WorkerHandle { tx, phases, thread })
+}
+
+#[test]
+fn test_pool_runs_requests_concurrently() {
+    let arrived = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    let first = list_fuzz_targets(&tx);
+    let second = list_fuzz_targets(&tx);
+    tx.send(GenerationRequest::Shutdown).unwrap();
+
+    watchdog::run(rx, None, 2, move || rendezvous_worker(arrived.clone(), 2)).unwrap();
+
+    assert_eq!(first.blocking_recv().unwrap().unwrap(), "together");
+    assert_eq!(second.blocking_recv().unwrap().unwrap(), "together");
+}
+
+/// A worker that numbers the requests it runs, taking a while over each.
+fn counting_worker(runs: Arc<AtomicUsize>) -> anyhow::Result<WorkerHandle> {
+    let phases = PhaseTracker::default();
+    let (tx, rx) = mpsc::channel::<GenerationRequest>();
+    let thread = thread::spawn(move || {
+        for request in rx.iter() {
+            let Some(reply) = request.into_reply() else {
+                break;
+            };
+            let run = runs.fetch_add(1, Ordering::SeqCst) + 1;
+            thread::sleep(Duration::from_millis(300));
+            let _ = reply.send(Ok(format!("run {}", run)));
+        }
+    });
+    Ok(WorkerHandle { tx, phases, thread })
+}
+
+#[test]
+fn test_identical_queued_requests_are_coalesced() {
+    let runs = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    let running = list_fuzz_targets(&tx);
+    let queued = list_fuzz_targets(&tx);
+    let duplicate = list_fuzz_targets(&tx);
+    tx.send(GenerationRequest::Shutdown).unwrap();
+
+    let counter = runs.clone();
+    watchdog::run(rx, None, 1, move || counting_worker(counter.clone())).unwrap();
+
+    assert_eq!(running.blocking_recv().unwrap().unwrap(), "run 1");
+    assert_eq!(queued.blocking_recv().unwrap().unwrap(), "run 2");
+    assert_eq!(duplicate.blocking_recv().unwrap().unwrap(), "run 2");
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+/// [`cancellable_worker`], announcing on `started` each request it starts.
+fn announcing_worker(started: mpsc::Sender<()>) -> anyhow::Result<WorkerHandle> {
+    let phases = PhaseTracker::default();
+    let (tx, rx) = mpsc::channel::<GenerationRequest>();
+    let thread = thread::spawn(move || {
+        for request in rx.iter() {
+            let cancel = request.cancel_token().cloned().unwrap_or_default();
+            let Some(reply) = request.into_reply() else {
+                break;
+            };
+            let _ = started.send(());
+            let mut waited = Duration::ZERO;
+            while !cancel.load(Ordering::Relaxed) && waited < Duration::from_millis(500) {
+                thread::sleep(Duration::from_millis(10));
+                waited += Duration::from_millis(10);
+            }
+            let _ = reply.send(if cancel.load(Ordering::Relaxed) {
+                Err(RequestCancelled.into())
+            } else {
+                Ok("finished".to_string())
+            });
+        }
+    });
+    Ok(WorkerHandle { tx, phases, thread })
+}
+
+#[test]
+fn test_leader_cancels_and_follower_still_gets_a_result() {
+    let (tx, rx) = mpsc::channel();
+    let (started_tx, started) = mpsc::channel();
+    let watchdog = thread::spawn(move || {
+        watchdog::run(rx, None, 1, move || announcing_worker(started_tx.clone())).unwrap()
+    });
+
+    let running = list_fuzz_targets(&tx);
+    started.recv().unwrap();
+    let leader_cancel = CancelToken::default();
+    let leader = cancellable_fuzz_targets(&tx, leader_cancel.clone());
+    let follower = list_fuzz_targets(&tx);
+    // The shared run starts once the first request is done.
+    started.recv().unwrap();
+    leader_cancel.store(true, Ordering::Relaxed);
+
+    assert!(leader
+        .blocking_recv()
+        .unwrap()
+        .unwrap_err()
+        .is::<RequestCancelled>());
+    assert_eq!(follower.blocking_recv().unwrap().unwrap(), "finished");
+    assert_eq!(running.blocking_recv().unwrap().unwrap(), "finished");
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    watchdog.join().unwrap();
+    assert!(started.try_recv().is_err());
+}
+
+#[test]
+fn test_shared_run_is_cancelled_once_every_caller_cancels() {
+    let (tx, rx) = mpsc::channel();
+    let (started_tx, started) = mpsc::channel();
+    let watchdog = thread::spawn(move || {
+        watchdog::run(rx, None, 1, move || announcing_worker(started_tx.clone())).unwrap()
+    });
+
+    let _running = list_fuzz_targets(&tx);
+    started.recv().unwrap();
+    let cancels = [CancelToken::default(), CancelToken::default()];
+    let replies: Vec<_> = cancels
+        .iter()
+        .map(|cancel| cancellable_fuzz_targets(&tx, cancel.clone()))
+        .collect();
+    started.recv().unwrap();
+    let shared = std::time::Instant::now();
+    for cancel in &cancels {
+        cancel.store(true, Ordering::Relaxed);
+    }
+
+    for reply in replies {
+        assert!(reply
+            .blocking_recv()
+            .unwrap()
+            .unwrap_err()
+            .is::<RequestCancelled>());
+    }
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    watchdog.join().unwrap();
+    // The worker stopped early rather than running to completion.
+    assert!(shared.elapsed() < Duration::from_millis(450));
+}
+
+fn generate_all(
+    tx: &mpsc::Sender<GenerationRequest>,
+    workspace_folder: &str,
+) -> oneshot::Receiver<anyhow::Result<String>> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::GenerateAllDiagrams {
+        uris: Vec::new(),
+        workspace_folder: PathBuf::from(workspace_folder),
+        contract_name: None,
+        dot_config: Default::default(),
+        mermaid_config: Default::default(),
+        filter: Default::default(),
+        progress: None,
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    reply_rx
+}
+
+/// A worker that runs until its request is cancelled, or for half a second.
+fn cancellable_worker() -> anyhow::Result<WorkerHandle> {
+    let phases = PhaseTracker::default();
+    let (tx, rx) = mpsc::channel::<GenerationRequest>();
+    let thread = thread::spawn(move || {
+        for request in rx.iter() {
+            let cancel = request.cancel_token().cloned().unwrap_or_default();
+            let Some(reply) = request.into_reply() else {
+                break;
+            };
+            let mut waited = Duration::ZERO;
+            while !cancel.load(Ordering::Relaxed) && waited < Duration::from_millis(500) {
+                thread::sleep(Duration::from_millis(10));
+                waited += Duration::from_millis(10);
+            }
+            let _ = reply.send(if cancel.load(Ordering::Relaxed) {
+                Err(RequestCancelled.into())
+            } else {
+                Ok("finished".to_string())
+            });
+        }
+    });
+    Ok(WorkerHandle { tx, phases, thread })
+}
+
+#[test]
+fn test_newer_workspace_generation_supersedes_older() {
+    let (tx, rx) = mpsc::channel();
+    let older = generate_all(&tx, "/workspace");
+    let other_folder = generate_all(&tx, "/other");
+    let newer = generate_all(&tx, "/workspace");
+    tx.send(GenerationRequest::Shutdown).unwrap();
+
+    watchdog::run(rx, None, 3, cancellable_worker).unwrap();
+
+    assert!(older
+        .blocking_recv()
+        .unwrap()
+        .unwrap_err()
+        .is::<RequestCancelled>());
+    assert_eq!(other_folder.blocking_recv().unwrap().unwrap(), "finished");
+    assert_eq!(newer.blocking_recv().unwrap().unwrap(), "finished");
+}
+
+#[test]
+fn test_watchdog_runs_generator_worker() {
+    let (tx, rx) = mpsc::channel();
+    let reply = list_fuzz_targets(&tx);
+    tx.send(GenerationRequest::Shutdown).unwrap();
+
+    watchdog::run(rx, Some(Duration::from_secs(60)), 2, || {
+        watchdog::spawn_worker(GeneratorWorker::new(Config::default())?)
+    })
+    .unwrap();
+
+    let response: serde_json::Value =
+        serde_json::from_str(&reply.blocking_recv().unwrap().unwrap()).unwrap();
+    assert_eq!(response["targets"], serde_json::json!([]));
+}
+
+#[test]
+fn test_panicking_request_fails_and_worker_survives() {
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, panicked) = oneshot::channel();
+    tx.send(GenerationRequest::WarmCache {
+        uris: Vec::new(),
+        progress: Some(Box::new(|_, _| panic!("progress sink exploded"))),
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+    let next = list_fuzz_targets(&tx);
+    tx.send(GenerationRequest::Shutdown).unwrap();
+
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+
+    let error = panicked.blocking_recv().unwrap().unwrap_err();
+    let panicked = error.downcast_ref::<WorkerPanicked>().unwrap();
+    assert_eq!(panicked.request, "cache warm-up");
+    assert_eq!(panicked.message, "progress sink exploded");
+    let response: serde_json::Value =
+        serde_json::from_str(&next.blocking_recv().unwrap().unwrap()).unwrap();
+    assert_eq!(response["targets"], serde_json::json!([]));
+}
+
+#[test]
+fn test_watchdog_restarts_after_failure() {
+    let spawned = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel();
+    let rejected = list_fuzz_targets(&tx);
+
+    let counter = spawned.clone();
+    let watchdog = thread::spawn(move || {
+        watchdog::run_restarting(rx, None, 1, move || {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("worker failed to start");
+            }
+            counting_worker(Arc::new(AtomicUsize::new(0)))
+        })
+    });
+
+    let error = rejected.blocking_recv().unwrap().unwrap_err();
+    assert!(error.to_string().contains("worker failed to start"));
+    let served = list_fuzz_targets(&tx);
+    assert_eq!(served.blocking_recv().unwrap().unwrap(), "run 1");
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    watchdog.join().unwrap();
+    assert_eq!(spawned.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_phase_dump() {
+    let phases = PhaseTracker::default();
+    assert_eq!(phases.dump(), "no request running");
+
+    phases.start("audit report");
+    phases.enter("reading sources");
+    phases.enter("running audit checks");
+    assert_eq!(phases.current(), Some("running audit checks"));
+    let dump = phases.dump();
+    assert!(dump.starts_with("audit report running for"));
+    assert!(dump.contains("\n  reading sources: "));
+    assert!(dump.ends_with(" <- current"));
+
+    phases.finish();
+    assert_eq!(phases.current(), None);
+}
+
+#[test]
+fn test_generation_timeout_config() {
+    let config: Config = serde_json::from_value(serde_json::json!({})).unwrap();
+    assert_eq!(
+        config.generation_timeout(),
+        Some(watchdog::DEFAULT_GENERATION_TIMEOUT)
+    );
+    let config: Config =
+        serde_json::from_value(serde_json::json!({ "generation_timeout_secs": 0 })).unwrap();
+    assert_eq!(config.generation_timeout(), None);
+    let config: Config =
+        serde_json::from_value(serde_json::json!({ "generation_timeout_secs": 30 })).unwrap();
+    assert_eq!(config.generation_timeout(), Some(Duration::from_secs(30)));
+}
+
+#[test]
+fn test_generation_workers_config() {
+    assert_eq!(
+        Config::default().generation_workers(),
+        watchdog::DEFAULT_GENERATION_WORKERS
+    );
+    let config: Config =
+        serde_json::from_value(serde_json::json!({ "generation_workers": 0 })).unwrap();
+    assert_eq!(config.generation_workers(), 1);
+}
@@ -1,3 +1,6 @@
+#![allow(clippy::len_zero)]
+
+use traverse_lsp::config::{Config, DotConfig, MermaidConfig};
 use traverse_lsp::traverse_adapter::TraverseAdapter;
 
 const SIMPLE_CONTRACT: &str = r#"
@@ -103,6 +106,104 @@ fn test_workspace_dot_generation() {
     assert!(dot.contains("->"));
 }
 
+#[test]
+fn test_dot_layout_parameters() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(COMPLEX_CONTRACT)
+        .expect("Failed to build call graph");
+    let config = DotConfig {
+        rankdir: Some("TB".to_string()),
+        splines: Some("ortho".to_string()),
+        nodesep: Some(0.8),
+        concentrate: Some(true),
+        ..DotConfig::default()
+    };
+    let dot = adapter
+        .generate_dot_with_config(&graph, &config)
+        .expect("Failed to generate DOT");
+
+    let graph_lines: Vec<&str> = dot
+        .lines()
+        .filter(|line| line.trim_start().starts_with("graph ["))
+        .collect();
+    assert_eq!(graph_lines.len(), 2);
+    assert_eq!(
+        graph_lines[1].trim(),
+        r#"graph [rankdir="TB", splines="ortho", nodesep=0.8, concentrate=true];"#
+    );
+    assert!(dot.contains("deposit"));
+    assert!(dot.trim_end().ends_with('}'));
+
+    let default_dot = adapter.generate_dot_diagram(&graph).unwrap();
+    assert_eq!(
+        adapter
+            .generate_dot_with_config(&graph, &DotConfig::default())
+            .unwrap(),
+        default_dot
+    );
+}
+
+#[test]
+fn test_dot_repeated_calls_are_counted() {
+    let source = r#"
+pragma solidity ^0.8.0;
+
+contract Batch {
+    uint256 total;
+
+    function run() public {
+        _step();
+        _step();
+        _step();
+        _finish();
+    }
+
+    function _step() internal {
+        total += 1;
+    }
+
+    function _finish() internal {}
+}
+"#;
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(source)
+        .expect("Failed to build call graph");
+    let id = |name: &str| graph.nodes.iter().find(|n| n.name == name).unwrap().id;
+    let (run, step, finish) = (id("run"), id("_step"), id("_finish"));
+
+    let config = DotConfig {
+        scale_pen_width: true,
+        ..DotConfig::default()
+    };
+    let dot = adapter
+        .generate_dot_with_config(&graph, &config)
+        .expect("Failed to generate DOT");
+
+    let step_edges: Vec<&str> = dot
+        .lines()
+        .filter(|l| {
+            l.trim_start()
+                .starts_with(&format!("n{} -> n{} ", run, step))
+        })
+        .collect();
+    assert_eq!(step_edges.len(), 1);
+    assert!(step_edges[0].contains("label=\"3× "));
+    assert!(step_edges[0].contains("weight=\"3\""));
+    assert!(step_edges[0].contains("penwidth=\"2.6\""));
+
+    let finish_edge = dot
+        .lines()
+        .find(|l| {
+            l.trim_start()
+                .starts_with(&format!("n{} -> n{} ", run, finish))
+        })
+        .unwrap();
+    assert!(!finish_edge.contains("×"));
+    assert!(!finish_edge.contains("penwidth"));
+}
+
 #[test]
 fn test_workspace_mermaid_generation() {
     let adapter = TraverseAdapter::new().expect("Failed to create adapter");
@@ -117,3 +218,254 @@ fn test_workspace_mermaid_generation() {
     assert!(mermaid.contains("SimpleToken"));
     assert!(mermaid.contains("transfer"));
 }
+
+#[test]
+fn test_mermaid_init_directive_injection() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(SIMPLE_CONTRACT)
+        .expect("Failed to build call graph");
+
+    let mut init = serde_json::Map::new();
+    init.insert("theme".to_string(), serde_json::json!("forest"));
+    let config = MermaidConfig {
+        no_chunk: true,
+        theme: Some("dark".to_string()),
+        max_text_size: Some(200_000),
+        init,
+        ..MermaidConfig::default()
+    };
+    let result = adapter
+        .generate_mermaid_with_config(&graph, &config)
+        .expect("Failed to generate Mermaid");
+
+    let first_line = result.content.lines().next().unwrap();
+    assert_eq!(
+        first_line,
+        r#"%%{init: {"maxTextSize":200000,"theme":"forest"}}%%"#
+    );
+    assert!(result.content.contains("sequenceDiagram"));
+
+    let plain = adapter
+        .generate_mermaid_with_config(
+            &graph,
+            &MermaidConfig {
+                no_chunk: true,
+                ..MermaidConfig::default()
+            },
+        )
+        .expect("Failed to generate Mermaid");
+    assert!(!plain.content.starts_with("%%{init"));
+}
+
+#[test]
+fn test_mermaid_init_directive_in_chunk_files() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(COMPLEX_CONTRACT)
+        .expect("Failed to build call graph");
+    let dir = tempfile::tempdir().unwrap();
+
+    let config = MermaidConfig {
+        chunk_dir: dir.path().to_path_buf(),
+        theme: Some("neutral".to_string()),
+        ..MermaidConfig::default()
+    };
+    let result = adapter
+        .generate_mermaid_with_config(&graph, &config)
+        .expect("Failed to generate Mermaid");
+    assert!(result.is_chunked);
+
+    let directive = r#"%%{init: {"theme":"neutral"}}%%"#;
+    assert!(result.content.starts_with(directive));
+    for name in ["chunk_001.mmd", "index.mmd"] {
+        let content = std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert!(content.starts_with(directive), "{} lacks directive", name);
+        assert_eq!(content.matches("%%{init").count(), 1);
+    }
+}
+
+#[test]
+fn test_chunk_threshold_from_config() {
+    let graph = TraverseAdapter::new()
+        .unwrap()
+        .build_call_graph(COMPLEX_CONTRACT)
+        .expect("Failed to build call graph");
+    let dir = tempfile::tempdir().unwrap();
+    let config = MermaidConfig {
+        chunk_dir: dir.path().to_path_buf(),
+        ..MermaidConfig::default()
+    };
+    let generate = |nodes| {
+        TraverseAdapter::new()
+            .unwrap()
+            .with_config(Config {
+                chunk_threshold_nodes: Some(nodes),
+                ..Config::default()
+            })
+            .generate_mermaid_with_config(&graph, &config)
+            .unwrap()
+    };
+
+    // Graphs at or below `chunk_threshold_nodes` are written as one file.
+    assert!(!generate(graph.nodes.len()).is_chunked);
+    assert!(generate(graph.nodes.len() - 1).is_chunked);
+}
+
+#[test]
+fn test_chunk_directory_is_replaced_per_run() {
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(COMPLEX_CONTRACT)
+        .expect("Failed to build call graph");
+    let root = tempfile::tempdir().unwrap();
+    let chunk_dir = root.path().join("chunks");
+    std::fs::create_dir_all(&chunk_dir).unwrap();
+    std::fs::write(chunk_dir.join("chunk_099.mmd"), "stale").unwrap();
+
+    let config = MermaidConfig {
+        chunk_dir: chunk_dir.clone(),
+        ..MermaidConfig::default()
+    };
+    let result = adapter
+        .generate_mermaid_with_config(&graph, &config)
+        .expect("Failed to generate Mermaid");
+    assert_eq!(result.chunk_dir.as_deref(), Some(chunk_dir.as_path()));
+    assert!(chunk_dir.join("chunk_001.mmd").exists());
+    assert!(!chunk_dir.join("chunk_099.mmd").exists());
+
+    // No staging or swapped-out directories are left next to it.
+    let siblings: Vec<_> = std::fs::read_dir(root.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(siblings, vec![std::ffi::OsString::from("chunks")]);
+}
+
+#[test]
+fn test_deterministic_chunks_are_byte_identical() {
+    // A chain of contracts, each calling into the previous one, large enough
+    // to be split into several chunks.
+    let mut source = String::from("pragma solidity ^0.8.0;\n");
+    for c in 0..4 {
+        source.push_str(&format!("contract C{} {{\n    uint256 v;\n", c));
+        if c > 0 {
+            source.push_str(&format!("    C{} prev;\n", c - 1));
+        }
+        for f in 0..12 {
+            let call = if c > 0 {
+                format!(" prev.f{}();", f)
+            } else {
+                String::new()
+            };
+            source.push_str(&format!(
+                "    function f{}() public {{ v += 1;{} }}\n",
+                f, call
+            ));
+        }
+        source.push_str("}\n");
+    }
+
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter
+        .build_call_graph(&source)
+        .expect("Failed to build call graph");
+    let root = tempfile::tempdir().unwrap();
+    let run = |name: &str, deterministic: bool| {
+        let config = MermaidConfig {
+            chunk_dir: root.path().join(name),
+            deterministic,
+            ..MermaidConfig::default()
+        };
+        let result = adapter
+            .generate_mermaid_with_config(&graph, &config)
+            .expect("Failed to generate Mermaid");
+        let dir = result.chunk_dir.unwrap();
+        let mut files: Vec<(String, String)> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| {
+                let path = e.unwrap().path();
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, std::fs::read_to_string(&path).unwrap())
+            })
+            .collect();
+        files.sort();
+        files
+    };
+
+    let first = run("first", true);
+    assert_eq!(first, run("second", true));
+    assert!(
+        first
+            .iter()
+            .filter(|(name, _)| name.starts_with("chunk_"))
+            .count()
+            > 1
+    );
+
+    let metadata: serde_json::Value = serde_json::from_str(
+        &first
+            .iter()
+            .find(|(name, _)| name == "metadata.json")
+            .unwrap()
+            .1,
+    )
+    .unwrap();
+    assert!(metadata.get("generated").is_none());
+
+    // Participants are declared in the order of the full diagram.
+    let full = adapter
+        .generate_mermaid_with_config(
+            &graph,
+            &MermaidConfig {
+                no_chunk: true,
+                ..MermaidConfig::default()
+            },
+        )
+        .unwrap()
+        .content;
+    let declared = |text: &str| -> Vec<String> {
+        text.lines()
+            .filter_map(|line| line.trim().strip_prefix("participant "))
+            .map(|name| name.split(" as ").next().unwrap().trim().to_string())
+            .collect()
+    };
+    let order = declared(&full);
+    for (name, content) in first.iter().filter(|(name, _)| name.starts_with("chunk_")) {
+        let positions: Vec<usize> = declared(content)
+            .iter()
+            .map(|p| order.iter().position(|o| o == p).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]), "{}", name);
+    }
+
+    let plain = run("plain", false);
+    assert!(plain
+        .iter()
+        .any(|(name, content)| name == "metadata.json" && content.contains("\"generated\"")));
+}
+
+#[test]
+fn test_shutdown_rejects_pending_requests() {
+    use std::sync::mpsc;
+    use tokio::sync::oneshot;
+    use traverse_lsp::config::Config;
+    use traverse_lsp::generator_worker::ShuttingDown;
+    use traverse_lsp::{GenerationRequest, GeneratorWorker};
+
+    let (tx, rx) = mpsc::channel();
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.send(GenerationRequest::Shutdown).unwrap();
+    tx.send(GenerationRequest::ListFuzzTargets {
+        uris: Vec::new(),
+        cancel: Default::default(),
+        tx: reply_tx,
+    })
+    .unwrap();
+
+    GeneratorWorker::new(Config::default()).unwrap().run(rx);
+
+    let error = reply_rx.blocking_recv().unwrap().unwrap_err();
+    assert!(error.is::<ShuttingDown>());
+    assert!(tx.send(GenerationRequest::Shutdown).is_err());
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::{command_request, InMemoryServer};
+use lsp_types::{InitializeParams, Url, WorkspaceFolder, WorkspaceFoldersChangeEvent};
+use serde_json::json;
+use std::path::PathBuf;
+use traverse_lsp::commands;
+use traverse_lsp::handlers::execute_command;
+use traverse_lsp::workspace_folders::{fill_folder_argument, WorkspaceFolders};
+
+fn folder(path: &str) -> WorkspaceFolder {
+    WorkspaceFolder {
+        uri: Url::from_file_path(path).unwrap(),
+        name: path.rsplit('/').next().unwrap().to_string(),
+    }
+}
+
+#[test]
+fn test_folders_follow_change_events() {
+    let init = InitializeParams {
+        workspace_folders: Some(vec![folder("/work/core"), folder("/work/periphery")]),
+        ..InitializeParams::default()
+    };
+    let folders = WorkspaceFolders::from_init(&init);
+    assert_eq!(
+        folders.list(),
+        vec![
+            PathBuf::from("/work/core"),
+            PathBuf::from("/work/periphery")
+        ]
+    );
+
+    let (added, removed) = folders.apply(&WorkspaceFoldersChangeEvent {
+        added: vec![folder("/work/governance"), folder("/work/core")],
+        removed: vec![folder("/work/periphery"), folder("/work/unknown")],
+    });
+    assert_eq!(added, vec![PathBuf::from("/work/governance")]);
+    assert_eq!(removed, vec![PathBuf::from("/work/periphery")]);
+    assert_eq!(
+        folders.list(),
+        vec![
+            PathBuf::from("/work/core"),
+            PathBuf::from("/work/governance")
+        ]
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_root_uri_without_workspace_folders() {
+    let init = InitializeParams {
+        root_uri: Some(Url::from_file_path("/work/core").unwrap()),
+        ..InitializeParams::default()
+    };
+    let folders = WorkspaceFolders::from_init(&init);
+    assert_eq!(folders.list(), vec![PathBuf::from("/work/core")]);
+}
+
+#[test]
+fn test_fill_folder_argument() {
+    let one = [PathBuf::from("/work/core")];
+    let two = [
+        PathBuf::from("/work/core"),
+        PathBuf::from("/work/periphery"),
+    ];
+
+    let mut arguments = Vec::new();
+    fill_folder_argument(&mut arguments, &one);
+    assert_eq!(arguments, vec![json!({ "workspace_folder": "/work/core" })]);
+
+    let mut arguments = vec![json!({ "format": "cypher" })];
+    fill_folder_argument(&mut arguments, &two);
+    assert_eq!(
+        arguments,
+        vec![json!({
+            "format": "cypher",
+            "workspace_folders": ["/work/core", "/work/periphery"],
+        })]
+    );
+
+    // A folder named by the client is kept.
+    let mut arguments = vec![json!({ "workspace_folder": "/elsewhere" })];
+    fill_folder_argument(&mut arguments, &two);
+    assert_eq!(arguments, vec![json!({ "workspace_folder": "/elsewhere" })]);
+
+    let mut arguments = Vec::new();
+    fill_folder_argument(&mut arguments, &[]);
+    assert!(arguments.is_empty());
+}
+
+#[test]
+fn test_command_runs_on_every_folder() {
+    let core = tempfile::tempdir().unwrap();
+    std::fs::write(
+        core.path().join("Vault.sol"),
+        "contract Vault {\n    function deposit(uint256 amount) external {}\n}\n",
+    )
+    .unwrap();
+    let empty = tempfile::tempdir().unwrap();
+
+    let server = InMemoryServer::start();
+    let request = command_request(
+        1,
+        commands::LIST_FUZZ_TARGETS_WORKSPACE,
+        vec![json!({
+            "workspace_folders": [
+                core.path().to_str().unwrap(),
+                empty.path().to_str().unwrap(),
+            ],
+        })],
+        None,
+    );
+    execute_command(
+        request,
+        &server.server.sender,
+        &server.generator_tx,
+        Default::default(),
+    )
+    .unwrap();
+    let response = server.response();
+    server.finish();
+    let result = response.result.unwrap();
+    assert_eq!(result["success"], true);
+    assert_eq!(result["files"], 1);
+    let roots = result["roots"].as_array().unwrap();
+    assert_eq!(roots.len(), 2);
+    assert_eq!(roots[0]["workspace_folder"], core.path().to_str().unwrap());
+    assert_eq!(roots[0]["result"]["files"], 1);
+    // A folder without sources answers `null`, as a single-folder command
+    // would.
+    assert_eq!(roots[1]["workspace_folder"], empty.path().to_str().unwrap());
+    assert_eq!(roots[1]["result"], serde_json::Value::Null);
+}
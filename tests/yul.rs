@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+use traverse_graph::cg::EdgeType;
+use traverse_lsp::analysis::model::SourceUnit;
+use traverse_lsp::source_map::SourceMap;
+use traverse_lsp::traverse_adapter::TraverseAdapter;
+use traverse_lsp::utils::find_source_files;
+use traverse_lsp::yul;
+
+const COUNTER: &str = r#"/// @use-src 0:"Counter.sol"
+object "Counter" {
+    code {
+        datacopy(0, dataoffset("Counter_deployed"), datasize("Counter_deployed"))
+        return(0, datasize("Counter_deployed"))
+    }
+    object "Counter_deployed" {
+        code {
+            switch selector()
+            case 0xd09de08a /* increment() */ {
+                increment(1)
+            }
+            default { revert(0, 0) }
+
+            function selector() -> s {
+                s := shr(224, calldataload(0))
+            }
+
+            function increment(by) {
+                let next := checked_add(sload(0), by)
+                sstore(0, next)
+            }
+
+            function checked_add(a, b) -> sum {
+                sum := add(a, b)
+                if lt(sum, a) { verbatim_0i_0o(hex"fe") }
+            }
+        }
+        data ".metadata" hex"a2646970667358"
+    }
+}
+"#;
+
+#[test]
+fn test_parse_yul_objects() {
+    let objects = yul::parse(COUNTER, "Counter");
+    let names: Vec<_> = objects.iter().map(|o| o.name.as_str()).collect();
+    assert_eq!(names, vec!["Counter", "Counter_deployed"]);
+    assert!(objects[0].functions.is_empty());
+
+    let deployed = &objects[1];
+    let functions: Vec<_> = deployed.functions.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(functions, vec!["selector", "increment", "checked_add"]);
+    assert_eq!(deployed.functions[2].parameters, vec!["a", "b"]);
+    assert_eq!(deployed.functions[2].returns, vec!["sum"]);
+    let body = &COUNTER[deployed.functions[1].span.0..deployed.functions[1].span.1];
+    assert!(body.starts_with("function increment(by) {"));
+    assert!(body.ends_with("sstore(0, next)\n            }"));
+
+    let top_level: Vec<_> = deployed.calls.iter().map(|c| c.callee.as_str()).collect();
+    assert_eq!(top_level, vec!["selector", "increment", "revert"]);
+    let increment = &deployed.functions[1].calls;
+    assert_eq!(increment[0].callee, "checked_add");
+    assert_eq!(increment[0].arguments, vec!["sload(0)", "by"]);
+    assert_eq!(
+        &COUNTER[increment[0].span.0..increment[0].span.1],
+        "checked_add(sload(0), by)"
+    );
+    assert_eq!(increment[1].callee, "sload");
+}
+
+#[test]
+fn test_parse_bare_yul_block() {
+    let objects = yul::parse("{ function f() {} f() }", "Snippet");
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].name, "Snippet");
+    assert_eq!(objects[0].code, Some((0, 23)));
+    assert_eq!(objects[0].calls[0].callee, "f");
+}
+
+#[test]
+fn test_yul_functions_join_workspace_graph() {
+    let sources = vec![
+        SourceUnit {
+            path: PathBuf::from("/workspace/src/Vault.sol"),
+            content: "pragma solidity ^0.8.0;\n\ncontract Vault {\n    function deposit() external {}\n}\n"
+                .to_string(),
+        },
+        SourceUnit {
+            path: PathBuf::from("/workspace/yul/Counter.yul"),
+            content: COUNTER.to_string(),
+        },
+    ];
+    let adapter = TraverseAdapter::new().expect("Failed to create adapter");
+    let graph = adapter.build_workspace_call_graph(&sources).unwrap();
+
+    let node = |contract: &str, name: &str| {
+        graph
+            .nodes
+            .iter()
+            .find(|n| n.contract_name.as_deref() == Some(contract) && n.name == name)
+            .unwrap_or_else(|| panic!("Missing {contract}.{name}"))
+    };
+    assert!(graph.nodes.iter().any(|n| n.name == "deposit"));
+    let code = node("Counter_deployed", yul::CODE_NODE);
+    let increment = node("Counter_deployed", "increment");
+    let checked_add = node("Counter_deployed", "checked_add");
+    node("Counter", yul::CODE_NODE);
+
+    let calls: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|e| e.edge_type == EdgeType::Call)
+        .map(|e| (e.source_node_id, e.target_node_id))
+        .collect();
+    assert!(calls.contains(&(code.id, increment.id)));
+    assert!(calls.contains(&(increment.id, checked_add.id)));
+
+    let location = SourceMap::new(&sources).locate(increment.span).unwrap();
+    assert!(location.uri.path().ends_with("/yul/Counter.yul"));
+    assert_eq!(location.range.start.line, 18);
+}
+
+#[test]
+fn test_find_source_files_includes_yul() {
+    let workspace = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("Vault.sol"), "").unwrap();
+    std::fs::write(workspace.path().join("Counter.yul"), "").unwrap();
+    std::fs::write(workspace.path().join("notes.md"), "").unwrap();
+
+    let files = find_source_files(workspace.path()).unwrap();
+    let names: Vec<_> = files
+        .iter()
+        .map(|uri| uri.path().rsplit('/').next().unwrap().to_string())
+        .collect();
+    assert_eq!(names, vec!["Counter.yul", "Vault.sol"]);
+}